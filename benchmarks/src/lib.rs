@@ -17,13 +17,14 @@ use std::time::Duration;
 use anyhow::anyhow;
 use futures_util::{TryFutureExt, future};
 use http::Uri;
-use http::header::CONTENT_TYPE;
 use pprof::flamegraph::Options;
 use tokio::net::TcpListener;
 use tokio::runtime::Runtime;
 use tokio::sync::oneshot;
 use tracing::warn;
 
+use restate_admin_client::AdminClient;
+use restate_admin_rest_model::deployments::RegisterDeploymentRequest;
 use restate_core::{TaskCenter, TaskCenterBuilder, TaskKind, cancellation_token, task_center};
 use restate_node::Node;
 use restate_rocksdb::RocksDbManager;
@@ -38,36 +39,20 @@ use restate_types::net::listener::AddressBook;
 use restate_types::retries::RetryPolicy;
 
 pub fn discover_deployment(current_thread_rt: &Runtime, address: Uri) {
+    let admin_client = AdminClient::builder("http://localhost:9070".parse().unwrap())
+        .retry_policy(RetryPolicy::fixed_delay(
+            Duration::from_millis(200),
+            Some(50),
+        ))
+        .build()
+        .expect("admin client should build");
+    current_thread_rt
+        .block_on(admin_client.register_deployment(RegisterDeploymentRequest::http(address)))
+        .expect("Discovery must be successful");
+
     let client = reqwest::Client::builder()
         .build()
         .expect("client should build");
-    let discovery_payload = serde_json::json!({"uri": address.to_string()}).to_string();
-    let discovery_result = current_thread_rt.block_on(async {
-        RetryPolicy::fixed_delay(Duration::from_millis(200), Some(50))
-            .retry(|| {
-                client
-                    .post("http://localhost:9070/deployments")
-                    .header(CONTENT_TYPE, "application/json")
-                    .body(discovery_payload.clone())
-                    .send()
-                    .map_err(anyhow::Error::from)
-                    .and_then(|response| {
-                        if response.status().is_success() {
-                            future::ready(Ok(response))
-                        } else {
-                            future::ready(Err(anyhow::anyhow!("Discovery was unsuccessful.")))
-                        }
-                    })
-            })
-            .await
-    });
-
-    assert!(
-        discovery_result
-            .expect("Discovery must be successful")
-            .status()
-            .is_success(),
-    );
 
     // wait for ingress being available
     // todo replace with node get_ident/status once it signals that the node is fully up and running
@@ -67,6 +67,8 @@ pub enum Command {
     Invocations(invocations::Invocations),
     /// Runs SQL queries against the data fusion service
     Sql(sql::Sql),
+    #[cfg(feature = "inspect-cmd")]
+    Inspect(inspect::Inspect),
     /// Download one of Restate's examples in this directory.
     #[clap(name = "example", alias = "examples")]
     Examples(examples::Examples),
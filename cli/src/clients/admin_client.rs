@@ -9,14 +9,13 @@
 // by the Apache License, Version 2.0.
 
 //! A wrapper client for admin HTTP service.
-
-use std::time::Duration;
+//!
+//! The requests are sent by [`restate_admin_client::AdminClient`], this wrapper builds it from the
+//! CLI environment, negotiates the admin API version and renders its errors.
 
 use anyhow::bail;
-use http::StatusCode;
-use serde::{Serialize, de::DeserializeOwned};
 use thiserror::Error;
-use tracing::{debug, info};
+use tracing::debug;
 use url::Url;
 
 use restate_admin_rest_model::version::{AdminApiVersion, VersionInformation};
@@ -28,7 +27,7 @@ use crate::build_info;
 use crate::cli_env::CliEnv;
 use crate::clients::AdminClientInterface;
 
-use super::errors::ApiError;
+use super::errors::{ApiError, ApiErrorBody};
 
 /// Min/max supported admin API versions
 pub const MIN_ADMIN_API_VERSION: AdminApiVersion = AdminApiVersion::V2;
@@ -42,82 +41,24 @@ pub enum Error {
     #[error("(Protocol error) {0}")]
     Serialization(#[from] serde_json::Error),
     Network(#[from] reqwest::Error),
+    #[error("invalid admin url: {0}")]
+    InvalidUrl(#[from] url::ParseError),
 }
 
-/// A lazy wrapper around a reqwest response that deserializes the body on
-/// demand and decodes our custom error body on non-2xx responses.
-pub struct Envelope<T> {
-    inner: reqwest::Response,
-
-    _phantom: std::marker::PhantomData<T>,
-}
-
-impl<T> Envelope<T>
-where
-    T: DeserializeOwned,
-{
-    pub fn status_code(&self) -> StatusCode {
-        self.inner.status()
-    }
-
-    pub fn url(&self) -> &Url {
-        self.inner.url()
-    }
-
-    pub async fn into_body(self) -> Result<T, Error> {
-        let http_status_code = self.inner.status();
-        let url = self.inner.url().clone();
-        if !self.status_code().is_success() {
-            let body = self.inner.text().await?;
-            info!("Response from {} ({})", url, http_status_code);
-            info!("  {}", body);
-            // Wrap the error into ApiError
-            return Err(Error::Api(Box::new(ApiError {
-                http_status_code,
-                url,
-                body: serde_json::from_str(&body)?,
-            })));
-        }
-
-        debug!("Response from {} ({})", url, http_status_code);
-        let body = self.inner.text().await?;
-        debug!("  {}", body);
-        Ok(serde_json::from_str(&body)?)
-    }
-
-    pub async fn into_api_error(self) -> Result<ApiError, Error> {
-        let http_status_code = self.inner.status();
-        let url = self.inner.url().clone();
-
-        debug!("Response from {} ({})", url, http_status_code);
-        let body = self.inner.text().await?;
-        debug!("  {}", body);
-        Ok(ApiError {
-            http_status_code,
-            url,
-            body: serde_json::from_str(&body)?,
-        })
-    }
-
-    pub async fn into_text(self) -> Result<String, Error> {
-        Ok(self.inner.text().await?)
-    }
-    pub fn success_or_error(self) -> Result<StatusCode, Error> {
-        let http_status_code = self.inner.status();
-        let url = self.inner.url().clone();
-        info!("Response from {} ({})", url, http_status_code);
-        match self.inner.error_for_status() {
-            Ok(_) => Ok(http_status_code),
-            Err(e) => Err(Error::Network(e)),
-        }
-    }
-}
-
-impl<T> From<reqwest::Response> for Envelope<T> {
-    fn from(value: reqwest::Response) -> Self {
-        Self {
-            inner: value,
-            _phantom: Default::default(),
+impl From<restate_admin_client::Error> for Error {
+    fn from(value: restate_admin_client::Error) -> Self {
+        match value {
+            restate_admin_client::Error::Api(api_error) => Error::Api(Box::new(ApiError {
+                http_status_code: api_error.http_status_code,
+                url: api_error.url,
+                body: ApiErrorBody {
+                    restate_code: api_error.body.restate_code,
+                    message: api_error.body.message,
+                },
+            })),
+            restate_admin_client::Error::Serialization(err) => Error::Serialization(err),
+            restate_admin_client::Error::Network(err) => Error::Network(err),
+            restate_admin_client::Error::InvalidUrl(err) => Error::InvalidUrl(err),
         }
     }
 }
@@ -125,10 +66,8 @@ impl<T> From<reqwest::Response> for Envelope<T> {
 /// A handy client for the admin HTTP service.
 #[derive(Clone)]
 pub struct AdminClient {
-    pub(crate) inner: reqwest::Client,
+    pub(crate) inner: restate_admin_client::AdminClient,
     pub(crate) base_url: Url,
-    pub(crate) bearer_token: Option<String>,
-    pub(crate) request_timeout: Duration,
     pub(crate) admin_api_version: AdminApiVersion,
     pub(crate) restate_server_version: SemanticRestateVersion,
     pub(crate) advertised_ingress_address: Option<String>,
@@ -161,37 +100,32 @@ impl AdminClient {
             }
         };
 
-        let bearer_token = env.bearer_token()?.map(str::to_string);
+        let mut inner = restate_admin_client::AdminClient::builder(base_url.clone())
+            .client(raw_client)
+            .request_timeout(CliContext::get().request_timeout());
+        if let Some(bearer_token) = env.bearer_token()? {
+            inner = inner.bearer_token(bearer_token);
+        }
 
         let client = Self {
-            inner: raw_client,
+            inner: inner.build()?,
             base_url,
-            bearer_token,
-            request_timeout: CliContext::get().request_timeout(),
             admin_api_version: AdminApiVersion::Unknown,
             restate_server_version: SemanticRestateVersion::unknown(),
             advertised_ingress_address: None,
         };
 
-        if let Ok(envelope) = client.version().await {
-            match envelope.into_body().await {
-                Ok(version_information) => {
-                    return Self::choose_api_version(client, version_information);
-                }
-                Err(err) => debug!("Failed parsing the version information: {err}"),
+        match client.version().await {
+            Ok(version_information) => {
+                return Self::choose_api_version(client, version_information);
             }
+            Err(err) => debug!("Failed getting the version information: {err}"),
         }
 
         // we couldn't validate the admin API. This could mean that the server is not running or
         // runs an old version which does not support version information. Query the health endpoint
         // to see whether the server is reachable and fail if not.
-        if client
-            .health()
-            .await
-            .map_err(Into::into)
-            .and_then(|r| r.success_or_error())
-            .is_err()
-        {
+        if client.health().await.is_err() {
             bail!(
                 "Unable to connect to the Restate server '{}'. Please make sure that it is running and reachable.",
                 client.base_url
@@ -206,22 +140,7 @@ impl AdminClient {
     }
 
     pub fn versioned_url(&self, path: impl IntoIterator<Item = impl AsRef<str>>) -> Url {
-        let mut url = self.base_url.clone();
-
-        {
-            let mut segments = url.path_segments_mut().expect("Bad url!");
-            segments.pop_if_empty();
-
-            match self.admin_api_version {
-                AdminApiVersion::Unknown => segments.extend(path),
-                // v1 clusters didn't support versioned urls
-                AdminApiVersion::V1 => segments.extend(path),
-                AdminApiVersion::V2 => segments.push("v2").extend(path),
-                AdminApiVersion::V3 => segments.push("v3").extend(path),
-            };
-        }
-
-        url
+        self.inner.versioned_url(path)
     }
 
     fn choose_api_version(
@@ -244,6 +163,7 @@ impl AdminClient {
                     }
                 };
             client.admin_api_version = admin_api_version;
+            client.inner = client.inner.with_admin_api_version(admin_api_version);
             client.advertised_ingress_address =
                 version_information.ingress_endpoint.map(|u| u.to_string());
             Ok(client)
@@ -258,61 +178,7 @@ impl AdminClient {
 
     /// Prepare a request builder for the given method and path.
     pub(crate) fn prepare(&self, method: reqwest::Method, path: Url) -> reqwest::RequestBuilder {
-        let request_builder = self
-            .inner
-            .request(method, path)
-            .timeout(self.request_timeout);
-
-        match self.bearer_token.as_deref() {
-            Some(token) => request_builder.bearer_auth(token),
-            None => request_builder,
-        }
-    }
-
-    /// Prepare a request builder that encodes the body as JSON.
-    fn prepare_with_body<B>(
-        &self,
-        method: reqwest::Method,
-        path: Url,
-        body: B,
-    ) -> reqwest::RequestBuilder
-    where
-        B: Serialize,
-    {
-        self.prepare(method, path).json(&body)
-    }
-
-    /// Execute a request and return the response as a lazy Envelope.
-    pub(crate) async fn run<T>(
-        &self,
-        method: reqwest::Method,
-        path: Url,
-    ) -> reqwest::Result<Envelope<T>>
-    where
-        T: DeserializeOwned + Send,
-    {
-        debug!("Sending request {} ({})", method, path);
-        let request = self.prepare(method, path.clone());
-        let resp = request.send().await?;
-        debug!("Response from {} ({})", path, resp.status());
-        Ok(resp.into())
-    }
-
-    pub(crate) async fn run_with_body<T, B>(
-        &self,
-        method: reqwest::Method,
-        path: Url,
-        body: B,
-    ) -> reqwest::Result<Envelope<T>>
-    where
-        T: DeserializeOwned + Send,
-        B: Serialize + std::fmt::Debug + Send,
-    {
-        debug!("Sending request {} ({}): {:?}", method, path, body);
-        let request = self.prepare_with_body(method, path.clone(), body);
-        let resp = request.send().await?;
-        debug!("Response from {} ({})", path, resp.status());
-        Ok(resp.into())
+        self.inner.request(method, path)
     }
 }
 
@@ -9,8 +9,8 @@
 // by the Apache License, Version 2.0.
 
 use super::AdminClient;
-use super::admin_client::Envelope;
-use http::{Uri, Version};
+use super::admin_client::Error;
+use http::{StatusCode, Uri, Version};
 use std::collections::HashMap;
 
 use restate_admin_rest_model::deployments::*;
@@ -24,158 +24,132 @@ use restate_types::schema::service::ServiceMetadata;
 
 pub trait AdminClientInterface {
     /// Check if the admin service is healthy by invoking /health
-    async fn health(&self) -> reqwest::Result<Envelope<()>>;
-    async fn get_services(&self) -> reqwest::Result<Envelope<ListServicesResponse>>;
-    async fn get_service(&self, name: &str) -> reqwest::Result<Envelope<ServiceMetadata>>;
+    async fn health(&self) -> Result<(), Error>;
+    async fn get_services(&self) -> Result<ListServicesResponse, Error>;
+    async fn get_service(&self, name: &str) -> Result<ServiceMetadata, Error>;
     async fn patch_service(
         &self,
         name: &str,
         modify_service_request: ModifyServiceRequest,
-    ) -> reqwest::Result<Envelope<ServiceMetadata>>;
-    async fn get_deployments(&self) -> reqwest::Result<Envelope<ListDeploymentsResponse>>;
-    async fn get_deployment<D: AsRef<str>>(
-        &self,
-        id: D,
-    ) -> reqwest::Result<Envelope<DetailedDeploymentResponse>>;
-    async fn remove_deployment(&self, id: &str, force: bool) -> reqwest::Result<Envelope<()>>;
+    ) -> Result<ServiceMetadata, Error>;
+    async fn get_deployments(&self) -> Result<ListDeploymentsResponse, Error>;
+    async fn get_deployment(&self, id: &DeploymentId) -> Result<DetailedDeploymentResponse, Error>;
+    async fn remove_deployment(&self, id: &DeploymentId, force: bool) -> Result<(), Error>;
 
     async fn discover_deployment(
         &self,
         body: RegisterDeploymentRequest,
-    ) -> reqwest::Result<Envelope<RegisterDeploymentResponse>>;
+    ) -> Result<RegisterDeploymentResponse, Error>;
 
-    async fn cancel_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>>;
+    /// Returns the status code together with the response, see
+    /// [`restate_admin_client::AdminClient::register_deployment_with_status`].
+    async fn discover_deployment_with_status(
+        &self,
+        body: RegisterDeploymentRequest,
+    ) -> Result<(StatusCode, RegisterDeploymentResponse), Error>;
 
-    async fn kill_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>>;
+    async fn cancel_invocation(&self, id: &str) -> Result<(), Error>;
 
-    async fn purge_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>>;
+    async fn kill_invocation(&self, id: &str) -> Result<(), Error>;
 
-    async fn restart_invocation(
-        &self,
-        id: &str,
-    ) -> reqwest::Result<Envelope<RestartAsNewInvocationResponse>>;
+    async fn purge_invocation(&self, id: &str) -> Result<(), Error>;
 
-    async fn resume_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>>;
+    async fn restart_invocation(&self, id: &str) -> Result<RestartAsNewInvocationResponse, Error>;
 
-    async fn pause_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>>;
+    async fn resume_invocation(&self, id: &str) -> Result<(), Error>;
 
-    async fn patch_state(
-        &self,
-        service: &str,
-        req: ModifyServiceStateRequest,
-    ) -> reqwest::Result<Envelope<()>>;
+    async fn pause_invocation(&self, id: &str) -> Result<(), Error>;
 
-    async fn version(&self) -> reqwest::Result<Envelope<VersionInformation>>;
+    async fn patch_state(&self, service: &str, req: ModifyServiceStateRequest)
+    -> Result<(), Error>;
+
+    async fn version(&self) -> Result<VersionInformation, Error>;
 }
 
 impl AdminClientInterface for AdminClient {
-    async fn health(&self) -> reqwest::Result<Envelope<()>> {
-        let url = self.versioned_url(["health"]);
-        self.run(reqwest::Method::GET, url).await
+    async fn health(&self) -> Result<(), Error> {
+        Ok(self.inner.health().await?)
     }
 
-    async fn get_services(&self) -> reqwest::Result<Envelope<ListServicesResponse>> {
-        let url = self.versioned_url(["services"]);
-        self.run(reqwest::Method::GET, url).await
+    async fn get_services(&self) -> Result<ListServicesResponse, Error> {
+        Ok(self.inner.list_services().await?)
     }
 
-    async fn get_service(&self, name: &str) -> reqwest::Result<Envelope<ServiceMetadata>> {
-        let url = self.versioned_url(["services", name]);
-        self.run(reqwest::Method::GET, url).await
+    async fn get_service(&self, name: &str) -> Result<ServiceMetadata, Error> {
+        Ok(self.inner.get_service(name).await?)
     }
 
     async fn patch_service(
         &self,
         name: &str,
         modify_service_request: ModifyServiceRequest,
-    ) -> reqwest::Result<Envelope<ServiceMetadata>> {
-        let url = self.versioned_url(["services", name]);
-        self.run_with_body(reqwest::Method::PATCH, url, modify_service_request)
-            .await
+    ) -> Result<ServiceMetadata, Error> {
+        Ok(self
+            .inner
+            .modify_service(name, modify_service_request)
+            .await?)
     }
 
-    async fn get_deployments(&self) -> reqwest::Result<Envelope<ListDeploymentsResponse>> {
-        let url = self.versioned_url(["deployments"]);
-        self.run(reqwest::Method::GET, url).await
+    async fn get_deployments(&self) -> Result<ListDeploymentsResponse, Error> {
+        Ok(self.inner.list_deployments().await?)
     }
 
-    async fn get_deployment<D: AsRef<str>>(
-        &self,
-        id: D,
-    ) -> reqwest::Result<Envelope<DetailedDeploymentResponse>> {
-        let url = self.versioned_url(["deployments", id.as_ref()]);
-        self.run(reqwest::Method::GET, url).await
+    async fn get_deployment(&self, id: &DeploymentId) -> Result<DetailedDeploymentResponse, Error> {
+        Ok(self.inner.get_deployment(id).await?)
     }
 
-    async fn remove_deployment(&self, id: &str, force: bool) -> reqwest::Result<Envelope<()>> {
-        let mut url = self.versioned_url(["deployments", id]);
-        url.set_query(Some(&format!("force={force}")));
-
-        self.run(reqwest::Method::DELETE, url).await
+    async fn remove_deployment(&self, id: &DeploymentId, force: bool) -> Result<(), Error> {
+        Ok(self.inner.remove_deployment(id, force).await?)
     }
 
     async fn discover_deployment(
         &self,
         body: RegisterDeploymentRequest,
-    ) -> reqwest::Result<Envelope<RegisterDeploymentResponse>> {
-        let url = self.versioned_url(["deployments"]);
-        self.run_with_body(reqwest::Method::POST, url, body).await
+    ) -> Result<RegisterDeploymentResponse, Error> {
+        Ok(self.inner.register_deployment(body).await?)
+    }
+
+    async fn discover_deployment_with_status(
+        &self,
+        body: RegisterDeploymentRequest,
+    ) -> Result<(StatusCode, RegisterDeploymentResponse), Error> {
+        Ok(self.inner.register_deployment_with_status(body).await?)
     }
 
-    async fn cancel_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>> {
-        self.run(
-            reqwest::Method::PATCH,
-            self.versioned_url(["invocations", id, "cancel"]),
-        )
-        .await
+    async fn cancel_invocation(&self, id: &str) -> Result<(), Error> {
+        Ok(self.inner.cancel_invocation(id).await?)
     }
 
-    async fn kill_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>> {
-        self.run(
-            reqwest::Method::PATCH,
-            self.versioned_url(["invocations", id, "kill"]),
-        )
-        .await
+    async fn kill_invocation(&self, id: &str) -> Result<(), Error> {
+        Ok(self.inner.kill_invocation(id).await?)
     }
 
-    async fn purge_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>> {
-        self.run(
-            reqwest::Method::PATCH,
-            self.versioned_url(["invocations", id, "purge"]),
-        )
-        .await
+    async fn purge_invocation(&self, id: &str) -> Result<(), Error> {
+        Ok(self.inner.purge_invocation(id).await?)
     }
 
-    async fn restart_invocation(
-        &self,
-        id: &str,
-    ) -> reqwest::Result<Envelope<RestartAsNewInvocationResponse>> {
-        let url = self.versioned_url(["invocations", id, "restart-as-new"]);
-        self.run(reqwest::Method::PATCH, url).await
+    async fn restart_invocation(&self, id: &str) -> Result<RestartAsNewInvocationResponse, Error> {
+        Ok(self.inner.restart_as_new_invocation(id).await?)
     }
 
-    async fn resume_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>> {
-        let url = self.versioned_url(["invocations", id, "resume"]);
-        self.run(reqwest::Method::PATCH, url).await
+    async fn resume_invocation(&self, id: &str) -> Result<(), Error> {
+        Ok(self.inner.resume_invocation(id).await?)
     }
 
-    async fn pause_invocation(&self, id: &str) -> reqwest::Result<Envelope<()>> {
-        let url = self.versioned_url(["invocations", id, "pause"]);
-        self.run(reqwest::Method::PATCH, url).await
+    async fn pause_invocation(&self, id: &str) -> Result<(), Error> {
+        Ok(self.inner.pause_invocation(id).await?)
     }
 
     async fn patch_state(
         &self,
         service: &str,
         req: ModifyServiceStateRequest,
-    ) -> reqwest::Result<Envelope<()>> {
-        let url = self.versioned_url(["services", service, "state"]);
-        self.run_with_body(reqwest::Method::POST, url, req).await
+    ) -> Result<(), Error> {
+        Ok(self.inner.modify_service_state(service, req).await?)
     }
 
-    async fn version(&self) -> reqwest::Result<Envelope<VersionInformation>> {
-        let url = self.versioned_url(["version"]);
-        self.run(reqwest::Method::GET, url).await
+    async fn version(&self) -> Result<VersionInformation, Error> {
+        Ok(self.inner.version().await?)
     }
 }
 
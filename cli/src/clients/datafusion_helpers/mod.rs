@@ -19,6 +19,16 @@ pub struct ServiceHandlerUsage {
     pub inv_count: i64,
 }
 
+/// Calls from a service to another one, see the `sys_service_call` view
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceCall {
+    pub caller_service_name: String,
+    pub callee_service_name: String,
+    pub calls: i64,
+    pub completed_calls: i64,
+    pub failed_calls: i64,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SimpleInvocation {
     pub id: String,
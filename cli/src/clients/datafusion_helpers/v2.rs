@@ -25,7 +25,7 @@ use restate_types::identifiers::{AwakeableIdentifier, InvocationId, ServiceId};
 
 use super::{
     HandlerStateStats, Invocation, InvocationCompletion, InvocationState, JournalEntry,
-    JournalEntryTypeV1, JournalEntryV1, JournalEntryV2, LockedKeyInfo, OutgoingInvoke,
+    JournalEntryTypeV1, JournalEntryV1, JournalEntryV2, LockedKeyInfo, OutgoingInvoke, ServiceCall,
     ServiceHandlerLockedKeysMap, ServiceHandlerUsage, ServiceStatusMap, SimpleInvocation,
 };
 
@@ -566,6 +566,34 @@ pub async fn get_service_invocations(
     .0)
 }
 
+/// Returns the calls between services, optionally only the ones from or to the given service.
+pub async fn get_service_calls(
+    client: &DataFusionHttpClient,
+    service: Option<&str>,
+) -> Result<Vec<ServiceCall>> {
+    if !client
+        .check_columns_exists("sys_service_call", &["caller_service_name"])
+        .await?
+    {
+        anyhow::bail!(
+            "The server doesn't support the service call graph, upgrade it to a newer version"
+        );
+    }
+
+    let filter = service
+        .map(|service| {
+            format!("WHERE caller_service_name = '{service}' OR callee_service_name = '{service}'")
+        })
+        .unwrap_or_default();
+    let query = format!(
+        "SELECT * FROM sys_service_call
+        {filter}
+        ORDER BY caller_service_name, callee_service_name"
+    );
+
+    Ok(client.run_json_query::<ServiceCall>(query).await?)
+}
+
 pub async fn get_invocation(
     client: &DataFusionHttpClient,
     invocation_id: &str,
@@ -18,12 +18,17 @@ use itertools::Itertools;
 use remote::RemotePort;
 use tokio_util::sync::CancellationToken;
 
+use restate_admin_rest_model::deployments::RegisterDeploymentRequest;
 use restate_cli_util::CliContext;
 
 use crate::cli_env::EnvironmentType;
 use crate::clients::cloud::{CloudClient, CloudClientInterface};
+use crate::clients::{AdminClient, AdminClientInterface};
+use crate::commands::deployments::register::tunnel_proxy_uri;
 use crate::{build_info, cli_env::CliEnv};
 
+use self::renderer::{Registration, TunnelRenderer};
+
 mod local;
 mod remote;
 mod renderer;
@@ -48,6 +53,11 @@ pub struct Tunnel {
     /// A name for the tunnel; a random name will be generated if not provided
     #[clap(long = "tunnel-name")]
     tunnel_name: Option<String>,
+
+    /// Register the local service as a deployment of the Environment once the tunnel is
+    /// established, overwriting a previous registration of the same tunnel
+    #[clap(long)]
+    register: bool,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -89,6 +99,11 @@ pub async fn run_tunnel(State(env): State<CliEnv>, opts: &Tunnel) -> Result<()>
         opts.local_port = Some(9080);
         opts.remote_port = vec![RemotePort::Ingress, RemotePort::Admin];
     }
+    if opts.register && opts.local_port.is_none() {
+        return Err(anyhow::anyhow!(
+            "--register requires a local service to expose, set it with --local-port"
+        ));
+    }
 
     let client = CloudClient::new(&env)?;
 
@@ -132,7 +147,7 @@ pub async fn run_tunnel(State(env): State<CliEnv>, opts: &Tunnel) -> Result<()>
         *crate::EXIT_HANDLER.lock().unwrap() = Some(boxed);
     }
 
-    let tunnel_renderer = Arc::new(renderer::TunnelRenderer::new(&opts.remote_port).unwrap());
+    let tunnel_renderer = Arc::new(TunnelRenderer::new(&opts.remote_port).unwrap());
 
     let local_fut = local::run_local(
         &env,
@@ -178,6 +193,7 @@ pub async fn run_tunnel(State(env): State<CliEnv>, opts: &Tunnel) -> Result<()>
     }
 
     let mut rerender = tokio::time::interval(Duration::from_millis(100));
+    let mut registration: Option<tokio::task::JoinHandle<()>> = None;
 
     let res = {
         let local_fut = local_fut.fuse();
@@ -190,12 +206,28 @@ pub async fn run_tunnel(State(env): State<CliEnv>, opts: &Tunnel) -> Result<()>
                 Some(Err(remote_err)) = remote_futs.next() => break Err(Error::Remote(remote_err)),
                 _ = cancellation.cancelled() => break Err(Error::ControlC),
                 _ = rerender.tick() => {
+                    if opts.register
+                        && registration.is_none()
+                        && let Some(local) = tunnel_renderer.local.get()
+                    {
+                        registration = Some(tokio::spawn(register_tunnel(
+                            env.clone(),
+                            format!("tunnel://{}:{}", local.tunnel_name, local.proxy_port),
+                            tunnel_renderer.clone(),
+                        )));
+                    }
                     tunnel_renderer.render();
                 },
             }
         }
     };
 
+    if let Some(registration) = registration {
+        // release the renderer held by a registration still in flight
+        registration.abort();
+        let _ = registration.await;
+    }
+
     if let Some(mut renderer) = Arc::into_inner(tunnel_renderer)
         && let Some(local) = renderer.local.take()
     {
@@ -213,8 +245,9 @@ pub async fn run_tunnel(State(env): State<CliEnv>, opts: &Tunnel) -> Result<()>
                     .join(" --remote-port ")
             )
         };
+        let register = if opts.register { " --register" } else { "" };
         eprintln!(
-            "To retry with the same endpoint:\nrestate cloud env tunnel --local-port {port} --tunnel-url {tunnel_url} --tunnel-name {tunnel_name}{remote_ports}"
+            "To retry with the same endpoint:\nrestate cloud env tunnel --local-port {port} --tunnel-url {tunnel_url} --tunnel-name {tunnel_name}{remote_ports}{register}"
         );
     };
 
@@ -227,3 +260,32 @@ pub async fn run_tunnel(State(env): State<CliEnv>, opts: &Tunnel) -> Result<()>
         Err(err) => Err(err.into()),
     }
 }
+
+async fn register_tunnel(env: CliEnv, tunnel_uri: String, tunnel_renderer: Arc<TunnelRenderer>) {
+    tunnel_renderer.store_registration(Registration::Registering);
+
+    let result = async {
+        let uri = tunnel_proxy_uri(&env, &tunnel_uri.parse()?)?;
+        let client = AdminClient::new(&env).await?;
+        let response = client
+            .discover_deployment(RegisterDeploymentRequest::Http {
+                uri,
+                additional_headers: None,
+                metadata: Default::default(),
+                use_http_11: false,
+                // the same tunnel is registered again whenever the local service changes,
+                // possibly with incompatible changes to its services
+                breaking: true,
+                force: Some(true),
+                dry_run: false,
+            })
+            .await?;
+        anyhow::Ok(response.id)
+    }
+    .await;
+
+    tunnel_renderer.store_registration(match result {
+        Ok(deployment_id) => Registration::Registered(deployment_id),
+        Err(err) => Registration::Failed(format!("{err:#}")),
+    });
+}
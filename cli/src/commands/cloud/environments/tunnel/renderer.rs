@@ -25,7 +25,8 @@ use crossterm::{
 use restate_cli_util::ui::console::StyledTable;
 use restate_cli_util::ui::output::Console;
 use restate_cli_util::ui::stylesheet;
-use restate_cli_util::{CliContext, c_indent_table, c_println, c_tip, c_warn};
+use restate_cli_util::{CliContext, c_indent_table, c_println, c_success, c_tip, c_warn};
+use restate_types::identifiers::DeploymentId;
 
 use super::remote::RemotePort;
 
@@ -34,6 +35,14 @@ pub(crate) struct TunnelRenderer {
     pub local: OnceLock<LocalRenderer>,
     pub remote: Vec<RemoteRenderer>,
     last_error: ArcSwapOption<String>,
+    registration: ArcSwapOption<Registration>,
+}
+
+/// Status of the registration of the tunnelled deployment, when requested with `--register`.
+pub(crate) enum Registration {
+    Registering,
+    Registered(DeploymentId),
+    Failed(String),
 }
 
 impl TunnelRenderer {
@@ -58,6 +67,7 @@ impl TunnelRenderer {
                 .map(|p| RemoteRenderer::new(*p))
                 .collect(),
             last_error: ArcSwapOption::empty(),
+            registration: ArcSwapOption::empty(),
         })
     }
 
@@ -71,6 +81,11 @@ impl TunnelRenderer {
         self.render()
     }
 
+    pub(crate) fn store_registration(&self, registration: Registration) {
+        self.registration.store(Some(Arc::new(registration)));
+        self.render()
+    }
+
     pub(crate) fn render(&self) {
         let mut stdout = std::io::stdout();
 
@@ -138,7 +153,26 @@ impl TunnelRenderer {
             c_warn!("Error: {last_error}")
         }
 
-        if let Some(local) = self.local.get() {
+        let registration = self.registration.load();
+        match registration.as_deref() {
+            Some(Registration::Registering) => {
+                c_println!("Registering the tunnelled deployment...");
+            }
+            Some(Registration::Registered(deployment_id)) => {
+                c_success!("Registered the tunnelled deployment as {deployment_id}");
+            }
+            Some(Registration::Failed(err)) => {
+                c_warn!("Failed to register the tunnelled deployment: {err}");
+            }
+            None => {}
+        }
+
+        if let Some(local) = self.local.get()
+            && !matches!(
+                registration.as_deref(),
+                Some(Registration::Registering | Registration::Registered(_))
+            )
+        {
             c_tip!(
                 "To discover:\nrestate deployments register tunnel://{}:{}\nThe deployment is only reachable from this Restate Cloud environment ({}).",
                 local.tunnel_name,
@@ -0,0 +1,645 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use cling::prelude::*;
+use futures::StreamExt;
+use http::StatusCode;
+use indicatif::ProgressBar;
+use serde::Deserialize;
+
+use restate_admin_rest_model::deployments::{
+    RegisterDeploymentRequest, RegisterDeploymentResponse,
+};
+use restate_admin_rest_model::services::ModifyServiceRequest;
+use restate_admin_rest_model::version::AdminApiVersion;
+use restate_cli_util::ui::console::{Styled, confirm_or_exit};
+use restate_cli_util::ui::stylesheet::Style;
+use restate_cli_util::{c_indentln, c_println, c_success};
+use restate_serde_util::SerdeableHeaderHashMap;
+use restate_time_util::DurationExt;
+use restate_types::identifiers::DeploymentId;
+use restate_types::schema::service::{IngressRoute, ServiceMetadata};
+
+use super::register::{DeploymentEndpoint, infer_deployment_metadata_from_environment};
+use crate::cli_env::CliEnv;
+use crate::clients::{AdminClient, AdminClientInterface, MetasClientError};
+
+#[derive(Run, Parser, Collect, Clone)]
+#[cling(run = "run_apply")]
+pub struct Apply {
+    /// Directory containing the deployment manifests, or a single manifest.
+    ///
+    /// Every `*.toml` file of the directory describes a deployment, for example:
+    ///
+    ///   deployment = "http://greeter:9080"   # URL or Lambda ARN
+    ///   # assume_role_arn = "arn:aws:iam::..."
+    ///   # use_http_11 = true
+    ///   # breaking = true
+    ///
+    ///   [additional_headers]
+    ///   authorization = "Bearer ..."
+    ///
+    ///   [metadata]
+    ///   team = "payments"
+    ///
+    ///   [services.Greeter]   # same format as `restate services config edit`
+    ///   public = false
+    ///   idempotency_retention = "1d"
+    #[clap(short = 'f', long = "file", verbatim_doc_comment)]
+    path: PathBuf,
+
+    /// Maximum number of deployments discovered at the same time.
+    #[clap(long, default_value = "4")]
+    concurrency: NonZeroUsize,
+
+    /// Only print the changes, without applying them.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+/// Deployment manifest, see [`Apply::path`].
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeploymentManifest {
+    deployment: String,
+    #[serde(default)]
+    assume_role_arn: Option<String>,
+    #[serde(default)]
+    additional_headers: Option<SerdeableHeaderHashMap>,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+    #[serde(default)]
+    use_http_11: bool,
+    #[serde(default)]
+    breaking: bool,
+    /// Configuration overrides of the services of the deployment
+    #[serde(default)]
+    services: BTreeMap<String, ModifyServiceRequest>,
+}
+
+struct LoadedManifest {
+    path: PathBuf,
+    endpoint: DeploymentEndpoint,
+    manifest: DeploymentManifest,
+}
+
+impl LoadedManifest {
+    fn register_request(&self, dry_run: bool) -> RegisterDeploymentRequest {
+        let manifest = &self.manifest;
+        match &self.endpoint {
+            DeploymentEndpoint::Uri(uri) => RegisterDeploymentRequest::Http {
+                uri: uri.clone(),
+                additional_headers: manifest.additional_headers.clone(),
+                metadata: manifest.metadata.clone(),
+                use_http_11: manifest.use_http_11,
+                breaking: manifest.breaking,
+                // existing deployments are never overwritten, to keep apply idempotent
+                force: Some(false),
+                dry_run,
+            },
+            DeploymentEndpoint::Lambda(arn) => RegisterDeploymentRequest::Lambda {
+                arn: arn.to_string(),
+                assume_role_arn: manifest.assume_role_arn.clone(),
+                additional_headers: manifest.additional_headers.clone(),
+                metadata: manifest.metadata.clone(),
+                breaking: manifest.breaking,
+                force: Some(false),
+                dry_run,
+            },
+        }
+    }
+}
+
+enum DeploymentChange {
+    Create(RegisterDeploymentResponse),
+    Unchanged(DeploymentId),
+    Conflict(String),
+}
+
+struct ServiceChange {
+    name: String,
+    /// Changed fields, with their current and their new value
+    fields: Vec<(&'static str, String, String)>,
+    request: ModifyServiceRequest,
+}
+
+enum ServicePlan {
+    Update(ServiceChange),
+    Unchanged(String),
+    Conflict(String, String),
+}
+
+pub async fn run_apply(State(env): State<CliEnv>, opts: &Apply) -> Result<()> {
+    let mut manifests = load_manifests(&opts.path)?;
+    if manifests.is_empty() {
+        bail!("No deployment manifest found in {}", opts.path.display());
+    }
+
+    let client = AdminClient::new(&env).await?;
+    if client.admin_api_version < AdminApiVersion::V3 {
+        bail!("deployments apply is only supported when interacting with Restate >= 1.6");
+    }
+
+    for manifest in &mut manifests {
+        infer_deployment_metadata_from_environment(&mut manifest.manifest.metadata);
+        #[cfg(feature = "cloud")]
+        if let DeploymentEndpoint::Uri(uri) = &manifest.endpoint
+            && uri.scheme_str() == Some("tunnel")
+        {
+            manifest.endpoint =
+                DeploymentEndpoint::Uri(super::register::tunnel_proxy_uri(&env, uri)?);
+        }
+    }
+
+    let progress = ProgressBar::new_spinner();
+    progress
+        .set_style(indicatif::ProgressStyle::with_template("{spinner} [{elapsed}] {msg}").unwrap());
+    progress.enable_steady_tick(std::time::Duration::from_millis(120));
+    progress.set_message(format!(
+        "Asking restate server at {} for a dry-run discovery of {} deployments",
+        &client.base_url,
+        manifests.len()
+    ));
+
+    let deployment_changes = futures::stream::iter(
+        manifests
+            .iter()
+            .map(|manifest| plan_deployment(&client, manifest)),
+    )
+    .buffered(opts.concurrency.get())
+    .collect::<Vec<_>>()
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>>>()?;
+
+    progress.set_message("Fetching the configuration of the services");
+    let mut service_plans = Vec::with_capacity(manifests.len());
+    for (manifest, deployment_change) in manifests.iter_mut().zip(&deployment_changes) {
+        service_plans.push(plan_services(&client, manifest, deployment_change).await?);
+    }
+    progress.finish_and_clear();
+
+    let mut conflicts = 0;
+    let mut changes = 0;
+    for ((manifest, deployment_change), service_plans) in manifests
+        .iter()
+        .zip(&deployment_changes)
+        .zip(&service_plans)
+    {
+        match deployment_change {
+            DeploymentChange::Create(response) => {
+                changes += 1;
+                c_println!(
+                    "{} deployment {} ({})",
+                    Styled(Style::Success, "+"),
+                    manifest.endpoint,
+                    response
+                        .services
+                        .iter()
+                        .map(|service| service.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            DeploymentChange::Unchanged(id) => {
+                c_println!("= deployment {} ({id})", manifest.endpoint);
+            }
+            DeploymentChange::Conflict(reason) => {
+                conflicts += 1;
+                c_println!(
+                    "{} deployment {} ({}): {reason}",
+                    Styled(Style::Danger, "!"),
+                    manifest.endpoint,
+                    manifest.path.display()
+                );
+            }
+        }
+        for service_plan in service_plans {
+            match service_plan {
+                ServicePlan::Update(change) => {
+                    changes += 1;
+                    c_println!("{} service {}", Styled(Style::Warn, "~"), change.name);
+                    for (field, current, new) in &change.fields {
+                        c_indentln!(1, "{field}: {current} -> {}", Styled(Style::Success, new));
+                    }
+                }
+                ServicePlan::Unchanged(name) => c_println!("= service {name}"),
+                ServicePlan::Conflict(name, reason) => {
+                    conflicts += 1;
+                    c_println!(
+                        "{} service {name} ({}): {reason}",
+                        Styled(Style::Danger, "!"),
+                        manifest.path.display()
+                    );
+                }
+            }
+        }
+    }
+
+    c_println!();
+    if conflicts > 0 {
+        bail!("Found {conflicts} conflicts, no changes were applied");
+    }
+    if changes == 0 {
+        c_success!("Everything is up to date");
+        return Ok(());
+    }
+    if opts.dry_run {
+        c_println!("Dry run, {changes} changes were not applied");
+        return Ok(());
+    }
+    confirm_or_exit("Are you sure you want to apply those changes?")?;
+
+    // Services are configured after registering the deployments, since re-discovery overwrites
+    // their configuration.
+    let created = futures::stream::iter(
+        manifests
+            .iter()
+            .zip(&deployment_changes)
+            .filter(|(_, change)| matches!(change, DeploymentChange::Create(_)))
+            .map(|(manifest, _)| async {
+                client
+                    .discover_deployment(manifest.register_request(false))
+                    .await
+                    .with_context(|| format!("Failed to register deployment {}", manifest.endpoint))
+            }),
+    )
+    .buffered(opts.concurrency.get())
+    .collect::<Vec<_>>()
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>>>()?;
+    for response in created {
+        c_success!("Registered deployment {}", response.id);
+    }
+
+    for service_change in service_plans
+        .into_iter()
+        .flatten()
+        .filter_map(|plan| match plan {
+            ServicePlan::Update(change) => Some(change),
+            _ => None,
+        })
+    {
+        client
+            .patch_service(&service_change.name, service_change.request)
+            .await
+            .with_context(|| format!("Failed to configure service {}", service_change.name))?;
+        c_success!("Configured service {}", service_change.name);
+    }
+
+    Ok(())
+}
+
+/// Reads the manifests, sorted by file name. Fails if two manifests describe the same deployment
+/// or configure the same service.
+fn load_manifests(path: &Path) -> Result<Vec<LoadedManifest>> {
+    let paths = if path.is_dir() {
+        let mut paths = std::fs::read_dir(path)
+            .with_context(|| format!("Cannot read directory {}", path.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"));
+        paths.sort();
+        paths
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut manifests: Vec<LoadedManifest> = Vec::with_capacity(paths.len());
+    for path in paths {
+        let manifest: DeploymentManifest = toml::from_str(
+            &std::fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read manifest {}", path.display()))?,
+        )
+        .with_context(|| format!("Invalid manifest {}", path.display()))?;
+        let endpoint = super::register::parse_deployment(&manifest.deployment)
+            .map_err(|err| anyhow::anyhow!("Invalid deployment in {}: {err}", path.display()))?;
+
+        if let Some(other) = manifests.iter().find(|other| {
+            other.endpoint.cli_parameter_display() == endpoint.cli_parameter_display()
+        }) {
+            bail!(
+                "Manifests {} and {} both describe the deployment {endpoint}",
+                other.path.display(),
+                path.display()
+            );
+        }
+        if let Some((other, service)) = manifests.iter().find_map(|other| {
+            manifest
+                .services
+                .keys()
+                .find(|service| other.manifest.services.contains_key(*service))
+                .map(|service| (other, service))
+        }) {
+            bail!(
+                "Manifests {} and {} both configure the service {service}",
+                other.path.display(),
+                path.display()
+            );
+        }
+        manifests.push(LoadedManifest {
+            path,
+            endpoint,
+            manifest,
+        });
+    }
+    Ok(manifests)
+}
+
+async fn plan_deployment(
+    client: &AdminClient,
+    manifest: &LoadedManifest,
+) -> Result<DeploymentChange> {
+    match client
+        .discover_deployment_with_status(manifest.register_request(true))
+        .await
+    {
+        Err(MetasClientError::Api(api_error))
+            if api_error.http_status_code == StatusCode::CONFLICT =>
+        {
+            Ok(DeploymentChange::Conflict(api_error.body.to_string()))
+        }
+        // Admin API V3 returns OK if the deployment already exists and force = false.
+        Ok((StatusCode::OK, response)) => Ok(DeploymentChange::Unchanged(response.id)),
+        Ok((_, response)) => Ok(DeploymentChange::Create(response)),
+        Err(err) => Err(err)
+            .with_context(|| format!("Failed the discovery of deployment {}", manifest.endpoint)),
+    }
+}
+
+async fn plan_services(
+    client: &AdminClient,
+    manifest: &mut LoadedManifest,
+    deployment_change: &DeploymentChange,
+) -> Result<Vec<ServicePlan>> {
+    let mut plans = Vec::with_capacity(manifest.manifest.services.len());
+    for (name, request) in std::mem::take(&mut manifest.manifest.services) {
+        let current = match deployment_change {
+            // the conflict is reported for the deployment already
+            DeploymentChange::Conflict(_) => continue,
+            DeploymentChange::Create(response) => response
+                .services
+                .iter()
+                .find(|svc| svc.name == name)
+                .cloned(),
+            DeploymentChange::Unchanged(_) => match client.get_service(&name).await {
+                Ok(service) => Some(service),
+                Err(MetasClientError::Api(err))
+                    if err.http_status_code == StatusCode::NOT_FOUND =>
+                {
+                    None
+                }
+                Err(err) => return Err(err.into()),
+            },
+        };
+
+        plans.push(match current {
+            None => ServicePlan::Conflict(
+                name,
+                format!("not defined by deployment {}", manifest.endpoint),
+            ),
+            Some(current) => {
+                let fields = changed_fields(&current, &request);
+                if fields.is_empty() {
+                    ServicePlan::Unchanged(name)
+                } else {
+                    ServicePlan::Update(ServiceChange {
+                        name,
+                        fields,
+                        request,
+                    })
+                }
+            }
+        });
+    }
+    Ok(plans)
+}
+
+/// Fields of the service configuration modified by the request
+fn changed_fields(
+    current: &ServiceMetadata,
+    request: &ModifyServiceRequest,
+) -> Vec<(&'static str, String, String)> {
+    fn duration(d: &Duration) -> String {
+        d.friendly().to_days_span().to_string()
+    }
+    fn optional_duration(d: &Option<Duration>) -> String {
+        d.as_ref()
+            .map(duration)
+            .unwrap_or_else(|| "<UNSET>".to_owned())
+    }
+    fn ingress_route(route: &Option<IngressRoute>) -> String {
+        match route {
+            None => "<UNSET>".to_owned(),
+            Some(route) => format!(
+                "{}{}",
+                route.host.as_deref().unwrap_or("<ANY>"),
+                route.path_prefix.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+
+    let mut fields = Vec::new();
+    if let Some(public) = request.public
+        && public != current.public
+    {
+        fields.push(("public", current.public.to_string(), public.to_string()));
+    }
+    if let Some(retention) = request.idempotency_retention
+        && retention != current.idempotency_retention
+    {
+        fields.push((
+            "idempotency_retention",
+            duration(&current.idempotency_retention),
+            duration(&retention),
+        ));
+    }
+    if let Some(retention) = request.workflow_completion_retention
+        && Some(retention) != current.workflow_completion_retention
+    {
+        fields.push((
+            "workflow_completion_retention",
+            optional_duration(&current.workflow_completion_retention),
+            duration(&retention),
+        ));
+    }
+    if let Some(retention) = request.journal_retention
+        && Some(retention) != current.journal_retention
+    {
+        fields.push((
+            "journal_retention",
+            optional_duration(&current.journal_retention),
+            duration(&retention),
+        ));
+    }
+    if let Some(timeout) = request.inactivity_timeout
+        && timeout != current.inactivity_timeout
+    {
+        fields.push((
+            "inactivity_timeout",
+            duration(&current.inactivity_timeout),
+            duration(&timeout),
+        ));
+    }
+    if let Some(timeout) = request.abort_timeout
+        && timeout != current.abort_timeout
+    {
+        fields.push((
+            "abort_timeout",
+            duration(&current.abort_timeout),
+            duration(&timeout),
+        ));
+    }
+    if let Some(max_journal_length) = request.max_journal_length
+        && Some(max_journal_length) != current.max_journal_length
+    {
+        fields.push((
+            "max_journal_length",
+            current
+                .max_journal_length
+                .map(|l| l.to_string())
+                .unwrap_or_else(|| "<UNSET>".to_owned()),
+            max_journal_length.to_string(),
+        ));
+    }
+    if let Some(ttl) = request.state_ttl
+        && Some(ttl) != current.state_ttl
+    {
+        fields.push((
+            "state_ttl",
+            optional_duration(&current.state_ttl),
+            duration(&ttl),
+        ));
+    }
+    if let Some(retention) = request.completion_retention
+        && Some(retention) != current.completion_retention
+    {
+        fields.push((
+            "completion_retention",
+            optional_duration(&current.completion_retention),
+            duration(&retention),
+        ));
+    }
+    if let Some(route) = &request.ingress_route {
+        // an empty route removes the current one
+        let route = Some(route.clone()).filter(|route| !route.is_empty());
+        if route != current.ingress_route {
+            fields.push((
+                "ingress_route",
+                ingress_route(&current.ingress_route),
+                ingress_route(&route),
+            ));
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest: DeploymentManifest = toml::from_str(
+            r#"
+            deployment = "http://greeter:9080"
+            use_http_11 = true
+
+            [additional_headers]
+            authorization = "Bearer token"
+
+            [services.Greeter]
+            public = false
+            idempotency_retention = "6h"
+            ingress_route = { path_prefix = "/greeter" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.deployment, "http://greeter:9080");
+        assert!(manifest.use_http_11);
+        assert!(!manifest.breaking);
+        let headers: HashMap<_, _> = manifest.additional_headers.unwrap().into();
+        assert_eq!(headers["authorization"], "Bearer token");
+
+        let greeter = &manifest.services["Greeter"];
+        assert_eq!(greeter.public, Some(false));
+        assert_eq!(
+            greeter.idempotency_retention,
+            Some(Duration::from_secs(6 * 60 * 60))
+        );
+        assert_eq!(
+            greeter
+                .ingress_route
+                .as_ref()
+                .unwrap()
+                .path_prefix
+                .as_deref(),
+            Some("/greeter")
+        );
+        assert!(greeter.journal_retention.is_none());
+    }
+
+    #[test]
+    fn load_manifests_from_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("greeter.toml"),
+            "deployment = \"http://greeter:9080\"\n[services.Greeter]\npublic = false\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("counter.toml"),
+            "deployment = \"arn:aws:lambda:eu-central-1:123456789012:function:counter:1\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a manifest").unwrap();
+
+        let manifests = load_manifests(dir.path()).unwrap();
+        assert_eq!(manifests.len(), 2);
+        assert!(matches!(
+            manifests[0].endpoint,
+            DeploymentEndpoint::Lambda(_)
+        ));
+        assert!(matches!(manifests[1].endpoint, DeploymentEndpoint::Uri(_)));
+
+        // two manifests configuring the same service conflict
+        std::fs::write(
+            dir.path().join("greeter-v2.toml"),
+            "deployment = \"http://greeter-v2:9080\"\n[services.Greeter]\npublic = true\n",
+        )
+        .unwrap();
+        let err = load_manifests(dir.path()).err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("both configure the service Greeter")
+        );
+    }
+
+    #[test]
+    fn reject_unknown_manifest_fields() {
+        assert!(
+            toml::from_str::<DeploymentManifest>(
+                r#"
+                deployment = "http://greeter:9080"
+                use_http2 = true
+                "#
+            )
+            .is_err()
+        );
+    }
+}
@@ -19,6 +19,7 @@ use restate_cli_util::ui::console::{Styled, StyledTable};
 use restate_cli_util::ui::stylesheet::Style;
 use restate_cli_util::ui::watcher::Watch;
 use restate_cli_util::{c_eprintln, c_indent_table, c_indentln, c_println, c_title};
+use restate_types::identifiers::DeploymentId;
 use restate_types::schema::service::ServiceMetadata;
 
 use crate::cli_env::CliEnv;
@@ -37,7 +38,7 @@ pub struct Describe {
     // TODO: Support inference of endpoint or ID, but this require the deployment
     // ID to follow a more constrained format
     /// Deployment ID
-    deployment_id: String,
+    deployment_id: DeploymentId,
 
     #[clap(flatten)]
     watch: Watch,
@@ -56,16 +57,12 @@ async fn describe(env: &CliEnv, opts: &Describe) -> Result<()> {
 
     let mut latest_services: HashMap<String, ServiceMetadata> = HashMap::new();
     // To know the latest version of every service.
-    let services = client.get_services().await?.into_body().await?.services;
+    let services = client.get_services().await?.services;
     for service in services {
         latest_services.insert(service.name.clone(), service);
     }
 
-    let deployment = client
-        .get_deployment(&opts.deployment_id)
-        .await?
-        .into_body()
-        .await?;
+    let deployment = client.get_deployment(&opts.deployment_id).await?;
 
     let (deployment_id, deployment, services) =
         Deployment::from_detailed_deployment_response(deployment);
@@ -53,14 +53,9 @@ async fn list(env: &CliEnv, list_opts: &List) -> Result<()> {
     let client = crate::clients::AdminClient::new(env).await?;
     let sql_client = crate::clients::DataFusionHttpClient::from(client.clone());
     // To know the latest version of every service.
-    let services = client.get_services().await?.into_body().await?.services;
-
-    let deployments = client
-        .get_deployments()
-        .await?
-        .into_body()
-        .await?
-        .deployments;
+    let services = client.get_services().await?.services;
+
+    let deployments = client.get_deployments().await?.deployments;
 
     if deployments.is_empty() {
         c_error!(
@@ -8,9 +8,10 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod apply;
 mod describe;
 mod list;
-mod register;
+pub(crate) mod register;
 mod remove;
 
 use cling::prelude::*;
@@ -26,4 +27,10 @@ pub enum Deployments {
     Describe(describe::Describe),
     /// Remove a drained deployment
     Remove(remove::Remove),
+    /// Register the deployments described by a directory of manifests, and configure their services
+    ///
+    /// Deployments that are already registered are left unchanged, making it safe to run from CI
+    /// pipelines. Exits with an error, without applying any change, if a manifest conflicts with
+    /// the registered deployments.
+    Apply(apply::Apply),
 }
@@ -26,7 +26,7 @@ use restate_admin_rest_model::version::AdminApiVersion;
 use restate_cli_util::ui::console::{Styled, StyledTable, confirm_or_exit};
 use restate_cli_util::ui::stylesheet::Style;
 use restate_cli_util::{c_eprintln, c_error, c_indent_table, c_indentln, c_success, c_warn};
-use restate_types::identifiers::LambdaARN;
+use restate_types::identifiers::{DeploymentId, LambdaARN};
 use restate_types::schema::service::ServiceMetadata;
 
 use crate::cli_env::CliEnv;
@@ -45,8 +45,8 @@ pub struct Register {
     #[clap(long)]
     breaking: bool,
 
-    /// Force overwriting the deployment if it already exists or if incompatible changes were
-    /// detected during discovery. When set, implies `--breaking`.
+    /// Force overwriting the deployment if it already exists. Incompatible changes detected
+    /// during discovery are still rejected, unless `--breaking` is set as well.
     #[clap(long)]
     force: bool,
 
@@ -93,13 +93,13 @@ struct Metadata {
 }
 
 #[derive(Clone, Debug)]
-enum DeploymentEndpoint {
+pub(super) enum DeploymentEndpoint {
     Uri(Uri),
     Lambda(LambdaARN),
 }
 
 impl DeploymentEndpoint {
-    fn cli_parameter_display(&self) -> String {
+    pub(super) fn cli_parameter_display(&self) -> String {
         match self {
             DeploymentEndpoint::Uri(uri) => uri.to_string(),
             DeploymentEndpoint::Lambda(arn) => arn.to_string(),
@@ -149,7 +149,7 @@ fn parse_metadata(
 }
 
 // Needed as a function to allow clap to parse to [`Deployment`]
-fn parse_deployment(
+pub(super) fn parse_deployment(
     raw: &str,
 ) -> Result<DeploymentEndpoint, Box<dyn std::error::Error + Send + Sync + 'static>> {
     let deployment = if raw.starts_with("arn:") {
@@ -200,50 +200,7 @@ pub async fn run_register(State(env): State<CliEnv>, discover_opts: &Register) -
     let deployment = match &discover_opts.deployment {
         #[cfg(feature = "cloud")]
         DeploymentEndpoint::Uri(uri) if uri.scheme_str() == Some("tunnel") => {
-            let environment_info = match (
-                &env.config.environment_type,
-                &env.config.cloud.environment_info,
-            ) {
-                (crate::cli_env::EnvironmentType::Cloud, Some(environment_info)) => {
-                    environment_info
-                }
-                _ => {
-                    return Err(anyhow::anyhow!(
-                        "To register tunnel:// URLs, first switch to the Cloud environment you're tunnelling to using `restate config use-environment`"
-                    ));
-                }
-            };
-
-            let unprefixed_environment_id = environment_info
-                .environment_id
-                .strip_prefix("env_")
-                .ok_or(anyhow::anyhow!(
-                    "Unexpected environment ID format: {}",
-                    environment_info.environment_id
-                ))?;
-
-            let authority = uri
-                .authority()
-                .ok_or(anyhow::anyhow!("tunnel:// URLs must have an authority"))?;
-
-            let port = authority
-                .port_u16()
-                .ok_or(anyhow::anyhow!("tunnel:// URLs must have a port"))?;
-
-            let proxy_host = &env
-                .config
-                .cloud
-                .proxy_base_url
-                .host_str()
-                .expect("proxy_base_url must have a host");
-
-            let uri = Uri::builder()
-                .scheme(env.config.cloud.proxy_base_url.scheme())
-                .authority(format!("{proxy_host}:{port}"))
-                .path_and_query(format!("/{unprefixed_environment_id}/{}", authority.host()))
-                .build()?;
-
-            DeploymentEndpoint::Uri(uri)
+            DeploymentEndpoint::Uri(tunnel_proxy_uri(&env, uri)?)
         }
         other => other.clone(),
     };
@@ -278,6 +235,53 @@ pub async fn run_register(State(env): State<CliEnv>, discover_opts: &Register) -
     Ok(())
 }
 
+/// Resolves a `tunnel://<tunnel-name>:<port>` URL to the Restate Cloud proxy URL through which the
+/// current Cloud environment reaches the tunnelled deployment.
+#[cfg(feature = "cloud")]
+pub(crate) fn tunnel_proxy_uri(env: &CliEnv, uri: &Uri) -> Result<Uri> {
+    let environment_info = match (
+        &env.config.environment_type,
+        &env.config.cloud.environment_info,
+    ) {
+        (crate::cli_env::EnvironmentType::Cloud, Some(environment_info)) => environment_info,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "To register tunnel:// URLs, first switch to the Cloud environment you're tunnelling to using `restate config use-environment`"
+            ));
+        }
+    };
+
+    let unprefixed_environment_id =
+        environment_info
+            .environment_id
+            .strip_prefix("env_")
+            .ok_or(anyhow::anyhow!(
+                "Unexpected environment ID format: {}",
+                environment_info.environment_id
+            ))?;
+
+    let authority = uri
+        .authority()
+        .ok_or(anyhow::anyhow!("tunnel:// URLs must have an authority"))?;
+
+    let port = authority
+        .port_u16()
+        .ok_or(anyhow::anyhow!("tunnel:// URLs must have a port"))?;
+
+    let proxy_host = &env
+        .config
+        .cloud
+        .proxy_base_url
+        .host_str()
+        .expect("proxy_base_url must have a host");
+
+    Ok(Uri::builder()
+        .scheme(env.config.cloud.proxy_base_url.scheme())
+        .authority(format!("{proxy_host}:{port}"))
+        .path_and_query(format!("/{unprefixed_environment_id}/{}", authority.host()))
+        .build()?)
+}
+
 async fn register_v3_admin_api(
     discover_opts: &Register,
     client: AdminClient,
@@ -297,34 +301,40 @@ async fn register_v3_admin_api(
     let dry_run_result = client
         // We use force in the dry-run to make sure we get the result of the discovery
         // even if there is it's an existing endpoint
-        .discover_deployment(mk_request_body(
+        .discover_deployment_with_status(mk_request_body(
             discover_opts.breaking,
             discover_opts.force, /* dry_run = */
             true,
         ))
-        .await?;
-
-    if dry_run_result.status_code() == StatusCode::CONFLICT {
-        progress.finish_and_clear();
-        let api_error = dry_run_result.into_api_error().await?;
-        c_println!(
-            indoc! {
-                "{}
-                {}
+        .await;
+
+    let (status_code, dry_run_response) = match dry_run_result {
+        Ok(result) => result,
+        Err(MetasClientError::Api(api_error))
+            if api_error.http_status_code == StatusCode::CONFLICT =>
+        {
+            progress.finish_and_clear();
+            c_println!(
+                indoc! {
+                    "{}
+                    {}
+
+                ❯ To register a deployment containing breaking changes for a service, use:
+                    restate deployment register {}{} --breaking"
+                },
+                Styled(Style::Danger, "❯ Breaking changes detected:"),
+                Styled(Style::Warn, api_error.body),
+                discover_opts.deployment.cli_parameter_display(),
+                if discover_opts.force { " --force" } else { "" }
+            );
+            bail!("Registration failed");
+        }
+        Err(err) => return Err(err.into()),
+    };
 
-            ❯ To register a deployment containing breaking changes for a service, use:
-                restate deployment register {} --breaking"
-            },
-            Styled(Style::Danger, "❯ Breaking changes detected:"),
-            Styled(Style::Warn, api_error.body),
-            discover_opts.deployment.cli_parameter_display()
-        );
-        bail!("Registration failed");
-    }
-    if dry_run_result.status_code() == StatusCode::OK && !discover_opts.force {
+    if status_code == StatusCode::OK && !discover_opts.force {
         progress.finish_and_clear();
         // Admin API V3 returns OK if the deployment already exists and force = false.
-        let dry_run_result = dry_run_result.into_body().await?;
         c_println!(
             indoc! {
                 "❯ Deployment already exists with id {}
@@ -336,25 +346,21 @@ async fn register_v3_admin_api(
             ❯ To modify connection parameters, use:
                 restate deployment edit {}"
             },
-            Styled(Style::Info, &dry_run_result.id),
+            Styled(Style::Info, &dry_run_response.id),
             discover_opts.deployment.cli_parameter_display(),
-            dry_run_result.id
+            dry_run_response.id
         );
         return Ok(());
     }
     // At this point, if the deployment exists, StatusCode == OK and force = true
-    let deployment_exists = dry_run_result.status_code() == StatusCode::OK;
-
-    let dry_run_response = dry_run_result.into_body().await?;
+    let deployment_exists = status_code == StatusCode::OK;
 
     progress.finish_and_clear();
 
     let existing_deployment = if deployment_exists {
         Some(
             client
-                .get_deployment(&dry_run_response.id.to_string())
-                .await?
-                .into_body()
+                .get_deployment(&dry_run_response.id)
                 .await
                 .with_context(|| format!("Failed to get deployment {}", dry_run_response.id))?,
         )
@@ -397,8 +403,6 @@ async fn register_v3_admin_api(
             discover_opts.force,
             /* dry_run = */ false,
         ))
-        .await?
-        .into_body()
         .await?;
 
     progress.finish_and_clear();
@@ -436,19 +440,12 @@ async fn register_v2_admin_api(
         .discover_deployment(mk_request_body(
             /* breaking */ true, /* force = */ true, /* dry_run = */ true,
         ))
-        .await?
-        .into_body()
         .await?;
 
     progress.finish_and_clear();
 
     // Is this an existing deployment?
-    let existing_deployment = match client
-        .get_deployment(&dry_run_result.id.to_string())
-        .await?
-        .into_body()
-        .await
-    {
+    let existing_deployment = match client.get_deployment(&dry_run_result.id).await {
         Ok(existing_deployment) => {
             // Appears to be an existing endpoint.
             Some(existing_deployment)
@@ -500,8 +497,6 @@ async fn register_v2_admin_api(
             discover_opts.force,
             /* dry_run = */ false,
         ))
-        .await?
-        .into_body()
         .await?;
 
     progress.finish_and_clear();
@@ -564,7 +559,7 @@ async fn print_registration_changes(
     if !updated.is_empty() {
         c_println!();
         // used to resolving old deployments.
-        let mut deployment_cache: HashMap<String, Deployment> = HashMap::new();
+        let mut deployment_cache: HashMap<DeploymentId, Deployment> = HashMap::new();
         // A single spinner spans all requests.
         let progress = ProgressBar::new_spinner();
         progress.set_style(
@@ -579,7 +574,7 @@ async fn print_registration_changes(
                 "Fetching information about service '{}'",
                 service.name,
             ));
-            match client.get_service(&service.name).await?.into_body().await {
+            match client.get_service(&service.name).await {
                 Ok(service_metadata) => {
                     existing_services.insert(service.name.clone(), service_metadata);
                 }
@@ -617,7 +612,7 @@ async fn print_registration_changes(
                     let maybe_old_deployment = resolve_deployment(
                         client,
                         &mut deployment_cache,
-                        &existing_svc.deployment_id.to_string(),
+                        &existing_svc.deployment_id,
                     )
                     .await;
                     let old_deployment_message = maybe_old_deployment
@@ -673,8 +668,8 @@ async fn print_registration_changes(
 
 async fn resolve_deployment(
     client: &AdminClient,
-    cache: &mut HashMap<String, Deployment>,
-    deployment_id: &str,
+    cache: &mut HashMap<DeploymentId, Deployment>,
+    deployment_id: &DeploymentId,
 ) -> Option<Deployment> {
     if cache.contains_key(deployment_id) {
         return cache.get(deployment_id).cloned();
@@ -692,13 +687,10 @@ async fn resolve_deployment(
     let deployment = client
         .get_deployment(deployment_id)
         .await
-        .ok()?
-        .into_body()
-        .await
         .ok()
         .map(|endpoint| {
             let (_, deployment, _) = Deployment::from_detailed_deployment_response(endpoint);
-            cache.insert(deployment_id.to_string(), deployment.clone());
+            cache.insert(*deployment_id, deployment.clone());
             Some(deployment)
         })?;
     progress.finish_and_clear();
@@ -706,7 +698,7 @@ async fn resolve_deployment(
     deployment
 }
 
-fn infer_deployment_metadata_from_environment(metadata: &mut HashMap<String, String>) {
+pub(super) fn infer_deployment_metadata_from_environment(metadata: &mut HashMap<String, String>) {
     use restate_types::deployment::metadata::*;
 
     macro_rules! add_envs {
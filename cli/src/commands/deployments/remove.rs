@@ -19,6 +19,7 @@ use restate_admin_rest_model::deployments::ServiceNameRevPair;
 use restate_cli_util::ui::console::{Styled, StyledTable, confirm_or_exit};
 use restate_cli_util::ui::stylesheet::Style;
 use restate_cli_util::{c_eprintln, c_error, c_indentln, c_success};
+use restate_types::identifiers::DeploymentId;
 use restate_types::schema::service::ServiceMetadata;
 
 use crate::cli_env::CliEnv;
@@ -42,7 +43,7 @@ pub struct Remove {
     // TODO: Support inference of endpoint or ID, but this require the deployment
     // ID to follow a more constrained format
     /// Deployment ID
-    deployment_id: String,
+    deployment_id: DeploymentId,
 }
 
 pub async fn run_remove(State(env): State<CliEnv>, opts: &Remove) -> Result<()> {
@@ -50,18 +51,14 @@ pub async fn run_remove(State(env): State<CliEnv>, opts: &Remove) -> Result<()>
     let client = AdminClient::new(&env).await?;
     let sql_client = crate::clients::DataFusionHttpClient::from(client.clone());
 
-    let deployment = client
-        .get_deployment(&opts.deployment_id)
-        .await?
-        .into_body()
-        .await?;
+    let deployment = client.get_deployment(&opts.deployment_id).await?;
     let (deployment_id, deployment, deployment_services) =
         Deployment::from_detailed_deployment_response(deployment);
     let active_inv = count_deployment_active_inv_by_method(&sql_client, &deployment_id).await?;
 
     let mut latest_services: HashMap<String, ServiceMetadata> = HashMap::new();
     // To know the latest version of every service.
-    for service in client.get_services().await?.into_body().await?.services {
+    for service in client.get_services().await?.services {
         latest_services.insert(service.name.clone(), service);
     }
 
@@ -176,14 +173,13 @@ pub async fn run_remove(State(env): State<CliEnv>, opts: &Remove) -> Result<()>
 
     confirm_or_exit("Are you sure you want to remove this deployment?")?;
 
-    let result = client
+    client
         .remove_deployment(
             &opts.deployment_id,
             //TODO: Use opts.force when the server implements the false + validation case!
             true,
         )
         .await?;
-    let _ = result.success_or_error()?;
 
     c_println!();
     c_success!("Deployment {} removed successfully", &opts.deployment_id);
@@ -8,7 +8,11 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use anyhow::{Result, anyhow};
+mod scenario;
+
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow, bail};
 use cling::prelude::*;
 use comfy_table::{Cell, Table};
 use tokio::net::TcpListener;
@@ -25,6 +29,8 @@ use restate_types::net::address::{AdminPort, HttpIngressPort, ListenerPort};
 use crate::build_info;
 use crate::cli_env::CliEnv;
 
+use self::scenario::Scenario;
+
 #[derive(Run, Parser, Collect, Clone)]
 #[cling(run = "run")]
 pub struct Dev {
@@ -39,9 +45,19 @@ pub struct Dev {
     /// Do not delete the temporary data directory after exiting
     #[clap(long)]
     retain: bool,
+
+    /// Run the steps of the given YAML scenario once restate is running, then exit. The exit
+    /// code reports whether the scenario succeeded.
+    #[clap(long, value_name = "FILE")]
+    scenario: Option<PathBuf>,
 }
 
 pub async fn run(State(_env): State<CliEnv>, opts: &Dev) -> Result<()> {
+    let scenario = opts.scenario.as_deref().map(Scenario::load).transpose()?;
+    if scenario.is_some() && opts.use_unix_sockets {
+        bail!("Scenarios invoke handlers through the HTTP ingress, which needs TCP listeners");
+    }
+
     let cancellation = CancellationToken::new();
     let temp_dir = tempfile::tempdir()?;
     let data_dir = temp_dir.path().to_path_buf();
@@ -116,6 +132,18 @@ pub async fn run(State(_env): State<CliEnv>, opts: &Dev) -> Result<()> {
         c_println!("✅ `Counter` service endpoint is running on {mock_svc_addr}");
     }
 
+    if let Some(scenario) = scenario {
+        let ingress_url = addresses
+            .iter()
+            .find(|address| address.name == HttpIngressPort::NAME)
+            .map(|address| address.address.clone())
+            .expect("Ingress port is always set");
+        c_println!();
+        let result = scenario.run(&restate, &ingress_url).await;
+        restate.stop().await?;
+        return result;
+    }
+
     if let Err(_err) = open::that(&admin_url) {
         c_println!("Failed to open browser automatically. Please open {admin_url} manually.")
     }
@@ -0,0 +1,214 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Scenarios are small YAML scripts executed by `restate dev --scenario <file>` against the
+//! embedded Restate instance, e.g.:
+//!
+//! ```yaml
+//! steps:
+//!   - register: http://localhost:9080
+//!   - invoke:
+//!       target: Greeter/greet
+//!       payload: Sarah
+//!       expect: Hello Sarah!
+//!   - sleep: 1s
+//!   - invoke:
+//!       target: Counter/alice/add
+//!       payload: 1
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use indicatif::ProgressBar;
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
+
+use restate_cli_util::{c_error, c_success};
+use restate_lite::Restate;
+use restate_time_util::{DurationExt, FriendlyDuration};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Step {
+    /// Registers the deployment listening at the given uri
+    Register(String),
+    /// Invokes a handler and waits for its completion
+    Invoke(Invoke),
+    /// Pauses the scenario
+    Sleep(FriendlyDuration),
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Invoke {
+    /// `Service/handler`, or `Object/key/handler` for Virtual Objects and Workflows
+    target: String,
+    /// JSON payload of the request, the request has no body if unset
+    #[serde(default)]
+    payload: Option<Value>,
+    /// Expected JSON output of the handler, the output is not checked if unset
+    #[serde(default)]
+    expect: Option<Value>,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Register(uri) => write!(f, "Register deployment {uri}"),
+            Step::Invoke(invoke) => write!(f, "Invoke {}", invoke.target),
+            Step::Sleep(duration) => write!(f, "Sleep {}", duration.to_std().friendly()),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read scenario {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("cannot parse scenario {}", path.display()))
+    }
+
+    /// Runs the steps in order, stopping at the first failing one.
+    pub async fn run(&self, restate: &Restate, ingress_url: &str) -> Result<()> {
+        let ingress_url = Url::parse(ingress_url)?;
+        let client = reqwest::Client::new();
+
+        for (idx, step) in self.steps.iter().enumerate() {
+            let progress = ProgressBar::new_spinner();
+            progress.set_style(
+                indicatif::ProgressStyle::with_template("{spinner} [{elapsed}] {msg}").unwrap(),
+            );
+            progress.enable_steady_tick(std::time::Duration::from_millis(120));
+            progress.set_message(step.to_string());
+
+            let result = match step {
+                Step::Register(uri) => restate.discover_deployment(uri).await,
+                Step::Invoke(invoke) => invoke.run(&client, &ingress_url).await,
+                Step::Sleep(duration) => {
+                    tokio::time::sleep(duration.to_std()).await;
+                    Ok(())
+                }
+            };
+            progress.finish_and_clear();
+
+            match result {
+                Ok(()) => c_success!("[{}/{}] {step}", idx + 1, self.steps.len()),
+                Err(err) => {
+                    c_error!("[{}/{}] {step}: {err:#}", idx + 1, self.steps.len());
+                    bail!("scenario failed at step {}", idx + 1);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Invoke {
+    async fn run(&self, client: &reqwest::Client, ingress_url: &Url) -> Result<()> {
+        let url = ingress_url.join(&self.target)?;
+        let mut request = client.post(url);
+        if let Some(payload) = &self.payload {
+            request = request.json(payload);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            bail!("failed with {status}: {}", String::from_utf8_lossy(&body));
+        }
+
+        let Some(expected) = &self.expect else {
+            return Ok(());
+        };
+        let output = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&body).context("output is not JSON")?
+        };
+        check_output(expected, &output)
+    }
+}
+
+fn check_output(expected: &Value, output: &Value) -> Result<()> {
+    if expected != output {
+        bail!("expected output {expected}, got {output}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    #[test]
+    fn parse_scenario() {
+        let scenario: Scenario = serde_yaml::from_str(
+            "
+steps:
+  - register: http://localhost:9080
+  - invoke:
+      target: Greeter/greet
+      payload: Sarah
+      expect: Hello Sarah!
+  - sleep: 1s
+  - invoke:
+      target: Counter/alice/add
+      payload:
+        amount: 1
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scenario.steps,
+            vec![
+                Step::Register("http://localhost:9080".to_owned()),
+                Step::Invoke(Invoke {
+                    target: "Greeter/greet".to_owned(),
+                    payload: Some(json!("Sarah")),
+                    expect: Some(json!("Hello Sarah!")),
+                }),
+                Step::Sleep(FriendlyDuration::from(Duration::from_secs(1))),
+                Step::Invoke(Invoke {
+                    target: "Counter/alice/add".to_owned(),
+                    payload: Some(json!({"amount": 1})),
+                    expect: None,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_step_is_rejected() {
+        assert!(serde_yaml::from_str::<Scenario>("steps:\n  - kill: Greeter\n").is_err());
+    }
+
+    #[test]
+    fn output_is_checked() {
+        assert!(check_output(&json!({"count": 1}), &json!({"count": 1})).is_ok());
+        assert!(check_output(&json!({"count": 1}), &json!({"count": 2})).is_err());
+    }
+}
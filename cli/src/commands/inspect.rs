@@ -0,0 +1,260 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use cling::prelude::*;
+use futures::TryStreamExt;
+
+use restate_cli_util::c_eprintln;
+use restate_cli_util::ui::console::Styled;
+use restate_cli_util::ui::stylesheet::Style;
+use restate_core::{TaskCenterBuilder, TaskCenterFutureExt};
+use restate_partition_store::PartitionStoreManager;
+use restate_rocksdb::RocksDbManager;
+use restate_storage_query_datafusion::offline::create_offline_query_context;
+use restate_types::config::Configuration;
+use restate_types::identifiers::{InvocationId, PartitionId, PartitionKey};
+use restate_types::partitions::Partition;
+
+use crate::commands::sql::print_batches;
+
+/// Inspect the invocations, journals and state of a node's data, without a running server.
+///
+/// The path can be either a copy of the data directory of a node, the directory containing the
+/// `db` directory, which is opened read-only, or an exported bundle of partition snapshots.
+#[derive(Run, Parser, Collect, Clone)]
+#[cling(run = "run_inspect")]
+#[command(group(clap::ArgGroup::new("query").required(true)))]
+pub struct Inspect {
+    /// Path to the node data directory or to the bundle of partition snapshots
+    path: PathBuf,
+
+    /// List the most recently modified invocations
+    #[arg(long, group = "query")]
+    invocations: bool,
+
+    /// Print the journal of the given invocation
+    #[arg(long, group = "query", value_name = "INVOCATION_ID")]
+    journal: Option<InvocationId>,
+
+    /// Print the state of the given virtual object or workflow
+    #[arg(long, group = "query", value_name = "SERVICE/KEY")]
+    state: Option<String>,
+
+    /// Run the given SQL query. Only the tables populated by the partitions are available, e.g.
+    /// sys_invocation, sys_journal, sys_journal_events, sys_inbox, sys_idempotency, sys_promise
+    /// and state.
+    #[arg(long, group = "query")]
+    sql: Option<String>,
+
+    /// Limit of the listed invocations
+    #[arg(long, default_value = "100")]
+    limit: usize,
+
+    /// Print result as line delimited json instead of using the tabular format
+    #[arg(long, alias = "ldjson")]
+    jsonl: bool,
+
+    /// Print result as json array instead of using the tabular format
+    #[arg(long)]
+    json: bool,
+}
+
+enum Source {
+    /// Copy of the data directory of a node
+    DataDir(PathBuf),
+    /// Partition snapshots, laid out as in the snapshot repository
+    SnapshotBundle {
+        path: PathBuf,
+        partition_ids: Vec<PartitionId>,
+    },
+}
+
+impl Source {
+    fn detect(path: &Path) -> Result<Self> {
+        let path = std::fs::canonicalize(path)
+            .with_context(|| format!("cannot access '{}'", path.display()))?;
+
+        if path.join("db").is_dir() {
+            return Ok(Source::DataDir(path));
+        }
+
+        let mut partition_ids = Vec::new();
+        for entry in std::fs::read_dir(&path)? {
+            let entry = entry?;
+            if let Some(partition_id) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<PartitionId>().ok())
+                && entry.path().join("latest.json").is_file()
+            {
+                partition_ids.push(partition_id);
+            }
+        }
+        if partition_ids.is_empty() {
+            bail!(
+                "'{}' is neither a node data directory nor a bundle of partition snapshots",
+                path.display()
+            );
+        }
+        partition_ids.sort();
+
+        Ok(Source::SnapshotBundle {
+            path,
+            partition_ids,
+        })
+    }
+}
+
+impl Inspect {
+    fn query(&self) -> String {
+        if let Some(sql) = &self.sql {
+            sql.clone()
+        } else if let Some(invocation_id) = &self.journal {
+            format!(
+                "SELECT index, entry_type, name, completed, entry_json FROM sys_journal WHERE id = '{invocation_id}' ORDER BY index"
+            )
+        } else if let Some(state) = &self.state {
+            let (service, key) = state.split_once('/').unwrap_or((state, ""));
+            // the key is arbitrary user input, escape the quotes of the string literals
+            let service = service.replace('\'', "''");
+            let key = key.replace('\'', "''");
+            format!(
+                "SELECT key, value_utf8, value_length FROM state WHERE service_name = '{service}' AND service_key = '{key}' ORDER BY key"
+            )
+        } else {
+            format!(
+                "SELECT id, target, status, created_at, modified_at FROM sys_invocation ORDER BY modified_at DESC LIMIT {}",
+                self.limit
+            )
+        }
+    }
+}
+
+pub async fn run_inspect(opts: &Inspect) -> Result<()> {
+    let source = Source::detect(&opts.path)?;
+    // keeps the rocksdb info logs of the secondary instance, or the restored snapshots
+    let scratch_dir = tempfile::tempdir()?;
+
+    let mut config = Configuration::default();
+    match &source {
+        Source::DataDir(path) => {
+            if let Some(parent) = path.parent() {
+                config.common.set_base_dir(parent);
+            }
+            if let Some(node_name) = path.file_name().and_then(|name| name.to_str()) {
+                config.common.set_node_name(node_name);
+            }
+        }
+        Source::SnapshotBundle { path, .. } => {
+            config.common.set_base_dir(scratch_dir.path());
+            config.worker.snapshots.destination = Some(format!("file://{}", path.display()));
+        }
+    }
+    let config = config.apply_cascading_values();
+    restate_types::config::set_current_config(config.clone());
+
+    let task_center = TaskCenterBuilder::default()
+        .default_runtime_handle(tokio::runtime::Handle::current())
+        .options(config.common.clone())
+        .build()
+        .context("failed to build task center")?
+        .into_handle();
+
+    let result = async {
+        RocksDbManager::init();
+        let result = inspect(opts, &source, &config, scratch_dir.path()).await;
+        RocksDbManager::get().shutdown().await;
+        result
+    }
+    .in_tc(&task_center)
+    .await;
+
+    task_center.shutdown_node("finished", 0).await;
+    result
+}
+
+async fn inspect(
+    opts: &Inspect,
+    source: &Source,
+    config: &Configuration,
+    scratch_dir: &Path,
+) -> Result<()> {
+    let (partition_store_manager, partition_ids) = match source {
+        Source::DataDir(_) => {
+            let db_name = full_partition(PartitionId::MIN).db_name();
+            let partition_ids = PartitionStoreManager::list_local_partitions(db_name.as_ref())?;
+            let manager =
+                PartitionStoreManager::create_secondary(scratch_dir.join("secondary")).await?;
+            (manager, partition_ids)
+        }
+        Source::SnapshotBundle { partition_ids, .. } => (
+            PartitionStoreManager::create().await?,
+            partition_ids.clone(),
+        ),
+    };
+
+    // The key ranges of the partitions aren't known offline, but each partition holds the data of
+    // its own range only.
+    let mut partitions = Vec::with_capacity(partition_ids.len());
+    for partition_id in partition_ids {
+        let partition = full_partition(partition_id);
+        partition_store_manager
+            .open(&partition, None)
+            .await
+            .with_context(|| format!("failed to open partition {partition_id}"))?;
+        partitions.push((partition_id, partition));
+    }
+    c_eprintln!(
+        "Opened {} partitions",
+        Styled(Style::Notice, partitions.len())
+    );
+
+    let query_context = create_offline_query_context(
+        &config.admin.query_engine,
+        partitions,
+        partition_store_manager,
+    )
+    .await?;
+
+    let start_time = Instant::now();
+    let stream = query_context.execute(&opts.query()).await?;
+    let schema = stream.schema();
+    let batches: Vec<_> = stream.try_collect().await?;
+
+    let row_count = print_batches(&schema, batches, opts.json, opts.jsonl)?;
+    c_eprintln!(
+        "{} rows. Query took {:?}",
+        row_count,
+        Styled(Style::Notice, start_time.elapsed())
+    );
+    Ok(())
+}
+
+fn full_partition(partition_id: PartitionId) -> Partition {
+    Partition::new(partition_id, PartitionKey::MIN..=PartitionKey::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_query_escapes_quotes() {
+        let inspect = Inspect::parse_from(["inspect", ".", "--state", "Greeter/O'Brien"]);
+        assert_eq!(
+            inspect.query(),
+            "SELECT key, value_utf8, value_length FROM state WHERE service_name = 'Greeter' AND service_key = 'O''Brien' ORDER BY key"
+        );
+    }
+}
@@ -64,7 +64,6 @@ pub async fn run_restart_as_new(State(env): State<CliEnv>, opts: &RestartAsNew)
                 client
                     .restart_invocation(&invocation_id)
                     .map_err(anyhow::Error::from)
-                    .and_then(|e| async { e.into_body().map_err(anyhow::Error::from).await })
                     .await
                     .map(|response| (invocation_id.clone(), response.new_invocation_id))
                     .map_err(|e| (invocation_id, e))
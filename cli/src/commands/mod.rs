@@ -16,6 +16,8 @@ pub mod deployments;
 #[cfg(feature = "dev-cmd")]
 pub mod dev;
 pub mod examples;
+#[cfg(feature = "inspect-cmd")]
+pub mod inspect;
 pub mod invocations;
 pub mod services;
 pub mod sql;
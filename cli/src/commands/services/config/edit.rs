@@ -31,11 +31,7 @@ pub async fn run_edit(State(env): State<CliEnv>, opts: &Edit) -> Result<()> {
 
 async fn edit(env: &CliEnv, opts: &Edit) -> Result<()> {
     let admin_client = AdminClient::new(env).await?;
-    let svc = admin_client
-        .get_service(&opts.service)
-        .await?
-        .into_body()
-        .await?;
+    let svc = admin_client.get_service(&opts.service).await?;
 
     // Prepare file to edit
     let tempdir = tempdir().context("unable to create a temporary directory")?;
@@ -102,6 +98,38 @@ fn write_out_edit_toml(w: &mut impl io::Write, service_type: ServiceType) -> Res
     writeln!(w, "# abort_timeout = \"10min\"")?;
     writeln!(w)?;
 
+    write_prefixed_lines(w, "# ", super::view::MAX_JOURNAL_LENGTH)?;
+    writeln!(w, "# Example:")?;
+    writeln!(w, "# max_journal_length = 10000")?;
+    writeln!(w)?;
+
+    write_prefixed_lines(w, "# ", super::patch::STATE_TTL_EDIT_DESCRIPTION)?;
+    writeln!(w, "# Example:")?;
+    writeln!(w, "# state_ttl = \"7days\"")?;
+    writeln!(w)?;
+
+    write_prefixed_lines(w, "# ", super::patch::COMPLETION_RETENTION_EDIT_DESCRIPTION)?;
+    writeln!(w, "# Example:")?;
+    writeln!(w, "# completion_retention = \"1day\"")?;
+    writeln!(w)?;
+
+    write_prefixed_lines(w, "# ", super::view::INGRESS_ROUTE)?;
+    writeln!(w, "# Example:")?;
+    writeln!(w, "# [ingress_route]")?;
+    writeln!(w, "# host = \"payments.example.com\"")?;
+    writeln!(w, "# path_prefix = \"/payments\"")?;
+    writeln!(w)?;
+
+    write_prefixed_lines(w, "# ", super::view::INGRESS_LIMITS)?;
+    writeln!(w, "# Example:")?;
+    writeln!(w, "# [ingress_limits.service]")?;
+    writeln!(w, "# requests_per_second = 100")?;
+    writeln!(w, "# max_concurrency = 50")?;
+    writeln!(w, "# [ingress_limits.handlers.charge]")?;
+    writeln!(w, "# requests_per_second = 10")?;
+    writeln!(w, "# burst = 20")?;
+    writeln!(w)?;
+
     Ok(())
 }
 
@@ -8,6 +8,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::num::NonZeroU32;
+
 use anyhow::Result;
 use cling::prelude::*;
 use comfy_table::Table;
@@ -17,6 +19,7 @@ use restate_admin_rest_model::services::ModifyServiceRequest;
 use restate_cli_util::c_println;
 use restate_cli_util::ui::console::{StyledTable, confirm_or_exit};
 use restate_time_util::{DurationExt, FriendlyDuration};
+use restate_types::schema::service::IngressRoute;
 
 use crate::cli_env::CliEnv;
 use crate::clients::{AdminClient, AdminClientInterface};
@@ -45,6 +48,13 @@ pub(super) const INACTIVITY_TIMEOUT_EDIT_DESCRIPTION: &str = concatcp!(
 );
 pub(super) const ABORT_TIMEOUT_EDIT_DESCRIPTION: &str =
     concatcp!(super::view::ABORT_TIMEOUT, "\n", DURATION_EDIT_DESCRIPTION);
+pub(super) const STATE_TTL_EDIT_DESCRIPTION: &str =
+    concatcp!(super::view::STATE_TTL, "\n", DURATION_EDIT_DESCRIPTION);
+pub(super) const COMPLETION_RETENTION_EDIT_DESCRIPTION: &str = concatcp!(
+    super::view::COMPLETION_RETENTION,
+    "\n",
+    DURATION_EDIT_DESCRIPTION
+);
 
 #[derive(Run, Parser, Collect, Clone)]
 #[cling(run = "run_patch")]
@@ -67,6 +77,25 @@ pub struct Patch {
     #[clap(long, alias = "abort_timeout", help = ABORT_TIMEOUT_EDIT_DESCRIPTION)]
     abort_timeout: Option<FriendlyDuration>,
 
+    #[clap(long, alias = "max_journal_length", help = super::view::MAX_JOURNAL_LENGTH)]
+    max_journal_length: Option<NonZeroU32>,
+
+    #[clap(long, alias = "state_ttl", help = STATE_TTL_EDIT_DESCRIPTION)]
+    state_ttl: Option<FriendlyDuration>,
+
+    #[clap(long, alias = "completion_retention", help = COMPLETION_RETENTION_EDIT_DESCRIPTION)]
+    completion_retention: Option<FriendlyDuration>,
+
+    /// Host of the ingress route, e.g. `payments.example.com`.
+    /// Setting the host or the path prefix replaces the whole ingress route, while setting both to an empty string removes it.
+    #[clap(long, alias = "ingress_host")]
+    ingress_host: Option<String>,
+
+    /// Path prefix of the ingress route, e.g. `/payments`.
+    /// Setting the host or the path prefix replaces the whole ingress route, while setting both to an empty string removes it.
+    #[clap(long, alias = "ingress_path_prefix")]
+    ingress_path_prefix: Option<String>,
+
     /// Service name
     service: String,
 }
@@ -86,6 +115,16 @@ async fn patch(env: &CliEnv, opts: &Patch) -> Result<()> {
         journal_retention: opts.journal_retention.map(FriendlyDuration::to_std),
         inactivity_timeout: opts.inactivity_timeout.map(FriendlyDuration::to_std),
         abort_timeout: opts.abort_timeout.map(FriendlyDuration::to_std),
+        max_journal_length: opts.max_journal_length,
+        state_ttl: opts.state_ttl.map(FriendlyDuration::to_std),
+        completion_retention: opts.completion_retention.map(FriendlyDuration::to_std),
+        ingress_route: (opts.ingress_host.is_some() || opts.ingress_path_prefix.is_some()).then(
+            || IngressRoute {
+                host: opts.ingress_host.clone().filter(|h| !h.is_empty()),
+                path_prefix: opts.ingress_path_prefix.clone().filter(|p| !p.is_empty()),
+            },
+        ),
+        ingress_limits: None,
     };
 
     apply_service_configuration_patch(&opts.service, admin_client, modify_request).await
@@ -103,6 +142,11 @@ pub(super) async fn apply_service_configuration_patch(
         && modify_request.inactivity_timeout.is_none()
         && modify_request.journal_retention.is_none()
         && modify_request.abort_timeout.is_none()
+        && modify_request.max_journal_length.is_none()
+        && modify_request.state_ttl.is_none()
+        && modify_request.completion_retention.is_none()
+        && modify_request.ingress_route.is_none()
+        && modify_request.ingress_limits.is_none()
     {
         c_println!("No changes requested");
         return Ok(());
@@ -140,13 +184,53 @@ pub(super) async fn apply_service_configuration_patch(
     if let Some(abort_timeout) = &modify_request.abort_timeout {
         table.add_kv_row("Abort timeout:", abort_timeout.friendly().to_days_span());
     }
+    if let Some(max_journal_length) = &modify_request.max_journal_length {
+        table.add_kv_row("Max journal length:", max_journal_length);
+    }
+    if let Some(state_ttl) = &modify_request.state_ttl {
+        table.add_kv_row("State ttl:", state_ttl.friendly().to_days_span());
+    }
+    if let Some(completion_retention) = &modify_request.completion_retention {
+        table.add_kv_row(
+            "Completion retention:",
+            completion_retention.friendly().to_days_span(),
+        );
+    }
+    if let Some(ingress_route) = &modify_request.ingress_route {
+        if ingress_route.is_empty() {
+            table.add_kv_row("Ingress route:", "<UNSET>");
+        } else {
+            table.add_kv_row(
+                "Ingress route host:",
+                ingress_route.host.as_deref().unwrap_or("<ANY>"),
+            );
+            table.add_kv_row(
+                "Ingress route path prefix:",
+                ingress_route.path_prefix.as_deref().unwrap_or("<NONE>"),
+            );
+        }
+    }
+    if let Some(ingress_limits) = &modify_request.ingress_limits {
+        if ingress_limits.is_empty() {
+            table.add_kv_row("Ingress limits:", "<UNSET>");
+        } else {
+            table.add_kv_row(
+                "Ingress limits:",
+                super::view::format_ingress_limit(&ingress_limits.service),
+            );
+            for (handler_name, limit) in &ingress_limits.handlers {
+                table.add_kv_row(
+                    &format!("Ingress limits of {handler_name}:"),
+                    super::view::format_ingress_limit(limit),
+                );
+            }
+        }
+    }
     c_println!("{table}");
     confirm_or_exit("Are you sure you want to apply these changes?")?;
 
     let _ = admin_client
         .patch_service(service_name, modify_request)
-        .await?
-        .into_body()
         .await?;
 
     Ok(())
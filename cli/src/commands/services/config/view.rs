@@ -18,6 +18,7 @@ use restate_cli_util::ui::console::StyledTable;
 use restate_cli_util::{c_println, c_tip};
 use restate_time_util::DurationExt;
 use restate_types::invocation::ServiceType;
+use restate_types::schema::service::IngressLimit;
 
 use crate::cli_env::CliEnv;
 use crate::clients::{AdminClient, AdminClientInterface};
@@ -70,6 +71,31 @@ pub(super) const ENABLE_LAZY_STATE: &str = indoc! {
     "If true, lazy state will be enabled for all invocations to this service.
     This is relevant only for Workflows and Virtual Objects."
 };
+pub(super) const MAX_JOURNAL_LENGTH: &str = indoc! {
+    "Maximum number of journal entries of an invocation of this service.
+    Invocations exceeding it fail with a terminal error.
+
+    This overrides the default max journal length set in invocation options."
+};
+pub(super) const STATE_TTL: &str = indoc! {
+    "Time to live of the state entries set by the invocations of this service,
+    after which they're not returned anymore and eventually dropped.
+    Entries set before changing it keep their expiration."
+};
+pub(super) const COMPLETION_RETENTION: &str = indoc! {
+    "Retention of the completed invocations of this service, including the ones without idempotency key.
+    Idempotent invocations and workflows are retained for at least the idempotency and workflow retention."
+};
+pub(super) const INGRESS_ROUTE: &str = indoc! {
+    "Custom route exposing this service through the ingress, matching the request host
+    and/or a path prefix replacing `/<SERVICE_NAME>`.
+    E.g. routing the host `payments.example.com` exposes the handler `charge` at `https://payments.example.com/charge`."
+};
+pub(super) const INGRESS_LIMITS: &str = indoc! {
+    "Rate limits and concurrency caps of the ingress requests to this service, and to its single handlers.
+    The rate is limited with a token bucket refilled at requests_per_second, holding up to burst requests.
+    Requests exceeding the limits are rejected with 429 Too Many Requests."
+};
 pub(super) const RETRY_POLICY: &str = indoc! {
     "Retry policy to use for transient errors. The next retry interval is calculated as
     initial_interval * (exponentiation_factor ^ attempt), capped at max_interval.
@@ -91,7 +117,7 @@ pub async fn run_view(State(env): State<CliEnv>, opts: &View) -> Result<()> {
 
 async fn view(env: &CliEnv, opts: &View) -> Result<()> {
     let client = AdminClient::new(env).await?;
-    let service = client.get_service(&opts.service).await?.into_body().await?;
+    let service = client.get_service(&opts.service).await?;
 
     let mut table = Table::new_styled();
     table.add_kv_row("Name:", &service.name);
@@ -158,6 +184,76 @@ async fn view(env: &CliEnv, opts: &View) -> Result<()> {
     c_tip!("{}", ENABLE_LAZY_STATE);
     c_println!();
 
+    let mut table = Table::new_styled();
+    table.add_kv_row(
+        "Max journal length:",
+        service
+            .max_journal_length
+            .map(|l| l.to_string())
+            .unwrap_or_else(|| "<UNSET>".to_string()),
+    );
+    c_println!("{table}");
+    c_tip!("{}", MAX_JOURNAL_LENGTH);
+    c_println!();
+
+    let mut table = Table::new_styled();
+    table.add_kv_row(
+        "State ttl:",
+        service
+            .state_ttl
+            .map(|d| d.friendly().to_string())
+            .unwrap_or_else(|| "<UNSET>".to_string()),
+    );
+    c_println!("{table}");
+    c_tip!("{}", STATE_TTL);
+    c_println!();
+
+    let mut table = Table::new_styled();
+    table.add_kv_row(
+        "Completion retention:",
+        service
+            .completion_retention
+            .map(|d| d.friendly().to_string())
+            .unwrap_or_else(|| "<UNSET>".to_string()),
+    );
+    c_println!("{table}");
+    c_tip!("{}", COMPLETION_RETENTION);
+    c_println!();
+
+    let mut table = Table::new_styled();
+    table.add_kv_row(
+        "Ingress route host:",
+        service
+            .ingress_route
+            .as_ref()
+            .and_then(|r| r.host.clone())
+            .unwrap_or_else(|| "<UNSET>".to_string()),
+    );
+    table.add_kv_row(
+        "Ingress route path prefix:",
+        service
+            .ingress_route
+            .as_ref()
+            .and_then(|r| r.path_prefix.clone())
+            .unwrap_or_else(|| "<UNSET>".to_string()),
+    );
+    c_println!("{table}");
+    c_tip!("{}", INGRESS_ROUTE);
+    c_println!();
+
+    let mut table = Table::new_styled();
+    let ingress_limits = service.ingress_limits.clone().unwrap_or_default();
+    table.add_kv_row(
+        "Ingress limits:",
+        format_ingress_limit(&ingress_limits.service),
+    );
+    for (handler_name, limit) in &ingress_limits.handlers {
+        table.add_kv_row(&format!("  {handler_name}:"), format_ingress_limit(limit));
+    }
+    c_println!("{table}");
+    c_tip!("{}", INGRESS_LIMITS);
+    c_println!();
+
     let mut table = Table::new_styled();
     table.add_row(vec!["Retry Policy:".bold()]);
     table.add_kv_row(
@@ -282,3 +378,20 @@ fn is_retry_policy_empty(
         && retry_policy.max_interval.is_none()
         && retry_policy.on_max_attempts.is_none()
 }
+
+pub(super) fn format_ingress_limit(limit: &IngressLimit) -> String {
+    if limit.is_empty() {
+        return "<UNSET>".to_string();
+    }
+    let mut parts = vec![];
+    if let Some(requests_per_second) = limit.requests_per_second {
+        parts.push(format!("{requests_per_second} req/s"));
+    }
+    if let Some(burst) = limit.effective_burst() {
+        parts.push(format!("burst {burst}"));
+    }
+    if let Some(max_concurrency) = limit.max_concurrency {
+        parts.push(format!("max {max_concurrency} in flight"));
+    }
+    parts.join(", ")
+}
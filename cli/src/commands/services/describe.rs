@@ -43,7 +43,7 @@ pub async fn run_describe(State(env): State<CliEnv>, opts: &Describe) -> Result<
 
 async fn describe(env: &CliEnv, opts: &Describe) -> Result<()> {
     let client = AdminClient::new(env).await?;
-    let service = client.get_service(&opts.name).await?.into_body().await?;
+    let service = client.get_service(&opts.name).await?;
 
     let mut table = Table::new_styled();
     table.add_kv_row("Name:", &service.name);
@@ -52,11 +52,7 @@ async fn describe(env: &CliEnv, opts: &Describe) -> Result<()> {
     table.add_kv_row("Public:", service.public);
     table.add_kv_row("Deployment ID:", service.deployment_id);
 
-    let deployment = client
-        .get_deployment(&service.deployment_id.to_string())
-        .await?
-        .into_body()
-        .await?;
+    let deployment = client.get_deployment(&service.deployment_id).await?;
     let (_, deployment, _) = Deployment::from_detailed_deployment_response(deployment);
     add_deployment_to_kv_table(&deployment, &mut table);
 
@@ -83,8 +79,6 @@ async fn describe(env: &CliEnv, opts: &Describe) -> Result<()> {
     let mut other_deployments: Vec<_> = client
         .get_deployments()
         .await?
-        .into_body()
-        .await?
         .deployments
         .into_iter()
         .filter_map(|e| {
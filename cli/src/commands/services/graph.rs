@@ -0,0 +1,212 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use cling::prelude::*;
+
+use restate_cli_util::ui::console::Styled;
+use restate_cli_util::ui::stylesheet::Style;
+use restate_cli_util::{c_println, c_warn};
+
+use crate::cli_env::CliEnv;
+use crate::clients::DataFusionHttpClient;
+use crate::clients::datafusion_helpers::{ServiceCall, get_service_calls};
+
+#[derive(Run, Parser, Collect, Clone)]
+#[cling(run = "run_graph")]
+pub struct Graph {
+    /// Show only the calls from or to this service
+    service: Option<String>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value_t = GraphFormat::Ascii)]
+    format: GraphFormat,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum GraphFormat {
+    /// Tree of the services and the services they call
+    #[default]
+    Ascii,
+    /// Graphviz DOT graph
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+pub async fn run_graph(State(env): State<CliEnv>, opts: &Graph) -> Result<()> {
+    let client = crate::clients::AdminClient::new(&env).await?;
+    let sql_client = DataFusionHttpClient::from(client);
+    let calls = get_service_calls(&sql_client, opts.service.as_deref()).await?;
+
+    if calls.is_empty() {
+        c_warn!(
+            "No calls between services were found. Only the journals of the invocations currently \
+            stored are analyzed."
+        );
+        return Ok(());
+    }
+
+    match opts.format {
+        GraphFormat::Ascii => render_ascii(&calls),
+        GraphFormat::Dot => c_println!("{}", render_dot(&calls)),
+        GraphFormat::Mermaid => c_println!("{}", render_mermaid(&calls)),
+    }
+    Ok(())
+}
+
+fn edge_label(call: &ServiceCall) -> String {
+    let calls = if call.calls == 1 {
+        "1 call".to_owned()
+    } else {
+        format!("{} calls", call.calls)
+    };
+    if call.failed_calls == 0 {
+        return calls;
+    }
+    // The error rate is relative to the calls whose outcome is known
+    let error_rate = call.failed_calls as f64 * 100.0 / call.completed_calls.max(1) as f64;
+    format!("{calls}, {error_rate:.0}% failed")
+}
+
+fn render_ascii(calls: &[ServiceCall]) {
+    let mut by_caller: BTreeMap<&str, Vec<&ServiceCall>> = BTreeMap::new();
+    for call in calls {
+        by_caller
+            .entry(call.caller_service_name.as_str())
+            .or_default()
+            .push(call);
+    }
+
+    for (caller, callees) in by_caller {
+        c_println!("{}", Styled(Style::Info, caller));
+        let width = callees
+            .iter()
+            .map(|call| call.callee_service_name.len())
+            .max()
+            .unwrap_or_default();
+        for (i, call) in callees.iter().enumerate() {
+            let branch = if i + 1 == callees.len() {
+                "└─▶"
+            } else {
+                "├─▶"
+            };
+            let label = edge_label(call);
+            let label = if call.failed_calls > 0 {
+                Styled(Style::Danger, label)
+            } else {
+                Styled(Style::Normal, label)
+            };
+            c_println!("{branch} {:width$}  {label}", call.callee_service_name);
+        }
+        c_println!();
+    }
+}
+
+fn render_dot(calls: &[ServiceCall]) -> String {
+    let mut out = String::from("digraph services {\n    rankdir=LR;\n");
+    for call in calls {
+        out.push_str(&format!(
+            "    {:?} -> {:?} [label={:?}{}];\n",
+            call.caller_service_name,
+            call.callee_service_name,
+            edge_label(call),
+            if call.failed_calls > 0 {
+                ", color=red"
+            } else {
+                ""
+            }
+        ));
+    }
+    out.push('}');
+    out
+}
+
+fn render_mermaid(calls: &[ServiceCall]) -> String {
+    // Service names can contain characters which are not valid in mermaid ids
+    let mut ids = BTreeMap::new();
+    for call in calls {
+        for service in [&call.caller_service_name, &call.callee_service_name] {
+            let next_id = ids.len();
+            ids.entry(service.as_str()).or_insert(next_id);
+        }
+    }
+
+    let mut out = String::from("flowchart LR\n");
+    for (service, id) in &ids {
+        out.push_str(&format!(
+            "    s{id}[\"{}\"]\n",
+            service.replace('"', "#quot;")
+        ));
+    }
+    for call in calls {
+        out.push_str(&format!(
+            "    s{} -->|\"{}\"| s{}\n",
+            ids[call.caller_service_name.as_str()],
+            edge_label(call),
+            ids[call.callee_service_name.as_str()],
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(caller: &str, callee: &str, calls: i64, failed_calls: i64) -> ServiceCall {
+        ServiceCall {
+            caller_service_name: caller.to_owned(),
+            callee_service_name: callee.to_owned(),
+            calls,
+            completed_calls: calls,
+            failed_calls,
+        }
+    }
+
+    #[test]
+    fn dot_graph() {
+        let calls = [
+            call("Checkout", "Cart", 4, 1),
+            call("Checkout", "Email", 1, 0),
+        ];
+
+        assert_eq!(
+            render_dot(&calls),
+            "digraph services {
+    rankdir=LR;
+    \"Checkout\" -> \"Cart\" [label=\"4 calls, 25% failed\", color=red];
+    \"Checkout\" -> \"Email\" [label=\"1 call\"];
+}"
+        );
+    }
+
+    #[test]
+    fn mermaid_graph() {
+        let calls = [
+            call("Checkout", "Cart", 4, 1),
+            call("Cart", "Inventory", 2, 0),
+        ];
+
+        assert_eq!(
+            render_mermaid(&calls),
+            "flowchart LR
+    s1[\"Cart\"]
+    s0[\"Checkout\"]
+    s2[\"Inventory\"]
+    s0 -->|\"4 calls, 25% failed\"| s1
+    s1 -->|\"2 calls\"| s2
+"
+        );
+    }
+}
@@ -48,7 +48,7 @@ pub async fn run_list(State(env): State<CliEnv>, opts: &List) -> Result<()> {
 
 async fn list(env: &CliEnv, list_opts: &List) -> Result<()> {
     let client = crate::clients::AdminClient::new(env).await?;
-    let defs = client.get_services().await?.into_body().await?;
+    let defs = client.get_services().await?;
 
     if defs.services.is_empty() {
         c_error!(
@@ -57,7 +57,7 @@ async fn list(env: &CliEnv, list_opts: &List) -> Result<()> {
         return Ok(());
     }
 
-    let deployments = client.get_deployments().await?.into_body().await?;
+    let deployments = client.get_deployments().await?;
 
     let mut deployment_cache: HashMap<DeploymentId, DeploymentResponse> = HashMap::new();
 
@@ -10,6 +10,7 @@
 
 mod config;
 mod describe;
+mod graph;
 mod list;
 mod status;
 
@@ -24,6 +25,9 @@ pub enum Services {
     Describe(describe::Describe),
     /// Prints activity information about a given service (and method)
     Status(status::Status),
+    /// Prints the graph of the calls between services, found in the journals of the stored
+    /// invocations
+    Graph(graph::Graph),
     /// Configure a service
     #[clap(name = "config", alias = "conf")]
     #[clap(subcommand)]
@@ -29,12 +29,7 @@ pub async fn run_aggregated_status(
     progress.enable_steady_tick(std::time::Duration::from_millis(120));
 
     progress.set_message("Fetching services status");
-    let services = metas_client
-        .get_services()
-        .await?
-        .into_body()
-        .await?
-        .services;
+    let services = metas_client.get_services().await?.services;
     if services.is_empty() {
         progress.finish_and_clear();
         c_error!(
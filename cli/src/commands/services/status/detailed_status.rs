@@ -33,11 +33,7 @@ pub async fn run_detailed_status(
     progress.enable_steady_tick(std::time::Duration::from_millis(120));
 
     progress.set_message("Fetching service status");
-    let service = metas_client
-        .get_service(service_name)
-        .await?
-        .into_body()
-        .await?;
+    let service = metas_client.get_service(service_name).await?;
 
     let is_stateful = service.ty.has_state();
 
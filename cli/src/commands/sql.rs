@@ -12,7 +12,9 @@ use std::io;
 use std::time::Instant;
 
 use anyhow::Result;
+use arrow::datatypes::Schema;
 use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
 use arrow::util::display::ArrayFormatter;
 use arrow::util::display::FormatOptions;
 use cling::prelude::*;
@@ -55,32 +57,49 @@ async fn run_query(env: &CliEnv, sql_opts: &Sql) -> Result<()> {
     let start_time = Instant::now();
     let resp = client.run_arrow_query(sql_opts.query.clone()).await?;
 
+    let row_count = print_batches(&resp.schema, resp.batches, sql_opts.json, sql_opts.jsonl)?;
+
+    c_eprintln!(
+        "{} rows. Query took {:?}",
+        row_count,
+        Styled(Style::Notice, start_time.elapsed())
+    );
+    Ok(())
+}
+
+/// Prints the query results, either as a table or as json. Returns the number of printed rows.
+pub(crate) fn print_batches(
+    schema: &Schema,
+    batches: Vec<RecordBatch>,
+    json: bool,
+    jsonl: bool,
+) -> Result<usize> {
     let mut table = Table::new_styled();
     // add headers.
     let mut headers = vec![];
-    for col in resp.schema.fields() {
+    for col in schema.fields() {
         headers.push(col.name().clone().to_uppercase());
     }
     table.set_styled_header(headers);
 
     let mut row_count: usize = 0;
-    if sql_opts.json {
+    if json {
         let mut writer = arrow::json::ArrayWriter::new(io::stdout());
-        for batch in resp.batches {
+        for batch in batches {
             row_count += batch.num_rows();
             writer.write_batches(&[&batch])?;
         }
         writer.finish()?;
-    } else if sql_opts.jsonl {
+    } else if jsonl {
         let mut writer = arrow::json::LineDelimitedWriter::new(io::stdout());
-        for batch in resp.batches {
+        for batch in batches {
             row_count += batch.num_rows();
             writer.write_batches(&[&batch])?;
         }
         writer.finish()?;
     } else {
         let format_options = FormatOptions::default().with_display_error(true);
-        for batch in resp.batches {
+        for batch in batches {
             let formatters = batch
                 .columns()
                 .iter()
@@ -95,22 +114,14 @@ async fn run_query(env: &CliEnv, sql_opts: &Sql) -> Result<()> {
                 table.add_row(cells);
             }
         }
+        row_count = table.row_count();
 
         // Only print if there are actual results.
-        if table.row_count() > 0 {
+        if row_count > 0 {
             c_println!("{}", table);
             c_println!();
         }
     }
 
-    c_eprintln!(
-        "{} rows. Query took {:?}",
-        if sql_opts.json || sql_opts.jsonl {
-            row_count
-        } else {
-            table.row_count()
-        },
-        Styled(Style::Notice, start_time.elapsed())
-    );
-    Ok(())
+    Ok(row_count)
 }
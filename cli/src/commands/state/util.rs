@@ -50,7 +50,7 @@ pub(crate) async fn get_current_state(
     // 0. require that this is a keyed service
     //
     let client = AdminClient::new(env).await?;
-    match client.get_service(service).await?.into_body().await {
+    match client.get_service(service).await {
         Ok(service_meta) => match service_meta.ty {
             ServiceType::VirtualObject | ServiceType::Workflow => {}
             ServiceType::Service => bail!("Only virtual objects and workflows support state"),
@@ -100,7 +100,7 @@ pub(crate) async fn update_state(
     };
 
     let client = AdminClient::new(env).await?;
-    let _ = client.patch_state(service, req).await?.success_or_error()?;
+    client.patch_state(service, req).await?;
 
     Ok(())
 }
@@ -20,7 +20,7 @@ use restate_types::art::render_restate_logo;
 
 use crate::build_info;
 use crate::cli_env::{CliEnv, EnvironmentType};
-use crate::clients::AdminClientInterface;
+use crate::clients::{AdminClientInterface, MetasClientError};
 use crate::clients::{MAX_ADMIN_API_VERSION, MIN_ADMIN_API_VERSION};
 
 #[derive(Run, Parser, Clone)]
@@ -201,7 +201,7 @@ pub async fn run(State(env): State<CliEnv>) {
     // Get admin client, don't fail completely if we can't get one!
     match crate::clients::AdminClient::new(&env).await {
         Ok(client) => match client.health().await {
-            Ok(envelope) if envelope.status_code().is_success() => {
+            Ok(()) => {
                 c_success!("Admin Service '{}' is healthy!", client.base_url);
                 if let Some(advertised_ingress_address) = client.advertised_ingress_address {
                     let mut table = Table::new();
@@ -213,13 +213,14 @@ pub async fn run(State(env): State<CliEnv>) {
                     c_println!("{}", table);
                 }
             }
-            Ok(envelope) => {
+            Err(MetasClientError::Api(err)) => {
                 c_error!("Admin Service '{}' is unhealthy:", client.base_url);
-                let url = envelope.url().clone();
-                let status_code = envelope.status_code();
-                let body = envelope.into_text().await;
-                c_eprintln!("   >> [{}] from '{}'", status_code.to_string(), url);
-                c_eprintln!("   >> {}", body.unwrap_or_default());
+                c_eprintln!(
+                    "   >> [{}] from '{}'",
+                    err.http_status_code.to_string(),
+                    err.url
+                );
+                c_eprintln!("   >> {}", err.body.message);
             }
             Err(e) => {
                 c_error!("Admin Service '{}' is unhealthy:", client.base_url);
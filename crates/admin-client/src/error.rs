@@ -0,0 +1,102 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use http::{Method, StatusCode};
+use serde::Deserialize;
+use url::Url;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    // Error is boxed because ApiError can get quite large if the message body is large.
+    #[error(transparent)]
+    Api(#[from] Box<ApiError>),
+    #[error("(Protocol error) {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error(transparent)]
+    Network(#[from] reqwest::Error),
+    #[error("invalid admin url: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+}
+
+impl Error {
+    /// Returns the http status code returned by the admin API, if any.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self {
+            Error::Api(api_error) => Some(api_error.http_status_code),
+            Error::Network(err) => err.status(),
+            Error::Serialization(_) | Error::InvalidUrl(_) => None,
+        }
+    }
+
+    /// Returns true if a request with the given method can be retried, that is the admin API
+    /// was not reachable or temporarily unavailable.
+    ///
+    /// Timeouts and gateway errors leave unknown whether the admin API processed the request,
+    /// hence they're retried only for idempotent methods.
+    pub fn is_retryable(&self, method: &Method) -> bool {
+        let is_idempotent = matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE
+        );
+        match self {
+            Error::Network(err) => err.is_connect() || (err.is_timeout() && is_idempotent),
+            Error::Api(api_error) => match api_error.http_status_code {
+                StatusCode::SERVICE_UNAVAILABLE => true,
+                StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => is_idempotent,
+                _ => false,
+            },
+            Error::Serialization(_) | Error::InvalidUrl(_) => false,
+        }
+    }
+}
+
+/// Error body returned by the admin API.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiErrorBody {
+    pub restate_code: Option<String>,
+    pub message: String,
+}
+
+impl From<String> for ApiErrorBody {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            restate_code: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiErrorBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = self.restate_code.as_deref().unwrap_or("<UNKNOWN>");
+        write!(f, "{code} {}", self.message)
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{body}\n  -> Http status code {http_status_code} at '{url}'")]
+pub struct ApiError {
+    pub http_status_code: StatusCode,
+    pub url: Url,
+    pub body: ApiErrorBody,
+}
+
+impl ApiError {
+    /// Decodes the error returned by the admin API. Bodies which are not json encoded
+    /// are used as error message.
+    pub fn from_body(http_status_code: StatusCode, url: Url, body: String) -> Self {
+        let body = serde_json::from_str(&body).unwrap_or_else(|_| ApiErrorBody::from(body));
+        Self {
+            http_status_code,
+            url,
+            body,
+        }
+    }
+}
@@ -0,0 +1,484 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Typed client for the Restate admin REST API.
+//!
+//! Requests and responses are modeled with the types of `restate-admin-rest-model`.
+//!
+//! The typed methods are used by the CLI, by the dev mode auto-registration of `restate-lite` and
+//! by the benchmarks. [`AdminClient::request`] builds authenticated requests for the endpoints
+//! without a typed method, such as the SQL query endpoint.
+
+mod error;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use http::StatusCode;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tracing::debug;
+use url::Url;
+
+use restate_admin_rest_model::deployments::{
+    DetailedDeploymentResponse, ListDeploymentsResponse, RegisterDeploymentRequest,
+    RegisterDeploymentResponse,
+};
+use restate_admin_rest_model::invocations::RestartAsNewInvocationResponse;
+use restate_admin_rest_model::services::{
+    ListServicesResponse, ModifyServiceRequest, ModifyServiceStateRequest,
+};
+use restate_admin_rest_model::version::{AdminApiVersion, VersionInformation};
+use restate_types::identifiers::DeploymentId;
+use restate_types::retries::RetryPolicy;
+use restate_types::schema::service::ServiceMetadata;
+
+pub use error::{ApiError, ApiErrorBody, Error};
+
+/// Client for the admin REST API.
+///
+/// The client is cheap to clone, as the underlying connection pool is shared.
+#[derive(Clone)]
+pub struct AdminClient {
+    inner: reqwest::Client,
+    base_url: Url,
+    bearer_token: Option<String>,
+    request_timeout: Option<Duration>,
+    admin_api_version: AdminApiVersion,
+    retry_policy: RetryPolicy,
+}
+
+impl AdminClient {
+    pub fn builder(base_url: Url) -> AdminClientBuilder {
+        AdminClientBuilder::new(base_url)
+    }
+
+    /// Creates a client for the admin API listening on the given unix domain socket.
+    pub fn from_uds(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let client = reqwest::Client::builder()
+            .unix_socket(path.into())
+            .build()?;
+        AdminClientBuilder::new("http://localhost/".parse()?)
+            .client(client)
+            .build()
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    pub fn admin_api_version(&self) -> AdminApiVersion {
+        self.admin_api_version
+    }
+
+    /// Use the given admin API version to build the request paths.
+    pub fn with_admin_api_version(mut self, admin_api_version: AdminApiVersion) -> Self {
+        self.admin_api_version = admin_api_version;
+        self
+    }
+
+    /// Builds the url of the given path, prefixed with the admin API version in use.
+    pub fn versioned_url(&self, path: impl IntoIterator<Item = impl AsRef<str>>) -> Url {
+        let mut url = self.base_url.clone();
+
+        {
+            let mut segments = url.path_segments_mut().expect("Bad url!");
+            segments.pop_if_empty();
+
+            match self.admin_api_version {
+                AdminApiVersion::Unknown => segments.extend(path),
+                // v1 clusters didn't support versioned urls
+                AdminApiVersion::V1 => segments.extend(path),
+                AdminApiVersion::V2 => segments.push("v2").extend(path),
+                AdminApiVersion::V3 => segments.push("v3").extend(path),
+            };
+        }
+
+        url
+    }
+
+    /// Prepare a request builder for the given method and url, with authentication and timeout.
+    pub fn request(&self, method: reqwest::Method, url: Url) -> reqwest::RequestBuilder {
+        let mut request_builder = self.inner.request(method, url);
+
+        if let Some(request_timeout) = self.request_timeout {
+            request_builder = request_builder.timeout(request_timeout);
+        }
+
+        match self.bearer_token.as_deref() {
+            Some(token) => request_builder.bearer_auth(token),
+            None => request_builder,
+        }
+    }
+
+    /// Check if the admin service is healthy by invoking /health
+    pub async fn health(&self) -> Result<(), Error> {
+        self.send_no_content(
+            reqwest::Method::GET,
+            self.versioned_url(["health"]),
+            None::<()>,
+        )
+        .await
+    }
+
+    pub async fn version(&self) -> Result<VersionInformation, Error> {
+        self.send(
+            reqwest::Method::GET,
+            self.versioned_url(["version"]),
+            None::<()>,
+        )
+        .await
+    }
+
+    pub async fn list_services(&self) -> Result<ListServicesResponse, Error> {
+        self.send(
+            reqwest::Method::GET,
+            self.versioned_url(["services"]),
+            None::<()>,
+        )
+        .await
+    }
+
+    pub async fn get_service(&self, name: &str) -> Result<ServiceMetadata, Error> {
+        self.send(
+            reqwest::Method::GET,
+            self.versioned_url(["services", name]),
+            None::<()>,
+        )
+        .await
+    }
+
+    pub async fn modify_service(
+        &self,
+        name: &str,
+        request: ModifyServiceRequest,
+    ) -> Result<ServiceMetadata, Error> {
+        self.send(
+            reqwest::Method::PATCH,
+            self.versioned_url(["services", name]),
+            Some(request),
+        )
+        .await
+    }
+
+    pub async fn modify_service_state(
+        &self,
+        name: &str,
+        request: ModifyServiceStateRequest,
+    ) -> Result<(), Error> {
+        self.send_no_content(
+            reqwest::Method::POST,
+            self.versioned_url(["services", name, "state"]),
+            Some(request),
+        )
+        .await
+    }
+
+    pub async fn list_deployments(&self) -> Result<ListDeploymentsResponse, Error> {
+        self.send(
+            reqwest::Method::GET,
+            self.versioned_url(["deployments"]),
+            None::<()>,
+        )
+        .await
+    }
+
+    pub async fn get_deployment(
+        &self,
+        id: &DeploymentId,
+    ) -> Result<DetailedDeploymentResponse, Error> {
+        let id = id.to_string();
+        self.send(
+            reqwest::Method::GET,
+            self.versioned_url(["deployments", id.as_str()]),
+            None::<()>,
+        )
+        .await
+    }
+
+    pub async fn register_deployment(
+        &self,
+        request: RegisterDeploymentRequest,
+    ) -> Result<RegisterDeploymentResponse, Error> {
+        self.send(
+            reqwest::Method::POST,
+            self.versioned_url(["deployments"]),
+            Some(request),
+        )
+        .await
+    }
+
+    /// Same as [`Self::register_deployment`], also returning the status code of the response.
+    ///
+    /// Admin API V3 replies with `200 OK` rather than `201 Created` when the deployment already
+    /// exists, which tells apart a dry run of a new deployment from the one of a known deployment.
+    pub async fn register_deployment_with_status(
+        &self,
+        request: RegisterDeploymentRequest,
+    ) -> Result<(StatusCode, RegisterDeploymentResponse), Error> {
+        let response = self
+            .send_with_retry(
+                reqwest::Method::POST,
+                self.versioned_url(["deployments"]),
+                Some(request),
+            )
+            .await?;
+        let status_code = response.status();
+        let body = response.text().await?;
+        Ok((status_code, serde_json::from_str(&body)?))
+    }
+
+    pub async fn remove_deployment(&self, id: &DeploymentId, force: bool) -> Result<(), Error> {
+        let id = id.to_string();
+        let mut url = self.versioned_url(["deployments", id.as_str()]);
+        url.set_query(Some(&format!("force={force}")));
+
+        self.send_no_content(reqwest::Method::DELETE, url, None::<()>)
+            .await
+    }
+
+    pub async fn cancel_invocation(&self, id: &str) -> Result<(), Error> {
+        self.patch_invocation(id, "cancel").await
+    }
+
+    pub async fn kill_invocation(&self, id: &str) -> Result<(), Error> {
+        self.patch_invocation(id, "kill").await
+    }
+
+    pub async fn purge_invocation(&self, id: &str) -> Result<(), Error> {
+        self.patch_invocation(id, "purge").await
+    }
+
+    pub async fn pause_invocation(&self, id: &str) -> Result<(), Error> {
+        self.patch_invocation(id, "pause").await
+    }
+
+    pub async fn resume_invocation(&self, id: &str) -> Result<(), Error> {
+        self.patch_invocation(id, "resume").await
+    }
+
+    pub async fn restart_as_new_invocation(
+        &self,
+        id: &str,
+    ) -> Result<RestartAsNewInvocationResponse, Error> {
+        self.send(
+            reqwest::Method::PATCH,
+            self.versioned_url(["invocations", id, "restart-as-new"]),
+            None::<()>,
+        )
+        .await
+    }
+
+    async fn patch_invocation(&self, id: &str, operation: &str) -> Result<(), Error> {
+        self.send_no_content(
+            reqwest::Method::PATCH,
+            self.versioned_url(["invocations", id, operation]),
+            None::<()>,
+        )
+        .await
+    }
+
+    /// Sends the request and decodes the json response body.
+    async fn send<T, B>(
+        &self,
+        method: reqwest::Method,
+        url: Url,
+        body: Option<B>,
+    ) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+        B: Serialize,
+    {
+        let response = self.send_with_retry(method, url, body).await?;
+        let body = response.text().await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Sends the request, ignoring the response body.
+    async fn send_no_content<B>(
+        &self,
+        method: reqwest::Method,
+        url: Url,
+        body: Option<B>,
+    ) -> Result<(), Error>
+    where
+        B: Serialize,
+    {
+        self.send_with_retry(method, url, body).await?;
+        Ok(())
+    }
+
+    async fn send_with_retry<B>(
+        &self,
+        method: reqwest::Method,
+        url: Url,
+        body: Option<B>,
+    ) -> Result<reqwest::Response, Error>
+    where
+        B: Serialize,
+    {
+        self.retry_policy
+            .clone()
+            .retry_if(
+                || async {
+                    debug!("Sending request {} ({})", method, url);
+                    let mut request = self.request(method.clone(), url.clone());
+                    if let Some(body) = &body {
+                        request = request.json(body);
+                    }
+
+                    let response = request.send().await?;
+                    debug!("Response from {} ({})", url, response.status());
+                    check_status(response).await
+                },
+                |err| err.is_retryable(&method),
+            )
+            .await
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let http_status_code = response.status();
+    if http_status_code.is_success() {
+        return Ok(response);
+    }
+
+    let url = response.url().clone();
+    let body = response.text().await?;
+    Err(Error::Api(Box::new(ApiError::from_body(
+        http_status_code,
+        url,
+        body,
+    ))))
+}
+
+/// Builder for the [`AdminClient`].
+pub struct AdminClientBuilder {
+    base_url: Url,
+    client: Option<reqwest::Client>,
+    bearer_token: Option<String>,
+    request_timeout: Option<Duration>,
+    admin_api_version: AdminApiVersion,
+    retry_policy: RetryPolicy,
+}
+
+impl AdminClientBuilder {
+    fn new(base_url: Url) -> Self {
+        Self {
+            base_url,
+            client: None,
+            bearer_token: None,
+            request_timeout: None,
+            admin_api_version: AdminApiVersion::Unknown,
+            retry_policy: RetryPolicy::None,
+        }
+    }
+
+    /// Use the given http client to send the requests, e.g. to configure TLS or the user agent.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Token sent as bearer authentication with every request.
+    pub fn bearer_token(mut self, bearer_token: impl Into<String>) -> Self {
+        self.bearer_token = Some(bearer_token.into());
+        self
+    }
+
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = Some(request_timeout);
+        self
+    }
+
+    pub fn admin_api_version(mut self, admin_api_version: AdminApiVersion) -> Self {
+        self.admin_api_version = admin_api_version;
+        self
+    }
+
+    /// Retry policy applied when the admin API is unreachable or temporarily unavailable.
+    /// Defaults to [`RetryPolicy::None`].
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn build(self) -> Result<AdminClient, Error> {
+        let inner = match self.client {
+            Some(client) => client,
+            None => reqwest::Client::builder().build()?,
+        };
+
+        Ok(AdminClient {
+            inner,
+            base_url: self.base_url,
+            bearer_token: self.bearer_token,
+            request_timeout: self.request_timeout,
+            admin_api_version: self.admin_api_version,
+            retry_policy: self.retry_policy,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use googletest::prelude::*;
+
+    fn client(base_url: &str, admin_api_version: AdminApiVersion) -> AdminClient {
+        AdminClient::builder(base_url.parse().unwrap())
+            .admin_api_version(admin_api_version)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn versioned_url() {
+        assert_that!(
+            client("http://localhost:9070", AdminApiVersion::Unknown)
+                .versioned_url(["deployments"])
+                .as_str(),
+            eq("http://localhost:9070/deployments")
+        );
+        assert_that!(
+            client("http://localhost:9070/", AdminApiVersion::V3)
+                .versioned_url(["services", "Greeter", "state"])
+                .as_str(),
+            eq("http://localhost:9070/v3/services/Greeter/state")
+        );
+        assert_that!(
+            client("https://example.com/admin/", AdminApiVersion::V2)
+                .versioned_url(["invocations", "inv_1", "cancel"])
+                .as_str(),
+            eq("https://example.com/admin/v2/invocations/inv_1/cancel")
+        );
+    }
+
+    #[test]
+    fn api_error_body() {
+        let url: Url = "http://localhost:9070/deployments".parse().unwrap();
+
+        let err = ApiError::from_body(
+            StatusCode::BAD_REQUEST,
+            url.clone(),
+            r#"{"message": "bad uri", "restate_code": "META0003"}"#.to_owned(),
+        );
+        assert_that!(err.body.message, eq("bad uri"));
+        assert_that!(err.body.restate_code, some(eq("META0003")));
+
+        let err = ApiError::from_body(StatusCode::BAD_GATEWAY, url, "upstream down".to_owned());
+        assert_that!(err.body.message, eq("upstream down"));
+        assert_that!(err.body.restate_code, none());
+        let err = Error::Api(Box::new(err));
+        assert!(err.is_retryable(&reqwest::Method::GET));
+        assert!(!err.is_retryable(&reqwest::Method::POST));
+    }
+}
@@ -66,7 +66,8 @@ pub enum RegisterDeploymentRequest {
         /// # Breaking
         ///
         /// If `true`, it allows registering new service revisions with
-        /// schemas incompatible with previous service revisions, such as changing service type, removing a handler, etc.
+        /// schemas incompatible with previous service revisions, such as changing service type, removing a handler,
+        /// changing a handler type or its input/output content-type, etc.
         ///
         /// See the [versioning documentation](https://docs.restate.dev/operate/versioning) for more information.
         #[serde(default = "restate_serde_util::default::bool::<false>")]
@@ -77,7 +78,8 @@ pub enum RegisterDeploymentRequest {
         /// If `true`, it overrides, if existing, any deployment using the same `uri`.
         /// Beware that this can lead inflight invocations to an unrecoverable error state.
         ///
-        /// When set to `true`, it implies `breaking = true`.
+        /// Changes to the existing service revisions are still checked for compatibility, unless `breaking = true`.
+        /// With admin API versions 1 and 2 this implies `breaking = true`.
         ///
         /// See the [versioning documentation](https://docs.restate.dev/operate/versioning) for more information.
         #[cfg_attr(
@@ -126,7 +128,8 @@ pub enum RegisterDeploymentRequest {
         /// # Breaking
         ///
         /// If `true`, it allows registering new service revisions with
-        /// schemas incompatible with previous service revisions, such as changing service type, removing a handler, etc.
+        /// schemas incompatible with previous service revisions, such as changing service type, removing a handler,
+        /// changing a handler type or its input/output content-type, etc.
         ///
         /// See the [versioning documentation](https://docs.restate.dev/operate/versioning) for more information.
         #[serde(default = "restate_serde_util::default::bool::<false>")]
@@ -137,7 +140,8 @@ pub enum RegisterDeploymentRequest {
         /// If `true`, it overrides, if existing, any deployment using the same `uri`.
         /// Beware that this can lead inflight invocations to an unrecoverable error state.
         ///
-        /// This implies `breaking = true`.
+        /// Changes to the existing service revisions are still checked for compatibility, unless `breaking = true`.
+        /// With admin API versions 1 and 2 this implies `breaking = true`.
         ///
         /// See the [versioning documentation](https://docs.restate.dev/operate/versioning) for more information.
         #[cfg_attr(
@@ -156,6 +160,21 @@ pub enum RegisterDeploymentRequest {
     },
 }
 
+impl RegisterDeploymentRequest {
+    /// Request to register the http deployment at the given `uri`, using the default options.
+    pub fn http(uri: Uri) -> Self {
+        RegisterDeploymentRequest::Http {
+            uri,
+            additional_headers: None,
+            metadata: HashMap::new(),
+            use_http_11: false,
+            breaking: false,
+            force: None,
+            dry_run: false,
+        }
+    }
+}
+
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ServiceNameRevPair {
@@ -252,6 +271,18 @@ pub enum DeploymentResponse {
         #[cfg_attr(feature = "schema", schemars(with = "String"))]
         created_at: humantime::Timestamp,
 
+        /// # Drain deadline
+        ///
+        /// Set while the deployment is draining: new invocations are not routed to it anymore,
+        /// and it is deleted once its in-flight invocations completed, if they do before this deadline.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_with::As::<Option<serde_with::DisplayFromStr>>"
+        )]
+        #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+        drain_deadline: Option<humantime::Timestamp>,
+
         /// # Minimum Service Protocol version
         ///
         /// During registration, the SDKs declare a range from minimum (included) to maximum (included) Service Protocol supported version.
@@ -324,6 +355,18 @@ pub enum DeploymentResponse {
         #[cfg_attr(feature = "schema", schemars(with = "String"))]
         created_at: humantime::Timestamp,
 
+        /// # Drain deadline
+        ///
+        /// Set while the deployment is draining: new invocations are not routed to it anymore,
+        /// and it is deleted once its in-flight invocations completed, if they do before this deadline.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_with::As::<Option<serde_with::DisplayFromStr>>"
+        )]
+        #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+        drain_deadline: Option<humantime::Timestamp>,
+
         /// # Minimum Service Protocol version
         ///
         /// During registration, the SDKs declare a range from minimum (included) to maximum (included) Service Protocol supported version.
@@ -412,6 +455,18 @@ pub enum DetailedDeploymentResponse {
         #[cfg_attr(feature = "schema", schemars(with = "String"))]
         created_at: humantime::Timestamp,
 
+        /// # Drain deadline
+        ///
+        /// Set while the deployment is draining: new invocations are not routed to it anymore,
+        /// and it is deleted once its in-flight invocations completed, if they do before this deadline.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_with::As::<Option<serde_with::DisplayFromStr>>"
+        )]
+        #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+        drain_deadline: Option<humantime::Timestamp>,
+
         /// # Minimum Service Protocol version
         ///
         /// During registration, the SDKs declare a range from minimum (included) to maximum (included) Service Protocol supported version.
@@ -483,6 +538,18 @@ pub enum DetailedDeploymentResponse {
         #[cfg_attr(feature = "schema", schemars(with = "String"))]
         created_at: humantime::Timestamp,
 
+        /// # Drain deadline
+        ///
+        /// Set while the deployment is draining: new invocations are not routed to it anymore,
+        /// and it is deleted once its in-flight invocations completed, if they do before this deadline.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_with::As::<Option<serde_with::DisplayFromStr>>"
+        )]
+        #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+        drain_deadline: Option<humantime::Timestamp>,
+
         /// # Minimum Service Protocol version
         ///
         /// During registration, the SDKs declare a range from minimum (included) to maximum (included) Service Protocol supported version.
@@ -613,3 +680,44 @@ pub enum UpdateDeploymentRequest {
         dry_run: bool,
     },
 }
+
+/// # Rollout status
+///
+/// Progress of the rollout of a deployment. New invocations of its services are always sent to
+/// the latest deployment, while the invocations already pinned to the deployments registered
+/// before it for the same services need to drain.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RolloutStatusResponse {
+    pub id: DeploymentId,
+    pub status: RolloutStatus,
+
+    /// # Draining deployments
+    ///
+    /// Deployments registered before this one for the same services, which still have in-flight
+    /// invocations pinned to them.
+    pub draining_deployments: Vec<DrainingDeployment>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutStatus {
+    /// There are still in-flight invocations pinned to the previous deployments.
+    Draining,
+    /// All the invocations pinned to the previous deployments completed.
+    Drained,
+    /// There are still in-flight invocations pinned to the previous deployments, but the drain
+    /// timeout since the registration of the deployment passed.
+    DeadlineExceeded,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DrainingDeployment {
+    pub id: DeploymentId,
+    /// # Pinned invocations
+    ///
+    /// Number of in-flight invocations pinned to this deployment.
+    pub pinned_invocations: u64,
+}
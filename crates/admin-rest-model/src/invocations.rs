@@ -8,7 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use restate_types::identifiers::InvocationId;
+use restate_types::identifiers::{DeploymentId, InvocationId};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,3 +17,117 @@ pub struct RestartAsNewInvocationResponse {
     /// The invocation id of the new invocation.
     pub new_invocation_id: InvocationId,
 }
+
+/// Status of an invocation that is not completed yet.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::EnumString,
+    strum::IntoStaticStr,
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum InFlightInvocationStatus {
+    /// Enqueued in the inbox of a virtual object, waiting for the lock.
+    Pending,
+    /// Scheduled to start at a later point in time.
+    Scheduled,
+    /// Ready to be run by the invoker.
+    Ready,
+    /// Currently running on a deployment.
+    Running,
+    /// Waiting to be retried after a failed attempt.
+    BackingOff,
+    /// Suspended, waiting for a completion.
+    Suspended,
+    /// Paused after exhausting its retries.
+    Paused,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ListInvocationsResponse {
+    pub invocations: Vec<InvocationResponse>,
+
+    /// # Next cursor
+    ///
+    /// Cursor to pass to fetch the next page of invocations. Absent on the last page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InvocationResponse {
+    /// # ID
+    ///
+    /// Invocation identifier.
+    pub id: InvocationId,
+
+    /// # Target
+    ///
+    /// Invocation target, e.g. `Greeter/greet` or `Greeter/Francesco/greet`.
+    pub target: String,
+
+    /// # Status
+    pub status: InFlightInvocationStatus,
+
+    /// # Created at
+    ///
+    /// When the invocation was created.
+    #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub created_at: humantime::Timestamp,
+
+    /// # Deployment ID
+    ///
+    /// The deployment the invocation is pinned to, or the deployment of its last attempt if
+    /// not pinned yet. Absent if the invocation was never attempted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment_id: Option<DeploymentId>,
+
+    /// # Retry count
+    ///
+    /// Number of attempts made by the current partition leader to run the invocation. Not a
+    /// global counter across suspensions and leadership changes.
+    pub retry_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CompleteJournalEntryRequest {
+    /// # Result
+    ///
+    /// Either `{"value": <json>}` to complete the entry successfully, or
+    /// `{"failure": {"code": 500, "message": "..."}}` to fail it.
+    #[serde(flatten)]
+    pub result: JournalEntryCompletionResult,
+
+    /// # Confirmation token
+    ///
+    /// Token returned by a previous request without it. The completion is only applied when
+    /// the token matches the current state of the entry and the requested result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_token: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEntryCompletionResult {
+    /// Complete the entry with the JSON encoded value.
+    Value(serde_json::Value),
+    /// Complete the entry with a failure.
+    Failure {
+        /// Error code, 500 by default.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<u16>,
+        message: String,
+    },
+}
@@ -11,6 +11,7 @@
 pub mod deployments;
 pub mod handlers;
 pub mod invocations;
+pub mod schema_changes;
 pub mod services;
 pub mod subscriptions;
 pub mod version;
@@ -0,0 +1,57 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use serde::{Deserialize, Serialize};
+
+use restate_types::identifiers::{DeploymentId, ServiceRevision};
+
+/// Event of the schema changes stream, sent every time deployments or services are registered,
+/// updated or removed.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaChangeEvent {
+    /// # Schema version
+    ///
+    /// Version of the schema after the changes were applied.
+    pub version: u32,
+
+    /// # Changes
+    ///
+    /// Summary of the changes compared to the previous event.
+    pub changes: Vec<SchemaChange>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SchemaChange {
+    DeploymentRegistered {
+        id: DeploymentId,
+    },
+    /// The address, the headers or the services of the deployment changed.
+    DeploymentUpdated {
+        id: DeploymentId,
+    },
+    DeploymentRemoved {
+        id: DeploymentId,
+    },
+    ServiceRegistered {
+        name: String,
+        revision: ServiceRevision,
+    },
+    /// A new revision of the service was registered, or its configuration was modified.
+    ServiceUpdated {
+        name: String,
+        revision: ServiceRevision,
+    },
+    ServiceRemoved {
+        name: String,
+    },
+}
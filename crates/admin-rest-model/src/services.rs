@@ -9,13 +9,15 @@
 // by the Apache License, Version 2.0.
 
 use std::collections::HashMap;
+use std::num::NonZeroU32;
 use std::time::Duration;
 
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 
 use restate_time_util::FriendlyDuration;
-use restate_types::schema::service::ServiceMetadata;
+use restate_types::identifiers::InvocationId;
+use restate_types::schema::service::{IngressLimits, IngressRoute, ServiceMetadata};
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Debug, Serialize, Deserialize)]
@@ -95,6 +97,56 @@ pub struct ModifyServiceRequest {
     #[serde(default, with = "serde_with::As::<Option<FriendlyDuration>>")]
     #[cfg_attr(feature = "schema", schemars(with = "Option<String>" /* TODO(slinkydeveloper) https://github.com/restatedev/restate/issues/3766 */))]
     pub abort_timeout: Option<Duration>,
+
+    /// # Max journal length
+    ///
+    /// Maximum number of journal entries of an invocation of this service. Invocations exceeding
+    /// it fail with a terminal error.
+    ///
+    /// This overrides the default max journal length set in invocation options.
+    #[serde(default)]
+    pub max_journal_length: Option<NonZeroU32>,
+
+    /// # State ttl
+    ///
+    /// Time to live of the state entries set by the invocations of this service, after which
+    /// they're not returned anymore and eventually dropped. Entries set before changing it keep
+    /// their expiration.
+    ///
+    /// Can be configured using the [`jiff::fmt::friendly`](https://docs.rs/jiff/latest/jiff/fmt/friendly/index.html) format or ISO8601, for example `5 hours`.
+    #[serde(default, with = "serde_with::As::<Option<FriendlyDuration>>")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>" /* TODO(slinkydeveloper) https://github.com/restatedev/restate/issues/3766 */))]
+    pub state_ttl: Option<Duration>,
+
+    /// # Completion retention
+    ///
+    /// Modify the retention of the completed invocations of this service, including the ones without
+    /// idempotency key. Idempotent invocations and workflows are retained for at least the
+    /// `idempotency_retention` and `workflow_completion_retention` respectively.
+    ///
+    /// Can be configured using the [`jiff::fmt::friendly`](https://docs.rs/jiff/latest/jiff/fmt/friendly/index.html) format or ISO8601, for example `5 hours`.
+    #[serde(default, with = "serde_with::As::<Option<FriendlyDuration>>")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>" /* TODO(slinkydeveloper) https://github.com/restatedev/restate/issues/3766 */))]
+    pub completion_retention: Option<Duration>,
+
+    /// # Ingress route
+    ///
+    /// Custom route exposing this service through the ingress, e.g. routing the host
+    /// `payments.example.com` or the path prefix `/payments` to this service. Requests matching
+    /// the route address the handlers without the `/<SERVICE_NAME>` path segment.
+    ///
+    /// This replaces the current route of the service. An empty route removes it.
+    #[serde(default)]
+    pub ingress_route: Option<IngressRoute>,
+
+    /// # Ingress limits
+    ///
+    /// Rate limits and concurrency caps of the ingress requests to this service, and to its
+    /// single handlers. Requests exceeding them are rejected with `429 Too Many Requests`.
+    ///
+    /// This replaces the current limits of the service. Empty limits remove them.
+    #[serde(default)]
+    pub ingress_limits: Option<IngressLimits>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -116,3 +168,63 @@ pub struct ModifyServiceStateRequest {
     /// The new state to replace the previous state with
     pub new_state: HashMap<String, Bytes>,
 }
+
+/// # Virtual object lock
+///
+/// Lock of a virtual object or workflow key, telling which invocation holds it and how many
+/// are waiting for it.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VirtualObjectLockResponse {
+    /// # Locked by
+    ///
+    /// The invocation holding the lock. Unset if the key is not locked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_by: Option<LockHolder>,
+
+    /// # Queue length
+    ///
+    /// Number of invocations and state mutations waiting for the lock to be released.
+    pub queue_length: u64,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub invocation_id: InvocationId,
+
+    /// # Handler
+    ///
+    /// The handler of the invocation holding the lock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handler: Option<String>,
+
+    /// # Status
+    ///
+    /// Status of the invocation holding the lock, as reported by `sys_invocation`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    /// # Locked since
+    ///
+    /// Since when the invocation holds the lock.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_with::As::<Option<serde_with::DisplayFromStr>>"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub locked_since: Option<humantime::Timestamp>,
+
+    /// # Retry count
+    ///
+    /// Number of attempts of the invocation holding the lock, if it is being executed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_count: Option<u64>,
+
+    /// # Last failure
+    ///
+    /// Error of the most recent failed attempt of the invocation holding the lock, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failure: Option<String>,
+}
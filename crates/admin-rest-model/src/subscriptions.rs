@@ -26,6 +26,7 @@ pub struct CreateSubscriptionRequest {
     /// Source uri. Accepted forms:
     ///
     /// * `kafka://<cluster_name>/<topic_name>`, e.g. `kafka://my-cluster/my-topic`
+    /// * `sqs://<connection_name>/<queue_name>`, e.g. `sqs://my-aws/my-queue`
     #[serde_as(as = "serde_with::DisplayFromStr")]
     #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub source: Uri,
@@ -40,6 +41,10 @@ pub struct CreateSubscriptionRequest {
     /// # Options
     ///
     /// Additional options to apply to the subscription.
+    ///
+    /// For SQS sources, the supported options are `key.attribute` (name of the message attribute
+    /// containing the key, required for virtual object and workflow sinks), `wait.time.seconds`,
+    /// `max.number.of.messages` and `visibility.timeout.seconds`.
     pub options: Option<HashMap<String, String>>,
 }
 
@@ -73,6 +73,35 @@ pub struct VersionInformation {
     pub ingress_endpoint: Option<AdvertisedAddress<HttpIngressPort>>,
 }
 
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionCompatibilityInformation {
+    /// # Server version
+    ///
+    /// Version of the server
+    pub version: String,
+    /// # Min forward compatible version
+    ///
+    /// Minimum Restate version able to read the data written by this server
+    pub min_forward_compatible_version: String,
+    /// # Min backward compatible version
+    ///
+    /// Minimum Restate version whose data this server can read
+    pub min_backward_compatible_version: String,
+    /// # Target version
+    ///
+    /// The version this server is going to be replaced with, as provided in the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_version: Option<String>,
+    /// # Compatible
+    ///
+    /// True if the target version can read the data written by this server,
+    /// hence it's safe to replace this server with the target version.
+    /// Present only if a target version was provided in the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compatible: Option<bool>,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::version::AdminApiVersion;
@@ -0,0 +1,58 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! API to inject faults in the node, for resilience testing. See [`restate_types::chaos`].
+
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use http::StatusCode;
+use tracing::warn;
+
+use restate_types::chaos::{self, Faults, InvalidPercentageError};
+
+pub fn router() -> Router {
+    Router::new().route(
+        "/chaos/faults",
+        get(get_faults).put(put_faults).delete(delete_faults),
+    )
+}
+
+/// Gets the faults currently injected in the node
+async fn get_faults() -> Json<Faults> {
+    Json(chaos::current_faults())
+}
+
+/// Replaces the faults injected in the node
+async fn put_faults(Json(faults): Json<Faults>) -> Result<Json<Faults>, ChaosApiError> {
+    chaos::set_faults(faults)?;
+    let faults = chaos::current_faults();
+    warn!("Injecting faults: {:?}", faults);
+    Ok(Json(faults))
+}
+
+/// Removes all the faults injected in the node
+async fn delete_faults() -> StatusCode {
+    chaos::clear_faults();
+    warn!("Removed all the injected faults");
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ChaosApiError {
+    #[error(transparent)]
+    InvalidPercentage(#[from] InvalidPercentageError),
+}
+
+impl IntoResponse for ChaosApiError {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
@@ -0,0 +1,286 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! API to compare the effective configuration of the nodes of the cluster:
+//!
+//! * `GET /cluster/configuration-diff` collects the configuration of every node and returns the
+//!   options whose values differ between them
+//!
+//! Options which are expected to differ, like the node name or the addresses, are not compared.
+//! Mismatches of the options which must be equal on all the nodes, like the number of partitions
+//! or the journal retention, are flagged as dangerous. Secrets are redacted by the nodes, hence
+//! they're never reported as different.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::future;
+use http::StatusCode;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::debug;
+
+use restate_core::Metadata;
+use restate_core::network::net_util::create_tonic_channel;
+use restate_core::protobuf::node_ctl_svc::new_node_ctl_client;
+use restate_types::PlainNodeId;
+use restate_types::config::Configuration;
+
+const GET_CONFIGURATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options which must have the same value on all the nodes, by path prefix.
+const DANGEROUS_OPTIONS: &[&str] = &[
+    "cluster-name",
+    "default-num-partitions",
+    "default-replication",
+    "default-journal-retention",
+    "max-journal-retention",
+    "default-max-journal-length",
+    "worker.state-size-quota",
+    "worker.payload-encryption",
+    "worker.completion-kafka-sink",
+    "worker.timers-max-batch-size",
+    "bifrost.default-provider",
+];
+
+/// Options which are specific to each node, by any segment of their path.
+const NODE_LOCAL_OPTIONS: &[&str] = &[
+    "node-name",
+    "force-node-id",
+    "location",
+    "base-dir",
+    "roles",
+    "bind-address",
+    "advertised-address",
+    "advertised-admin-endpoint",
+    "advertised-ingress-endpoint",
+];
+
+pub fn router() -> Router {
+    Router::new().route("/cluster/configuration-diff", get(get_configuration_diff))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ConfigurationDiff {
+    /// Nodes whose configuration was compared
+    nodes: Vec<PlainNodeId>,
+    /// Nodes whose configuration couldn't be retrieved
+    unreachable_nodes: Vec<UnreachableNode>,
+    /// Options with different values, the dangerous ones first
+    differences: Vec<OptionDifference>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct UnreachableNode {
+    node_id: PlainNodeId,
+    error: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct OptionDifference {
+    /// Path of the option, with the segments separated by dots
+    path: String,
+    dangerous: bool,
+    /// Value by node, null if the option is unset
+    values: BTreeMap<PlainNodeId, Value>,
+}
+
+/// Gets the differences between the effective configuration of the nodes
+async fn get_configuration_diff() -> Response {
+    let Some(nodes_configuration) =
+        Metadata::with_current(|m| m.my_node_id_opt().is_some().then(|| m.nodes_config_ref()))
+    else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The cluster does not seem to be provisioned yet. Try again later.",
+        )
+            .into_response();
+    };
+
+    let networking = Configuration::pinned().networking.clone();
+    let responses = future::join_all(nodes_configuration.iter().map(|(node_id, node_config)| {
+        let mut client = new_node_ctl_client(create_tonic_channel(
+            node_config.address.clone(),
+            &networking,
+        ));
+        async move {
+            let result =
+                match tokio::time::timeout(GET_CONFIGURATION_TIMEOUT, client.get_configuration(()))
+                    .await
+                {
+                    Ok(Ok(response)) => {
+                        serde_json::from_str::<Value>(&response.into_inner().configuration)
+                            .map_err(|err| format!("cannot decode the configuration: {err}"))
+                    }
+                    Ok(Err(status)) => Err(status.message().to_owned()),
+                    Err(_) => Err("timed out".to_owned()),
+                };
+            (node_id, result)
+        }
+    }))
+    .await;
+
+    let mut configurations = Vec::with_capacity(responses.len());
+    let mut unreachable_nodes = Vec::new();
+    for (node_id, result) in responses {
+        match result {
+            Ok(configuration) => configurations.push((node_id, configuration)),
+            Err(error) => {
+                debug!("Failed retrieving the configuration of '{node_id}': {error}");
+                unreachable_nodes.push(UnreachableNode { node_id, error });
+            }
+        }
+    }
+
+    Json(ConfigurationDiff {
+        nodes: configurations.iter().map(|(node_id, _)| *node_id).collect(),
+        unreachable_nodes,
+        differences: diff_configurations(&configurations),
+    })
+    .into_response()
+}
+
+fn diff_configurations(configurations: &[(PlainNodeId, Value)]) -> Vec<OptionDifference> {
+    let flattened: Vec<_> = configurations
+        .iter()
+        .map(|(node_id, configuration)| {
+            let mut options = BTreeMap::new();
+            flatten_options(String::new(), configuration, &mut options);
+            (*node_id, options)
+        })
+        .collect();
+
+    let paths: BTreeSet<_> = flattened
+        .iter()
+        .flat_map(|(_, options)| options.keys())
+        .collect();
+
+    let mut differences: Vec<_> = paths
+        .into_iter()
+        .filter(|path| !is_node_local(path))
+        .filter_map(|path| {
+            let values: BTreeMap<_, _> = flattened
+                .iter()
+                .map(|(node_id, options)| {
+                    (*node_id, options.get(path).cloned().unwrap_or(Value::Null))
+                })
+                .collect();
+            let mut distinct_values = values.values();
+            let first = distinct_values.next()?;
+            distinct_values
+                .any(|value| value != first)
+                .then(|| OptionDifference {
+                    path: path.clone(),
+                    dangerous: is_dangerous(path),
+                    values,
+                })
+        })
+        .collect();
+
+    differences.sort_by(|a, b| b.dangerous.cmp(&a.dangerous).then(a.path.cmp(&b.path)));
+    differences
+}
+
+/// Flattens the nested objects, the arrays are compared as a whole.
+fn flatten_options(prefix: String, value: &Value, options: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_options(path, value, options);
+            }
+        }
+        value => {
+            options.insert(prefix, value.clone());
+        }
+    }
+}
+
+fn is_dangerous(path: &str) -> bool {
+    DANGEROUS_OPTIONS.iter().any(|option| {
+        path.strip_prefix(option)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+fn is_node_local(path: &str) -> bool {
+    path.split('.')
+        .any(|segment| NODE_LOCAL_OPTIONS.contains(&segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use googletest::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_flags_dangerous_mismatches_first() {
+        let differences = diff_configurations(&[
+            (
+                PlainNodeId::new(1),
+                json!({
+                    "node-name": "n1",
+                    "default-num-partitions": 24,
+                    "worker": {"internal-queue-length": 1000},
+                    "admin": {"bind-address": "0.0.0.0:9070"}
+                }),
+            ),
+            (
+                PlainNodeId::new(2),
+                json!({
+                    "node-name": "n2",
+                    "default-num-partitions": 12,
+                    "worker": {"internal-queue-length": 2000, "state-size-quota": "1 MiB"},
+                    "admin": {"bind-address": "0.0.0.0:19070"}
+                }),
+            ),
+        ]);
+
+        assert_that!(
+            differences
+                .iter()
+                .map(|difference| (difference.path.as_str(), difference.dangerous))
+                .collect::<Vec<_>>(),
+            eq(vec![
+                ("default-num-partitions", true),
+                ("worker.state-size-quota", true),
+                ("worker.internal-queue-length", false),
+            ])
+        );
+        assert_that!(
+            differences[1].values.get(&PlainNodeId::new(1)),
+            some(eq(&Value::Null))
+        );
+    }
+
+    #[test]
+    fn no_differences_between_equal_configurations() {
+        let configuration = json!({"worker": {"internal-queue-length": 1000}});
+        assert_that!(
+            diff_configurations(&[
+                (PlainNodeId::new(1), configuration.clone()),
+                (PlainNodeId::new(2), configuration),
+            ]),
+            empty()
+        );
+    }
+}
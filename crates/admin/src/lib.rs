@@ -8,19 +8,26 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+#[cfg(feature = "chaos")]
+mod chaos_api;
 pub mod cluster_controller;
+mod config_diff_api;
 mod error;
 #[cfg(feature = "metadata-api")]
 mod metadata_api;
 mod metric_definitions;
+mod outbox_consistency_api;
+mod parked_commands_api;
 mod query_utils;
 mod rest_api;
 pub mod schema_registry_integration;
 pub mod service;
+mod snapshots_api;
 mod state;
 mod storage_accounting;
 #[cfg(feature = "storage-query")]
 mod storage_query;
+mod timers_api;
 #[cfg(feature = "serve-web-ui")]
 mod web_ui;
 
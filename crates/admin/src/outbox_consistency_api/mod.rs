@@ -0,0 +1,75 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! API to inspect the outbox consistency checks of the partition leaders running on this node,
+//! see `worker.outbox-consistency-check-interval`:
+//!
+//! * `GET /partitions/outbox-consistency` lists the last report of every partition leader
+//! * `GET /partitions/{partition_id}/outbox-consistency` returns the last report of the partition
+
+use axum::extract::Path;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use http::StatusCode;
+use serde::Serialize;
+
+use restate_types::identifiers::PartitionId;
+use restate_types::partitions::outbox_consistency::{self, OutboxConsistencyReport};
+
+pub fn router() -> Router {
+    Router::new()
+        .route(
+            "/partitions/outbox-consistency",
+            get(get_outbox_consistency_reports),
+        )
+        .route(
+            "/partitions/{partition_id}/outbox-consistency",
+            get(get_outbox_consistency_report),
+        )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PartitionOutboxConsistency {
+    partition_id: PartitionId,
+    #[serde(flatten)]
+    report: OutboxConsistencyReport,
+}
+
+/// Lists the last outbox consistency reports, ordered by partition id
+async fn get_outbox_consistency_reports() -> Json<Vec<PartitionOutboxConsistency>> {
+    Json(
+        outbox_consistency::all()
+            .into_iter()
+            .map(|(partition_id, report)| PartitionOutboxConsistency {
+                partition_id,
+                report,
+            })
+            .collect(),
+    )
+}
+
+/// Gets the last outbox consistency report of the given partition
+async fn get_outbox_consistency_report(Path(partition_id): Path<u16>) -> Response {
+    let partition_id = PartitionId::from(partition_id);
+    match outbox_consistency::report(partition_id) {
+        Some(report) => Json(PartitionOutboxConsistency {
+            partition_id,
+            report,
+        })
+        .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            format!("Partition {partition_id} was not checked by this node yet, is its leader running here?"),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! API to inspect and retry the commands parked by the partition processors running on this node,
+//! see `worker.poison-command-max-attempts`:
+//!
+//! * `GET /partitions/{partition_id}/parked-commands` lists the parked commands of the partition
+//! * `POST /partitions/{partition_id}/parked-commands/{lsn}/retry` proposes to apply the parked
+//!   command again. It's served only by the node running the leader of the partition.
+
+use axum::extract::{Path, Query};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use http::StatusCode;
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+use restate_types::identifiers::PartitionId;
+use restate_types::logs::Lsn;
+use restate_types::partitions::parked_commands::{self, ParkedCommandInfo, ParkedCommandsRequest};
+
+const DEFAULT_PARKED_COMMANDS_LIMIT: usize = 100;
+
+pub fn router() -> Router {
+    Router::new()
+        .route(
+            "/partitions/{partition_id}/parked-commands",
+            get(get_parked_commands),
+        )
+        .route(
+            "/partitions/{partition_id}/parked-commands/{lsn}/retry",
+            post(retry_parked_command),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct ParkedCommandsParams {
+    /// Maximum number of parked commands to return, 100 by default
+    limit: Option<usize>,
+}
+
+fn not_running_here(partition_id: PartitionId) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        format!("Partition {partition_id} is not running on this node"),
+    )
+        .into_response()
+}
+
+/// Lists the parked commands of the given partition, ordered by lsn
+async fn get_parked_commands(
+    Path(partition_id): Path<u16>,
+    Query(params): Query<ParkedCommandsParams>,
+) -> Response {
+    let partition_id = PartitionId::from(partition_id);
+    let Some(partition_processor) = parked_commands::partition_processor(partition_id) else {
+        return not_running_here(partition_id);
+    };
+
+    let (tx, rx) = oneshot::channel();
+    let request = ParkedCommandsRequest::List {
+        limit: params.limit.unwrap_or(DEFAULT_PARKED_COMMANDS_LIMIT),
+        reply: tx,
+    };
+    if partition_processor.send(request).await.is_err() {
+        return not_running_here(partition_id);
+    }
+    match rx.await {
+        Ok(Ok(parked_commands)) => Json::<Vec<ParkedCommandInfo>>(parked_commands).into_response(),
+        Ok(Err(err)) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+        Err(_) => not_running_here(partition_id),
+    }
+}
+
+/// Proposes to apply the parked command of the given partition again
+async fn retry_parked_command(Path((partition_id, lsn)): Path<(u16, u64)>) -> Response {
+    let partition_id = PartitionId::from(partition_id);
+    let lsn = Lsn::from(lsn);
+    let Some(partition_processor) = parked_commands::partition_processor(partition_id) else {
+        return not_running_here(partition_id);
+    };
+
+    let (tx, rx) = oneshot::channel();
+    let request = ParkedCommandsRequest::Retry { lsn, reply: tx };
+    if partition_processor.send(request).await.is_err() {
+        return not_running_here(partition_id);
+    }
+    match rx.await {
+        Ok(Ok(true)) => StatusCode::ACCEPTED.into_response(),
+        Ok(Ok(false)) => (
+            StatusCode::NOT_FOUND,
+            format!("Partition {partition_id} has no parked command at lsn {lsn}"),
+        )
+            .into_response(),
+        Ok(Err(err)) => (StatusCode::CONFLICT, err).into_response(),
+        Err(_) => not_running_here(partition_id),
+    }
+}
@@ -10,27 +10,41 @@
 
 use super::error::*;
 use crate::state::AdminServiceState;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use axum::extract::{Path, Query, State};
 use axum::http::{StatusCode, header};
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
+use datafusion::arrow::array::RecordBatch;
+use datafusion::common::cast::as_int64_array;
+use datafusion::error::DataFusionError;
+use futures::TryStreamExt;
 use http::{Method, Uri};
 use okapi_operation::*;
 use restate_admin_rest_model::deployments::*;
 use restate_admin_rest_model::version::AdminApiVersion;
+use restate_core::{TaskCenter, TaskKind};
 use restate_errors::warn_it;
+use restate_storage_query_datafusion::context::QueryContext;
+use restate_time_util::FriendlyDuration;
 use restate_types::deployment::{HttpDeploymentAddress, LambdaDeploymentAddress};
 use restate_types::identifiers::{DeploymentId, InvalidLambdaARN, ServiceRevision};
 use restate_types::schema;
 use restate_types::schema::deployment::{Deployment, DeploymentType};
 use restate_types::schema::registry::{
     AddDeploymentResult, AllowBreakingChanges, ApplyMode, DiscoveryClient, MetadataService,
-    Overwrite, TelemetryClient,
+    Overwrite, SchemaRegistry, TelemetryClient,
 };
 use restate_types::schema::service::ServiceMetadata;
+use restate_types::time::MillisSinceEpoch;
 use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+/// Interval at which a draining deployment is checked for in-flight invocations.
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Time to wait for the in-flight invocations of a draining deployment, unless requested otherwise.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(60 * 60);
 
 /// Create deployment and return discovered services.
 #[openapi(
@@ -83,15 +97,21 @@ where
             ..
         } => (*force, *breaking, *dry_run),
     };
-    let (allow_breaking, overwrite) =
-        // Force defaults to true only in admin api version 1 or 2
-        if force.unwrap_or(version == AdminApiVersion::V1 || version == AdminApiVersion::V2) {
-            (AllowBreakingChanges::Yes, Overwrite::Yes)
-        } else if breaking {
-            (AllowBreakingChanges::Yes, Overwrite::No)
-        } else {
-            (AllowBreakingChanges::No, Overwrite::No)
-        };
+    let legacy_admin_api = version == AdminApiVersion::V1 || version == AdminApiVersion::V2;
+    // Force defaults to true only in admin api version 1 or 2
+    let overwrite = if force.unwrap_or(legacy_admin_api) {
+        Overwrite::Yes
+    } else {
+        Overwrite::No
+    };
+    // In admin api version 1 or 2 force implies breaking, as there is no way to set breaking.
+    // From version 3, force only overwrites the existing deployment,
+    // and breaking changes must be explicitly allowed with breaking.
+    let allow_breaking = if breaking || (legacy_admin_api && overwrite == Overwrite::Yes) {
+        AllowBreakingChanges::Yes
+    } else {
+        AllowBreakingChanges::No
+    };
     let apply_mode = if dry_run {
         ApplyMode::DryRun
     } else {
@@ -229,12 +249,17 @@ where
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct DeleteDeploymentParams {
     pub force: Option<bool>,
+    #[serde(default, with = "serde_with::As::<Option<FriendlyDuration>>")]
+    #[schemars(with = "Option<String>")]
+    pub drain_timeout: Option<Duration>,
 }
 
 /// Discover endpoint and return discovered endpoints.
 #[openapi(
     summary = "Delete deployment",
-    description = "Delete deployment. Currently it's supported to remove a deployment only using the force flag",
+    description = "Delete deployment. \
+    Unless forced, the deployment is drained first: new invocations are not routed to it anymore, and it is deleted once the in-flight invocations pinned to it completed. \
+    If they don't complete within the drain timeout, the deployment is kept draining and can be deleted using the force flag.",
     operation_id = "delete_deployment",
     tags = "deployment",
     parameters(
@@ -250,6 +275,14 @@ pub struct DeleteDeploymentParams {
             style = "simple",
             allow_empty_value = false,
             schema = "bool",
+        ),
+        query(
+            name = "drain_timeout",
+            description = "Time to wait for the in-flight invocations pinned to the deployment to complete, when not forcing the deletion. Defaults to 1 hour.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "std::string::String",
         )
     ),
     responses(
@@ -261,7 +294,7 @@ pub struct DeleteDeploymentParams {
         ),
         response(
             status = "501",
-            description = "Not implemented. Only using the force flag is supported at the moment.",
+            description = "Not implemented. Draining deployments requires the storage query engine, only using the force flag is supported without it.",
             content = "okapi_operation::Empty",
         ),
         from_type = "MetaApiError",
@@ -270,10 +303,15 @@ pub struct DeleteDeploymentParams {
 pub async fn delete_deployment<Metadata, Discovery, Telemetry, Invocations>(
     State(state): State<AdminServiceState<Metadata, Discovery, Telemetry, Invocations>>,
     Path(deployment_id): Path<DeploymentId>,
-    Query(DeleteDeploymentParams { force }): Query<DeleteDeploymentParams>,
+    Query(DeleteDeploymentParams {
+        force,
+        drain_timeout,
+    }): Query<DeleteDeploymentParams>,
 ) -> Result<StatusCode, MetaApiError>
 where
-    Metadata: MetadataService,
+    Metadata: MetadataService + Send + Sync + 'static,
+    Discovery: Send + Sync + 'static,
+    Telemetry: Send + Sync + 'static,
 {
     if let Some(true) = force {
         state
@@ -283,8 +321,144 @@ where
             .inspect_err(|e| warn_it!(e))?;
         Ok(StatusCode::ACCEPTED)
     } else {
-        Ok(StatusCode::NOT_IMPLEMENTED)
+        let Some(query_context) = state.query_context else {
+            // The in-flight invocations can't be tracked without the query engine
+            return Ok(StatusCode::NOT_IMPLEMENTED);
+        };
+
+        let drain_deadline = MillisSinceEpoch::from(
+            SystemTime::now() + drain_timeout.unwrap_or(DEFAULT_DRAIN_TIMEOUT),
+        );
+        state
+            .schema_registry
+            .drain_deployment(deployment_id, drain_deadline)
+            .await
+            .inspect_err(|e| warn_it!(e))?;
+        TaskCenter::spawn(
+            TaskKind::Disposable,
+            "drain-deployment",
+            delete_when_drained(
+                state.schema_registry,
+                query_context,
+                deployment_id,
+                drain_deadline,
+            ),
+        )?;
+        Ok(StatusCode::ACCEPTED)
+    }
+}
+
+/// Resumes waiting for the deployments which were draining when the admin service was stopped,
+/// as the drain deadline is stored in the schema.
+#[cfg(feature = "storage-query")]
+pub(crate) async fn resume_draining_deployments<Metadata, Discovery, Telemetry>(
+    schema_registry: SchemaRegistry<Metadata, Discovery, Telemetry>,
+    query_context: QueryContext,
+) -> anyhow::Result<()>
+where
+    Metadata: MetadataService + Send + Sync + 'static,
+    Discovery: Send + Sync + 'static,
+    Telemetry: Send + Sync + 'static,
+{
+    // Wait for the schema to be synced, otherwise no draining deployment would be found
+    restate_core::Metadata::current()
+        .wait_for_version(
+            restate_core::MetadataKind::Schema,
+            restate_types::Version::MIN,
+        )
+        .await?;
+
+    for (deployment, _) in schema_registry.list_deployments() {
+        let Some(drain_deadline) = deployment.drain_deadline else {
+            continue;
+        };
+        debug!(
+            restate.deployment.id = %deployment.id,
+            "Resuming draining the deployment"
+        );
+        TaskCenter::spawn(
+            TaskKind::Disposable,
+            "drain-deployment",
+            delete_when_drained(
+                schema_registry.clone(),
+                query_context.clone(),
+                deployment.id,
+                drain_deadline,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Deletes the draining deployment once no in-flight invocations are pinned to it anymore.
+async fn delete_when_drained<Metadata, Discovery, Telemetry>(
+    schema_registry: SchemaRegistry<Metadata, Discovery, Telemetry>,
+    query_context: QueryContext,
+    deployment_id: DeploymentId,
+    drain_deadline: MillisSinceEpoch,
+) -> anyhow::Result<()>
+where
+    Metadata: MetadataService,
+{
+    loop {
+        // The deployment might have been deleted meanwhile, e.g. forcefully or by another admin
+        // node draining it as well, or drained again with a new deadline
+        match schema_registry.get_deployment(deployment_id) {
+            Some((deployment, _)) if deployment.drain_deadline == Some(drain_deadline) => {}
+            _ => return Ok(()),
+        }
+
+        match count_pinned_invocations(&query_context, deployment_id).await {
+            Ok(0) => break,
+            Ok(pinned_invocations) => debug!(
+                restate.deployment.id = %deployment_id,
+                "Waiting for {pinned_invocations} in-flight invocation(s) before deleting the deployment"
+            ),
+            Err(err) => warn!(
+                restate.deployment.id = %deployment_id,
+                "Failed to count the in-flight invocations of the draining deployment: {err}"
+            ),
+        }
+        if MillisSinceEpoch::now() >= drain_deadline {
+            warn!(
+                restate.deployment.id = %deployment_id,
+                "The deployment didn't drain before its drain deadline, keeping it for the in-flight invocations pinned to it. Delete it with force=true to remove it anyway"
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(DRAIN_CHECK_INTERVAL).await;
+    }
+
+    schema_registry
+        .delete_deployment(deployment_id)
+        .await
+        .inspect_err(|e| warn_it!(e))?;
+    info!(
+        restate.deployment.id = %deployment_id,
+        "Deleted the drained deployment"
+    );
+    Ok(())
+}
+
+async fn count_pinned_invocations(
+    query_context: &QueryContext,
+    deployment_id: DeploymentId,
+) -> Result<u64, DataFusionError> {
+    let batches: Vec<RecordBatch> = query_context
+        .execute(&format!(
+            "SELECT COUNT(*) FROM sys_invocation_status \
+            WHERE status != 'completed' AND pinned_deployment_id = '{deployment_id}'"
+        ))
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut pinned_invocations = 0;
+    for batch in batches {
+        let counts = as_int64_array(batch.column(0))?;
+        pinned_invocations += counts.values().iter().sum::<i64>() as u64;
     }
+    Ok(pinned_invocations)
 }
 
 /// Update a deployment
@@ -450,6 +624,7 @@ fn to_deployment_response(
         sdk_version,
         created_at,
         metadata,
+        drain_deadline,
         info,
         ..
     }: Deployment,
@@ -468,6 +643,7 @@ fn to_deployment_response(
             additional_headers: additional_headers.into(),
             metadata,
             created_at: SystemTime::from(created_at).into(),
+            drain_deadline: drain_deadline.map(|deadline| SystemTime::from(deadline).into()),
             min_protocol_version: *supported_protocol_versions.start(),
             max_protocol_version: *supported_protocol_versions.end(),
             sdk_version,
@@ -489,6 +665,7 @@ fn to_deployment_response(
             additional_headers: additional_headers.into(),
             metadata,
             created_at: SystemTime::from(created_at).into(),
+            drain_deadline: drain_deadline.map(|deadline| SystemTime::from(deadline).into()),
             min_protocol_version: *supported_protocol_versions.start(),
             max_protocol_version: *supported_protocol_versions.end(),
             sdk_version,
@@ -510,6 +687,7 @@ fn to_detailed_deployment_response(
         sdk_version,
         created_at,
         metadata,
+        drain_deadline,
         info,
         ..
     }: Deployment,
@@ -528,6 +706,7 @@ fn to_detailed_deployment_response(
             additional_headers: additional_headers.into(),
             metadata,
             created_at: SystemTime::from(created_at).into(),
+            drain_deadline: drain_deadline.map(|deadline| SystemTime::from(deadline).into()),
             min_protocol_version: *supported_protocol_versions.start(),
             max_protocol_version: *supported_protocol_versions.end(),
             sdk_version,
@@ -546,6 +725,7 @@ fn to_detailed_deployment_response(
             additional_headers: additional_headers.into(),
             metadata,
             created_at: SystemTime::from(created_at).into(),
+            drain_deadline: drain_deadline.map(|deadline| SystemTime::from(deadline).into()),
             min_protocol_version: *supported_protocol_versions.start(),
             max_protocol_version: *supported_protocol_versions.end(),
             sdk_version,
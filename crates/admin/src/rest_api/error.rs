@@ -157,6 +157,41 @@ impl_meta_api_error!(InvalidFieldError: BAD_REQUEST);
 pub(crate) struct InvocationNotFoundError(pub(crate) String);
 impl_meta_api_error!(InvocationNotFoundError: NOT_FOUND);
 
+#[derive(Debug, thiserror::Error)]
+#[error("{0} requires the query engine, which is not available on this node.")]
+pub(crate) struct QueryEngineUnavailableError(pub(crate) &'static str);
+impl_meta_api_error!(QueryEngineUnavailableError: SERVICE_UNAVAILABLE "The query engine is not available on this node.");
+
+#[derive(Debug, thiserror::Error)]
+#[error("Error when querying the invocations. Reason: {0}")]
+pub(crate) struct InvocationsQueryError(#[from] pub(crate) datafusion::error::DataFusionError);
+impl_meta_api_error!(InvocationsQueryError: INTERNAL_SERVER_ERROR "Error when querying the invocations.");
+
+#[derive(Debug, thiserror::Error)]
+#[error("The journal entry {1} of the invocation '{0}' does not exist")]
+pub(crate) struct JournalEntryNotFoundError(pub(crate) String, pub(crate) u32);
+impl_meta_api_error!(JournalEntryNotFoundError: NOT_FOUND);
+
+#[derive(Debug, thiserror::Error)]
+#[error("The journal entry cannot be completed: {0}.")]
+pub(crate) struct JournalEntryNotPendingError(pub(crate) String);
+impl_meta_api_error!(JournalEntryNotPendingError: CONFLICT "Only pending journal entries of in-flight invocations can be completed manually.");
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Completing the pending {entry_type} entry must be confirmed, repeat the request with \"confirmation_token\": \"{token}\"."
+)]
+pub(crate) struct ConfirmationRequiredError {
+    pub(crate) entry_type: String,
+    pub(crate) token: String,
+}
+impl_meta_api_error!(ConfirmationRequiredError: PRECONDITION_REQUIRED "The operation must be confirmed by repeating the request with the returned confirmation token.");
+
+#[derive(Debug, thiserror::Error)]
+#[error("Failed sending the command to the cluster.")]
+pub(crate) struct CommandAppendError;
+impl_meta_api_error!(CommandAppendError: SERVICE_UNAVAILABLE);
+
 #[derive(Debug, thiserror::Error)]
 #[error("Error when routing the request internally. Reason: {0}")]
 pub(crate) struct InvocationClientError(
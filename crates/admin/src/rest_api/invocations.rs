@@ -15,8 +15,21 @@ use crate::state::AdminServiceState;
 use axum::Json;
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
+use bytes::Bytes;
+use datafusion::arrow::array::{Array, RecordBatch};
+use datafusion::common::cast::{
+    as_boolean_array, as_large_string_array, as_timestamp_millisecond_array, as_uint32_array,
+    as_uint64_array,
+};
+use datafusion::error::DataFusionError;
+use futures::TryStreamExt;
 use okapi_operation::*;
-use restate_admin_rest_model::invocations::RestartAsNewInvocationResponse;
+use restate_admin_rest_model::invocations::{
+    CompleteJournalEntryRequest, InFlightInvocationStatus, InvocationResponse,
+    JournalEntryCompletionResult, ListInvocationsResponse, RestartAsNewInvocationResponse,
+};
+use restate_storage_query_datafusion::context::QueryContext;
+use restate_types::errors::{InvocationError, InvocationErrorCode, codes};
 use restate_types::identifiers::{
     DeploymentId, InvocationId, PartitionProcessorRpcRequestId, WithPartitionKey,
 };
@@ -24,12 +37,191 @@ use restate_types::invocation::client::{
     self, CancelInvocationResponse, InvocationClient, KillInvocationResponse,
     PauseInvocationResponse, PurgeInvocationResponse, ResumeInvocationResponse,
 };
-use restate_types::invocation::{InvocationTermination, PurgeInvocationRequest, TerminationFlavor};
-use restate_types::journal_v2::EntryIndex;
+use restate_types::invocation::{
+    CompletePendingEntryRequest, InvocationEpoch, InvocationTermination, PurgeInvocationRequest,
+    ResponseResult, TerminationFlavor,
+};
+use restate_types::journal_v2::{CompletionId, EntryIndex};
 use restate_wal_protocol::{Command, Envelope};
 use serde::Deserialize;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::Arc;
-use tracing::warn;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+/// Target of the log lines recording manual interventions on invocations.
+const AUDIT_LOG_TARGET: &str = "restate_admin::audit";
+
+const DEFAULT_LIST_INVOCATIONS_LIMIT: usize = 100;
+const MAX_LIST_INVOCATIONS_LIMIT: usize = 1000;
+
+generate_meta_api_error!(ListInvocationsError: [InvalidFieldError, QueryEngineUnavailableError, InvocationsQueryError]);
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ListInvocationsParams {
+    pub service: Option<String>,
+    pub status: Option<InFlightInvocationStatus>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// List in-flight invocations
+#[openapi(
+    summary = "List invocations",
+    description = "List the invocations that are not completed yet, ordered by invocation id. \
+    Use the returned `next_cursor` as `cursor` to fetch the next page.",
+    operation_id = "list_invocations",
+    tags = "invocation",
+    parameters(
+        query(
+            name = "service",
+            description = "Only list the invocations of this service.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "std::string::String",
+        ),
+        query(
+            name = "status",
+            description = "Only list the invocations with this status.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "InFlightInvocationStatus",
+        ),
+        query(
+            name = "limit",
+            description = "Maximum number of invocations to return, 100 by default and at most 1000.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "usize",
+        ),
+        query(
+            name = "cursor",
+            description = "The `next_cursor` returned by the previous page.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "std::string::String",
+        )
+    )
+)]
+pub async fn list_invocations<Metadata, Discovery, Telemetry, Invocations>(
+    State(state): State<AdminServiceState<Metadata, Discovery, Telemetry, Invocations>>,
+    Query(ListInvocationsParams {
+        service,
+        status,
+        limit,
+        cursor,
+    }): Query<ListInvocationsParams>,
+) -> Result<Json<ListInvocationsResponse>, ListInvocationsError> {
+    let Some(query_context) = state.query_context else {
+        return Err(QueryEngineUnavailableError("Listing invocations").into());
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_LIST_INVOCATIONS_LIMIT);
+    if limit == 0 || limit > MAX_LIST_INVOCATIONS_LIMIT {
+        Err(InvalidFieldError(
+            "limit",
+            format!("must be between 1 and {MAX_LIST_INVOCATIONS_LIMIT}"),
+        ))?
+    }
+    let cursor = cursor
+        .map(|cursor| cursor.parse::<InvocationId>())
+        .transpose()
+        .map_err(|e| InvalidFieldError("cursor", e.to_string()))?;
+
+    let mut filters = vec!["status != 'completed'".to_owned()];
+    if let Some(service) = service {
+        filters.push(format!(
+            "target_service_name = '{}'",
+            service.replace('\'', "''")
+        ));
+    }
+    if let Some(status) = status {
+        filters.push(format!("status = '{}'", <&'static str>::from(status)));
+    }
+    if let Some(cursor) = cursor {
+        filters.push(format!("id > '{cursor}'"));
+    }
+
+    // fetch one more row to learn whether there is a next page
+    let mut invocations = query_in_flight_invocations(
+        &query_context,
+        &format!(
+            "SELECT id, target, status, created_at, \
+                COALESCE(pinned_deployment_id, last_attempt_deployment_id), retry_count \
+            FROM sys_invocation WHERE {} ORDER BY id LIMIT {}",
+            filters.join(" AND "),
+            limit + 1
+        ),
+    )
+    .await
+    .map_err(InvocationsQueryError)?;
+
+    let next_cursor = if invocations.len() > limit {
+        invocations.truncate(limit);
+        invocations
+            .last()
+            .map(|invocation| invocation.id.to_string())
+    } else {
+        None
+    };
+
+    Ok(Json(ListInvocationsResponse {
+        invocations,
+        next_cursor,
+    }))
+}
+
+async fn query_in_flight_invocations(
+    query_context: &QueryContext,
+    query: &str,
+) -> Result<Vec<InvocationResponse>, DataFusionError> {
+    let batches: Vec<RecordBatch> = query_context.execute(query).await?.try_collect().await?;
+
+    let parse_error = |e: &dyn std::fmt::Display| {
+        DataFusionError::Internal(format!("unexpected sys_invocation row: {e}"))
+    };
+
+    let mut invocations = Vec::new();
+    for batch in batches {
+        let ids = as_large_string_array(batch.column(0))?;
+        let targets = as_large_string_array(batch.column(1))?;
+        let statuses = as_large_string_array(batch.column(2))?;
+        let created_at = as_timestamp_millisecond_array(batch.column(3))?;
+        let deployment_ids = as_large_string_array(batch.column(4))?;
+        let retry_counts = as_uint64_array(batch.column(5))?;
+
+        for row in 0..batch.num_rows() {
+            invocations.push(InvocationResponse {
+                id: ids.value(row).parse().map_err(|e| parse_error(&e))?,
+                target: targets.value(row).to_owned(),
+                status: statuses.value(row).parse().map_err(|e| parse_error(&e))?,
+                created_at: (SystemTime::UNIX_EPOCH
+                    + Duration::from_millis(created_at.value(row).max(0) as u64))
+                .into(),
+                deployment_id: if deployment_ids.is_null(row) {
+                    None
+                } else {
+                    Some(
+                        deployment_ids
+                            .value(row)
+                            .parse()
+                            .map_err(|e| parse_error(&e))?,
+                    )
+                },
+                retry_count: if retry_counts.is_null(row) {
+                    0
+                } else {
+                    retry_counts.value(row)
+                },
+            });
+        }
+    }
+    Ok(invocations)
+}
 
 #[derive(Debug, Default, Deserialize, JsonSchema)]
 pub enum DeletionMode {
@@ -468,6 +660,30 @@ where
     }
 }
 
+/// Restart an invocation from the beginning
+#[openapi(
+    summary = "Restart an invocation",
+    description = "Re-run the given completed invocation, either succeeded or failed, with the same target and input. \
+    This creates a new invocation with a different invocation id, whose 'restarted_from' column links it to the given invocation. \
+    Equivalent to restarting the invocation as new without the 'from' query parameter.",
+    operation_id = "restart_invocation",
+    tags = "invocation",
+    parameters(path(
+        name = "invocation_id",
+        description = "Invocation identifier.",
+        schema = "std::string::String"
+    ))
+)]
+pub async fn restart_invocation<Metadata, Discovery, Telemetry, Invocations>(
+    state: State<AdminServiceState<Metadata, Discovery, Telemetry, Invocations>>,
+    invocation_id: Path<String>,
+) -> Result<Json<RestartAsNewInvocationResponse>, RestartInvocationError>
+where
+    Invocations: InvocationClient,
+{
+    restart_as_new_invocation(state, invocation_id, Query(Default::default())).await
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct ResumeInvocationQueryParams {
     pub deployment: Option<PatchDeploymentId>,
@@ -625,3 +841,302 @@ where
 
     Ok(StatusCode::ACCEPTED)
 }
+
+generate_meta_api_error!(CompleteJournalEntryError: [
+    InvocationNotFoundError,
+    InvalidFieldError,
+    QueryEngineUnavailableError,
+    InvocationsQueryError,
+    JournalEntryNotFoundError,
+    JournalEntryNotPendingError,
+    ConfirmationRequiredError,
+    CommandAppendError
+]);
+
+/// Manually complete a pending journal entry
+#[openapi(
+    summary = "Complete a pending journal entry",
+    description = "Manually complete a pending journal entry of an in-flight invocation with the given value or failure, \
+    e.g. a call or a promise whose completer is gone, to unblock the invocation without killing it. \
+    A request without `confirmation_token` only validates the entry, and fails with 428 returning the token to confirm the completion with. \
+    Every manual completion is recorded in the audit log of the admin server.",
+    operation_id = "complete_journal_entry",
+    tags = "invocation",
+    parameters(
+        path(
+            name = "invocation_id",
+            description = "Invocation identifier.",
+            schema = "std::string::String"
+        ),
+        path(
+            name = "entry_index",
+            description = "Index of the pending journal entry.",
+            schema = "u32"
+        )
+    ),
+    responses(
+        ignore_return_type = true,
+        response(
+            status = "202",
+            description = "Accepted",
+            content = "okapi_operation::Empty",
+        ),
+        from_type = "CompleteJournalEntryError",
+    )
+)]
+pub async fn complete_journal_entry<Metadata, Discovery, Telemetry, Invocations>(
+    State(state): State<AdminServiceState<Metadata, Discovery, Telemetry, Invocations>>,
+    Path((invocation_id, entry_index)): Path<(String, EntryIndex)>,
+    #[request_body(required = true)] Json(CompleteJournalEntryRequest {
+        result,
+        confirmation_token,
+    }): Json<CompleteJournalEntryRequest>,
+) -> Result<StatusCode, CompleteJournalEntryError> {
+    let invocation_id = invocation_id
+        .parse::<InvocationId>()
+        .map_err(|e| InvalidFieldError("invocation_id", e.to_string()))?;
+    let Some(query_context) = state.query_context else {
+        return Err(QueryEngineUnavailableError("Completing journal entries").into());
+    };
+
+    let pending_entry = find_pending_entry(&query_context, invocation_id, entry_index).await?;
+
+    let expected_token =
+        confirmation_token_for(invocation_id, entry_index, &pending_entry, &result);
+    if confirmation_token.as_deref() != Some(expected_token.as_str()) {
+        Err(ConfirmationRequiredError {
+            entry_type: pending_entry.entry_type,
+            token: expected_token,
+        })?
+    }
+
+    let response_result = match &result {
+        JournalEntryCompletionResult::Value(value) => ResponseResult::Success(Bytes::from(
+            serde_json::to_vec(value).expect("JSON value must be serializable"),
+        )),
+        JournalEntryCompletionResult::Failure { code, message } => {
+            ResponseResult::Failure(InvocationError::new(
+                code.map(InvocationErrorCode::from)
+                    .unwrap_or(codes::INTERNAL),
+                message.clone(),
+            ))
+        }
+    };
+    // The partition processor re-validates the entry is still pending when applying the command
+    let cmd = Command::CompletePendingEntry(CompletePendingEntryRequest {
+        invocation_id,
+        invocation_epoch: pending_entry.invocation_epoch,
+        entry_index,
+        completion_id: pending_entry.completion_id,
+        result: response_result,
+    });
+
+    restate_bifrost::append_to_bifrost(
+        &state.bifrost,
+        Arc::new(Envelope::new(
+            create_envelope_header(invocation_id.partition_key()),
+            cmd,
+        )),
+    )
+    .await
+    .map_err(|err| {
+        warn!("Could not append the manual journal entry completion to Bifrost: {err}");
+        CommandAppendError
+    })?;
+
+    info!(
+        target: AUDIT_LOG_TARGET,
+        restate.invocation.id = %invocation_id,
+        restate.journal.index = entry_index,
+        "Manually completed the pending {} entry {entry_index} of invocation {invocation_id} with {}",
+        pending_entry.entry_type,
+        match result {
+            JournalEntryCompletionResult::Value(_) => "a value".to_owned(),
+            JournalEntryCompletionResult::Failure { message, .. } => {
+                format!("the failure '{message}'")
+            }
+        }
+    );
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// A journal entry waiting for a completion.
+#[derive(Debug)]
+struct PendingJournalEntry {
+    entry_type: String,
+    completion_id: CompletionId,
+    invocation_epoch: InvocationEpoch,
+}
+
+struct JournalRow {
+    index: EntryIndex,
+    version: u32,
+    entry_type: String,
+    completed: bool,
+    entry_lite_json: Option<String>,
+}
+
+async fn find_pending_entry(
+    query_context: &QueryContext,
+    invocation_id: InvocationId,
+    entry_index: EntryIndex,
+) -> Result<PendingJournalEntry, CompleteJournalEntryError> {
+    let batches: Vec<RecordBatch> = query_context
+        .execute(&format!(
+            "SELECT status, invocation_epoch FROM sys_invocation_status WHERE id = '{invocation_id}'"
+        ))
+        .await
+        .map_err(InvocationsQueryError)?
+        .try_collect()
+        .await
+        .map_err(InvocationsQueryError)?;
+    let Some((status, invocation_epoch)) = batches
+        .iter()
+        .find(|batch| batch.num_rows() > 0)
+        .map(|batch| {
+            let statuses = as_large_string_array(batch.column(0))?;
+            let epochs = as_uint32_array(batch.column(1))?;
+            Ok::<_, DataFusionError>((
+                statuses.value(0).to_owned(),
+                if epochs.is_null(0) {
+                    0
+                } else {
+                    epochs.value(0)
+                },
+            ))
+        })
+        .transpose()
+        .map_err(InvocationsQueryError)?
+    else {
+        return Err(InvocationNotFoundError(invocation_id.to_string()).into());
+    };
+    if !matches!(status.as_str(), "invoked" | "suspended" | "paused") {
+        return Err(JournalEntryNotPendingError(format!(
+            "the invocation '{invocation_id}' is {status}"
+        ))
+        .into());
+    }
+
+    let batches: Vec<RecordBatch> = query_context
+        .execute(&format!(
+            "SELECT index, version, entry_type, completed, entry_lite_json \
+            FROM sys_journal WHERE id = '{invocation_id}'"
+        ))
+        .await
+        .map_err(InvocationsQueryError)?
+        .try_collect()
+        .await
+        .map_err(InvocationsQueryError)?;
+    let mut journal = Vec::new();
+    for batch in batches {
+        let indexes = as_uint32_array(batch.column(0)).map_err(InvocationsQueryError)?;
+        let versions = as_uint32_array(batch.column(1)).map_err(InvocationsQueryError)?;
+        let entry_types = as_large_string_array(batch.column(2)).map_err(InvocationsQueryError)?;
+        let completed = as_boolean_array(batch.column(3)).map_err(InvocationsQueryError)?;
+        let entries_lite_json =
+            as_large_string_array(batch.column(4)).map_err(InvocationsQueryError)?;
+        for row in 0..batch.num_rows() {
+            journal.push(JournalRow {
+                index: indexes.value(row),
+                version: versions.value(row),
+                entry_type: entry_types.value(row).to_owned(),
+                completed: !completed.is_null(row) && completed.value(row),
+                entry_lite_json: (!entries_lite_json.is_null(row))
+                    .then(|| entries_lite_json.value(row).to_owned()),
+            });
+        }
+    }
+
+    pending_entry_of(&journal, invocation_id, entry_index, invocation_epoch)
+}
+
+fn pending_entry_of(
+    journal: &[JournalRow],
+    invocation_id: InvocationId,
+    entry_index: EntryIndex,
+    invocation_epoch: InvocationEpoch,
+) -> Result<PendingJournalEntry, CompleteJournalEntryError> {
+    let Some(entry) = journal.iter().find(|row| row.index == entry_index) else {
+        return Err(JournalEntryNotFoundError(invocation_id.to_string(), entry_index).into());
+    };
+    let not_pending = |reason: String| {
+        CompleteJournalEntryError::from(JournalEntryNotPendingError(format!(
+            "the {} entry {entry_index} {reason}",
+            entry.entry_type
+        )))
+    };
+
+    if entry.version < 2 {
+        // Journals of service protocol <= 3 are completed by entry index
+        if entry.completed {
+            return Err(not_pending("is already completed".to_owned()));
+        }
+        return Ok(PendingJournalEntry {
+            entry_type: entry.entry_type.clone(),
+            completion_id: entry_index,
+            invocation_epoch,
+        });
+    }
+
+    let lite_entry = |row: &JournalRow| {
+        row.entry_lite_json
+            .as_deref()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+    };
+    let Some((command_type, command)) = lite_entry(entry).and_then(|lite_entry| {
+        let (command_type, command) = lite_entry.get("Command")?.as_object()?.iter().next()?;
+        Some((command_type.clone(), command.clone()))
+    }) else {
+        return Err(not_pending("is not a command".to_owned()));
+    };
+    // These are the commands the partition processor can complete manually
+    let completion_id_field = match command_type.as_str() {
+        "Call" => "result_completion_id",
+        "Sleep" | "GetPromise" | "AttachInvocation" | "GetInvocationOutput" => "completion_id",
+        _ => return Err(not_pending("cannot be completed manually".to_owned())),
+    };
+    let Some(completion_id) = command
+        .get(completion_id_field)
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|id| CompletionId::try_from(id).ok())
+    else {
+        return Err(not_pending("has no completion id".to_owned()));
+    };
+
+    let completed = journal.iter().any(|row| {
+        lite_entry(row).is_some_and(|lite_entry| {
+            lite_entry
+                .pointer("/Notification/id/CompletionId")
+                .and_then(serde_json::Value::as_u64)
+                == Some(u64::from(completion_id))
+        })
+    });
+    if completed {
+        return Err(not_pending("is already completed".to_owned()));
+    }
+
+    Ok(PendingJournalEntry {
+        entry_type: entry.entry_type.clone(),
+        completion_id,
+        invocation_epoch,
+    })
+}
+
+/// The token binds the confirmation to the entry, the invocation epoch and the result it is
+/// completed with.
+fn confirmation_token_for(
+    invocation_id: InvocationId,
+    entry_index: EntryIndex,
+    pending_entry: &PendingJournalEntry,
+    result: &JournalEntryCompletionResult,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+    invocation_id.to_string().hash(&mut hasher);
+    entry_index.hash(&mut hasher);
+    pending_entry.completion_id.hash(&mut hasher);
+    pending_entry.invocation_epoch.hash(&mut hasher);
+    serde_json::to_string(result)
+        .expect("completion result must be serializable")
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
@@ -16,6 +16,7 @@ mod error;
 mod handlers;
 mod health;
 mod invocations;
+mod schema_changes;
 mod services;
 mod subscriptions;
 mod version;
@@ -30,6 +31,8 @@ use restate_wal_protocol::{Destination, Header, Source};
 
 use crate::state::AdminServiceState;
 
+#[cfg(feature = "storage-query")]
+pub(crate) use deployments::resume_draining_deployments;
 pub use version::{MAX_ADMIN_API_VERSION, MIN_ADMIN_API_VERSION};
 
 pub fn create_router<Metadata, Discovery, Telemetry, Invocations>(
@@ -66,6 +69,10 @@ where
             "/deployments/{deployment}",
             axum::routing::put(deployments::update_deployment),
         )
+        .route(
+            "/schema/changes",
+            axum::routing::get(schema_changes::stream_schema_changes),
+        )
         .route("/services", get(openapi_handler!(services::list_services)))
         .route(
             "/services/{service}",
@@ -91,6 +98,10 @@ where
             "/services/{service}/handlers/{handler}",
             get(openapi_handler!(handlers::get_service_handler)),
         )
+        .route(
+            "/invocations",
+            get(openapi_handler!(invocations::list_invocations)),
+        )
         .route(
             "/invocations/{invocation_id}",
             delete(openapi_handler!(invocations::delete_invocation)),
@@ -115,10 +126,18 @@ where
             "/invocations/{invocation_id}/restart-as-new",
             patch(openapi_handler!(invocations::restart_as_new_invocation)),
         )
+        .route(
+            "/invocations/{invocation_id}/restart",
+            post(openapi_handler!(invocations::restart_invocation)),
+        )
         .route(
             "/invocations/{invocation_id}/resume",
             patch(openapi_handler!(invocations::resume_invocation)),
         )
+        .route(
+            "/invocations/{invocation_id}/journal/{entry_index}/complete",
+            post(openapi_handler!(invocations::complete_journal_entry)),
+        )
         .route(
             "/invocations/{invocation_id}/pause",
             patch(openapi_handler!(invocations::pause_invocation)),
@@ -141,6 +160,10 @@ where
         )
         .route("/health", get(openapi_handler!(health::health)))
         .route("/version", get(openapi_handler!(version::version)))
+        .route(
+            "/version-compatibility",
+            get(openapi_handler!(version::version_compatibility)),
+        )
         .route(
             "/cluster-health",
             get(openapi_handler!(cluster_health::cluster_health)),
@@ -202,10 +225,26 @@ where
             ..Default::default()
         });
 
+    let title = "Admin API";
+    let version = env!("CARGO_PKG_VERSION");
+
+    // /openapi negotiates the format of the document with the Accept header, while
+    // /openapi.json serves it as JSON to any client, e.g. code generators.
+    let openapi_spec = router
+        .generate_openapi_builder()
+        .title(title)
+        .version(version)
+        .build()
+        .expect("Error when building the OpenAPI specification");
+
     // Finish router
     router
-        .finish_openapi("/openapi", "Admin API", env!("CARGO_PKG_VERSION"))
+        .finish_openapi("/openapi", title, version)
         .expect("Error when building the OpenAPI specification")
+        .route(
+            "/openapi.json",
+            axum::routing::get(move || std::future::ready(axum::Json(openapi_spec.clone()))),
+        )
         .with_state(state)
 }
 
@@ -0,0 +1,312 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::{Stream, stream};
+
+use restate_admin_rest_model::schema_changes::{SchemaChange, SchemaChangeEvent};
+use restate_core::{Metadata, MetadataKind};
+use restate_types::Versioned;
+use restate_types::identifiers::ServiceRevision;
+use restate_types::schema::Schema;
+use restate_types::schema::deployment::{Deployment, DeploymentResolver};
+use restate_types::schema::service::{ServiceMetadata, ServiceMetadataResolver};
+
+/// Streams the changes of the registered deployments and services as server-sent events.
+///
+/// The changes are observed through the schema metadata of this node, hence they include the
+/// registrations done through any other node of the cluster. Intermediate versions might be
+/// skipped when the schema changes faster than this node syncs it, in which case the event
+/// summarizes all the changes since the last event.
+pub async fn stream_schema_changes() -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let metadata = Metadata::current();
+    let mut schema_watch = metadata.watch(MetadataKind::Schema);
+    schema_watch.mark_unchanged();
+    let previous = metadata.schema_snapshot();
+
+    let events = stream::unfold(
+        (metadata, schema_watch, previous),
+        |(metadata, mut schema_watch, mut previous)| async move {
+            loop {
+                schema_watch.changed().await.ok()?;
+                let current = metadata.schema_snapshot();
+                if current.version() <= previous.version() {
+                    continue;
+                }
+
+                let changes = SchemaSnapshot::new(&previous).diff(&SchemaSnapshot::new(&current));
+                previous = current;
+                if changes.is_empty() {
+                    // e.g. only the subscriptions changed
+                    continue;
+                }
+
+                let event = SchemaChangeEvent {
+                    version: u32::from(previous.version()),
+                    changes,
+                };
+                let event = Event::default()
+                    .event("schema_change")
+                    .id(event.version.to_string())
+                    .json_data(event);
+                return Some((event, (metadata, schema_watch, previous)));
+            }
+        },
+    );
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// The deployments and services of a schema, sorted to produce a deterministic diff.
+struct SchemaSnapshot {
+    /// The deployments sorted by creation time, with their services sorted by name.
+    deployments: Vec<(Deployment, Vec<(String, ServiceRevision)>)>,
+    /// The latest revision of each service, sorted by name.
+    services: Vec<ServiceMetadata>,
+}
+
+impl SchemaSnapshot {
+    fn new(schema: &Schema) -> Self {
+        Self::from_parts(schema.get_deployments(), schema.list_services())
+    }
+
+    fn from_parts(
+        mut deployments: Vec<(Deployment, Vec<(String, ServiceRevision)>)>,
+        mut services: Vec<ServiceMetadata>,
+    ) -> Self {
+        deployments.sort_by_key(|(deployment, _)| deployment.created_at);
+        for (_, services) in &mut deployments {
+            services.sort();
+        }
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            deployments,
+            services,
+        }
+    }
+
+    fn diff(&self, current: &SchemaSnapshot) -> Vec<SchemaChange> {
+        let mut changes = Vec::new();
+
+        let previous_deployments: HashMap<_, _> = self
+            .deployments
+            .iter()
+            .map(|(deployment, services)| (deployment.id, (deployment, services)))
+            .collect();
+        for (deployment, services) in &current.deployments {
+            match previous_deployments.get(&deployment.id) {
+                None => changes.push(SchemaChange::DeploymentRegistered { id: deployment.id }),
+                Some((previous_deployment, previous_services))
+                    if previous_deployment.ty != deployment.ty
+                        || previous_deployment.additional_headers
+                            != deployment.additional_headers
+                        || *previous_services != services =>
+                {
+                    changes.push(SchemaChange::DeploymentUpdated { id: deployment.id })
+                }
+                Some(_) => {}
+            }
+        }
+        changes.extend(
+            self.deployments
+                .iter()
+                .filter(|(deployment, _)| {
+                    !current
+                        .deployments
+                        .iter()
+                        .any(|(current, _)| current.id == deployment.id)
+                })
+                .map(|(deployment, _)| SchemaChange::DeploymentRemoved { id: deployment.id }),
+        );
+
+        let previous_services: HashMap<_, _> = self
+            .services
+            .iter()
+            .map(|service| (&service.name, service))
+            .collect();
+        for service in &current.services {
+            match previous_services.get(&service.name) {
+                None => changes.push(SchemaChange::ServiceRegistered {
+                    name: service.name.clone(),
+                    revision: service.revision,
+                }),
+                // A service is updated either by registering a new revision, or by modifying its
+                // configuration.
+                Some(previous_service) if *previous_service != service => {
+                    changes.push(SchemaChange::ServiceUpdated {
+                        name: service.name.clone(),
+                        revision: service.revision,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        changes.extend(
+            self.services
+                .iter()
+                .filter(|service| {
+                    !current
+                        .services
+                        .iter()
+                        .any(|current| current.name == service.name)
+                })
+                .map(|service| SchemaChange::ServiceRemoved {
+                    name: service.name.clone(),
+                }),
+        );
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use restate_types::identifiers::DeploymentId;
+
+    fn deployment() -> Deployment {
+        let mut deployment = Deployment::mock();
+        deployment.id = DeploymentId::new();
+        deployment
+    }
+
+    fn service(name: &str, deployment: &Deployment, revision: ServiceRevision) -> ServiceMetadata {
+        ServiceMetadata {
+            deployment_id: deployment.id,
+            revision,
+            ..ServiceMetadata::mock_service(name, ["greet"])
+        }
+    }
+
+    fn snapshot(
+        deployments: impl IntoIterator<Item = Deployment>,
+        services: impl IntoIterator<Item = ServiceMetadata>,
+    ) -> SchemaSnapshot {
+        let services: Vec<_> = services.into_iter().collect();
+        let deployments = deployments
+            .into_iter()
+            .map(|deployment| {
+                let deployment_services = services
+                    .iter()
+                    .filter(|service| service.deployment_id == deployment.id)
+                    .map(|service| (service.name.clone(), service.revision))
+                    .collect();
+                (deployment, deployment_services)
+            })
+            .collect();
+        SchemaSnapshot::from_parts(deployments, services)
+    }
+
+    #[test]
+    fn register_deployment() {
+        let deployment = deployment();
+        let previous = snapshot([], []);
+        let current = snapshot(
+            [deployment.clone()],
+            [
+                service("greeter.Greeter", &deployment, 1),
+                service("greeter.AnotherGreeter", &deployment, 1),
+            ],
+        );
+
+        assert_eq!(
+            previous.diff(&current),
+            vec![
+                SchemaChange::DeploymentRegistered { id: deployment.id },
+                SchemaChange::ServiceRegistered {
+                    name: "greeter.AnotherGreeter".to_owned(),
+                    revision: 1
+                },
+                SchemaChange::ServiceRegistered {
+                    name: "greeter.Greeter".to_owned(),
+                    revision: 1
+                },
+            ]
+        );
+        assert!(current.diff(&current).is_empty());
+    }
+
+    #[test]
+    fn update_service_revision() {
+        let deployment_1 = deployment();
+        let deployment_2 = deployment();
+        let previous = snapshot(
+            [deployment_1.clone()],
+            [service("greeter.Greeter", &deployment_1, 1)],
+        );
+        let current = snapshot(
+            [deployment_1.clone(), deployment_2.clone()],
+            [service("greeter.Greeter", &deployment_2, 2)],
+        );
+
+        assert_eq!(
+            previous.diff(&current),
+            vec![
+                SchemaChange::DeploymentUpdated {
+                    id: deployment_1.id
+                },
+                SchemaChange::DeploymentRegistered {
+                    id: deployment_2.id
+                },
+                SchemaChange::ServiceUpdated {
+                    name: "greeter.Greeter".to_owned(),
+                    revision: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn update_service_configuration() {
+        let deployment = deployment();
+        let previous_service = service("greeter.Greeter", &deployment, 1);
+        let mut current_service = previous_service.clone();
+        current_service
+            .handlers
+            .get_mut("greet")
+            .unwrap()
+            .inactivity_timeout = Some(Duration::from_secs(60));
+        let previous = snapshot([deployment.clone()], [previous_service]);
+        let current = snapshot([deployment], [current_service]);
+
+        assert_eq!(
+            previous.diff(&current),
+            vec![SchemaChange::ServiceUpdated {
+                name: "greeter.Greeter".to_owned(),
+                revision: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn remove_deployment() {
+        let deployment = deployment();
+        let previous = snapshot(
+            [deployment.clone()],
+            [service("greeter.Greeter", &deployment, 1)],
+        );
+        let current = snapshot([], []);
+
+        assert_eq!(
+            previous.diff(&current),
+            vec![
+                SchemaChange::DeploymentRemoved { id: deployment.id },
+                SchemaChange::ServiceRemoved {
+                    name: "greeter.Greeter".to_owned()
+                },
+            ]
+        );
+    }
+}
@@ -12,17 +12,18 @@ use std::sync::Arc;
 use tracing::{debug, warn};
 
 use axum::Json;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use bytes::Bytes;
 use http::StatusCode;
 use okapi_operation::*;
+use serde::Deserialize;
 
 use restate_admin_rest_model::services::ListServicesResponse;
 use restate_admin_rest_model::services::*;
 use restate_core::TaskCenter;
 use restate_errors::warn_it;
 use restate_types::config::Configuration;
-use restate_types::identifiers::{ServiceId, WithPartitionKey};
+use restate_types::identifiers::{DeploymentId, ServiceId, WithPartitionKey};
 use restate_types::schema;
 use restate_types::schema::registry::MetadataService;
 use restate_types::schema::service::ServiceMetadata;
@@ -33,20 +34,45 @@ use super::create_envelope_header;
 use super::error::*;
 use crate::state::AdminServiceState;
 
+#[derive(Debug, Default, Deserialize)]
+pub struct ListServicesParams {
+    pub deployment: Option<DeploymentId>,
+}
+
 /// List services
 #[openapi(
     summary = "List services",
     description = "List all registered services.",
     operation_id = "list_services",
-    tags = "service"
+    tags = "service",
+    parameters(query(
+        name = "deployment",
+        description = "If provided, list only the services whose latest revision is served by the given deployment.",
+        required = false,
+        style = "simple",
+        allow_empty_value = false,
+        schema = "std::string::String",
+    ))
 )]
 pub async fn list_services<Metadata, Discovery, Telemetry, Invocations>(
     State(state): State<AdminServiceState<Metadata, Discovery, Telemetry, Invocations>>,
+    Query(ListServicesParams { deployment }): Query<ListServicesParams>,
 ) -> Result<Json<ListServicesResponse>, MetaApiError>
 where
     Metadata: MetadataService,
 {
-    let services = state.schema_registry.list_services();
+    let mut services = state.schema_registry.list_services();
+
+    if let Some(deployment_id) = deployment {
+        if state
+            .schema_registry
+            .get_deployment(deployment_id)
+            .is_none()
+        {
+            return Err(MetaApiError::DeploymentNotFound(deployment_id));
+        }
+        services.retain(|service| service.deployment_id == deployment_id);
+    }
 
     Ok(ListServicesResponse { services }.into())
 }
@@ -141,6 +167,11 @@ pub async fn modify_service<Metadata, Discovery, Telemetry, Invocations>(
         journal_retention,
         inactivity_timeout,
         abort_timeout,
+        max_journal_length,
+        state_ttl,
+        completion_retention,
+        ingress_route,
+        ingress_limits,
     }): Json<ModifyServiceRequest>,
 ) -> Result<Json<ServiceMetadata>, MetaApiError>
 where
@@ -153,6 +184,11 @@ where
         workflow_completion_retention,
         inactivity_timeout,
         abort_timeout,
+        max_journal_length,
+        state_ttl,
+        completion_retention,
+        ingress_route,
+        ingress_limits,
     };
 
     if modify_request.public.is_none()
@@ -161,6 +197,11 @@ where
         && modify_request.workflow_completion_retention.is_none()
         && modify_request.inactivity_timeout.is_none()
         && modify_request.abort_timeout.is_none()
+        && modify_request.max_journal_length.is_none()
+        && modify_request.state_ttl.is_none()
+        && modify_request.completion_retention.is_none()
+        && modify_request.ingress_route.is_none()
+        && modify_request.ingress_limits.is_none()
     {
         // No need to do anything
         return get_service(State(state), Path(service_name)).await;
@@ -8,11 +8,18 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use super::error::*;
+use crate::generate_meta_api_error;
 use axum::Json;
+use axum::extract::Query;
 use okapi_operation::*;
-use restate_admin_rest_model::version::{AdminApiVersion, VersionInformation};
+use restate_admin_rest_model::version::{
+    AdminApiVersion, VersionCompatibilityInformation, VersionInformation,
+};
 use restate_core::TaskCenter;
 use restate_types::config::Configuration;
+use restate_types::{COMPATIBILITY_INFORMATION, SemanticRestateVersion};
+use serde::Deserialize;
 
 /// Min/max supported admin api versions by the server
 pub const MIN_ADMIN_API_VERSION: AdminApiVersion = AdminApiVersion::V2;
@@ -37,3 +44,51 @@ pub async fn version() -> Json<VersionInformation> {
         })),
     })
 }
+
+generate_meta_api_error!(VersionCompatibilityError: [InvalidFieldError]);
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct VersionCompatibilityParams {
+    pub target_version: Option<String>,
+}
+
+/// Version compatibility endpoint
+#[openapi(
+    summary = "Version compatibility information",
+    description = "Obtain the data compatibility boundaries of this server version. \
+    When `target_version` is provided, checks whether it's safe to replace this server with the given version.",
+    operation_id = "version_compatibility",
+    tags = "version",
+    parameters(query(
+        name = "target_version",
+        description = "The Restate version this server is going to be replaced with.",
+        required = false,
+        style = "simple",
+        allow_empty_value = false,
+        schema = "std::string::String",
+    ))
+)]
+pub async fn version_compatibility(
+    Query(VersionCompatibilityParams { target_version }): Query<VersionCompatibilityParams>,
+) -> Result<Json<VersionCompatibilityInformation>, VersionCompatibilityError> {
+    let compatible = target_version
+        .as_deref()
+        .map(|target_version| {
+            SemanticRestateVersion::parse(target_version)
+                .map(|target_version| COMPATIBILITY_INFORMATION.is_compatible_with(&target_version))
+                .map_err(|e| InvalidFieldError("target_version", e.to_string()))
+        })
+        .transpose()?;
+
+    Ok(Json(VersionCompatibilityInformation {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        min_forward_compatible_version: COMPATIBILITY_INFORMATION
+            .min_forward_compatible_version
+            .to_string(),
+        min_backward_compatible_version: COMPATIBILITY_INFORMATION
+            .min_backward_compatible_version
+            .to_string(),
+        target_version,
+        compatible,
+    }))
+}
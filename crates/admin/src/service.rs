@@ -8,12 +8,15 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use axum::error_handling::HandleErrorLayer;
+use axum::response::IntoResponse;
 use http::{Request, Response, StatusCode};
 use tower::ServiceBuilder;
 use tower_http::classify::ServerErrorsFailureClass;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{Span, debug, error, info, info_span};
 
@@ -21,6 +24,7 @@ use restate_admin_rest_model::version::AdminApiVersion;
 use restate_bifrost::Bifrost;
 use restate_core::network::net_util;
 use restate_core::{MetadataWriter, TaskCenter};
+use restate_http_auth::{AuthLayer, Authenticator, TlsAcceptor};
 use restate_service_client::HttpClient;
 use restate_service_protocol::discovery::ServiceDiscovery;
 use restate_time_util::DurationExt;
@@ -29,12 +33,17 @@ use restate_types::invocation::client::InvocationClient;
 use restate_types::live::LiveLoad;
 use restate_types::net::address::AdminPort;
 use restate_types::net::listener::Listeners;
+use restate_types::partitions::recovery;
 use restate_types::schema::registry::SchemaRegistry;
 
+use crate::cluster_controller::ClusterControllerHandle;
 use crate::rest_api::{MAX_ADMIN_API_VERSION, MIN_ADMIN_API_VERSION};
 use crate::schema_registry_integration::{MetadataService, TelemetryClient};
 use crate::{rest_api, state};
 
+/// Paths served without credentials, when authentication is enabled.
+const PUBLIC_PATHS: &[&str] = &["/health", "/v2/health", "/v3/health"];
+
 #[derive(Debug, thiserror::Error)]
 #[error("could not create the service client: {0}")]
 pub struct BuildError(#[from] restate_service_client::BuildError);
@@ -44,6 +53,7 @@ pub struct AdminService<Metadata, Discovery, Telemetry, Invocations> {
     bifrost: Bifrost,
     schema_registry: SchemaRegistry<Metadata, Discovery, Telemetry>,
     invocation_client: Invocations,
+    cluster_controller: Option<Arc<ClusterControllerHandle>>,
     #[cfg(feature = "storage-query")]
     query_context: Option<restate_storage_query_datafusion::context::QueryContext>,
     #[cfg(feature = "metadata-api")]
@@ -73,11 +83,20 @@ where
                 TelemetryClient(telemetry_http_client),
             ),
             invocation_client,
+            cluster_controller: None,
             #[cfg(feature = "storage-query")]
             query_context: None,
         }
     }
 
+    /// Exposes the APIs which need the cluster controller, e.g. to create partition snapshots.
+    pub fn with_cluster_controller(self, cluster_controller: ClusterControllerHandle) -> Self {
+        Self {
+            cluster_controller: Some(Arc::new(cluster_controller)),
+            ..self
+        }
+    }
+
     #[cfg(feature = "storage-query")]
     pub fn with_query_context(
         self,
@@ -94,12 +113,32 @@ where
         mut updateable_config: impl LiveLoad<Live = AdminOptions>,
     ) -> anyhow::Result<()> {
         let opts = updateable_config.live_load();
+        let authenticator = opts.authentication().map(Authenticator::new).transpose()?;
+        let tls_acceptor = opts
+            .authentication()
+            .and_then(|authentication| authentication.tls.as_ref())
+            .map(TlsAcceptor::new)
+            .transpose()?;
 
         let rest_state = state::AdminServiceState::new(
             self.schema_registry,
             self.invocation_client,
             self.bifrost,
         );
+        #[cfg(feature = "storage-query")]
+        let rest_state = rest_state.with_query_context(self.query_context.clone());
+
+        #[cfg(feature = "storage-query")]
+        if let Some(query_context) = &self.query_context {
+            TaskCenter::spawn(
+                restate_core::TaskKind::Disposable,
+                "resume-draining-deployments",
+                rest_api::resume_draining_deployments(
+                    rest_state.schema_registry.clone(),
+                    query_context.clone(),
+                ),
+            )?;
+        }
 
         let router = axum::Router::new();
 
@@ -115,6 +154,25 @@ where
             self.metadata_writer.raw_metadata_store_client(),
         ));
 
+        let router = router.merge(crate::timers_api::router());
+        let router = router.merge(crate::parked_commands_api::router());
+        let router = router.merge(crate::outbox_consistency_api::router());
+        let router = router.merge(crate::config_diff_api::router());
+
+        let router = if let Some(cluster_controller) = self.cluster_controller {
+            router.merge(crate::snapshots_api::router(cluster_controller))
+        } else {
+            router
+        };
+
+        #[cfg(feature = "chaos")]
+        let router = if opts.enable_chaos_api {
+            tracing::warn!("Chaos API is enabled, faults can be injected in this node");
+            router.merge(crate::chaos_api::router())
+        } else {
+            router
+        };
+
         // Add now the tracing layer, this makes sure we don't log ui requests
         let router = router.layer(
             TraceLayer::new_for_http()
@@ -126,7 +184,12 @@ where
                         http.request.method = %request.method(),
                         url.path = request.uri().path(),
                         url.query = request.uri().query().unwrap_or_default(),
-                        url.scheme = request.uri().scheme_str().unwrap_or("http")
+                        url.scheme = request.uri().scheme_str().unwrap_or("http"),
+                        http.request.id = request
+                            .headers()
+                            .get("x-request-id")
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or_default()
                     )
                 })
                 // Just log on response
@@ -175,6 +238,12 @@ where
         // Merge meta API router
         let router = router.merge(rest_api::create_router(rest_state));
 
+        let router = if opts.read_only_during_recovery {
+            router.layer(axum::middleware::from_fn(reject_writes_during_recovery))
+        } else {
+            router
+        };
+
         let router = axum::Router::new()
             .merge(with_api_version_middleware(
                 router.clone(),
@@ -198,7 +267,14 @@ where
                     .layer(tower::limit::GlobalConcurrencyLimitLayer::new(
                         opts.concurrent_api_requests_limit(),
                     )),
-            );
+            )
+            .layer(tower::util::option_layer(authenticator.map(
+                |authenticator| AuthLayer::new(authenticator, PUBLIC_PATHS),
+            )))
+            // Accept the request id provided by the client, or generate one, and echo it in the
+            // response, errors included.
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid));
 
         let service = hyper_util::service::TowerToHyperService::new(router.into_service());
 
@@ -207,7 +283,7 @@ where
             TaskCenter::with_current(|tc| opts.advertised_address(tc.address_book()))
         );
 
-        net_util::run_hyper_server(self.listeners, service, || ())
+        net_util::run_hyper_server(self.listeners, tls_acceptor, service, || ())
             .await
             .map_err(Into::into)
     }
@@ -223,6 +299,21 @@ fn unsupported_api_version(version: AdminApiVersion) -> axum::Router {
     ))
 }
 
+async fn reject_writes_during_recovery(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let method = request.method();
+    if method != http::Method::GET && method != http::Method::HEAD && recovery::is_recovering() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The node is recovering its partitions, only read-only requests are served until it completes.",
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
 fn with_api_version_middleware(router: axum::Router, version: AdminApiVersion) -> axum::Router {
     router.layer(axum::middleware::from_fn(
         move |mut request: axum::extract::Request, next: axum::middleware::Next| {
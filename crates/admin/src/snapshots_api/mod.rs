@@ -0,0 +1,96 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! API to take a snapshot of a partition while the server is running, the REST counterpart of
+//! `restatectl snapshots create`.
+//!
+//! Snapshots are uploaded to the repository configured in `worker.snapshots`, together with their
+//! metadata (partition key range, minimum applied LSN). Partition stores are restored from the
+//! latest snapshot when a node starts without a local copy, after validating that metadata.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use restate_core::Metadata;
+use restate_types::identifiers::PartitionId;
+use restate_types::logs::Lsn;
+use restate_types::net::partition_processor_manager::Snapshot;
+
+use crate::cluster_controller::ClusterControllerHandle;
+
+pub fn router(cluster_controller: Arc<ClusterControllerHandle>) -> Router {
+    Router::new()
+        .route("/partitions/{partition_id}/snapshot", post(create_snapshot))
+        .with_state(cluster_controller)
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateSnapshotParams {
+    /// The snapshot must include at least the changes up to this LSN
+    min_target_lsn: Option<u64>,
+    /// Trim the log of the partition up to the snapshot, once it's uploaded
+    #[serde(default)]
+    trim_log: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotResponse {
+    snapshot_id: String,
+    log_id: u32,
+    min_applied_lsn: u64,
+}
+
+/// Creates a snapshot of the given partition on one of the nodes running it
+async fn create_snapshot(
+    State(cluster_controller): State<Arc<ClusterControllerHandle>>,
+    Path(partition_id): Path<u16>,
+    Query(params): Query<CreateSnapshotParams>,
+) -> Response {
+    let partition_id = PartitionId::from(partition_id);
+    if !Metadata::with_current(|m| m.partition_table_ref().contains(&partition_id)) {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("Partition {partition_id} not found"),
+        )
+            .into_response();
+    }
+
+    match cluster_controller
+        .create_partition_snapshot(
+            partition_id,
+            params.min_target_lsn.map(Lsn::from),
+            params.trim_log,
+        )
+        .await
+    {
+        Err(_) => (StatusCode::SERVICE_UNAVAILABLE, "Node is shutting down").into_response(),
+        Ok(Err(err)) => {
+            info!("Failed to create snapshot of partition {partition_id}: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+        Ok(Ok(Snapshot {
+            snapshot_id,
+            log_id,
+            min_applied_lsn,
+        })) => Json(SnapshotResponse {
+            snapshot_id: snapshot_id.to_string(),
+            log_id: log_id.into(),
+            min_applied_lsn: min_applied_lsn.as_u64(),
+        })
+        .into_response(),
+    }
+}
@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use restate_bifrost::Bifrost;
+use restate_storage_query_datafusion::context::QueryContext;
 use restate_types::schema::registry::SchemaRegistry;
 
 #[derive(Clone, derive_builder::Builder)]
@@ -16,6 +17,10 @@ pub struct AdminServiceState<Metadata, Discovery, Telemetry, Invocations> {
     pub schema_registry: SchemaRegistry<Metadata, Discovery, Telemetry>,
     pub invocation_client: Invocations,
     pub bifrost: Bifrost,
+    /// Set when the storage query engine is available, e.g. to track the in-flight invocations
+    /// of a draining deployment.
+    #[builder(default)]
+    pub query_context: Option<QueryContext>,
 }
 
 impl<Metadata, Discovery, Telemetry, Invocations>
@@ -30,6 +35,14 @@ impl<Metadata, Discovery, Telemetry, Invocations>
             schema_registry,
             invocation_client,
             bifrost,
+            query_context: None,
+        }
+    }
+
+    pub fn with_query_context(self, query_context: Option<QueryContext>) -> Self {
+        Self {
+            query_context,
+            ..self
         }
     }
 }
@@ -19,12 +19,23 @@ use okapi_operation::{Components, ToMediaTypes, ToResponses, okapi};
 use schemars::JsonSchema;
 use serde::Serialize;
 
+use restate_storage_query_datafusion::QueryLimitError;
+use restate_types::identifiers::DeploymentId;
+
 /// This error is used by handlers to propagate API errors,
 /// and later converted to a response through the IntoResponse implementation
 #[derive(Debug, thiserror::Error)]
 pub enum StorageQueryError {
     #[error("datafusion failed: {0}")]
     DataFusion(#[from] DataFusionError),
+    #[error("the search text must not be empty")]
+    EmptySearchText,
+    #[error("deployment '{0}' not found")]
+    DeploymentNotFound(DeploymentId),
+    #[error("service '{0}' not found")]
+    ServiceNotFound(String),
+    #[error("service '{0}' is neither a virtual object nor a workflow")]
+    ServiceNotKeyed(String),
 }
 
 /// # Error description response
@@ -37,7 +48,18 @@ struct ErrorDescriptionResponse {
 
 impl IntoResponse for StorageQueryError {
     fn into_response(self) -> Response {
-        let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+        let status_code = match &self {
+            StorageQueryError::DataFusion(err) if exceeds_query_limits(err) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            StorageQueryError::DataFusion(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            StorageQueryError::EmptySearchText | StorageQueryError::ServiceNotKeyed(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            StorageQueryError::DeploymentNotFound(_) | StorageQueryError::ServiceNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+        };
 
         (
             status_code,
@@ -49,6 +71,15 @@ impl IntoResponse for StorageQueryError {
     }
 }
 
+/// Whether the query failed because it exceeded the configured query limits.
+fn exceeds_query_limits(err: &DataFusionError) -> bool {
+    match err.find_root() {
+        DataFusionError::ResourcesExhausted(_) => true,
+        DataFusionError::External(err) => err.downcast_ref::<QueryLimitError>().is_some(),
+        _ => false,
+    }
+}
+
 impl ToResponses for StorageQueryError {
     fn generate(components: &mut Components) -> Result<Responses, Error> {
         let error_media_type =
@@ -67,6 +98,9 @@ impl ToResponses for StorageQueryError {
                 "409".into() => okapi::openapi3::RefOr::Object(
                     okapi::openapi3::Response { content: error_media_type.clone(), ..Default::default() }
                 ),
+                "422".into() => okapi::openapi3::RefOr::Object(
+                    okapi::openapi3::Response { content: error_media_type.clone(), ..Default::default() }
+                ),
                 "500".into() => okapi::openapi3::RefOr::Object(
                     okapi::openapi3::Response { content: error_media_type.clone(), ..Default::default() }
                 ),
@@ -0,0 +1,98 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use datafusion::arrow::array::{Array, RecordBatch};
+use datafusion::common::cast::{
+    as_int64_array, as_large_string_array, as_timestamp_millisecond_array, as_uint64_array,
+};
+use futures::TryStreamExt;
+
+use restate_admin_rest_model::services::{LockHolder, VirtualObjectLockResponse};
+use restate_core::Metadata;
+use restate_types::schema::service::ServiceMetadataResolver;
+use restate_types::time::MillisSinceEpoch;
+
+use super::QueryServiceState;
+use super::error::StorageQueryError;
+
+/// Reports which invocation holds the lock of the given virtual object or workflow key, since
+/// when, and how many invocations are queued waiting for it.
+pub async fn virtual_object_lock(
+    State(state): State<Arc<QueryServiceState>>,
+    Path((service, key)): Path<(String, String)>,
+) -> Result<Json<VirtualObjectLockResponse>, StorageQueryError> {
+    let Some(service_metadata) =
+        Metadata::with_current(|m| m.schema_ref().resolve_latest_service(&service))
+    else {
+        return Err(StorageQueryError::ServiceNotFound(service));
+    };
+    if !service_metadata.ty.is_keyed() {
+        return Err(StorageQueryError::ServiceNotKeyed(service));
+    }
+
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "''"));
+    let query = format!(
+        "SELECT locked_by_id, locked_by_handler_name, locked_by_status, locked_since, \
+        locked_by_retry_count, locked_by_last_failure, queue_length \
+        FROM sys_virtual_object_lock \
+        WHERE service_name = {} AND service_key = {}",
+        quote(&service),
+        quote(&key),
+    );
+
+    let batches: Vec<RecordBatch> = state
+        .query_context
+        .execute(&query)
+        .await?
+        .try_collect()
+        .await?;
+
+    for batch in batches {
+        if batch.num_rows() == 0 {
+            continue;
+        }
+        let locked_by_id = as_large_string_array(batch.column(0))?;
+        let handler = as_large_string_array(batch.column(1))?;
+        let status = as_large_string_array(batch.column(2))?;
+        let locked_since = as_timestamp_millisecond_array(batch.column(3))?;
+        let retry_count = as_uint64_array(batch.column(4))?;
+        let last_failure = as_large_string_array(batch.column(5))?;
+        let queue_length = as_int64_array(batch.column(6))?;
+
+        let string_at = |array: &datafusion::arrow::array::LargeStringArray| {
+            (!array.is_null(0)).then(|| array.value(0).to_owned())
+        };
+
+        return Ok(Json(VirtualObjectLockResponse {
+            locked_by: Some(LockHolder {
+                invocation_id: locked_by_id.value(0).parse()?,
+                handler: string_at(handler),
+                status: string_at(status),
+                locked_since: (!locked_since.is_null(0)).then(|| {
+                    SystemTime::from(MillisSinceEpoch::new(locked_since.value(0) as u64)).into()
+                }),
+                retry_count: (!retry_count.is_null(0)).then(|| retry_count.value(0)),
+                last_failure: string_at(last_failure),
+            }),
+            queue_length: queue_length.value(0) as u64,
+        }));
+    }
+
+    // Invocations are queued only while the key is locked
+    Ok(Json(VirtualObjectLockResponse {
+        locked_by: None,
+        queue_length: 0,
+    }))
+}
@@ -9,9 +9,15 @@
 // by the Apache License, Version 2.0.
 
 mod error;
+mod lock;
+mod partitions;
 mod query;
+mod retention;
+mod rollout;
+mod search;
 
-use axum::{Router, routing::post};
+use axum::Router;
+use axum::routing::{get, post};
 use std::sync::Arc;
 
 use restate_storage_query_datafusion::context::QueryContext;
@@ -27,5 +33,16 @@ pub fn router(query_context: QueryContext) -> Router {
     // Setup the router
     axum::Router::new()
         .route("/query", post(query::query))
+        .route("/partitions", get(partitions::list_partitions))
+        .route("/retention/preview", get(retention::preview))
+        .route(
+            "/deployments/{deployment}/rollout",
+            get(rollout::rollout_status),
+        )
+        .route("/invocations:search", get(search::search_invocations))
+        .route(
+            "/services/{service}/keys/{key}/lock",
+            get(lock::virtual_object_lock),
+        )
         .with_state(query_state)
 }
@@ -0,0 +1,47 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http;
+use axum::response::{IntoResponse, Response};
+use futures::TryStreamExt;
+use http_body::Frame;
+use http_body_util::StreamBody;
+
+use crate::query_utils::WriteRecordBatchStream;
+
+use super::QueryServiceState;
+use super::error::StorageQueryError;
+use super::query::JsonWriter;
+
+const LIST_PARTITIONS_QUERY: &str = "SELECT \
+    partition_id, plain_node_id, effective_mode, replay_status, applied_log_lsn, target_tail_lsn, \
+    last_record_applied_at, applied_commands_per_second, apply_lag, oldest_unapplied_command_age \
+    FROM partition_state ORDER BY partition_id, plain_node_id";
+
+/// List the partition processors of the cluster, including how far behind the log tail they are.
+pub async fn list_partitions(
+    State(state): State<Arc<QueryServiceState>>,
+) -> Result<impl IntoResponse, StorageQueryError> {
+    let record_batch_stream = state.query_context.execute(LIST_PARTITIONS_QUERY).await?;
+
+    let result_stream = WriteRecordBatchStream::<JsonWriter>::new(
+        record_batch_stream,
+        LIST_PARTITIONS_QUERY.to_owned(),
+    )?
+    .map_ok(Frame::data);
+
+    Ok(Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(StreamBody::new(result_stream))
+        .expect("content-type header is correct"))
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http;
+use axum::response::{IntoResponse, Response};
+use futures::TryStreamExt;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use serde::Deserialize;
+
+use crate::query_utils::WriteRecordBatchStream;
+
+use super::QueryServiceState;
+use super::error::StorageQueryError;
+use super::query::JsonWriter;
+
+const DEFAULT_LIMIT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct RetentionPreviewParams {
+    /// Only the invocations of this service
+    service: Option<String>,
+    limit: Option<usize>,
+}
+
+/// Dry-run of the cleanup of completed invocations: lists the invocations whose retention
+/// expired, and whether the whole invocation or only its journal will be purged.
+pub async fn preview(
+    State(state): State<Arc<QueryServiceState>>,
+    Query(params): Query<RetentionPreviewParams>,
+) -> Result<impl IntoResponse, StorageQueryError> {
+    let query = preview_query(&params);
+    let record_batch_stream = state.query_context.execute(&query).await?;
+
+    let result_stream =
+        WriteRecordBatchStream::<JsonWriter>::new(record_batch_stream, query)?.map_ok(Frame::data);
+
+    Ok(Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(StreamBody::new(result_stream))
+        .expect("content-type header is correct"))
+}
+
+fn preview_query(params: &RetentionPreviewParams) -> String {
+    let service_filter = params
+        .service
+        .as_ref()
+        .map(|service| {
+            format!(
+                " AND target_service_name = '{}'",
+                service.replace('\'', "''")
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        "SELECT id, target, completed_at, completion_retention, journal_retention, \
+        CASE WHEN completed_at + completion_retention <= now() THEN 'invocation' ELSE 'journal' END AS purge \
+        FROM sys_invocation_status \
+        WHERE status = 'completed' AND completed_at IS NOT NULL \
+        AND (completed_at + completion_retention <= now() \
+        OR (journal_size > 0 AND completed_at + journal_retention <= now())){service_filter} \
+        ORDER BY completed_at LIMIT {}",
+        params.limit.unwrap_or(DEFAULT_LIMIT)
+    )
+}
@@ -0,0 +1,219 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use datafusion::arrow::array::{Array, RecordBatch};
+use datafusion::common::cast::{as_int64_array, as_large_string_array};
+use futures::TryStreamExt;
+use serde::Deserialize;
+
+use restate_admin_rest_model::deployments::{
+    DrainingDeployment, RolloutStatus, RolloutStatusResponse,
+};
+use restate_core::Metadata;
+use restate_time_util::FriendlyDuration;
+use restate_types::identifiers::DeploymentId;
+use restate_types::schema::deployment::{Deployment, DeploymentResolver};
+use restate_types::time::MillisSinceEpoch;
+
+use super::QueryServiceState;
+use super::error::StorageQueryError;
+
+#[derive(Debug, Deserialize)]
+pub struct RolloutStatusParams {
+    /// Time since the registration of the deployment after which the rollout is reported as
+    /// exceeding its deadline, if invocations are still pinned to the previous deployments.
+    #[serde(default, with = "serde_with::As::<Option<FriendlyDuration>>")]
+    drain_timeout: Option<Duration>,
+}
+
+/// Reports whether the in-flight invocations pinned to the deployments superseded by the given
+/// one completed, so that deployment automation can wait for them before decommissioning the
+/// previous deployments.
+pub async fn rollout_status(
+    State(state): State<Arc<QueryServiceState>>,
+    Path(deployment_id): Path<DeploymentId>,
+    Query(params): Query<RolloutStatusParams>,
+) -> Result<Json<RolloutStatusResponse>, StorageQueryError> {
+    let schema = Metadata::with_current(|m| m.schema_snapshot());
+    let mut deployments = schema.get_deployments();
+    let Some((deployment, services)) = deployments
+        .iter()
+        .find(|(deployment, _)| deployment.id == deployment_id)
+        .cloned()
+    else {
+        return Err(StorageQueryError::DeploymentNotFound(deployment_id));
+    };
+    deployments.retain(|(previous, previous_services)| {
+        previous.created_at < deployment.created_at
+            && previous_services
+                .iter()
+                .any(|(service, _)| services.iter().any(|(name, _)| name == service))
+    });
+    let pinned_invocations = if deployments.is_empty() {
+        HashMap::new()
+    } else {
+        count_pinned_invocations(
+            &state,
+            deployments.iter().map(|(previous, _)| previous),
+            services.iter().map(|(name, _)| name.as_str()),
+        )
+        .await?
+    };
+
+    let mut draining_deployments: Vec<_> = pinned_invocations
+        .into_iter()
+        .map(|(id, pinned_invocations)| DrainingDeployment {
+            id,
+            pinned_invocations,
+        })
+        .collect();
+    draining_deployments.sort_by_key(|draining| {
+        deployments
+            .iter()
+            .find(|(previous, _)| previous.id == draining.id)
+            .map(|(previous, _)| previous.created_at)
+    });
+
+    Ok(Json(RolloutStatusResponse {
+        id: deployment_id,
+        status: rollout_status_of(
+            &deployment,
+            &draining_deployments,
+            params.drain_timeout,
+            MillisSinceEpoch::now(),
+        ),
+        draining_deployments,
+    }))
+}
+
+fn rollout_status_of(
+    deployment: &Deployment,
+    draining_deployments: &[DrainingDeployment],
+    drain_timeout: Option<Duration>,
+    now: MillisSinceEpoch,
+) -> RolloutStatus {
+    if draining_deployments.is_empty() {
+        RolloutStatus::Drained
+    } else if drain_timeout.is_some_and(|drain_timeout| {
+        now.as_u64() >= deployment.created_at.as_u64() + drain_timeout.as_millis() as u64
+    }) {
+        RolloutStatus::DeadlineExceeded
+    } else {
+        RolloutStatus::Draining
+    }
+}
+
+/// Counts the in-flight invocations of the given services pinned to the given deployments.
+async fn count_pinned_invocations<'a>(
+    state: &QueryServiceState,
+    deployments: impl Iterator<Item = &'a Deployment>,
+    services: impl Iterator<Item = &'a str>,
+) -> Result<HashMap<DeploymentId, u64>, StorageQueryError> {
+    let quoted_list = |values: Vec<String>| {
+        values
+            .into_iter()
+            .map(|value| format!("'{}'", value.replace('\'', "''")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let query = format!(
+        "SELECT pinned_deployment_id, COUNT(*) AS pinned_invocations \
+        FROM sys_invocation_status \
+        WHERE status != 'completed' AND pinned_deployment_id IN ({}) \
+        AND target_service_name IN ({}) \
+        GROUP BY pinned_deployment_id",
+        quoted_list(
+            deployments
+                .map(|deployment| deployment.id.to_string())
+                .collect()
+        ),
+        quoted_list(services.map(str::to_owned).collect()),
+    );
+
+    let batches: Vec<RecordBatch> = state
+        .query_context
+        .execute(&query)
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut pinned_invocations = HashMap::new();
+    for batch in batches {
+        let deployment_ids = as_large_string_array(batch.column(0))?;
+        let counts = as_int64_array(batch.column(1))?;
+        for i in 0..batch.num_rows() {
+            if deployment_ids.is_null(i) {
+                continue;
+            }
+            let Ok(deployment_id) = deployment_ids.value(i).parse() else {
+                continue;
+            };
+            pinned_invocations.insert(deployment_id, counts.value(i) as u64);
+        }
+    }
+    Ok(pinned_invocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draining() -> Vec<DrainingDeployment> {
+        vec![DrainingDeployment {
+            id: DeploymentId::new(),
+            pinned_invocations: 1,
+        }]
+    }
+
+    #[test]
+    fn rollout_status_with_drain_timeout() {
+        let mut deployment = Deployment::mock();
+        deployment.created_at = MillisSinceEpoch::new(1_000);
+        let drain_timeout = Some(Duration::from_secs(1));
+
+        assert_eq!(
+            rollout_status_of(
+                &deployment,
+                &[],
+                drain_timeout,
+                MillisSinceEpoch::new(5_000)
+            ),
+            RolloutStatus::Drained
+        );
+        assert_eq!(
+            rollout_status_of(&deployment, &draining(), None, MillisSinceEpoch::new(5_000)),
+            RolloutStatus::Draining
+        );
+        assert_eq!(
+            rollout_status_of(
+                &deployment,
+                &draining(),
+                drain_timeout,
+                MillisSinceEpoch::new(1_999)
+            ),
+            RolloutStatus::Draining
+        );
+        assert_eq!(
+            rollout_status_of(
+                &deployment,
+                &draining(),
+                drain_timeout,
+                MillisSinceEpoch::new(2_000)
+            ),
+            RolloutStatus::DeadlineExceeded
+        );
+    }
+}
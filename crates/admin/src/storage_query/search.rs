@@ -0,0 +1,76 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http;
+use axum::response::{IntoResponse, Response};
+use futures::TryStreamExt;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use serde::Deserialize;
+
+use crate::query_utils::WriteRecordBatchStream;
+
+use super::QueryServiceState;
+use super::error::StorageQueryError;
+use super::query::JsonWriter;
+
+const DEFAULT_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    /// Case-insensitive text to look for
+    q: String,
+    limit: Option<usize>,
+}
+
+/// Search the invocations whose service name, handler name or key contain the given text. The most
+/// recent invocations are returned first.
+///
+/// There is no index backing the search: it scans `sys_invocation_status` on every partition, so
+/// its cost grows with the number of retained invocations.
+pub async fn search_invocations(
+    State(state): State<Arc<QueryServiceState>>,
+    Query(params): Query<SearchParams>,
+) -> Result<impl IntoResponse, StorageQueryError> {
+    let text = params.q.trim();
+    if text.is_empty() {
+        return Err(StorageQueryError::EmptySearchText);
+    }
+
+    let query = search_query(text, params.limit.unwrap_or(DEFAULT_LIMIT));
+    let record_batch_stream = state.query_context.execute(&query).await?;
+
+    let result_stream =
+        WriteRecordBatchStream::<JsonWriter>::new(record_batch_stream, query)?.map_ok(Frame::data);
+
+    Ok(Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(StreamBody::new(result_stream))
+        .expect("content-type header is correct"))
+}
+
+fn search_query(text: &str, limit: usize) -> String {
+    // strpos rather than LIKE, so that % and _ in the text don't need escaping
+    let text = text.to_lowercase().replace('\'', "''");
+    let contains = |column: &str| format!("strpos(lower({column}), '{text}') > 0");
+
+    format!(
+        "SELECT id, target, target_service_key, status, created_at, modified_at \
+        FROM sys_invocation_status \
+        WHERE {} OR {} OR {} \
+        ORDER BY created_at DESC LIMIT {limit}",
+        contains("target_service_name"),
+        contains("target_handler_name"),
+        contains("target_service_key"),
+    )
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! API to pause the firing of a kind of timers, see [`restate_types::timer::TimerKind`].
+//!
+//! The paused kinds apply only to the partition leaders running on the node which received them,
+//! and they're reset to `worker.paused-timer-kinds` on restart.
+//!
+//! The timer services of the partition leaders running on this node can be inspected as well:
+//! `GET /timers/partitions` returns their statistics, and
+//! `GET /timers/partitions/{partition_id}/pending` lists the timers they loaded in memory.
+
+use axum::extract::{Path, Query};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use enumset::EnumSet;
+use http::StatusCode;
+use serde::Deserialize;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use restate_types::identifiers::PartitionId;
+use restate_types::timer::{
+    self, PendingTimer, TimerKind, TimerKindStats, TimerServiceRequest, TimerServiceStats,
+};
+
+const DEFAULT_PENDING_TIMERS_LIMIT: usize = 100;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/timers", get(get_timers))
+        .route(
+            "/timers/paused",
+            put(put_paused_timer_kinds).delete(delete_paused_timer_kinds),
+        )
+        .route("/timers/partitions", get(get_timer_services))
+        .route(
+            "/timers/partitions/{partition_id}/pending",
+            get(get_pending_timers),
+        )
+}
+
+/// Gets the statistics of the timers fired by this node, by kind
+async fn get_timers() -> Json<Vec<TimerKindStats>> {
+    Json(timer_stats())
+}
+
+/// Replaces the kinds of timers whose firing is paused on this node
+async fn put_paused_timer_kinds(
+    Json(paused_timer_kinds): Json<EnumSet<TimerKind>>,
+) -> Json<Vec<TimerKindStats>> {
+    warn!(
+        "Pausing firing of the {:?} timers",
+        paused_timer_kinds.iter().collect::<Vec<_>>()
+    );
+    timer::set_paused_timer_kinds(paused_timer_kinds);
+    Json(timer_stats())
+}
+
+/// Resumes the firing of all kinds of timers on this node
+async fn delete_paused_timer_kinds() -> StatusCode {
+    timer::set_paused_timer_kinds(EnumSet::empty());
+    info!("Resumed firing of all the timers");
+    StatusCode::NO_CONTENT
+}
+
+fn timer_stats() -> Vec<TimerKindStats> {
+    EnumSet::<TimerKind>::all()
+        .iter()
+        .map(TimerKind::stats)
+        .collect()
+}
+
+/// Gets the statistics of the timer services of the partitions led by this node
+async fn get_timer_services() -> Json<Vec<TimerServiceStats>> {
+    let mut stats = Vec::new();
+    for (partition_id, timer_service) in timer::timer_services() {
+        let (tx, rx) = oneshot::channel();
+        // the leader might have stepped down in the meantime
+        if timer_service
+            .send(TimerServiceRequest::Stats(tx))
+            .await
+            .is_err()
+        {
+            continue;
+        }
+        match rx.await {
+            Ok(partition_stats) => stats.push(partition_stats),
+            Err(_) => info!(%partition_id, "Partition leader stepped down, skipping its timers"),
+        }
+    }
+    Json(stats)
+}
+
+#[derive(Debug, Deserialize)]
+struct PendingTimersParams {
+    /// Maximum number of timers to return, 100 by default
+    limit: Option<usize>,
+}
+
+/// Lists the timers loaded in memory by the leader of the given partition, in the order they fire
+async fn get_pending_timers(
+    Path(partition_id): Path<u16>,
+    Query(params): Query<PendingTimersParams>,
+) -> Response {
+    let partition_id = PartitionId::from(partition_id);
+    let not_led_here = || {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Partition {partition_id} is not led by this node"),
+        )
+            .into_response()
+    };
+
+    let Some((_, timer_service)) = timer::timer_services()
+        .into_iter()
+        .find(|(id, _)| *id == partition_id)
+    else {
+        return not_led_here();
+    };
+
+    let (tx, rx) = oneshot::channel();
+    let request = TimerServiceRequest::ListPendingTimers {
+        limit: params.limit.unwrap_or(DEFAULT_PENDING_TIMERS_LIMIT),
+        reply: tx,
+    };
+    if timer_service.send(request).await.is_err() {
+        return not_led_here();
+    }
+    match rx.await {
+        Ok(pending_timers) => Json::<Vec<PendingTimer>>(pending_timers).into_response(),
+        Err(_) => not_led_here(),
+    }
+}
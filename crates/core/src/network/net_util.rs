@@ -15,6 +15,7 @@ use std::time::Duration;
 
 use http::Uri;
 use hyper::body::{Body, Incoming};
+use hyper::service::Service as _;
 use hyper_util::rt::TokioIo;
 use hyper_util::server::graceful::GracefulShutdown;
 use tokio::io;
@@ -23,6 +24,7 @@ use tokio_util::either::Either;
 use tonic::transport::{Channel, Endpoint};
 use tracing::{Instrument, Span, debug, error_span, info, instrument, trace};
 
+use restate_http_auth::TlsAcceptor;
 use restate_types::config::Configuration;
 use restate_types::errors::GenericError;
 use restate_types::net::address::{AdvertisedAddress, GrpcPort};
@@ -98,6 +100,7 @@ pub enum Error {
 )]
 pub async fn run_hyper_server<P: ListenerPort, S, B>(
     listeners: Listeners<P>,
+    tls_acceptor: Option<TlsAcceptor>,
     service: S,
     on_stop: impl Fn(),
 ) -> Result<(), Error>
@@ -122,7 +125,7 @@ where
     }
 
     info!("Server listening");
-    run_listener_loop(listeners, service, P::NAME).await?;
+    run_listener_loop(listeners, tls_acceptor, service, P::NAME).await?;
     on_stop();
 
     info!("Stopped listening");
@@ -132,6 +135,7 @@ where
 
 async fn run_listener_loop<P: ListenerPort, S, B>(
     mut listeners: Listeners<P>,
+    tls_acceptor: Option<TlsAcceptor>,
     service: S,
     server_name: &'static str,
 ) -> Result<(), Error>
@@ -177,22 +181,36 @@ where
                 match stream {
                     Either::Left(tcp_stream) => {
                         // TCP SOCKET
-                        let io = TokioIo::new(tcp_stream);
-                        let connection = graceful_shutdown.watch(builder
-                            .serve_connection(io, service.clone()).into_owned());
+                        let service = service.clone();
+                        let tls_acceptor = tls_acceptor.clone();
+                        let watcher = graceful_shutdown.watcher();
                         TaskCenter::spawn(TaskKind::SocketHandler, task_name.clone(), async move {
                             trace!("New tcp connection accepted");
-                            if let Err(e) = connection.await {
-                                if let Some(hyper_error) = e.downcast_ref::<hyper::Error>() {
-                                    if hyper_error.is_incomplete_message() {
-                                        debug!("Connection closed before request completed");
-                                    }
-                                } else {
-                                    debug!("Connection terminated due to error: {e}");
+                            let result = match tls_acceptor {
+                                None => {
+                                    let io = TokioIo::new(tcp_stream);
+                                    watcher.watch(builder.serve_connection(io, service).into_owned()).await
+                                }
+                                Some(tls_acceptor) => {
+                                    let (tls_stream, client_certificate) = match tls_acceptor.accept(tcp_stream).await {
+                                        Ok(accepted) => accepted,
+                                        Err(e) => {
+                                            debug!("TLS handshake failed: {e}");
+                                            return Ok(());
+                                        }
+                                    };
+                                    // Let the authentication layer know about the verified client
+                                    let service = hyper::service::service_fn(move |mut request: http::Request<Incoming>| {
+                                        if let Some(client_certificate) = &client_certificate {
+                                            request.extensions_mut().insert(client_certificate.clone());
+                                        }
+                                        service.call(request)
+                                    });
+                                    let io = TokioIo::new(tls_stream);
+                                    watcher.watch(builder.serve_connection(io, service).into_owned()).await
                                 }
-                            } else {
-                                trace!("Connection completed cleanly");
-                            }
+                            };
+                            log_connection_result(result);
                             Ok(())
                         }.instrument(socket_span))?;
 
@@ -204,17 +222,7 @@ where
                             .serve_connection(io, service.clone()).into_owned());
                         TaskCenter::spawn(TaskKind::SocketHandler, task_name.clone(), async move {
                             trace!("New uds connection accepted");
-                            if let Err(e) = connection.await {
-                                if let Some(hyper_error) = e.downcast_ref::<hyper::Error>() {
-                                    if hyper_error.is_incomplete_message() {
-                                        debug!("Connection closed before request completed");
-                                    }
-                                } else {
-                                    debug!("Connection terminated due to error: {e}");
-                                }
-                            } else {
-                                trace!("Connection completed cleanly");
-                            }
+                            log_connection_result(connection.await);
                             Ok(())
                         }.instrument(socket_span))?;
                     }
@@ -237,6 +245,20 @@ where
     Ok(())
 }
 
+fn log_connection_result(result: Result<(), Box<dyn std::error::Error + Send + Sync>>) {
+    if let Err(e) = result {
+        if let Some(hyper_error) = e.downcast_ref::<hyper::Error>() {
+            if hyper_error.is_incomplete_message() {
+                debug!("Connection closed before request completed");
+            }
+        } else {
+            debug!("Connection terminated due to error: {e}");
+        }
+    } else {
+        trace!("Connection completed cleanly");
+    }
+}
+
 #[derive(Clone, Default)]
 struct TaskCenterExecutor;
 
@@ -115,7 +115,7 @@ impl NetworkServerBuilder {
 
         node_rpc_health.update(NodeRpcStatus::Ready);
 
-        run_hyper_server(self.listeners, service, || {
+        run_hyper_server(self.listeners, None, service, || {
             node_rpc_health.update(NodeRpcStatus::Stopping)
         })
         .await?;
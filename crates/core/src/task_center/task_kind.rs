@@ -119,6 +119,9 @@ pub enum TaskKind {
     Shuffle,
     Cleaner,
     LogTrimmer,
+    /// Delivers the completion events of a partition leader to the completion Kafka sink
+    #[strum(props(OnError = "log"))]
+    CompletionSink,
     MetadataServer,
     Background,
     // -- Bifrost Tasks
@@ -15,13 +15,14 @@ use crate::network::{Networking, TransportConnect};
 use crate::partitions::PartitionRouting;
 use assert2::let_assert;
 use restate_types::NodeId;
+use restate_types::errors::ErrorClass;
 use restate_types::identifiers::{
     EntryIndex, InvocationId, PartitionId, PartitionProcessorRpcRequestId, WithPartitionKey,
 };
 use restate_types::invocation::client::{
-    AttachInvocationResponse, CancelInvocationResponse, GetInvocationOutputResponse,
-    InvocationClient, InvocationClientError, InvocationOutput, KillInvocationResponse,
-    PatchDeploymentId, PauseInvocationResponse, PurgeInvocationResponse,
+    AttachInvocationResponse, CancelInvocationResponse, ConsistencyToken,
+    GetInvocationOutputResponse, InvocationClient, InvocationClientError, InvocationOutput,
+    KillInvocationResponse, PatchDeploymentId, PauseInvocationResponse, PurgeInvocationResponse,
     RestartAsNewInvocationResponse, ResumeInvocationResponse, SubmittedInvocationNotification,
 };
 use restate_types::invocation::{InvocationQuery, InvocationRequest, InvocationResponse};
@@ -78,17 +79,16 @@ pub enum RpcErrorKind {
 }
 
 impl PartitionProcessorInvocationClientError {
-    /// Returns true when the operation can be retried assuming no state mutation could have occurred in the PartitionProcessor.
-    pub fn is_safe_to_retry(&self) -> bool {
+    pub fn error_class(&self) -> ErrorClass {
         match self {
             PartitionProcessorInvocationClientError::UnknownPartition(_)
             | PartitionProcessorInvocationClientError::UnknownNode(_) => {
                 // These are pre-flight error that we can distinguish,
                 // and for which we know for certain that no message was proposed yet to the log.
-                true
+                ErrorClass::Transient
             }
-            PartitionProcessorInvocationClientError::Rpc(rpc) => rpc.is_safe_to_retry(),
-            _ => false,
+            PartitionProcessorInvocationClientError::Shutdown(_) => ErrorClass::TerminalSystem,
+            PartitionProcessorInvocationClientError::Rpc(rpc) => rpc.error_class(),
         }
     }
 }
@@ -102,7 +102,7 @@ impl RpcError {
         }
     }
 
-    fn is_safe_to_retry(&self) -> bool {
+    fn error_class(&self) -> ErrorClass {
         match self.source {
             RpcErrorKind::Connect(_)
             | RpcErrorKind::NotLeader
@@ -112,17 +112,20 @@ impl RpcError {
             | RpcErrorKind::Stopping => {
                 // These are pre-flight error that we can distinguish,
                 // and for which we know for certain that no message was proposed yet to the log.
-                true
+                ErrorClass::Transient
+            }
+            // The request might have been proposed to the log already
+            RpcErrorKind::LostLeadership | RpcErrorKind::Internal(_) => {
+                ErrorClass::RetryableWithBackoff
             }
-            _ => false,
         }
     }
 }
 
 impl From<PartitionProcessorInvocationClientError> for InvocationClientError {
     fn from(value: PartitionProcessorInvocationClientError) -> Self {
-        let is_safe_to_retry = value.is_safe_to_retry();
-        Self::new(value, is_safe_to_retry)
+        let class = value.error_class();
+        Self::new(value, class)
     }
 }
 
@@ -198,11 +201,27 @@ where
         &self,
         request_id: PartitionProcessorRpcRequestId,
         inner_request: PartitionProcessorRpcRequestInner,
+    ) -> Result<PartitionProcessorRpcResponse, PartitionProcessorInvocationClientError> {
+        self.resolve_partition_id_and_send_consistent(request_id, inner_request, None)
+            .await
+    }
+
+    /// Like [`Self::resolve_partition_id_and_send`], but the request is served only once the
+    /// partition processor caught up with the given consistency token.
+    async fn resolve_partition_id_and_send_consistent(
+        &self,
+        request_id: PartitionProcessorRpcRequestId,
+        inner_request: PartitionProcessorRpcRequestInner,
+        consistency_token: Option<ConsistencyToken>,
     ) -> Result<PartitionProcessorRpcResponse, PartitionProcessorInvocationClientError> {
         let partition_id = self
             .partition_table
             .pinned()
             .find_partition_id(inner_request.partition_key())?;
+        // Tokens of other partitions are meaningless for this partition log
+        let min_applied_lsn = consistency_token
+            .filter(|token| token.partition_id == partition_id)
+            .map(|token| token.applied_lsn);
 
         let node_id = NodeId::from(
             self.partition_routing
@@ -228,6 +247,7 @@ where
                     request_id,
                     partition_id,
                     inner: inner_request,
+                    min_applied_lsn,
                 },
                 Some(*partition_id as u64),
             )
@@ -310,14 +330,16 @@ where
         &self,
         request_id: PartitionProcessorRpcRequestId,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> Result<AttachInvocationResponse, InvocationClientError> {
         let response = self
-            .resolve_partition_id_and_send(
+            .resolve_partition_id_and_send_consistent(
                 request_id,
                 PartitionProcessorRpcRequestInner::GetInvocationOutput(
                     invocation_query,
                     GetInvocationOutputResponseMode::BlockWhenNotReady,
                 ),
+                consistency_token,
             )
             .await?;
 
@@ -338,14 +360,16 @@ where
         &self,
         request_id: PartitionProcessorRpcRequestId,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> Result<GetInvocationOutputResponse, InvocationClientError> {
         let response = self
-            .resolve_partition_id_and_send(
+            .resolve_partition_id_and_send_consistent(
                 request_id,
                 PartitionProcessorRpcRequestInner::GetInvocationOutput(
                     invocation_query,
                     GetInvocationOutputResponseMode::ReplyIfNotReady,
                 ),
+                consistency_token,
             )
             .await?;
 
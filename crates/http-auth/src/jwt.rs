@@ -0,0 +1,178 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use restate_types::config::JwtOptions;
+
+use crate::{AuthError, BuildError, Principal};
+
+const JWKS_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tokens signed by unknown keys can't trigger fetches more often than this.
+const MIN_JWKS_FETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: Option<String>,
+}
+
+/// Validates the JWTs with the keys of the configured JWKS URL, fetched lazily and refreshed
+/// periodically.
+pub(crate) struct JwtValidator {
+    options: JwtOptions,
+    client: reqwest::Client,
+    key_set: RwLock<Option<Arc<KeySet>>>,
+    /// Serializes the fetches, so that a burst of requests fetches the key set once
+    fetch_lock: tokio::sync::Mutex<()>,
+}
+
+struct KeySet {
+    keys: HashMap<String, DecodingKey>,
+    fetched_at: Instant,
+}
+
+impl JwtValidator {
+    pub(crate) fn new(options: JwtOptions) -> Result<Self, BuildError> {
+        Ok(Self {
+            options,
+            client: reqwest::Client::builder()
+                .timeout(JWKS_FETCH_TIMEOUT)
+                .build()?,
+            key_set: RwLock::new(None),
+            fetch_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub(crate) async fn validate(&self, token: &str) -> Result<Principal, AuthError> {
+        let header = jsonwebtoken::decode_header(token)?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            // The key set publishes public keys only
+            return Err(AuthError::UnsupportedAlgorithm(header.alg));
+        }
+        let key_id = header.kid.ok_or(AuthError::MissingKeyId)?;
+        let key = self.decoding_key(&key_id).await?;
+
+        let mut validation = Validation::new(header.alg);
+        if let Some(issuer) = &self.options.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        validation.set_audience(&[&self.options.audience]);
+
+        let claims = jsonwebtoken::decode::<Claims>(token, &key, &validation)?.claims;
+        Ok(Principal::Jwt {
+            subject: claims.sub,
+        })
+    }
+
+    async fn decoding_key(&self, key_id: &str) -> Result<DecodingKey, AuthError> {
+        let current = self.key_set.read().clone();
+        if let Some(key_set) = &current {
+            let age = key_set.fetched_at.elapsed();
+            if age < self.options.jwks_refresh_interval.to_std() {
+                match key_set.keys.get(key_id) {
+                    Some(key) => return Ok(key.clone()),
+                    None if age < MIN_JWKS_FETCH_INTERVAL => {
+                        return Err(AuthError::UnknownKey(key_id.to_owned()));
+                    }
+                    // The keys might have been rotated
+                    None => {}
+                }
+            }
+        }
+
+        let key_set = self
+            .refresh(current.map(|key_set| key_set.fetched_at))
+            .await?;
+        key_set
+            .keys
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| AuthError::UnknownKey(key_id.to_owned()))
+    }
+
+    /// Fetches the key set, unless it changed since the given fetch time.
+    async fn refresh(&self, stale_fetched_at: Option<Instant>) -> Result<Arc<KeySet>, AuthError> {
+        let _guard = self.fetch_lock.lock().await;
+        let current = self.key_set.read().clone();
+        if let Some(key_set) = &current
+            && Some(key_set.fetched_at) != stale_fetched_at
+        {
+            // Fetched by a concurrent request
+            return Ok(Arc::clone(key_set));
+        }
+
+        match self.fetch().await {
+            Ok(keys) => {
+                debug!("Fetched {} keys from {}", keys.len(), self.options.jwks_url);
+                let key_set = Arc::new(KeySet {
+                    keys,
+                    fetched_at: Instant::now(),
+                });
+                *self.key_set.write() = Some(Arc::clone(&key_set));
+                Ok(key_set)
+            }
+            Err(err) => match current {
+                Some(key_set) => {
+                    // Keep using the keys fetched before, rather than failing every request
+                    // while the identity provider is unavailable
+                    warn!(
+                        "Failed fetching the keys from {}, using the previous ones: {err}",
+                        self.options.jwks_url
+                    );
+                    let key_set = Arc::new(KeySet {
+                        keys: key_set.keys.clone(),
+                        fetched_at: Instant::now(),
+                    });
+                    *self.key_set.write() = Some(Arc::clone(&key_set));
+                    Ok(key_set)
+                }
+                None => Err(AuthError::KeySetUnavailable(err)),
+            },
+        }
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, DecodingKey>, String> {
+        let response = self
+            .client
+            .get(self.options.jwks_url.to_string())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| err.to_string())?;
+        let body = response.bytes().await.map_err(|err| err.to_string())?;
+        let key_set: JwkSet = serde_json::from_slice(&body).map_err(|err| err.to_string())?;
+
+        Ok(key_set
+            .keys
+            .iter()
+            .filter_map(|jwk| {
+                let key_id = jwk.common.key_id.clone()?;
+                match DecodingKey::from_jwk(jwk) {
+                    Ok(key) => Some((key_id, key)),
+                    Err(err) => {
+                        debug!("Ignoring the unsupported key '{key_id}': {err}");
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+}
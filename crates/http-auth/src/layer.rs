@@ -0,0 +1,225 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use http::{HeaderValue, Request, Response, StatusCode, header};
+use serde::Serialize;
+use tracing::{debug, trace};
+
+use crate::{AuthError, Authenticator, ClientCertificate};
+
+/// Rejects the requests without valid credentials with `401 Unauthorized`, or with
+/// `503 Service Unavailable` if the keys verifying them can't be fetched.
+///
+/// The header carrying the credentials is removed from the authenticated requests, so that it's
+/// not forwarded to the services, and the [`Principal`](crate::Principal) is added to their
+/// extensions.
+#[derive(Clone)]
+pub struct AuthLayer {
+    authenticator: Authenticator,
+    public_paths: &'static [&'static str],
+}
+
+impl AuthLayer {
+    /// The requests to the given paths don't need credentials, e.g. the health checks. A path
+    /// ending with `/` matches all the paths starting with it.
+    pub fn new(authenticator: Authenticator, public_paths: &'static [&'static str]) -> Self {
+        Self {
+            authenticator,
+            public_paths,
+        }
+    }
+}
+
+impl<S> tower::Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            authenticator: self.authenticator.clone(),
+            public_paths: self.public_paths,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    authenticator: Authenticator,
+    public_paths: &'static [&'static str],
+}
+
+impl<S> AuthService<S> {
+    fn is_public(&self, path: &str) -> bool {
+        self.public_paths.iter().any(|public_path| {
+            if public_path.ends_with('/') {
+                path.starts_with(public_path)
+            } else {
+                path == *public_path
+            }
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+impl<S, ReqBody, ResBody> tower::Service<Request<ReqBody>> for AuthService<S>
+where
+    S: tower::Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: From<Bytes>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        if !self.authenticator.requires_credentials() || self.is_public(req.uri().path()) {
+            return self.inner.call(req).boxed();
+        }
+
+        // Take the service which was driven to readiness
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let authenticator = self.authenticator.clone();
+        async move {
+            let client_certificate = req.extensions().get::<ClientCertificate>().cloned();
+            match authenticator
+                .authenticate(req.headers(), client_certificate.as_ref())
+                .await
+            {
+                Ok((principal, credentials_header)) => {
+                    trace!("Authenticated the request of {principal}");
+                    if let Some(credentials_header) = credentials_header {
+                        req.headers_mut().remove(credentials_header);
+                    }
+                    req.extensions_mut().insert(principal);
+                    inner.call(req).await
+                }
+                Err(err) => {
+                    debug!("Rejecting unauthenticated request: {err}");
+                    Ok(error_response(err))
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+fn error_response<B: From<Bytes>>(err: AuthError) -> Response<B> {
+    let status_code = match err {
+        AuthError::KeySetUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::UNAUTHORIZED,
+    };
+    let body = serde_json::to_vec(&ErrorResponse {
+        message: err.to_string(),
+    })
+    .expect("error response must be serializable");
+
+    let mut response = Response::builder().status(status_code).header(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    if status_code == StatusCode::UNAUTHORIZED {
+        response = response.header(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    }
+    response.body(Bytes::from(body).into()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::Infallible;
+
+    use http_body_util::{BodyExt, Full};
+    use tower::{ServiceBuilder, ServiceExt};
+
+    use restate_types::config::{ApiKeyOptions, AuthenticationOptions};
+
+    use crate::{Principal, X_RESTATE_API_KEY};
+
+    async fn call(req: Request<()>) -> Response<Full<Bytes>> {
+        let authenticator = Authenticator::new(&AuthenticationOptions {
+            api_keys: vec![ApiKeyOptions {
+                name: "team-a".to_owned(),
+                key: "key-a".to_owned(),
+            }],
+            ..AuthenticationOptions::default()
+        })
+        .unwrap();
+
+        ServiceBuilder::new()
+            .layer(AuthLayer::new(authenticator, &["/health", "/public/"]))
+            .service_fn(|req: Request<()>| async move {
+                // Echo the principal, the API key must not be forwarded
+                assert!(!req.headers().contains_key(X_RESTATE_API_KEY));
+                let principal = req
+                    .extensions()
+                    .get::<Principal>()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                Ok::<_, Infallible>(Response::new(Full::new(Bytes::from(principal))))
+            })
+            .oneshot(req)
+            .await
+            .unwrap()
+    }
+
+    async fn body(response: Response<Full<Bytes>>) -> Bytes {
+        response.into_body().collect().await.unwrap().to_bytes()
+    }
+
+    #[tokio::test]
+    async fn authenticate_requests() {
+        let response = call(
+            Request::get("/services")
+                .header(X_RESTATE_API_KEY, "key-a")
+                .body(())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await, "API key 'team-a'");
+
+        let response = call(Request::get("/services").body(()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+    }
+
+    #[tokio::test]
+    async fn public_paths_need_no_credentials() {
+        for (path, status_code) in [
+            ("/health", StatusCode::OK),
+            ("/health/deep", StatusCode::UNAUTHORIZED),
+            ("/public/index.html", StatusCode::OK),
+            ("/public", StatusCode::UNAUTHORIZED),
+        ] {
+            let response = call(Request::get(path).body(()).unwrap()).await;
+            assert_eq!(response.status(), status_code, "{path}");
+        }
+    }
+}
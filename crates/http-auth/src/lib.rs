@@ -0,0 +1,261 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Authentication of the requests to the HTTP servers of Restate, the ingress and the Admin API,
+//! as configured by their [`AuthenticationOptions`]:
+//!
+//! * static API keys, in the `x-restate-api-key` header or as `Authorization: Bearer <key>`
+//! * JWTs, as `Authorization: Bearer <token>`, verified with the keys of a JWKS URL
+//! * client certificates, verified during the TLS handshake against the configured CAs
+//!
+//! The [`AuthLayer`] rejects the requests without valid credentials, while the [`TlsAcceptor`]
+//! terminates TLS on the connections, tagging their requests with the [`ClientCertificate`].
+
+mod jwt;
+mod layer;
+mod tls;
+
+use std::fmt;
+use std::sync::Arc;
+
+use http::{HeaderMap, HeaderName, HeaderValue, header};
+use sha2::{Digest, Sha256};
+
+use restate_types::config::AuthenticationOptions;
+
+use crate::jwt::JwtValidator;
+
+pub use layer::{AuthLayer, AuthService};
+pub use tls::{ClientCertificate, TlsAcceptor, TlsError};
+
+/// Carries the API key, as an alternative to the `Authorization` header.
+pub const X_RESTATE_API_KEY: HeaderName = HeaderName::from_static("x-restate-api-key");
+
+#[derive(Debug, thiserror::Error)]
+#[error("cannot create the JWKS client: {0}")]
+pub struct BuildError(#[from] reqwest::Error);
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error(
+        "missing credentials, pass an API key in the x-restate-api-key header or a token as 'Authorization: Bearer <token>'"
+    )]
+    MissingCredentials,
+    #[error("bad {0} header, expected a bearer token")]
+    BadHeader(HeaderName),
+    #[error("invalid API key")]
+    InvalidApiKey,
+    #[error("invalid token: {0}")]
+    InvalidToken(#[from] jsonwebtoken::errors::Error),
+    #[error("the token has no key id")]
+    MissingKeyId,
+    #[error("the token was signed by the unknown key '{0}'")]
+    UnknownKey(String),
+    #[error("the token signature algorithm {0:?} is not supported")]
+    UnsupportedAlgorithm(jsonwebtoken::Algorithm),
+    #[error("cannot fetch the keys verifying the token: {0}")]
+    KeySetUnavailable(String),
+}
+
+/// Identity of the authenticated client, added to the request extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Principal {
+    ApiKey { name: String },
+    Jwt { subject: Option<String> },
+    ClientCertificate(ClientCertificate),
+}
+
+impl fmt::Display for Principal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Principal::ApiKey { name } => write!(f, "API key '{name}'"),
+            Principal::Jwt {
+                subject: Some(subject),
+            } => write!(f, "JWT subject '{subject}'"),
+            Principal::Jwt { subject: None } => write!(f, "JWT without subject"),
+            Principal::ClientCertificate(certificate) => {
+                write!(f, "client certificate {}", certificate.fingerprint())
+            }
+        }
+    }
+}
+
+/// Validates the credentials of the requests, see the [crate docs](crate).
+#[derive(Clone)]
+pub struct Authenticator {
+    inner: Arc<AuthenticatorInner>,
+}
+
+struct AuthenticatorInner {
+    /// Names of the API keys, with the SHA-256 digest of the keys to compare them in constant
+    /// time with respect to the key contents.
+    api_keys: Vec<(String, [u8; 32])>,
+    jwt: Option<JwtValidator>,
+    requires_credentials: bool,
+}
+
+impl Authenticator {
+    pub fn new(options: &AuthenticationOptions) -> Result<Self, BuildError> {
+        Ok(Self {
+            inner: Arc::new(AuthenticatorInner {
+                api_keys: options
+                    .api_keys
+                    .iter()
+                    .map(|api_key| (api_key.name.clone(), digest(&api_key.key)))
+                    .collect(),
+                jwt: options.jwt.clone().map(JwtValidator::new).transpose()?,
+                requires_credentials: options.requires_credentials(),
+            }),
+        })
+    }
+
+    /// Whether the requests must carry credentials, otherwise [`Self::authenticate`] accepts
+    /// all of them.
+    pub fn requires_credentials(&self) -> bool {
+        self.inner.requires_credentials
+    }
+
+    /// Authenticates the request with the given headers, coming from a connection with the
+    /// given verified client certificate. Returns the principal and the header carrying the
+    /// credentials, if any.
+    pub async fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        client_certificate: Option<&ClientCertificate>,
+    ) -> Result<(Principal, Option<HeaderName>), AuthError> {
+        if let Some(client_certificate) = client_certificate {
+            return Ok((
+                Principal::ClientCertificate(client_certificate.clone()),
+                None,
+            ));
+        }
+
+        if let Some(api_key) = headers.get(X_RESTATE_API_KEY) {
+            let principal = self.authenticate_api_key(api_key.as_bytes())?;
+            return Ok((principal, Some(X_RESTATE_API_KEY)));
+        }
+
+        let Some(authorization) = headers.get(header::AUTHORIZATION) else {
+            return Err(AuthError::MissingCredentials);
+        };
+        let token = bearer_token(authorization)?;
+        let principal = match (self.authenticate_api_key(token.as_bytes()), &self.inner.jwt) {
+            (Ok(principal), _) => principal,
+            (Err(_), Some(jwt)) => jwt.validate(token).await?,
+            (Err(err), None) => return Err(err),
+        };
+        Ok((principal, Some(header::AUTHORIZATION)))
+    }
+
+    fn authenticate_api_key(&self, api_key: &[u8]) -> Result<Principal, AuthError> {
+        let api_key = digest(api_key);
+        self.inner
+            .api_keys
+            .iter()
+            .find(|(_, digest)| *digest == api_key)
+            .map(|(name, _)| Principal::ApiKey { name: name.clone() })
+            .ok_or(AuthError::InvalidApiKey)
+    }
+}
+
+fn digest(value: impl AsRef<[u8]>) -> [u8; 32] {
+    Sha256::digest(value).into()
+}
+
+fn bearer_token(authorization: &HeaderValue) -> Result<&str, AuthError> {
+    authorization
+        .to_str()
+        .ok()
+        .and_then(|value| {
+            let (scheme, token) = value.split_once(' ')?;
+            scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+        })
+        .ok_or(AuthError::BadHeader(header::AUTHORIZATION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_types::config::ApiKeyOptions;
+
+    fn authenticator() -> Authenticator {
+        Authenticator::new(&AuthenticationOptions {
+            api_keys: vec![ApiKeyOptions {
+                name: "team-a".to_owned(),
+                key: "key-a".to_owned(),
+            }],
+            ..AuthenticationOptions::default()
+        })
+        .unwrap()
+    }
+
+    fn headers(name: HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[tokio::test]
+    async fn authenticate_api_key() {
+        let authenticator = authenticator();
+        let team_a = Principal::ApiKey {
+            name: "team-a".to_owned(),
+        };
+
+        let (principal, header) = authenticator
+            .authenticate(&headers(X_RESTATE_API_KEY, "key-a"), None)
+            .await
+            .unwrap();
+        assert_eq!(principal, team_a);
+        assert_eq!(header, Some(X_RESTATE_API_KEY));
+
+        let (principal, header) = authenticator
+            .authenticate(&headers(header::AUTHORIZATION, "Bearer key-a"), None)
+            .await
+            .unwrap();
+        assert_eq!(principal, team_a);
+        assert_eq!(header, Some(header::AUTHORIZATION));
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_credentials() {
+        let authenticator = authenticator();
+
+        assert!(matches!(
+            authenticator.authenticate(&HeaderMap::new(), None).await,
+            Err(AuthError::MissingCredentials)
+        ));
+        assert!(matches!(
+            authenticator
+                .authenticate(&headers(X_RESTATE_API_KEY, "key-b"), None)
+                .await,
+            Err(AuthError::InvalidApiKey)
+        ));
+        assert!(matches!(
+            authenticator
+                .authenticate(&headers(header::AUTHORIZATION, "Basic a2V5LWE="), None)
+                .await,
+            Err(AuthError::BadHeader(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn client_certificate_takes_precedence() {
+        let certificate = ClientCertificate::from_der(b"certificate");
+
+        let (principal, header) = authenticator()
+            .authenticate(&headers(X_RESTATE_API_KEY, "key-b"), Some(&certificate))
+            .await
+            .unwrap();
+        assert_eq!(principal, Principal::ClientCertificate(certificate));
+        assert_eq!(header, None);
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::RootCertStore;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+
+use restate_types::config::TlsOptions;
+
+/// Clients failing to complete the handshake in time are disconnected.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("cannot read '{}': {1}", .0.display())]
+    Pem(PathBuf, #[source] rustls::pki_types::pem::Error),
+    #[error("'{}' contains no certificate", .0.display())]
+    NoCertificate(PathBuf),
+    #[error("require-client-certificate needs the client-ca-path")]
+    MissingClientCa,
+    #[error("bad client CA: {0}")]
+    ClientCa(#[from] rustls::server::VerifierBuilderError),
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Client certificate verified during the TLS handshake, added to the extensions of the
+/// requests of the connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    fingerprint: String,
+}
+
+impl ClientCertificate {
+    pub fn from_der(certificate: &[u8]) -> Self {
+        Self {
+            fingerprint: hex::encode(Sha256::digest(certificate)),
+        }
+    }
+
+    /// SHA-256 fingerprint of the certificate, hex encoded.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// Terminates TLS on the accepted connections, verifying the client certificates if configured.
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    acceptor: tokio_rustls::TlsAcceptor,
+}
+
+impl TlsAcceptor {
+    pub fn new(options: &TlsOptions) -> Result<Self, TlsError> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = rustls::ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()?;
+
+        let builder = match &options.client_ca_path {
+            Some(client_ca_path) => builder.with_client_cert_verifier(client_verifier(
+                options,
+                client_ca_path,
+                provider,
+            )?),
+            None if options.require_client_certificate => return Err(TlsError::MissingClientCa),
+            None => builder.with_no_client_auth(),
+        };
+
+        let certificates = read_certificates(&options.certificate_path)?;
+        let key = PrivateKeyDer::from_pem_file(&options.key_path)
+            .map_err(|err| TlsError::Pem(options.key_path.clone(), err))?;
+        let mut config = builder.with_single_cert(certificates, key)?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(Self {
+            acceptor: tokio_rustls::TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    /// Completes the TLS handshake, returning the stream and the verified client certificate,
+    /// if the client presented one.
+    pub async fn accept<IO>(
+        &self,
+        stream: IO,
+    ) -> io::Result<(TlsStream<IO>, Option<ClientCertificate>)>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let stream = tokio::time::timeout(HANDSHAKE_TIMEOUT, self.acceptor.accept(stream))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))??;
+        let client_certificate = stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .map(|certificate| ClientCertificate::from_der(certificate));
+        Ok((stream, client_certificate))
+    }
+}
+
+fn client_verifier(
+    options: &TlsOptions,
+    client_ca_path: &Path,
+    provider: Arc<CryptoProvider>,
+) -> Result<Arc<dyn rustls::server::danger::ClientCertVerifier>, TlsError> {
+    let mut roots = RootCertStore::empty();
+    for certificate in read_certificates(client_ca_path)? {
+        roots.add(certificate)?;
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+    let builder = if options.require_client_certificate {
+        builder
+    } else {
+        // The clients without certificate can authenticate with the other credentials
+        builder.allow_unauthenticated()
+    };
+    Ok(builder.build()?)
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| TlsError::Pem(path.to_owned(), err))?;
+    if certificates.is_empty() {
+        return Err(TlsError::NoCertificate(path.to_owned()));
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn require_client_certificate_without_client_ca() {
+        let result = TlsAcceptor::new(&TlsOptions {
+            certificate_path: "server.pem".into(),
+            key_path: "server.key".into(),
+            client_ca_path: None,
+            require_client_certificate: true,
+        });
+        assert!(matches!(result, Err(TlsError::MissingClientCa)));
+    }
+
+    #[test]
+    fn missing_certificate_file() {
+        let result = TlsAcceptor::new(&TlsOptions {
+            certificate_path: "/non-existing/server.pem".into(),
+            key_path: "/non-existing/server.key".into(),
+            client_ca_path: None,
+            require_client_certificate: false,
+        });
+        assert!(
+            matches!(result, Err(TlsError::Pem(path, _)) if path == Path::new("/non-existing/server.pem"))
+        );
+    }
+}
@@ -15,7 +15,6 @@ use super::path_parsing::AwakeableRequestType;
 use crate::RequestDispatcher;
 use bytes::Bytes;
 use http::{Method, Request, Response, StatusCode};
-use http_body_util::BodyExt;
 use http_body_util::Full;
 use restate_types::errors::{InvocationError, codes};
 use restate_types::identifiers::{AwakeableIdentifier, ExternalSignalIdentifier, WithInvocationId};
@@ -42,12 +41,7 @@ where
         }
 
         // Collect body
-        let collected_request_bytes = req
-            .into_body()
-            .collect()
-            .await
-            .map_err(|e| HandlerError::Body(e.into()))?
-            .to_bytes();
+        let collected_request_bytes = self.collect_body(req.into_body()).await?;
         trace!(rpc.request = ?collected_request_bytes);
 
         let (awakeable_id, result) = match awakeable_request_type {
@@ -9,15 +9,19 @@
 // by the Apache License, Version 2.0.
 
 use super::APPLICATION_JSON;
+use super::session_token::SessionTokenError;
+use super::signature::SignatureError;
 
 use crate::RequestDispatcherError;
 use bytes::Bytes;
 use http::{Response, StatusCode, header};
-use restate_types::errors::{IdDecodeError, InvocationError};
+use restate_types::errors::{ErrorClass, IdDecodeError, InvocationError};
 use restate_types::identifiers::DeploymentId;
+use restate_types::invocation::client::ConsistencyTokenParseError;
 use restate_types::schema::invocation_target::InputValidationError;
 use serde::Serialize;
 use std::string;
+use std::time::Duration;
 
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum HandlerError {
@@ -35,6 +39,10 @@ pub(crate) enum HandlerError {
     DeploymentDeprecated(String, DeploymentId),
     #[error("invocation not found")]
     InvocationNotFound,
+    #[error(
+        "request id '{0}' not found. Request ids are indexed on a best-effort basis by the node which received the request, and only for its most recent requests."
+    )]
+    RequestIdNotFound(String),
     #[error(
         "bad path, expected either /:service-name/:handler or /:object-name/:object-key/:handler"
     )]
@@ -51,24 +59,43 @@ pub(crate) enum HandlerError {
         "bad path, expected either /restate/workflow/:workflow_name/:workflow_key/output or /restate/workflow/:workflow_name/:workflow_key/attach"
     )]
     BadWorkflowPath,
+    #[error(
+        "bad path, expected either /restate/session/attach or /restate/session/output or /restate/session/call/:handler"
+    )]
+    BadSessionPath,
+    #[error("invalid session token: {0}")]
+    SessionToken(#[from] SessionTokenError),
     #[error("not implemented")]
     NotImplemented,
     #[error("bad header {0}: {1:?}")]
     BadHeader(header::HeaderName, #[source] header::ToStrError),
-    #[error("bad delay query parameter, must be a ISO8601 duration: {0}")]
+    #[error("bad x-restate-consistency-token header: {0}")]
+    BadConsistencyToken(#[from] ConsistencyTokenParseError),
+    #[error("bad delay query parameter or x-restate-delay header, must be a ISO8601 duration: {0}")]
     BadDelayDuration(String),
+    #[error("bad {0} header, must be a duration: {1}")]
+    BadTimeout(header::HeaderName, String),
     #[error("bad path, cannot decode key: {0:?}")]
     UrlDecodingError(string::FromUtf8Error),
     #[error("the invoked service is not public")]
     PrivateService,
     #[error("cannot read body: {0:?}")]
     Body(anyhow::Error),
+    #[error("the request body exceeds the limit of {0} bytes")]
+    PayloadTooLarge(usize),
     #[error("unavailable")]
     Unavailable,
+    #[error(
+        "the ingress limits of the service are exceeded, retry after {} seconds",
+        retry_after_secs(.0)
+    )]
+    IngressLimitExceeded(Duration),
     #[error("the invocation exists but has not completed yet")]
     NotReady,
     #[error("method not allowed")]
     MethodNotAllowed,
+    #[error("invalid request signature: {0}")]
+    InvalidSignature(#[from] SignatureError),
     #[error(
         "cannot get output for the given invocation. You can get output only for invocations created with an idempotency key, or for workflow methods."
     )]
@@ -78,7 +105,7 @@ pub(crate) enum HandlerError {
     #[error("input validation error: {0}")]
     InputValidation(#[from] InputValidationError),
     #[error(
-        "cannot use the delay query parameter with calls. The delay is supported only with sends"
+        "cannot use the delay query parameter or x-restate-delay header with calls. The delay is supported only with sends"
     )]
     UnsupportedDelay,
     #[error(
@@ -87,6 +114,8 @@ pub(crate) enum HandlerError {
     UnsupportedIdempotencyKey,
     #[error("bad awakeable id '{0}': {1}")]
     BadAwakeableId(String, IdDecodeError),
+    #[error("bad websocket handshake: {0}")]
+    BadWebSocketHandshake(&'static str),
     #[error("bad invocation id '{0}': {1}")]
     BadInvocationId(String, IdDecodeError),
     #[error(
@@ -119,35 +148,54 @@ impl HandlerError {
             HandlerError::NotFound
             | HandlerError::ServiceNotFound(_)
             | HandlerError::ServiceHandlerNotFound(_, _)
-            | HandlerError::InvocationNotFound => StatusCode::NOT_FOUND,
+            | HandlerError::InvocationNotFound
+            | HandlerError::RequestIdNotFound(_) => StatusCode::NOT_FOUND,
             HandlerError::BadServicePath
             | HandlerError::PrivateService
             | HandlerError::UrlDecodingError(_)
             | HandlerError::BadDelayDuration(_)
+            | HandlerError::BadTimeout(_, _)
             | HandlerError::BadAwakeablesPath
             | HandlerError::UnsupportedDelay
             | HandlerError::BadHeader(_, _)
+            | HandlerError::BadConsistencyToken(_)
             | HandlerError::BadAwakeableId(_, _)
             | HandlerError::BadInvocationPath
             | HandlerError::BadInvocationId(_, _)
+            | HandlerError::BadWebSocketHandshake(_)
             | HandlerError::BadWorkflowPath
+            | HandlerError::BadSessionPath
             | HandlerError::InputValidation(_)
             | HandlerError::UnsupportedIdempotencyKey
             | HandlerError::UnsupportedGetOutput
             | HandlerError::DeploymentDeprecated(_, _) => StatusCode::BAD_REQUEST,
-            HandlerError::DispatcherError(_) => {
-                // TODO add more distinctions between different dispatcher errors (unavailable, etc)
-                StatusCode::INTERNAL_SERVER_ERROR
-            }
+            HandlerError::DispatcherError(e) => match e.error_class() {
+                ErrorClass::Transient | ErrorClass::RetryableWithBackoff => {
+                    StatusCode::SERVICE_UNAVAILABLE
+                }
+                ErrorClass::TerminalUser => StatusCode::BAD_REQUEST,
+                ErrorClass::TerminalSystem => StatusCode::INTERNAL_SERVER_ERROR,
+            },
             HandlerError::Body(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            HandlerError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
             HandlerError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            HandlerError::IngressLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
             HandlerError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            HandlerError::InvalidSignature(_) => StatusCode::UNAUTHORIZED,
+            HandlerError::SessionToken(SessionTokenError::OutOfScope(_)) => StatusCode::FORBIDDEN,
+            HandlerError::SessionToken(_) => StatusCode::UNAUTHORIZED,
             HandlerError::NotImplemented => StatusCode::NOT_IMPLEMENTED,
             HandlerError::Invocation(e) => {
                 StatusCode::from_u16(e.code().into()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
             }
             HandlerError::NotReady => StatusCode::from_u16(470).unwrap(),
         };
+        let res_builder = match &self {
+            HandlerError::IngressLimitExceeded(retry_after) => {
+                res_builder.header(header::RETRY_AFTER, retry_after_secs(retry_after))
+            }
+            _ => res_builder,
+        };
 
         let error_response = match self {
             HandlerError::Invocation(e) => ErrorResponse::Invocation(e),
@@ -171,3 +219,8 @@ impl HandlerError {
         self.fill_builder(http::response::Builder::new())
     }
 }
+
+/// The `Retry-After` header takes whole seconds, round up to not retry too early.
+fn retry_after_secs(retry_after: &Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
@@ -0,0 +1,331 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use restate_types::schema::service::IngressLimit;
+
+/// Suggested wait for requests rejected by a concurrency cap, as it's unknown when the requests
+/// in flight will complete.
+const CONCURRENCY_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Enforces the [`IngressLimit`]s of services and handlers, as configured through the admin API.
+///
+/// The rate is limited by a token bucket per service and per handler, while the concurrency caps
+/// count the requests holding an [`IngressPermit`]. A request is admitted only if both the
+/// service and the handler limits allow it.
+#[derive(Clone, Default)]
+pub(crate) struct IngressLimiter {
+    limits: Arc<Mutex<HashMap<LimitKey, LimitState>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LimitKey {
+    service: String,
+    handler: Option<String>,
+}
+
+struct LimitState {
+    limit: IngressLimit,
+    tokens: f64,
+    last_refill: Instant,
+    in_flight: u32,
+}
+
+impl LimitState {
+    fn new(limit: IngressLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: limit
+                .effective_burst()
+                .map_or(0.0, |burst| f64::from(burst.get())),
+            last_refill: now,
+            in_flight: 0,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let (Some(requests_per_second), Some(burst)) =
+            (self.limit.requests_per_second, self.limit.effective_burst())
+        {
+            let elapsed = now.saturating_duration_since(self.last_refill);
+            self.tokens = (self.tokens
+                + elapsed.as_secs_f64() * f64::from(requests_per_second.get()))
+            .min(f64::from(burst.get()));
+        }
+        self.last_refill = now;
+    }
+
+    /// Applies the limit as modified through the admin API, keeping the requests in flight.
+    fn update_limit(&mut self, limit: IngressLimit, now: Instant) {
+        if self.limit != limit {
+            *self = Self {
+                in_flight: self.in_flight,
+                ..Self::new(limit, now)
+            };
+        }
+    }
+
+    /// Returns how long to wait before retrying, if the request can't be admitted now.
+    fn check(&self) -> Option<Duration> {
+        if self
+            .limit
+            .max_concurrency
+            .is_some_and(|max_concurrency| self.in_flight >= max_concurrency.get())
+        {
+            return Some(CONCURRENCY_RETRY_AFTER);
+        }
+        match self.limit.requests_per_second {
+            Some(requests_per_second) if self.tokens < 1.0 => Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / f64::from(requests_per_second.get()),
+            )),
+            _ => None,
+        }
+    }
+
+    fn admit(&mut self) {
+        if self.limit.requests_per_second.is_some() {
+            self.tokens -= 1.0;
+        }
+        self.in_flight += 1;
+    }
+}
+
+impl IngressLimiter {
+    /// Admits a request to the given handler, or returns how long the caller should wait
+    /// before retrying.
+    pub(crate) fn acquire(
+        &self,
+        service_name: &str,
+        handler_name: &str,
+        service_limit: &IngressLimit,
+        handler_limit: &IngressLimit,
+    ) -> Result<Option<IngressPermit>, Duration> {
+        self.acquire_at(
+            service_name,
+            handler_name,
+            service_limit,
+            handler_limit,
+            Instant::now(),
+        )
+    }
+
+    fn acquire_at(
+        &self,
+        service_name: &str,
+        handler_name: &str,
+        service_limit: &IngressLimit,
+        handler_limit: &IngressLimit,
+        now: Instant,
+    ) -> Result<Option<IngressPermit>, Duration> {
+        if service_limit.is_empty() && handler_limit.is_empty() {
+            return Ok(None);
+        }
+
+        let limits = [
+            (
+                LimitKey {
+                    service: service_name.to_owned(),
+                    handler: None,
+                },
+                service_limit,
+            ),
+            (
+                LimitKey {
+                    service: service_name.to_owned(),
+                    handler: Some(handler_name.to_owned()),
+                },
+                handler_limit,
+            ),
+        ];
+
+        let mut states = self.limits.lock();
+        let mut keys = Vec::with_capacity(limits.len());
+        for (key, limit) in limits {
+            if limit.is_empty() {
+                // Keep the state of a removed limit, its permits still count the requests in
+                // flight in case it's set again
+                continue;
+            }
+            let state = states
+                .entry(key.clone())
+                .or_insert_with(|| LimitState::new(*limit, now));
+            state.update_limit(*limit, now);
+            state.refill(now);
+            keys.push(key);
+        }
+
+        if let Some(retry_after) = keys.iter().filter_map(|key| states[key].check()).max() {
+            return Err(retry_after);
+        }
+
+        for key in &keys {
+            states
+                .get_mut(key)
+                .expect("limit state was just inserted")
+                .admit();
+        }
+        drop(states);
+
+        Ok(Some(IngressPermit {
+            limiter: self.clone(),
+            keys,
+        }))
+    }
+}
+
+/// Counts an admitted request against the concurrency caps until dropped.
+pub(crate) struct IngressPermit {
+    limiter: IngressLimiter,
+    keys: Vec<LimitKey>,
+}
+
+impl Drop for IngressPermit {
+    fn drop(&mut self) {
+        let mut states = self.limiter.limits.lock();
+        for key in &self.keys {
+            if let Some(state) = states.get_mut(key) {
+                state.in_flight -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::num::NonZeroU32;
+
+    fn rate_limit(requests_per_second: u32, burst: u32) -> IngressLimit {
+        IngressLimit {
+            requests_per_second: NonZeroU32::new(requests_per_second),
+            burst: NonZeroU32::new(burst),
+            max_concurrency: None,
+        }
+    }
+
+    fn concurrency_limit(max_concurrency: u32) -> IngressLimit {
+        IngressLimit {
+            max_concurrency: NonZeroU32::new(max_concurrency),
+            ..IngressLimit::default()
+        }
+    }
+
+    #[test]
+    fn no_limits() {
+        let limiter = IngressLimiter::default();
+        let permit = limiter
+            .acquire(
+                "greeter",
+                "greet",
+                &IngressLimit::default(),
+                &IngressLimit::default(),
+            )
+            .unwrap();
+        assert!(permit.is_none());
+        assert!(limiter.limits.lock().is_empty());
+    }
+
+    #[test]
+    fn rate_limit_refills_over_time() {
+        let limiter = IngressLimiter::default();
+        let limit = rate_limit(2, 3);
+        let no_limit = IngressLimit::default();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(
+                limiter
+                    .acquire_at("greeter", "greet", &limit, &no_limit, now)
+                    .is_ok()
+            );
+        }
+        let retry_after = limiter
+            .acquire_at("greeter", "greet", &limit, &no_limit, now)
+            .err()
+            .unwrap();
+        assert_eq!(retry_after, Duration::from_millis(500));
+
+        // Half a second later a single token was refilled
+        let later = now + Duration::from_millis(500);
+        assert!(
+            limiter
+                .acquire_at("greeter", "greet", &limit, &no_limit, later)
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .acquire_at("greeter", "greet", &limit, &no_limit, later)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn concurrency_limit_counts_requests_in_flight() {
+        let limiter = IngressLimiter::default();
+        let limit = concurrency_limit(1);
+        let no_limit = IngressLimit::default();
+
+        let permit = limiter
+            .acquire("greeter", "greet", &no_limit, &limit)
+            .unwrap();
+        assert_eq!(
+            limiter.acquire("greeter", "greet", &no_limit, &limit).err(),
+            Some(CONCURRENCY_RETRY_AFTER)
+        );
+        // Other handlers of the service are not limited
+        assert!(
+            limiter
+                .acquire("greeter", "other", &no_limit, &no_limit)
+                .is_ok()
+        );
+
+        drop(permit);
+        assert!(
+            limiter
+                .acquire("greeter", "greet", &no_limit, &limit)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn rejected_requests_consume_no_tokens() {
+        let limiter = IngressLimiter::default();
+        let service_limit = rate_limit(1, 2);
+        let handler_limit = concurrency_limit(1);
+        let now = Instant::now();
+
+        let _permit = limiter
+            .acquire_at("greeter", "greet", &service_limit, &handler_limit, now)
+            .unwrap();
+        // Rejected by the handler concurrency cap, the service token stays available
+        assert!(
+            limiter
+                .acquire_at("greeter", "greet", &service_limit, &handler_limit, now)
+                .is_err()
+        );
+        assert!(
+            limiter
+                .acquire_at(
+                    "greeter",
+                    "other",
+                    &service_limit,
+                    &IngressLimit::default(),
+                    now
+                )
+                .is_ok()
+        );
+    }
+}
@@ -11,6 +11,7 @@
 use super::Handler;
 use super::HandlerError;
 use super::path_parsing::{InvocationRequestType, InvocationTargetType, TargetType};
+use super::responses::X_RESTATE_CONSISTENCY_TOKEN;
 
 use crate::RequestDispatcher;
 use bytes::Bytes;
@@ -18,7 +19,9 @@ use http::{Method, Request, Response};
 use http_body_util::Full;
 use restate_types::identifiers::IdempotencyId;
 use restate_types::invocation::InvocationQuery;
-use restate_types::invocation::client::{AttachInvocationResponse, GetInvocationOutputResponse};
+use restate_types::invocation::client::{
+    AttachInvocationResponse, ConsistencyToken, GetInvocationOutputResponse,
+};
 use restate_types::schema::invocation_target::InvocationTargetResolver;
 use tracing::warn;
 
@@ -78,6 +81,20 @@ where
         }
     }
 
+    /// Parses the consistency token the client got when submitting the invocation, if any.
+    pub(crate) fn parse_consistency_token<B>(
+        req: &Request<B>,
+    ) -> Result<Option<ConsistencyToken>, HandlerError> {
+        let Some(value) = req.headers().get(X_RESTATE_CONSISTENCY_TOKEN) else {
+            return Ok(None);
+        };
+        let value = value
+            .to_str()
+            .map_err(|e| HandlerError::BadHeader(X_RESTATE_CONSISTENCY_TOKEN, e))?;
+
+        Ok(Some(value.parse()?))
+    }
+
     pub(crate) async fn handle_invocation_attach<B: http_body::Body>(
         self,
         req: Request<B>,
@@ -91,10 +108,12 @@ where
             return Err(HandlerError::MethodNotAllowed);
         }
 
+        let consistency_token = Self::parse_consistency_token(&req)?;
+
         // Wait on response
         let response = match self
             .dispatcher
-            .attach_invocation(invocation_query.clone())
+            .attach_invocation(invocation_query.clone(), consistency_token)
             .await?
         {
             AttachInvocationResponse::NotFound => {
@@ -130,9 +149,11 @@ where
             return Err(HandlerError::MethodNotAllowed);
         }
 
+        let consistency_token = Self::parse_consistency_token(&req)?;
+
         let response = match self
             .dispatcher
-            .get_invocation_output(invocation_query.clone())
+            .get_invocation_output(invocation_query.clone(), consistency_token)
             .await
         {
             Ok(GetInvocationOutputResponse::Ready(out)) => out,
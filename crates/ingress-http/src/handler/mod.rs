@@ -11,28 +11,41 @@
 mod awakeables;
 mod error;
 mod health;
+mod ingress_limiter;
 mod invocation;
 mod path_parsing;
+mod request_id;
+mod response_cache;
 mod responses;
 mod service_handler;
+mod session;
+mod session_token;
+mod signature;
+mod subscriptions;
 #[cfg(test)]
 mod tests;
 mod tracing;
 mod workflow;
 
 use std::convert::Infallible;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use error::HandlerError;
 use futures::FutureExt;
 use futures::future::BoxFuture;
-use http_body_util::Full;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
 use hyper::http::HeaderValue;
 use hyper::{Request, Response};
+use ingress_limiter::IngressLimiter;
 use path_parsing::RequestType;
+use request_id::RequestIdIndex;
+pub(crate) use response_cache::ResponseCache;
+use restate_types::config::{IngressExposure, RequestSignatureOptions, SessionTokenOptions};
 use restate_types::live::Live;
 use restate_types::schema::invocation_target::InvocationTargetResolver;
 use restate_types::schema::service::ServiceMetadataResolver;
+pub(crate) use session_token::X_RESTATE_SESSION_TOKEN;
 
 use super::*;
 
@@ -42,6 +55,14 @@ const APPLICATION_JSON: HeaderValue = HeaderValue::from_static("application/json
 pub(crate) struct Handler<Schemas, Dispatcher> {
     schemas: Live<Schemas>,
     dispatcher: Dispatcher,
+    request_id_index: RequestIdIndex,
+    request_signatures: Arc<[RequestSignatureOptions]>,
+    response_cache: Option<ResponseCache>,
+    session_tokens: Option<Arc<SessionTokenOptions>>,
+    validate_json_schemas: bool,
+    exposure: IngressExposure,
+    ingress_limiter: IngressLimiter,
+    max_request_body_size: usize,
 }
 
 impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher> {
@@ -49,8 +70,77 @@ impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher> {
         Self {
             schemas,
             dispatcher,
+            request_id_index: RequestIdIndex::default(),
+            request_signatures: Arc::new([]),
+            response_cache: None,
+            session_tokens: None,
+            validate_json_schemas: false,
+            exposure: IngressExposure::default(),
+            ingress_limiter: IngressLimiter::default(),
+            max_request_body_size: usize::MAX,
         }
     }
+
+    pub(crate) fn with_request_signatures(
+        mut self,
+        request_signatures: Arc<[RequestSignatureOptions]>,
+    ) -> Self {
+        self.request_signatures = request_signatures;
+        self
+    }
+
+    pub(crate) fn with_response_cache(mut self, response_cache: Option<ResponseCache>) -> Self {
+        self.response_cache = response_cache;
+        self
+    }
+
+    pub(crate) fn with_session_tokens(
+        mut self,
+        session_tokens: Option<Arc<SessionTokenOptions>>,
+    ) -> Self {
+        self.session_tokens = session_tokens;
+        self
+    }
+
+    pub(crate) fn with_json_schema_validation(mut self, validate_json_schemas: bool) -> Self {
+        self.validate_json_schemas = validate_json_schemas;
+        self
+    }
+
+    pub(crate) fn with_exposure(mut self, exposure: IngressExposure) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    pub(crate) fn with_max_request_body_size(mut self, max_request_body_size: usize) -> Self {
+        self.max_request_body_size = max_request_body_size;
+        self
+    }
+
+    /// Collects the request body, rejecting it as soon as it's known to exceed the size limit:
+    /// upfront if its declared length is larger, otherwise once the limit is reached while
+    /// streaming it, without buffering the rest.
+    async fn collect_body<B: http_body::Body>(&self, body: B) -> Result<Bytes, HandlerError>
+    where
+        <B as http_body::Body>::Error: std::error::Error + Send + Sync + 'static,
+    {
+        // Hyper derives the size hint of the request body from its content-length
+        if body.size_hint().lower() > self.max_request_body_size as u64 {
+            return Err(HandlerError::PayloadTooLarge(self.max_request_body_size));
+        }
+
+        Limited::new(body, self.max_request_body_size)
+            .collect()
+            .await
+            .map(|collected| collected.to_bytes())
+            .map_err(|err| {
+                if err.is::<LengthLimitError>() {
+                    HandlerError::PayloadTooLarge(self.max_request_body_size)
+                } else {
+                    HandlerError::Body(anyhow::anyhow!(err))
+                }
+            })
+    }
 }
 
 impl<Schemas, Dispatcher, Body> tower::Service<Request<Body>> for Handler<Schemas, Dispatcher>
@@ -70,7 +160,7 @@ where
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        let res = self.parse_path(req.uri());
+        let res = self.parse_path(req.uri(), req.headers());
 
         let mut this = self.clone();
         async move {
@@ -86,6 +176,13 @@ where
                 RequestType::Service(service_request) => {
                     this.handle_service_request(req, service_request).await
                 }
+                RequestType::Session(session_request) => {
+                    this.handle_session(req, session_request).await
+                }
+                RequestType::Subscribe => this.handle_subscribe(req),
+                RequestType::RequestId(request_id) => {
+                    this.handle_request_id_lookup(req, request_id)
+                }
                 RequestType::Invocation(invocation_request) => {
                     this.handle_invocation(req, invocation_request).await
                 }
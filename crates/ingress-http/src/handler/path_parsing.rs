@@ -10,8 +10,11 @@
 
 use super::Handler;
 use super::HandlerError;
-use http::Uri;
+use http::header::HOST;
+use http::uri::Authority;
+use http::{HeaderMap, Uri};
 use restate_types::schema::invocation_target::InvocationTargetResolver;
+use restate_types::schema::service::ServiceMetadataResolver;
 
 pub(crate) enum WorkflowRequestType {
     Attach(String, String),
@@ -41,6 +44,35 @@ impl WorkflowRequestType {
     }
 }
 
+pub(crate) enum SessionRequestType {
+    Attach,
+    GetOutput,
+    CallSharedHandler(String),
+}
+
+impl SessionRequestType {
+    fn from_path_chunks<'a>(
+        mut path_parts: impl Iterator<Item = &'a str>,
+    ) -> Result<Self, HandlerError> {
+        let request_type = match path_parts.next().ok_or(HandlerError::BadSessionPath)? {
+            "attach" => SessionRequestType::Attach,
+            "output" => SessionRequestType::GetOutput,
+            "call" => SessionRequestType::CallSharedHandler(
+                path_parts
+                    .next()
+                    .ok_or(HandlerError::BadSessionPath)?
+                    .to_owned(),
+            ),
+            _ => return Err(HandlerError::NotFound),
+        };
+
+        if path_parts.next().is_some() {
+            return Err(HandlerError::BadSessionPath);
+        }
+        Ok(request_type)
+    }
+}
+
 pub(crate) enum InvocationTargetType {
     InvocationId(String),
     IdempotencyId {
@@ -93,7 +125,10 @@ impl InvocationRequestType {
             };
 
             let handler = next_chunk.to_owned();
-            let idempotency_id = next_next_chunk.to_owned();
+            // The idempotency key is user supplied, like the service key, hence it can contain any char
+            let idempotency_id = urlencoding::decode(next_next_chunk)
+                .map_err(HandlerError::UrlDecodingError)?
+                .into_owned();
 
             (
                 InvocationTargetType::IdempotencyId {
@@ -216,16 +251,23 @@ pub(crate) enum RequestType {
     OpenAPI,
     Awakeable(AwakeableRequestType),
     Invocation(InvocationRequestType),
+    RequestId(String),
     Service(ServiceRequestType),
+    Session(SessionRequestType),
+    Subscribe,
     Workflow(WorkflowRequestType),
 }
 
 impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher>
 where
-    Schemas: InvocationTargetResolver + Clone + Send + Sync + 'static,
+    Schemas: ServiceMetadataResolver + InvocationTargetResolver + Clone + Send + Sync + 'static,
 {
     /// This function takes care of parsing the path of the request, inferring the correct request type
-    pub(crate) fn parse_path(&mut self, uri: &Uri) -> Result<RequestType, HandlerError> {
+    pub(crate) fn parse_path(
+        &mut self,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Result<RequestType, HandlerError> {
         let mut path_parts = uri.path().split('/').skip(1);
 
         let first_segment = path_parts.next().ok_or(HandlerError::NotFound)?;
@@ -240,17 +282,53 @@ where
                 "invocation" => Ok(RequestType::Invocation(
                     InvocationRequestType::from_path_chunks(path_parts, schema)?,
                 )),
+                "request" => Ok(RequestType::RequestId(
+                    urlencoding::decode(path_parts.next().ok_or(HandlerError::NotFound)?)
+                        .map_err(HandlerError::UrlDecodingError)?
+                        .into_owned(),
+                )),
+                "session" => Ok(RequestType::Session(SessionRequestType::from_path_chunks(
+                    path_parts,
+                )?)),
+                "subscribe" => Ok(RequestType::Subscribe),
                 "workflow" => Ok(RequestType::Workflow(
                     WorkflowRequestType::from_path_chunks(path_parts)?,
                 )),
                 _ => Err(HandlerError::NotFound),
             },
             "openapi" => Ok(RequestType::OpenAPI),
-            segment => Ok(RequestType::Service(ServiceRequestType::from_path_chunks(
-                path_parts,
-                segment.to_owned(),
-                schema,
-            )?)),
+            segment => {
+                // Custom ingress routes replace the /<SERVICE_NAME> segment
+                let host = request_host(uri, headers);
+                if let Some((service_name, remaining_path)) =
+                    schema.resolve_ingress_route(host.as_deref(), uri.path())
+                {
+                    return Ok(RequestType::Service(ServiceRequestType::from_path_chunks(
+                        remaining_path.split('/').skip(1),
+                        service_name,
+                        schema,
+                    )?));
+                }
+
+                Ok(RequestType::Service(ServiceRequestType::from_path_chunks(
+                    path_parts,
+                    segment.to_owned(),
+                    schema,
+                )?))
+            }
         }
     }
 }
+
+/// Host targeted by the request, without port. HTTP/2 requests carry it in the uri authority.
+fn request_host(uri: &Uri, headers: &HeaderMap) -> Option<String> {
+    uri.host().map(str::to_owned).or_else(|| {
+        headers
+            .get(HOST)?
+            .to_str()
+            .ok()?
+            .parse::<Authority>()
+            .ok()
+            .map(|authority| authority.host().to_owned())
+    })
+}
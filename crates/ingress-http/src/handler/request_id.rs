@@ -0,0 +1,136 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, Method, Request, Response, StatusCode, header};
+use http_body_util::Full;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use restate_types::identifiers::InvocationId;
+
+use super::{APPLICATION_JSON, Handler};
+use crate::handler::error::HandlerError;
+
+/// Correlation id of the request, either provided by the client or generated by the ingress.
+pub(crate) const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Max number of request ids retained by the [`RequestIdIndex`].
+const REQUEST_ID_INDEX_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RequestIdLookupResponse {
+    pub(crate) invocation_id: InvocationId,
+}
+
+/// Index of the invocations submitted through this ingress by their request id, to join the
+/// client-side logs with the server-side invocations.
+///
+/// The index is best-effort and node-local: it's kept in memory, it's not replicated, and it's
+/// bounded. Hence a request id can be looked up only through the node which received the request,
+/// until it's evicted by more recent requests or the node restarts. A missing request id doesn't
+/// imply that no invocation was submitted for it.
+#[derive(Clone, Default)]
+pub(crate) struct RequestIdIndex {
+    inner: Arc<Mutex<RequestIdIndexInner>>,
+}
+
+#[derive(Default)]
+struct RequestIdIndexInner {
+    invocations: HashMap<String, InvocationId>,
+    insertion_order: VecDeque<String>,
+}
+
+impl RequestIdIndex {
+    pub(crate) fn record(&self, headers: &HeaderMap, invocation_id: InvocationId) {
+        let Some(request_id) = headers.get(X_REQUEST_ID).and_then(|v| v.to_str().ok()) else {
+            return;
+        };
+
+        let mut inner = self.inner.lock();
+        if inner
+            .invocations
+            .insert(request_id.to_owned(), invocation_id)
+            .is_none()
+        {
+            inner.insertion_order.push_back(request_id.to_owned());
+        }
+        while inner.insertion_order.len() > REQUEST_ID_INDEX_CAPACITY {
+            let evicted = inner.insertion_order.pop_front().expect("not empty");
+            inner.invocations.remove(&evicted);
+        }
+    }
+
+    pub(crate) fn get(&self, request_id: &str) -> Option<InvocationId> {
+        self.inner.lock().invocations.get(request_id).copied()
+    }
+}
+
+impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher> {
+    pub(crate) fn handle_request_id_lookup<B: http_body::Body>(
+        &self,
+        req: Request<B>,
+        request_id: String,
+    ) -> Result<Response<Full<Bytes>>, HandlerError> {
+        if req.method() != Method::GET {
+            return Err(HandlerError::MethodNotAllowed);
+        }
+        let Some(invocation_id) = self.request_id_index.get(&request_id) else {
+            return Err(HandlerError::RequestIdNotFound(request_id));
+        };
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, APPLICATION_JSON)
+            .body(Full::new(
+                serde_json::to_vec(&RequestIdLookupResponse { invocation_id })
+                    .expect("Serializing the RequestIdLookupResponse must not fail")
+                    .into(),
+            ))
+            .unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use http::HeaderValue;
+
+    fn headers(request_id: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(X_REQUEST_ID, HeaderValue::from_str(request_id).unwrap());
+        headers
+    }
+
+    #[test]
+    fn evicts_oldest_request_ids() {
+        let index = RequestIdIndex::default();
+        let first = InvocationId::mock_random();
+        index.record(&headers("first"), first);
+        index.record(&HeaderMap::new(), InvocationId::mock_random());
+        assert_eq!(index.get("first"), Some(first));
+
+        for i in 0..REQUEST_ID_INDEX_CAPACITY {
+            index.record(&headers(&i.to_string()), InvocationId::mock_random());
+        }
+        assert_eq!(index.get("first"), None);
+        assert!(index.get("0").is_some());
+        assert_eq!(
+            index.inner.lock().invocations.len(),
+            REQUEST_ID_INDEX_CAPACITY
+        );
+    }
+}
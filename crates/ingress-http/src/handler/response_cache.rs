@@ -0,0 +1,233 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use bytestring::ByteString;
+use http::{HeaderMap, HeaderName, HeaderValue, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use parking_lot::Mutex;
+
+use restate_types::config::ResponseCacheOptions;
+use restate_types::invocation::InvocationTarget;
+
+/// Set on the responses served from the [`ResponseCache`].
+pub(crate) const X_RESTATE_CACHE: HeaderName = HeaderName::from_static("x-restate-cache");
+const CACHE_HIT: HeaderValue = HeaderValue::from_static("hit");
+
+/// Responses of the side-effect-free handlers, keyed by service, handler, object or workflow key,
+/// and hash of the request body.
+///
+/// The cache is kept in memory and it's bounded, the oldest responses are evicted first. It's
+/// local to the node and it doesn't observe the state changes of the cached targets: a response
+/// is served until its ttl expires, even if the state it was computed from changed in between.
+#[derive(Clone)]
+pub(crate) struct ResponseCache {
+    ttl: Duration,
+    capacity: usize,
+    inner: Arc<Mutex<ResponseCacheInner>>,
+}
+
+#[derive(Default)]
+struct ResponseCacheInner {
+    responses: HashMap<CacheKey, CachedResponse>,
+    insertion_order: VecDeque<(CacheKey, u64)>,
+    next_insertion: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    service: ByteString,
+    key: Option<ByteString>,
+    handler: ByteString,
+    payload_hash: u64,
+}
+
+impl CacheKey {
+    fn new(invocation_target: &InvocationTarget, payload: &Bytes) -> Self {
+        let mut hasher = DefaultHasher::new();
+        payload.hash(&mut hasher);
+        Self {
+            service: invocation_target.service_name().clone(),
+            key: invocation_target.key().cloned(),
+            handler: invocation_target.handler_name().clone(),
+            payload_hash: hasher.finish(),
+        }
+    }
+}
+
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    expires_at: Instant,
+    insertion: u64,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(options: &ResponseCacheOptions) -> Self {
+        Self {
+            ttl: options.ttl.into(),
+            capacity: options.capacity.get(),
+            inner: Default::default(),
+        }
+    }
+
+    pub(crate) fn get(
+        &self,
+        invocation_target: &InvocationTarget,
+        payload: &Bytes,
+    ) -> Option<Response<Full<Bytes>>> {
+        let cache_key = CacheKey::new(invocation_target, payload);
+
+        let mut inner = self.inner.lock();
+        let cached = inner.responses.get(&cache_key)?;
+        if cached.expires_at <= Instant::now() {
+            inner.responses.remove(&cache_key);
+            return None;
+        }
+
+        let mut response = Response::new(Full::new(cached.body.clone()));
+        *response.status_mut() = cached.status;
+        *response.headers_mut() = cached.headers.clone();
+        response.headers_mut().insert(X_RESTATE_CACHE, CACHE_HIT);
+        Some(response)
+    }
+
+    /// Caches the successful response, returning it to be sent to the client.
+    pub(crate) async fn put(
+        &self,
+        invocation_target: &InvocationTarget,
+        payload: &Bytes,
+        response: Response<Full<Bytes>>,
+    ) -> Response<Full<Bytes>> {
+        let (parts, body) = response.into_parts();
+        let Ok(body) = body.collect().await;
+        let body = body.to_bytes();
+        if parts.status.is_success() {
+            let cache_key = CacheKey::new(invocation_target, payload);
+
+            let mut inner = self.inner.lock();
+            let insertion = inner.next_insertion;
+            inner.next_insertion += 1;
+            inner
+                .insertion_order
+                .push_back((cache_key.clone(), insertion));
+            inner.responses.insert(
+                cache_key,
+                CachedResponse {
+                    status: parts.status,
+                    headers: parts.headers.clone(),
+                    body: body.clone(),
+                    expires_at: Instant::now() + self.ttl,
+                    insertion,
+                },
+            );
+            while inner.insertion_order.len() > self.capacity {
+                let (cache_key, insertion) = inner.insertion_order.pop_front().expect("not empty");
+                // The response might have been overwritten since the given insertion
+                if inner
+                    .responses
+                    .get(&cache_key)
+                    .is_some_and(|cached| cached.insertion == insertion)
+                {
+                    inner.responses.remove(&cache_key);
+                }
+            }
+        }
+
+        Response::from_parts(parts, Full::new(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::num::NonZeroUsize;
+
+    use restate_time_util::FriendlyDuration;
+    use restate_types::invocation::VirtualObjectHandlerType;
+
+    fn cache(capacity: usize) -> ResponseCache {
+        ResponseCache::new(&ResponseCacheOptions {
+            ttl: FriendlyDuration::from_secs(60),
+            capacity: NonZeroUsize::new(capacity).unwrap(),
+        })
+    }
+
+    fn response(status: StatusCode, body: &'static str) -> Response<Full<Bytes>> {
+        let mut response = Response::new(Full::new(Bytes::from_static(body.as_bytes())));
+        *response.status_mut() = status;
+        response
+    }
+
+    fn counter(key: &str, handler: &str) -> InvocationTarget {
+        InvocationTarget::virtual_object("Counter", key, handler, VirtualObjectHandlerType::Shared)
+    }
+
+    #[tokio::test]
+    async fn caches_successful_responses() {
+        let cache = cache(10);
+        let get = counter("my-key", "get");
+        let payload = Bytes::from_static(b"{}");
+
+        assert!(cache.get(&get, &payload).is_none());
+        cache
+            .put(&get, &payload, response(StatusCode::OK, "1"))
+            .await;
+        let cached = cache.get(&get, &payload).unwrap();
+        assert_eq!(cached.headers().get(X_RESTATE_CACHE).unwrap(), "hit");
+        let Ok(body) = cached.into_body().collect().await;
+        assert_eq!(body.to_bytes(), "1");
+
+        // different key, handler, or payload
+        let get_other_key = counter("other-key", "get");
+        assert!(cache.get(&get_other_key, &payload).is_none());
+        assert!(cache.get(&counter("my-key", "peek"), &payload).is_none());
+        assert!(cache.get(&get, &Bytes::from_static(b"[]")).is_none());
+
+        // failures are not cached
+        cache
+            .put(
+                &get_other_key,
+                &payload,
+                response(StatusCode::INTERNAL_SERVER_ERROR, "boom"),
+            )
+            .await;
+        assert!(cache.get(&get_other_key, &payload).is_none());
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_responses() {
+        let cache = cache(2);
+        let greet = InvocationTarget::service("Greeter", "greet");
+        let payload = |i: usize| Bytes::from(i.to_string());
+
+        for i in 0..3 {
+            cache
+                .put(&greet, &payload(i), response(StatusCode::OK, "hello"))
+                .await;
+        }
+        // overwriting doesn't evict the new response
+        cache
+            .put(&greet, &payload(1), response(StatusCode::OK, "hello"))
+            .await;
+
+        assert!(cache.get(&greet, &payload(0)).is_none());
+        assert!(cache.get(&greet, &payload(1)).is_some());
+        assert!(cache.get(&greet, &payload(2)).is_none());
+    }
+}
@@ -22,6 +22,10 @@ use tracing::{info, trace};
 pub(crate) const IDEMPOTENCY_EXPIRES: HeaderName = HeaderName::from_static("idempotency-expires");
 /// Contains the string representation of the invocation id
 pub(crate) const X_RESTATE_ID: HeaderName = HeaderName::from_static("x-restate-id");
+/// Contains the consistency token returned when submitting an invocation. When provided to the
+/// attach/output endpoints, the ingress reads the invocation only once the write is visible.
+pub(crate) const X_RESTATE_CONSISTENCY_TOKEN: HeaderName =
+    HeaderName::from_static("x-restate-consistency-token");
 
 impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher> {
     pub(crate) fn reply_with_invocation_response(
@@ -13,25 +13,31 @@ use std::time::{Duration, Instant, SystemTime};
 
 use bytes::Bytes;
 use bytestring::ByteString;
-use http::{HeaderMap, HeaderName, Method, Request, Response, StatusCode, header};
-use http_body_util::{BodyExt, Full};
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, header};
+use http_body_util::Full;
 use metrics::{counter, histogram};
 use serde::de::IntoDeserializer;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use sha2::{Digest, Sha256};
 use tracing::{Instrument, debug, trace, trace_span};
 
 use super::HandlerError;
 use super::path_parsing::{InvokeType, ServiceRequestType, TargetType};
+use super::session_token::{X_RESTATE_SESSION_TOKEN, issue_session_token};
+use super::signature::verify_signature;
 use super::tracing::prepare_tracing_span;
 use super::{APPLICATION_JSON, Handler};
 use crate::RequestDispatcher;
-use crate::handler::responses::{IDEMPOTENCY_EXPIRES, X_RESTATE_ID};
-use crate::metric_definitions::{INGRESS_REQUEST_DURATION, INGRESS_REQUESTS, REQUEST_COMPLETED};
+use crate::handler::responses::{IDEMPOTENCY_EXPIRES, X_RESTATE_CONSISTENCY_TOKEN, X_RESTATE_ID};
+use crate::metric_definitions::{
+    INGRESS_REQUEST_DURATION, INGRESS_REQUESTS, REQUEST_COMPLETED, REQUEST_RATE_LIMITED,
+};
+use restate_types::config::IngressExposure;
 use restate_types::identifiers::{InvocationId, WithInvocationId};
 use restate_types::invocation::{
-    Header, InvocationRequest, InvocationRequestHeader, InvocationTarget, InvocationTargetType,
-    SpanRelation, WorkflowHandlerType,
+    DEADLINE_HEADER_NAME, Header, InvocationRequest, InvocationRequestHeader, InvocationTarget,
+    InvocationTargetType, SpanRelation, WorkflowHandlerType,
 };
 use restate_types::schema::invocation_target::{
     DeploymentStatus, InvocationTargetMetadata, InvocationTargetResolver,
@@ -40,7 +46,10 @@ use restate_types::time::MillisSinceEpoch;
 
 pub(crate) const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
 const DELAY_QUERY_PARAM: &str = "delay";
+const X_RESTATE_DELAY: HeaderName = HeaderName::from_static("x-restate-delay");
 const X_RESTATE_INGRESS_PATH: ByteString = ByteString::from_static("x-restate-ingress-path");
+const X_RESTATE_TIMEOUT: HeaderName = HeaderName::from_static("x-restate-timeout");
+const GRPC_TIMEOUT: HeaderName = HeaderName::from_static("grpc-timeout");
 
 #[derive(Debug, Serialize)]
 #[cfg_attr(test, derive(serde::Deserialize))]
@@ -62,6 +71,8 @@ pub(crate) struct SendResponse {
     )]
     execution_time: Option<humantime::Timestamp>,
     status: SendStatus,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) session_token: Option<String>,
 }
 
 impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher>
@@ -91,7 +102,7 @@ where
             .pinned()
             .resolve_latest_invocation_target(&service_name, &handler_name)
         {
-            if !invocation_target.public {
+            if !invocation_target.public && self.exposure != IngressExposure::All {
                 return Err(HandlerError::PrivateService);
             }
             invocation_target
@@ -105,20 +116,43 @@ where
             return Err(HandlerError::DeploymentDeprecated(service_name, dp_id));
         }
 
+        // Enforce the ingress limits before reading the body, the permit is held until the
+        // response is sent back
+        let _ingress_permit = match self.ingress_limiter.acquire(
+            &service_name,
+            &handler_name,
+            &invocation_target_meta.service_ingress_limit,
+            &invocation_target_meta.handler_ingress_limit,
+        ) {
+            Ok(permit) => permit,
+            Err(retry_after) => {
+                debug!(
+                    "Rejecting the request to '{service_name}/{handler_name}' exceeding the ingress limits"
+                );
+                counter!(
+                    INGRESS_REQUESTS,
+                    "status" => REQUEST_RATE_LIMITED,
+                    "rpc.service" => service_name,
+                )
+                .increment(1);
+                return Err(HandlerError::IngressLimitExceeded(retry_after));
+            }
+        };
+
         // Check if Idempotency-Key is available
         let idempotency_key = parse_idempotency(req.headers())?;
-        if idempotency_key.is_some()
-            && invocation_target_meta.target_ty
-                == InvocationTargetType::Workflow(WorkflowHandlerType::Workflow)
-        {
+        let is_workflow_run = invocation_target_meta.target_ty
+            == InvocationTargetType::Workflow(WorkflowHandlerType::Workflow);
+        if idempotency_key.is_some() && is_workflow_run {
             return Err(HandlerError::UnsupportedIdempotencyKey);
         }
 
-        // Compute retention values
-        let invocation_retention =
-            invocation_target_meta.compute_retention(idempotency_key.is_some());
+        // Without Idempotency-Key, the handler might ask to derive one from the request content
+        let derived_idempotency_key_window = invocation_target_meta
+            .derived_idempotency_key_window
+            .filter(|_| idempotency_key.is_none() && !is_workflow_run);
 
-        // Craft Invocation Target and Id
+        // Craft Invocation Target
         let invocation_target = if let TargetType::Keyed { key } = target {
             match invocation_target_meta.target_ty {
                 InvocationTargetType::VirtualObject(handler_ty) => {
@@ -141,18 +175,18 @@ where
         } else {
             InvocationTarget::service(&*service_name, &*handler_name)
         };
-        let invocation_id = InvocationId::generate(&invocation_target, idempotency_key.as_deref());
+        let request_signature = self
+            .request_signatures
+            .iter()
+            .find(|options| options.services.contains(&service_name))
+            .cloned();
 
-        let result = async move {
-            let ingress_span_context =
-                prepare_tracing_span(&invocation_id, &invocation_target, &req);
-
-            debug!(
-                restate.invocation.id = %invocation_id,
-                restate.invocation.target = %invocation_target.short(),
-                "Processing invocation request"
-            );
+        let cacheable = invocation_target_meta.side_effect_free
+            && matches!(invoke_ty, InvokeType::Call)
+            && idempotency_key.is_none()
+            && derived_idempotency_key_window.is_none();
 
+        let result = async move {
             let (parts, body) = req.into_parts();
 
             // Check HTTP Method
@@ -161,28 +195,94 @@ where
             }
 
             // Collect body
-            let body = body
-                .collect()
-                .await
-                .map_err(|e| HandlerError::Body(e.into()))?
-                .to_bytes();
+            let body = self.collect_body(body).await?;
             trace!(rpc.request = ?body);
 
+            if let Some(options) = request_signature {
+                verify_signature(&options, &parts, &body, SystemTime::now())?;
+            }
+
             // Validate content-type and body
-            invocation_target_meta.input_rules.validate(
-                parts
-                    .headers
-                    .get(header::CONTENT_TYPE)
-                    .map(|h| {
-                        h.to_str()
-                            .map_err(|e| HandlerError::BadHeader(header::CONTENT_TYPE, e))
-                    })
-                    .transpose()?,
-                &body,
-            )?;
+            let content_type = parts
+                .headers
+                .get(header::CONTENT_TYPE)
+                .map(|h| {
+                    h.to_str()
+                        .map_err(|e| HandlerError::BadHeader(header::CONTENT_TYPE, e))
+                })
+                .transpose()?;
+            invocation_target_meta
+                .input_rules
+                .validate(content_type, &body)?;
+            if self.validate_json_schemas {
+                invocation_target_meta
+                    .input_rules
+                    .validate_json_schema(content_type, &body)?;
+            }
+
+            // Compute idempotency key, retention values and invocation id
+            let (idempotency_key, invocation_retention) = match derived_idempotency_key_window {
+                Some(window) => (
+                    Some(derive_idempotency_key(
+                        &invocation_target,
+                        content_type,
+                        &body,
+                    )),
+                    invocation_target_meta.compute_derived_idempotency_key_retention(window),
+                ),
+                None => {
+                    let invocation_retention =
+                        invocation_target_meta.compute_retention(idempotency_key.is_some());
+                    (idempotency_key, invocation_retention)
+                }
+            };
+            let invocation_id =
+                InvocationId::generate(&invocation_target, idempotency_key.as_deref());
+            // Indexed only once the signature is verified, so that unsigned requests can't
+            // claim the request ids of signed ones
+            self.request_id_index.record(&parts.headers, invocation_id);
+
+            // Browsers can follow up on the workflow run through a session token
+            let session_token = self
+                .session_tokens
+                .as_deref()
+                .filter(|options| {
+                    is_workflow_run && options.issues_tokens_for(invocation_target.service_name())
+                })
+                .map(|options| {
+                    issue_session_token(
+                        options,
+                        invocation_id,
+                        &invocation_target,
+                        SystemTime::now(),
+                    )
+                });
+
+            let ingress_span_context =
+                prepare_tracing_span(&invocation_id, &invocation_target, &parts.extensions);
 
-            // Parse delay query parameter
-            let delay = parse_delay(parts.uri.query())?;
+            debug!(
+                restate.invocation.id = %invocation_id,
+                restate.invocation.target = %invocation_target.short(),
+                "Processing invocation request"
+            );
+
+            let response_cache = match self.response_cache {
+                Some(response_cache) if cacheable => {
+                    if let Some(response) = response_cache.get(&invocation_target, &body) {
+                        trace!("Serving the response from the cache");
+                        return Ok(response);
+                    }
+                    Some((response_cache, invocation_target.clone()))
+                }
+                _ => None,
+            };
+
+            // Parse delay query parameter or header
+            let delay = parse_delay(parts.uri.query(), &parts.headers)?;
+
+            // Parse the time the caller is willing to wait, the invocation fails once it's over
+            let timeout = parse_timeout(&parts.headers)?;
 
             // Get headers
             let headers = parse_headers(parts)?;
@@ -196,18 +296,40 @@ where
                 invocation_request_header.idempotency_key = Some(key);
             }
             invocation_request_header.headers = headers;
+            if let Some(timeout) = timeout {
+                // For delayed invocations, the timeout starts when the invocation is executed
+                invocation_request_header.with_deadline(
+                    (SystemTime::now() + delay.unwrap_or_default() + timeout).into(),
+                );
+            }
 
             match invoke_ty {
                 InvokeType::Call => {
                     if delay.is_some() {
                         return Err(HandlerError::UnsupportedDelay);
                     }
-                    Self::handle_service_call(
-                        Arc::new(InvocationRequest::new(invocation_request_header, body)),
+                    let mut response = Self::handle_service_call(
+                        Arc::new(InvocationRequest::new(
+                            invocation_request_header,
+                            body.clone(),
+                        )),
                         invocation_target_meta,
                         self.dispatcher,
                     )
-                    .await
+                    .await?;
+                    if let Some(session_token) = &session_token {
+                        response
+                            .headers_mut()
+                            .insert(X_RESTATE_SESSION_TOKEN, session_token_header(session_token));
+                    }
+                    Ok(match response_cache {
+                        Some((response_cache, invocation_target)) => {
+                            response_cache
+                                .put(&invocation_target, &body, response)
+                                .await
+                        }
+                        None => response,
+                    })
                 }
                 InvokeType::Send => {
                     invocation_request_header.execution_time =
@@ -215,6 +337,7 @@ where
 
                     Self::handle_service_send(
                         Arc::new(InvocationRequest::new(invocation_request_header, body)),
+                        session_token,
                         self.dispatcher,
                     )
                     .await
@@ -255,6 +378,7 @@ where
 
     async fn handle_service_send(
         invocation_request: Arc<InvocationRequest>,
+        session_token: Option<String>,
         dispatcher: Dispatcher,
     ) -> Result<Response<Full<Bytes>>, HandlerError> {
         let invocation_id = invocation_request.invocation_id();
@@ -263,10 +387,19 @@ where
         let response = dispatcher.send(invocation_request).await?;
 
         trace!("Complete external HTTP send request successfully");
-        Ok(Response::builder()
+        let mut response_builder = Response::builder()
             .status(StatusCode::ACCEPTED)
             .header(header::CONTENT_TYPE, APPLICATION_JSON)
-            .header(X_RESTATE_ID, invocation_id.to_string())
+            .header(X_RESTATE_ID, invocation_id.to_string());
+        if let Some(consistency_token) = response.consistency_token {
+            response_builder =
+                response_builder.header(X_RESTATE_CONSISTENCY_TOKEN, consistency_token.to_string());
+        }
+        if let Some(session_token) = &session_token {
+            response_builder = response_builder
+                .header(X_RESTATE_SESSION_TOKEN, session_token_header(session_token));
+        }
+        Ok(response_builder
             .body(Full::new(
                 serde_json::to_vec(&SendResponse {
                     invocation_id,
@@ -287,6 +420,7 @@ where
                     } else {
                         SendStatus::PreviouslyAccepted
                     },
+                    session_token,
                 })
                 .unwrap()
                 .into(),
@@ -295,6 +429,10 @@ where
     }
 }
 
+fn session_token_header(session_token: &str) -> HeaderValue {
+    HeaderValue::from_str(session_token).expect("session tokens are URL-safe base64")
+}
+
 fn parse_headers(parts: http::request::Parts) -> Result<Vec<Header>, HandlerError> {
     let mut headers = Vec::with_capacity(1 + parts.headers.keys_len());
 
@@ -311,7 +449,13 @@ fn parse_headers(parts: http::request::Parts) -> Result<Vec<Header>, HandlerErro
             || k == header::HOST
             || k == IDEMPOTENCY_KEY
             || k == IDEMPOTENCY_EXPIRES
+            || k == X_RESTATE_TIMEOUT
+            || k == GRPC_TIMEOUT
+            || k == DEADLINE_HEADER_NAME
+            || k == X_RESTATE_DELAY
         {
+            // The timeout headers are turned into the deadline header, set by the ingress only,
+            // and the delay into the execution time of the invocation
             continue;
         }
 
@@ -329,26 +473,82 @@ fn parse_headers(parts: http::request::Parts) -> Result<Vec<Header>, HandlerErro
 #[serde(transparent)]
 struct DurationQueryParam(#[serde_as(as = "restate_time_util::FriendlyDuration")] Duration);
 
-fn parse_delay(query: Option<&str>) -> Result<Option<Duration>, HandlerError> {
-    if query.is_none() {
-        return Ok(None);
-    }
+/// Parses the delay from either the `delay` query parameter, or the `x-restate-delay` header.
+fn parse_delay(query: Option<&str>, headers: &HeaderMap) -> Result<Option<Duration>, HandlerError> {
+    let parse = |delay: &str| {
+        DurationQueryParam::deserialize(delay.into_deserializer())
+            .map(|d| Some(d.0))
+            .map_err(|e: serde::de::value::Error| HandlerError::BadDelayDuration(e.to_string()))
+    };
 
-    for (k, v) in url::form_urlencoded::parse(query.unwrap().as_bytes()) {
-        if k.eq_ignore_ascii_case(DELAY_QUERY_PARAM) {
-            return Ok(Some(
-                DurationQueryParam::deserialize(v.as_ref().into_deserializer())
-                    .map_err(|e: serde::de::value::Error| {
-                        HandlerError::BadDelayDuration(e.to_string())
-                    })?
-                    .0,
-            ));
+    if let Some(query) = query {
+        for (k, v) in url::form_urlencoded::parse(query.as_bytes()) {
+            if k.eq_ignore_ascii_case(DELAY_QUERY_PARAM) {
+                return parse(v.as_ref());
+            }
         }
     }
 
+    if let Some(delay) = headers.get(X_RESTATE_DELAY) {
+        return parse(
+            delay
+                .to_str()
+                .map_err(|e| HandlerError::BadHeader(X_RESTATE_DELAY, e))?,
+        );
+    }
+
     Ok(None)
 }
 
+/// Parses the timeout from either `x-restate-timeout`, or the `grpc-timeout` header set by gRPC
+/// clients with a deadline.
+fn parse_timeout(headers: &HeaderMap) -> Result<Option<Duration>, HandlerError> {
+    if let Some(timeout) = headers.get(X_RESTATE_TIMEOUT) {
+        let timeout = timeout
+            .to_str()
+            .map_err(|e| HandlerError::BadHeader(X_RESTATE_TIMEOUT, e))?;
+        return Ok(Some(
+            DurationQueryParam::deserialize(timeout.into_deserializer())
+                .map_err(|e: serde::de::value::Error| {
+                    HandlerError::BadTimeout(X_RESTATE_TIMEOUT, e.to_string())
+                })?
+                .0,
+        ));
+    }
+
+    if let Some(timeout) = headers.get(GRPC_TIMEOUT) {
+        let timeout = timeout
+            .to_str()
+            .map_err(|e| HandlerError::BadHeader(GRPC_TIMEOUT, e))?;
+        return parse_grpc_timeout(timeout)
+            .map(Some)
+            .ok_or_else(|| HandlerError::BadTimeout(GRPC_TIMEOUT, timeout.to_owned()));
+    }
+
+    Ok(None)
+}
+
+/// See https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md#requests
+fn parse_grpc_timeout(timeout: &str) -> Option<Duration> {
+    if timeout.len() < 2 || timeout.len() > 9 {
+        return None;
+    }
+    let (value, unit) = timeout.split_at(timeout.len() - 1);
+    if !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: u64 = value.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(value * 60 * 60),
+        "M" => Duration::from_secs(value * 60),
+        "S" => Duration::from_secs(value),
+        "m" => Duration::from_millis(value),
+        "u" => Duration::from_micros(value),
+        "n" => Duration::from_nanos(value),
+        _ => return None,
+    })
+}
+
 fn parse_idempotency(headers: &HeaderMap) -> Result<Option<ByteString>, HandlerError> {
     let idempotency_key = if let Some(idempotency_key) = headers.get(IDEMPOTENCY_KEY) {
         ByteString::from(
@@ -363,6 +563,66 @@ fn parse_idempotency(headers: &HeaderMap) -> Result<Option<ByteString>, HandlerE
     Ok(Some(idempotency_key))
 }
 
+/// Derives the idempotency key of a request from its target and content. JSON payloads are
+/// canonicalized first, so that requests differing only in the formatting or in the order of
+/// the object fields are deduplicated too.
+fn derive_idempotency_key(
+    invocation_target: &InvocationTarget,
+    content_type: Option<&str>,
+    body: &Bytes,
+) -> ByteString {
+    let mut hasher = Sha256::new();
+    hasher.update(invocation_target.service_name().as_bytes());
+    hasher.update([0]);
+    if let Some(key) = invocation_target.key() {
+        hasher.update(key.as_bytes());
+    }
+    hasher.update([0]);
+    hasher.update(invocation_target.handler_name().as_bytes());
+    hasher.update([0]);
+
+    let json_payload = content_type
+        .is_some_and(|content_type| content_type.starts_with("application/json"))
+        .then(|| serde_json::from_slice::<serde_json::Value>(body).ok())
+        .flatten();
+    match json_payload {
+        Some(json_payload) => hash_canonical_json(&mut hasher, &json_payload),
+        None => hasher.update(body),
+    }
+
+    let mut key = String::from("derived-");
+    for byte in hasher.finalize() {
+        key.push_str(&format!("{byte:02x}"));
+    }
+    ByteString::from(key)
+}
+
+fn hash_canonical_json(hasher: &mut Sha256, value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            hasher.update(b"{");
+            for (key, value) in entries {
+                hash_canonical_json(hasher, &serde_json::Value::String(key.clone()));
+                hasher.update(b":");
+                hash_canonical_json(hasher, value);
+                hasher.update(b",");
+            }
+            hasher.update(b"}");
+        }
+        serde_json::Value::Array(values) => {
+            hasher.update(b"[");
+            for value in values {
+                hash_canonical_json(hasher, value);
+                hasher.update(b",");
+            }
+            hasher.update(b"]");
+        }
+        scalar => hasher.update(scalar.to_string().as_bytes()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,24 +630,74 @@ mod tests {
     #[test]
     fn delay() {
         assert_eq!(
-            parse_delay(Some("delay=PT60S")).unwrap().unwrap(),
+            parse_delay(Some("delay=PT60S"), &HeaderMap::new())
+                .unwrap()
+                .unwrap(),
             Duration::from_secs(60),
         );
         assert_eq!(
-            parse_delay(Some("delay=60+sec")).unwrap().unwrap(),
+            parse_delay(Some("delay=60+sec"), &HeaderMap::new())
+                .unwrap()
+                .unwrap(),
             Duration::from_secs(60),
         );
         assert_eq!(
-            parse_delay(Some("delay=60sec")).unwrap().unwrap(),
+            parse_delay(Some("delay=60sec"), &HeaderMap::new())
+                .unwrap()
+                .unwrap(),
             Duration::from_secs(60),
         );
         assert_eq!(
-            parse_delay(Some("delay=60ms")).unwrap().unwrap(),
+            parse_delay(Some("delay=60ms"), &HeaderMap::new())
+                .unwrap()
+                .unwrap(),
             Duration::from_millis(60),
         );
         assert_eq!(
-            parse_delay(Some("delay=60000ms")).unwrap().unwrap(),
+            parse_delay(Some("delay=60000ms"), &HeaderMap::new())
+                .unwrap()
+                .unwrap(),
             Duration::from_millis(60000),
         );
+
+        let mut headers = HeaderMap::new();
+        assert!(parse_delay(None, &headers).unwrap().is_none());
+        headers.insert(X_RESTATE_DELAY, "5min".parse().unwrap());
+        assert_eq!(
+            parse_delay(None, &headers).unwrap().unwrap(),
+            Duration::from_secs(300),
+        );
+        // the query parameter takes precedence
+        assert_eq!(
+            parse_delay(Some("delay=PT60S"), &headers).unwrap().unwrap(),
+            Duration::from_secs(60),
+        );
+        headers.insert(X_RESTATE_DELAY, "soon".parse().unwrap());
+        assert!(parse_delay(None, &headers).is_err());
+    }
+
+    #[test]
+    fn timeout() {
+        let mut headers = HeaderMap::new();
+        assert!(parse_timeout(&headers).unwrap().is_none());
+
+        headers.insert(GRPC_TIMEOUT, "1500m".parse().unwrap());
+        assert_eq!(
+            parse_timeout(&headers).unwrap().unwrap(),
+            Duration::from_millis(1500),
+        );
+
+        // x-restate-timeout takes precedence
+        headers.insert(X_RESTATE_TIMEOUT, "10sec".parse().unwrap());
+        assert_eq!(
+            parse_timeout(&headers).unwrap().unwrap(),
+            Duration::from_secs(10),
+        );
+
+        assert_eq!(parse_grpc_timeout("2H"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_grpc_timeout("10"), None);
+        assert_eq!(parse_grpc_timeout("S"), None);
+        assert_eq!(parse_grpc_timeout("-1S"), None);
+        assert_eq!(parse_grpc_timeout("123456789S"), None);
     }
 }
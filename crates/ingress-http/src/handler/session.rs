@@ -0,0 +1,102 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::SystemTime;
+
+use super::Handler;
+use super::HandlerError;
+use super::path_parsing::{InvokeType, ServiceRequestType, SessionRequestType, TargetType};
+use super::session_token::{SessionTokenError, verify_session_token};
+
+use crate::RequestDispatcher;
+use bytes::Bytes;
+use http::{Request, Response};
+use http_body_util::Full;
+use restate_types::config::SessionTokenScope;
+use restate_types::invocation::{InvocationQuery, InvocationTargetType, WorkflowHandlerType};
+use restate_types::schema::invocation_target::InvocationTargetResolver;
+use tracing::info;
+
+impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher>
+where
+    Schemas: InvocationTargetResolver + Clone + Send + Sync + 'static,
+    Dispatcher: RequestDispatcher + Clone + Send + Sync + 'static,
+{
+    /// Serves the requests on behalf of the workflow run the session token was issued for.
+    pub(crate) async fn handle_session<B: http_body::Body>(
+        self,
+        req: Request<B>,
+        session_request_type: SessionRequestType,
+    ) -> Result<Response<Full<Bytes>>, HandlerError>
+    where
+        <B as http_body::Body>::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let Some(options) = self.session_tokens.as_deref() else {
+            return Err(HandlerError::NotFound);
+        };
+        let claims = verify_session_token(options, req.headers(), SystemTime::now())?;
+
+        info!(
+            restate.invocation.id = %claims.invocation_id,
+            "Processing session request"
+        );
+
+        match session_request_type {
+            SessionRequestType::Attach => {
+                claims.check_scope(SessionTokenScope::Attach)?;
+                self.handle_invocation_attach(
+                    req,
+                    InvocationQuery::Invocation(claims.invocation_id),
+                )
+                .await
+            }
+            SessionRequestType::GetOutput => {
+                claims.check_scope(SessionTokenScope::Output)?;
+                self.handle_invocation_get_output(
+                    req,
+                    InvocationQuery::Invocation(claims.invocation_id),
+                )
+                .await
+            }
+            SessionRequestType::CallSharedHandler(handler) => {
+                claims.check_scope(SessionTokenScope::SharedHandlers)?;
+
+                // The token must not give access to the workflow run handler itself
+                let target_ty = self
+                    .schemas
+                    .pinned()
+                    .resolve_latest_invocation_target(&claims.workflow_name, &handler)
+                    .ok_or_else(|| {
+                        HandlerError::ServiceHandlerNotFound(
+                            claims.workflow_name.clone(),
+                            handler.clone(),
+                        )
+                    })?
+                    .target_ty;
+                if target_ty != InvocationTargetType::Workflow(WorkflowHandlerType::Shared) {
+                    return Err(SessionTokenError::OutOfScope("call non-shared handlers").into());
+                }
+
+                self.handle_service_request(
+                    req,
+                    ServiceRequestType {
+                        name: claims.workflow_name,
+                        handler,
+                        target: TargetType::Keyed {
+                            key: claims.workflow_key,
+                        },
+                        invoke_ty: InvokeType::Call,
+                    },
+                )
+                .await
+            }
+        }
+    }
+}
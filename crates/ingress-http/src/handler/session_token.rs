@@ -0,0 +1,243 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use http::{HeaderMap, HeaderName};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use restate_types::config::{SessionTokenOptions, SessionTokenScope};
+use restate_types::identifiers::InvocationId;
+use restate_types::invocation::InvocationTarget;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Carries the session token, both when issued and when redeemed.
+pub(crate) const X_RESTATE_SESSION_TOKEN: HeaderName =
+    HeaderName::from_static("x-restate-session-token");
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SessionTokenError {
+    #[error("missing x-restate-session-token header")]
+    Missing,
+    #[error("malformed session token")]
+    Malformed,
+    #[error("session token signature mismatch")]
+    Mismatch,
+    #[error("the session token expired")]
+    Expired,
+    #[error("the session token doesn't allow to {0}")]
+    OutOfScope(&'static str),
+}
+
+/// Claims signed into a session token, scoping it to a single workflow run.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SessionClaims {
+    pub(crate) invocation_id: InvocationId,
+    pub(crate) workflow_name: String,
+    pub(crate) workflow_key: String,
+    /// Seconds since the Unix epoch
+    expires_at: u64,
+    scopes: Vec<SessionTokenScope>,
+}
+
+impl SessionClaims {
+    pub(crate) fn check_scope(&self, scope: SessionTokenScope) -> Result<(), SessionTokenError> {
+        if self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            Err(SessionTokenError::OutOfScope(match scope {
+                SessionTokenScope::Attach => "attach to the workflow",
+                SessionTokenScope::Output => "get the workflow output",
+                SessionTokenScope::SharedHandlers => "call the workflow shared handlers",
+            }))
+        }
+    }
+}
+
+/// Issues a token for the run of the given workflow, formatted as `<claims>.<signature>`, with
+/// both the JSON claims and their HMAC-SHA256 encoded in URL-safe base64.
+pub(crate) fn issue_session_token(
+    options: &SessionTokenOptions,
+    invocation_id: InvocationId,
+    workflow_target: &InvocationTarget,
+    now: SystemTime,
+) -> String {
+    let expires_at = (now + options.ttl.to_std())
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let claims = SessionClaims {
+        invocation_id,
+        workflow_name: workflow_target.service_name().to_string(),
+        workflow_key: workflow_target
+            .key()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        expires_at,
+        scopes: options.scopes.clone(),
+    };
+
+    let claims = BASE64_URL_SAFE_NO_PAD
+        .encode(serde_json::to_vec(&claims).expect("session claims must be serializable"));
+    let mut mac = new_mac(options);
+    mac.update(claims.as_bytes());
+    let signature = BASE64_URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{claims}.{signature}")
+}
+
+/// Verifies the session token carried by the given headers, returning its claims.
+pub(crate) fn verify_session_token(
+    options: &SessionTokenOptions,
+    headers: &HeaderMap,
+    now: SystemTime,
+) -> Result<SessionClaims, SessionTokenError> {
+    let token = headers
+        .get(X_RESTATE_SESSION_TOKEN)
+        .ok_or(SessionTokenError::Missing)?
+        .to_str()
+        .map_err(|_| SessionTokenError::Malformed)?;
+    let (claims, signature) = token.split_once('.').ok_or(SessionTokenError::Malformed)?;
+    let signature = BASE64_URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| SessionTokenError::Malformed)?;
+
+    let mut mac = new_mac(options);
+    mac.update(claims.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| SessionTokenError::Mismatch)?;
+
+    let claims: SessionClaims = BASE64_URL_SAFE_NO_PAD
+        .decode(claims)
+        .ok()
+        .and_then(|claims| serde_json::from_slice(&claims).ok())
+        .ok_or(SessionTokenError::Malformed)?;
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    if claims.expires_at <= now {
+        return Err(SessionTokenError::Expired);
+    }
+    Ok(claims)
+}
+
+fn new_mac(options: &SessionTokenOptions) -> HmacSha256 {
+    HmacSha256::new_from_slice(options.secret.as_bytes()).expect("HMAC accepts keys of any size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use http::HeaderValue;
+    use restate_time_util::FriendlyDuration;
+    use restate_types::invocation::WorkflowHandlerType;
+
+    fn options(scopes: Vec<SessionTokenScope>) -> SessionTokenOptions {
+        SessionTokenOptions {
+            secret: "s3cr3t".to_owned(),
+            ttl: FriendlyDuration::from_secs(60),
+            workflows: vec![],
+            scopes,
+        }
+    }
+
+    fn headers(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            X_RESTATE_SESSION_TOKEN,
+            HeaderValue::from_str(token).unwrap(),
+        );
+        headers
+    }
+
+    fn workflow_target() -> InvocationTarget {
+        InvocationTarget::workflow("Signup", "alice", "run", WorkflowHandlerType::Workflow)
+    }
+
+    #[test]
+    fn round_trip() {
+        let options = options(vec![SessionTokenScope::Attach]);
+        let invocation_id = InvocationId::mock_random();
+        let now = SystemTime::now();
+
+        let token = issue_session_token(&options, invocation_id, &workflow_target(), now);
+        let claims = verify_session_token(&options, &headers(&token), now).unwrap();
+
+        assert_eq!(claims.invocation_id, invocation_id);
+        assert_eq!(claims.workflow_name, "Signup");
+        assert_eq!(claims.workflow_key, "alice");
+        assert!(claims.check_scope(SessionTokenScope::Attach).is_ok());
+        assert!(matches!(
+            claims.check_scope(SessionTokenScope::SharedHandlers),
+            Err(SessionTokenError::OutOfScope(_))
+        ));
+    }
+
+    #[test]
+    fn reject_expired_token() {
+        let options = options(vec![SessionTokenScope::Attach]);
+        let now = SystemTime::now();
+
+        let token = issue_session_token(
+            &options,
+            InvocationId::mock_random(),
+            &workflow_target(),
+            now,
+        );
+
+        assert!(matches!(
+            verify_session_token(&options, &headers(&token), now + Duration::from_secs(61)),
+            Err(SessionTokenError::Expired)
+        ));
+    }
+
+    #[test]
+    fn reject_tampered_token() {
+        let options = options(vec![SessionTokenScope::Attach]);
+        let now = SystemTime::now();
+
+        let token = issue_session_token(
+            &options,
+            InvocationId::mock_random(),
+            &workflow_target(),
+            now,
+        );
+        let (encoded_claims, signature) = token.split_once('.').unwrap();
+
+        // Claims widening the scopes, with the original signature
+        let mut claims: SessionClaims =
+            serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(encoded_claims).unwrap())
+                .unwrap();
+        claims.scopes.push(SessionTokenScope::SharedHandlers);
+        let tampered = format!(
+            "{}.{signature}",
+            BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap())
+        );
+        assert!(matches!(
+            verify_session_token(&options, &headers(&tampered), now),
+            Err(SessionTokenError::Mismatch)
+        ));
+
+        // Token signed with another secret
+        let other_options = SessionTokenOptions {
+            secret: "other".to_owned(),
+            ..options.clone()
+        };
+        assert!(matches!(
+            verify_session_token(&other_options, &headers(&token), now),
+            Err(SessionTokenError::Mismatch)
+        ));
+    }
+}
@@ -0,0 +1,444 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use http::request::Parts;
+use http::{HeaderMap, header};
+use sha2::{Digest, Sha256};
+
+use restate_types::config::{RequestSignatureOptions, RequestSignatureScheme, SignatureEncoding};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const AWS_SIGV4_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const AWS_SIGV4_TERMINATOR: &str = "aws4_request";
+const X_AMZ_DATE: &str = "x-amz-date";
+const X_AMZ_CONTENT_SHA256: &str = "x-amz-content-sha256";
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SignatureError {
+    #[error("missing header {0}")]
+    MissingHeader(String),
+    #[error("malformed header {0}")]
+    MalformedHeader(String),
+    #[error("the request was signed too long ago, or in the future")]
+    Stale,
+    #[error("unknown credential")]
+    UnknownCredential,
+    #[error("signature mismatch")]
+    Mismatch,
+}
+
+/// Verifies the signature of a request with the given (already collected) body.
+pub(crate) fn verify_signature(
+    options: &RequestSignatureOptions,
+    parts: &Parts,
+    body: &[u8],
+    now: SystemTime,
+) -> Result<(), SignatureError> {
+    let max_clock_skew = options.max_clock_skew.to_std();
+    match &options.scheme {
+        RequestSignatureScheme::Hmac {
+            secret,
+            signature_header,
+            signature_prefix,
+            encoding,
+            timestamp_header,
+        } => {
+            let malformed = || SignatureError::MalformedHeader(signature_header.clone());
+
+            let mut signature = header_str(&parts.headers, signature_header)?;
+            if let Some(prefix) = signature_prefix {
+                signature = signature
+                    .strip_prefix(prefix.as_str())
+                    .ok_or_else(malformed)?;
+            }
+            let signature = match encoding {
+                SignatureEncoding::Hex => hex::decode(signature).ok(),
+                SignatureEncoding::Base64 => BASE64_STANDARD.decode(signature).ok(),
+            }
+            .ok_or_else(malformed)?;
+
+            let mut mac = new_mac(secret.as_bytes());
+            // The timestamp is signed too, so that stale requests can't be replayed
+            if let Some(timestamp_header) = timestamp_header {
+                let timestamp = header_str(&parts.headers, timestamp_header)?;
+                let signed_at = timestamp
+                    .parse()
+                    .map_err(|_| SignatureError::MalformedHeader(timestamp_header.clone()))?;
+                check_clock_skew(signed_at, now, max_clock_skew)?;
+
+                mac.update(timestamp.as_bytes());
+                mac.update(b".");
+            }
+            mac.update(body);
+            mac.verify_slice(&signature)
+                .map_err(|_| SignatureError::Mismatch)
+        }
+        RequestSignatureScheme::AwsSigv4 {
+            access_key_id,
+            secret_access_key,
+            region,
+            service,
+        } => {
+            let authorization = header_str(&parts.headers, header::AUTHORIZATION.as_str())?;
+            let authorization = AwsAuthorization::parse(authorization).ok_or_else(|| {
+                SignatureError::MalformedHeader(header::AUTHORIZATION.to_string())
+            })?;
+            if authorization.access_key_id != access_key_id
+                || authorization.region != region
+                || authorization.service != service
+            {
+                return Err(SignatureError::UnknownCredential);
+            }
+            // Without these, the signature could be replayed against another host or time
+            for required in [header::HOST.as_str(), X_AMZ_DATE] {
+                if !authorization.signed_headers.contains(&required) {
+                    return Err(SignatureError::MalformedHeader(
+                        header::AUTHORIZATION.to_string(),
+                    ));
+                }
+            }
+
+            let amz_date = header_str(&parts.headers, X_AMZ_DATE)?;
+            let signed_at = NaiveDateTime::parse_from_str(amz_date, "%Y%m%dT%H%M%SZ")
+                .map_err(|_| SignatureError::MalformedHeader(X_AMZ_DATE.to_owned()))?
+                .and_utc()
+                .timestamp();
+            if !amz_date.starts_with(authorization.date) {
+                return Err(SignatureError::MalformedHeader(X_AMZ_DATE.to_owned()));
+            }
+            check_clock_skew(signed_at, now, max_clock_skew)?;
+
+            // The payload is always part of the signature, unsigned payloads are not accepted
+            let payload_hash = hex::encode(Sha256::digest(body));
+            if let Some(content_sha256) = parts.headers.get(X_AMZ_CONTENT_SHA256)
+                && content_sha256 != payload_hash.as_str()
+            {
+                return Err(SignatureError::Mismatch);
+            }
+
+            let canonical_request =
+                canonical_request(parts, &authorization.signed_headers, &payload_hash)?;
+            let string_to_sign = format!(
+                "{AWS_SIGV4_ALGORITHM}\n{amz_date}\n{}/{region}/{service}/{AWS_SIGV4_TERMINATOR}\n{}",
+                authorization.date,
+                hex::encode(Sha256::digest(canonical_request))
+            );
+
+            let signature = hex::decode(authorization.signature)
+                .map_err(|_| SignatureError::MalformedHeader(header::AUTHORIZATION.to_string()))?;
+            let mut mac = new_mac(&aws_signing_key(
+                secret_access_key,
+                authorization.date,
+                region,
+                service,
+            ));
+            mac.update(string_to_sign.as_bytes());
+            mac.verify_slice(&signature)
+                .map_err(|_| SignatureError::Mismatch)
+        }
+    }
+}
+
+fn new_mac(key: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, SignatureError> {
+    headers
+        .get(name)
+        .ok_or_else(|| SignatureError::MissingHeader(name.to_owned()))?
+        .to_str()
+        .map_err(|_| SignatureError::MalformedHeader(name.to_owned()))
+}
+
+fn check_clock_skew(
+    signed_at_secs: i64,
+    now: SystemTime,
+    max_clock_skew: Duration,
+) -> Result<(), SignatureError> {
+    let now_secs = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .try_into()
+        .unwrap_or(i64::MAX);
+    if signed_at_secs.abs_diff(now_secs) > max_clock_skew.as_secs() {
+        return Err(SignatureError::Stale);
+    }
+    Ok(())
+}
+
+/// Fields of an `Authorization: AWS4-HMAC-SHA256 Credential=..., SignedHeaders=..., Signature=...`
+/// header.
+struct AwsAuthorization<'a> {
+    access_key_id: &'a str,
+    date: &'a str,
+    region: &'a str,
+    service: &'a str,
+    signed_headers: Vec<&'a str>,
+    signature: &'a str,
+}
+
+impl<'a> AwsAuthorization<'a> {
+    fn parse(value: &'a str) -> Option<Self> {
+        let mut credential = None;
+        let mut signed_headers = None;
+        let mut signature = None;
+        for field in value.strip_prefix(AWS_SIGV4_ALGORITHM)?.split(',') {
+            match field.trim().split_once('=')? {
+                ("Credential", v) => credential = Some(v),
+                ("SignedHeaders", v) => signed_headers = Some(v),
+                ("Signature", v) => signature = Some(v),
+                _ => {}
+            }
+        }
+
+        let mut scope = credential?.splitn(5, '/');
+        let access_key_id = scope.next()?;
+        let date = scope.next()?;
+        let region = scope.next()?;
+        let service = scope.next()?;
+        if scope.next()? != AWS_SIGV4_TERMINATOR {
+            return None;
+        }
+
+        Some(Self {
+            access_key_id,
+            date,
+            region,
+            service,
+            signed_headers: signed_headers?.split(';').collect(),
+            signature: signature?,
+        })
+    }
+}
+
+fn canonical_request(
+    parts: &Parts,
+    signed_headers: &[&str],
+    payload_hash: &str,
+) -> Result<String, SignatureError> {
+    // The path is encoded once more, as done by the signers of services other than S3
+    let path = parts
+        .uri
+        .path()
+        .split('/')
+        .map(urlencoding::encode)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut query = parts
+        .uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (aws_uri_encode(name), aws_uri_encode(value))
+        })
+        .collect::<Vec<_>>();
+    query.sort();
+    let query = query
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut canonical_headers = String::new();
+    for name in signed_headers {
+        let mut values = parts
+            .headers
+            .get_all(*name)
+            .iter()
+            .map(|value| {
+                value
+                    .to_str()
+                    .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .map_err(|_| SignatureError::MalformedHeader((*name).to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // HTTP/2 requests carry the host in the :authority pseudo-header
+        if values.is_empty()
+            && *name == header::HOST.as_str()
+            && let Some(authority) = parts.uri.authority()
+        {
+            values.push(authority.to_string());
+        }
+        if values.is_empty() {
+            return Err(SignatureError::MissingHeader((*name).to_owned()));
+        }
+        canonical_headers.push_str(&format!("{name}:{}\n", values.join(",")));
+    }
+
+    Ok(format!(
+        "{}\n{path}\n{query}\n{canonical_headers}\n{}\n{payload_hash}",
+        parts.method,
+        signed_headers.join(";"),
+    ))
+}
+
+/// Normalizes a percent-encoded query component to the encoding used by SigV4.
+fn aws_uri_encode(component: &str) -> String {
+    urlencoding::encode_binary(&urlencoding::decode_binary(component.as_bytes())).into_owned()
+}
+
+fn aws_signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let mut key = format!("AWS4{secret_access_key}").into_bytes();
+    for part in [date, region, service, AWS_SIGV4_TERMINATOR] {
+        let mut mac = new_mac(&key);
+        mac.update(part.as_bytes());
+        key = mac.finalize().into_bytes().to_vec();
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use http::Request;
+    use restate_time_util::FriendlyDuration;
+
+    fn options(scheme: RequestSignatureScheme) -> RequestSignatureOptions {
+        RequestSignatureOptions {
+            services: vec!["Webhooks".to_owned()],
+            max_clock_skew: FriendlyDuration::from_secs(300),
+            scheme,
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn hmac() {
+        let options = options(RequestSignatureScheme::Hmac {
+            secret: "secret".to_owned(),
+            signature_header: "x-signature".to_owned(),
+            signature_prefix: Some("sha256=".to_owned()),
+            encoding: SignatureEncoding::Hex,
+            timestamp_header: Some("x-timestamp".to_owned()),
+        });
+        let parts = |signature: &str| {
+            Request::post("http://localhost/Webhooks/handle")
+                .header("x-signature", signature)
+                .header("x-timestamp", "1700000000")
+                .body(())
+                .unwrap()
+                .into_parts()
+                .0
+        };
+        let valid =
+            parts("sha256=47b1df0ab12338b2685470b0d2b37033add7c3b2bc8172f313e77413f1bb78c8");
+
+        assert!(verify_signature(&options, &valid, b"hello", at(1_700_000_100)).is_ok());
+        assert!(matches!(
+            verify_signature(&options, &valid, b"hello!", at(1_700_000_100)),
+            Err(SignatureError::Mismatch)
+        ));
+        assert!(matches!(
+            verify_signature(&options, &valid, b"hello", at(1_700_001_000)),
+            Err(SignatureError::Stale)
+        ));
+        assert!(matches!(
+            verify_signature(&options, &parts("47b1df"), b"hello", at(1_700_000_100)),
+            Err(SignatureError::MalformedHeader(_))
+        ));
+
+        // The timestamp is mandatory when configured
+        let (without_timestamp, _) = Request::post("http://localhost/Webhooks/handle")
+            .header(
+                "x-signature",
+                "sha256=47b1df0ab12338b2685470b0d2b37033add7c3b2bc8172f313e77413f1bb78c8",
+            )
+            .body(())
+            .unwrap()
+            .into_parts();
+        assert!(matches!(
+            verify_signature(&options, &without_timestamp, b"hello", at(1_700_000_100)),
+            Err(SignatureError::MissingHeader(_))
+        ));
+    }
+
+    #[test]
+    fn hmac_without_timestamp() {
+        let options = options(RequestSignatureScheme::Hmac {
+            secret: "secret".to_owned(),
+            signature_header: "x-hub-signature-256".to_owned(),
+            signature_prefix: Some("sha256=".to_owned()),
+            encoding: SignatureEncoding::Hex,
+            timestamp_header: None,
+        });
+        let mut mac = new_mac(b"secret");
+        mac.update(b"hello");
+        let (parts, _) = Request::post("http://localhost/Webhooks/handle")
+            .header(
+                "x-hub-signature-256",
+                format!("sha256={}", hex::encode(mac.finalize().into_bytes())),
+            )
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        // Body-only signatures carry no signing time, hence they never get stale
+        assert!(verify_signature(&options, &parts, b"hello", at(1_700_000_100)).is_ok());
+        assert!(verify_signature(&options, &parts, b"hello", at(0)).is_ok());
+        assert!(matches!(
+            verify_signature(&options, &parts, b"hello!", at(1_700_000_100)),
+            Err(SignatureError::Mismatch)
+        ));
+    }
+
+    // The get-vanilla case of the AWS SigV4 test suite
+    #[test]
+    fn aws_sigv4() {
+        let options = options(RequestSignatureScheme::AwsSigv4 {
+            access_key_id: "AKIDEXAMPLE".to_owned(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_owned(),
+            region: "us-east-1".to_owned(),
+            service: "service".to_owned(),
+        });
+        let (parts, _) = Request::get("/")
+            .header("host", "example.amazonaws.com")
+            .header("x-amz-date", "20150830T123600Z")
+            .header(
+                "authorization",
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+                SignedHeaders=host;x-amz-date, \
+                Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+            )
+            .body(())
+            .unwrap()
+            .into_parts();
+        let signed_at = NaiveDateTime::parse_from_str("20150830T123600Z", "%Y%m%dT%H%M%SZ")
+            .unwrap()
+            .and_utc()
+            .timestamp() as u64;
+
+        assert!(verify_signature(&options, &parts, b"", at(signed_at + 60)).is_ok());
+        assert!(matches!(
+            verify_signature(&options, &parts, b"payload", at(signed_at + 60)),
+            Err(SignatureError::Mismatch)
+        ));
+        assert!(matches!(
+            verify_signature(&options, &parts, b"", at(signed_at + 3600)),
+            Err(SignatureError::Stale)
+        ));
+    }
+}
@@ -0,0 +1,432 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::Handler;
+use super::HandlerError;
+
+use crate::{RequestDispatcher, SubscriptionCompletion, SubscriptionTarget};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use bytes::Bytes;
+use futures::future::{AbortHandle, Abortable, BoxFuture};
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, SinkExt, StreamExt};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode, header};
+use http_body_util::Full;
+use hyper::upgrade::OnUpgrade;
+use hyper_util::rt::TokioIo;
+use restate_core::{TaskCenter, TaskKind, cancellation_watcher};
+use restate_types::errors::InvocationError;
+use restate_types::identifiers::InvocationId;
+use restate_types::invocation::client::{AttachInvocationResponse, InvocationOutputResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tracing::{debug, trace};
+
+const WEBSOCKET_VERSION: &str = "13";
+const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 1024;
+
+/// Message sent by the client over the WebSocket.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe(TargetMessage),
+    Unsubscribe(TargetMessage),
+}
+
+#[derive(Debug, Deserialize)]
+struct TargetMessage {
+    invocation_id: String,
+}
+
+/// Message pushed by the ingress over the WebSocket.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Subscribed {
+        #[serde(flatten)]
+        target: TargetId,
+    },
+    Completed {
+        #[serde(flatten)]
+        target: TargetId,
+        #[serde(flatten)]
+        result: CompletionResult,
+    },
+    Error {
+        #[serde(flatten)]
+        target: Option<TargetId>,
+        message: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TargetId {
+    InvocationId(String),
+}
+
+impl From<&SubscriptionTarget> for TargetId {
+    fn from(value: &SubscriptionTarget) -> Self {
+        match value {
+            SubscriptionTarget::Invocation(invocation_id) => {
+                TargetId::InvocationId(invocation_id.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CompletionResult {
+    /// The completion value, when it is valid JSON.
+    Value(serde_json::Value),
+    ValueBase64(String),
+    Failure(InvocationError),
+}
+
+impl CompletionResult {
+    fn success(value: Bytes) -> Self {
+        match serde_json::from_slice(&value) {
+            Ok(value) => CompletionResult::Value(value),
+            Err(_) => CompletionResult::ValueBase64(BASE64_STANDARD.encode(value)),
+        }
+    }
+}
+
+impl ServerMessage {
+    fn error(target: Option<&SubscriptionTarget>, message: impl ToString) -> Self {
+        ServerMessage::Error {
+            target: target.map(Into::into),
+            message: message.to_string(),
+        }
+    }
+
+    fn completed(target: &SubscriptionTarget, completion: SubscriptionCompletion) -> Self {
+        let result = match completion {
+            SubscriptionCompletion::Invocation(AttachInvocationResponse::Ready(output)) => {
+                match output.response {
+                    InvocationOutputResponse::Success(_, value) => CompletionResult::success(value),
+                    InvocationOutputResponse::Failure(failure) => {
+                        CompletionResult::Failure(failure)
+                    }
+                }
+            }
+            SubscriptionCompletion::Invocation(AttachInvocationResponse::NotFound) => {
+                return ServerMessage::error(Some(target), HandlerError::InvocationNotFound);
+            }
+            SubscriptionCompletion::Invocation(AttachInvocationResponse::NotSupported) => {
+                return ServerMessage::error(
+                    Some(target),
+                    "cannot subscribe to the given invocation. You can subscribe to completed invocations only when created with an idempotency key, or for workflow methods.",
+                );
+            }
+            SubscriptionCompletion::Failed(err) => {
+                return ServerMessage::error(Some(target), err);
+            }
+        };
+
+        ServerMessage::Completed {
+            target: target.into(),
+            result,
+        }
+    }
+
+    fn into_message(self) -> Message {
+        Message::text(
+            serde_json::to_string(&self).expect("Serializing ServerMessage should not fail"),
+        )
+    }
+}
+
+impl TargetMessage {
+    fn into_target(self) -> Result<SubscriptionTarget, String> {
+        InvocationId::from_str(&self.invocation_id)
+            .map(SubscriptionTarget::Invocation)
+            .map_err(|e| HandlerError::BadInvocationId(self.invocation_id, e).to_string())
+    }
+}
+
+impl<Schemas, Dispatcher> Handler<Schemas, Dispatcher>
+where
+    Dispatcher: RequestDispatcher + Clone + Send + Sync + 'static,
+{
+    /// Upgrades the request to a WebSocket, over which the client subscribes to the completion
+    /// of invocations.
+    pub(crate) fn handle_subscribe<B: http_body::Body>(
+        self,
+        mut req: Request<B>,
+    ) -> Result<Response<Full<Bytes>>, HandlerError> {
+        if req.method() != Method::GET {
+            return Err(HandlerError::MethodNotAllowed);
+        }
+        let accept_key = websocket_accept_key(req.headers())?;
+
+        let on_upgrade = hyper::upgrade::on(&mut req);
+        TaskCenter::spawn(
+            TaskKind::Ingress,
+            "ingress-subscriptions",
+            serve_subscriptions(self.dispatcher, on_upgrade),
+        )
+        .map_err(|_| HandlerError::Unavailable)?;
+
+        Ok(Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::UPGRADE, HeaderValue::from_static("websocket"))
+            .header(header::CONNECTION, HeaderValue::from_static("upgrade"))
+            .header(header::SEC_WEBSOCKET_ACCEPT, accept_key)
+            .body(Full::default())
+            .unwrap())
+    }
+}
+
+/// Validates the WebSocket handshake headers, returning the accept key.
+fn websocket_accept_key(headers: &HeaderMap) -> Result<String, HandlerError> {
+    let header_contains = |name: header::HeaderName, token: &str| {
+        headers.get_all(name).iter().any(|value| {
+            value.to_str().is_ok_and(|value| {
+                value
+                    .split(',')
+                    .any(|part| part.trim().eq_ignore_ascii_case(token))
+            })
+        })
+    };
+
+    if !header_contains(header::UPGRADE, "websocket") {
+        return Err(HandlerError::BadWebSocketHandshake(
+            "expected the 'upgrade: websocket' header",
+        ));
+    }
+    if !header_contains(header::CONNECTION, "upgrade") {
+        return Err(HandlerError::BadWebSocketHandshake(
+            "expected the 'connection: upgrade' header",
+        ));
+    }
+    if headers.get(header::SEC_WEBSOCKET_VERSION)
+        != Some(&HeaderValue::from_static(WEBSOCKET_VERSION))
+    {
+        return Err(HandlerError::BadWebSocketHandshake(
+            "expected the 'sec-websocket-version: 13' header",
+        ));
+    }
+    let key = headers
+        .get(header::SEC_WEBSOCKET_KEY)
+        .ok_or(HandlerError::BadWebSocketHandshake(
+            "missing the 'sec-websocket-key' header",
+        ))?;
+
+    Ok(derive_accept_key(key.as_bytes()))
+}
+
+type CompletionFuture = BoxFuture<'static, (SubscriptionTarget, SubscriptionCompletion)>;
+
+/// Subscriptions of a single WebSocket connection.
+struct ConnectionSubscriptions<Dispatcher> {
+    dispatcher: Dispatcher,
+    subscriptions: HashMap<SubscriptionTarget, AbortHandle>,
+    completions: FuturesUnordered<Abortable<CompletionFuture>>,
+}
+
+impl<Dispatcher> ConnectionSubscriptions<Dispatcher>
+where
+    Dispatcher: RequestDispatcher + Clone + Send + Sync + 'static,
+{
+    fn handle_client_message(&mut self, text: &str) -> Option<ServerMessage> {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(err) => return Some(ServerMessage::error(None, format!("bad message: {err}"))),
+        };
+
+        match message {
+            ClientMessage::Subscribe(target) => match target.into_target() {
+                Ok(target) => Some(self.subscribe(target)),
+                Err(message) => Some(ServerMessage::error(None, message)),
+            },
+            ClientMessage::Unsubscribe(target) => match target.into_target() {
+                Ok(target) => {
+                    if let Some(abort_handle) = self.subscriptions.remove(&target) {
+                        trace!(?target, "Unsubscribing");
+                        abort_handle.abort();
+                    }
+                    None
+                }
+                Err(message) => Some(ServerMessage::error(None, message)),
+            },
+        }
+    }
+
+    fn subscribe(&mut self, target: SubscriptionTarget) -> ServerMessage {
+        if self.subscriptions.contains_key(&target) {
+            return ServerMessage::Subscribed {
+                target: (&target).into(),
+            };
+        }
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+            return ServerMessage::error(
+                Some(&target),
+                format!(
+                    "too many subscriptions, at most {MAX_SUBSCRIPTIONS_PER_CONNECTION} are allowed per connection"
+                ),
+            );
+        }
+
+        trace!(?target, "Subscribing");
+        let dispatcher = self.dispatcher.clone();
+        let subscription_target = target.clone();
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        self.completions.push(Abortable::new(
+            async move {
+                let completion = dispatcher.subscribe(subscription_target.clone()).await;
+                (subscription_target, completion)
+            }
+            .boxed(),
+            abort_registration,
+        ));
+        let reply = ServerMessage::Subscribed {
+            target: (&target).into(),
+        };
+        self.subscriptions.insert(target, abort_handle);
+        reply
+    }
+}
+
+async fn serve_subscriptions<Dispatcher>(
+    dispatcher: Dispatcher,
+    on_upgrade: OnUpgrade,
+) -> anyhow::Result<()>
+where
+    Dispatcher: RequestDispatcher + Clone + Send + Sync + 'static,
+{
+    let upgraded = match on_upgrade.await {
+        Ok(upgraded) => upgraded,
+        Err(err) => {
+            debug!("Failed to upgrade the connection to a WebSocket: {err}");
+            return Ok(());
+        }
+    };
+    let mut ws = WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None).await;
+
+    let mut shutdown = std::pin::pin!(cancellation_watcher());
+    let mut connection = ConnectionSubscriptions {
+        dispatcher,
+        subscriptions: HashMap::new(),
+        completions: FuturesUnordered::new(),
+    };
+
+    loop {
+        let reply = tokio::select! {
+            _ = &mut shutdown => {
+                let _ = ws.close(None).await;
+                break;
+            }
+            msg = ws.next() => match msg {
+                Some(Ok(Message::Text(text))) => connection.handle_client_message(text.as_str()),
+                Some(Ok(Message::Binary(_))) => {
+                    Some(ServerMessage::error(None, "binary messages are not supported"))
+                }
+                // Pings are answered by the WebSocket stream itself
+                Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => None,
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Err(err)) => {
+                    debug!("Error when reading from the WebSocket: {err}");
+                    break;
+                }
+            },
+            Some(completed) = connection.completions.next() => {
+                // Aborted futures were unsubscribed already
+                completed.ok().map(|(target, completion)| {
+                    connection.subscriptions.remove(&target);
+                    ServerMessage::completed(&target, completion)
+                })
+            }
+        };
+
+        if let Some(reply) = reply {
+            ws.send(reply.into_message()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_test_util::assert_eq;
+    use restate_types::identifiers::PartitionProcessorRpcRequestId;
+    use restate_types::invocation::InvocationTarget;
+    use restate_types::invocation::client::InvocationOutput;
+
+    #[test]
+    fn serialize_completed_invocation() {
+        let invocation_id = InvocationId::mock_random();
+        let message = ServerMessage::completed(
+            &SubscriptionTarget::Invocation(invocation_id),
+            SubscriptionCompletion::Invocation(AttachInvocationResponse::Ready(InvocationOutput {
+                request_id: PartitionProcessorRpcRequestId::default(),
+                invocation_id: Some(invocation_id),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    InvocationTarget::mock_service(),
+                    Bytes::from_static(br#"{"greeting":"hello"}"#),
+                ),
+            })),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({
+                "type": "completed",
+                "invocation_id": invocation_id.to_string(),
+                "value": {"greeting": "hello"}
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_non_json_invocation_value() {
+        let invocation_id = InvocationId::mock_random();
+        let message = ServerMessage::completed(
+            &SubscriptionTarget::Invocation(invocation_id),
+            SubscriptionCompletion::Invocation(AttachInvocationResponse::Ready(InvocationOutput {
+                request_id: PartitionProcessorRpcRequestId::default(),
+                invocation_id: Some(invocation_id),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    InvocationTarget::mock_service(),
+                    Bytes::from_static(b"hello"),
+                ),
+            })),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({
+                "type": "completed",
+                "invocation_id": invocation_id.to_string(),
+                "value_base64": BASE64_STANDARD.encode("hello")
+            })
+        );
+    }
+
+    #[test]
+    fn reject_bad_invocation_id() {
+        let target = TargetMessage {
+            invocation_id: "sign_1".to_owned(),
+        };
+        assert!(target.into_target().is_err());
+    }
+}
@@ -8,7 +8,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::convert::Infallible;
 use std::future::ready;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -17,35 +19,48 @@ use bytestring::ByteString;
 use futures::FutureExt;
 use http::StatusCode;
 use http::{HeaderValue, Method, Request, Response};
-use http_body_util::{BodyExt, Empty, Full};
+use http_body::Frame;
+use http_body_util::{BodyExt, Empty, Full, StreamBody};
 use tower::ServiceExt;
 use tracing_test::traced_test;
 
 use restate_core::TestCoreEnv;
 use restate_test_util::{assert, assert_eq};
-use restate_types::identifiers::{IdempotencyId, InvocationId, ServiceId, WithInvocationId};
+use restate_time_util::FriendlyDuration;
+use restate_types::config::{
+    IngressExposure, ResponseCacheOptions, SessionTokenOptions, SessionTokenScope,
+};
+use restate_types::errors::ErrorClass;
+use restate_types::identifiers::{
+    IdempotencyId, InvocationId, PartitionId, ServiceId, WithInvocationId,
+};
 use restate_types::invocation::client::{
-    AttachInvocationResponse, GetInvocationOutputResponse, InvocationOutput,
-    InvocationOutputResponse, SubmittedInvocationNotification,
+    AttachInvocationResponse, ConsistencyToken, GetInvocationOutputResponse, InvocationClientError,
+    InvocationOutput, InvocationOutputResponse, SubmittedInvocationNotification,
 };
 use restate_types::invocation::{
     InvocationQuery, InvocationTarget, InvocationTargetType, VirtualObjectHandlerType,
     WorkflowHandlerType,
 };
 use restate_types::live::Live;
+use restate_types::logs::Lsn;
 use restate_types::net::address::SocketAddress;
 use restate_types::schema::invocation_target::{
     InputContentType, InputRules, InputValidationRule, InvocationTargetMetadata,
     OutputContentTypeRule, OutputRules,
 };
+use restate_types::schema::service::{IngressLimit, IngressRoute};
 
 use super::ConnectInfo;
 use super::Handler;
+use super::ResponseCache;
 use super::health::HealthResponse;
 use super::mocks::*;
+use super::response_cache::X_RESTATE_CACHE;
 use super::service_handler::*;
-use crate::MockRequestDispatcher;
-use crate::handler::responses::X_RESTATE_ID;
+use super::session_token::X_RESTATE_SESSION_TOKEN;
+use crate::handler::responses::{X_RESTATE_CONSISTENCY_TOKEN, X_RESTATE_ID};
+use crate::{MockRequestDispatcher, RequestDispatcherError};
 
 #[restate_core::test]
 #[traced_test]
@@ -104,6 +119,95 @@ async fn call_service() {
     assert_eq!(response_value.greeting, "Igal");
 }
 
+#[restate_core::test]
+#[traced_test]
+async fn call_service_through_ingress_route() {
+    let mock_schemas = mock_schemas()
+        .with_ingress_route(
+            "greeter.Greeter",
+            IngressRoute {
+                host: Some("greeter.example.com".to_owned()),
+                path_prefix: None,
+            },
+        )
+        .with_ingress_route(
+            "greeter.GreeterObject",
+            IngressRoute {
+                host: None,
+                path_prefix: Some("/objects".to_owned()),
+            },
+        );
+
+    // Host based route, with the host taken from the Host header
+    let req = hyper::Request::builder()
+        .uri("/greet/send")
+        .method(Method::POST)
+        .header("host", "greeter.example.com:8080")
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from(
+            serde_json::to_vec(&GreetingRequest {
+                person: "Francesco".to_string(),
+            })
+            .unwrap(),
+        )))
+        .unwrap();
+
+    let mut mock_dispatcher = MockRequestDispatcher::default();
+    mock_dispatcher
+        .expect_send()
+        .return_once(|invocation_request| {
+            assert_eq!(
+                invocation_request.header.target.service_name(),
+                "greeter.Greeter"
+            );
+            assert_eq!(invocation_request.header.target.handler_name(), "greet");
+            ready(Ok(SubmittedInvocationNotification {
+                request_id: Default::default(),
+                execution_time: None,
+                is_new_invocation: true,
+                consistency_token: None,
+            }))
+            .boxed()
+        });
+    let response =
+        handle_with_schemas_and_dispatcher(req, mock_schemas.clone(), mock_dispatcher).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    // Path prefix based route
+    let req = hyper::Request::builder()
+        .uri("http://localhost/objects/my-key/greet/send")
+        .method(Method::POST)
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from(
+            serde_json::to_vec(&GreetingRequest {
+                person: "Francesco".to_string(),
+            })
+            .unwrap(),
+        )))
+        .unwrap();
+
+    let mut mock_dispatcher = MockRequestDispatcher::default();
+    mock_dispatcher
+        .expect_send()
+        .return_once(|invocation_request| {
+            assert_eq!(
+                invocation_request.header.target.service_name(),
+                "greeter.GreeterObject"
+            );
+            assert_eq!(invocation_request.header.target.key().unwrap(), &"my-key");
+            assert_eq!(invocation_request.header.target.handler_name(), "greet");
+            ready(Ok(SubmittedInvocationNotification {
+                request_id: Default::default(),
+                execution_time: None,
+                is_new_invocation: true,
+                consistency_token: None,
+            }))
+            .boxed()
+        });
+    let response = handle_with_schemas_and_dispatcher(req, mock_schemas, mock_dispatcher).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+}
+
 #[restate_core::test]
 #[traced_test]
 async fn call_service_with_get() {
@@ -253,6 +357,7 @@ async fn send_service() {
                 request_id: Default::default(),
                 execution_time: None,
                 is_new_invocation: true,
+                consistency_token: None,
             }))
             .boxed()
         });
@@ -265,6 +370,50 @@ async fn send_service() {
     let _: SendResponse = serde_json::from_slice(&response_bytes).unwrap();
 }
 
+#[restate_core::test]
+#[traced_test]
+async fn dispatcher_error_status_follows_error_class() {
+    for (class, expected_status) in [
+        (ErrorClass::Transient, StatusCode::SERVICE_UNAVAILABLE),
+        (
+            ErrorClass::RetryableWithBackoff,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        (ErrorClass::TerminalUser, StatusCode::BAD_REQUEST),
+        (
+            ErrorClass::TerminalSystem,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    ] {
+        let req = hyper::Request::builder()
+            .uri("http://localhost/greeter.Greeter/greet/send")
+            .method(Method::POST)
+            .header("content-type", "application/json")
+            .body(Full::new(Bytes::from(
+                serde_json::to_vec(&GreetingRequest {
+                    person: "Francesco".to_string(),
+                })
+                .unwrap(),
+            )))
+            .unwrap();
+
+        let mut mock_dispatcher = MockRequestDispatcher::default();
+        mock_dispatcher.expect_send().return_once(move |_| {
+            ready(Err(RequestDispatcherError::from(
+                InvocationClientError::new(
+                    anyhow::anyhow!("partition processor not reachable"),
+                    class,
+                ),
+            )))
+            .boxed()
+        });
+
+        let response = handle(req, mock_dispatcher).await;
+
+        assert_eq!(response.status(), expected_status, "for class {class}");
+    }
+}
+
 #[restate_core::test]
 #[traced_test]
 async fn send_with_delay_service() {
@@ -301,6 +450,7 @@ async fn send_with_delay_service() {
                 request_id: Default::default(),
                 execution_time: None,
                 is_new_invocation: true,
+                consistency_token: None,
             }))
             .boxed()
         });
@@ -313,6 +463,55 @@ async fn send_with_delay_service() {
     let _: SendResponse = serde_json::from_slice(&response_bytes).unwrap();
 }
 
+#[restate_core::test]
+#[traced_test]
+async fn send_with_delay_header() {
+    let greeting_req = GreetingRequest {
+        person: "Francesco".to_string(),
+    };
+
+    let req = hyper::Request::builder()
+        .uri("http://localhost/greeter.Greeter/greet/send")
+        .method(Method::POST)
+        .header("content-type", "application/json")
+        .header("x-restate-delay", "1min")
+        .body(Full::new(Bytes::from(
+            serde_json::to_vec(&greeting_req).unwrap(),
+        )))
+        .unwrap();
+
+    let mut mock_dispatcher = MockRequestDispatcher::default();
+    mock_dispatcher
+        .expect_send()
+        .return_once(|invocation_request| {
+            let execution_time = invocation_request.header.execution_time;
+            assert!(execution_time.is_some());
+            assert!(
+                !invocation_request
+                    .header
+                    .headers
+                    .iter()
+                    .any(|h| h.name.eq_ignore_ascii_case("x-restate-delay"))
+            );
+
+            ready(Ok(SubmittedInvocationNotification {
+                request_id: Default::default(),
+                execution_time,
+                is_new_invocation: true,
+                consistency_token: None,
+            }))
+            .boxed()
+        });
+
+    let response = handle(req, mock_dispatcher).await;
+
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let (_, response_body) = response.into_parts();
+    let response_bytes = response_body.collect().await.unwrap().to_bytes();
+    let response: serde_json::Value = serde_json::from_slice(&response_bytes).unwrap();
+    assert!(response["executionTime"].is_string());
+}
+
 #[restate_core::test]
 #[traced_test]
 async fn send_virtual_object() {
@@ -348,6 +547,7 @@ async fn send_virtual_object() {
                 request_id: Default::default(),
                 execution_time: None,
                 is_new_invocation: true,
+                consistency_token: None,
             }))
             .boxed()
         });
@@ -469,6 +669,7 @@ async fn idempotency_key_and_send() {
                 request_id: Default::default(),
                 execution_time: None,
                 is_new_invocation: true,
+                consistency_token: None,
             }))
             .boxed()
         });
@@ -530,6 +731,7 @@ async fn idempotency_key_and_send_with_different_invocation_id() {
                 request_id: Default::default(),
                 execution_time: None,
                 is_new_invocation: true,
+                consistency_token: None,
             }))
             .boxed()
         });
@@ -566,7 +768,7 @@ async fn attach_with_invocation_id() {
     let mut mock_dispatcher = MockRequestDispatcher::default();
     mock_dispatcher
         .expect_attach_invocation()
-        .return_once(move |actual_invocation_query| {
+        .return_once(move |actual_invocation_query, _| {
             assert_eq!(
                 InvocationQuery::Invocation(invocation_id),
                 actual_invocation_query
@@ -597,6 +799,54 @@ async fn attach_with_invocation_id() {
     assert_eq!(response_value.greeting, "Igal");
 }
 
+#[restate_core::test]
+#[traced_test]
+async fn attach_with_consistency_token() {
+    let invocation_id = InvocationId::mock_random();
+    let consistency_token = ConsistencyToken::new(PartitionId::from(1), Lsn::from(10));
+
+    let mock_schemas = MockSchemas::default().with_service_and_target(
+        "greeter.Greeter",
+        "greet",
+        InvocationTargetMetadata::mock(InvocationTargetType::Service),
+    );
+
+    let req = hyper::Request::builder()
+        .uri(format!(
+            "http://localhost/restate/invocation/{invocation_id}/attach"
+        ))
+        .method(Method::GET)
+        .header(X_RESTATE_CONSISTENCY_TOKEN, consistency_token.to_string())
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+
+    let mut mock_dispatcher = MockRequestDispatcher::default();
+    mock_dispatcher
+        .expect_attach_invocation()
+        .return_once(move |_, actual_consistency_token| {
+            assert_eq!(Some(consistency_token), actual_consistency_token);
+
+            ready(Ok(AttachInvocationResponse::Ready(InvocationOutput {
+                request_id: Default::default(),
+                invocation_id: Some(invocation_id),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    InvocationTarget::service("greeter.Greeter", "greet"),
+                    serde_json::to_vec(&GreetingResponse {
+                        greeting: "Igal".to_string(),
+                    })
+                    .unwrap()
+                    .into(),
+                ),
+            })))
+            .boxed()
+        });
+
+    let response = handle_with_schemas_and_dispatcher(req, mock_schemas, mock_dispatcher).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
 #[restate_core::test]
 #[traced_test]
 async fn attach_with_idempotency_id_to_unkeyed_service() {
@@ -617,7 +867,7 @@ async fn attach_with_idempotency_id_to_unkeyed_service() {
     let mut mock_dispatcher = MockRequestDispatcher::default();
     mock_dispatcher
         .expect_attach_invocation()
-        .return_once(move |actual_invocation_query| {
+        .return_once(move |actual_invocation_query, _| {
             assert_eq!(
                 InvocationQuery::IdempotencyId(IdempotencyId::new(
                     "greeter.Greeter".into(),
@@ -675,7 +925,7 @@ async fn attach_with_idempotency_id_to_keyed_service() {
     let mut mock_dispatcher = MockRequestDispatcher::default();
     mock_dispatcher
         .expect_attach_invocation()
-        .return_once(move |actual_invocation_query| {
+        .return_once(move |actual_invocation_query, _| {
             assert_eq!(
                 InvocationQuery::IdempotencyId(IdempotencyId::new(
                     "greeter.Greeter".into(),
@@ -716,6 +966,63 @@ async fn attach_with_idempotency_id_to_keyed_service() {
     assert_eq!(response_value.greeting, "Igal");
 }
 
+#[restate_core::test]
+#[traced_test]
+async fn get_output_with_url_encoded_idempotency_id() {
+    let mock_schemas = MockSchemas::default().with_service_and_target(
+        "greeter.Greeter",
+        "greet",
+        InvocationTargetMetadata::mock(InvocationTargetType::Service),
+    );
+    let invocation_id = InvocationId::mock_random();
+
+    let req = hyper::Request::builder()
+        .uri("http://localhost/restate/invocation/greeter.Greeter/greet/order%2F123%20abc/output")
+        .method(Method::GET)
+        .header("content-type", "application/json")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+
+    let mut mock_dispatcher = MockRequestDispatcher::default();
+    mock_dispatcher.expect_get_invocation_output().return_once(
+        move |actual_invocation_query, _| {
+            assert_eq!(
+                InvocationQuery::IdempotencyId(IdempotencyId::new(
+                    "greeter.Greeter".into(),
+                    None,
+                    "greet".into(),
+                    "order/123 abc".into()
+                )),
+                actual_invocation_query
+            );
+
+            ready(Ok(GetInvocationOutputResponse::Ready(InvocationOutput {
+                request_id: Default::default(),
+                invocation_id: Some(invocation_id),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    InvocationTarget::service("greeter.Greeter", "greet"),
+                    serde_json::to_vec(&GreetingResponse {
+                        greeting: "Igal".to_string(),
+                    })
+                    .unwrap()
+                    .into(),
+                ),
+            })))
+            .boxed()
+        },
+    );
+
+    let response = handle_with_schemas_and_dispatcher(req, mock_schemas, mock_dispatcher).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    // The client gets back the invocation id it might have lost
+    assert_eq!(
+        response.headers().get(X_RESTATE_ID).unwrap(),
+        invocation_id.to_string().as_str()
+    );
+}
+
 #[restate_core::test]
 #[traced_test]
 async fn get_output_with_invocation_id() {
@@ -737,9 +1044,8 @@ async fn get_output_with_invocation_id() {
         .unwrap();
 
     let mut mock_dispatcher = MockRequestDispatcher::default();
-    mock_dispatcher
-        .expect_get_invocation_output()
-        .return_once(move |actual_invocation_query| {
+    mock_dispatcher.expect_get_invocation_output().return_once(
+        move |actual_invocation_query, _| {
             assert_eq!(
                 InvocationQuery::Invocation(invocation_id),
                 actual_invocation_query
@@ -759,7 +1065,8 @@ async fn get_output_with_invocation_id() {
                 ),
             })))
             .boxed()
-        });
+        },
+    );
 
     let response = handle_with_schemas_and_dispatcher(req, mock_schemas, mock_dispatcher).await;
 
@@ -796,7 +1103,7 @@ async fn get_output_with_workflow_key() {
     let mut mock_dispatcher = MockRequestDispatcher::default();
     mock_dispatcher
         .expect_get_invocation_output()
-        .return_once(|actual_invocation_query| {
+        .return_once(|actual_invocation_query, _| {
             assert_eq!(
                 InvocationQuery::Workflow(service_id.clone()),
                 actual_invocation_query
@@ -943,6 +1250,50 @@ async fn private_service() {
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
+#[restate_core::test]
+#[traced_test]
+async fn private_service_on_listener_exposing_all_services() {
+    let _env = TestCoreEnv::create_with_single_node(1, 1).await;
+
+    let mut mock_dispatcher = MockRequestDispatcher::default();
+    mock_dispatcher
+        .expect_call()
+        .once()
+        .return_once(|invocation_request| {
+            Box::pin(ready(Ok(InvocationOutput {
+                request_id: Default::default(),
+                invocation_id: Some(invocation_request.invocation_id()),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    invocation_request.header.target.clone(),
+                    Bytes::new(),
+                ),
+            })))
+        });
+    let schemas = MockSchemas::default().with_service_and_target(
+        "greeter.GreeterPrivate",
+        "greet",
+        InvocationTargetMetadata {
+            public: false,
+            ..InvocationTargetMetadata::mock(InvocationTargetType::Service)
+        },
+    );
+
+    let mut req = hyper::Request::get("http://localhost/greeter.GreeterPrivate/greet")
+        .body(Empty::<Bytes>::default())
+        .unwrap();
+    req.extensions_mut()
+        .insert(ConnectInfo::new(SocketAddress::Anonymous));
+    req.extensions_mut().insert(opentelemetry::Context::new());
+
+    let response = Handler::new(Live::from_value(schemas), Arc::new(mock_dispatcher))
+        .with_exposure(IngressExposure::All)
+        .oneshot(req)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
 #[restate_core::test]
 #[traced_test]
 async fn invalid_input() {
@@ -1088,6 +1439,383 @@ async fn health() {
     let _: HealthResponse = serde_json::from_slice(&response_bytes).unwrap();
 }
 
+#[restate_core::test]
+#[traced_test]
+async fn subscribe_upgrades_to_websocket() {
+    let req = hyper::Request::builder()
+        .uri("http://localhost/restate/subscribe")
+        .method(Method::GET)
+        .header("upgrade", "websocket")
+        .header("connection", "keep-alive, Upgrade")
+        .header("sec-websocket-version", "13")
+        .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+        .body(Empty::<Bytes>::default())
+        .unwrap();
+
+    let response = handle(req, MockRequestDispatcher::default()).await;
+
+    assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(
+        response.headers().get("sec-websocket-accept").unwrap(),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+}
+
+#[restate_core::test]
+#[traced_test]
+async fn subscribe_without_websocket_handshake() {
+    let req = hyper::Request::builder()
+        .uri("http://localhost/restate/subscribe")
+        .method(Method::GET)
+        .body(Empty::<Bytes>::default())
+        .unwrap();
+
+    let response = handle(req, MockRequestDispatcher::default()).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[restate_core::test]
+#[traced_test]
+async fn cache_side_effect_free_response() {
+    let _env = TestCoreEnv::create_with_single_node(1, 1).await;
+
+    let mut mock_dispatcher = MockRequestDispatcher::new();
+    mock_dispatcher
+        .expect_call()
+        .times(1)
+        .returning(|invocation_request| {
+            ready(Ok(InvocationOutput {
+                request_id: Default::default(),
+                invocation_id: Some(invocation_request.invocation_id()),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    invocation_request.header.target.clone(),
+                    Bytes::from_static(b"123"),
+                ),
+            }))
+            .boxed()
+        });
+    let handler = Handler::new(
+        Live::from_value(MockSchemas::default().with_service_and_target(
+            "greeter.Greeter",
+            "greet",
+            InvocationTargetMetadata {
+                side_effect_free: true,
+                ..InvocationTargetMetadata::mock(InvocationTargetType::Service)
+            },
+        )),
+        Arc::new(mock_dispatcher),
+    )
+    .with_response_cache(Some(ResponseCache::new(&ResponseCacheOptions {
+        ttl: FriendlyDuration::from_secs(60),
+        capacity: NonZeroUsize::new(10).unwrap(),
+    })));
+    let request = || {
+        let mut req = hyper::Request::get("http://localhost/greeter.Greeter/greet")
+            .body(Empty::<Bytes>::default())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo::new(SocketAddress::Anonymous));
+        req.extensions_mut().insert(opentelemetry::Context::new());
+        req
+    };
+
+    let response = handler.clone().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(X_RESTATE_CACHE).is_none());
+
+    // The second call is served from the cache, without reaching the dispatcher
+    let response = handler.oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(X_RESTATE_CACHE).unwrap(), "hit");
+    let response_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(response_bytes, Bytes::from_static(b"123"));
+}
+
+#[restate_core::test]
+#[traced_test]
+async fn derive_idempotency_key_from_request_content() {
+    let _env = TestCoreEnv::create_with_single_node(1, 1).await;
+
+    let idempotency_keys = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let mut mock_dispatcher = MockRequestDispatcher::new();
+    mock_dispatcher.expect_call().times(3).returning({
+        let idempotency_keys = Arc::clone(&idempotency_keys);
+        move |invocation_request| {
+            assert_eq!(
+                invocation_request.header.completion_retention_duration(),
+                Duration::from_secs(60)
+            );
+            idempotency_keys
+                .lock()
+                .push(invocation_request.header.idempotency_key.clone().unwrap());
+            ready(Ok(InvocationOutput {
+                request_id: Default::default(),
+                invocation_id: Some(invocation_request.invocation_id()),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    invocation_request.header.target.clone(),
+                    Bytes::new(),
+                ),
+            }))
+            .boxed()
+        }
+    });
+    let handler = Handler::new(
+        Live::from_value(MockSchemas::default().with_service_and_target(
+            "greeter.Greeter",
+            "greet",
+            InvocationTargetMetadata {
+                derived_idempotency_key_window: Some(Duration::from_secs(60)),
+                ..InvocationTargetMetadata::mock(InvocationTargetType::Service)
+            },
+        )),
+        Arc::new(mock_dispatcher),
+    );
+    let request = |body: &'static str| {
+        let mut req = hyper::Request::post("http://localhost/greeter.Greeter/greet")
+            .header("content-type", "application/json")
+            .body(Full::new(Bytes::from_static(body.as_bytes())))
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo::new(SocketAddress::Anonymous));
+        req.extensions_mut().insert(opentelemetry::Context::new());
+        req
+    };
+
+    for body in [
+        r#"{"person": "Francesco", "age": 42}"#,
+        r#"{"age":42,"person":"Francesco"}"#,
+        r#"{"person": "Igal", "age": 42}"#,
+    ] {
+        let response = handler.clone().oneshot(request(body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Requests differing only in formatting and field order share the derived key
+    let idempotency_keys = idempotency_keys.lock();
+    assert!(idempotency_keys[0].starts_with("derived-"));
+    assert_eq!(idempotency_keys[0], idempotency_keys[1]);
+    assert_ne!(idempotency_keys[0], idempotency_keys[2]);
+}
+
+#[restate_core::test]
+#[traced_test]
+async fn reject_requests_exceeding_ingress_limits() {
+    let _env = TestCoreEnv::create_with_single_node(1, 1).await;
+
+    let handler = Handler::new(
+        Live::from_value(MockSchemas::default().with_service_and_target(
+            "greeter.Greeter",
+            "greet",
+            InvocationTargetMetadata {
+                service_ingress_limit: IngressLimit {
+                    requests_per_second: NonZeroU32::new(1),
+                    burst: None,
+                    max_concurrency: None,
+                },
+                ..InvocationTargetMetadata::mock(InvocationTargetType::Service)
+            },
+        )),
+        Arc::new(expect_invocation_and_reply_with_empty()),
+    );
+    let request = || {
+        let mut req = hyper::Request::get("http://localhost/greeter.Greeter/greet")
+            .body(Empty::<Bytes>::default())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo::new(SocketAddress::Anonymous));
+        req.extensions_mut().insert(opentelemetry::Context::new());
+        req
+    };
+
+    let response = handler.clone().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The second request is rejected without reaching the dispatcher
+    let response = handler.oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        response.headers().get(http::header::RETRY_AFTER).unwrap(),
+        "1"
+    );
+}
+
+#[restate_core::test]
+#[traced_test]
+async fn reject_request_body_exceeding_size_limit() {
+    let _env = TestCoreEnv::create_with_single_node(1, 1).await;
+
+    let handler = Handler::new(
+        Live::from_value(mock_schemas()),
+        Arc::new(expect_invocation_and_reply_with_empty()),
+    )
+    .with_max_request_body_size(8);
+    let request = |body| {
+        let mut req = hyper::Request::post("http://localhost/greeter.Greeter/greet")
+            .body(body)
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo::new(SocketAddress::Anonymous));
+        req.extensions_mut().insert(opentelemetry::Context::new());
+        req
+    };
+
+    // Rejected upfront by the declared length
+    let response = handler
+        .clone()
+        .oneshot(request(
+            Full::new(Bytes::from_static(b"0123456789")).boxed(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Rejected while streaming the body, which doesn't declare its length
+    let response = handler
+        .clone()
+        .oneshot(request(
+            StreamBody::new(futures::stream::iter(
+                [b"01234".as_slice(), b"56789".as_slice()]
+                    .map(|chunk| Ok::<_, Infallible>(Frame::data(Bytes::from_static(chunk)))),
+            ))
+            .boxed(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let response = handler
+        .oneshot(request(Full::new(Bytes::from_static(b"01234567")).boxed()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[restate_core::test]
+#[traced_test]
+async fn redeem_session_token_of_workflow_submission() {
+    let _env = TestCoreEnv::create_with_single_node(1, 1).await;
+
+    let mut mock_dispatcher = MockRequestDispatcher::new();
+    mock_dispatcher.expect_send().return_once(|_| {
+        ready(Ok(SubmittedInvocationNotification {
+            request_id: Default::default(),
+            execution_time: None,
+            is_new_invocation: true,
+            consistency_token: None,
+        }))
+        .boxed()
+    });
+    mock_dispatcher
+        .expect_attach_invocation()
+        .return_once(|invocation_query, _| {
+            let InvocationQuery::Invocation(invocation_id) = invocation_query else {
+                panic!("Unexpected query {invocation_query:?}");
+            };
+            ready(Ok(AttachInvocationResponse::Ready(InvocationOutput {
+                request_id: Default::default(),
+                invocation_id: Some(invocation_id),
+                completion_expiry_time: None,
+                response: InvocationOutputResponse::Success(
+                    InvocationTarget::workflow(
+                        "MyWorkflow",
+                        "my-key",
+                        "run",
+                        WorkflowHandlerType::Workflow,
+                    ),
+                    Bytes::new(),
+                ),
+            })))
+            .boxed()
+        });
+
+    let handler = Handler::new(
+        Live::from_value(MockSchemas::default().with_service_and_target(
+            "MyWorkflow",
+            "run",
+            InvocationTargetMetadata::mock(InvocationTargetType::Workflow(
+                WorkflowHandlerType::Workflow,
+            )),
+        )),
+        Arc::new(mock_dispatcher),
+    )
+    .with_session_tokens(Some(Arc::new(SessionTokenOptions {
+        secret: "s3cr3t".to_owned(),
+        ttl: FriendlyDuration::from_secs(60),
+        workflows: vec![],
+        scopes: vec![SessionTokenScope::Attach],
+    })));
+    let request = |uri: &str, method: Method, session_token: Option<&HeaderValue>| {
+        let mut req = hyper::Request::builder().uri(uri).method(method);
+        if let Some(session_token) = session_token {
+            req = req.header(X_RESTATE_SESSION_TOKEN, session_token);
+        }
+        let mut req = req.body(Empty::<Bytes>::default()).unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo::new(SocketAddress::Anonymous));
+        req.extensions_mut().insert(opentelemetry::Context::new());
+        req
+    };
+
+    let response = handler
+        .clone()
+        .oneshot(request(
+            "http://localhost/MyWorkflow/my-key/run/send",
+            Method::POST,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let session_token = response
+        .headers()
+        .get(X_RESTATE_SESSION_TOKEN)
+        .cloned()
+        .unwrap();
+    let response_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let send_response: SendResponse = serde_json::from_slice(&response_bytes).unwrap();
+    assert_eq!(
+        send_response.session_token.as_deref(),
+        Some(session_token.to_str().unwrap())
+    );
+
+    // Attach is in the token scopes
+    let response = handler
+        .clone()
+        .oneshot(request(
+            "http://localhost/restate/session/attach",
+            Method::GET,
+            Some(&session_token),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Getting the output isn't
+    let response = handler
+        .clone()
+        .oneshot(request(
+            "http://localhost/restate/session/output",
+            Method::GET,
+            Some(&session_token),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = handler
+        .oneshot(request(
+            "http://localhost/restate/session/attach",
+            Method::GET,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
 fn expect_invocation_and_reply_with_empty() -> MockRequestDispatcher {
     let mut mock_dispatcher = MockRequestDispatcher::new();
     mock_dispatcher
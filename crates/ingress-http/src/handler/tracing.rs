@@ -10,26 +10,24 @@
 
 use super::ConnectInfo;
 
-use http::Request;
+use http::Extensions;
 use opentelemetry::global::ObjectSafeSpan;
 use opentelemetry::trace::{SpanContext, TraceContextExt};
 use restate_tracing_instrumentation as instrumentation;
 use restate_types::identifiers::InvocationId;
 use restate_types::invocation::{InvocationTarget, SpanRelation};
 
-pub(crate) fn prepare_tracing_span<B>(
+pub(crate) fn prepare_tracing_span(
     invocation_id: &InvocationId,
     invocation_target: &InvocationTarget,
-    req: &Request<B>,
+    extensions: &Extensions,
 ) -> SpanContext {
-    let connect_info: &ConnectInfo = req
-        .extensions()
+    let connect_info: &ConnectInfo = extensions
         .get()
         .expect("Should have been injected by the previous layer");
     let (client_addr, client_port) = (connect_info.address(), connect_info.port());
 
-    let tracing_context: &opentelemetry::Context = req
-        .extensions()
+    let tracing_context: &opentelemetry::Context = extensions
         .get()
         .expect("Should have been injected by the previous layer");
 
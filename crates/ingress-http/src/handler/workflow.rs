@@ -65,10 +65,15 @@ where
             "Processing workflow attach request"
         );
 
+        let consistency_token = Self::parse_consistency_token(&req)?;
+
         // Wait on response
         let response = match self
             .dispatcher
-            .attach_invocation(InvocationQuery::Workflow(workflow_id.clone()))
+            .attach_invocation(
+                InvocationQuery::Workflow(workflow_id.clone()),
+                consistency_token,
+            )
             .await?
         {
             AttachInvocationResponse::NotFound => {
@@ -104,9 +109,14 @@ where
             return Err(HandlerError::MethodNotAllowed);
         }
 
+        let consistency_token = Self::parse_consistency_token(&req)?;
+
         let response = match self
             .dispatcher
-            .get_invocation_output(InvocationQuery::Workflow(workflow_id.clone()))
+            .get_invocation_output(
+                InvocationQuery::Workflow(workflow_id.clone()),
+                consistency_token,
+            )
             .await
         {
             Ok(GetInvocationOutputResponse::Ready(out)) => out,
@@ -13,6 +13,7 @@ mod layers;
 mod metric_definitions;
 mod rpc_request_dispatcher;
 mod server;
+mod subscription_registry;
 
 pub use rpc_request_dispatcher::InvocationClientRequestDispatcher;
 pub use server::{HyperServerIngress, IngressServerError};
@@ -21,10 +22,11 @@ use bytes::Bytes;
 use std::future::Future;
 use std::sync::Arc;
 
+use restate_types::errors::ErrorClass;
 use restate_types::identifiers::InvocationId;
 use restate_types::invocation::client::{
-    AttachInvocationResponse, GetInvocationOutputResponse, InvocationOutput,
-    SubmittedInvocationNotification,
+    AttachInvocationResponse, ConsistencyToken, GetInvocationOutputResponse, InvocationClientError,
+    InvocationOutput, SubmittedInvocationNotification,
 };
 use restate_types::invocation::{InvocationQuery, InvocationRequest, InvocationResponse};
 use restate_types::journal_v2::Signal;
@@ -59,10 +61,38 @@ impl ConnectInfo {
 
 #[derive(Debug, thiserror::Error)]
 pub enum RequestDispatcherError {
+    #[error(transparent)]
+    InvocationClient(#[from] InvocationClientError),
     #[error(transparent)]
     Internal(#[from] anyhow::Error),
 }
 
+impl RequestDispatcherError {
+    pub fn error_class(&self) -> ErrorClass {
+        match self {
+            RequestDispatcherError::InvocationClient(err) => err.error_class(),
+            RequestDispatcherError::Internal(_) => ErrorClass::TerminalSystem,
+        }
+    }
+}
+
+/// Target of a subscription, see [`RequestDispatcher::subscribe`].
+///
+/// Awakeables are not a valid target: they can be completed through any ingress or by other
+/// invocations, hence their completion cannot be observed by a single ingress.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SubscriptionTarget {
+    Invocation(InvocationId),
+}
+
+/// Completion of a [`SubscriptionTarget`], shared by all its subscribers.
+#[derive(Debug, Clone)]
+pub enum SubscriptionCompletion {
+    Invocation(AttachInvocationResponse),
+    /// Waiting for the completion failed.
+    Failed(Arc<RequestDispatcherError>),
+}
+
 /// Trait used by the invoker to dispatch requests to target partition processors.
 #[cfg_attr(test, mockall::automock)]
 pub trait RequestDispatcher {
@@ -78,16 +108,20 @@ pub trait RequestDispatcher {
         invocation_request: Arc<InvocationRequest>,
     ) -> impl Future<Output = Result<InvocationOutput, RequestDispatcherError>> + Send;
 
-    /// Attach to an invocation using the given query
+    /// Attach to an invocation using the given query.
+    /// If a consistency token is provided, the invocation is read only after the token has been caught up with.
     fn attach_invocation(
         &self,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> impl Future<Output = Result<AttachInvocationResponse, RequestDispatcherError>> + Send;
 
     /// Get invocation output, without blocking when it's still running.
+    /// If a consistency token is provided, the invocation is read only after the token has been caught up with.
     fn get_invocation_output(
         &self,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> impl Future<Output = Result<GetInvocationOutputResponse, RequestDispatcherError>> + Send;
 
     /// Send invocation response (for awakeables).
@@ -104,6 +138,13 @@ pub trait RequestDispatcher {
         target_invocation: InvocationId,
         signal: Signal,
     ) -> impl Future<Output = Result<(), RequestDispatcherError>> + Send;
+
+    /// Wait for the completion of the given target. The subscriptions to the same target share
+    /// the wait: invocations are attached to once, whatever the number of subscribers.
+    fn subscribe(
+        &self,
+        target: SubscriptionTarget,
+    ) -> impl Future<Output = SubscriptionCompletion> + Send;
 }
 
 // Contains some mocks we use in unit tests in this crate
@@ -124,7 +165,7 @@ mod mocks {
     };
     use restate_types::schema::service::test_util::MockServiceMetadataResolver;
     use restate_types::schema::service::{
-        HandlerMetadata, ServiceMetadata, ServiceMetadataResolver,
+        HandlerMetadata, IngressRoute, ServiceMetadata, ServiceMetadataResolver,
     };
     use serde::{Deserialize, Serialize};
     use serde_json::Value;
@@ -189,6 +230,11 @@ mod mocks {
                 inactivity_timeout: DEFAULT_INACTIVITY_TIMEOUT,
                 abort_timeout: DEFAULT_ABORT_TIMEOUT,
                 enable_lazy_state: false,
+                max_journal_length: None,
+                state_ttl: None,
+                completion_retention: None,
+                ingress_route: None,
+                ingress_limits: None,
                 retry_policy: Default::default(),
                 info: vec![],
             });
@@ -208,6 +254,20 @@ mod mocks {
             self.add_service_and_target(service_name, handler_name, invocation_target_metadata);
             self
         }
+
+        pub fn with_ingress_route(
+            mut self,
+            service_name: &str,
+            ingress_route: IngressRoute,
+        ) -> Self {
+            let mut service_metadata = self
+                .0
+                .resolve_latest_service(service_name)
+                .expect("service must be registered first");
+            service_metadata.ingress_route = Some(ingress_route);
+            self.0.add(service_metadata);
+            self
+        }
     }
 
     impl ServiceMetadataResolver for MockSchemas {
@@ -231,6 +291,14 @@ mod mocks {
         fn list_service_names(&self) -> Vec<String> {
             self.0.list_service_names()
         }
+
+        fn resolve_ingress_route<'a>(
+            &self,
+            host: Option<&str>,
+            path: &'a str,
+        ) -> Option<(String, &'a str)> {
+            self.0.resolve_ingress_route(host, path)
+        }
     }
 
     impl InvocationTargetResolver for MockSchemas {
@@ -309,17 +377,19 @@ mod mocks {
         fn attach_invocation(
             &self,
             invocation_query: InvocationQuery,
+            consistency_token: Option<ConsistencyToken>,
         ) -> impl Future<Output = Result<AttachInvocationResponse, RequestDispatcherError>> + Send
         {
-            MockRequestDispatcher::attach_invocation(self, invocation_query)
+            MockRequestDispatcher::attach_invocation(self, invocation_query, consistency_token)
         }
 
         fn get_invocation_output(
             &self,
             invocation_query: InvocationQuery,
+            consistency_token: Option<ConsistencyToken>,
         ) -> impl Future<Output = Result<GetInvocationOutputResponse, RequestDispatcherError>> + Send
         {
-            MockRequestDispatcher::get_invocation_output(self, invocation_query)
+            MockRequestDispatcher::get_invocation_output(self, invocation_query, consistency_token)
         }
 
         fn send_invocation_response(
@@ -336,5 +406,12 @@ mod mocks {
         ) -> impl Future<Output = Result<(), RequestDispatcherError>> + Send {
             MockRequestDispatcher::send_signal(self, target_invocation, signal)
         }
+
+        fn subscribe(
+            &self,
+            target: SubscriptionTarget,
+        ) -> impl Future<Output = SubscriptionCompletion> + Send {
+            MockRequestDispatcher::subscribe(self, target)
+        }
     }
 }
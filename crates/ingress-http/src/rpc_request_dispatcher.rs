@@ -8,12 +8,17 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use super::{RequestDispatcher, RequestDispatcherError};
+use super::subscription_registry::SubscriptionRegistry;
+use super::{
+    RequestDispatcher, RequestDispatcherError, SubscriptionCompletion, SubscriptionTarget,
+};
 
+use restate_core::{TaskCenter, TaskKind};
+use restate_types::errors::ErrorClass;
 use restate_types::identifiers::{InvocationId, PartitionProcessorRpcRequestId, WithInvocationId};
 use restate_types::invocation::client::{
-    AttachInvocationResponse, GetInvocationOutputResponse, InvocationClient, InvocationClientError,
-    InvocationOutput, SubmittedInvocationNotification,
+    AttachInvocationResponse, ConsistencyToken, GetInvocationOutputResponse, InvocationClient,
+    InvocationClientError, InvocationOutput, SubmittedInvocationNotification,
 };
 use restate_types::invocation::{InvocationQuery, InvocationRequest, InvocationResponse};
 use restate_types::journal_v2::Signal;
@@ -21,11 +26,12 @@ use restate_types::retries::RetryPolicy;
 use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{Instrument, debug_span, trace};
+use tracing::{Instrument, debug, debug_span, trace};
 
 pub struct InvocationClientRequestDispatcher<IC> {
     invocation_client: IC,
     retry_policy: RetryPolicy,
+    subscriptions: SubscriptionRegistry,
 }
 
 impl<IC: Clone> Clone for InvocationClientRequestDispatcher<IC> {
@@ -33,6 +39,7 @@ impl<IC: Clone> Clone for InvocationClientRequestDispatcher<IC> {
         InvocationClientRequestDispatcher {
             invocation_client: self.invocation_client.clone(),
             retry_policy: self.retry_policy.clone(),
+            subscriptions: self.subscriptions.clone(),
         }
     }
 }
@@ -43,6 +50,7 @@ impl<IC> InvocationClientRequestDispatcher<IC> {
             invocation_client,
             // TODO figure out how to tune this?
             retry_policy: RetryPolicy::fixed_delay(Duration::from_millis(50), None),
+            subscriptions: SubscriptionRegistry::default(),
         }
     }
 
@@ -59,7 +67,12 @@ impl<IC> InvocationClientRequestDispatcher<IC> {
             .retry_policy
             .clone()
             .retry_if(operation, |e| {
-                let retry = is_idempotent || e.is_safe_to_retry();
+                // Errors which might have been applied are retried only for idempotent requests
+                let retry = match e.error_class() {
+                    ErrorClass::Transient => true,
+                    ErrorClass::RetryableWithBackoff => is_idempotent,
+                    ErrorClass::TerminalUser | ErrorClass::TerminalSystem => false,
+                };
 
                 if retry {
                     trace!("Retrying rpc because of error: {e}.");
@@ -69,8 +82,7 @@ impl<IC> InvocationClientRequestDispatcher<IC> {
 
                 retry
             })
-            .await
-            .map_err(|e| e.into_inner())?)
+            .await?)
     }
 }
 
@@ -112,11 +124,15 @@ where
     async fn attach_invocation(
         &self,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> Result<AttachInvocationResponse, RequestDispatcherError> {
         let request_id = PartitionProcessorRpcRequestId::default();
         self.execute_rpc(true, || {
-            self.invocation_client
-                .attach_invocation(request_id, invocation_query.clone())
+            self.invocation_client.attach_invocation(
+                request_id,
+                invocation_query.clone(),
+                consistency_token,
+            )
         })
         .instrument(debug_span!("attach to invocation", %request_id, invocation_id = %invocation_query.to_invocation_id()))
         .await
@@ -125,11 +141,15 @@ where
     async fn get_invocation_output(
         &self,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> Result<GetInvocationOutputResponse, RequestDispatcherError> {
         let request_id = PartitionProcessorRpcRequestId::default();
         self.execute_rpc(true, || {
-            self.invocation_client
-                .get_invocation_output(request_id, invocation_query.clone())
+            self.invocation_client.get_invocation_output(
+                request_id,
+                invocation_query.clone(),
+                consistency_token,
+            )
         })
         .instrument(debug_span!("get invocation output", %request_id, invocation_id = %invocation_query.to_invocation_id()))
         .await
@@ -161,4 +181,47 @@ where
             .instrument(debug_span!("send invocation response", %request_id, invocation_id = %target_invocation))
             .await
     }
+
+    async fn subscribe(&self, target: SubscriptionTarget) -> SubscriptionCompletion {
+        let (subscription, sender) = self.subscriptions.subscribe(target.clone());
+
+        // The first subscriber of an invocation attaches to it on behalf of all the subscribers.
+        // Attaching goes through the partition processor, hence it observes the completion
+        // whichever node completes the invocation.
+        if let (Some(sender), SubscriptionTarget::Invocation(invocation_id)) = (sender, &target) {
+            let this = self.clone();
+            let invocation_id = *invocation_id;
+            let attach = async move {
+                let completion = tokio::select! {
+                    res = this.attach_invocation(InvocationQuery::Invocation(invocation_id), None) => {
+                        match res {
+                            Ok(response) => SubscriptionCompletion::Invocation(response),
+                            Err(err) => SubscriptionCompletion::Failed(Arc::new(err)),
+                        }
+                    }
+                    // Stop attaching once all the subscribers are gone
+                    _ = sender.closed() => {
+                        debug!(restate.invocation.id = %invocation_id, "No subscribers left, stop attaching");
+                        return Ok(());
+                    }
+                };
+                this.subscriptions
+                    .complete_sender(&target, &sender, completion);
+                Ok(())
+            };
+            if let Err(err) =
+                TaskCenter::spawn(TaskKind::Disposable, "ingress-subscription", attach)
+            {
+                return SubscriptionCompletion::Failed(Arc::new(RequestDispatcherError::Internal(
+                    err.into(),
+                )));
+            }
+        }
+
+        subscription.completion().await.unwrap_or_else(|| {
+            SubscriptionCompletion::Failed(Arc::new(RequestDispatcherError::Internal(
+                anyhow::anyhow!("the subscription was dropped before completing"),
+            )))
+        })
+    }
 }
@@ -10,6 +10,7 @@
 
 use std::convert::Infallible;
 use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
 use codederror::CodedError;
@@ -23,12 +24,17 @@ use tower::{ServiceBuilder, ServiceExt};
 use tower_http::classify::ServerErrorsFailureClass;
 use tower_http::cors::CorsLayer;
 use tower_http::normalize_path::NormalizePathLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{Span, debug, info, info_span, instrument};
 
 use restate_core::{TaskCenter, TaskKind, cancellation_watcher};
+use restate_http_auth::{AuthLayer, Authenticator, ClientCertificate, TlsAcceptor};
 use restate_time_util::DurationExt;
-use restate_types::config::IngressOptions;
+use restate_types::config::{
+    AuthenticationOptions, IngressListenerOptions, IngressOptions, RequestSignatureOptions,
+    SessionTokenOptions,
+};
 use restate_types::health::HealthStatus;
 use restate_types::live::Live;
 use restate_types::net::address::{HttpIngressPort, ListenerPort, SocketAddress};
@@ -38,7 +44,11 @@ use restate_types::schema::invocation_target::InvocationTargetResolver;
 use restate_types::schema::service::ServiceMetadataResolver;
 
 use super::*;
-use crate::handler::Handler;
+use crate::handler::{Handler, ResponseCache, X_RESTATE_SESSION_TOKEN};
+
+/// Paths served without credentials, when authentication is enabled. The session requests are
+/// authenticated by their session token.
+const PUBLIC_PATHS: &[&str] = &["/restate/health", "/restate/session/"];
 
 #[derive(Debug, thiserror::Error, CodedError)]
 pub enum IngressServerError {
@@ -54,6 +64,13 @@ pub struct HyperServerIngress<Schemas, Dispatcher> {
     // Parameters to build the layers
     schemas: Live<Schemas>,
     dispatcher: Dispatcher,
+    request_signatures: Arc<[RequestSignatureOptions]>,
+    response_cache: Option<ResponseCache>,
+    session_tokens: Option<Arc<SessionTokenOptions>>,
+    validate_json_schemas: bool,
+    max_request_body_size: usize,
+    additional_listeners: Vec<IngressListenerOptions>,
+    authentication: Option<AuthenticationOptions>,
 
     health: HealthStatus<IngressStatus>,
 }
@@ -78,6 +95,13 @@ where
             dispatcher,
             health,
         )
+        .with_request_signatures(ingress_options.request_signatures().into())
+        .with_response_cache(ingress_options.response_cache().map(ResponseCache::new))
+        .with_session_tokens(ingress_options.session_tokens().cloned().map(Arc::new))
+        .with_json_schema_validation(ingress_options.validate_json_schemas())
+        .with_max_request_body_size(ingress_options.max_request_body_size())
+        .with_additional_listeners(ingress_options.additional_listeners().to_vec())
+        .with_authentication(ingress_options.authentication().cloned())
     }
 }
 
@@ -100,10 +124,64 @@ where
             concurrency_limit,
             schemas,
             dispatcher,
+            request_signatures: Arc::new([]),
+            response_cache: None,
+            session_tokens: None,
+            validate_json_schemas: false,
+            max_request_body_size: usize::MAX,
+            additional_listeners: Vec::new(),
+            authentication: None,
             health,
         }
     }
 
+    pub(crate) fn with_request_signatures(
+        mut self,
+        request_signatures: Arc<[RequestSignatureOptions]>,
+    ) -> Self {
+        self.request_signatures = request_signatures;
+        self
+    }
+
+    pub(crate) fn with_response_cache(mut self, response_cache: Option<ResponseCache>) -> Self {
+        self.response_cache = response_cache;
+        self
+    }
+
+    pub(crate) fn with_session_tokens(
+        mut self,
+        session_tokens: Option<Arc<SessionTokenOptions>>,
+    ) -> Self {
+        self.session_tokens = session_tokens;
+        self
+    }
+
+    pub(crate) fn with_json_schema_validation(mut self, validate_json_schemas: bool) -> Self {
+        self.validate_json_schemas = validate_json_schemas;
+        self
+    }
+
+    pub(crate) fn with_max_request_body_size(mut self, max_request_body_size: usize) -> Self {
+        self.max_request_body_size = max_request_body_size;
+        self
+    }
+
+    pub(crate) fn with_additional_listeners(
+        mut self,
+        additional_listeners: Vec<IngressListenerOptions>,
+    ) -> Self {
+        self.additional_listeners = additional_listeners;
+        self
+    }
+
+    pub(crate) fn with_authentication(
+        mut self,
+        authentication: Option<AuthenticationOptions>,
+    ) -> Self {
+        self.authentication = authentication;
+        self
+    }
+
     #[instrument(
         level = "error",
         name = "server",
@@ -112,69 +190,142 @@ where
     )]
     pub async fn run(self) -> anyhow::Result<()> {
         let HyperServerIngress {
-            mut listeners,
+            listeners,
             concurrency_limit,
             schemas,
             dispatcher,
+            request_signatures,
+            response_cache,
+            session_tokens,
+            validate_json_schemas,
+            max_request_body_size,
+            additional_listeners,
+            authentication,
             health,
         } = self;
 
+        let authenticator = authentication
+            .as_ref()
+            .map(Authenticator::new)
+            .transpose()?;
+        let tls_acceptor = authentication
+            .as_ref()
+            .and_then(|authentication| authentication.tls.as_ref())
+            .map(TlsAcceptor::new)
+            .transpose()?;
+
+        // Bind the additional listeners upfront, so that a wrong address fails the ingress
+        let mut additional = Vec::with_capacity(additional_listeners.len());
+        for listener_options in additional_listeners {
+            let listeners =
+                Listeners::<HttpIngressPort>::new_tcp_listener(listener_options.bind_address)
+                    .await?;
+            additional.push((listener_options, listeners));
+        }
+
         // Prepare the handler
-        let service = ServiceBuilder::new()
-            .layer(
-                TraceLayer::new_for_http()
-                    .make_span_with(|request: &Request<_>| {
-                        info_span!(
-                            target: "restate_ingress_http::api",
-                            "ingress-http-request",
-                            http.version = ?request.version(),
-                            http.request.method = %request.method(),
-                            url.path = request.uri().path(),
-                            url.query = request.uri().query().unwrap_or_default(),
-                            url.scheme = request.uri().scheme_str().unwrap_or("http")
-                        )
-                    })
-                    // Just log on response
-                    .on_request(())
-                    .on_eos(())
-                    .on_body_chunk(())
-                    .on_response(
-                        move |response: &Response<_>, latency: Duration, span: &Span| {
-                            debug!(
-                                name: "access-log",
+        let make_service = move |handler: Handler<Schemas, Dispatcher>| {
+            ServiceBuilder::new()
+                // Accept the request id provided by the client, or generate one. It's forwarded to
+                // the service together with the other request headers.
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(PropagateRequestIdLayer::x_request_id())
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(|request: &Request<_>| {
+                            info_span!(
                                 target: "restate_ingress_http::api",
-                                parent: span,
-                                { http.response.status_code = response.status().as_u16(), http.response.latency = %latency.friendly().to_seconds_span() },
-                                "Replied"
+                                "ingress-http-request",
+                                http.version = ?request.version(),
+                                http.request.method = %request.method(),
+                                url.path = request.uri().path(),
+                                url.query = request.uri().query().unwrap_or_default(),
+                                url.scheme = request.uri().scheme_str().unwrap_or("http"),
+                                http.request.id = request
+                                    .headers()
+                                    .get("x-request-id")
+                                    .and_then(|v| v.to_str().ok())
+                                    .unwrap_or_default()
                             )
-                        },
-                    )
-                    .on_failure(
-                        move |error: ServerErrorsFailureClass, latency: Duration, span: &Span| {
-                            match error {
-                                ServerErrorsFailureClass::StatusCode(_) => {
-                                    // No need to log it, on_response will log it already
-                                }
-                                ServerErrorsFailureClass::Error(error_string) => {
-                                    debug!(
-                                        name: "access-log",
-                                        target: "restate_ingress_http::api",
-                                        parent: span,
-                                        { error.type = error_string, http.response.latency = %latency.friendly().to_seconds_span() },
-                                        "Failed processing"
-                                    )
+                        })
+                        // Just log on response
+                        .on_request(())
+                        .on_eos(())
+                        .on_body_chunk(())
+                        .on_response(
+                            move |response: &Response<_>, latency: Duration, span: &Span| {
+                                debug!(
+                                    name: "access-log",
+                                    target: "restate_ingress_http::api",
+                                    parent: span,
+                                    { http.response.status_code = response.status().as_u16(), http.response.latency = %latency.friendly().to_seconds_span() },
+                                    "Replied"
+                                )
+                            },
+                        )
+                        .on_failure(
+                            move |error: ServerErrorsFailureClass, latency: Duration, span: &Span| {
+                                match error {
+                                    ServerErrorsFailureClass::StatusCode(_) => {
+                                        // No need to log it, on_response will log it already
+                                    }
+                                    ServerErrorsFailureClass::Error(error_string) => {
+                                        debug!(
+                                            name: "access-log",
+                                            target: "restate_ingress_http::api",
+                                            parent: span,
+                                            { error.type = error_string, http.response.latency = %latency.friendly().to_seconds_span() },
+                                            "Failed processing"
+                                        )
+                                    }
                                 }
-                            }
-                        },
-                    ),
-            )
-            .layer(NormalizePathLayer::trim_trailing_slash())
-            .layer(layers::load_shed::LoadShedLayer::new(concurrency_limit))
-            .layer(CorsLayer::very_permissive())
-            .layer(layers::tracing_context_extractor::HttpTraceContextExtractorLayer)
-            .service(Handler::new(schemas, dispatcher));
+                            },
+                        ),
+                )
+                .layer(NormalizePathLayer::trim_trailing_slash())
+                .layer(layers::load_shed::LoadShedLayer::new(concurrency_limit))
+                .layer(CorsLayer::very_permissive().expose_headers([X_RESTATE_SESSION_TOKEN]))
+                .option_layer(
+                    authenticator
+                        .clone()
+                        .map(|authenticator| AuthLayer::new(authenticator, PUBLIC_PATHS)),
+                )
+                .layer(layers::tracing_context_extractor::HttpTraceContextExtractorLayer)
+                .service(handler)
+        };
+
+        // All the listeners share the dispatcher, the request id index and the response cache
+        let handler = Handler::new(schemas, dispatcher)
+            .with_request_signatures(request_signatures)
+            .with_response_cache(response_cache)
+            .with_session_tokens(session_tokens)
+            .with_json_schema_validation(validate_json_schemas)
+            .with_max_request_body_size(max_request_body_size);
+
+        for (listener_options, listeners) in additional {
+            let mut listener_handler = handler.clone().with_exposure(listener_options.exposure);
+            if let Some(request_signatures) = listener_options.request_signatures {
+                listener_handler =
+                    listener_handler.with_request_signatures(request_signatures.into());
+            }
+            info!(
+                listener.name = %listener_options.name,
+                server.address = %listener_options.bind_address,
+                exposure = ?listener_options.exposure,
+                "Ingress HTTP listening on additional listener"
+            );
+            TaskCenter::spawn_child(
+                TaskKind::Ingress,
+                "ingress-http-listener",
+                Self::accept_connections(
+                    listeners,
+                    tls_acceptor.clone(),
+                    make_service(listener_handler),
+                ),
+            )?;
+        }
 
-        let mut shutdown = std::pin::pin!(cancellation_watcher());
+        let service = make_service(handler);
 
         if let Some(uds_path) = listeners.uds_address() {
             Span::current().record("uds.path", uds_path.display().to_string());
@@ -186,23 +337,58 @@ where
         info!("Ingress HTTP listening");
         health.update(IngressStatus::Ready);
 
-        // UDS
+        Self::accept_connections(listeners, tls_acceptor, service).await
+    }
+
+    async fn accept_connections<T, F, B>(
+        mut listeners: Listeners<HttpIngressPort>,
+        tls_acceptor: Option<TlsAcceptor>,
+        service: T,
+    ) -> anyhow::Result<()>
+    where
+        F: Send,
+        B: http_body::Body + Send + 'static,
+        <B as http_body::Body>::Data: Send + 'static,
+        <B as http_body::Body>::Error: std::error::Error + Sync + Send + 'static,
+        T: tower::Service<
+                Request<Incoming>,
+                Response = Response<B>,
+                Error = Infallible,
+                Future = F,
+            > + Clone
+            + Send
+            + 'static,
+    {
+        let mut shutdown = std::pin::pin!(cancellation_watcher());
+
         loop {
             tokio::select! {
                 res = listeners.accept() => {
                     let (stream, peer_addr) = res?;
                     match stream {
-                        Either::Left(tcp_stream) => {
-                            Self::handle_connection(
-                                tcp_stream,
-                                peer_addr,
-                                service.clone()
-                            )?;
-                        }
+                        Either::Left(tcp_stream) => match &tls_acceptor {
+                            Some(tls_acceptor) => {
+                                Self::handle_tls_connection(
+                                    tls_acceptor.clone(),
+                                    tcp_stream,
+                                    peer_addr,
+                                    service.clone()
+                                )?;
+                            }
+                            None => {
+                                Self::handle_connection(
+                                    tcp_stream,
+                                    peer_addr,
+                                    None,
+                                    service.clone()
+                                )?;
+                            }
+                        },
                         Either::Right(unix_stream) => {
                             Self::handle_connection(
                                 unix_stream,
                                 peer_addr,
+                                None,
                                 service.clone()
                             )?;
                         }
@@ -216,9 +402,47 @@ where
         }
     }
 
+    fn handle_tls_connection<S, T, F, B>(
+        tls_acceptor: TlsAcceptor,
+        stream: S,
+        remote_peer: SocketAddress,
+        handler: T,
+    ) -> anyhow::Result<()>
+    where
+        S: AsyncWrite + AsyncRead + Unpin + Send + 'static,
+        F: Send,
+        B: http_body::Body + Send + 'static,
+        <B as http_body::Body>::Data: Send + 'static,
+        <B as http_body::Body>::Error: std::error::Error + Sync + Send + 'static,
+        T: tower::Service<
+                Request<Incoming>,
+                Response = Response<B>,
+                Error = Infallible,
+                Future = F,
+            > + Clone
+            + Send
+            + 'static,
+    {
+        // Complete the handshake in its own task, not to block accepting other connections
+        TaskCenter::spawn(TaskKind::Ingress, "ingress-tls-handshake", async move {
+            match tls_acceptor.accept(stream).await {
+                Ok((tls_stream, client_certificate)) => {
+                    Self::handle_connection(tls_stream, remote_peer, client_certificate, handler)
+                }
+                Err(err) => {
+                    debug!("TLS handshake failed: {err}");
+                    Ok(())
+                }
+            }
+        })?;
+
+        Ok(())
+    }
+
     fn handle_connection<S, T, F, B>(
         stream: S,
         remote_peer: SocketAddress,
+        client_certificate: Option<ClientCertificate>,
         handler: T,
     ) -> anyhow::Result<()>
     where
@@ -241,6 +465,9 @@ where
         let handler = hyper_util::service::TowerToHyperService::new(handler.map_request(
             move |mut req: Request<Incoming>| {
                 req.extensions_mut().insert(connect_info.clone());
+                if let Some(client_certificate) = &client_certificate {
+                    req.extensions_mut().insert(client_certificate.clone());
+                }
                 req
             },
         ));
@@ -249,7 +476,7 @@ where
         TaskCenter::spawn(TaskKind::Ingress, "ingress", async move {
             let shutdown = cancellation_watcher();
             let auto_connection = auto::Builder::new(TaskCenterExecutor);
-            let serve_connection_fut = auto_connection.serve_connection(io, handler);
+            let serve_connection_fut = auto_connection.serve_connection_with_upgrades(io, handler);
 
             tokio::select! {
                 res = serve_connection_fut => {
@@ -0,0 +1,183 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::watch;
+
+use super::{SubscriptionCompletion, SubscriptionTarget};
+
+type CompletionSender = Arc<watch::Sender<Option<SubscriptionCompletion>>>;
+
+/// Registry of the subscribers waiting for the completion of invocations.
+///
+/// Subscribers of the same target share a single [`watch`] channel, which is removed from the
+/// registry once the target completes, or once its last subscriber is dropped.
+#[derive(Clone, Default)]
+pub(crate) struct SubscriptionRegistry {
+    subscriptions: Arc<Mutex<HashMap<SubscriptionTarget, CompletionSender>>>,
+}
+
+impl SubscriptionRegistry {
+    /// Subscribes to the completion of the given target. The returned sender is set only for the
+    /// first subscriber of the target, which is responsible for waiting for the completion.
+    pub(crate) fn subscribe(
+        &self,
+        target: SubscriptionTarget,
+    ) -> (Subscription, Option<CompletionSender>) {
+        let mut subscriptions = self.subscriptions.lock();
+        let (sender, is_first) = match subscriptions.get(&target) {
+            Some(sender) => (Arc::clone(sender), false),
+            None => {
+                let sender = Arc::new(watch::Sender::new(None));
+                subscriptions.insert(target.clone(), Arc::clone(&sender));
+                (sender, true)
+            }
+        };
+
+        let subscription = Subscription {
+            registry: self.clone(),
+            receiver: Some(sender.subscribe()),
+            target,
+        };
+        (subscription, is_first.then_some(sender))
+    }
+
+    /// Completes the subscriptions of the given sender, unless they were completed already.
+    pub(crate) fn complete_sender(
+        &self,
+        target: &SubscriptionTarget,
+        sender: &CompletionSender,
+        completion: SubscriptionCompletion,
+    ) {
+        let mut subscriptions = self.subscriptions.lock();
+        if subscriptions
+            .get(target)
+            .is_some_and(|current| Arc::ptr_eq(current, sender))
+        {
+            subscriptions.remove(target);
+        }
+        drop(subscriptions);
+        sender.send_if_modified(|current| {
+            if current.is_none() {
+                *current = Some(completion);
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.subscriptions.lock().len()
+    }
+}
+
+/// Subscription to the completion of a target, unregistered when dropped.
+pub(crate) struct Subscription {
+    registry: SubscriptionRegistry,
+    receiver: Option<watch::Receiver<Option<SubscriptionCompletion>>>,
+    target: SubscriptionTarget,
+}
+
+impl Subscription {
+    /// Waits for the completion of the target.
+    pub(crate) async fn completion(mut self) -> Option<SubscriptionCompletion> {
+        let receiver = self
+            .receiver
+            .as_mut()
+            .expect("receiver is set until dropped");
+        receiver
+            .wait_for(Option::is_some)
+            .await
+            .ok()
+            .and_then(|completion| completion.clone())
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut subscriptions = self.registry.subscriptions.lock();
+        // Drop the receiver while holding the lock, so that no other subscriber can join
+        // in between
+        drop(self.receiver.take());
+        if subscriptions
+            .get(&self.target)
+            .is_some_and(|sender| sender.receiver_count() == 0)
+        {
+            subscriptions.remove(&self.target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_types::identifiers::InvocationId;
+    use restate_types::invocation::client::AttachInvocationResponse;
+
+    #[tokio::test]
+    async fn subscribers_share_the_completion() {
+        let registry = SubscriptionRegistry::default();
+        let target = SubscriptionTarget::Invocation(InvocationId::mock_random());
+
+        let (first, first_sender) = registry.subscribe(target.clone());
+        let (second, second_sender) = registry.subscribe(target.clone());
+        assert!(second_sender.is_none());
+        assert_eq!(registry.len(), 1);
+
+        registry.complete_sender(
+            &target,
+            &first_sender.unwrap(),
+            SubscriptionCompletion::Invocation(AttachInvocationResponse::NotFound),
+        );
+        assert_eq!(registry.len(), 0);
+
+        for subscription in [first, second] {
+            assert!(matches!(
+                subscription.completion().await,
+                Some(SubscriptionCompletion::Invocation(
+                    AttachInvocationResponse::NotFound
+                ))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn last_dropped_subscriber_unregisters() {
+        let registry = SubscriptionRegistry::default();
+        let target = SubscriptionTarget::Invocation(InvocationId::mock_random());
+
+        let (first, sender) = registry.subscribe(target.clone());
+        let sender = sender.unwrap();
+        let (second, _) = registry.subscribe(target.clone());
+
+        drop(first);
+        assert_eq!(registry.len(), 1);
+        drop(second);
+        assert_eq!(registry.len(), 0);
+        assert_eq!(sender.receiver_count(), 0);
+
+        // A new subscriber is again the first one, and the stale sender doesn't complete it
+        let (third, new_sender) = registry.subscribe(target.clone());
+        assert!(new_sender.is_some());
+        registry.complete_sender(
+            &target,
+            &sender,
+            SubscriptionCompletion::Invocation(AttachInvocationResponse::NotFound),
+        );
+        assert_eq!(registry.len(), 1);
+        drop(third);
+    }
+}
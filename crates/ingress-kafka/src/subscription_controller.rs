@@ -18,6 +18,7 @@ use anyhow::Context;
 use rdkafka::error::KafkaError;
 use restate_bifrost::Bifrost;
 use restate_core::cancellation_watcher;
+use restate_ingress_sqs::{SqsConsumerTask, SqsIngressDispatcher, SqsMessageSender};
 use restate_types::config::IngressOptions;
 use restate_types::identifiers::SubscriptionId;
 use restate_types::live::{Live, LiveLoad};
@@ -41,10 +42,38 @@ pub enum Error {
     Kafka(#[from] KafkaError),
 }
 
+/// Task consuming the source of a subscription
+#[derive(Clone)]
+enum SubscriptionTask {
+    Kafka(consumer_task::ConsumerTask),
+    Sqs(SqsConsumerTask),
+}
+
+#[derive(Debug, thiserror::Error)]
+enum SubscriptionTaskError {
+    #[error(transparent)]
+    Kafka(#[from] consumer_task::Error),
+    #[error(transparent)]
+    Sqs(#[from] restate_ingress_sqs::Error),
+}
+
+impl SubscriptionTask {
+    async fn run(
+        self,
+        rx: tokio::sync::oneshot::Receiver<()>,
+    ) -> Result<(), SubscriptionTaskError> {
+        match self {
+            SubscriptionTask::Kafka(task) => task.run(rx).await.map_err(Into::into),
+            SubscriptionTask::Sqs(task) => task.run(rx).await.map_err(Into::into),
+        }
+    }
+}
+
 // For simplicity of the current implementation, this currently lives in this module
 // In future versions, we should either pull this out in a separate process, or generify it and move it to the worker, or an ad-hoc module
 pub struct Service {
     dispatcher: KafkaIngressDispatcher,
+    sqs_dispatcher: SqsIngressDispatcher,
     schema: Live<Schema>,
 
     commands_tx: SubscriptionCommandSender,
@@ -54,10 +83,12 @@ pub struct Service {
 impl Service {
     pub fn new(bifrost: Bifrost, schema: Live<Schema>) -> Service {
         metric_definitions::describe_metrics();
+        restate_ingress_sqs::describe_metrics();
         let (commands_tx, commands_rx) = mpsc::channel(10);
 
         Service {
-            dispatcher: KafkaIngressDispatcher::new(bifrost),
+            dispatcher: KafkaIngressDispatcher::new(bifrost.clone()),
+            sqs_dispatcher: SqsIngressDispatcher::new(bifrost),
             schema,
             commands_tx,
             commands_rx,
@@ -119,9 +150,30 @@ impl Service {
         subscription: Subscription,
         task_orchestrator: &mut TaskOrchestrator,
     ) -> anyhow::Result<()> {
-        let mut client_config = rdkafka::ClientConfig::new();
+        let subscription_id = subscription.id();
 
-        let Source::Kafka { cluster, topic, .. } = subscription.source();
+        let consumer_task = match subscription.source().clone() {
+            Source::Kafka { cluster, topic } => SubscriptionTask::Kafka(
+                self.create_kafka_consumer_task(options, &cluster, topic, subscription)?,
+            ),
+            Source::Sqs { connection, queue } => SubscriptionTask::Sqs(
+                self.create_sqs_consumer_task(options, &connection, queue, subscription)?,
+            ),
+        };
+
+        task_orchestrator.start(subscription_id, consumer_task);
+
+        Ok(())
+    }
+
+    fn create_kafka_consumer_task(
+        &self,
+        options: &IngressOptions,
+        cluster: &str,
+        topic: String,
+        subscription: Subscription,
+    ) -> anyhow::Result<consumer_task::ConsumerTask> {
+        let mut client_config = rdkafka::ClientConfig::new();
 
         // Copy cluster options and subscription metadata into client_config
         let cluster_options = options
@@ -141,18 +193,34 @@ impl Service {
         client_config.set("enable.auto.commit", "true");
         client_config.set("enable.auto.offset.store", "false");
 
-        let subscription_id = subscription.id();
-
         // Create the consumer task
-        let consumer_task = consumer_task::ConsumerTask::new(
+        Ok(consumer_task::ConsumerTask::new(
             client_config,
-            vec![topic.to_string()],
+            vec![topic],
             MessageSender::new(subscription, self.dispatcher.clone(), self.schema.clone()),
-        );
-
-        task_orchestrator.start(subscription_id, consumer_task);
+        ))
+    }
 
-        Ok(())
+    fn create_sqs_consumer_task(
+        &self,
+        options: &IngressOptions,
+        connection: &str,
+        queue: String,
+        subscription: Subscription,
+    ) -> anyhow::Result<SqsConsumerTask> {
+        let connection_options = options
+            .get_sqs_connection(connection)
+            .with_context(|| format!("IngressOptions is expected to contain the SQS connection '{}'. This might happen if you registered a subscription with a connection name, but this connection is not available anymore in the configuration. Configured SQS connections: {:?}", connection, options.available_sqs_connections()))?;
+
+        Ok(SqsConsumerTask::new(
+            connection_options.clone(),
+            queue,
+            SqsMessageSender::new(
+                subscription,
+                self.sqs_dispatcher.clone(),
+                self.schema.clone(),
+            ),
+        ))
     }
 
     fn handle_stop_subscription(
@@ -188,7 +256,7 @@ impl Service {
 }
 
 mod task_orchestrator {
-    use crate::consumer_task;
+    use super::{SubscriptionTask, SubscriptionTaskError};
     use restate_core::{TaskCenterFutureExt, TaskKind};
     use restate_timer_queue::TimerQueue;
     use restate_types::identifiers::SubscriptionId;
@@ -202,7 +270,7 @@ mod task_orchestrator {
 
     struct TaskState {
         // We use this to restart the consumer task in case of a failure
-        consumer_task_clone: consumer_task::ConsumerTask,
+        consumer_task_clone: SubscriptionTask,
         task_state_inner: TaskStateInner,
         retry_iter: RetryIter<'static>,
     }
@@ -219,7 +287,7 @@ mod task_orchestrator {
         retry_policy: RetryPolicy,
         running_tasks_to_subscriptions: HashMap<task::Id, SubscriptionId>,
         subscription_id_to_task_state: HashMap<SubscriptionId, TaskState>,
-        tasks: JoinSet<Result<(), consumer_task::Error>>,
+        tasks: JoinSet<Result<(), SubscriptionTaskError>>,
         timer_queue: TimerQueue<SubscriptionId>,
     }
 
@@ -251,7 +319,7 @@ mod task_orchestrator {
 
         fn handle_task_closed(
             &mut self,
-            result: Result<(task::Id, Result<(), consumer_task::Error>), JoinError>,
+            result: Result<(task::Id, Result<(), SubscriptionTaskError>), JoinError>,
         ) {
             let task_id = match result {
                 Ok((id, _)) => id,
@@ -339,7 +407,7 @@ mod task_orchestrator {
         pub(super) fn start(
             &mut self,
             subscription_id: SubscriptionId,
-            consumer_task_clone: consumer_task::ConsumerTask,
+            consumer_task_clone: SubscriptionTask,
         ) {
             // Shutdown old task, if any
             if let Some(task_state) = self.subscription_id_to_task_state.remove(&subscription_id) {
@@ -356,17 +424,25 @@ mod task_orchestrator {
                 "Spawning the consumer task for subscription id {}",
                 subscription_id
             );
+            let (name, task_kind, task_name) = match &consumer_task_clone {
+                SubscriptionTask::Kafka(_) => {
+                    ("kafka-consumer", TaskKind::Kafka, "kafka-consumer-task")
+                }
+                SubscriptionTask::Sqs(_) => {
+                    ("sqs-consumer", TaskKind::Ingress, "sqs-consumer-task")
+                }
+            };
             let task_id = self
                 .tasks
                 .build_task()
-                .name("kafka-consumer")
+                .name(name)
                 .spawn({
                     let consumer_task_clone = consumer_task_clone.clone();
                     consumer_task_clone
                         .run(rx)
-                        .in_current_tc_as_task(TaskKind::Kafka, "kafka-consumer-task")
+                        .in_current_tc_as_task(task_kind, task_name)
                 })
-                .expect("to spawn kafka consumer task")
+                .expect("to spawn consumer task")
                 .id();
 
             self.running_tasks_to_subscriptions
@@ -0,0 +1,306 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_sqs::types::{DeleteMessageBatchRequestEntry, Message, MessageSystemAttributeName};
+use bytes::Bytes;
+use metrics::counter;
+use restate_types::config::SqsConnectionOptions;
+use restate_types::invocation::Header;
+use restate_types::live::Live;
+use restate_types::schema::Schema;
+use restate_types::schema::subscriptions::Subscription;
+use tokio::sync::oneshot;
+use tracing::{Instrument, debug, info, info_span, warn};
+
+use crate::dispatcher::{DispatchSqsEvent, SqsIngressDispatcher, SqsIngressEvent};
+use crate::metric_definitions::SQS_INGRESS_REQUESTS;
+
+const DEFAULT_WAIT_TIME_SECONDS: i32 = 20;
+const DEFAULT_MAX_NUMBER_OF_MESSAGES: i32 = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqs(Box<aws_sdk_sqs::Error>),
+    #[error("cannot resolve the url of the queue {0}")]
+    QueueUrlNotFound(String),
+    #[error("error processing message {message_id} of queue {queue}: {cause}")]
+    Event {
+        queue: String,
+        message_id: String,
+        #[source]
+        cause: anyhow::Error,
+    },
+    #[error("ingress dispatcher channel is closed")]
+    IngressDispatcherClosed,
+}
+
+impl Error {
+    fn sqs(err: impl Into<aws_sdk_sqs::Error>) -> Self {
+        Error::Sqs(Box::new(err.into()))
+    }
+}
+
+#[derive(Clone)]
+pub struct SqsMessageSender {
+    subscription: Subscription,
+    dispatcher: SqsIngressDispatcher,
+    schema: Live<Schema>,
+
+    key_attribute: Option<String>,
+    subscription_id: String,
+    ingress_request_counter: metrics::Counter,
+}
+
+impl SqsMessageSender {
+    pub fn new(
+        subscription: Subscription,
+        dispatcher: SqsIngressDispatcher,
+        schema: Live<Schema>,
+    ) -> Self {
+        Self {
+            key_attribute: subscription.metadata().get("key.attribute").cloned(),
+            subscription_id: subscription.id().to_string(),
+            ingress_request_counter: counter!(
+                SQS_INGRESS_REQUESTS,
+                "subscription" => subscription.id().to_string()
+            ),
+            subscription,
+            dispatcher,
+            schema,
+        }
+    }
+
+    async fn send(&self, queue: &str, msg: &Message) -> Result<(), Error> {
+        let message_id = msg.message_id().unwrap_or_default();
+
+        // Prepare ingress span
+        let ingress_span = info_span!(
+            "sqs_ingress_consume",
+            otel.name = "sqs_ingress_consume",
+            messaging.system = "aws_sqs",
+            messaging.operation = "receive",
+            messaging.source.name = queue,
+            messaging.destination.name = %self.subscription.sink(),
+            messaging.message.id = message_id,
+            restate.subscription.id = %self.subscription.id(),
+        );
+        info!(parent: &ingress_span, "Processing SQS ingress request");
+
+        let key = self.key_attribute.as_ref().and_then(|key_attribute| {
+            msg.message_attributes()
+                .and_then(|attributes| attributes.get(key_attribute))
+                .and_then(|value| value.string_value())
+        });
+        let payload = msg
+            .body()
+            .map(|body| Bytes::copy_from_slice(body.as_bytes()))
+            .unwrap_or_default();
+        let headers = Self::generate_events_attributes(msg, queue, &self.subscription_id);
+
+        let req = SqsIngressEvent::new(
+            &self.subscription,
+            self.schema.pinned(),
+            key,
+            payload,
+            message_id,
+            headers,
+            queue,
+        )
+        .map_err(|cause| Error::Event {
+            queue: queue.to_owned(),
+            message_id: message_id.to_owned(),
+            cause,
+        })?;
+
+        self.ingress_request_counter.increment(1);
+
+        self.dispatcher
+            .dispatch_sqs_event(req)
+            .instrument(ingress_span)
+            .await
+            .map_err(|_| Error::IngressDispatcherClosed)?;
+        Ok(())
+    }
+
+    fn generate_events_attributes(
+        msg: &Message,
+        queue: &str,
+        subscription_id: &str,
+    ) -> Vec<Header> {
+        let mut headers = Vec::with_capacity(5);
+        headers.push(Header::new("sqs.queue", queue));
+        if let Some(message_id) = msg.message_id() {
+            headers.push(Header::new("sqs.message_id", message_id));
+        }
+        if let Some(system_attributes) = msg.attributes() {
+            if let Some(sent_timestamp) =
+                system_attributes.get(&MessageSystemAttributeName::SentTimestamp)
+            {
+                headers.push(Header::new("sqs.sent_timestamp", sent_timestamp.as_str()));
+            }
+            if let Some(receive_count) =
+                system_attributes.get(&MessageSystemAttributeName::ApproximateReceiveCount)
+            {
+                headers.push(Header::new(
+                    "sqs.approximate_receive_count",
+                    receive_count.as_str(),
+                ));
+            }
+        }
+        headers.push(Header::new(
+            "restate.subscription.id".to_string(),
+            subscription_id,
+        ));
+
+        // Propagate the string message attributes, e.g. to carry the tracing context
+        if let Some(message_attributes) = msg.message_attributes() {
+            for (name, value) in message_attributes {
+                if let Some(value) = value.string_value() {
+                    headers.push(Header::new(name.as_str(), value));
+                }
+            }
+        }
+
+        headers
+    }
+}
+
+#[derive(Clone)]
+pub struct SqsConsumerTask {
+    connection_options: SqsConnectionOptions,
+    queue: String,
+    wait_time_seconds: i32,
+    max_number_of_messages: i32,
+    visibility_timeout_seconds: Option<i32>,
+    sender: SqsMessageSender,
+}
+
+impl SqsConsumerTask {
+    pub fn new(
+        connection_options: SqsConnectionOptions,
+        queue: String,
+        sender: SqsMessageSender,
+    ) -> Self {
+        // Options are validated when creating the subscription
+        let metadata = sender.subscription.metadata();
+        let parse_option = |name: &str| metadata.get(name).and_then(|v| v.parse::<i32>().ok());
+
+        Self {
+            wait_time_seconds: parse_option("wait.time.seconds")
+                .unwrap_or(DEFAULT_WAIT_TIME_SECONDS),
+            max_number_of_messages: parse_option("max.number.of.messages")
+                .unwrap_or(DEFAULT_MAX_NUMBER_OF_MESSAGES),
+            visibility_timeout_seconds: parse_option("visibility.timeout.seconds"),
+            connection_options,
+            queue,
+            sender,
+        }
+    }
+
+    pub async fn run(self, mut rx: oneshot::Receiver<()>) -> Result<(), Error> {
+        debug!(
+            restate.subscription.id = %self.sender.subscription.id(),
+            "Starting SQS consumer for queue {} with connection {:?}",
+            self.queue, self.connection_options
+        );
+
+        let client = self.create_client().await;
+        let queue_url = client
+            .get_queue_url()
+            .queue_name(&self.queue)
+            .send()
+            .await
+            .map_err(Error::sqs)?
+            .queue_url()
+            .ok_or_else(|| Error::QueueUrlNotFound(self.queue.clone()))?
+            .to_owned();
+
+        loop {
+            let received = tokio::select! {
+                res = client
+                    .receive_message()
+                    .queue_url(&queue_url)
+                    .max_number_of_messages(self.max_number_of_messages)
+                    .wait_time_seconds(self.wait_time_seconds)
+                    .set_visibility_timeout(self.visibility_timeout_seconds)
+                    .message_attribute_names("All")
+                    .message_system_attribute_names(MessageSystemAttributeName::All)
+                    .send() => res.map_err(Error::sqs)?,
+                _ = &mut rx => {
+                    return Ok(());
+                }
+            };
+
+            let mut delete_entries = Vec::with_capacity(received.messages().len());
+            for msg in received.messages() {
+                match self.sender.send(&self.queue, msg).await {
+                    Ok(()) => {}
+                    Err(err @ Error::Event { .. }) => {
+                        // Leave the message in the queue: it becomes visible again once the
+                        // visibility timeout expires, and the redrive policy of the queue
+                        // eventually moves it to the dead-letter queue.
+                        warn!(
+                            restate.subscription.id = %self.sender.subscription.id(),
+                            "Skipping SQS message: {err}"
+                        );
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                }
+                if let Some(receipt_handle) = msg.receipt_handle() {
+                    delete_entries.push(
+                        DeleteMessageBatchRequestEntry::builder()
+                            .id(delete_entries.len().to_string())
+                            .receipt_handle(receipt_handle)
+                            .build()
+                            .expect("id and receipt handle are set"),
+                    );
+                }
+            }
+
+            if delete_entries.is_empty() {
+                continue;
+            }
+            let deleted = client
+                .delete_message_batch()
+                .queue_url(&queue_url)
+                .set_entries(Some(delete_entries))
+                .send()
+                .await
+                .map_err(Error::sqs)?;
+            for failed in deleted.failed() {
+                // The message is redelivered, and deduplicated by its idempotency key
+                warn!(
+                    restate.subscription.id = %self.sender.subscription.id(),
+                    "Failed to delete an SQS message of queue {} after dispatching it: {}",
+                    self.queue,
+                    failed.message().unwrap_or(failed.code())
+                );
+            }
+        }
+    }
+
+    async fn create_client(&self) -> aws_sdk_sqs::Client {
+        let mut config = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(aws_profile) = &self.connection_options.aws_profile {
+            config = config.profile_name(aws_profile);
+        }
+        if let Some(region) = &self.connection_options.region {
+            config = config.region(Region::new(region.clone()));
+        }
+        if let Some(endpoint_url) = &self.connection_options.endpoint_url {
+            config = config.endpoint_url(endpoint_url);
+        }
+
+        aws_sdk_sqs::Client::new(&config.load().await)
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use anyhow::bail;
+use bytes::Bytes;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::{Span, SpanContext, TraceContextExt};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use std::borrow::Borrow;
+use std::sync::Arc;
+use tracing::debug;
+
+use restate_bifrost::Bifrost;
+use restate_types::identifiers::{InvocationId, WithPartitionKey};
+use restate_types::invocation::{
+    InvocationTarget, InvocationTargetType, ServiceInvocation, SpanRelation, WorkflowHandlerType,
+};
+use restate_types::live;
+use restate_types::schema::Schema;
+use restate_types::schema::invocation_target::{DeploymentStatus, InvocationTargetResolver};
+use restate_types::schema::subscriptions::{EventInvocationTargetTemplate, Sink, Subscription};
+use restate_wal_protocol::{Command, Destination, Envelope, Header, Source};
+
+#[derive(Debug)]
+pub struct SqsIngressEvent {
+    service_invocation: Box<ServiceInvocation>,
+}
+
+impl SqsIngressEvent {
+    pub fn new(
+        subscription: &Subscription,
+        schema: live::Pinned<Schema>,
+        key: Option<&str>,
+        payload: Bytes,
+        message_id: &str,
+        headers: Vec<restate_types::invocation::Header>,
+        queue: &str,
+    ) -> Result<Self, anyhow::Error> {
+        let key = || {
+            key.map(str::to_owned)
+                .ok_or_else(|| anyhow::anyhow!("The SQS message has no key attribute"))
+        };
+        let invocation_target = match subscription.sink() {
+            Sink::Invocation {
+                event_invocation_target_template,
+            } => match event_invocation_target_template {
+                EventInvocationTargetTemplate::Service { name, handler } => {
+                    InvocationTarget::service(name.clone(), handler.clone())
+                }
+                EventInvocationTargetTemplate::VirtualObject {
+                    name,
+                    handler,
+                    handler_ty,
+                } => InvocationTarget::virtual_object(
+                    name.clone(),
+                    key()?,
+                    handler.clone(),
+                    *handler_ty,
+                ),
+                EventInvocationTargetTemplate::Workflow {
+                    name,
+                    handler,
+                    handler_ty,
+                } => InvocationTarget::workflow(name.clone(), key()?, handler.clone(), *handler_ty),
+            },
+        };
+
+        let target = schema
+            .resolve_latest_invocation_target(
+                invocation_target.service_name(),
+                invocation_target.handler_name(),
+            )
+            .ok_or_else(|| anyhow::anyhow!("Service and handler are not registered"))?;
+
+        if let DeploymentStatus::Deprecated(dp_id) = target.deployment_status {
+            bail!(
+                "the service {} is exposed by the deprecated deployment {dp_id}, please upgrade the SDK.",
+                invocation_target.service_name()
+            )
+        }
+
+        // SQS delivers messages at least once, and has no offsets to deduplicate them:
+        // the message id is stable across redeliveries, hence we use it as idempotency key.
+        // Workflow runs are deduplicated by their key already, and don't accept idempotency keys.
+        let idempotency_key = (invocation_target.invocation_target_ty()
+            != InvocationTargetType::Workflow(WorkflowHandlerType::Workflow))
+        .then_some(message_id);
+        let invocation_retention = target.compute_retention(idempotency_key.is_some());
+
+        let invocation_id = InvocationId::generate(&invocation_target, idempotency_key);
+
+        let ingress_span_context = prepare_tracing_span(
+            &invocation_id,
+            &invocation_target,
+            &headers,
+            queue,
+            message_id,
+        );
+
+        let mut service_invocation = Box::new(ServiceInvocation::initialize(
+            invocation_id,
+            invocation_target,
+            restate_types::invocation::Source::Subscription(subscription.id()),
+        ));
+        service_invocation.with_related_span(SpanRelation::parent(ingress_span_context));
+        service_invocation.argument = payload;
+        service_invocation.headers = headers;
+        service_invocation.idempotency_key = idempotency_key.map(|key| key.to_owned().into());
+        service_invocation.with_retention(invocation_retention);
+
+        Ok(SqsIngressEvent { service_invocation })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IngressDispatchError {
+    #[error("bifrost error: {0}")]
+    WalProtocol(#[from] restate_bifrost::AppendError),
+}
+
+/// Dispatches a request from the SQS ingress to bifrost
+pub trait DispatchSqsEvent {
+    fn dispatch_sqs_event(
+        &self,
+        event: SqsIngressEvent,
+    ) -> impl std::future::Future<Output = Result<(), IngressDispatchError>> + Send;
+}
+
+#[derive(Clone)]
+pub struct SqsIngressDispatcher {
+    bifrost: Bifrost,
+}
+
+impl SqsIngressDispatcher {
+    pub fn new(bifrost: Bifrost) -> Self {
+        Self { bifrost }
+    }
+}
+
+impl DispatchSqsEvent for SqsIngressDispatcher {
+    async fn dispatch_sqs_event(
+        &self,
+        ingress_request: SqsIngressEvent,
+    ) -> Result<(), IngressDispatchError> {
+        let SqsIngressEvent { service_invocation } = ingress_request;
+
+        // No deduplication information, the invocation is deduplicated by its idempotency key
+        let header = Header {
+            source: Source::Ingress {},
+            dest: Destination::Processor {
+                partition_key: service_invocation.partition_key(),
+                dedup: None,
+            },
+        };
+        let envelope = Envelope::new(header, Command::Invoke(service_invocation));
+        let (log_id, lsn) =
+            restate_bifrost::append_to_bifrost(&self.bifrost, Arc::new(envelope)).await?;
+
+        debug!(
+            log_id = %log_id,
+            lsn = %lsn,
+            "Ingress request written to bifrost"
+        );
+        Ok(())
+    }
+}
+
+fn prepare_tracing_span(
+    invocation_id: &InvocationId,
+    invocation_target: &InvocationTarget,
+    headers: &[restate_types::invocation::Header],
+    queue: &str,
+    message_id: &str,
+) -> SpanContext {
+    let tracing_context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    let inbound_span = tracing_context.span();
+
+    let relation = if inbound_span.span_context().is_valid() {
+        SpanRelation::parent(inbound_span.span_context())
+    } else {
+        SpanRelation::None
+    };
+
+    let span = restate_tracing_instrumentation::info_invocation_span!(
+        relation = relation,
+        prefix = "ingress_sqs",
+        id = invocation_id,
+        target = invocation_target,
+        tags = (
+            messaging.system = "aws_sqs",
+            messaging.operation.type = "process",
+            messaging.message.id = message_id.to_owned(),
+            messaging.source.name = queue.to_owned()
+        )
+    );
+
+    span.span_context().clone()
+}
+
+struct HeaderExtractor<'a>(pub &'a [restate_types::invocation::Header]);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(key))
+            .map(|value| value.value.borrow())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.iter().map(|h| h.name.borrow()).collect::<Vec<_>>()
+    }
+}
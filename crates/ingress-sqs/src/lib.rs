@@ -0,0 +1,24 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Consumes AWS SQS queues, dispatching their messages as invocations of the subscription sinks.
+//!
+//! The lifecycle of the consumer tasks is driven by the subscription controller, which runs
+//! a [`SqsConsumerTask`] for every subscription with an SQS source.
+
+mod consumer_task;
+mod dispatcher;
+mod metric_definitions;
+
+pub use consumer_task::{Error, SqsConsumerTask, SqsMessageSender};
+pub use dispatcher::{
+    DispatchSqsEvent, IngressDispatchError, SqsIngressDispatcher, SqsIngressEvent,
+};
+pub use metric_definitions::describe_metrics;
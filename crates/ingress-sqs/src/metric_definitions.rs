@@ -0,0 +1,21 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use metrics::{Unit, describe_counter};
+
+pub const SQS_INGRESS_REQUESTS: &str = "restate.sqs_ingress.requests.total";
+
+pub fn describe_metrics() {
+    describe_counter!(
+        SQS_INGRESS_REQUESTS,
+        Unit::Count,
+        "Number of SQS ingress requests"
+    );
+}
@@ -18,7 +18,10 @@ pub use effects::*;
 pub use entry_enricher::EntryEnricher;
 pub use handle::*;
 pub use invocation_reader::JournalMetadata;
-pub use status_handle::{InvocationErrorReport, InvocationStatusReport, StatusHandle};
+pub use status_handle::{
+    InvocationErrorReport, InvocationStats, InvocationStatsReport, InvocationStatusReport,
+    LatencySketch, StatusHandle,
+};
 
 #[cfg(any(test, feature = "test-util"))]
 pub mod test_util {
@@ -73,6 +76,7 @@ pub mod test_util {
                     0,
                     MillisSinceEpoch::UNIX_EPOCH,
                     0,
+                    None,
                 ),
                 futures::stream::empty(),
             )))
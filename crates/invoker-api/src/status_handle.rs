@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use bytestring::ByteString;
 use codederror::Code;
 use restate_types::errors::InvocationError;
 use restate_types::identifiers::{DeploymentId, InvocationId, PartitionKey};
@@ -16,7 +17,7 @@ use restate_types::journal::{EntryIndex, EntryType};
 use restate_types::service_protocol::ServiceProtocolVersion;
 use std::future::Future;
 use std::ops::RangeInclusive;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 // -- Status data structure
 
@@ -30,6 +31,8 @@ pub struct InvocationStatusReportInner {
     pub last_attempt_deployment_id: Option<DeploymentId>,
     pub last_attempt_protocol_version: Option<ServiceProtocolVersion>,
     pub last_attempt_server: Option<String>,
+    /// Configured diagnostic headers of the last attempt response, as name/value pairs.
+    pub last_attempt_diagnostic_headers: Vec<(String, String)>,
 }
 
 impl Default for InvocationStatusReportInner {
@@ -43,6 +46,7 @@ impl Default for InvocationStatusReportInner {
             last_attempt_deployment_id: None,
             last_attempt_protocol_version: None,
             last_attempt_server: None,
+            last_attempt_diagnostic_headers: Vec::new(),
         }
     }
 }
@@ -106,6 +110,10 @@ impl InvocationStatusReport {
     pub fn last_attempt_server(&self) -> Option<&str> {
         self.2.last_attempt_server.as_deref()
     }
+
+    pub fn last_attempt_diagnostic_headers(&self) -> &[(String, String)] {
+        &self.2.last_attempt_diagnostic_headers
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -117,9 +125,92 @@ pub struct InvocationErrorReport {
     pub related_entry_type: Option<EntryType>,
 }
 
+// -- Statistics data structure
+
+/// Number of buckets of [`LatencySketch`].
+const LATENCY_SKETCH_BUCKETS: usize = 32;
+
+/// Histogram of durations with millisecond resolution, where the upper bound of each bucket
+/// doubles the one of the previous bucket. Quantiles are approximated with the upper bound of the
+/// bucket they fall into.
+#[derive(Debug, Clone, Default)]
+pub struct LatencySketch {
+    buckets: [u64; LATENCY_SKETCH_BUCKETS],
+    count: u64,
+    sum_millis: u64,
+    max_millis: u64,
+}
+
+impl LatencySketch {
+    pub fn record(&mut self, duration: Duration) {
+        let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        let bucket = (u64::BITS - millis.leading_zeros()) as usize;
+        self.buckets[bucket.min(LATENCY_SKETCH_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.sum_millis = self.sum_millis.saturating_add(millis);
+        self.max_millis = self.max_millis.max(millis);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        self.sum_millis
+            .checked_div(self.count)
+            .map(Duration::from_millis)
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_millis(self.max_millis))
+    }
+
+    /// Returns the approximated quantile, where `quantile` is between 0 and 1.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                // Values in bucket `n` are lower than 2^n milliseconds
+                let upper_bound = (1u64 << bucket).saturating_sub(1);
+                return Some(Duration::from_millis(upper_bound.min(self.max_millis)));
+            }
+        }
+        self.max()
+    }
+}
+
+/// Statistics of the invocation attempts of a handler.
+#[derive(Debug, Clone, Default)]
+pub struct InvocationStats {
+    pub attempts: u64,
+    pub completed: u64,
+    pub suspended: u64,
+    pub failed: u64,
+    /// Duration of the attempts, from the start to the completion, suspension or failure.
+    pub attempt_duration: LatencySketch,
+}
+
+/// Statistics of the invocation attempts of a handler, within a time bucket.
+#[derive(Debug, Clone)]
+pub struct InvocationStatsReport {
+    pub partition_id: PartitionId,
+    pub bucket_start: SystemTime,
+    pub bucket_width: Duration,
+    pub service_name: ByteString,
+    pub handler_name: ByteString,
+    pub stats: InvocationStats,
+}
+
 /// Struct to access the status of the invocations currently handled by the invoker
 pub trait StatusHandle {
     type Iterator: Iterator<Item = InvocationStatusReport> + Send;
+    type StatsIterator: Iterator<Item = InvocationStatsReport> + Send;
 
     /// This method returns a snapshot of the status of all the invocations currently being processed by this invoker,
     /// filtered by the partition key range
@@ -129,6 +220,15 @@ pub trait StatusHandle {
         &self,
         keys: RangeInclusive<PartitionKey>,
     ) -> impl Future<Output = Self::Iterator> + Send;
+
+    /// This method returns the time-bucketed statistics of the invocation attempts executed by this
+    /// invoker for the partitions overlapping the given partition key range.
+    ///
+    /// Statistics are kept in memory for a limited time window, and are reset on leadership changes.
+    fn read_stats(
+        &self,
+        keys: RangeInclusive<PartitionKey>,
+    ) -> impl Future<Output = Self::StatsIterator> + Send;
 }
 
 #[cfg(any(test, feature = "test-util"))]
@@ -136,20 +236,55 @@ pub mod test_util {
     use super::*;
 
     #[derive(Debug, Clone, Default)]
-    pub struct MockStatusHandle(Vec<InvocationStatusReport>);
+    pub struct MockStatusHandle(Vec<InvocationStatusReport>, Vec<InvocationStatsReport>);
 
     impl MockStatusHandle {
         pub fn with(mut self, invocation_status_report: InvocationStatusReport) -> Self {
             self.0.push(invocation_status_report);
             self
         }
+
+        pub fn with_stats(mut self, invocation_stats_report: InvocationStatsReport) -> Self {
+            self.1.push(invocation_stats_report);
+            self
+        }
     }
 
     impl StatusHandle for MockStatusHandle {
         type Iterator = std::vec::IntoIter<InvocationStatusReport>;
+        type StatsIterator = std::vec::IntoIter<InvocationStatsReport>;
 
         async fn read_status(&self, _keys: RangeInclusive<PartitionKey>) -> Self::Iterator {
             self.0.clone().into_iter()
         }
+
+        async fn read_stats(&self, _keys: RangeInclusive<PartitionKey>) -> Self::StatsIterator {
+            self.1.clone().into_iter()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_sketch() {
+        let mut sketch = LatencySketch::default();
+        assert_eq!(sketch.quantile(0.5), None);
+        assert_eq!(sketch.mean(), None);
+
+        for millis in [0, 3, 10, 100, 1_000] {
+            sketch.record(Duration::from_millis(millis));
+        }
+
+        assert_eq!(sketch.count(), 5);
+        assert_eq!(sketch.mean(), Some(Duration::from_millis(222)));
+        assert_eq!(sketch.max(), Some(Duration::from_millis(1_000)));
+        assert_eq!(sketch.quantile(0.0), Some(Duration::ZERO));
+        // 10 falls into the bucket [8, 16)
+        assert_eq!(sketch.quantile(0.5), Some(Duration::from_millis(15)));
+        // Bounded by the max value
+        assert_eq!(sketch.quantile(1.0), Some(Duration::from_millis(1_000)));
     }
 }
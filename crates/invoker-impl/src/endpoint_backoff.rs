@@ -0,0 +1,109 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use restate_types::identifiers::DeploymentId;
+
+/// Upper bound of the factor applied to the retry policy delay.
+const MAX_BACKOFF_FACTOR: u32 = 16;
+/// The scaled delay never exceeds this bound, unless the retry policy delay is already higher.
+const MAX_SCALED_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Adapts the retry delays per deployment, depending on how the deployment behaved recently.
+///
+/// This follows an AIMD scheme: every attempt failing because the endpoint is overloaded or
+/// unreachable doubles the backoff factor of the deployment, while every successful attempt
+/// decreases it by one. Deployments that are behaving well are not tracked.
+#[derive(Debug, Default)]
+pub(crate) struct EndpointBackoff {
+    factors: HashMap<DeploymentId, u32>,
+}
+
+impl EndpointBackoff {
+    pub(crate) fn on_endpoint_unavailable(&mut self, deployment_id: DeploymentId) {
+        let factor = self.factors.entry(deployment_id).or_insert(1);
+        *factor = factor.saturating_mul(2).min(MAX_BACKOFF_FACTOR);
+    }
+
+    pub(crate) fn on_success(&mut self, deployment_id: DeploymentId) {
+        if let Some(factor) = self.factors.get_mut(&deployment_id) {
+            *factor -= 1;
+            if *factor <= 1 {
+                self.factors.remove(&deployment_id);
+            }
+        }
+    }
+
+    /// Scales the delay computed by the retry policy with the backoff factor of the deployment.
+    pub(crate) fn scale(&self, deployment_id: DeploymentId, delay: Duration) -> Duration {
+        match self.factors.get(&deployment_id) {
+            Some(factor) => (delay * *factor).min(delay.max(MAX_SCALED_DELAY)),
+            None => delay,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplicative_increase_additive_decrease() {
+        let mut backoff = EndpointBackoff::default();
+        let deployment_id = DeploymentId::new();
+        let other_deployment_id = DeploymentId::new();
+        let delay = Duration::from_millis(100);
+
+        assert_eq!(backoff.scale(deployment_id, delay), delay);
+
+        backoff.on_endpoint_unavailable(deployment_id);
+        backoff.on_endpoint_unavailable(deployment_id);
+        assert_eq!(backoff.scale(deployment_id, delay), delay * 4);
+        assert_eq!(backoff.scale(other_deployment_id, delay), delay);
+
+        for _ in 0..10 {
+            backoff.on_endpoint_unavailable(deployment_id);
+        }
+        assert_eq!(
+            backoff.scale(deployment_id, delay),
+            delay * MAX_BACKOFF_FACTOR
+        );
+
+        backoff.on_success(deployment_id);
+        assert_eq!(
+            backoff.scale(deployment_id, delay),
+            delay * (MAX_BACKOFF_FACTOR - 1)
+        );
+
+        for _ in 0..MAX_BACKOFF_FACTOR {
+            backoff.on_success(deployment_id);
+        }
+        assert_eq!(backoff.scale(deployment_id, delay), delay);
+        assert!(backoff.factors.is_empty());
+    }
+
+    #[test]
+    fn scaled_delay_is_bounded() {
+        let mut backoff = EndpointBackoff::default();
+        let deployment_id = DeploymentId::new();
+        backoff.on_endpoint_unavailable(deployment_id);
+
+        assert_eq!(
+            backoff.scale(deployment_id, Duration::from_secs(200)),
+            MAX_SCALED_DELAY
+        );
+        assert_eq!(
+            backoff.scale(deployment_id, Duration::from_secs(600)),
+            Duration::from_secs(600)
+        );
+    }
+}
@@ -12,8 +12,9 @@ use http::{HeaderName, HeaderValue};
 use restate_invoker_api::InvocationErrorReport;
 use restate_service_client::ServiceClientError;
 use restate_service_protocol::message::{EncodingError, MessageType};
+use restate_service_protocol_v4::message_codec::EncodingError as EncodingErrorV2;
 use restate_time_util::FriendlyDuration;
-use restate_types::errors::{InvocationError, InvocationErrorCode, codes};
+use restate_types::errors::{ErrorClass, InvocationError, InvocationErrorCode, codes};
 use restate_types::identifiers::DeploymentId;
 use restate_types::invocation::InvocationEpoch;
 use restate_types::journal::raw::RawEntryCodecError;
@@ -44,7 +45,7 @@ pub(crate) enum InvokerError {
 
     #[error("unexpected http status code: {0}")]
     #[code(restate_errors::RT0012)]
-    UnexpectedResponse(http::StatusCode),
+    UnexpectedResponse(http::StatusCode, Option<Duration>),
     #[error("cannot start the invocation because the SDK doesn't support the protocol version '{}' negotiated at discovery time", .0.as_repr())]
     #[code(restate_errors::RT0015)]
     BadNegotiatedServiceProtocolVersion(ServiceProtocolVersion),
@@ -171,7 +172,12 @@ pub(crate) enum InvokerError {
 
     #[error("service is temporary unavailable '{0}'")]
     #[code(restate_errors::RT0010)]
-    ServiceUnavailable(http::StatusCode),
+    ServiceUnavailable(http::StatusCode, Option<Duration>),
+
+    #[cfg(feature = "chaos")]
+    #[error("invocation attempt failed because of the injected faults")]
+    #[code(unknown)]
+    InjectedFault,
 }
 
 impl InvokerError {
@@ -189,8 +195,12 @@ impl InvokerError {
         }
     }
 
-    pub(crate) fn is_transient(&self) -> bool {
-        !matches!(self, InvokerError::NotInvoked)
+    pub(crate) fn error_class(&self) -> ErrorClass {
+        match self {
+            // The invocation doesn't exist anymore, there's nothing to retry
+            InvokerError::NotInvoked => ErrorClass::TerminalSystem,
+            _ => ErrorClass::RetryableWithBackoff,
+        }
     }
 
     pub(crate) fn should_bump_start_message_retry_count_since_last_stored_entry(&self) -> bool {
@@ -221,6 +231,29 @@ impl InvokerError {
         }
     }
 
+    /// Delay requested through the `Retry-After` header by the endpoint, or by the gateway in
+    /// front of it. Unlike [`Self::next_retry_interval_override`], this is a lower bound for
+    /// the delay computed by the retry policy, so the retry still counts as an attempt.
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match self {
+            InvokerError::UnexpectedResponse(_, retry_after)
+            | InvokerError::ServiceUnavailable(_, retry_after) => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Returns true if this error signals that the endpoint is overloaded or unreachable, rather
+    /// than a failure of the invocation itself.
+    pub(crate) fn is_endpoint_unavailable(&self) -> bool {
+        match self {
+            InvokerError::ServiceUnavailable(_, _) | InvokerError::Client(_) => true,
+            InvokerError::UnexpectedResponse(status, _) => {
+                *status == http::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+            _ => false,
+        }
+    }
+
     pub(crate) fn into_invocation_error(self) -> InvocationError {
         match self {
             InvokerError::Sdk(sdk_error) => *sdk_error.error,
@@ -241,6 +274,11 @@ impl InvokerError {
             e @ InvokerError::BadNegotiatedServiceProtocolVersion(_) => {
                 InvocationError::new(codes::UNSUPPORTED_MEDIA_TYPE, e.to_string())
             }
+            e @ (InvokerError::Encoding(EncodingError::MessageSizeLimit(..))
+            | InvokerError::EncoderV2(
+                EncodingErrorV2::MessageSizeLimit(..)
+                | EncodingErrorV2::OutgoingMessageSizeLimit(..),
+            )) => InvocationError::new(codes::PAYLOAD_TOO_LARGE, e.to_string()),
             e => InvocationError::internal(e.to_string()),
         }
     }
@@ -9,7 +9,9 @@
 // by the Apache License, Version 2.0.
 
 use restate_errors::NotRunningError;
-use restate_invoker_api::{Effect, InvocationStatusReport, InvokeInputJournal, StatusHandle};
+use restate_invoker_api::{
+    Effect, InvocationStatsReport, InvocationStatusReport, InvokeInputJournal, StatusHandle,
+};
 use restate_types::identifiers::{InvocationId, PartitionKey, PartitionLeaderEpoch};
 use restate_types::invocation::{InvocationEpoch, InvocationTarget};
 use restate_types::journal::Completion;
@@ -235,25 +237,31 @@ impl<SR: Send> restate_invoker_api::InvokerHandle<SR> for InvokerHandle<SR> {
     }
 }
 
+pub(crate) type StatusCommand = restate_futures_util::command::Command<
+    RangeInclusive<PartitionKey>,
+    Vec<InvocationStatusReport>,
+>;
+pub(crate) type StatsCommand = restate_futures_util::command::Command<
+    RangeInclusive<PartitionKey>,
+    Vec<InvocationStatsReport>,
+>;
+
 #[derive(Debug, Clone)]
-pub struct ChannelStatusReader(
-    pub(super)  mpsc::UnboundedSender<
-        restate_futures_util::command::Command<
-            RangeInclusive<PartitionKey>,
-            Vec<InvocationStatusReport>,
-        >,
-    >,
-);
+pub struct ChannelStatusReader {
+    pub(super) status_tx: mpsc::UnboundedSender<StatusCommand>,
+    pub(super) stats_tx: mpsc::UnboundedSender<StatsCommand>,
+}
 
 impl StatusHandle for ChannelStatusReader {
     type Iterator = itertools::Either<
         std::iter::Empty<InvocationStatusReport>,
         std::vec::IntoIter<InvocationStatusReport>,
     >;
+    type StatsIterator = std::vec::IntoIter<InvocationStatsReport>;
 
     async fn read_status(&self, keys: RangeInclusive<PartitionKey>) -> Self::Iterator {
         let (cmd, rx) = restate_futures_util::command::Command::prepare(keys);
-        if self.0.send(cmd).is_err() {
+        if self.status_tx.send(cmd).is_err() {
             return itertools::Either::Left(std::iter::empty::<InvocationStatusReport>());
         }
 
@@ -264,4 +272,13 @@ impl StatusHandle for ChannelStatusReader {
             itertools::Either::Left(std::iter::empty::<InvocationStatusReport>())
         }
     }
+
+    async fn read_stats(&self, keys: RangeInclusive<PartitionKey>) -> Self::StatsIterator {
+        let (cmd, rx) = restate_futures_util::command::Command::prepare(keys);
+        if self.stats_tx.send(cmd).is_err() {
+            return Vec::new().into_iter();
+        }
+
+        rx.await.unwrap_or_default().into_iter()
+    }
 }
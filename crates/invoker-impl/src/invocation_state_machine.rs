@@ -10,12 +10,13 @@
 
 use super::*;
 
+use restate_types::errors::ErrorClass;
 use restate_types::journal::Completion;
 use restate_types::journal_v2::raw::RawEntry;
 use restate_types::retries;
 use restate_types::schema::invocation_target::OnMaxAttempts;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::task::AbortHandle;
 
@@ -122,6 +123,8 @@ enum AttemptState {
         using_deployment: Option<PinnedDeployment>,
         // If true, we need to notify the deployment id to the partition processor
         should_notify_pinned_deployment: bool,
+
+        started_at: Instant,
     },
 
     WaitingRetry {
@@ -209,6 +212,7 @@ impl InvocationStateMachine {
             command_acks_to_propagate: Default::default(),
             using_deployment: None,
             should_notify_pinned_deployment: false,
+            started_at: Instant::now(),
         };
     }
 
@@ -413,7 +417,7 @@ impl InvocationStateMachine {
 
     pub(super) fn handle_task_error(
         &mut self,
-        error_is_transient: bool,
+        error_class: ErrorClass,
         next_retry_interval_override: Option<Duration>,
         should_bump_start_message_retry_count_since_last_stored_command: bool,
     ) -> OnTaskError {
@@ -442,7 +446,7 @@ impl InvocationStateMachine {
             return OnTaskError::Pause;
         }
 
-        if error_is_transient
+        if error_class.is_retryable()
             && let Some(next_timer) =
                 next_retry_interval_override.or_else(|| self.retry_policy_state.retry_iter.next())
         {
@@ -472,6 +476,13 @@ impl InvocationStateMachine {
         }
     }
 
+    pub(super) fn attempt_duration(&self) -> Option<Duration> {
+        match &self.invocation_state {
+            AttemptState::InFlight { started_at, .. } => Some(started_at.elapsed()),
+            _ => None,
+        }
+    }
+
     pub(super) fn attempt_deployment_id(&self) -> AttemptDeploymentId {
         AttemptDeploymentId(match &self.invocation_state {
             AttemptState::InFlight {
@@ -537,6 +548,12 @@ pub(super) enum OnTaskError {
 
 pub(super) struct AttemptDeploymentId(Option<DeploymentId>);
 
+impl AttemptDeploymentId {
+    pub(super) fn deployment_id(&self) -> Option<DeploymentId> {
+        self.0
+    }
+}
+
 impl fmt::Display for AttemptDeploymentId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.0 {
@@ -578,8 +595,11 @@ mod tests {
         );
 
         let_assert!(
-            OnTaskError::ScheduleRetry(_) =
-                invocation_state_machine.handle_task_error(true, None, true)
+            OnTaskError::ScheduleRetry(_) = invocation_state_machine.handle_task_error(
+                ErrorClass::RetryableWithBackoff,
+                None,
+                true
+            )
         );
         check!(let AttemptState::WaitingRetry { .. } = invocation_state_machine.invocation_state);
 
@@ -587,8 +607,11 @@ mod tests {
 
         // We stay in `WaitingForRetry`
         let_assert!(
-            OnTaskError::ScheduleRetry(_) =
-                invocation_state_machine.handle_task_error(true, None, true)
+            OnTaskError::ScheduleRetry(_) = invocation_state_machine.handle_task_error(
+                ErrorClass::RetryableWithBackoff,
+                None,
+                true
+            )
         );
         check!(let AttemptState::WaitingRetry { .. } = invocation_state_machine.invocation_state);
     }
@@ -610,8 +633,11 @@ mod tests {
 
         // Notify error
         let_assert!(
-            OnTaskError::ScheduleRetry(_) =
-                invocation_state_machine.handle_task_error(true, None, true)
+            OnTaskError::ScheduleRetry(_) = invocation_state_machine.handle_task_error(
+                ErrorClass::RetryableWithBackoff,
+                None,
+                true
+            )
         );
         assert_eq!(
             invocation_state_machine.start_message_retry_count_since_last_stored_command,
@@ -626,8 +652,11 @@ mod tests {
 
         // Get error again
         let_assert!(
-            OnTaskError::ScheduleRetry(_) =
-                invocation_state_machine.handle_task_error(true, None, true)
+            OnTaskError::ScheduleRetry(_) = invocation_state_machine.handle_task_error(
+                ErrorClass::RetryableWithBackoff,
+                None,
+                true
+            )
         );
         assert_eq!(
             invocation_state_machine.start_message_retry_count_since_last_stored_command,
@@ -701,8 +730,11 @@ mod tests {
         // Invoker generates entry 1
         invocation_state_machine.notify_new_command(1, false);
         let_assert!(
-            OnTaskError::ScheduleRetry(_) =
-                invocation_state_machine.handle_task_error(true, None, true)
+            OnTaskError::ScheduleRetry(_) = invocation_state_machine.handle_task_error(
+                ErrorClass::RetryableWithBackoff,
+                None,
+                true
+            )
         );
 
         // PP sends ack for command 1
@@ -733,8 +765,11 @@ mod tests {
         invocation_state_machine.notify_new_notification_proposal(NotificationId::SignalIndex(18));
         invocation_state_machine.notify_new_notification_proposal(NotificationId::CompletionId(1));
         let_assert!(
-            OnTaskError::ScheduleRetry(_) =
-                invocation_state_machine.handle_task_error(true, None, true)
+            OnTaskError::ScheduleRetry(_) = invocation_state_machine.handle_task_error(
+                ErrorClass::RetryableWithBackoff,
+                None,
+                true
+            )
         );
 
         // Waiting notifications acks and retry timer fired
@@ -18,41 +18,49 @@ use std::convert::Infallible;
 use std::iter::Empty;
 use std::pin::Pin;
 use std::task::{Context, Poll, ready};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 use bytes::Bytes;
-use futures::{FutureExt, Stream, future, stream};
+use futures::{FutureExt, Stream, StreamExt, future, stream};
 use http::response::Parts as ResponseParts;
-use http::{HeaderName, HeaderValue, Response};
+use http::{HeaderMap, HeaderName, HeaderValue, Response};
 use http_body::{Body, Frame};
 use metrics::histogram;
+use prost::Message;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::task::AbortOnDropHandle;
 use tracing::instrument;
 
 use restate_invoker_api::invocation_reader::{
-    EagerState, InvocationReader, InvocationReaderTransaction,
+    EagerState, InvocationReader, InvocationReaderTransaction, JournalEntry,
 };
 use restate_invoker_api::{EntryEnricher, InvokeInputJournal};
 use restate_service_client::{Request, ResponseBody, ServiceClient, ServiceClientError};
+use restate_service_protocol::codec::ProtobufRawEntryCodec;
+#[cfg(feature = "chaos")]
+use restate_types::chaos;
+use restate_types::config::ServiceStubOptions;
 use restate_types::deployment::PinnedDeployment;
-use restate_types::identifiers::{InvocationId, PartitionLeaderEpoch};
+use restate_types::identifiers::{DeploymentId, InvocationId, PartitionLeaderEpoch};
 use restate_types::invocation::{InvocationEpoch, InvocationTarget};
-use restate_types::journal::EntryIndex;
-use restate_types::journal::enriched::EnrichedRawEntry;
+use restate_types::journal::enriched::{EnrichedEntryHeader, EnrichedRawEntry};
+use restate_types::journal::{Entry, EntryIndex};
 use restate_types::journal_v2;
 use restate_types::journal_v2::raw::RawNotification;
 use restate_types::journal_v2::{CommandIndex, NotificationId};
 use restate_types::live::Live;
 use restate_types::schema::deployment::DeploymentResolver;
 use restate_types::schema::invocation_target::InvocationTargetResolver;
-use restate_types::service_protocol::ServiceProtocolVersion;
+use restate_types::service_protocol::{
+    OutputEntryMessage, ServiceProtocolVersion, output_entry_message,
+};
 
-use crate::TokenBucket;
 use crate::error::InvokerError;
 use crate::invocation_task::service_protocol_runner::ServiceProtocolRunner;
 use crate::metric_definitions::{ID_LOOKUP, INVOKER_TASK_DURATION};
+use crate::service_stub::{ServiceStubs, render_stub_response};
+use crate::{ReplayLimiter, TokenBucket};
 
 // Clippy false positive, might be caused by Bytes contained within HeaderValue.
 // https://github.com/rust-lang/rust/issues/40543#issuecomment-1212981256
@@ -83,6 +91,14 @@ const SERVICE_PROTOCOL_VERSION_V6: HeaderValue =
 #[allow(clippy::declare_interior_mutable_const)]
 const X_RESTATE_SERVER: HeaderName = HeaderName::from_static("x-restate-server");
 
+#[allow(clippy::declare_interior_mutable_const)]
+const X_RESTATE_MAX_MESSAGE_SIZE: HeaderName =
+    HeaderName::from_static("x-restate-max-message-size");
+
+/// Flags the requests of mirrored invocations, see [`InvocationTask::with_shadow_deployment`].
+#[allow(clippy::declare_interior_mutable_const)]
+const X_RESTATE_SHADOW: HeaderName = HeaderName::from_static("x-restate-shadow");
+
 pub(super) struct InvocationTaskOutput {
     pub(super) partition: PartitionLeaderEpoch,
     pub(super) invocation_id: InvocationId,
@@ -94,6 +110,8 @@ pub(super) enum InvocationTaskOutputInner {
     // `has_changed` indicates if we believe this is a freshly selected endpoint or not.
     PinnedDeployment(PinnedDeployment, /* has_changed: */ bool),
     ServerHeaderReceived(String),
+    /// Values of the diagnostic headers of the deployment response, see [`InvocationTask::with_diagnostic_headers`].
+    DiagnosticHeadersReceived(Vec<(String, String)>),
     NewEntry {
         entry_index: EntryIndex,
         entry: Box<EnrichedRawEntry>,
@@ -160,6 +178,15 @@ pub(super) struct InvocationTask<IR, EE, DMR> {
 
     // throttling
     action_token_bucket: Option<TokenBucket>,
+    replay_limiter: ReplayLimiter,
+
+    service_stubs: ServiceStubs,
+
+    // Set when this task mirrors the invocation to a shadow deployment
+    shadow_deployment: Option<DeploymentId>,
+
+    // Response headers to report to the invoker status
+    diagnostic_headers: Vec<String>,
 }
 
 /// This is needed to split the run_internal in multiple loop functions and have shortcircuiting.
@@ -219,6 +246,8 @@ where
         invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
         invoker_rx: mpsc::UnboundedReceiver<Notification>,
         action_token_bucket: Option<TokenBucket>,
+        replay_limiter: ReplayLimiter,
+        service_stubs: ServiceStubs,
     ) -> Self {
         Self {
             client,
@@ -238,9 +267,31 @@ where
             message_size_warning,
             retry_count_since_last_stored_entry,
             action_token_bucket,
+            replay_limiter,
+            service_stubs,
+            shadow_deployment: None,
+            diagnostic_headers: Vec::new(),
         }
     }
 
+    /// Reports the values of the given headers of the deployment response to the invoker status,
+    /// in order to show which infrastructure handled the last attempt.
+    /// Names are matched case-insensitively, invalid header names never match.
+    pub fn with_diagnostic_headers(mut self, diagnostic_headers: Vec<String>) -> Self {
+        self.diagnostic_headers = diagnostic_headers;
+        self
+    }
+
+    /// Mirrors the invocation to the given deployment, instead of executing it.
+    ///
+    /// The deployment is used regardless of the pinned deployment, and its requests are flagged
+    /// with the `x-restate-shadow` header. The owner of the task is expected to discard its
+    /// output, and to never send notifications to it.
+    pub fn with_shadow_deployment(mut self, deployment_id: DeploymentId) -> Self {
+        self.shadow_deployment = Some(deployment_id);
+        self
+    }
+
     /// Loop opening the request to deployment and consuming the stream
     #[instrument(
         level = "debug",
@@ -279,6 +330,23 @@ where
         &mut self,
         input_journal: InvokeInputJournal,
     ) -> TerminalLoopState<()> {
+        #[cfg(feature = "chaos")]
+        if chaos::should_fail_invocation_attempt(self.invocation_target.service_name()) {
+            shortcircuit!(Err(InvokerError::InjectedFault));
+        }
+
+        // Journals which are not cached need to be replayed from storage, hence they're subject
+        // to the replay limits. Wait for a slot before starting to read.
+        let replay_permit = if matches!(input_journal, InvokeInputJournal::NoCachedJournal) {
+            Some(
+                self.replay_limiter
+                    .acquire(self.invocation_target.service_name())
+                    .await,
+            )
+        } else {
+            None
+        };
+
         let mut txn = self.invocation_reader.transaction();
         // Resolve journal and its metadata
         let (journal_metadata, journal_stream) = match input_journal {
@@ -289,7 +357,13 @@ where
                         .map_err(|e| InvokerError::JournalReader(e.into()))
                         .and_then(|opt| opt.ok_or_else(|| InvokerError::NotInvoked))
                 );
-                (journal_meta, future::Either::Left(journal_stream))
+                (
+                    journal_meta,
+                    future::Either::Left(self.replay_limiter.throttle(
+                        replay_permit.expect("replay permit must be acquired"),
+                        journal_stream,
+                    )),
+                )
             }
             InvokeInputJournal::CachedJournal(journal_meta, journal_items) => (
                 journal_meta,
@@ -307,7 +381,31 @@ where
         // Resolve the deployment metadata
         let schemas = self.schemas.live_load();
         let (deployment, chosen_service_protocol_version, deployment_changed) =
-            if let Some(pinned_deployment) = &journal_metadata.pinned_deployment {
+            if let Some(shadow_deployment_id) = self.shadow_deployment {
+                let mut deployment = shortcircuit!(
+                    schemas
+                        .get_deployment(&shadow_deployment_id)
+                        .ok_or(InvokerError::UnknownDeployment(shadow_deployment_id))
+                );
+                let chosen_service_protocol_version = shortcircuit!(
+                    ServiceProtocolVersion::pick(&deployment.supported_protocol_versions)
+                        .ok_or_else(|| {
+                            InvokerError::IncompatibleServiceEndpoint(
+                                deployment.id,
+                                deployment.supported_protocol_versions.clone(),
+                            )
+                        })
+                );
+                deployment
+                    .additional_headers
+                    .insert(X_RESTATE_SHADOW, HeaderValue::from_static("true"));
+
+                (
+                    deployment,
+                    chosen_service_protocol_version,
+                    /* has_changed= */ false,
+                )
+            } else if let Some(pinned_deployment) = &journal_metadata.pinned_deployment {
                 // We have a pinned deployment that we can't change even if newer
                 // deployments have been registered for the same service.
                 let deployment_metadata = shortcircuit!(
@@ -337,13 +435,24 @@ where
             } else {
                 // We can choose the freshest deployment for the latest revision
                 // of the registered service.
-                let deployment = shortcircuit!(
-                    schemas
-                        .resolve_latest_deployment_for_service(
-                            self.invocation_target.service_name()
+                let Some(deployment) = schemas
+                    .resolve_latest_deployment_for_service(self.invocation_target.service_name())
+                else {
+                    // Handlers of services that are not registered yet can be stubbed in dev mode
+                    if let Some(stub) = self
+                        .service_stubs
+                        .get(
+                            self.invocation_target.service_name(),
+                            self.invocation_target.handler_name(),
                         )
-                        .ok_or(InvokerError::NoDeploymentForService)
-                );
+                        .cloned()
+                    {
+                        return self
+                            .complete_with_stub(&stub, journal_metadata.length, journal_stream)
+                            .await;
+                    }
+                    return TerminalLoopState::Failed(InvokerError::NoDeploymentForService);
+                };
 
                 let chosen_service_protocol_version = shortcircuit!(
                     ServiceProtocolVersion::pick(&deployment.supported_protocol_versions,)
@@ -398,10 +507,12 @@ where
         // No need to read from Rocksdb anymore
         drop(txn);
 
-        self.send_invoker_tx(InvocationTaskOutputInner::PinnedDeployment(
-            PinnedDeployment::new(deployment.id, chosen_service_protocol_version),
-            deployment_changed,
-        ));
+        if self.shadow_deployment.is_none() {
+            self.send_invoker_tx(InvocationTaskOutputInner::PinnedDeployment(
+                PinnedDeployment::new(deployment.id, chosen_service_protocol_version),
+                deployment_changed,
+            ));
+        }
 
         if chosen_service_protocol_version <= ServiceProtocolVersion::V3 {
             // Protocol runner for service protocol <= v3
@@ -424,6 +535,59 @@ where
 }
 
 impl<IR, EE, DMR> InvocationTask<IR, EE, DMR> {
+    /// Completes the invocation with the response of the given stub, without invoking any deployment.
+    async fn complete_with_stub(
+        &self,
+        stub: &ServiceStubOptions,
+        journal_length: EntryIndex,
+        journal_stream: impl Stream<Item = JournalEntry>,
+    ) -> TerminalLoopState<()> {
+        let mut journal_stream = std::pin::pin!(journal_stream);
+        // Invocations without a pinned deployment always use the journal v1
+        let Some(JournalEntry::JournalV1(first_entry)) = journal_stream.next().await else {
+            return TerminalLoopState::Failed(InvokerError::NoDeploymentForService);
+        };
+        let Entry::Input(input) =
+            shortcircuit!(first_entry.deserialize_entry::<ProtobufRawEntryCodec>())
+        else {
+            return TerminalLoopState::Failed(InvokerError::NoDeploymentForService);
+        };
+
+        let output = OutputEntryMessage {
+            result: Some(output_entry_message::Result::Value(render_stub_response(
+                stub,
+                &input.value,
+            ))),
+            ..Default::default()
+        };
+        self.send_invoker_tx(InvocationTaskOutputInner::NewEntry {
+            entry_index: journal_length,
+            entry: Box::new(EnrichedRawEntry::new(
+                EnrichedEntryHeader::Output {},
+                output.encode_to_vec().into(),
+            )),
+            requires_ack: false,
+        });
+
+        TerminalLoopState::Closed
+    }
+
+    fn report_diagnostic_headers(&self, headers: &HeaderMap) {
+        let diagnostic_headers: Vec<_> = self
+            .diagnostic_headers
+            .iter()
+            .filter_map(|name| {
+                let value = headers.get(name.as_str())?.to_str().ok()?;
+                Some((name.to_ascii_lowercase(), value.to_owned()))
+            })
+            .collect();
+        if !diagnostic_headers.is_empty() {
+            self.send_invoker_tx(InvocationTaskOutputInner::DiagnosticHeadersReceived(
+                diagnostic_headers,
+            ));
+        }
+    }
+
     fn send_invoker_tx(&self, invocation_task_output_inner: InvocationTaskOutputInner) {
         let _ = self.invoker_tx.send(InvocationTaskOutput {
             partition: self.partition,
@@ -457,6 +621,30 @@ fn invocation_id_to_header_value(invocation_id: &InvocationId) -> HeaderValue {
         .unwrap_or_else(|_| unreachable!("invocation id should be always valid"))
 }
 
+/// Upper bound for the delay requested by an endpoint through the `Retry-After` header.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Parses the `Retry-After` header, either in the delay-seconds or in the HTTP-date form.
+/// Unparseable values are ignored, so the retry policy applies.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    let retry_after = if let Ok(seconds) = value.parse::<u64>() {
+        Duration::from_secs(seconds)
+    } else {
+        httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    };
+
+    Some(retry_after.min(MAX_RETRY_AFTER))
+}
+
 enum ResponseChunk {
     Parts(ResponseParts),
     Data(Bytes),
@@ -536,3 +724,50 @@ impl Stream for ResponseStream {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with_retry_after(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::RETRY_AFTER,
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn parse_retry_after_seconds() {
+        assert_eq!(
+            parse_retry_after(&headers_with_retry_after("120")),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after(&headers_with_retry_after("86400")),
+            Some(MAX_RETRY_AFTER)
+        );
+    }
+
+    #[test]
+    fn parse_retry_after_http_date() {
+        let in_the_future = SystemTime::now() + Duration::from_secs(300);
+        let retry_after = parse_retry_after(&headers_with_retry_after(&httpdate::fmt_http_date(
+            in_the_future,
+        )))
+        .unwrap();
+        assert!(retry_after > Duration::from_secs(290) && retry_after <= Duration::from_secs(300));
+
+        assert_eq!(
+            parse_retry_after(&headers_with_retry_after("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn parse_retry_after_invalid() {
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+        assert_eq!(parse_retry_after(&headers_with_retry_after("soon")), None);
+    }
+}
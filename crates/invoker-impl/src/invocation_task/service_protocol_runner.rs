@@ -39,17 +39,20 @@ use restate_types::journal_v2;
 use restate_types::journal_v2::EntryMetadata;
 use restate_types::schema::deployment::{Deployment, DeploymentType, ProtocolType};
 use restate_types::service_protocol::ServiceProtocolVersion;
+use restate_types::time::MillisSinceEpoch;
 
 use crate::Notification;
 use crate::error::{InvocationErrorRelatedEntry, InvokerError, SdkInvocationError};
 use crate::invocation_task::{
     InvocationTask, InvocationTaskOutputInner, InvokerBodyStream, InvokerRequestStreamSender,
     ResponseChunk, ResponseStream, TerminalLoopState, X_RESTATE_SERVER,
-    invocation_id_to_header_value, service_protocol_version_to_header_value,
+    invocation_id_to_header_value, parse_retry_after, service_protocol_version_to_header_value,
 };
 
 ///  Provides the value of the invocation id
 const INVOCATION_ID_HEADER_NAME: HeaderName = HeaderName::from_static("x-restate-invocation-id");
+const DEADLINE_HEADER_NAME: HeaderName =
+    HeaderName::from_static(restate_types::invocation::DEADLINE_HEADER_NAME);
 
 const GATEWAY_ERRORS_CODES: [http::StatusCode; 3] = [
     http::StatusCode::BAD_GATEWAY,
@@ -147,6 +150,7 @@ where
             self.service_protocol_version,
             &self.invocation_task.invocation_id,
             &service_invocation_span_context,
+            journal_metadata.deadline,
         );
 
         crate::shortcircuit!(
@@ -214,6 +218,7 @@ where
         service_protocol_version: ServiceProtocolVersion,
         invocation_id: &InvocationId,
         parent_span_context: &ServiceInvocationSpanContext,
+        deadline: Option<MillisSinceEpoch>,
     ) -> (InvokerRequestStreamSender, Request<InvokerBodyStream>) {
         // Just an arbitrary buffering size
         let (http_stream_tx, http_stream_rx) = mpsc::channel(10);
@@ -232,6 +237,10 @@ where
             (http::header::ACCEPT, service_protocol_header_value),
             (INVOCATION_ID_HEADER_NAME, invocation_id_header_value),
         ]);
+        // Let the service know how long it can take, as the invocation is failed afterwards
+        if let Some(deadline) = deadline {
+            headers.insert(DEADLINE_HEADER_NAME, HeaderValue::from(deadline.as_u64()));
+        }
 
         // Inject OpenTelemetry context into the headers
         // The parent span as seen by the SDK will be the service invocation span context
@@ -468,11 +477,18 @@ where
         &mut self,
         mut parts: http::response::Parts,
     ) -> Result<(), InvokerError> {
+        // report the diagnostic headers first, as they're useful especially when the attempt fails
+        self.invocation_task
+            .report_diagnostic_headers(&parts.headers);
+
         // if service is running behind a gateway, the service can be down
         // but we still get a response code from the gateway itself. In that
         // case we still need to return the proper error
         if GATEWAY_ERRORS_CODES.contains(&parts.status) {
-            return Err(InvokerError::ServiceUnavailable(parts.status));
+            return Err(InvokerError::ServiceUnavailable(
+                parts.status,
+                parse_retry_after(&parts.headers),
+            ));
         }
 
         // otherwise we return generic UnexpectedResponse
@@ -484,7 +500,10 @@ where
                 ));
             }
 
-            return Err(InvokerError::UnexpectedResponse(parts.status));
+            return Err(InvokerError::UnexpectedResponse(
+                parts.status,
+                parse_retry_after(&parts.headers),
+            ));
         }
 
         let content_type = parts.headers.remove(http::header::CONTENT_TYPE);
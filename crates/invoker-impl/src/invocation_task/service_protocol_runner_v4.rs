@@ -52,6 +52,7 @@ use restate_types::journal_v2::{
 use restate_types::schema::deployment::{Deployment, DeploymentType, ProtocolType};
 use restate_types::schema::invocation_target::{DeploymentStatus, InvocationTargetResolver};
 use restate_types::service_protocol::ServiceProtocolVersion;
+use restate_types::time::MillisSinceEpoch;
 
 use crate::Notification;
 use crate::error::{
@@ -59,12 +60,15 @@ use crate::error::{
 };
 use crate::invocation_task::{
     InvocationTask, InvocationTaskOutputInner, InvokerBodyStream, InvokerRequestStreamSender,
-    ResponseChunk, ResponseStream, TerminalLoopState, X_RESTATE_SERVER,
-    invocation_id_to_header_value, service_protocol_version_to_header_value,
+    ResponseChunk, ResponseStream, TerminalLoopState, X_RESTATE_MAX_MESSAGE_SIZE, X_RESTATE_SERVER,
+    invocation_id_to_header_value, parse_retry_after, service_protocol_version_to_header_value,
 };
+use crate::service_stub::ServiceStubs;
 
 ///  Provides the value of the invocation id
 const INVOCATION_ID_HEADER_NAME: HeaderName = HeaderName::from_static("x-restate-invocation-id");
+const DEADLINE_HEADER_NAME: HeaderName =
+    HeaderName::from_static(restate_types::invocation::DEADLINE_HEADER_NAME);
 
 const GATEWAY_ERRORS_CODES: [StatusCode; 3] = [
     StatusCode::BAD_GATEWAY,
@@ -93,7 +97,7 @@ where
         invocation_task: &'a mut InvocationTask<IR, EE, Schemas>,
         service_protocol_version: ServiceProtocolVersion,
     ) -> Self {
-        let encoder = Encoder::new(service_protocol_version);
+        let encoder = Encoder::new(service_protocol_version, invocation_task.message_size_limit);
 
         Self {
             invocation_task,
@@ -155,6 +159,7 @@ where
             self.service_protocol_version,
             &self.invocation_task.invocation_id,
             &service_invocation_span_context,
+            journal_metadata.deadline,
         );
 
         crate::shortcircuit!(
@@ -230,6 +235,7 @@ where
         service_protocol_version: ServiceProtocolVersion,
         invocation_id: &InvocationId,
         parent_span_context: &ServiceInvocationSpanContext,
+        deadline: Option<MillisSinceEpoch>,
     ) -> (InvokerRequestStreamSender, Request<InvokerBodyStream>) {
         // Just an arbitrary buffering size
         let (http_stream_tx, http_stream_rx) = mpsc::channel(10);
@@ -248,6 +254,10 @@ where
             (http::header::ACCEPT, service_protocol_header_value),
             (INVOCATION_ID_HEADER_NAME, invocation_id_header_value),
         ]);
+        // Let the service know how long it can take, as the invocation is failed afterwards
+        if let Some(deadline) = deadline {
+            headers.insert(DEADLINE_HEADER_NAME, HeaderValue::from(deadline.as_u64()));
+        }
 
         // Inject OpenTelemetry context into the headers
         // The parent span as seen by the SDK will be the service invocation span context
@@ -459,25 +469,35 @@ where
     ) -> Result<(), InvokerError> {
         let is_partial = state_entries.is_partial();
 
+        let mut msg = Message::new_start_message(
+            Bytes::copy_from_slice(&self.invocation_task.invocation_id.to_bytes()),
+            self.invocation_task.invocation_id.to_string(),
+            self.invocation_task
+                .invocation_target
+                .key()
+                .map(|bs| bs.as_bytes().clone()),
+            journal_size,
+            is_partial,
+            state_entries,
+            retry_count_since_last_stored_entry,
+            duration_since_last_stored_entry,
+            random_seed,
+        );
+
+        // If the eager state doesn't fit in the message size limit, send it lazily instead
+        if let Message::Start(start) = &mut msg
+            && !self.encoder.fits(&msg)
+        {
+            debug!(
+                "Eager state of {} entries exceeds the message size limit, sending the start message with partial state",
+                start.state_map.len()
+            );
+            start.state_map.clear();
+            start.partial_state = true;
+        }
+
         // Send the invoke frame
-        self.write(
-            http_stream_tx,
-            Message::new_start_message(
-                Bytes::copy_from_slice(&self.invocation_task.invocation_id.to_bytes()),
-                self.invocation_task.invocation_id.to_string(),
-                self.invocation_task
-                    .invocation_target
-                    .key()
-                    .map(|bs| bs.as_bytes().clone()),
-                journal_size,
-                is_partial,
-                state_entries,
-                retry_count_since_last_stored_entry,
-                duration_since_last_stored_entry,
-                random_seed,
-            ),
-        )
-        .await
+        self.write(http_stream_tx, msg).await
     }
 
     async fn write_entry(
@@ -514,7 +534,7 @@ where
         msg: Message,
     ) -> Result<(), InvokerError> {
         trace!(restate.protocol.message = ?msg, "Sending message");
-        let buf = self.encoder.encode(msg);
+        let buf = self.encoder.encode(msg)?;
 
         if http_stream_tx.send(Ok(Frame::data(buf))).await.is_err() {
             return Err(InvokerError::UnexpectedClosedRequestStream);
@@ -529,7 +549,7 @@ where
         buf: Bytes,
     ) -> Result<(), InvokerError> {
         trace!(restate.protocol.message = ?ty, "Sending message");
-        let buf = self.encoder.encode_raw(ty, buf);
+        let buf = self.encoder.encode_raw(ty, buf)?;
 
         if http_stream_tx.send(Ok(Frame::data(buf))).await.is_err() {
             return Err(InvokerError::UnexpectedClosedRequestStream);
@@ -541,11 +561,18 @@ where
         &mut self,
         mut parts: http::response::Parts,
     ) -> Result<(), InvokerError> {
+        // report the diagnostic headers first, as they're useful especially when the attempt fails
+        self.invocation_task
+            .report_diagnostic_headers(&parts.headers);
+
         // if service is running behind a gateway, the service can be down
         // but we still get a response code from the gateway itself. In that
         // case we still need to return the proper error
         if GATEWAY_ERRORS_CODES.contains(&parts.status) {
-            return Err(InvokerError::ServiceUnavailable(parts.status));
+            return Err(InvokerError::ServiceUnavailable(
+                parts.status,
+                parse_retry_after(&parts.headers),
+            ));
         }
 
         // otherwise we return generic UnexpectedResponse
@@ -560,7 +587,10 @@ where
                 return Err(InvokerError::ContentTooLarge);
             }
 
-            return Err(InvokerError::UnexpectedResponse(parts.status));
+            return Err(InvokerError::UnexpectedResponse(
+                parts.status,
+                parse_retry_after(&parts.headers),
+            ));
         }
 
         let content_type = parts.headers.remove(http::header::CONTENT_TYPE);
@@ -594,6 +624,21 @@ where
                 ))
         }
 
+        // The deployment can advertise a lower message size limit than our own, see
+        // service-protocol/service-invocation-protocol.md#maximum-message-size
+        if let Some(hv) = parts.headers.remove(X_RESTATE_MAX_MESSAGE_SIZE) {
+            let hv = hv
+                .to_str()
+                .map_err(|e| InvokerError::BadHeader(X_RESTATE_MAX_MESSAGE_SIZE, e))?;
+            match hv.parse::<usize>() {
+                Ok(limit) if limit > 0 => self.encoder.restrict_message_size_limit(limit),
+                _ => warn!(
+                    "Ignoring invalid {} header value '{}'",
+                    X_RESTATE_MAX_MESSAGE_SIZE, hv
+                ),
+            }
+        }
+
         Ok(())
     }
 
@@ -710,6 +755,7 @@ where
                     request: crate::shortcircuit!(
                         resolve_call_request(
                             self.invocation_task.schemas.live_load(),
+                            &self.invocation_task.service_stubs,
                             InvokeRequest {
                                 service_name: cmd.service_name.into(),
                                 handler_name: cmd.handler_name.into(),
@@ -745,6 +791,7 @@ where
                     request: crate::shortcircuit!(
                         resolve_call_request(
                             self.invocation_task.schemas.live_load(),
+                            &self.invocation_task.service_stubs,
                             InvokeRequest {
                                 service_name: cmd.service_name.into(),
                                 handler_name: cmd.handler_name.into(),
@@ -1016,16 +1063,26 @@ pub struct InvokeRequest {
 
 fn resolve_call_request(
     invocation_target_resolver: &impl InvocationTargetResolver,
+    service_stubs: &ServiceStubs,
     request: InvokeRequest,
 ) -> Result<CallRequest, CommandPreconditionError> {
-    let meta = invocation_target_resolver
+    let meta = match invocation_target_resolver
         .resolve_latest_invocation_target(&request.service_name, &request.handler_name)
-        .ok_or_else(|| {
-            CommandPreconditionError::ServiceHandlerNotFound(
+    {
+        Some(meta) => meta,
+        None if service_stubs
+            .get(&request.service_name, &request.handler_name)
+            .is_some() =>
+        {
+            ServiceStubs::invocation_target_metadata(!request.key.is_empty())
+        }
+        None => {
+            return Err(CommandPreconditionError::ServiceHandlerNotFound(
                 request.service_name.to_string(),
                 request.handler_name.to_string(),
-            )
-        })?;
+            ));
+        }
+    };
 
     if let DeploymentStatus::Deprecated(dp_id) = meta.deployment_status {
         return Err(CommandPreconditionError::DeploymentDeprecated(
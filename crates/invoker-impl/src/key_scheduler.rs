@@ -0,0 +1,373 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{HashMap, VecDeque};
+
+use metrics::gauge;
+
+use restate_types::identifiers::{InvocationId, PartitionLeaderEpoch, ServiceId};
+use restate_types::invocation::{InvocationEpoch, InvocationTarget};
+
+use crate::input_command::InvokeCommand;
+use crate::metric_definitions::INVOKER_INVOCATIONS_WAITING_FOR_KEY;
+
+type Key = (PartitionLeaderEpoch, ServiceId);
+
+/// Schedules the invocations of keyed services fairly across keys.
+///
+/// Each key can run up to `limit` invocations concurrently. Invocations exceeding this limit
+/// are parked, and once their key has a free slot again they are started in round-robin order
+/// across keys, before any invocation still waiting in the input queue. This way a key with a
+/// large backlog (e.g. of shared handler invocations) cannot hold all the invoker slots, and an
+/// invocation of another key waits at most for one invocation of every other parked key.
+///
+/// Invocations of exclusive handlers are already serialized per key by the partition processor's
+/// inbox, so in practice only shared handlers are parked.
+///
+/// At most `max_parked` invocations are parked. Once full, the invoker stops taking invocations
+/// from its input queue, which spills them to disk.
+#[derive(Debug)]
+pub(super) struct KeyScheduler {
+    limit: Option<usize>,
+    max_parked: usize,
+    in_flight: HashMap<Key, usize>,
+    // Keys with at least one parked invocation and a free slot, in the order they will be served
+    ready_keys: VecDeque<Key>,
+    parked: HashMap<Key, VecDeque<Box<InvokeCommand>>>,
+    parked_len: usize,
+}
+
+impl KeyScheduler {
+    pub(super) fn new(limit: Option<usize>, max_parked: usize) -> Self {
+        gauge!(INVOKER_INVOCATIONS_WAITING_FOR_KEY).set(0.0);
+        Self {
+            limit,
+            max_parked,
+            in_flight: HashMap::new(),
+            ready_keys: VecDeque::new(),
+            parked: HashMap::new(),
+            parked_len: 0,
+        }
+    }
+
+    /// Returns true if no more invocations can be parked.
+    pub(super) fn is_full(&self) -> bool {
+        self.parked_len >= self.max_parked
+    }
+
+    /// Returns the command if it can be started right away, otherwise parks it until its key
+    /// has a free slot.
+    pub(super) fn admit(&mut self, command: Box<InvokeCommand>) -> Option<Box<InvokeCommand>> {
+        let Some(limit) = self.limit else {
+            return Some(command);
+        };
+        let Some(service_id) = command.invocation_target.as_keyed_service_id() else {
+            return Some(command);
+        };
+
+        let key = (command.partition, service_id);
+        // Don't overtake invocations of the same key that are already parked
+        if !self.parked.contains_key(&key) && self.in_flight.get(&key).copied().unwrap_or(0) < limit
+        {
+            return Some(command);
+        }
+
+        let queue = self.parked.entry(key.clone()).or_default();
+        queue.push_back(command);
+        if queue.len() == 1 && self.in_flight.get(&key).copied().unwrap_or(0) < limit {
+            self.ready_keys.push_back(key);
+        }
+        self.parked_len += 1;
+        gauge!(INVOKER_INVOCATIONS_WAITING_FOR_KEY).increment(1.0);
+
+        None
+    }
+
+    /// Pops the next parked invocation following the round-robin order among keys.
+    pub(super) fn pop_ready(&mut self) -> Option<Box<InvokeCommand>> {
+        let limit = self.limit?;
+        while let Some(key) = self.ready_keys.pop_front() {
+            // A key might be queued more than once, if an invocation was restarted in between
+            let Some(queue) = self.parked.get_mut(&key) else {
+                continue;
+            };
+            let next = queue.pop_front().expect("parked queues must be non empty");
+            if queue.is_empty() {
+                self.parked.remove(&key);
+            } else if self.in_flight.get(&key).copied().unwrap_or(0) + 1 < limit {
+                // The popped invocation is about to take one of the free slots
+                self.ready_keys.push_back(key);
+            }
+            self.parked_len -= 1;
+            gauge!(INVOKER_INVOCATIONS_WAITING_FOR_KEY).decrement(1.0);
+
+            return Some(next);
+        }
+
+        None
+    }
+
+    pub(super) fn on_start(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_target: &InvocationTarget,
+    ) {
+        if self.limit.is_none() {
+            return;
+        }
+        if let Some(service_id) = invocation_target.as_keyed_service_id() {
+            *self.in_flight.entry((partition, service_id)).or_default() += 1;
+        }
+    }
+
+    pub(super) fn on_end(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_target: &InvocationTarget,
+    ) {
+        let Some(limit) = self.limit else {
+            return;
+        };
+        let Some(service_id) = invocation_target.as_keyed_service_id() else {
+            return;
+        };
+
+        let key = (partition, service_id);
+        let Some(in_flight) = self.in_flight.get_mut(&key) else {
+            return;
+        };
+        *in_flight -= 1;
+        let in_flight = *in_flight;
+        if in_flight == 0 {
+            self.in_flight.remove(&key);
+        }
+        // The key just got a free slot back
+        if in_flight + 1 == limit && self.parked.contains_key(&key) {
+            self.ready_keys.push_back(key);
+        }
+    }
+
+    /// Drops the parked invocation with the given id and epoch, returning true if it was parked.
+    pub(super) fn remove_invocation(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: &InvocationId,
+        invocation_epoch: InvocationEpoch,
+    ) -> bool {
+        let Some((key, queue)) = self.parked.iter_mut().find(|((p, _), queue)| {
+            *p == partition
+                && queue.iter().any(|command| {
+                    command.invocation_id == *invocation_id
+                        && command.invocation_epoch == invocation_epoch
+                })
+        }) else {
+            return false;
+        };
+
+        let parked_len = queue.len();
+        queue.retain(|command| {
+            command.invocation_id != *invocation_id || command.invocation_epoch != invocation_epoch
+        });
+        let removed = parked_len - queue.len();
+        if queue.is_empty() {
+            let key = key.clone();
+            self.parked.remove(&key);
+            self.ready_keys.retain(|k| *k != key);
+        }
+        self.parked_len -= removed;
+        gauge!(INVOKER_INVOCATIONS_WAITING_FOR_KEY).decrement(removed as f64);
+
+        true
+    }
+
+    /// Drops the parked invocations of the given partition.
+    pub(super) fn remove_partition(&mut self, partition: PartitionLeaderEpoch) {
+        self.ready_keys.retain(|(p, _)| *p != partition);
+        self.parked.retain(|(p, _), queue| {
+            if *p == partition {
+                self.parked_len -= queue.len();
+                gauge!(INVOKER_INVOCATIONS_WAITING_FOR_KEY).decrement(queue.len() as f64);
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_invoker_api::InvokeInputJournal;
+    use restate_types::identifiers::{InvocationId, LeaderEpoch, PartitionId};
+    use restate_types::invocation::VirtualObjectHandlerType;
+
+    const PARTITION: PartitionLeaderEpoch = (PartitionId::MIN, LeaderEpoch::INITIAL);
+    const MAX_PARKED: usize = 1000;
+
+    fn invoke(key: &str) -> Box<InvokeCommand> {
+        Box::new(InvokeCommand {
+            partition: PARTITION,
+            invocation_id: InvocationId::mock_random(),
+            invocation_epoch: 0,
+            invocation_target: InvocationTarget::virtual_object(
+                "Counter",
+                key,
+                "get",
+                VirtualObjectHandlerType::Shared,
+            ),
+            journal: InvokeInputJournal::NoCachedJournal,
+        })
+    }
+
+    fn key_of(command: &InvokeCommand) -> String {
+        command
+            .invocation_target
+            .key()
+            .expect("keyed target")
+            .to_string()
+    }
+
+    #[test]
+    fn without_limit_everything_is_admitted() {
+        let mut scheduler = KeyScheduler::new(None, MAX_PARKED);
+        for _ in 0..10 {
+            let command = scheduler.admit(invoke("hot")).expect("admitted");
+            scheduler.on_start(PARTITION, &command.invocation_target);
+        }
+        assert!(scheduler.pop_ready().is_none());
+    }
+
+    #[test]
+    fn hot_key_cannot_starve_other_keys() {
+        let mut scheduler = KeyScheduler::new(Some(1), MAX_PARKED);
+
+        // A key with a large backlog takes its single slot, and parks the rest
+        let running = scheduler.admit(invoke("hot")).expect("admitted");
+        scheduler.on_start(PARTITION, &running.invocation_target);
+        for _ in 0..100 {
+            assert!(scheduler.admit(invoke("hot")).is_none());
+        }
+
+        // Another key enqueued after the backlog is started right away
+        let cold = scheduler.admit(invoke("cold")).expect("admitted");
+        scheduler.on_start(PARTITION, &cold.invocation_target);
+
+        // Once a key is parked too, it's served right after the next hot invocation
+        assert!(scheduler.admit(invoke("cold")).is_none());
+        assert!(scheduler.admit(invoke("warm")).is_some());
+        assert!(scheduler.pop_ready().is_none());
+
+        scheduler.on_end(PARTITION, &running.invocation_target);
+        scheduler.on_end(PARTITION, &cold.invocation_target);
+        let mut served = vec![];
+        while let Some(command) = scheduler.pop_ready() {
+            served.push(key_of(&command));
+            scheduler.on_start(PARTITION, &command.invocation_target);
+        }
+        assert_eq!(served, vec!["hot", "cold"]);
+    }
+
+    #[test]
+    fn parked_invocations_are_served_round_robin_across_keys() {
+        let mut scheduler = KeyScheduler::new(Some(2), MAX_PARKED);
+
+        let mut running = vec![];
+        for key in ["a", "a", "b", "b"] {
+            let command = scheduler.admit(invoke(key)).expect("admitted");
+            scheduler.on_start(PARTITION, &command.invocation_target);
+            running.push(command);
+        }
+        for key in ["a", "a", "a", "b"] {
+            assert!(scheduler.admit(invoke(key)).is_none());
+        }
+
+        // All the running invocations complete, which frees up two slots per key
+        for command in &running {
+            scheduler.on_end(PARTITION, &command.invocation_target);
+        }
+
+        let mut served = vec![];
+        while let Some(command) = scheduler.pop_ready() {
+            served.push(key_of(&command));
+            scheduler.on_start(PARTITION, &command.invocation_target);
+        }
+        assert_eq!(served, vec!["a", "b", "a"]);
+
+        // The last invocation of a needs one of the running ones to complete
+        scheduler.on_end(PARTITION, &invoke("a").invocation_target);
+        assert_eq!(key_of(&scheduler.pop_ready().expect("parked")), "a");
+        assert!(scheduler.pop_ready().is_none());
+    }
+
+    #[test]
+    fn remove_partition_drops_parked_invocations() {
+        let mut scheduler = KeyScheduler::new(Some(1), MAX_PARKED);
+
+        let running = scheduler.admit(invoke("hot")).expect("admitted");
+        scheduler.on_start(PARTITION, &running.invocation_target);
+        assert!(scheduler.admit(invoke("hot")).is_none());
+
+        scheduler.remove_partition(PARTITION);
+        scheduler.on_end(PARTITION, &running.invocation_target);
+
+        assert!(scheduler.pop_ready().is_none());
+        assert!(scheduler.admit(invoke("hot")).is_some());
+    }
+
+    #[test]
+    fn remove_invocation_drops_parked_invocation() {
+        let mut scheduler = KeyScheduler::new(Some(1), MAX_PARKED);
+
+        let running = scheduler.admit(invoke("hot")).expect("admitted");
+        scheduler.on_start(PARTITION, &running.invocation_target);
+        let aborted = invoke("hot");
+        let (aborted_id, aborted_epoch) = (aborted.invocation_id, aborted.invocation_epoch);
+        assert!(scheduler.admit(aborted).is_none());
+        let parked = invoke("hot");
+        let parked_id = parked.invocation_id;
+        assert!(scheduler.admit(parked).is_none());
+
+        // Epoch and partition must match
+        assert!(!scheduler.remove_invocation(PARTITION, &aborted_id, aborted_epoch + 1));
+        assert!(!scheduler.remove_invocation(
+            (PartitionId::from(1), LeaderEpoch::INITIAL),
+            &aborted_id,
+            aborted_epoch
+        ));
+        assert!(scheduler.remove_invocation(PARTITION, &aborted_id, aborted_epoch));
+        assert!(!scheduler.remove_invocation(PARTITION, &aborted_id, aborted_epoch));
+
+        scheduler.on_end(PARTITION, &running.invocation_target);
+        assert_eq!(
+            scheduler.pop_ready().expect("parked").invocation_id,
+            parked_id
+        );
+        assert!(scheduler.pop_ready().is_none());
+    }
+
+    #[test]
+    fn parked_invocations_are_bounded() {
+        let mut scheduler = KeyScheduler::new(Some(1), 2);
+
+        let running = scheduler.admit(invoke("hot")).expect("admitted");
+        scheduler.on_start(PARTITION, &running.invocation_target);
+        assert!(scheduler.admit(invoke("hot")).is_none());
+        assert!(!scheduler.is_full());
+        assert!(scheduler.admit(invoke("hot")).is_none());
+        assert!(scheduler.is_full());
+
+        scheduler.on_end(PARTITION, &running.invocation_target);
+        let next = scheduler.pop_ready().expect("parked");
+        scheduler.on_start(PARTITION, &next.invocation_target);
+        assert!(!scheduler.is_full());
+    }
+}
@@ -8,14 +8,20 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod endpoint_backoff;
 mod error;
 mod input_command;
 mod invocation_state_machine;
 mod invocation_task;
+mod key_scheduler;
 mod metric_definitions;
 mod quota;
+mod replay_limiter;
+mod service_stub;
 mod state_machine_manager;
+mod stats_store;
 mod status_store;
+mod traffic_mirror;
 
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
@@ -62,9 +68,10 @@ use restate_types::live::{Live, LiveLoad};
 use restate_types::schema::deployment::DeploymentResolver;
 use restate_types::schema::invocation_target::InvocationTargetResolver;
 
+use crate::endpoint_backoff::EndpointBackoff;
 use crate::error::InvokerError;
 use crate::error::SdkInvocationErrorV2;
-use crate::input_command::{InputCommand, InvokeCommand};
+use crate::input_command::{InputCommand, InvokeCommand, StatsCommand};
 use crate::invocation_state_machine::InvocationStateMachine;
 use crate::invocation_state_machine::OnTaskError;
 use crate::invocation_task::InvocationTask;
@@ -73,10 +80,14 @@ use crate::metric_definitions::{
     ID_LOOKUP, INVOKER_ENQUEUE, INVOKER_INVOCATION_TASKS, TASK_OP_COMPLETED, TASK_OP_FAILED,
     TASK_OP_STARTED, TASK_OP_SUSPENDED,
 };
+use crate::service_stub::ServiceStubs;
+use crate::stats_store::{AttemptOutcome, InvocationStatsStore};
 use crate::status_store::InvocationStatusStore;
+use crate::traffic_mirror::TrafficMirror;
 
 pub use input_command::ChannelStatusReader;
 pub use input_command::InvokerHandle;
+pub use replay_limiter::ReplayLimiter;
 
 pub type TokenBucket<C = gardal::TokioClock> =
     gardal::TokenBucket<gardal::PaddedAtomicSharedStorage, C>;
@@ -104,6 +115,7 @@ trait InvocationTaskRunner<SR> {
         invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
         invoker_rx: mpsc::UnboundedReceiver<Notification>,
         input_journal: InvokeInputJournal,
+        shadow_deployment: Option<DeploymentId>,
         task_pool: &mut JoinSet<()>,
     ) -> AbortHandle;
 }
@@ -113,6 +125,8 @@ struct DefaultInvocationTaskRunner<EE, Schemas> {
     entry_enricher: EE,
     schemas: Live<Schemas>,
     action_token_bucket: Option<TokenBucket>,
+    replay_limiter: ReplayLimiter,
+    service_stubs: ServiceStubs,
 }
 
 impl<IR, EE, Schemas> InvocationTaskRunner<IR> for DefaultInvocationTaskRunner<EE, Schemas>
@@ -133,33 +147,41 @@ where
         invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
         invoker_rx: mpsc::UnboundedReceiver<Notification>,
         input_journal: InvokeInputJournal,
+        shadow_deployment: Option<DeploymentId>,
         task_pool: &mut JoinSet<()>,
     ) -> AbortHandle {
+        let mut invocation_task = InvocationTask::new(
+            self.client.clone(),
+            partition,
+            invocation_id,
+            invocation_epoch,
+            invocation_target,
+            opts.inactivity_timeout.into(),
+            opts.abort_timeout.into(),
+            opts.disable_eager_state,
+            opts.message_size_warning.get(),
+            opts.message_size_limit(),
+            retry_count_since_last_stored_entry,
+            storage_reader,
+            self.entry_enricher.clone(),
+            self.schemas.clone(),
+            invoker_tx,
+            invoker_rx,
+            self.action_token_bucket.clone(),
+            self.replay_limiter.clone(),
+            self.service_stubs.clone(),
+        );
+        if let Some(shadow_deployment) = shadow_deployment {
+            invocation_task = invocation_task.with_shadow_deployment(shadow_deployment);
+        }
+        if !opts.attempt_diagnostic_headers.is_empty() {
+            invocation_task =
+                invocation_task.with_diagnostic_headers(opts.attempt_diagnostic_headers.clone());
+        }
         task_pool
             .build_task()
             .name("invocation-task")
-            .spawn(
-                InvocationTask::new(
-                    self.client.clone(),
-                    partition,
-                    invocation_id,
-                    invocation_epoch,
-                    invocation_target,
-                    opts.inactivity_timeout.into(),
-                    opts.abort_timeout.into(),
-                    opts.disable_eager_state,
-                    opts.message_size_warning.get(),
-                    opts.message_size_limit(),
-                    retry_count_since_last_stored_entry,
-                    storage_reader,
-                    self.entry_enricher.clone(),
-                    self.schemas.clone(),
-                    invoker_tx,
-                    invoker_rx,
-                    self.action_token_bucket.clone(),
-                )
-                .run(input_journal),
-            )
+            .spawn(invocation_task.run(input_journal))
             .expect("to spawn invocation task")
     }
 }
@@ -206,6 +228,7 @@ pub struct Service<StorageReader, EntryEnricher, Schemas> {
             Vec<InvocationStatusReport>,
         >,
     >,
+    stats_tx: mpsc::UnboundedSender<StatsCommand>,
     // For the segment queue
     tmp_dir: PathBuf,
     // We have this level of indirection to hide the InvocationTaskRunner,
@@ -225,6 +248,7 @@ impl<StorageReader, TEntryEnricher, Schemas> Service<StorageReader, TEntryEnrich
         entry_enricher: TEntryEnricher,
         invocation_token_bucket: Option<TokenBucket>,
         action_token_bucket: Option<TokenBucket>,
+        replay_limiter: ReplayLimiter,
     ) -> Service<StorageReader, TEntryEnricher, Schemas>
     where
         StorageReader: InvocationReader + Clone + Send + Sync + 'static,
@@ -233,22 +257,30 @@ impl<StorageReader, TEntryEnricher, Schemas> Service<StorageReader, TEntryEnrich
     {
         let (input_tx, input_rx) = mpsc::unbounded_channel();
         let (status_tx, status_rx) = mpsc::unbounded_channel();
+        let (stats_tx, stats_rx) = mpsc::unbounded_channel();
         let (invocation_tasks_tx, invocation_tasks_rx) = mpsc::unbounded_channel();
+        let (shadow_tasks_tx, shadow_tasks_rx) = mpsc::unbounded_channel();
 
         Self {
             input_tx,
             status_tx,
+            stats_tx,
             tmp_dir: options.gen_tmp_dir(),
             inner: ServiceInner {
                 input_rx,
                 status_rx,
+                stats_rx,
                 invocation_tasks_tx,
                 invocation_tasks_rx,
+                shadow_tasks_tx,
+                shadow_tasks_rx,
                 invocation_task_runner: DefaultInvocationTaskRunner {
                     client,
                     entry_enricher,
                     schemas: Live::clone(&schemas),
                     action_token_bucket,
+                    replay_limiter,
+                    service_stubs: ServiceStubs::from_options(options),
                 },
                 schemas,
                 invocation_tasks: Default::default(),
@@ -257,7 +289,14 @@ impl<StorageReader, TEntryEnricher, Schemas> Service<StorageReader, TEntryEnrich
                     invoker_id,
                     options.concurrent_invocations_limit(),
                 ),
+                key_scheduler: key_scheduler::KeyScheduler::new(
+                    options.concurrent_invocations_per_key_limit(),
+                    options.in_memory_queue_length_limit(),
+                ),
                 status_store: Default::default(),
+                stats_store: Default::default(),
+                endpoint_backoff: Default::default(),
+                traffic_mirror: Default::default(),
                 invocation_state_machine_manager: Default::default(),
             },
             invocation_token_bucket,
@@ -272,6 +311,7 @@ impl<StorageReader, TEntryEnricher, Schemas> Service<StorageReader, TEntryEnrich
         schemas: Live<Schemas>,
         invocation_token_bucket: Option<TokenBucket>,
         action_token_bucket: Option<TokenBucket>,
+        replay_limiter: ReplayLimiter,
     ) -> Result<Service<StorageReader, TEntryEnricher, Schemas>, BuildError>
     where
         StorageReader: InvocationReader + Clone + Send + Sync + 'static,
@@ -290,6 +330,7 @@ impl<StorageReader, TEntryEnricher, Schemas> Service<StorageReader, TEntryEnrich
             entry_enricher,
             invocation_token_bucket,
             action_token_bucket,
+            replay_limiter,
         ))
     }
 }
@@ -313,7 +354,10 @@ where
     }
 
     pub fn status_reader(&self) -> ChannelStatusReader {
-        ChannelStatusReader(self.status_tx.clone())
+        ChannelStatusReader {
+            status_tx: self.status_tx.clone(),
+            stats_tx: self.stats_tx.clone(),
+        }
     }
 
     pub async fn run(self, mut updateable_options: impl LiveLoad<Live = InvokerOptions>) {
@@ -382,11 +426,16 @@ struct ServiceInner<InvocationTaskRunner, Schemas, StorageReader> {
             Vec<InvocationStatusReport>,
         >,
     >,
+    stats_rx: mpsc::UnboundedReceiver<StatsCommand>,
 
     // Channel to communicate with invocation tasks
     invocation_tasks_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
     invocation_tasks_rx: mpsc::UnboundedReceiver<InvocationTaskOutput>,
 
+    // Channel to communicate with the tasks mirroring invocations to shadow deployments
+    shadow_tasks_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
+    shadow_tasks_rx: mpsc::UnboundedReceiver<InvocationTaskOutput>,
+
     // Invocation task factory
     invocation_task_runner: InvocationTaskRunner,
 
@@ -396,7 +445,11 @@ struct ServiceInner<InvocationTaskRunner, Schemas, StorageReader> {
     invocation_tasks: JoinSet<()>,
     retry_timers: TimerQueue<(PartitionLeaderEpoch, InvocationId, InvocationEpoch)>,
     quota: quota::InvokerConcurrencyQuota,
+    key_scheduler: key_scheduler::KeyScheduler,
     status_store: InvocationStatusStore,
+    stats_store: InvocationStatsStore,
+    endpoint_backoff: EndpointBackoff,
+    traffic_mirror: TrafficMirror,
     invocation_state_machine_manager:
         state_machine_manager::InvocationStateMachineManager<StorageReader>,
 }
@@ -436,6 +489,17 @@ where
                 let _ = cmd.reply(statuses);
             },
 
+            Some(cmd) = self.stats_rx.recv() => {
+                let keys = cmd.payload();
+                let stats = self
+                    .invocation_state_machine_manager
+                    .registered_partitions_with_keys(keys.clone())
+                    .flat_map(|partition| self.stats_store.stats_for_partition(partition))
+                    .collect();
+
+                let _ = cmd.reply(stats);
+            },
+
             Some(input_message) = self.input_rx.recv() => {
                 match input_message {
                     // --- Spillable queue loading/offloading
@@ -471,8 +535,10 @@ where
                     }
                 }
             },
-            Some(invoke_input_command) = segmented_input_queue.next(), if !segmented_input_queue.inner().is_empty() && self.quota.is_slot_available() => {
-                self.handle_invoke(options, invoke_input_command.partition, invoke_input_command.invocation_id, invoke_input_command.invocation_epoch, invoke_input_command.invocation_target, invoke_input_command.journal);
+            Some(invoke_input_command) = segmented_input_queue.next(), if !segmented_input_queue.inner().is_empty() && self.quota.is_slot_available() && !self.key_scheduler.is_full() => {
+                if let Some(invoke_input_command) = self.key_scheduler.admit(invoke_input_command) {
+                    self.handle_invoke(options, invoke_input_command.partition, invoke_input_command.invocation_id, invoke_input_command.invocation_epoch, invoke_input_command.invocation_target, invoke_input_command.journal);
+                }
             },
             Some(invocation_task_msg) = self.invocation_tasks_rx.recv() => {
                 let InvocationTaskOutput {
@@ -499,6 +565,14 @@ where
                             x_restate_server_header
                         )
                     }
+                    InvocationTaskOutputInner::DiagnosticHeadersReceived(diagnostic_headers) => {
+                        self.handle_diagnostic_headers_received(
+                            partition,
+                            invocation_id,
+                            invocation_epoch,
+                            diagnostic_headers
+                        )
+                    }
                     InvocationTaskOutputInner::NewEntry {entry_index, entry, requires_ack} => {
                         self.handle_new_entry(
                             partition,
@@ -541,6 +615,18 @@ where
                     }
                 };
             },
+            Some(shadow_task_msg) = self.shadow_tasks_rx.recv() => {
+                // The output of the shadow tasks is discarded, only their outcome is recorded
+                let outcome = match shadow_task_msg.inner {
+                    InvocationTaskOutputInner::Closed => Some(AttemptOutcome::Completed),
+                    InvocationTaskOutputInner::Suspended(_) | InvocationTaskOutputInner::SuspendedV2(_) => Some(AttemptOutcome::Suspended),
+                    InvocationTaskOutputInner::Failed(_) => Some(AttemptOutcome::Failed),
+                    _ => None,
+                };
+                if let Some(outcome) = outcome {
+                    self.traffic_mirror.on_shadow_end(shadow_task_msg.partition, shadow_task_msg.invocation_id, outcome);
+                }
+            },
             timer = self.retry_timers.await_timer() => {
                 let (partition, fid, invocation_epoch) = timer.into_inner();
                 self.handle_retry_timer_fired(options, partition, fid, invocation_epoch);
@@ -561,6 +647,22 @@ where
                 return false;
             }
         }
+
+        // Start the parked invocations whose key got a free slot back, before taking new ones
+        // from the input queue.
+        while self.quota.is_slot_available()
+            && let Some(invoke_command) = self.key_scheduler.pop_ready()
+        {
+            self.handle_invoke(
+                options,
+                invoke_command.partition,
+                invoke_command.invocation_id,
+                invoke_command.invocation_epoch,
+                invoke_command.invocation_target,
+                invoke_command.journal,
+            );
+        }
+
         // Execute next loop
         true
     }
@@ -655,7 +757,24 @@ where
                 .invocation_state_machine_manager
                 .partition_storage_reader(partition)
                 .expect("partition is registered");
+            // Only new invocations are mirrored, resumed ones are bound to their pinned deployment
+            let shadow_deployment = matches!(journal, InvokeInputJournal::CachedJournal(..))
+                .then(|| TrafficMirror::sample(options, &invocation_target))
+                .flatten();
+            if let Some(shadow_deployment) = shadow_deployment {
+                self.start_shadow_invocation_task(
+                    options,
+                    partition,
+                    storage_reader.clone(),
+                    invocation_id,
+                    invocation_epoch,
+                    &invocation_target,
+                    shadow_deployment,
+                );
+            }
+
             self.quota.reserve_slot();
+            self.key_scheduler.on_start(partition, &invocation_target);
             self.start_invocation_task(
                 options,
                 partition,
@@ -806,6 +925,27 @@ where
         );
     }
 
+    fn handle_diagnostic_headers_received(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+        invocation_epoch: InvocationEpoch,
+        diagnostic_headers: Vec<(String, String)>,
+    ) {
+        self.invocation_state_machine_manager.handle_for_invocation(
+            partition,
+            &invocation_id,
+            invocation_epoch,
+            |_, _| {
+                self.status_store.on_diagnostic_headers_received(
+                    &partition,
+                    &invocation_id,
+                    diagnostic_headers,
+                );
+            },
+        );
+    }
+
     #[instrument(
         level = "trace",
         skip_all,
@@ -1043,11 +1183,27 @@ where
             .remove_invocation_with_epoch(partition, &invocation_id, invocation_epoch)
         {
             debug_assert_eq!(invocation_epoch, ism.invocation_epoch);
+            if let Some(deployment_id) = ism.attempt_deployment_id().deployment_id() {
+                self.endpoint_backoff.on_success(deployment_id);
+            }
+            self.stats_store.on_end(
+                partition,
+                &ism.invocation_target,
+                AttemptOutcome::Completed,
+                ism.attempt_duration(),
+            );
+            self.traffic_mirror.on_primary_end(
+                partition,
+                invocation_id,
+                AttemptOutcome::Completed,
+                ism.attempt_duration(),
+            );
             counter!(INVOKER_INVOCATION_TASKS, "status" => TASK_OP_COMPLETED, "partition_id" => ID_LOOKUP.get(partition.0)).increment(1);
             trace!(
                 restate.invocation.target = %ism.invocation_target,
                 "Invocation task closed correctly");
             self.quota.unreserve_slot();
+            self.key_scheduler.on_end(partition, &ism.invocation_target);
             self.status_store.on_end(&partition, &invocation_id);
             let _ = sender
                 .send(Box::new(Effect {
@@ -1083,8 +1239,24 @@ where
             .remove_invocation_with_epoch(partition, &invocation_id, invocation_epoch)
         {
             debug_assert_eq!(invocation_epoch, ism.invocation_epoch);
+            if let Some(deployment_id) = ism.attempt_deployment_id().deployment_id() {
+                self.endpoint_backoff.on_success(deployment_id);
+            }
+            self.stats_store.on_end(
+                partition,
+                &ism.invocation_target,
+                AttemptOutcome::Suspended,
+                ism.attempt_duration(),
+            );
+            self.traffic_mirror.on_primary_end(
+                partition,
+                invocation_id,
+                AttemptOutcome::Suspended,
+                ism.attempt_duration(),
+            );
             counter!(INVOKER_INVOCATION_TASKS, "status" => TASK_OP_SUSPENDED, "partition_id" => ID_LOOKUP.get(partition.0)).increment(1);
             self.quota.unreserve_slot();
+            self.key_scheduler.on_end(partition, &ism.invocation_target);
             self.status_store.on_end(&partition, &invocation_id);
 
             if ism.requested_pause {
@@ -1148,9 +1320,25 @@ where
             .remove_invocation_with_epoch(partition, &invocation_id, invocation_epoch)
         {
             debug_assert_eq!(invocation_epoch, ism.invocation_epoch);
+            if let Some(deployment_id) = ism.attempt_deployment_id().deployment_id() {
+                self.endpoint_backoff.on_success(deployment_id);
+            }
+            self.stats_store.on_end(
+                partition,
+                &ism.invocation_target,
+                AttemptOutcome::Suspended,
+                ism.attempt_duration(),
+            );
+            self.traffic_mirror.on_primary_end(
+                partition,
+                invocation_id,
+                AttemptOutcome::Suspended,
+                ism.attempt_duration(),
+            );
             counter!(INVOKER_INVOCATION_TASKS, "status" => TASK_OP_SUSPENDED, "partition_id" => ID_LOOKUP.get(partition.0))
                 .increment(1);
             self.quota.unreserve_slot();
+            self.key_scheduler.on_end(partition, &ism.invocation_target);
             self.status_store.on_end(&partition, &invocation_id);
 
             if ism.requested_pause {
@@ -1248,7 +1436,15 @@ where
             );
             ism.abort();
             self.quota.unreserve_slot();
+            self.key_scheduler.on_end(partition, &ism.invocation_target);
             self.status_store.on_end(&partition, &invocation_id);
+            self.traffic_mirror
+                .on_primary_abort(partition, invocation_id);
+        } else if self
+            .key_scheduler
+            .remove_invocation(partition, &invocation_id, invocation_epoch)
+        {
+            trace!("Aborted invocation waiting for a free slot of its key");
         } else {
             trace!(
                 "Ignoring Abort command because there is no matching partition/invocation/invocation epoch"
@@ -1330,6 +1526,9 @@ where
         )
     )]
     fn handle_abort_partition(&mut self, partition: PartitionLeaderEpoch) {
+        self.key_scheduler.remove_partition(partition);
+        self.stats_store.remove_partition(&partition);
+        self.traffic_mirror.remove_partition(partition);
         if let Some(invocation_state_machines) = self
             .invocation_state_machine_manager
             .remove_partition(partition)
@@ -1342,6 +1541,7 @@ where
                 );
                 ism.abort();
                 self.quota.unreserve_slot();
+                self.key_scheduler.on_end(partition, &ism.invocation_target);
                 self.status_store.on_end(&partition, &fid);
             }
         } else {
@@ -1369,12 +1569,41 @@ where
         mut ism: InvocationStateMachine,
     ) {
         let attempt_deployment_id = ism.attempt_deployment_id();
+        self.stats_store.on_end(
+            partition,
+            &ism.invocation_target,
+            AttemptOutcome::Failed,
+            ism.attempt_duration(),
+        );
+        self.traffic_mirror.on_primary_end(
+            partition,
+            invocation_id,
+            AttemptOutcome::Failed,
+            ism.attempt_duration(),
+        );
+        if let Some(deployment_id) = attempt_deployment_id.deployment_id()
+            && error.is_endpoint_unavailable()
+        {
+            self.endpoint_backoff.on_endpoint_unavailable(deployment_id);
+        }
+        let next_retry_interval_override = error.next_retry_interval_override();
         match ism.handle_task_error(
-            error.is_transient(),
-            error.next_retry_interval_override(),
+            error.error_class(),
+            next_retry_interval_override,
             error.should_bump_start_message_retry_count_since_last_stored_entry(),
         ) {
-            OnTaskError::ScheduleRetry(next_retry_timer_duration) => {
+            OnTaskError::ScheduleRetry(mut next_retry_timer_duration) => {
+                // Delays explicitly requested by the SDK are respected as they are
+                if next_retry_interval_override.is_none() {
+                    if let Some(deployment_id) = attempt_deployment_id.deployment_id() {
+                        next_retry_timer_duration = self
+                            .endpoint_backoff
+                            .scale(deployment_id, next_retry_timer_duration);
+                    }
+                    if let Some(retry_after) = error.retry_after() {
+                        next_retry_timer_duration = next_retry_timer_duration.max(retry_after);
+                    }
+                }
                 counter!(INVOKER_INVOCATION_TASKS,
                     "status" => TASK_OP_FAILED,
                     "transient" => "true",
@@ -1481,6 +1710,7 @@ where
                     restate.deployment.id = %attempt_deployment_id,
                     "Error when executing the invocation, pausing the invocation.");
                 self.quota.unreserve_slot();
+                self.key_scheduler.on_end(partition, &ism.invocation_target);
                 self.status_store.on_end(&partition, &invocation_id);
 
                 let journal_v2_related_command_type =
@@ -1544,6 +1774,7 @@ where
                     restate.deployment.id = %attempt_deployment_id,
                     "Error when executing the invocation, not going to retry.");
                 self.quota.unreserve_slot();
+                self.key_scheduler.on_end(partition, &ism.invocation_target);
                 self.status_store.on_end(&partition, &invocation_id);
 
                 let _ = self
@@ -1582,11 +1813,13 @@ where
             self.invocation_tasks_tx.clone(),
             completions_rx,
             journal,
+            None,
             &mut self.invocation_tasks,
         );
 
         // Transition the state machine, and store it
         self.status_store.on_start(partition, invocation_id);
+        self.stats_store.on_start(partition, &ism.invocation_target);
         ism.start(abort_handle, completions_tx);
         trace!(
             restate.invocation.target = %ism.invocation_target,
@@ -1598,6 +1831,43 @@ where
             .register_invocation(partition, invocation_id, ism);
     }
 
+    /// Mirrors the invocation to the shadow deployment. Shadow tasks don't take a slot of the
+    /// concurrency quota, and never receive notifications.
+    #[allow(clippy::too_many_arguments)]
+    fn start_shadow_invocation_task(
+        &mut self,
+        options: &InvokerOptions,
+        partition: PartitionLeaderEpoch,
+        storage_reader: IR,
+        invocation_id: InvocationId,
+        invocation_epoch: InvocationEpoch,
+        invocation_target: &InvocationTarget,
+        shadow_deployment: DeploymentId,
+    ) {
+        trace!(
+            restate.invocation.target = %invocation_target,
+            restate.deployment.id = %shadow_deployment,
+            "Mirroring invocation to shadow deployment"
+        );
+        let (_, notifications_rx) = mpsc::unbounded_channel();
+        let abort_handle = self.invocation_task_runner.start_invocation_task(
+            options,
+            partition,
+            invocation_id,
+            invocation_epoch,
+            invocation_target.clone(),
+            0,
+            storage_reader,
+            self.shadow_tasks_tx.clone(),
+            notifications_rx,
+            InvokeInputJournal::NoCachedJournal,
+            Some(shadow_deployment),
+            &mut self.invocation_tasks,
+        );
+        self.traffic_mirror
+            .on_mirrored(partition, invocation_id, invocation_target, abort_handle);
+    }
+
     fn handle_retry_event<FN>(
         &mut self,
         options: &InvokerOptions,
@@ -1665,17 +1935,17 @@ mod tests {
     use tokio_util::sync::CancellationToken;
 
     use restate_core::{TaskCenter, TaskKind};
-    use restate_invoker_api::InvokerHandle;
     use restate_invoker_api::entry_enricher;
     use restate_invoker_api::test_util::EmptyStorageReader;
+    use restate_invoker_api::{InvokerHandle, JournalMetadata};
     use restate_service_protocol_v4::entry_codec::ServiceProtocolV4Codec;
     use restate_test_util::check;
     use restate_time_util::FriendlyDuration;
-    use restate_types::config::InvokerOptionsBuilder;
+    use restate_types::config::{InvokerOptionsBuilder, TrafficMirroringOptions};
     use restate_types::deployment::{DeploymentAddress, Headers};
-    use restate_types::errors::{InvocationError, codes};
+    use restate_types::errors::{ErrorClass, InvocationError, codes};
     use restate_types::identifiers::{LeaderEpoch, PartitionId, ServiceRevision};
-    use restate_types::invocation::ServiceType;
+    use restate_types::invocation::{ServiceInvocationSpanContext, ServiceType};
     use restate_types::journal::enriched::EnrichedEntryHeader;
     use restate_types::journal::raw::RawEntry;
     use restate_types::journal_events::EventType;
@@ -1690,8 +1960,10 @@ mod tests {
     };
     use restate_types::schema::service::ServiceMetadata;
     use restate_types::service_protocol::ServiceProtocolVersion;
+    use restate_types::time::MillisSinceEpoch;
 
     use crate::error::{InvokerError, SdkInvocationErrorV2};
+    use crate::key_scheduler::KeyScheduler;
     use crate::quota::InvokerConcurrencyQuota;
 
     // -- Mocks
@@ -1720,19 +1992,29 @@ mod tests {
         ) {
             let (input_tx, input_rx) = mpsc::unbounded_channel();
             let (status_tx, status_rx) = mpsc::unbounded_channel();
+            // Statistics are not read by the tests
+            let (_, stats_rx) = mpsc::unbounded_channel();
             let (invocation_tasks_tx, invocation_tasks_rx) = mpsc::unbounded_channel();
+            let (shadow_tasks_tx, shadow_tasks_rx) = mpsc::unbounded_channel();
 
             let service_inner = Self {
                 input_rx,
                 status_rx,
+                stats_rx,
                 invocation_tasks_tx,
                 invocation_tasks_rx,
+                shadow_tasks_tx,
+                shadow_tasks_rx,
                 invocation_task_runner,
                 schemas: Live::from_value(schemas),
                 invocation_tasks: Default::default(),
                 retry_timers: Default::default(),
                 quota: InvokerConcurrencyQuota::new(0, concurrency_limit),
+                key_scheduler: KeyScheduler::new(None, usize::MAX),
                 status_store: Default::default(),
+                stats_store: Default::default(),
+                endpoint_backoff: Default::default(),
+                traffic_mirror: Default::default(),
                 invocation_state_machine_manager: Default::default(),
             };
             (input_tx, status_tx, service_inner)
@@ -1779,6 +2061,7 @@ mod tests {
             invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
             invoker_rx: mpsc::UnboundedReceiver<Notification>,
             input_journal: InvokeInputJournal,
+            _shadow_deployment: Option<DeploymentId>,
             task_pool: &mut JoinSet<()>,
         ) -> AbortHandle {
             task_pool
@@ -1814,6 +2097,7 @@ mod tests {
             _invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
             _invoker_rx: mpsc::UnboundedReceiver<Notification>,
             _input_journal: InvokeInputJournal,
+            _shadow_deployment: Option<DeploymentId>,
             task_pool: &mut JoinSet<()>,
         ) -> AbortHandle {
             task_pool.spawn(pending())
@@ -1836,6 +2120,7 @@ mod tests {
             _invoker_tx: mpsc::UnboundedSender<InvocationTaskOutput>,
             _invoker_rx: mpsc::UnboundedReceiver<Notification>,
             _input_journal: InvokeInputJournal,
+            _shadow_deployment: Option<DeploymentId>,
             task_pool: &mut JoinSet<()>,
         ) -> AbortHandle {
             self.fetch_add(1, Ordering::SeqCst);
@@ -1942,6 +2227,7 @@ mod tests {
             entry_enricher::test_util::MockEntryEnricher,
             None,
             None,
+            ReplayLimiter::default(),
         );
 
         let mut handle = service.handle();
@@ -2251,6 +2537,46 @@ mod tests {
         );
     }
 
+    #[test(restate_core::test)]
+    async fn abort_invocation_waiting_for_key() {
+        let (_, _status_tx, mut service_inner) =
+            ServiceInner::mock((), MockSchemas::default(), None);
+        service_inner.key_scheduler = KeyScheduler::new(Some(1), usize::MAX);
+        let _ = service_inner.register_mock_partition(EmptyStorageReader);
+        let invocation_target = InvocationTarget::mock_virtual_object();
+
+        // The first invocation takes the only slot of the key
+        let running_id = InvocationId::mock_random();
+        service_inner.handle_invoke(
+            &InvokerOptions::default(),
+            MOCK_PARTITION,
+            running_id,
+            0,
+            invocation_target.clone(),
+            InvokeInputJournal::NoCachedJournal,
+        );
+
+        // The second one waits for a free slot of the key
+        let parked_id = InvocationId::mock_random();
+        assert!(
+            service_inner
+                .key_scheduler
+                .admit(Box::new(InvokeCommand {
+                    partition: MOCK_PARTITION,
+                    invocation_id: parked_id,
+                    invocation_epoch: 0,
+                    invocation_target,
+                    journal: InvokeInputJournal::NoCachedJournal,
+                }))
+                .is_none()
+        );
+
+        // Once aborted, it's not started when the key gets a free slot back
+        service_inner.handle_abort_invocation(MOCK_PARTITION, parked_id, 0);
+        service_inner.handle_abort_invocation(MOCK_PARTITION, running_id, 0);
+        assert!(service_inner.key_scheduler.pop_ready().is_none());
+    }
+
     #[test(restate_core::test)]
     async fn invoke_then_new_invoke_then_old_abort() {
         let invocation_id = InvocationId::mock_random();
@@ -2431,7 +2757,7 @@ mod tests {
         ism.notify_new_notification_proposal(NotificationId::CompletionId(1));
 
         // Put the state machine in the WaitingRetry state
-        ism.handle_task_error(true, None, true);
+        ism.handle_task_error(ErrorClass::RetryableWithBackoff, None, true);
 
         // Register the invocation state machine
         service_inner
@@ -2650,6 +2976,75 @@ mod tests {
         );
     }
 
+    #[test(restate_core::test)]
+    async fn only_new_invocations_are_mirrored() {
+        let started_tasks_count = Arc::new(AtomicUsize::new(0));
+        let invocation_target = InvocationTarget::mock_service();
+        let invoker_options = InvokerOptionsBuilder::default()
+            .traffic_mirroring(HashMap::from([(
+                invocation_target.service_name().to_string(),
+                TrafficMirroringOptions {
+                    deployment: DeploymentId::new(),
+                    percentage: 100,
+                },
+            )]))
+            .build()
+            .unwrap();
+
+        let (_, _status_tx, mut service_inner) =
+            ServiceInner::mock(started_tasks_count.clone(), MockSchemas::default(), Some(2));
+        let _effects_rx = service_inner.register_mock_partition(EmptyStorageReader);
+
+        // A new invocation starts both the primary and the shadow task
+        let new_invocation_id = InvocationId::mock_random();
+        service_inner.handle_invoke(
+            &invoker_options,
+            MOCK_PARTITION,
+            new_invocation_id,
+            0,
+            invocation_target.clone(),
+            InvokeInputJournal::CachedJournal(
+                JournalMetadata::new(
+                    1,
+                    ServiceInvocationSpanContext::empty(),
+                    None,
+                    0,
+                    MillisSinceEpoch::now(),
+                    0,
+                    None,
+                ),
+                vec![],
+            ),
+        );
+        assert_eq!(started_tasks_count.load(Ordering::SeqCst), 2);
+        // The shadow task doesn't take a slot
+        assert!(service_inner.quota.is_slot_available());
+
+        // A resumed invocation is not mirrored
+        service_inner.handle_invoke(
+            &invoker_options,
+            MOCK_PARTITION,
+            InvocationId::mock_random(),
+            0,
+            invocation_target,
+            InvokeInputJournal::NoCachedJournal,
+        );
+        assert_eq!(started_tasks_count.load(Ordering::SeqCst), 3);
+
+        // Shadow outcomes don't affect the primary invocation
+        service_inner.traffic_mirror.on_shadow_end(
+            MOCK_PARTITION,
+            new_invocation_id,
+            AttemptOutcome::Failed,
+        );
+        assert!(
+            service_inner
+                .invocation_state_machine_manager
+                .resolve_invocation(MOCK_PARTITION, &new_invocation_id)
+                .is_some()
+        );
+    }
+
     #[test(restate_core::test)]
     async fn abort_error_counts_towards_retry_policy() {
         // Enable proposing events and keep timers short for the test
@@ -68,12 +68,22 @@ pub const INVOKER_INVOCATION_TASKS: &str = "restate.invoker.invocation_tasks.tot
 pub const INVOKER_AVAILABLE_SLOTS: &str = "restate.invoker.available_slots";
 pub const INVOKER_CONCURRENCY_LIMIT: &str = "restate.invoker.concurrency_limit";
 pub const INVOKER_TASK_DURATION: &str = "restate.invoker.task_duration.seconds";
+pub const INVOKER_INVOCATIONS_WAITING_FOR_KEY: &str = "restate.invoker.invocations_waiting_for_key";
+pub const INVOKER_REPLAYS_QUEUED: &str = "restate.invoker.replays_queued";
+pub const INVOKER_REPLAY_QUEUE_DURATION: &str = "restate.invoker.replay_queue_duration.seconds";
+pub const INVOKER_REPLAYED_BYTES: &str = "restate.invoker.replayed_bytes.total";
+pub const INVOKER_MIRRORED_ATTEMPTS: &str = "restate.invoker.mirrored_attempts.total";
+pub const INVOKER_MIRRORED_ATTEMPT_DURATION: &str =
+    "restate.invoker.mirrored_attempt_duration.seconds";
 
 pub const TASK_OP_STARTED: &str = "started";
 pub const TASK_OP_SUSPENDED: &str = "suspended";
 pub const TASK_OP_FAILED: &str = "failed";
 pub const TASK_OP_COMPLETED: &str = "completed";
 
+pub const MIRROR_TARGET_PRIMARY: &str = "primary";
+pub const MIRROR_TARGET_SHADOW: &str = "shadow";
+
 pub(crate) fn describe_metrics() {
     describe_counter!(
         INVOKER_ENQUEUE,
@@ -104,4 +114,40 @@ pub(crate) fn describe_metrics() {
         Unit::Seconds,
         "Time taken to complete an invoker task"
     );
+
+    describe_gauge!(
+        INVOKER_INVOCATIONS_WAITING_FOR_KEY,
+        Unit::Count,
+        "Number of invocations waiting for a free slot of their key"
+    );
+
+    describe_gauge!(
+        INVOKER_REPLAYS_QUEUED,
+        Unit::Count,
+        "Number of journal replays waiting for a free replay slot"
+    );
+
+    describe_histogram!(
+        INVOKER_REPLAY_QUEUE_DURATION,
+        Unit::Seconds,
+        "Time a journal replay waited for a free replay slot"
+    );
+
+    describe_counter!(
+        INVOKER_REPLAYED_BYTES,
+        Unit::Bytes,
+        "Number of journal bytes replayed to the deployments"
+    );
+
+    describe_counter!(
+        INVOKER_MIRRORED_ATTEMPTS,
+        Unit::Count,
+        "Number of mirrored invocation attempts, by target deployment and outcome"
+    );
+
+    describe_histogram!(
+        INVOKER_MIRRORED_ATTEMPT_DURATION,
+        Unit::Seconds,
+        "Duration of the mirrored invocation attempts, by target deployment"
+    );
 }
@@ -0,0 +1,361 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroU32;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use bytestring::ByteString;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use metrics::{counter, gauge, histogram};
+use tokio::sync::oneshot;
+
+use restate_invoker_api::invocation_reader::JournalEntry;
+use restate_types::journal_v2::raw::RawEntry;
+
+use crate::TokenBucket;
+use crate::metric_definitions::{
+    INVOKER_REPLAY_QUEUE_DURATION, INVOKER_REPLAYED_BYTES, INVOKER_REPLAYS_QUEUED,
+};
+
+/// Limits the resources used by the invocation tasks to replay journals to the deployments.
+///
+/// The limiter is shared by all the invokers running on the node, and enforces:
+///
+/// * A maximum number of concurrent replays. Replays waiting for a slot are admitted in
+///   round-robin order across services.
+/// * A maximum bandwidth, where each token of the bucket is a byte of a replayed journal entry.
+#[derive(Clone, Default)]
+pub struct ReplayLimiter {
+    concurrency: Option<Arc<ConcurrencyLimiter>>,
+    bandwidth: Option<TokenBucket>,
+}
+
+impl ReplayLimiter {
+    pub fn new(concurrency_limit: Option<usize>, bandwidth: Option<TokenBucket>) -> Self {
+        Self {
+            concurrency: concurrency_limit.map(|limit| Arc::new(ConcurrencyLimiter::new(limit))),
+            bandwidth,
+        }
+    }
+
+    /// Wait until the given service is allowed to start a new journal replay.
+    pub(crate) async fn acquire(&self, service_name: &ByteString) -> ReplayPermit {
+        let Some(concurrency) = &self.concurrency else {
+            return ReplayPermit { concurrency: None };
+        };
+
+        let rx = match concurrency.try_acquire(service_name) {
+            Ok(()) => {
+                return ReplayPermit {
+                    concurrency: Some(Arc::clone(concurrency)),
+                };
+            }
+            Err(rx) => rx,
+        };
+
+        let start = Instant::now();
+        // The sender side is owned by the limiter, which we keep alive through self.
+        let permit = rx.await.expect("replay limiter must be alive");
+        histogram!(INVOKER_REPLAY_QUEUE_DURATION).record(start.elapsed());
+
+        permit
+    }
+
+    /// Applies the bandwidth limit to the given journal stream.
+    ///
+    /// The permit is held by the returned stream, hence it's released as soon as the replay is
+    /// over and the stream is dropped.
+    pub(crate) fn throttle<S>(
+        &self,
+        permit: ReplayPermit,
+        journal_stream: S,
+    ) -> BoxStream<'static, JournalEntry>
+    where
+        S: Stream<Item = JournalEntry> + Send + 'static,
+    {
+        let bandwidth = self.bandwidth.clone();
+        ReplayStream {
+            inner: journal_stream
+                .then(move |entry| consume_bandwidth(bandwidth.clone(), entry))
+                .boxed(),
+            _permit: permit,
+        }
+        .boxed()
+    }
+}
+
+async fn consume_bandwidth(bandwidth: Option<TokenBucket>, entry: JournalEntry) -> JournalEntry {
+    let entry_size = journal_entry_size(&entry);
+    counter!(INVOKER_REPLAYED_BYTES).increment(entry_size as u64);
+
+    if let Some(bucket) = bandwidth {
+        // Entries larger than the burst capacity consume the whole bucket
+        let burst = bucket.limit().burst();
+        let tokens = NonZeroU32::new(u32::try_from(entry_size).unwrap_or(u32::MAX))
+            .unwrap_or(NonZeroU32::MIN)
+            .min(burst);
+        if let Ok(Some(wait)) = bucket.consume_with_borrow(tokens) {
+            tokio::time::sleep(Duration::from(wait)).await;
+        }
+    }
+
+    entry
+}
+
+fn journal_entry_size(entry: &JournalEntry) -> usize {
+    match entry {
+        JournalEntry::JournalV1(entry) => entry.serialized_entry().len(),
+        JournalEntry::JournalV2(entry) => match &entry.inner {
+            RawEntry::Command(cmd) => cmd.serialized_content().len(),
+            RawEntry::Notification(notif) => notif.serialized_content().len(),
+        },
+    }
+}
+
+struct ReplayStream {
+    inner: BoxStream<'static, JournalEntry>,
+    _permit: ReplayPermit,
+}
+
+impl Stream for ReplayStream {
+    type Item = JournalEntry;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Slot to replay a journal. The slot is handed over to the next queued replay on drop.
+pub(crate) struct ReplayPermit {
+    concurrency: Option<Arc<ConcurrencyLimiter>>,
+}
+
+impl Drop for ReplayPermit {
+    fn drop(&mut self) {
+        if let Some(concurrency) = self.concurrency.take() {
+            concurrency.release();
+        }
+    }
+}
+
+struct ConcurrencyLimiter {
+    state: Mutex<ConcurrencyState>,
+}
+
+struct ConcurrencyState {
+    available_slots: usize,
+    // Services with at least one queued replay, in the order they will be served
+    ready_services: VecDeque<ByteString>,
+    queued_replays: HashMap<ByteString, VecDeque<oneshot::Sender<ReplayPermit>>>,
+}
+
+impl ConcurrencyLimiter {
+    fn new(limit: usize) -> Self {
+        gauge!(INVOKER_REPLAYS_QUEUED).set(0.0);
+        Self {
+            state: Mutex::new(ConcurrencyState {
+                available_slots: limit,
+                ready_services: VecDeque::new(),
+                queued_replays: HashMap::new(),
+            }),
+        }
+    }
+
+    fn try_acquire(
+        &self,
+        service_name: &ByteString,
+    ) -> Result<(), oneshot::Receiver<ReplayPermit>> {
+        let mut state = self.state.lock().unwrap();
+        // Don't overtake replays that are already queued
+        if state.available_slots > 0 && state.ready_services.is_empty() {
+            state.available_slots -= 1;
+            return Ok(());
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let queue = state
+            .queued_replays
+            .entry(service_name.clone())
+            .or_default();
+        queue.push_back(tx);
+        if queue.len() == 1 {
+            state.ready_services.push_back(service_name.clone());
+        }
+        gauge!(INVOKER_REPLAYS_QUEUED).increment(1.0);
+
+        Err(rx)
+    }
+
+    fn release(self: Arc<Self>) {
+        loop {
+            let Some(next) = self.state.lock().unwrap().pop_next() else {
+                return;
+            };
+
+            let permit = ReplayPermit {
+                concurrency: Some(Arc::clone(&self)),
+            };
+            match next.send(permit) {
+                Ok(()) => return,
+                Err(mut permit) => {
+                    // The waiting replay was cancelled, hand over the slot to the next one.
+                    // Take the limiter out of the permit, to avoid dropping it recursively.
+                    permit.concurrency = None;
+                }
+            }
+        }
+    }
+}
+
+impl ConcurrencyState {
+    /// Pops the next queued replay following the round-robin order among services,
+    /// or frees up the slot if no replay is waiting.
+    fn pop_next(&mut self) -> Option<oneshot::Sender<ReplayPermit>> {
+        let Some(service_name) = self.ready_services.pop_front() else {
+            self.available_slots += 1;
+            return None;
+        };
+
+        let queue = self
+            .queued_replays
+            .get_mut(&service_name)
+            .expect("ready services must have queued replays");
+        let next = queue.pop_front().expect("queue must be non empty");
+        if queue.is_empty() {
+            self.queued_replays.remove(&service_name);
+        } else {
+            self.ready_services.push_back(service_name);
+        }
+        gauge!(INVOKER_REPLAYS_QUEUED).decrement(1.0);
+
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+    use restate_types::journal_v2::CommandType;
+    use restate_types::journal_v2::raw::RawCommand;
+    use restate_types::storage::{StoredRawEntry, StoredRawEntryHeader};
+    use restate_types::time::MillisSinceEpoch;
+
+    fn poll_acquire(
+        acquire: &mut Pin<Box<impl Future<Output = ReplayPermit>>>,
+    ) -> Option<ReplayPermit> {
+        let waker = futures::task::noop_waker();
+        match acquire.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(permit) => Some(permit),
+            Poll::Pending => None,
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn unlimited_replays_never_wait() {
+        let limiter = ReplayLimiter::default();
+        let service = ByteString::from_static("Greeter");
+
+        let _permits = futures::future::join_all((0..10).map(|_| limiter.acquire(&service))).await;
+    }
+
+    #[test(tokio::test)]
+    async fn queued_replays_are_served_round_robin_across_services() {
+        let limiter = ReplayLimiter::new(Some(1), None);
+        let greeter = ByteString::from_static("Greeter");
+        let counter = ByteString::from_static("Counter");
+
+        let first = limiter.acquire(&greeter).await;
+
+        let mut greeter_1 = Box::pin(limiter.acquire(&greeter));
+        let mut greeter_2 = Box::pin(limiter.acquire(&greeter));
+        let mut counter_1 = Box::pin(limiter.acquire(&counter));
+        assert!(poll_acquire(&mut greeter_1).is_none());
+        assert!(poll_acquire(&mut greeter_2).is_none());
+        assert!(poll_acquire(&mut counter_1).is_none());
+
+        drop(first);
+        let permit = poll_acquire(&mut greeter_1).expect("first queued greeter replay");
+        assert!(poll_acquire(&mut greeter_2).is_none());
+        assert!(poll_acquire(&mut counter_1).is_none());
+
+        // Counter is served before the second Greeter replay
+        drop(permit);
+        let permit = poll_acquire(&mut counter_1).expect("counter replay");
+        assert!(poll_acquire(&mut greeter_2).is_none());
+
+        drop(permit);
+        let permit = poll_acquire(&mut greeter_2).expect("second greeter replay");
+        drop(permit);
+
+        // The slot is free again
+        let mut next = Box::pin(limiter.acquire(&counter));
+        assert!(poll_acquire(&mut next).is_some());
+    }
+
+    #[test(tokio::test)]
+    async fn cancelled_replays_give_back_the_slot() {
+        let limiter = ReplayLimiter::new(Some(1), None);
+        let greeter = ByteString::from_static("Greeter");
+
+        let first = limiter.acquire(&greeter).await;
+        let mut cancelled = Box::pin(limiter.acquire(&greeter));
+        assert!(poll_acquire(&mut cancelled).is_none());
+        let mut waiting = Box::pin(limiter.acquire(&greeter));
+        assert!(poll_acquire(&mut waiting).is_none());
+
+        drop(cancelled);
+        drop(first);
+
+        assert!(poll_acquire(&mut waiting).is_some());
+    }
+
+    #[test(tokio::test(start_paused = true))]
+    async fn throttle_journal_bandwidth() {
+        let bucket = TokenBucket::from_parts(
+            gardal::Limit::per_second_and_burst(
+                NonZeroU32::new(100).unwrap(),
+                NonZeroU32::new(100).unwrap(),
+            ),
+            gardal::TokioClock::default(),
+        );
+        bucket.add_tokens(100);
+        let limiter = ReplayLimiter::new(None, Some(bucket));
+
+        let entries = (0..3).map(|_| {
+            JournalEntry::JournalV2(StoredRawEntry::new(
+                StoredRawEntryHeader::new(MillisSinceEpoch::now()),
+                RawCommand::new(CommandType::Run, vec![0u8; 100]),
+            ))
+        });
+
+        let start = tokio::time::Instant::now();
+        let permit = limiter.acquire(&ByteString::from_static("Greeter")).await;
+        let replayed = limiter
+            .throttle(permit, futures::stream::iter(entries))
+            .count()
+            .await;
+
+        assert_eq!(replayed, 3);
+        // The first entry uses the initial burst, the other two need to wait for the refill
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use serde_json::Value;
+
+use restate_types::config::{InvokerOptions, ServiceStubOptions};
+use restate_types::invocation::{InvocationTargetType, VirtualObjectHandlerType};
+use restate_types::schema::invocation_target::{
+    DeploymentStatus, InputRules, InvocationTargetMetadata, OutputRules,
+};
+use restate_types::schema::service::IngressLimit;
+
+/// Stub responses configured for the handlers of services which are not registered yet.
+///
+/// The stubs are built once from the invoker options, and shared by the invocation tasks.
+///
+/// See [`InvokerOptions::service_stubs`] for more details.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ServiceStubs(Arc<HashMap<String, HashMap<String, ServiceStubOptions>>>);
+
+impl ServiceStubs {
+    pub(crate) fn from_options(options: &InvokerOptions) -> Self {
+        if options.disable_service_stubs {
+            return Self::default();
+        }
+        Self(Arc::new(options.service_stubs.clone()))
+    }
+
+    pub(crate) fn get(
+        &self,
+        service_name: &str,
+        handler_name: &str,
+    ) -> Option<&ServiceStubOptions> {
+        self.0.get(service_name)?.get(handler_name)
+    }
+
+    /// Metadata used to call a stubbed handler. Stubbed handlers are treated as service handlers,
+    /// or shared virtual object handlers when they're called with a key.
+    pub(crate) fn invocation_target_metadata(keyed: bool) -> InvocationTargetMetadata {
+        InvocationTargetMetadata {
+            public: false,
+            completion_retention: Duration::ZERO,
+            journal_retention: Duration::ZERO,
+            target_ty: if keyed {
+                InvocationTargetType::VirtualObject(VirtualObjectHandlerType::Shared)
+            } else {
+                InvocationTargetType::Service
+            },
+            input_rules: InputRules::default(),
+            output_rules: OutputRules::default(),
+            deployment_status: DeploymentStatus::Enabled,
+            side_effect_free: false,
+            derived_idempotency_key_window: None,
+            service_ingress_limit: IngressLimit::default(),
+            handler_ingress_limit: IngressLimit::default(),
+        }
+    }
+}
+
+/// Renders the stub response for the given request body.
+///
+/// Requests which are not valid JSON are treated as `null`.
+pub(crate) fn render_stub_response(stub: &ServiceStubOptions, request_body: &[u8]) -> Bytes {
+    let request = serde_json::from_slice(request_body).unwrap_or(Value::Null);
+    let response = render(&stub.response, &request);
+    serde_json::to_vec(&response)
+        .expect("serializing a json value cannot fail")
+        .into()
+}
+
+fn render(template: &Value, request: &Value) -> Value {
+    match template {
+        Value::String(s) => render_string(s, request),
+        Value::Array(values) => Value::Array(values.iter().map(|v| render(v, request)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), render(v, request)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+fn render_string(s: &str, request: &Value) -> Value {
+    // A string which is only a placeholder is replaced with the json value itself
+    if let Some(path) = s
+        .strip_prefix("{{")
+        .and_then(|s| s.strip_suffix("}}"))
+        .filter(|s| !s.contains("}}"))
+        .and_then(placeholder_path)
+    {
+        return lookup(request, path).clone();
+    }
+
+    let mut rendered = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let end = start + len + 2;
+        rendered.push_str(&rest[..start]);
+        match placeholder_path(&rest[start + 2..start + len]) {
+            Some(path) => match lookup(request, path) {
+                Value::String(value) => rendered.push_str(value),
+                value => rendered.push_str(&value.to_string()),
+            },
+            None => rendered.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    rendered.push_str(rest);
+
+    Value::String(rendered)
+}
+
+/// Parses the placeholder expression, returning the path within the request.
+fn placeholder_path(expression: &str) -> Option<&str> {
+    let expression = expression.trim();
+    if expression == "request" {
+        Some("")
+    } else {
+        expression.strip_prefix("request.")
+    }
+}
+
+fn lookup<'a>(request: &'a Value, path: &str) -> &'a Value {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(request, |value, segment| match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(values) => segment.parse::<usize>().ok().and_then(|i| values.get(i)),
+            _ => None,
+        })
+        .unwrap_or(&Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+    use test_log::test;
+
+    fn render_json(response: Value, request: Value) -> Value {
+        let stub = ServiceStubOptions { response };
+        let rendered = render_stub_response(&stub, &serde_json::to_vec(&request).unwrap());
+        serde_json::from_slice(&rendered).unwrap()
+    }
+
+    #[test]
+    fn static_response() {
+        assert_eq!(
+            render_json(json!({"status": "ok", "items": [1, 2]}), json!({"a": 1})),
+            json!({"status": "ok", "items": [1, 2]})
+        );
+    }
+
+    #[test]
+    fn placeholders_are_replaced_with_json_values() {
+        let request = json!({"order": {"id": "abc", "items": [{"qty": 3}]}});
+        assert_eq!(
+            render_json(
+                json!({
+                    "echo": "{{request}}",
+                    "order": "{{ request.order.id }}",
+                    "qty": "{{request.order.items.0.qty}}",
+                    "missing": "{{request.order.price}}",
+                }),
+                request.clone()
+            ),
+            json!({
+                "echo": request,
+                "order": "abc",
+                "qty": 3,
+                "missing": null,
+            })
+        );
+    }
+
+    #[test]
+    fn placeholders_within_strings_are_replaced_with_text() {
+        assert_eq!(
+            render_json(
+                json!("{{request.id}} of {{request.qty}} items {{unknown}}"),
+                json!({"id": "abc", "qty": 3})
+            ),
+            json!("abc of 3 items {{unknown}}")
+        );
+    }
+
+    #[test]
+    fn non_json_request() {
+        let stub = ServiceStubOptions {
+            response: json!({"echo": "{{request}}"}),
+        };
+        let rendered = render_stub_response(&stub, b"not json");
+        assert_eq!(
+            serde_json::from_slice::<Value>(&rendered).unwrap(),
+            json!({"echo": null})
+        );
+    }
+}
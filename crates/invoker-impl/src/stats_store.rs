@@ -0,0 +1,178 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::*;
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+use bytestring::ByteString;
+use restate_invoker_api::{InvocationStats, InvocationStatsReport};
+
+/// Width of the time buckets of the invocation statistics.
+const BUCKET_WIDTH: Duration = Duration::from_secs(60);
+/// Number of time buckets retained per partition, older buckets are discarded.
+const RETAINED_BUCKETS: u64 = 60;
+
+/// Bucket index, service name and handler name.
+type StatsKey = (u64, ByteString, ByteString);
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum AttemptOutcome {
+    Completed,
+    Suspended,
+    Failed,
+}
+
+/// Pre-aggregated statistics of the invocation attempts, per handler and time bucket.
+#[derive(Default, Debug)]
+pub(super) struct InvocationStatsStore(
+    HashMap<PartitionLeaderEpoch, BTreeMap<StatsKey, InvocationStats>>,
+);
+
+impl InvocationStatsStore {
+    pub(super) fn stats_for_partition(
+        &self,
+        partition_leader_epoch: PartitionLeaderEpoch,
+    ) -> impl Iterator<Item = InvocationStatsReport> + '_ {
+        self.0
+            .get(&partition_leader_epoch)
+            .into_iter()
+            .flat_map(move |buckets| {
+                buckets
+                    .iter()
+                    .map(move |((bucket, service_name, handler_name), stats)| {
+                        InvocationStatsReport {
+                            partition_id: partition_leader_epoch.0,
+                            bucket_start: SystemTime::UNIX_EPOCH + BUCKET_WIDTH * (*bucket as u32),
+                            bucket_width: BUCKET_WIDTH,
+                            service_name: service_name.clone(),
+                            handler_name: handler_name.clone(),
+                            stats: stats.clone(),
+                        }
+                    })
+            })
+    }
+
+    // -- Methods used by the invoker to notify the attempts
+
+    pub(super) fn on_start(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_target: &InvocationTarget,
+    ) {
+        self.stats_mut(partition, invocation_target).attempts += 1;
+    }
+
+    pub(super) fn on_end(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_target: &InvocationTarget,
+        outcome: AttemptOutcome,
+        attempt_duration: Option<Duration>,
+    ) {
+        let stats = self.stats_mut(partition, invocation_target);
+        match outcome {
+            AttemptOutcome::Completed => stats.completed += 1,
+            AttemptOutcome::Suspended => stats.suspended += 1,
+            AttemptOutcome::Failed => stats.failed += 1,
+        }
+        if let Some(attempt_duration) = attempt_duration {
+            stats.attempt_duration.record(attempt_duration);
+        }
+    }
+
+    pub(super) fn remove_partition(&mut self, partition: &PartitionLeaderEpoch) {
+        self.0.remove(partition);
+    }
+
+    fn stats_mut(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_target: &InvocationTarget,
+    ) -> &mut InvocationStats {
+        let bucket = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            / BUCKET_WIDTH.as_secs();
+        let buckets = self.0.entry(partition).or_default();
+
+        // Discard the buckets out of the retention window
+        let oldest_retained_bucket = bucket.saturating_sub(RETAINED_BUCKETS - 1);
+        if buckets
+            .first_key_value()
+            .is_some_and(|((first_bucket, _, _), _)| *first_bucket < oldest_retained_bucket)
+        {
+            *buckets =
+                buckets.split_off(&(oldest_retained_bucket, ByteString::new(), ByteString::new()));
+        }
+
+        buckets
+            .entry((
+                bucket,
+                invocation_target.service_name().clone(),
+                invocation_target.handler_name().clone(),
+            ))
+            .or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_types::identifiers::{LeaderEpoch, PartitionId};
+
+    #[test]
+    fn aggregate_attempts_per_handler() {
+        let mut store = InvocationStatsStore::default();
+        let partition = (PartitionId::MIN, LeaderEpoch::INITIAL);
+        let greet = InvocationTarget::mock_service();
+        let other = InvocationTarget::mock_virtual_object();
+
+        store.on_start(partition, &greet);
+        store.on_end(
+            partition,
+            &greet,
+            AttemptOutcome::Failed,
+            Some(Duration::from_millis(10)),
+        );
+        store.on_start(partition, &greet);
+        store.on_end(
+            partition,
+            &greet,
+            AttemptOutcome::Completed,
+            Some(Duration::from_millis(30)),
+        );
+        store.on_start(partition, &other);
+        store.on_end(partition, &other, AttemptOutcome::Suspended, None);
+
+        let reports: Vec<_> = store.stats_for_partition(partition).collect();
+        assert_eq!(reports.len(), 2);
+
+        let greet_stats = &reports
+            .iter()
+            .find(|report| report.handler_name == greet.handler_name())
+            .unwrap()
+            .stats;
+        assert_eq!(greet_stats.attempts, 2);
+        assert_eq!(greet_stats.completed, 1);
+        assert_eq!(greet_stats.failed, 1);
+        assert_eq!(greet_stats.suspended, 0);
+        assert_eq!(
+            greet_stats.attempt_duration.mean(),
+            Some(Duration::from_millis(20))
+        );
+
+        store.remove_partition(&partition);
+        assert_eq!(store.stats_for_partition(partition).count(), 0);
+    }
+}
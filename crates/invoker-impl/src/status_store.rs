@@ -56,6 +56,7 @@ impl InvocationStatusStore {
         report.last_start_at = SystemTime::now();
         report.next_retry_at = None;
         report.in_flight = true;
+        report.last_attempt_diagnostic_headers.clear();
     }
 
     pub(super) fn on_progress_made(
@@ -99,6 +100,19 @@ impl InvocationStatusStore {
         }
     }
 
+    pub(super) fn on_diagnostic_headers_received(
+        &mut self,
+        partition: &PartitionLeaderEpoch,
+        invocation_id: &InvocationId,
+        diagnostic_headers: Vec<(String, String)>,
+    ) {
+        if let Some(inner) = self.0.get_mut(partition)
+            && let Some(report) = inner.get_mut(invocation_id)
+        {
+            report.last_attempt_diagnostic_headers = diagnostic_headers;
+        }
+    }
+
     pub(super) fn on_end(
         &mut self,
         partition: &PartitionLeaderEpoch,
@@ -0,0 +1,164 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use bytestring::ByteString;
+use metrics::{counter, histogram};
+use tokio::task::AbortHandle;
+
+use restate_types::config::InvokerOptions;
+use restate_types::identifiers::{DeploymentId, InvocationId, PartitionLeaderEpoch};
+use restate_types::invocation::InvocationTarget;
+
+use crate::metric_definitions::{
+    INVOKER_MIRRORED_ATTEMPT_DURATION, INVOKER_MIRRORED_ATTEMPTS, MIRROR_TARGET_PRIMARY,
+    MIRROR_TARGET_SHADOW, TASK_OP_COMPLETED, TASK_OP_FAILED, TASK_OP_SUSPENDED,
+};
+use crate::stats_store::AttemptOutcome;
+
+type MirrorKey = (PartitionLeaderEpoch, InvocationId);
+
+#[derive(Debug)]
+struct ShadowAttempt {
+    service_name: ByteString,
+    started_at: Instant,
+    abort_handle: AbortHandle,
+}
+
+/// Invocations mirrored to a shadow deployment, see [`InvokerOptions::traffic_mirroring`].
+///
+/// Only the first attempt of a mirrored invocation is compared with its shadow attempt, so that
+/// both sides of the metrics refer to the same set of requests.
+#[derive(Debug, Default)]
+pub(crate) struct TrafficMirror {
+    primaries: HashMap<MirrorKey, ByteString>,
+    shadows: HashMap<MirrorKey, ShadowAttempt>,
+}
+
+impl TrafficMirror {
+    /// Returns the shadow deployment a new invocation should be mirrored to, if any.
+    pub(crate) fn sample(
+        options: &InvokerOptions,
+        invocation_target: &InvocationTarget,
+    ) -> Option<DeploymentId> {
+        let mirroring = options
+            .traffic_mirroring
+            .get(invocation_target.service_name().as_ref())?;
+        (mirroring.percentage > 0
+            && rand::random_ratio(u32::from(mirroring.percentage.min(100)), 100))
+        .then_some(mirroring.deployment)
+    }
+
+    pub(crate) fn on_mirrored(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+        invocation_target: &InvocationTarget,
+        shadow_abort_handle: AbortHandle,
+    ) {
+        let service_name = invocation_target.service_name().clone();
+        self.primaries
+            .insert((partition, invocation_id), service_name.clone());
+        if let Some(previous) = self.shadows.insert(
+            (partition, invocation_id),
+            ShadowAttempt {
+                service_name,
+                started_at: Instant::now(),
+                abort_handle: shadow_abort_handle,
+            },
+        ) {
+            previous.abort_handle.abort();
+        }
+    }
+
+    pub(crate) fn on_primary_end(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+        outcome: AttemptOutcome,
+        attempt_duration: Option<Duration>,
+    ) {
+        if let Some(service_name) = self.primaries.remove(&(partition, invocation_id)) {
+            record(
+                MIRROR_TARGET_PRIMARY,
+                service_name,
+                outcome,
+                attempt_duration,
+            );
+        }
+    }
+
+    /// The primary attempt was aborted, hence there's nothing to compare the shadow attempt with.
+    pub(crate) fn on_primary_abort(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+    ) {
+        self.primaries.remove(&(partition, invocation_id));
+    }
+
+    pub(crate) fn on_shadow_end(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        invocation_id: InvocationId,
+        outcome: AttemptOutcome,
+    ) {
+        if let Some(shadow) = self.shadows.remove(&(partition, invocation_id)) {
+            record(
+                MIRROR_TARGET_SHADOW,
+                shadow.service_name,
+                outcome,
+                Some(shadow.started_at.elapsed()),
+            );
+        }
+    }
+
+    pub(crate) fn remove_partition(&mut self, partition: PartitionLeaderEpoch) {
+        self.primaries.retain(|(p, _), _| *p != partition);
+        self.shadows.retain(|(p, _), shadow| {
+            if *p == partition {
+                shadow.abort_handle.abort();
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+fn record(
+    target: &'static str,
+    service_name: ByteString,
+    outcome: AttemptOutcome,
+    attempt_duration: Option<Duration>,
+) {
+    let outcome = match outcome {
+        AttemptOutcome::Completed => TASK_OP_COMPLETED,
+        AttemptOutcome::Suspended => TASK_OP_SUSPENDED,
+        AttemptOutcome::Failed => TASK_OP_FAILED,
+    };
+    counter!(
+        INVOKER_MIRRORED_ATTEMPTS,
+        "target" => target,
+        "rpc.service" => service_name.to_string(),
+        "outcome" => outcome
+    )
+    .increment(1);
+    if let Some(attempt_duration) = attempt_duration {
+        histogram!(
+            INVOKER_MIRRORED_ATTEMPT_DURATION,
+            "target" => target,
+            "rpc.service" => service_name.to_string()
+        )
+        .record(attempt_duration);
+    }
+}
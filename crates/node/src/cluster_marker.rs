@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use restate_types::config::node_filepath;
+use restate_types::{COMPATIBILITY_INFORMATION, CompatibilityInformation};
 use semver::Version;
 use std::cmp::Ordering;
 use std::fs::{File, OpenOptions};
@@ -18,41 +19,6 @@ use tracing::debug;
 const CLUSTER_MARKER_FILE_NAME: &str = ".cluster-marker";
 const TMP_CLUSTER_MARKER_FILE_NAME: &str = ".tmp-cluster-marker";
 
-/// Compatibility information for this version.
-///
-/// It includes:
-/// * Minimum forward compatible version which can still read data written by this version
-/// * Minimum backward compatible version whose data this version can still read
-///
-/// # Important
-/// This information needs to be updated whenever we release a version that changes the
-/// compatible versions boundaries.
-static COMPATIBILITY_INFORMATION: CompatibilityInformation =
-    CompatibilityInformation::new(Version::new(1, 4, 0), Version::new(1, 0, 0));
-
-/// Compatibility information defining the minimum Restate version that can read data written by
-/// this version. Additionally, it specifies the minimum with which this version is backwards
-/// compatible.
-#[derive(Debug, Clone)]
-struct CompatibilityInformation {
-    /// Minimum version required to read data written by this version.
-    min_forward_compatible_version: Version,
-    /// Minimum version from which this version can read data.
-    min_backward_compatible_version: Version,
-}
-
-impl CompatibilityInformation {
-    const fn new(
-        min_forward_compatible_version: Version,
-        min_backward_compatible_version: Version,
-    ) -> Self {
-        Self {
-            min_forward_compatible_version,
-            min_backward_compatible_version,
-        }
-    }
-}
-
 #[derive(Debug, thiserror::Error)]
 pub enum ClusterValidationError {
     #[error("failed parsing restate version: {0}")]
@@ -60,6 +60,9 @@ use crate::init::NodeInit;
 use crate::network_server::NetworkServer;
 use crate::roles::{AdminRole, IngressRole, WorkerRole};
 
+#[cfg(feature = "test-util")]
+pub use restate_worker::ManualClock;
+
 #[derive(Debug, thiserror::Error, CodedError)]
 pub enum Error {
     #[error("node failed to start due to failed safety check: {0}")]
@@ -588,6 +591,17 @@ impl Node {
     pub fn metadata_writer(&self) -> restate_core::MetadataWriter {
         self.metadata_manager.writer()
     }
+
+    /// Drives the timers of the partition processors running on this node with the given
+    /// [`ManualClock`] instead of the wall clock. Has no effect if this node doesn't run the
+    /// worker role.
+    #[cfg(feature = "test-util")]
+    pub fn with_timer_clock(mut self, clock: ManualClock) -> Self {
+        self.worker_role = self
+            .worker_role
+            .map(|worker_role| worker_role.with_timer_clock(clock));
+        self
+    }
 }
 
 #[derive(Clone, Debug, IntoProst)]
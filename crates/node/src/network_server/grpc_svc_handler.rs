@@ -27,8 +27,9 @@ use restate_metadata_store::protobuf::metadata_proxy_svc::{
 use restate_core::network::net_util::create_tonic_channel;
 use restate_core::protobuf::node_ctl_svc::node_ctl_svc_server::{NodeCtlSvc, NodeCtlSvcServer};
 use restate_core::protobuf::node_ctl_svc::{
-    ClusterHealthResponse, EmbeddedMetadataClusterHealth, GetMetadataRequest, GetMetadataResponse,
-    IdentResponse, ProvisionClusterRequest, ProvisionClusterResponse,
+    ClusterHealthResponse, EmbeddedMetadataClusterHealth, GetConfigurationResponse,
+    GetMetadataRequest, GetMetadataResponse, IdentResponse, ProvisionClusterRequest,
+    ProvisionClusterResponse,
 };
 use restate_core::{Identification, MetadataWriter};
 use restate_core::{Metadata, MetadataKind};
@@ -253,6 +254,16 @@ impl NodeCtlSvc for NodeCtlSvcHandler {
 
         Ok(Response::new(cluster_state_response))
     }
+
+    async fn get_configuration(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<GetConfigurationResponse>, Status> {
+        let configuration = Configuration::pinned().to_redacted_json();
+        Ok(Response::new(GetConfigurationResponse {
+            configuration: configuration.to_string(),
+        }))
+    }
 }
 
 pub struct MetadataProxySvcHandler {
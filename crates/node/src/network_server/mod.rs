@@ -13,6 +13,7 @@ mod metrics;
 mod pprof;
 mod prometheus_helpers;
 mod service;
+mod startup;
 mod state;
 
 pub use service::NetworkServer;
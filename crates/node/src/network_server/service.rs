@@ -20,6 +20,7 @@ use restate_types::config::Configuration;
 
 use super::grpc_svc_handler::{MetadataProxySvcHandler, NodeCtlSvcHandler};
 use super::pprof;
+use super::startup::report_startup;
 use crate::network_server::metrics::render_metrics;
 use crate::network_server::state::NodeCtrlHandlerStateBuilder;
 
@@ -46,6 +47,7 @@ impl NetworkServer {
         let axum_router = axum::Router::new()
             .route("/health", get(report_health))
             .route("/metrics", get(render_metrics))
+            .route("/startup", get(report_startup))
             .route("/debug/pprof/heap", get(pprof::heap))
             .route(
                 "/debug/pprof/heap/activate",
@@ -0,0 +1,58 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use axum::Json;
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use restate_types::identifiers::PartitionId;
+use restate_types::partitions::recovery::{self, RecoveryProgress};
+
+#[derive(Debug, Serialize)]
+pub struct StartupStatus {
+    /// Whether any of the partition processors of this node is still recovering.
+    recovering: bool,
+    partitions: Vec<PartitionRecoveryStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PartitionRecoveryStatus {
+    partition_id: PartitionId,
+    percentage: f64,
+    #[serde(flatten)]
+    progress: RecoveryProgress,
+}
+
+/// Reports the recovery progress of the partition processors running on this node. Responds with
+/// `503 Service Unavailable` until all of them have recovered.
+pub async fn report_startup() -> (StatusCode, Json<StartupStatus>) {
+    let partitions: Vec<_> = recovery::all()
+        .into_iter()
+        .map(|(partition_id, progress)| PartitionRecoveryStatus {
+            partition_id,
+            percentage: progress.percentage(),
+            progress,
+        })
+        .collect();
+    let recovering = partitions.iter().any(|p| !p.progress.is_recovered());
+
+    let status_code = if recovering {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (
+        status_code,
+        Json(StartupStatus {
+            recovering,
+            partitions,
+        }),
+    )
+}
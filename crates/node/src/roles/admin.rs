@@ -155,6 +155,11 @@ impl<T: TransportConnect> AdminRole<T> {
             None
         };
 
+        let admin = match &controller {
+            Some(controller) => admin.with_cluster_controller(controller.handle()),
+            None => admin,
+        };
+
         let storage_accounting_task = config
             .admin
             .storage_accounting_update_interval
@@ -64,6 +64,13 @@ impl WorkerRole {
         Ok(WorkerRole { worker })
     }
 
+    #[cfg(feature = "test-util")]
+    pub fn with_timer_clock(self, clock: restate_worker::ManualClock) -> Self {
+        WorkerRole {
+            worker: self.worker.with_timer_clock(clock),
+        }
+    }
+
     pub fn partition_processor_manager_handle(&self) -> ProcessorsManagerHandle {
         self.worker.partition_processor_manager_handle()
     }
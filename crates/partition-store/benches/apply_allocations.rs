@@ -0,0 +1,140 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Benchmarks the storage accesses the partition processor does when applying a command to an
+//! object, and reports the number of heap allocations per applied command:
+//!
+//! ```sh
+//! cargo bench -p restate-partition-store --bench apply_allocations
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bytes::Bytes;
+use criterion::{Criterion, criterion_group, criterion_main};
+use tokio::runtime::Builder;
+
+use restate_core::TaskCenterBuilder;
+use restate_partition_store::{PartitionStore, PartitionStoreManager};
+use restate_rocksdb::RocksDbManager;
+use restate_storage_api::Transaction;
+use restate_storage_api::inbox_table::ReadInboxTable;
+use restate_storage_api::state_table::{ReadStateTable, WriteStateTable};
+use restate_types::identifiers::{PartitionId, PartitionKey, ServiceId};
+use restate_types::partitions::Partition;
+
+/// Counts the allocations done by Rust code. Allocations done by RocksDB itself are not counted.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const OBJECTS: usize = 100;
+const COMMANDS: usize = 10_000;
+
+fn service_id(object: usize) -> ServiceId {
+    ServiceId::new("Counter", format!("object-{object}"))
+}
+
+/// Mimics the storage accesses of applying a command which reads and updates the state of an
+/// object, then checks whether the object has pending invocations in its inbox.
+async fn apply_commands(mut partition_store: PartitionStore, commands: usize) {
+    let value = Bytes::from_static(&[0u8; 64]);
+    for command in 0..commands {
+        let service_id = service_id(command % OBJECTS);
+        let mut txn = partition_store.transaction();
+        let current = txn.get_user_state(&service_id, "counter").await.unwrap();
+        txn.put_user_state(
+            &service_id,
+            "counter",
+            current.unwrap_or_else(|| value.clone()),
+            None,
+        )
+        .unwrap();
+        txn.delete_user_state(&service_id, "scratch").unwrap();
+        assert!(txn.peek_inbox(&service_id).await.unwrap().is_none());
+        txn.commit().await.unwrap();
+    }
+}
+
+fn apply_allocations_benchmark(c: &mut Criterion) {
+    let rt = Builder::new_multi_thread().enable_all().build().unwrap();
+
+    let tc = TaskCenterBuilder::default()
+        .default_runtime_handle(rt.handle().clone())
+        .build()
+        .expect("task_center builds")
+        .into_handle();
+
+    tc.block_on(async { RocksDbManager::init() });
+    let partition_store = tc.block_on(async {
+        let manager = PartitionStoreManager::create()
+            .await
+            .expect("DB creation succeeds");
+        manager
+            .open(
+                &Partition::new(PartitionId::MIN, RangeInclusive::new(0, PartitionKey::MAX)),
+                None,
+            )
+            .await
+            .expect("column family is open")
+    });
+
+    // warm up the thread-local buffers and the block cache before counting
+    rt.block_on(apply_commands(partition_store.clone(), OBJECTS));
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    rt.block_on(apply_commands(partition_store.clone(), COMMANDS));
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "apply path: {:.1} allocations per command",
+        allocations as f64 / COMMANDS as f64
+    );
+
+    let mut group = c.benchmark_group("ApplyPath");
+    group
+        .sample_size(10)
+        .bench_function("apply-commands", |bencher| {
+            bencher
+                .to_async(&rt)
+                .iter(|| apply_commands(partition_store.clone(), OBJECTS));
+        });
+
+    group.finish();
+    rt.block_on(tc.shutdown_node("completed", 0));
+    rt.block_on(RocksDbManager::get().shutdown());
+}
+
+criterion_group!(benches, apply_allocations_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,196 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Benchmarks of the state and journal lookups.
+//!
+//! The table key prefixes can be changed with the env variable `TABLE_KEY_PREFIXES`, either
+//! `partition-key` (default) or `table`, to compare the prefix bloom filters of the two layouts:
+//!
+//! ```sh
+//! TABLE_KEY_PREFIXES=table cargo bench -p restate-partition-store --bench table_lookups
+//! ```
+
+use std::ops::RangeInclusive;
+
+use bytes::Bytes;
+use criterion::{Criterion, criterion_group, criterion_main};
+use futures::{StreamExt, TryStreamExt};
+use tokio::runtime::Builder;
+
+use restate_core::TaskCenterBuilder;
+use restate_partition_store::{PartitionStore, PartitionStoreManager};
+use restate_rocksdb::RocksDbManager;
+use restate_storage_api::Transaction;
+use restate_storage_api::journal_table_v2::{ReadJournalTable, WriteJournalTable};
+use restate_storage_api::state_table::{ReadStateTable, WriteStateTable};
+use restate_types::config::{
+    Configuration, InvocationKeyPrefix, ServiceKeyPrefix, TableKeyPrefixOptions, set_current_config,
+};
+use restate_types::identifiers::{InvocationId, PartitionId, PartitionKey, ServiceId};
+use restate_types::journal_v2::CommandType;
+use restate_types::journal_v2::raw::RawCommand;
+use restate_types::partitions::Partition;
+use restate_types::storage::{StoredRawEntry, StoredRawEntryHeader};
+use restate_types::time::MillisSinceEpoch;
+
+const SERVICES: usize = 10;
+const OBJECTS_PER_SERVICE: usize = 1_000;
+const STATE_ENTRIES_PER_OBJECT: usize = 5;
+const INVOCATIONS: usize = 10_000;
+const JOURNAL_LENGTH: u32 = 10;
+
+fn table_key_prefixes() -> TableKeyPrefixOptions {
+    match std::env::var("TABLE_KEY_PREFIXES").as_deref() {
+        Ok("table") => TableKeyPrefixOptions {
+            state: ServiceKeyPrefix::Service,
+            promise: ServiceKeyPrefix::Service,
+            journal: InvocationKeyPrefix::Invocation,
+        },
+        _ => TableKeyPrefixOptions::default(),
+    }
+}
+
+fn service_id(service: usize, object: usize) -> ServiceId {
+    ServiceId::new(format!("Service-{service}"), format!("object-{object}"))
+}
+
+fn invocation_id(invocation: usize) -> InvocationId {
+    InvocationId::from_parts(
+        (invocation as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+        (invocation as u128).into(),
+    )
+}
+
+async fn populate(mut partition_store: PartitionStore) {
+    let mut txn = partition_store.transaction();
+    for service in 0..SERVICES {
+        for object in 0..OBJECTS_PER_SERVICE {
+            let service_id = service_id(service, object);
+            for entry in 0..STATE_ENTRIES_PER_OBJECT {
+                txn.put_user_state(
+                    &service_id,
+                    format!("key-{entry}"),
+                    Bytes::from_static(&[0u8; 64]),
+                    None,
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    let entry = StoredRawEntry::new(
+        StoredRawEntryHeader::new(MillisSinceEpoch::now()),
+        RawCommand::new(CommandType::Run, vec![0u8; 128]),
+    );
+    for invocation in 0..INVOCATIONS {
+        let invocation_id = invocation_id(invocation);
+        for index in 0..JOURNAL_LENGTH {
+            txn.put_journal_entry(invocation_id, index, &entry, &[])
+                .unwrap();
+        }
+    }
+    txn.commit().await.unwrap();
+
+    // Make sure the lookups hit the data files, and the bloom filters
+    partition_store
+        .partition_db()
+        .flush_memtables(true)
+        .await
+        .unwrap();
+}
+
+async fn state_point_lookups(mut partition_store: PartitionStore) {
+    for object in (0..OBJECTS_PER_SERVICE).step_by(10) {
+        let value = partition_store
+            .get_user_state(&service_id(object % SERVICES, object), "key-0")
+            .await
+            .unwrap();
+        assert!(value.is_some());
+    }
+}
+
+async fn state_prefix_scans(mut partition_store: PartitionStore) {
+    for object in (0..OBJECTS_PER_SERVICE).step_by(10) {
+        let entries = partition_store
+            .get_all_user_states_for_service(&service_id(object % SERVICES, object))
+            .unwrap()
+            .count()
+            .await;
+        assert_eq!(entries, STATE_ENTRIES_PER_OBJECT);
+    }
+}
+
+async fn journal_reads(mut partition_store: PartitionStore) {
+    for invocation in (0..INVOCATIONS).step_by(100) {
+        let entries: Vec<_> = partition_store
+            .get_journal(invocation_id(invocation), JOURNAL_LENGTH)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), JOURNAL_LENGTH as usize);
+    }
+}
+
+fn table_lookups_benchmark(c: &mut Criterion) {
+    let rt = Builder::new_multi_thread().enable_all().build().unwrap();
+
+    let tc = TaskCenterBuilder::default()
+        .default_runtime_handle(rt.handle().clone())
+        .build()
+        .expect("task_center builds")
+        .into_handle();
+
+    let mut config = Configuration::default();
+    config.worker.storage.table_key_prefixes = table_key_prefixes();
+    set_current_config(config);
+
+    tc.block_on(async { RocksDbManager::init() });
+    let partition_store = tc.block_on(async {
+        let manager = PartitionStoreManager::create()
+            .await
+            .expect("DB creation succeeds");
+        let partition_store = manager
+            .open(
+                &Partition::new(PartitionId::MIN, RangeInclusive::new(0, PartitionKey::MAX)),
+                None,
+            )
+            .await
+            .expect("column family is open");
+        populate(partition_store.clone()).await;
+        partition_store
+    });
+
+    let mut group = c.benchmark_group("TableLookups");
+    group
+        .sample_size(10)
+        .bench_function("state-point-lookups", |bencher| {
+            bencher
+                .to_async(&rt)
+                .iter(|| state_point_lookups(partition_store.clone()));
+        })
+        .bench_function("state-prefix-scans", |bencher| {
+            bencher
+                .to_async(&rt)
+                .iter(|| state_prefix_scans(partition_store.clone()));
+        })
+        .bench_function("journal-reads", |bencher| {
+            bencher
+                .to_async(&rt)
+                .iter(|| journal_reads(partition_store.clone()));
+        });
+
+    group.finish();
+    rt.block_on(tc.shutdown_node("completed", 0));
+    rt.block_on(RocksDbManager::get().shutdown());
+}
+
+criterion_group!(benches, table_lookups_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,139 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_rocksdb::RocksDbPerfGuard;
+use restate_storage_api::completion_event_table::{
+    CompletionEvent, CompletionEventSequence, ReadCompletionEventTable, WriteCompletionEventTable,
+};
+use restate_storage_api::{Result, StorageError};
+use restate_types::identifiers::PartitionId;
+use restate_types::logs::{Lsn, SequenceNumber};
+use restate_types::storage::StorageCodec;
+
+use crate::TableKind::PartitionStateMachine;
+use crate::keys::{KeyKind, TableKey, define_table_key};
+use crate::{
+    PaddedPartitionId, PartitionStore, PartitionStoreTransaction, StorageAccess, TableScan,
+    TableScanIterationDecision,
+};
+
+define_table_key!(
+    PartitionStateMachine,
+    KeyKind::CompletionEvent,
+    CompletionEventKey(partition_id: PaddedPartitionId, lsn: u64, index: u32)
+);
+
+#[inline]
+fn create_key(partition_id: PartitionId, sequence: CompletionEventSequence) -> CompletionEventKey {
+    CompletionEventKey {
+        partition_id: partition_id.into(),
+        lsn: sequence.lsn.as_u64(),
+        index: sequence.index,
+    }
+}
+
+fn get_completion_events<S: StorageAccess>(
+    storage: &mut S,
+    partition_id: PartitionId,
+    after: Option<CompletionEventSequence>,
+    limit: usize,
+) -> Result<Vec<(CompletionEventSequence, CompletionEvent)>> {
+    let _x = RocksDbPerfGuard::new("get-completion-events");
+    let start = create_key(
+        partition_id,
+        after.unwrap_or(CompletionEventSequence::new(Lsn::INVALID, 0)),
+    );
+    let end = create_key(
+        partition_id,
+        CompletionEventSequence::new(Lsn::MAX, u32::MAX),
+    );
+
+    let mut remaining = limit;
+    storage
+        .for_each_key_value_in_place(
+            TableScan::KeyRangeInclusiveInSinglePartition(partition_id, start, end),
+            move |mut k, mut v| {
+                let key = match CompletionEventKey::deserialize_from(&mut k) {
+                    Ok(key) => key,
+                    Err(err) => return TableScanIterationDecision::Emit(Err(err)),
+                };
+                let sequence = CompletionEventSequence::new(Lsn::new(key.lsn), key.index);
+                // The range starts at the given sequence, which is excluded
+                if after.is_some_and(|after| sequence <= after) {
+                    return TableScanIterationDecision::Continue;
+                }
+                if remaining == 0 {
+                    return TableScanIterationDecision::Break;
+                }
+                remaining -= 1;
+                TableScanIterationDecision::Emit(
+                    StorageCodec::decode::<CompletionEvent, _>(&mut v)
+                        .map(|event| (sequence, event))
+                        .map_err(|err| StorageError::Generic(err.into())),
+                )
+            },
+        )?
+        .into_iter()
+        .collect()
+}
+
+fn truncate_completion_events<S: StorageAccess>(
+    storage: &mut S,
+    partition_id: PartitionId,
+    up_to: CompletionEventSequence,
+) -> Result<()> {
+    let _x = RocksDbPerfGuard::new("truncate-completion-events");
+    let start = create_key(partition_id, CompletionEventSequence::new(Lsn::INVALID, 0));
+    let end = create_key(partition_id, up_to);
+
+    let keys = storage.for_each_key_value_in_place(
+        TableScan::KeyRangeInclusiveInSinglePartition(partition_id, start, end),
+        |mut k, _| TableScanIterationDecision::Emit(CompletionEventKey::deserialize_from(&mut k)),
+    )?;
+    for key in keys {
+        storage.delete_key(&key?)?;
+    }
+    Ok(())
+}
+
+impl ReadCompletionEventTable for PartitionStore {
+    async fn get_completion_events(
+        &mut self,
+        after: Option<CompletionEventSequence>,
+        limit: usize,
+    ) -> Result<Vec<(CompletionEventSequence, CompletionEvent)>> {
+        get_completion_events(self, self.partition_id(), after, limit)
+    }
+}
+
+impl ReadCompletionEventTable for PartitionStoreTransaction<'_> {
+    async fn get_completion_events(
+        &mut self,
+        after: Option<CompletionEventSequence>,
+        limit: usize,
+    ) -> Result<Vec<(CompletionEventSequence, CompletionEvent)>> {
+        get_completion_events(self, self.partition_id(), after, limit)
+    }
+}
+
+impl WriteCompletionEventTable for PartitionStoreTransaction<'_> {
+    fn put_completion_event(
+        &mut self,
+        sequence: CompletionEventSequence,
+        event: &CompletionEvent,
+    ) -> Result<()> {
+        let key = create_key(self.partition_id(), sequence);
+        self.put_kv_storage_codec(key, event)
+    }
+
+    fn truncate_completion_events(&mut self, up_to: CompletionEventSequence) -> Result<()> {
+        truncate_completion_events(self, self.partition_id(), up_to)
+    }
+}
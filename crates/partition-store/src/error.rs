@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use std::ops::ControlFlow;
+use std::path::PathBuf;
 
 use codederror::CodedError;
 
@@ -49,6 +50,18 @@ pub enum BuildError {
     #[error("snapshot repository configuration error: {0:#}")]
     #[code(unknown)]
     Snapshots(anyhow::Error),
+    #[error("invalid data path '{}': {source}", path.display())]
+    #[code(unknown)]
+    InvalidDataPath {
+        path: PathBuf,
+        source: rocksdb::Error,
+    },
+    #[error("cold data path '{}' is not writable: {source}", path.display())]
+    #[code(unknown)]
+    ColdDataPath {
+        path: PathBuf,
+        source: std::io::Error,
+    },
     #[error(transparent)]
     #[code(unknown)]
     Other(#[from] rocksdb::Error),
@@ -17,7 +17,9 @@ use restate_types::SemanticRestateVersion;
 use restate_types::identifiers::PartitionId;
 use restate_types::logs::Lsn;
 use restate_types::message::MessageIndex;
+use restate_types::partitions::settings::PartitionSettings;
 use restate_types::schema::Schema;
+use restate_types::time::MillisSinceEpoch;
 
 use crate::TableKind::PartitionStateMachine;
 use crate::keys::{KeyKind, define_table_key};
@@ -53,6 +55,10 @@ pub(crate) mod fsm_variable {
     pub(crate) const STORAGE_VERSION: u64 = 5;
 
     pub(crate) const SERVICES_SCHEMA_METADATA: u64 = 6;
+
+    pub(crate) const PARTITION_SETTINGS: u64 = 7;
+
+    pub(crate) const STATE_CLOCK: u64 = 8;
 }
 
 fn get<T: PartitionStoreProtobufValue, S: StorageAccess>(
@@ -158,6 +164,17 @@ impl ReadFsmTable for PartitionStore {
         let key = create_key(self.partition_id(), fsm_variable::SERVICES_SCHEMA_METADATA);
         self.get_value_storage_codec(key)
     }
+
+    async fn get_partition_settings(&mut self) -> Result<PartitionSettings> {
+        let key = create_key(self.partition_id(), fsm_variable::PARTITION_SETTINGS);
+        self.get_value_storage_codec(key)
+            .map(|opt| opt.unwrap_or_default())
+    }
+
+    async fn get_state_clock(&mut self) -> Result<MillisSinceEpoch> {
+        get::<SequenceNumber, _>(self, self.partition_id(), fsm_variable::STATE_CLOCK)
+            .map(|opt| opt.map_or(MillisSinceEpoch::UNIX_EPOCH, |s| MillisSinceEpoch::new(s.0)))
+    }
 }
 
 impl WriteFsmTable for PartitionStoreTransaction<'_> {
@@ -210,4 +227,18 @@ impl WriteFsmTable for PartitionStoreTransaction<'_> {
         let key = create_key(self.partition_id(), fsm_variable::SERVICES_SCHEMA_METADATA);
         self.put_kv_storage_codec(key, schema)
     }
+
+    fn put_partition_settings(&mut self, settings: &PartitionSettings) -> Result<()> {
+        let key = create_key(self.partition_id(), fsm_variable::PARTITION_SETTINGS);
+        self.put_kv_storage_codec(key, settings)
+    }
+
+    fn put_state_clock(&mut self, now: MillisSinceEpoch) -> Result<()> {
+        put(
+            self,
+            self.partition_id(),
+            fsm_variable::STATE_CLOCK,
+            &SequenceNumber::from(now.as_u64()),
+        )
+    }
 }
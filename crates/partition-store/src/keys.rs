@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::cell::RefCell;
 use std::mem;
 
 use anyhow::anyhow;
@@ -42,6 +43,10 @@ pub enum KeyKind {
     State,
     Timers,
     Promise,
+    ExpiringState,
+    StateSize,
+    ParkedCommand,
+    CompletionEvent,
 }
 
 impl KeyKind {
@@ -86,6 +91,10 @@ impl KeyKind {
             KeyKind::State => b"st",
             KeyKind::Timers => b"ti",
             KeyKind::Promise => b"pr",
+            KeyKind::ExpiringState => b"sT",
+            KeyKind::StateSize => b"sz",
+            KeyKind::ParkedCommand => b"pc",
+            KeyKind::CompletionEvent => b"ce",
         }
     }
 
@@ -115,6 +124,10 @@ impl KeyKind {
             b"st" => Some(KeyKind::State),
             b"ti" => Some(KeyKind::Timers),
             b"pr" => Some(KeyKind::Promise),
+            b"sT" => Some(KeyKind::ExpiringState),
+            b"sz" => Some(KeyKind::StateSize),
+            b"pc" => Some(KeyKind::ParkedCommand),
+            b"ce" => Some(KeyKind::CompletionEvent),
             _ => None,
         }
     }
@@ -145,6 +158,18 @@ pub trait TableKey: Sized + std::fmt::Debug + Send + 'static {
     fn serialized_length(&self) -> usize;
 }
 
+/// Size of the chunks of the thread-local arena used by [`TableKeyPrefix::serialize`].
+///
+/// Serialized keys are mostly short-lived scan prefixes and bounds, so instead of allocating a
+/// `BytesMut` per key, we carve them out of a shared chunk that is reused once all the keys
+/// pointing into it have been dropped. Holding on to a serialized key keeps the whole chunk
+/// alive, hence the chunk is kept small.
+const KEY_BUFFER_POOL_CHUNK_SIZE: usize = 4 * 1024;
+
+thread_local! {
+    static KEY_BUFFER_POOL: RefCell<BytesMut> = const { RefCell::new(BytesMut::new()) };
+}
+
 pub trait TableKeyPrefix: Sized + std::fmt::Debug + Send + 'static {
     const TABLE: TableKind;
     const KEY_KIND: KeyKind;
@@ -152,10 +177,19 @@ pub trait TableKeyPrefix: Sized + std::fmt::Debug + Send + 'static {
     fn serialize_to<B: BufMut>(&self, bytes: &mut B);
     fn serialize_key_kind<B: bytes::BufMut>(bytes: &mut B);
 
+    /// Serializes the key into a buffer carved out of a thread-local arena, see
+    /// [`KEY_BUFFER_POOL_CHUNK_SIZE`].
     fn serialize(&self) -> BytesMut {
-        let mut buf = BytesMut::with_capacity(self.serialized_length());
-        self.serialize_to(&mut buf);
-        buf
+        let serialized_length = self.serialized_length();
+        KEY_BUFFER_POOL.with_borrow_mut(|pool| {
+            if pool.capacity() < serialized_length {
+                // Reclaims the current chunk in place if all the keys carved out of it have
+                // been dropped already, otherwise allocates a new chunk.
+                pool.reserve(serialized_length.max(KEY_BUFFER_POOL_CHUNK_SIZE));
+            }
+            self.serialize_to(pool);
+            pool.split()
+        })
     }
 
     fn serialized_length(&self) -> usize;
@@ -584,6 +618,10 @@ impl KeyCodec for TimerKeyKind {
                 target.put_u8(3);
                 invocation_uuid.encode(target);
             }
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => {
+                target.put_u8(4);
+                invocation_uuid.encode(target);
+            }
         }
     }
 
@@ -615,6 +653,10 @@ impl KeyCodec for TimerKeyKind {
                 let invocation_uuid = InvocationUuid::decode(source)?;
                 TimerKeyKind::NeoInvoke { invocation_uuid }
             }
+            4 => {
+                let invocation_uuid = InvocationUuid::decode(source)?;
+                TimerKeyKind::InvocationDeadline { invocation_uuid }
+            }
             i => {
                 return Err(StorageError::Generic(anyhow!(
                     "Unknown discriminator for TimerKind: '{}'",
@@ -642,6 +684,9 @@ impl KeyCodec for TimerKeyKind {
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => {
                 KeyCodec::serialized_length(invocation_uuid)
             }
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => {
+                KeyCodec::serialized_length(invocation_uuid)
+            }
         }
     }
 }
@@ -783,6 +828,31 @@ mod tests {
 
     define_table_key!(TableKind::Deduplication, KeyKind::Deduplication, DeduplicationTestKey(value: u64));
 
+    define_table_key!(TableKind::State, KeyKind::State, PooledTestKey(name: ByteString, payload: Bytes));
+
+    #[test]
+    fn pooled_serialization_does_not_alias() {
+        let mut first = DeduplicationTestKey { value: 1 }.serialize();
+        let second = DeduplicationTestKey { value: 2 }.serialize();
+        // growing the first key must not overwrite the second one
+        first.put_u8(0xff);
+
+        assert_eq!(&first[..], b"de\0\0\0\0\0\0\0\x01\xff");
+        assert_eq!(&second[..], b"de\0\0\0\0\0\0\0\x02");
+
+        // keys larger than the pool chunk size are supported as well
+        let key = PooledTestKey {
+            name: ByteString::from_static("svc"),
+            payload: Bytes::from(vec![7u8; 2 * KEY_BUFFER_POOL_CHUNK_SIZE]),
+        };
+        let serialized = key.serialize();
+        assert_eq!(serialized.len(), TableKey::serialized_length(&key));
+        assert_eq!(
+            PooledTestKey::deserialize_from(&mut serialized.freeze()).unwrap(),
+            key
+        );
+    }
+
     #[test]
     fn key_prefix_mismatch() {
         let mut buffer = DeduplicationTestKey { value: 42 }.serialize();
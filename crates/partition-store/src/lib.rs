@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub mod completion_event_table;
 pub mod deduplication_table;
 mod durable_lsn_tracking;
 pub mod error;
@@ -23,9 +24,11 @@ mod memory;
 mod migrations;
 pub mod outbox_table;
 mod owned_iter;
+pub mod parked_command_table;
 mod partition_db;
 mod partition_store;
 mod partition_store_manager;
+mod prefix_extractor;
 pub mod promise_table;
 pub mod scan;
 pub mod service_status_table;
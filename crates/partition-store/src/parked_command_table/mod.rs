@@ -0,0 +1,105 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_rocksdb::RocksDbPerfGuard;
+use restate_storage_api::parked_command_table::{
+    ParkedCommand, ReadParkedCommandTable, WriteParkedCommandTable,
+};
+use restate_storage_api::{Result, StorageError};
+use restate_types::identifiers::PartitionId;
+use restate_types::logs::Lsn;
+use restate_types::storage::StorageCodec;
+
+use crate::TableKind::PartitionStateMachine;
+use crate::keys::{KeyKind, define_table_key};
+use crate::{
+    PaddedPartitionId, PartitionStore, PartitionStoreTransaction, StorageAccess, TableScan,
+    TableScanIterationDecision,
+};
+
+define_table_key!(
+    PartitionStateMachine,
+    KeyKind::ParkedCommand,
+    ParkedCommandKey(partition_id: PaddedPartitionId, lsn: u64)
+);
+
+#[inline]
+fn create_key(partition_id: PartitionId, lsn: Lsn) -> ParkedCommandKey {
+    ParkedCommandKey {
+        partition_id: partition_id.into(),
+        lsn: lsn.as_u64(),
+    }
+}
+
+fn get_parked_command<S: StorageAccess>(
+    storage: &mut S,
+    partition_id: PartitionId,
+    lsn: Lsn,
+) -> Result<Option<ParkedCommand>> {
+    let _x = RocksDbPerfGuard::new("get-parked-command");
+    storage.get_value_storage_codec(create_key(partition_id, lsn))
+}
+
+fn get_parked_commands<S: StorageAccess>(
+    storage: &mut S,
+    partition_id: PartitionId,
+    limit: usize,
+) -> Result<Vec<ParkedCommand>> {
+    let _x = RocksDbPerfGuard::new("get-parked-commands");
+    let mut remaining = limit;
+    storage
+        .for_each_key_value_in_place(
+            TableScan::<ParkedCommandKey>::SinglePartition(partition_id),
+            move |_, mut v| {
+                if remaining == 0 {
+                    return TableScanIterationDecision::Break;
+                }
+                remaining -= 1;
+                TableScanIterationDecision::Emit(
+                    StorageCodec::decode::<ParkedCommand, _>(&mut v)
+                        .map_err(|err| StorageError::Generic(err.into())),
+                )
+            },
+        )?
+        .into_iter()
+        .collect()
+}
+
+impl ReadParkedCommandTable for PartitionStore {
+    async fn get_parked_command(&mut self, lsn: Lsn) -> Result<Option<ParkedCommand>> {
+        get_parked_command(self, self.partition_id(), lsn)
+    }
+
+    async fn get_parked_commands(&mut self, limit: usize) -> Result<Vec<ParkedCommand>> {
+        get_parked_commands(self, self.partition_id(), limit)
+    }
+}
+
+impl ReadParkedCommandTable for PartitionStoreTransaction<'_> {
+    async fn get_parked_command(&mut self, lsn: Lsn) -> Result<Option<ParkedCommand>> {
+        get_parked_command(self, self.partition_id(), lsn)
+    }
+
+    async fn get_parked_commands(&mut self, limit: usize) -> Result<Vec<ParkedCommand>> {
+        get_parked_commands(self, self.partition_id(), limit)
+    }
+}
+
+impl WriteParkedCommandTable for PartitionStoreTransaction<'_> {
+    fn put_parked_command(&mut self, parked_command: &ParkedCommand) -> Result<()> {
+        let key = create_key(self.partition_id(), parked_command.lsn);
+        self.put_kv_storage_codec(key, parked_command)
+    }
+
+    fn delete_parked_command(&mut self, lsn: Lsn) -> Result<()> {
+        let key = create_key(self.partition_id(), lsn);
+        self.delete_key(&key)
+    }
+}
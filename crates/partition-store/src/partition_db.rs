@@ -10,7 +10,8 @@
 
 use std::marker::PhantomData;
 use std::ops::DerefMut;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 use parking_lot::RwLock;
@@ -39,6 +40,7 @@ pub struct PartitionDb {
     meta: Arc<Partition>,
     durable_lsn: watch::Sender<Option<Lsn>>,
     archived_lsn: watch::Sender<Option<Lsn>>,
+    state_clock: Arc<AtomicU64>,
     // Note: Rust will drop the fields in the order they are declared in the struct.
     // It's crucial to keep the column family and the database in this exact order.
     cf: PartitionBoundCfHandle,
@@ -49,6 +51,7 @@ impl PartitionDb {
     pub(crate) fn new(
         meta: Arc<Partition>,
         archived_lsn: watch::Sender<Option<Lsn>>,
+        state_clock: Arc<AtomicU64>,
         rocksdb: Arc<RocksDb>,
         cf: Arc<BoundColumnFamily<'_>>,
     ) -> Self {
@@ -56,6 +59,7 @@ impl PartitionDb {
             meta,
             durable_lsn: watch::Sender::new(None),
             archived_lsn,
+            state_clock,
             // SAFETY: the new BoundColumnFamily here just expanding lifetime to static,
             // it's safe to use here as long as rocksdb is dropped last.
             cf: unsafe { PartitionBoundCfHandle::new(cf) },
@@ -71,6 +75,13 @@ impl PartitionDb {
         &self.rocksdb
     }
 
+    /// Notes the state clock of the last committed transaction, see
+    /// [`restate_storage_api::state_table::WriteStateTable::set_state_clock`]. The compaction
+    /// filter drops the state entries expired according to it.
+    pub(crate) fn note_state_clock(&self, now: u64) {
+        self.state_clock.fetch_max(now, Ordering::Relaxed);
+    }
+
     #[cfg(test)]
     pub fn into_rocksdb(self) -> Arc<RocksDb> {
         self.rocksdb
@@ -173,6 +184,8 @@ pub(crate) struct PartitionCell {
     meta: Arc<Partition>,
     archived_lsn: watch::Sender<Option<Lsn>>,
     durable_lsn: RwLock<Option<watch::Sender<Option<Lsn>>>>,
+    /// Committed state clock of the partition, zero until the first transaction setting it
+    state_clock: Arc<AtomicU64>,
     pub(crate) inner: AsyncRwLock<State>,
 }
 
@@ -182,6 +195,7 @@ impl PartitionCell {
             meta: Arc::new(partition),
             archived_lsn: Default::default(),
             durable_lsn: Default::default(),
+            state_clock: Default::default(),
             inner: AsyncRwLock::new(State::Unknown),
         }
     }
@@ -190,6 +204,10 @@ impl PartitionCell {
         self.meta.cf_name()
     }
 
+    pub(crate) fn state_clock(&self) -> &Arc<AtomicU64> {
+        &self.state_clock
+    }
+
     fn open_local_cf(&self, guard: &mut tokio::sync::RwLockWriteGuard<'_, State>, db: PartitionDb) {
         let mut durable_lsn_guard = self.durable_lsn.write();
         *durable_lsn_guard = Some(db.durable_lsn_sender().clone());
@@ -215,6 +233,7 @@ impl PartitionCell {
                 let db = PartitionDb::new(
                     self.meta.clone(),
                     self.archived_lsn.clone(),
+                    self.state_clock.clone(),
                     rocksdb.clone(),
                     handle,
                 );
@@ -245,6 +264,7 @@ impl PartitionCell {
         let db = PartitionDb::new(
             self.meta.clone(),
             self.archived_lsn.clone(),
+            self.state_clock.clone(),
             rocksdb.clone(),
             handle,
         );
@@ -301,6 +321,7 @@ impl PartitionCell {
         let db = PartitionDb::new(
             self.meta.clone(),
             self.archived_lsn.clone(),
+            self.state_clock.clone(),
             rocksdb.clone(),
             rocksdb
                 .inner()
@@ -476,7 +497,7 @@ pub struct AllDataCf;
 impl DbConfigurator for RocksConfigurator<AllDataCf> {
     fn get_db_options(
         &self,
-        _db_name: &str,
+        db_name: &str,
         env: &rocksdb::Env,
         write_buffer_manager: &rocksdb::WriteBufferManager,
     ) -> rocksdb::Options {
@@ -486,10 +507,21 @@ impl DbConfigurator for RocksConfigurator<AllDataCf> {
             write_buffer_manager,
         );
 
-        self.apply_db_opts_from_config(
-            &mut db_options,
-            &Configuration::pinned().worker.storage.rocksdb,
-        );
+        let storage_options = &Configuration::pinned().worker.storage;
+        self.apply_db_opts_from_config(&mut db_options, &storage_options.rocksdb);
+
+        // Newer data files stay in the first paths, compactions move the older ones to the next
+        let data_paths: Vec<_> = storage_options
+            .data_paths(db_name)
+            .into_iter()
+            .map(|(path, target_size)| {
+                rocksdb::DBPath::new(path, target_size)
+                    .expect("data paths are checked when creating the partition store manager")
+            })
+            .collect();
+        if !data_paths.is_empty() {
+            db_options.set_db_paths(&data_paths);
+        }
 
         let event_listener = DurableLsnEventListener::new(&self.shared_state);
         db_options.add_event_listener(event_listener);
@@ -509,17 +541,17 @@ impl CfConfigurator for RocksConfigurator<AllDataCf> {
         let mut cf_options =
             restate_rocksdb::configuration::create_default_cf_options(Some(write_buffer_manager));
 
-        let block_options = restate_rocksdb::configuration::create_default_block_options(
-            &Configuration::pinned().worker.storage.rocksdb,
+        let storage_options = &Configuration::pinned().worker.storage;
+        let mut block_options = restate_rocksdb::configuration::create_default_block_options(
+            &storage_options.rocksdb,
             // use global block cache
             Some(global_cache),
         );
+        block_options.set_bloom_filter(storage_options.rocksdb_bloom_filter_bits_per_key(), true);
         cf_options.set_block_based_table_factory(&block_options);
 
-        // Actually, we would love to use CappedPrefixExtractor but unfortunately it's neither exposed
-        // in the C API nor the rust binding. That's okay and we can change it later.
-        cf_options.set_prefix_extractor(rocksdb::SliceTransform::create_fixed_prefix(
-            crate::DB_PREFIX_LENGTH,
+        cf_options.set_prefix_extractor(crate::prefix_extractor::create_prefix_extractor(
+            &storage_options.table_key_prefixes,
         ));
         cf_options.set_memtable_prefix_bloom_ratio(0.2);
         cf_options.set_memtable_whole_key_filtering(true);
@@ -537,6 +569,33 @@ impl CfConfigurator for RocksConfigurator<AllDataCf> {
             DBCompressionType::Zstd,
         ]);
 
+        // Drops the user state entries whose ttl expired according to the state clock of the
+        // partition, which is known only once the partition is open: the column families of the
+        // existing partitions are configured when opening the database.
+        let shared_state = Arc::downgrade(&self.shared_state);
+        let cf_name = cf_name.to_owned();
+        let state_clock = OnceLock::new();
+        cf_options.set_compaction_filter(
+            "restate.ExpiredStateFilter",
+            move |_level: u32, key: &[u8], value: &[u8]| {
+                if state_clock.get().is_none()
+                    && let Some(clock) = shared_state
+                        .upgrade()
+                        .and_then(|shared_state| shared_state.state_clock(&cf_name))
+                {
+                    let _ = state_clock.set(clock);
+                }
+                match state_clock.get() {
+                    Some(state_clock) => crate::state_table::drop_expired_state(
+                        state_clock.load(Ordering::Relaxed),
+                        key,
+                        value,
+                    ),
+                    None => rocksdb::compaction_filter::Decision::Keep,
+                }
+            },
+        );
+
         // Always collect applied LSN table properties in partition store CFs
         cf_options.add_table_properties_collector_factory(AppliedLsnCollectorFactory);
 
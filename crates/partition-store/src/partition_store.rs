@@ -33,6 +33,8 @@ use restate_rocksdb::{IoMode, IterAction, Priority, RocksDb, RocksError};
 use restate_storage_api::fsm_table::ReadFsmTable;
 use restate_storage_api::protobuf_types::{PartitionStoreProtobufValue, ProtobufStorageWrapper};
 use restate_storage_api::{IsolationLevel, Storage, StorageError, Transaction};
+#[cfg(feature = "chaos")]
+use restate_types::chaos;
 use restate_types::config::Configuration;
 use restate_types::identifiers::{PartitionId, PartitionKey, SnapshotId, WithPartitionKey};
 use restate_types::logs::Lsn;
@@ -40,6 +42,7 @@ use restate_types::partitions::Partition;
 use restate_types::storage::StorageCodec;
 use restate_types::storage::StorageDecode;
 use restate_types::storage::StorageEncode;
+use restate_types::time::MillisSinceEpoch;
 
 use crate::fsm_table::{get_locally_durable_lsn, get_storage_version, put_storage_version};
 use crate::keys::KeyKind;
@@ -47,6 +50,7 @@ use crate::keys::TableKey;
 use crate::keys::TableKeyPrefix;
 use crate::migrations::{LATEST_VERSION, SchemaVersion};
 use crate::partition_db::PartitionDb;
+use crate::prefix_extractor;
 use crate::scan::PhysicalScan;
 use crate::scan::TableScan;
 use crate::snapshots::LocalPartitionSnapshot;
@@ -144,14 +148,18 @@ pub enum TableKind {
 impl TableKind {
     pub const fn key_kinds(self) -> &'static [KeyKind] {
         match self {
-            Self::State => &[KeyKind::State],
+            Self::State => &[KeyKind::State, KeyKind::ExpiringState, KeyKind::StateSize],
             Self::InvocationStatus => &[KeyKind::InvocationStatusV1, KeyKind::InvocationStatus],
             Self::ServiceStatus => &[KeyKind::ServiceStatus],
             Self::Idempotency => &[KeyKind::Idempotency],
             Self::Inbox => &[KeyKind::Inbox],
             Self::Outbox => &[KeyKind::Outbox],
             Self::Deduplication => &[KeyKind::Deduplication],
-            Self::PartitionStateMachine => &[KeyKind::Fsm],
+            Self::PartitionStateMachine => &[
+                KeyKind::Fsm,
+                KeyKind::ParkedCommand,
+                KeyKind::CompletionEvent,
+            ],
             Self::Timers => &[KeyKind::Timers],
             Self::Journal => &[
                 KeyKind::Journal,
@@ -256,18 +264,15 @@ impl PartitionStore {
 
     fn new_prefix_iterator_opts(&self, _key_kind: KeyKind, prefix: Bytes) -> ReadOptions {
         let mut opts = ReadOptions::default();
-        opts.set_prefix_same_as_start(true);
+        set_prefix_seek_mode(&mut opts, &prefix);
         opts.set_iterate_range(PrefixRange(prefix.clone()));
         opts.set_async_io(true);
-        opts.set_total_order_seek(false);
         opts
     }
 
     fn new_range_iterator_opts(&self, scan_mode: ScanMode, from: Bytes, to: Bytes) -> ReadOptions {
         let mut opts = ReadOptions::default();
-        // todo: use auto_prefix_mode, at the moment, rocksdb doesn't expose this through the C
-        // binding.
-        opts.set_total_order_seek(scan_mode == ScanMode::TotalOrder);
+        set_range_seek_mode(&mut opts, scan_mode, &from, &to);
         opts.set_iterate_range(from..to);
         opts.set_async_io(true);
         opts
@@ -444,7 +449,7 @@ impl PartitionStore {
         }
     }
 
-    pub fn iterator_for_each<K: TableKey>(
+    pub fn iterator_for_each<K: TableKeyPrefix>(
         &self,
         name: &'static str,
         priority: Priority,
@@ -491,7 +496,7 @@ impl PartitionStore {
         Ok(ReceiverStream::new(rx))
     }
 
-    fn run_iterator_internal<K: TableKey>(
+    fn run_iterator_internal<K: TableKeyPrefix>(
         &self,
         name: &'static str,
         priority: Priority,
@@ -613,6 +618,11 @@ impl PartitionStore {
             value_buffer: &mut self.value_buffer,
             meta: self.db.partition(),
             snapshot,
+            track_user_state_size: true,
+            state_clock: None,
+            db: &self.db,
+            #[cfg(any(test, feature = "test-util"))]
+            write_failpoint: WriteFailpoint::default(),
         }
     }
 
@@ -809,6 +819,53 @@ pub struct PartitionStoreTransaction<'a> {
     key_buffer: &'a mut BytesMut,
     value_buffer: &'a mut BytesMut,
     snapshot: Option<SnapshotWithThreadMode<'a, rocksdb::DB>>,
+    /// See [`restate_storage_api::state_table::WriteStateTable::track_user_state_size`].
+    pub(crate) track_user_state_size: bool,
+    /// See [`restate_storage_api::state_table::WriteStateTable::set_state_clock`].
+    pub(crate) state_clock: Option<MillisSinceEpoch>,
+    db: &'a PartitionDb,
+    #[cfg(any(test, feature = "test-util"))]
+    write_failpoint: WriteFailpoint,
+}
+
+/// Fails the writes of a transaction after a number of them succeeded, to simulate a crash while
+/// applying the transaction.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Default)]
+struct WriteFailpoint {
+    remaining_writes: Option<usize>,
+    triggered: bool,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl WriteFailpoint {
+    fn check(&mut self) -> Result<()> {
+        match &mut self.remaining_writes {
+            Some(0) => {
+                self.triggered = true;
+                Err(StorageError::Generic(anyhow!("write failpoint triggered")))
+            }
+            Some(remaining_writes) => {
+                *remaining_writes -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl PartitionStoreTransaction<'_> {
+    /// Makes the writes fail once the given number of writes succeeded. The transaction must not
+    /// be committed after the failpoint triggered, like a crashing process wouldn't commit it.
+    pub fn fail_writes_after(&mut self, num_writes: usize) {
+        self.write_failpoint.remaining_writes = Some(num_writes);
+    }
+
+    /// Whether a write failed because of [`Self::fail_writes_after`].
+    pub fn write_failpoint_triggered(&self) -> bool {
+        self.write_failpoint.triggered
+    }
 }
 
 impl PartitionStoreTransaction<'_> {
@@ -831,8 +888,7 @@ impl PartitionStoreTransaction<'_> {
         let table = self.table_handle(table);
         let mut opts = self.read_options();
         opts.set_iterate_range(PrefixRange(prefix.clone()));
-        opts.set_prefix_same_as_start(true);
-        opts.set_total_order_seek(false);
+        set_prefix_seek_mode(&mut opts, &prefix);
 
         let it = self
             .rocksdb
@@ -854,9 +910,7 @@ impl PartitionStoreTransaction<'_> {
     ) -> Result<DBIterator<'_>> {
         let table = self.table_handle(table);
         let mut opts = self.read_options();
-        // todo: use auto_prefix_mode, at the moment, rocksdb doesn't expose this through the C
-        // binding.
-        opts.set_total_order_seek(scan_mode == ScanMode::TotalOrder);
+        set_range_seek_mode(&mut opts, scan_mode, &from, &to);
         opts.set_iterate_range(from.clone()..to);
 
         let it = self
@@ -898,14 +952,46 @@ fn assert_partition_key_or_err(
     )))
 }
 
+/// Uses a prefix seek when all the keys with the given prefix share the same extracted prefix,
+/// to leverage the prefix bloom filters. Otherwise, it falls back to a total order seek.
+fn set_prefix_seek_mode(opts: &mut ReadOptions, prefix: &[u8]) {
+    let prefix_seek = prefix_extractor::is_prefix_seekable(prefix);
+    opts.set_prefix_same_as_start(prefix_seek);
+    opts.set_total_order_seek(!prefix_seek);
+}
+
+fn set_range_seek_mode(opts: &mut ReadOptions, scan_mode: ScanMode, from: &[u8], to: &[u8]) {
+    // todo: use auto_prefix_mode, at the moment, rocksdb doesn't expose this through the C
+    // binding.
+    opts.set_total_order_seek(
+        scan_mode == ScanMode::TotalOrder || !prefix_extractor::is_range_within_prefix(from, to),
+    );
+}
+
 impl Transaction for PartitionStoreTransaction<'_> {
     async fn commit(self) -> Result<()> {
         // We cannot directly commit the txn because it might fail because of unrelated concurrent
         // writes to RocksDB. However, it is safe to write the WriteBatch for a given partition,
         // because there can only be a single writer (the leading PartitionProcessor).
+        #[cfg(any(test, feature = "test-util"))]
+        assert!(
+            !self.write_failpoint.triggered,
+            "transactions must not be committed after their write failpoint triggered"
+        );
         if self.write_batch_with_index.is_empty() {
             return Ok(());
         }
+        #[cfg(feature = "chaos")]
+        {
+            if let Some(delay) = chaos::storage_write_delay() {
+                tokio::time::sleep(delay).await;
+            }
+            if chaos::should_fail_storage_write() {
+                return Err(StorageError::Generic(anyhow!(
+                    "injected storage write failure"
+                )));
+            }
+        }
         let io_mode = if Configuration::pinned()
             .worker
             .storage
@@ -927,7 +1013,11 @@ impl Transaction for PartitionStoreTransaction<'_> {
                 self.write_batch_with_index,
             )
             .await
-            .map_err(|error| StorageError::Generic(error.into()))
+            .map_err(|error| StorageError::Generic(error.into()))?;
+        if let Some(state_clock) = self.state_clock {
+            self.db.note_state_clock(state_clock.as_u64());
+        }
+        Ok(())
     }
 }
 
@@ -1016,6 +1106,8 @@ impl StorageAccess for PartitionStoreTransaction<'_> {
         key: impl AsRef<[u8]>,
         value: impl AsRef<[u8]>,
     ) -> Result<()> {
+        #[cfg(any(test, feature = "test-util"))]
+        self.write_failpoint.check()?;
         self.write_batch_with_index
             .put_cf(self.data_cf_handle, key, value);
         Ok(())
@@ -1023,6 +1115,8 @@ impl StorageAccess for PartitionStoreTransaction<'_> {
 
     #[inline]
     fn delete_cf(&mut self, _table: TableKind, key: impl AsRef<[u8]>) -> Result<()> {
+        #[cfg(any(test, feature = "test-util"))]
+        self.write_failpoint.check()?;
         self.write_batch_with_index
             .delete_cf(self.data_cf_handle, key);
         Ok(())
@@ -1317,6 +1411,36 @@ mod tests {
         Ok(())
     }
 
+    #[restate_core::test]
+    async fn write_failpoint() -> googletest::Result<()> {
+        let rocksdb = RocksDbManager::init();
+        let partition_store_manager = PartitionStoreManager::create().await?;
+        let mut partition_store = partition_store_manager
+            .open(
+                &Partition::new(PartitionId::MIN, PartitionKey::MIN..=PartitionKey::MAX),
+                None,
+            )
+            .await?;
+        let key_a = "a".to_owned();
+        let key_b = "b".to_owned();
+
+        let mut txn = partition_store.transaction();
+        txn.fail_writes_after(1);
+        txn.put_kv_raw(key_a.clone(), 1_u32.to_be_bytes())?;
+        assert!(!txn.write_failpoint_triggered());
+        assert!(txn.put_kv_raw(key_b.clone(), 1_u32.to_be_bytes()).is_err());
+        assert!(txn.delete_key(&key_a).is_err());
+        assert!(txn.write_failpoint_triggered());
+        drop(txn);
+
+        // the aborted transaction didn't write anything
+        assert_eq!(partition_store.get_kv_raw(key_a, decode_u32)?, None);
+        assert_eq!(partition_store.get_kv_raw(key_b, decode_u32)?, None);
+
+        rocksdb.shutdown().await;
+        Ok(())
+    }
+
     fn decode_u32(_key: &[u8], bytes: Option<&[u8]>) -> Result<Option<u32>, StorageError> {
         if let Some(bytes) = bytes {
             bytes
@@ -8,7 +8,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, Weak};
 
 use ahash::HashMap;
@@ -25,7 +26,7 @@ use restate_types::partitions::Partition;
 
 use crate::SnapshotError;
 use crate::memory::MemoryController;
-use crate::partition_db::{AllDataCf, PartitionCell, PartitionDb, RocksConfigurator};
+use crate::partition_db::{AllDataCf, PartitionCell, PartitionDb, RocksConfigurator, State};
 use crate::snapshots::{LocalPartitionSnapshot, Snapshots};
 use crate::{BuildError, OpenError, PartitionStore, SnapshotErrorKind};
 
@@ -62,6 +63,15 @@ impl SharedState {
         guard.get(&partition_id).cloned()
     }
 
+    /// Gets the state clock of the partition stored in the given column family, if it's known.
+    pub fn state_clock(&self, cf_name: &str) -> Option<Arc<AtomicU64>> {
+        let guard = self.partitions.read();
+        guard
+            .values()
+            .find(|cell| cell.cf_name().as_ref() == cf_name)
+            .map(|cell| Arc::clone(cell.state_clock()))
+    }
+
     /// Used internally to introspect the state of the currently open partitions.
     pub(crate) async fn get_maybe_open_dbs(&self) -> Vec<crate::partition_db::State> {
         let cells: Vec<Arc<PartitionCell>> = self.partitions.read().values().cloned().collect();
@@ -107,18 +117,40 @@ impl SharedState {
             cell.note_durable_lsn(durable_lsn);
         };
     }
+}
 
-    #[cfg(test)]
-    pub async fn drop_partition(
-        &self,
-        partition_id: PartitionId,
-    ) -> Result<(), restate_rocksdb::RocksError> {
-        let Some(cell) = self.partitions.read().get(&partition_id).cloned() else {
-            return Ok(());
+/// Fails early if the data paths can't be used, rather than when opening the partition stores or
+/// when compactions start moving data files to the cold data paths.
+fn check_data_paths() -> Result<(), BuildError> {
+    let storage_options = &Configuration::pinned().worker.storage;
+    // The paths of the databases are sub-directories of these
+    for (path, target_size) in storage_options.data_paths("") {
+        rocksdb::DBPath::new(&path, target_size)
+            .map_err(|source| BuildError::InvalidDataPath { path, source })?;
+    }
+
+    for cold_data_path in &storage_options.cold_data_paths {
+        let path = &cold_data_path.path;
+        let check = || {
+            std::fs::create_dir_all(path)?;
+            let probe = path.join(".restate-write-check");
+            std::fs::write(&probe, b"")?;
+            std::fs::remove_file(&probe)
         };
-        let mut state_guard = cell.inner.write().await;
-        cell.drop_cf(&mut state_guard).await
+        check().map_err(|source| BuildError::ColdDataPath {
+            path: path.clone(),
+            source,
+        })?;
+        info!(
+            "Partition store data files will be moved to the cold data path '{}' with target size {}",
+            path.display(),
+            cold_data_path
+                .target_size
+                .map(|size| size.to_string())
+                .unwrap_or_else(|| "unbounded".to_owned())
+        );
     }
+    Ok(())
 }
 
 pub struct PartitionStoreManager {
@@ -126,10 +158,25 @@ pub struct PartitionStoreManager {
     snapshots: Snapshots,
     db_cache: AsyncMutex<HashMap<restate_rocksdb::DbName, Weak<RocksDb>>>,
     memory_controller: MemoryController,
+    /// Set if the databases are opened as read-only secondary instances
+    secondary_dir: Option<PathBuf>,
 }
 
 impl PartitionStoreManager {
     pub async fn create() -> Result<Arc<Self>, BuildError> {
+        Self::create_inner(None).await
+    }
+
+    /// Opens the partition stores of the configured data directory read-only, as secondary
+    /// instances keeping their own info logs in `secondary_dir`. Useful to inspect a copy of the
+    /// data directory of a node, as partitions which aren't stored locally can't be created.
+    pub async fn create_secondary(secondary_dir: PathBuf) -> Result<Arc<Self>, BuildError> {
+        Self::create_inner(Some(secondary_dir)).await
+    }
+
+    async fn create_inner(secondary_dir: Option<PathBuf>) -> Result<Arc<Self>, BuildError> {
+        check_data_paths()?;
+
         // Start the memory controller, how do we know when db is dropped?
         let state = Arc::new(SharedState::default());
         let memory_controller = MemoryController::start(state.clone())?;
@@ -141,6 +188,7 @@ impl PartitionStoreManager {
                 .map_err(BuildError::Snapshots)?,
             db_cache: Default::default(),
             memory_controller,
+            secondary_dir,
         });
 
         Ok(psm)
@@ -160,17 +208,20 @@ impl PartitionStoreManager {
             Arc::clone(&self.state),
         );
 
-        let db_spec = DbSpecBuilder::new(
+        let mut db_spec = DbSpecBuilder::new(
             db_name.clone(),
             Configuration::pinned().worker.storage.data_dir(&db_name),
             configurator.clone(),
         )
-        .add_cf_pattern(CfPrefixPattern::new(PARTITION_CF_PREFIX), configurator)
-        // This is added as an experiment. We might make this configurable to let users decide
-        // on the trade-off between shutdown time and startup catchup time.
-        .add_to_flush_on_shutdown(CfPrefixPattern::ANY)
-        .build()
-        .expect("valid spec");
+        .add_cf_pattern(CfPrefixPattern::new(PARTITION_CF_PREFIX), configurator);
+        if let Some(secondary_dir) = &self.secondary_dir {
+            db_spec = db_spec.open_as_secondary(secondary_dir.join(db_name.as_str()));
+        } else {
+            // This is added as an experiment. We might make this configurable to let users decide
+            // on the trade-off between shutdown time and startup catchup time.
+            db_spec = db_spec.add_to_flush_on_shutdown(CfPrefixPattern::ANY);
+        }
+        let db_spec = db_spec.build().expect("valid spec");
 
         let db = RocksDbManager::get().open_db(db_spec).await?;
 
@@ -179,6 +230,19 @@ impl PartitionStoreManager {
         Ok(db)
     }
 
+    /// Lists the partitions stored in the given local database.
+    pub fn list_local_partitions(db_name: &str) -> Result<Vec<PartitionId>, RocksError> {
+        let db_path = Configuration::pinned().worker.storage.data_dir(db_name);
+        let cfs = rocksdb::DB::list_cf(&rocksdb::Options::default(), db_path)?;
+
+        let mut partition_ids: Vec<PartitionId> = cfs
+            .iter()
+            .filter_map(|cf| cf.strip_prefix(PARTITION_CF_PREFIX)?.parse().ok())
+            .collect();
+        partition_ids.sort();
+        Ok(partition_ids)
+    }
+
     pub fn is_repository_configured(&self) -> bool {
         self.snapshots.is_repository_configured()
     }
@@ -367,12 +431,28 @@ impl PartitionStoreManager {
             .await
     }
 
-    #[cfg(test)]
-    pub async fn drop_partition(
-        &self,
-        partition_id: PartitionId,
-    ) -> Result<(), restate_rocksdb::RocksError> {
-        self.state.drop_partition(partition_id).await
+    /// Deletes the local data of the given partition, releasing its space, e.g. once the
+    /// partition has been moved away from this node. The partition is restored from the latest
+    /// snapshot if it's opened again later.
+    ///
+    /// Fails with [`RocksError::AlreadyOpen`] if the partition store is in use, the partition
+    /// processor must be stopped first.
+    pub async fn drop_partition(&self, partition: &Partition) -> Result<(), RocksError> {
+        let rocksdb = self.open_rocksdb(partition).await?;
+        let cell = self.state.get_or_default(partition);
+        let mut state_guard = cell.inner.write().await;
+        match &*state_guard {
+            State::Open { .. } => return Err(RocksError::AlreadyOpen),
+            // The column family might exist even if this node didn't open it since it started
+            State::Unknown => cell.open_cf(&mut state_guard, &rocksdb),
+            State::CfMissing | State::Closed { .. } => {}
+        }
+        cell.drop_cf(&mut state_guard).await?;
+        info!(
+            partition_id = %partition.partition_id,
+            "Dropped the local partition store"
+        );
+        Ok(())
     }
 
     #[cfg(test)]
@@ -0,0 +1,225 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Prefix extractor of the partition store column families.
+//!
+//! All the tables share the same column family, hence the same prefix extractor. The extracted
+//! prefix always starts with the key kind and the partition key (see [`DB_PREFIX_LENGTH`]), and
+//! depending on the [`TableKeyPrefixOptions`] it can include the following components of the key
+//! layout, as defined by `define_table_key!`.
+
+use std::sync::OnceLock;
+
+use rocksdb::SliceTransform;
+use tracing::warn;
+
+use restate_types::config::{InvocationKeyPrefix, ServiceKeyPrefix, TableKeyPrefixOptions};
+use restate_types::identifiers::InvocationUuid;
+
+use crate::DB_PREFIX_LENGTH;
+use crate::keys::KeyKind;
+
+// The extractor is a plain function pointer, so the configured layout needs to be global. It's
+// fixed by the first configured column family, and it's valid for the lifetime of the process.
+static TABLE_KEY_PREFIXES: OnceLock<TableKeyPrefixOptions> = OnceLock::new();
+
+fn table_key_prefixes() -> TableKeyPrefixOptions {
+    TABLE_KEY_PREFIXES.get().copied().unwrap_or_default()
+}
+
+/// Creates the prefix extractor for the given table key prefixes.
+pub(crate) fn create_prefix_extractor(options: &TableKeyPrefixOptions) -> SliceTransform {
+    let prefixes = *TABLE_KEY_PREFIXES.get_or_init(|| *options);
+    if prefixes != *options {
+        warn!(
+            "Changing the partition store table key prefixes requires a restart, keep using {:?}",
+            prefixes
+        );
+    }
+
+    if prefixes == TableKeyPrefixOptions::default() {
+        // Actually, we would love to use CappedPrefixExtractor but unfortunately it's neither exposed
+        // in the C API nor the rust binding. That's okay and we can change it later.
+        return SliceTransform::create_fixed_prefix(DB_PREFIX_LENGTH);
+    }
+
+    // The name is persisted in the data files, and rocksdb ignores the prefix filters of files
+    // created by a differently named extractor. Hence, it must change with the layout.
+    let name = format!(
+        "restate.TableKeyPrefix.state={}.promise={}.journal={}",
+        service_prefix_name(prefixes.state),
+        service_prefix_name(prefixes.promise),
+        invocation_prefix_name(prefixes.journal),
+    );
+    SliceTransform::create(&name, transform, Some(in_domain))
+}
+
+/// Returns true if all the keys starting with the given prefix share the same extracted prefix,
+/// meaning that they can be looked up using a prefix seek.
+pub(crate) fn is_prefix_seekable(prefix: &[u8]) -> bool {
+    in_domain(prefix)
+}
+
+/// Returns true if all the keys in the range `from..to` share the same extracted prefix.
+pub(crate) fn is_range_within_prefix(from: &[u8], to: &[u8]) -> bool {
+    prefix_len(from).is_some_and(|len| to.len() >= len && from[..len] == to[..len])
+}
+
+fn transform(key: &[u8]) -> &[u8] {
+    // rocksdb transforms only the keys within the domain
+    &key[..prefix_len(key).unwrap_or(key.len())]
+}
+
+fn in_domain(key: &[u8]) -> bool {
+    prefix_len(key).is_some()
+}
+
+fn prefix_len(key: &[u8]) -> Option<usize> {
+    key_prefix_len(&table_key_prefixes(), key)
+}
+
+/// Length of the prefix of the given key, or `None` if the key is shorter than its prefix.
+fn key_prefix_len(prefixes: &TableKeyPrefixOptions, key: &[u8]) -> Option<usize> {
+    if key.len() < DB_PREFIX_LENGTH {
+        return None;
+    }
+
+    let key_kind = KeyKind::from_bytes(
+        key[..KeyKind::SERIALIZED_LENGTH]
+            .try_into()
+            .expect("key kind must fit"),
+    );
+    let len = match key_kind {
+        Some(KeyKind::State) if prefixes.state == ServiceKeyPrefix::Service => {
+            delimited_end(key, DB_PREFIX_LENGTH)?
+        }
+        Some(KeyKind::Promise) if prefixes.promise == ServiceKeyPrefix::Service => {
+            delimited_end(key, DB_PREFIX_LENGTH)?
+        }
+        Some(
+            KeyKind::Journal
+            | KeyKind::JournalV2
+            | KeyKind::JournalV2CompletionIdToCommandIndex
+            | KeyKind::JournalV2NotificationIdToNotificationIndex,
+        ) if prefixes.journal == InvocationKeyPrefix::Invocation => {
+            DB_PREFIX_LENGTH + InvocationUuid::RAW_BYTES_LEN
+        }
+        _ => DB_PREFIX_LENGTH,
+    };
+
+    (len <= key.len()).then_some(len)
+}
+
+/// Returns the end offset of the length delimited component starting at the given offset.
+fn delimited_end(key: &[u8], offset: usize) -> Option<usize> {
+    let mut buf = &key[offset..];
+    let len = prost::encoding::decode_varint(&mut buf).ok()?;
+    (key.len() - buf.len()).checked_add(usize::try_from(len).ok()?)
+}
+
+fn service_prefix_name(prefix: ServiceKeyPrefix) -> &'static str {
+    match prefix {
+        ServiceKeyPrefix::PartitionKey => "partition-key",
+        ServiceKeyPrefix::Service => "service",
+    }
+}
+
+fn invocation_prefix_name(prefix: InvocationKeyPrefix) -> &'static str {
+    match prefix {
+        InvocationKeyPrefix::PartitionKey => "partition-key",
+        InvocationKeyPrefix::Invocation => "invocation",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::Bytes;
+    use bytestring::ByteString;
+
+    use crate::journal_table_v2::JournalKey;
+    use crate::keys::TableKey;
+    use crate::state_table::StateKey;
+
+    const TABLE_PREFIXES: TableKeyPrefixOptions = TableKeyPrefixOptions {
+        state: ServiceKeyPrefix::Service,
+        promise: ServiceKeyPrefix::PartitionKey,
+        journal: InvocationKeyPrefix::Invocation,
+    };
+
+    fn state_key(service_name: &'static str, service_key: &'static str) -> Vec<u8> {
+        StateKey {
+            partition_key: 42,
+            service_name: ByteString::from_static(service_name),
+            service_key: ByteString::from_static(service_key),
+            state_key: Bytes::from_static(b"my-state"),
+        }
+        .serialize()
+        .to_vec()
+    }
+
+    #[test]
+    fn default_prefix_is_partition_key() {
+        let key = state_key("Greeter", "slinky");
+        assert_eq!(
+            key_prefix_len(&TableKeyPrefixOptions::default(), &key),
+            Some(DB_PREFIX_LENGTH)
+        );
+        assert_eq!(
+            key_prefix_len(
+                &TableKeyPrefixOptions::default(),
+                &key[..DB_PREFIX_LENGTH - 1]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn state_prefix_includes_service_name() {
+        let key = state_key("Greeter", "slinky");
+        // varint length + service name
+        let expected_len = DB_PREFIX_LENGTH + 1 + "Greeter".len();
+        assert_eq!(key_prefix_len(&TABLE_PREFIXES, &key), Some(expected_len));
+
+        // keys of the same service share the prefix
+        let other_key = state_key("Greeter", "francesco");
+        assert_eq!(key[..expected_len], other_key[..expected_len]);
+
+        // keys must contain the whole service name to be in domain
+        assert_eq!(
+            key_prefix_len(&TABLE_PREFIXES, &key[..expected_len - 1]),
+            None
+        );
+        assert_eq!(
+            key_prefix_len(&TABLE_PREFIXES, &key[..expected_len]),
+            Some(expected_len)
+        );
+    }
+
+    #[test]
+    fn journal_prefix_includes_invocation_uuid() {
+        let key = JournalKey {
+            partition_key: 42,
+            invocation_uuid: InvocationUuid::mock_random(),
+            journal_index: 3,
+        }
+        .serialize();
+
+        assert_eq!(
+            key_prefix_len(&TABLE_PREFIXES, &key),
+            Some(DB_PREFIX_LENGTH + InvocationUuid::RAW_BYTES_LEN)
+        );
+        assert_eq!(
+            key_prefix_len(&TABLE_PREFIXES, &key[..DB_PREFIX_LENGTH]),
+            None
+        );
+    }
+}
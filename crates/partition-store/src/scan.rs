@@ -11,11 +11,12 @@
 use crate::keys::{KeyCodec, KeyKind, TableKey, TableKeyPrefix};
 use crate::scan::TableScan::{
     FullScanPartitionKeyRange, KeyRangeInclusiveInSinglePartition, SinglePartition,
-    SinglePartitionKeyPrefix,
+    SinglePartitionKeyPrefix, SinglePartitionKeyPrefixFrom,
 };
 use crate::{PaddedPartitionId, ScanMode, TableKind};
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use restate_types::identifiers::{PartitionId, PartitionKey};
+use std::num::NonZeroUsize;
 use std::ops::RangeInclusive;
 
 // Note: we take extra arguments like (PartitionId or PartitionKey) only to make sure that
@@ -30,6 +31,9 @@ pub enum TableScan<K> {
     FullScanPartitionKeyRange(RangeInclusive<PartitionKey>),
     /// Scan within a single partition key
     SinglePartitionKeyPrefix(PartitionKey, K),
+    /// Scan within a single partition key the keys with the given prefix, starting from the given
+    /// serialized key.
+    SinglePartitionKeyPrefixFrom(PartitionKey, K, Bytes),
     /// Inclusive Key Range in a single partition.
     KeyRangeInclusiveInSinglePartition(PartitionId, K, K),
 }
@@ -47,6 +51,23 @@ impl<K: TableKeyPrefix> From<TableScan<K>> for PhysicalScan {
             SinglePartitionKeyPrefix(_partition_key, key) => {
                 PhysicalScan::Prefix(K::TABLE, K::KEY_KIND, key.serialize())
             }
+            SinglePartitionKeyPrefixFrom(_partition_key, prefix, from) => {
+                let start = BytesMut::from(from.as_ref());
+                let mut end = prefix.serialize();
+                if try_increment(&mut end) {
+                    PhysicalScan::RangeExclusive(
+                        K::TABLE,
+                        K::KEY_KIND,
+                        ScanMode::WithinPrefix,
+                        start,
+                        end,
+                    )
+                } else {
+                    // not allowed to happen since we guarantee that KeyKind is
+                    // always incrementable.
+                    panic!("Key prefix overflowed, start key {:x?}", &start);
+                }
+            }
             KeyRangeInclusiveInSinglePartition(_partition_id, start, end) => {
                 let start = start.serialize();
                 let mut end = end.serialize();
@@ -107,6 +128,36 @@ impl<K: TableKey> TableScan<K> {
     }
 }
 
+/// Splits an inclusive partition key range into at most `shards` contiguous, non-overlapping
+/// sub-ranges of (almost) equal width, in ascending key order. Scanning each of the returned
+/// ranges as a [`TableScan::FullScanPartitionKeyRange`] covers exactly the keys of the input
+/// range, which allows a full scan to be spread over several background iterators.
+///
+/// Ranges narrower than `shards` are split into single-key ranges.
+pub fn split_partition_key_range(
+    range: &RangeInclusive<PartitionKey>,
+    shards: NonZeroUsize,
+) -> Vec<RangeInclusive<PartitionKey>> {
+    let (start, end) = (*range.start(), *range.end());
+    if start > end {
+        return vec![range.clone()];
+    }
+
+    // the width of the full u64 key space doesn't fit into a PartitionKey
+    let width = u128::from(end - start) + 1;
+    let shards = width.min(shards.get() as u128);
+    let shard_width = width.div_ceil(shards);
+
+    let mut result = Vec::with_capacity(shards as usize);
+    let mut shard_start = u128::from(start);
+    while shard_start <= u128::from(end) {
+        let shard_end = (shard_start + shard_width - 1).min(u128::from(end));
+        result.push(shard_start as PartitionKey..=shard_end as PartitionKey);
+        shard_start = shard_end + 1;
+    }
+    result
+}
+
 /// Binary increment the number represented by the given bytes.
 /// This function computes the next lexicographical byte string
 /// that comes after this string.
@@ -139,11 +190,13 @@ fn try_increment(bytes: &mut BytesMut) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::scan::try_increment;
+    use crate::scan::{split_partition_key_range, try_increment};
     use bytes::{BufMut, BytesMut};
     use num_bigint::BigUint;
+    use restate_types::identifiers::PartitionKey;
     use std::collections::BTreeMap;
-    use std::ops::Add;
+    use std::num::NonZeroUsize;
+    use std::ops::{Add, RangeInclusive};
 
     fn verify_binary_increment(bytes: &mut BytesMut) {
         let as_number = BigUint::from_bytes_be(bytes);
@@ -229,4 +282,37 @@ mod tests {
 
         verify_binary_increment(&mut bytes);
     }
+
+    fn verify_split_covers_exactly(range: RangeInclusive<PartitionKey>, shards: usize) {
+        let split = split_partition_key_range(&range, NonZeroUsize::new(shards).unwrap());
+
+        assert!(!split.is_empty() && split.len() <= shards);
+        assert_eq!(split.first().unwrap().start(), range.start());
+        assert_eq!(split.last().unwrap().end(), range.end());
+        for shard in &split {
+            assert!(shard.start() <= shard.end());
+        }
+        for pair in split.windows(2) {
+            assert_eq!(*pair[0].end() + 1, *pair[1].start());
+        }
+    }
+
+    #[test]
+    fn test_split_partition_key_range() {
+        verify_split_covers_exactly(0..=PartitionKey::MAX, 1);
+        verify_split_covers_exactly(0..=PartitionKey::MAX, 4);
+        verify_split_covers_exactly(0..=PartitionKey::MAX, 7);
+        verify_split_covers_exactly(1024..=(PartitionKey::MAX / 3), 8);
+        verify_split_covers_exactly(42..=42, 4);
+        verify_split_covers_exactly(10..=12, 8);
+
+        assert_eq!(
+            split_partition_key_range(&(0..=99), NonZeroUsize::new(4).unwrap()),
+            vec![0..=24, 25..=49, 50..=74, 75..=99]
+        );
+        assert_eq!(
+            split_partition_key_range(&(10..=12), NonZeroUsize::new(8).unwrap()),
+            vec![10..=10, 11..=11, 12..=12]
+        );
+    }
 }
@@ -8,20 +8,28 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::ops::RangeInclusive;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::num::NonZeroUsize;
+use std::ops::{ControlFlow, RangeInclusive};
+use std::sync::Arc;
 
-use bytes::Bytes;
+use bytes::{BufMut, Bytes, BytesMut};
 use bytestring::ByteString;
 use futures::Stream;
 use futures_util::stream;
+use parking_lot::Mutex;
+use rocksdb::compaction_filter::Decision;
 
 use restate_rocksdb::{Priority, RocksDbPerfGuard};
 use restate_storage_api::state_table::{ReadStateTable, ScanStateTable, WriteStateTable};
 use restate_storage_api::{Result, StorageError};
 use restate_types::identifiers::{PartitionKey, ServiceId, WithPartitionKey};
+use restate_types::payload_encryption;
+use restate_types::time::MillisSinceEpoch;
 
 use crate::TableKind::State;
-use crate::keys::{KeyKind, TableKey, define_table_key};
+use crate::keys::{KeyKind, TableKey, TableKeyPrefix, define_table_key};
 use crate::{PartitionStore, PartitionStoreTransaction, StorageAccess, break_on_err};
 use crate::{TableScan, TableScanIterationDecision};
 
@@ -36,6 +44,35 @@ define_table_key!(
     )
 );
 
+// Entries with a ttl are stored under their own key kind, with the expiration time prepended to
+// the value, so that the compaction filter can drop them by looking only at the entry itself.
+// An entry without ttl shadows the entry with ttl of the same state key.
+define_table_key!(
+    State,
+    KeyKind::ExpiringState,
+    ExpiringStateKey(
+        partition_key: PartitionKey,
+        service_name: ByteString,
+        service_key: ByteString,
+        state_key: Bytes
+    )
+);
+
+// Total size of the entries without ttl of a service, that is the sum of the lengths of their state
+// keys and values. It's maintained on every write, and it's missing for the services whose state
+// was never written since it was introduced. In that case it's computed by scanning the entries.
+define_table_key!(
+    State,
+    KeyKind::StateSize,
+    StateSizeKey(
+        partition_key: PartitionKey,
+        service_name: ByteString,
+        service_key: ByteString
+    )
+);
+
+const EXPIRATION_LENGTH: usize = size_of::<u64>();
+
 #[inline]
 fn write_state_entry_key(service_id: &ServiceId, state_key: impl AsRef<[u8]>) -> StateKey {
     StateKey {
@@ -46,28 +83,169 @@ fn write_state_entry_key(service_id: &ServiceId, state_key: impl AsRef<[u8]>) ->
     }
 }
 
+#[inline]
+fn write_expiring_state_entry_key(
+    service_id: &ServiceId,
+    state_key: impl AsRef<[u8]>,
+) -> ExpiringStateKey {
+    ExpiringStateKey {
+        partition_key: service_id.partition_key(),
+        service_name: service_id.service_name.clone(),
+        service_key: service_id.key.clone(),
+        state_key: state_key.as_ref().to_vec().into(),
+    }
+}
+
+#[inline]
+fn write_state_size_key(service_id: &ServiceId) -> StateSizeKey {
+    StateSizeKey {
+        partition_key: service_id.partition_key(),
+        service_name: service_id.service_name.clone(),
+        service_key: service_id.key.clone(),
+    }
+}
+
 #[inline]
 fn user_state_key_from_slice(mut key: &[u8]) -> Result<Bytes> {
     Ok(StateKey::deserialize_from(&mut key)?.state_key)
 }
 
+fn encode_expiring_value(expires_at: MillisSinceEpoch, value: &[u8]) -> BytesMut {
+    let mut buf = BytesMut::with_capacity(EXPIRATION_LENGTH + value.len());
+    buf.put_u64(expires_at.as_u64());
+    buf.put_slice(value);
+    buf
+}
+
+/// Returns the value of the entry with ttl, unless it's expired.
+fn decode_expiring_value(value: &[u8], now: MillisSinceEpoch) -> Result<Option<&[u8]>> {
+    let Some((expires_at, value)) = value.split_first_chunk::<EXPIRATION_LENGTH>() else {
+        return Err(StorageError::DataIntegrityError);
+    };
+    Ok((now.as_u64() < u64::from_be_bytes(*expires_at)).then_some(value))
+}
+
+/// Decrypts the value, if the state machine encrypted it, see `payload_encryption`.
+fn decrypt_value(value: &[u8]) -> Result<Bytes> {
+    payload_encryption::decrypt(Bytes::copy_from_slice(value))
+        .map_err(|err| StorageError::Generic(err.into()))
+}
+
+/// Compaction filter dropping the state entries expired according to the given state clock.
+pub(crate) fn drop_expired_state(state_clock: u64, key: &[u8], value: &[u8]) -> Decision {
+    if key.starts_with(KeyKind::ExpiringState.as_bytes())
+        && let Ok(None) = decode_expiring_value(value, MillisSinceEpoch::new(state_clock))
+    {
+        Decision::Remove
+    } else {
+        Decision::Keep
+    }
+}
+
 fn put_user_state<S: StorageAccess>(
     storage: &mut S,
     service_id: &ServiceId,
     state_key: impl AsRef<[u8]>,
     state_value: impl AsRef<[u8]>,
+    expires_at: Option<MillisSinceEpoch>,
+    track_size: bool,
 ) -> Result<()> {
-    let key = write_state_entry_key(service_id, state_key);
-    storage.put_kv_raw(key, state_value.as_ref())
+    let state_key = state_key.as_ref();
+    let Some(expires_at) = expires_at else {
+        update_user_state_size(
+            storage,
+            service_id,
+            state_key,
+            Some(state_key.len() + state_value.as_ref().len()),
+            track_size,
+        )?;
+        let key = write_state_entry_key(service_id, state_key);
+        return storage.put_kv_raw(key, state_value.as_ref());
+    };
+
+    // Otherwise the previous value without ttl would shadow this one
+    update_user_state_size(storage, service_id, state_key, None, track_size)?;
+    storage.delete_key(&write_state_entry_key(service_id, state_key))?;
+    let key = write_expiring_state_entry_key(service_id, state_key);
+    storage.put_kv_raw(key, encode_expiring_value(expires_at, state_value.as_ref()))
 }
 
 fn delete_user_state<S: StorageAccess>(
     storage: &mut S,
     service_id: &ServiceId,
     state_key: impl AsRef<[u8]>,
+    track_size: bool,
+) -> Result<()> {
+    let state_key = state_key.as_ref();
+    update_user_state_size(storage, service_id, state_key, None, track_size)?;
+    storage.delete_key(&write_state_entry_key(service_id, state_key))?;
+    storage.delete_key(&write_expiring_state_entry_key(service_id, state_key))
+}
+
+fn get_user_state_size<S: StorageAccess>(storage: &mut S, service_id: &ServiceId) -> Result<u64> {
+    let key = write_state_size_key(service_id);
+    let size = storage.get_kv_raw(key, |_k, v| {
+        v.map(|v| {
+            v.try_into()
+                .map(u64::from_be_bytes)
+                .map_err(|_| StorageError::DataIntegrityError)
+        })
+        .transpose()
+    })?;
+    if let Some(size) = size {
+        return Ok(size);
+    }
+
+    let key = StateKey::builder()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone());
+    storage
+        .for_each_key_value_in_place(
+            TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), key),
+            |k, v| {
+                TableScanIterationDecision::Emit(
+                    user_state_key_from_slice(k).map(|key| (key.len() + v.len()) as u64),
+                )
+            },
+        )?
+        .into_iter()
+        .sum()
+}
+
+/// Updates the total state size of the service, replacing the size of the entry without ttl of
+/// the given state key with the new size, if any. Must be called before writing the entry.
+///
+/// If the size is not tracked, the total state size is dropped instead, to be recomputed from the
+/// entries the next time it's read.
+fn update_user_state_size<S: StorageAccess>(
+    storage: &mut S,
+    service_id: &ServiceId,
+    state_key: &[u8],
+    new_entry_size: Option<usize>,
+    track_size: bool,
 ) -> Result<()> {
+    if !track_size {
+        return storage.delete_key(&write_state_size_key(service_id));
+    }
+
     let key = write_state_entry_key(service_id, state_key);
-    storage.delete_key(&key)
+    let previous_entry_size = storage.get_kv_raw(key, |_k, v| {
+        Ok(v.map_or(0, |v| (state_key.len() + v.len()) as u64))
+    })?;
+    let new_entry_size = new_entry_size.unwrap_or_default() as u64;
+    if previous_entry_size == new_entry_size {
+        return Ok(());
+    }
+
+    let size = (get_user_state_size(storage, service_id)? + new_entry_size)
+        .saturating_sub(previous_entry_size);
+    let key = write_state_size_key(service_id);
+    if size == 0 {
+        storage.delete_key(&key)
+    } else {
+        storage.put_kv_raw(key, size.to_be_bytes())
+    }
 }
 
 fn delete_all_user_state<S: StorageAccess>(storage: &mut S, service_id: &ServiceId) -> Result<()> {
@@ -75,33 +253,57 @@ fn delete_all_user_state<S: StorageAccess>(storage: &mut S, service_id: &Service
         .partition_key(service_id.partition_key())
         .service_name(service_id.service_name.clone())
         .service_key(service_id.key.clone());
-
-    let keys = storage.for_each_key_value_in_place(
+    let mut keys = storage.for_each_key_value_in_place(
         TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), prefix_key),
         |k, _| TableScanIterationDecision::Emit(Ok(Bytes::copy_from_slice(k))),
     )?;
 
+    let prefix_key = ExpiringStateKey::builder()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone());
+    keys.extend(storage.for_each_key_value_in_place(
+        TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), prefix_key),
+        |k, _| TableScanIterationDecision::Emit(Ok(Bytes::copy_from_slice(k))),
+    )?);
+
     for k in keys {
         let key = k?;
         storage.delete_cf(State, &key)?;
     }
 
-    Ok(())
+    storage.delete_key(&write_state_size_key(service_id))
 }
 
 fn get_user_state<S: StorageAccess>(
     storage: &mut S,
     service_id: &ServiceId,
     state_key: impl AsRef<[u8]>,
+    now: MillisSinceEpoch,
 ) -> Result<Option<Bytes>> {
     let _x = RocksDbPerfGuard::new("get-user-state");
-    let key = write_state_entry_key(service_id, state_key);
-    storage.get_kv_raw(key, move |_k, v| Ok(v.map(Bytes::copy_from_slice)))
+    let key = write_state_entry_key(service_id, state_key.as_ref());
+    if let Some(value) = storage.get_kv_raw(key, move |_k, v| v.map(decrypt_value).transpose())? {
+        return Ok(Some(value));
+    }
+
+    let key = write_expiring_state_entry_key(service_id, state_key);
+    storage.get_kv_raw(key, move |_k, v| match v {
+        Some(v) => decode_expiring_value(v, now)?
+            .map(decrypt_value)
+            .transpose(),
+        None => Ok(None),
+    })
 }
 
-fn get_all_user_states_for_service<S: StorageAccess>(
+/// Returns the entries of the service whose state key starts with the given prefix. As the state
+/// key is length-delimited, the matching keys are not contiguous: all the entries of the service
+/// are scanned, but only the matching ones are copied.
+fn get_user_states_for_service<S: StorageAccess>(
     storage: &mut S,
     service_id: &ServiceId,
+    prefix: &[u8],
+    now: MillisSinceEpoch,
 ) -> Result<Vec<Result<(Bytes, Bytes)>>> {
     let _x = RocksDbPerfGuard::new("get-all-user-state");
     let key = StateKey::builder()
@@ -109,10 +311,111 @@ fn get_all_user_states_for_service<S: StorageAccess>(
         .service_name(service_id.service_name.clone())
         .service_key(service_id.key.clone());
 
-    storage.for_each_key_value_in_place(
+    let entries = storage.for_each_key_value_in_place(
         TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), key),
-        |k, v| TableScanIterationDecision::Emit(decode_user_state_key_value(k, v)),
-    )
+        |k, v| match user_state_key_from_slice(k) {
+            Ok(key) if !key.starts_with(prefix) => TableScanIterationDecision::Continue,
+            Ok(key) => TableScanIterationDecision::Emit(decrypt_value(v).map(|v| (key, v))),
+            Err(err) => TableScanIterationDecision::Emit(Err(err)),
+        },
+    )?;
+
+    let key = ExpiringStateKey::builder()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone());
+    let expiring_entries = storage.for_each_key_value_in_place(
+        TableScan::SinglePartitionKeyPrefix(service_id.partition_key(), key),
+        |mut k, v| {
+            let key = match ExpiringStateKey::deserialize_from(&mut k) {
+                Ok(key) if !key.state_key.starts_with(prefix) => {
+                    return TableScanIterationDecision::Continue;
+                }
+                Ok(key) => key,
+                Err(err) => return TableScanIterationDecision::Emit(Err(err)),
+            };
+            match decode_expiring_value(v, now) {
+                Ok(Some(v)) => {
+                    TableScanIterationDecision::Emit(decrypt_value(v).map(|v| (key.state_key, v)))
+                }
+                Ok(None) => TableScanIterationDecision::Continue,
+                Err(err) => TableScanIterationDecision::Emit(Err(err)),
+            }
+        },
+    )?;
+    if expiring_entries.is_empty() {
+        return Ok(entries);
+    }
+
+    // Merge in the same order of the keys, the entries without ttl win
+    let mut merged = BTreeMap::new();
+    for entry in expiring_entries.into_iter().chain(entries) {
+        let (key, value) = entry?;
+        merged.insert((key.len(), key), value);
+    }
+    Ok(merged
+        .into_iter()
+        .map(|((_, key), value)| Ok((key, value)))
+        .collect())
+}
+
+/// Merges the entries with ttl, scanned upfront, into the scan of the entries without ttl.
+struct MergeExpiringUserState<F> {
+    f: F,
+    /// Full keys and values of the entries with ttl, sorted by key
+    pending: VecDeque<(Bytes, Bytes)>,
+    stopped: bool,
+}
+
+impl<F> MergeExpiringUserState<F>
+where
+    F: FnMut((ServiceId, Bytes, &[u8])) -> ControlFlow<()>,
+{
+    fn emit(&mut self, key: StateKey, value: &[u8]) -> ControlFlow<Result<()>> {
+        let (partition_key, service_name, service_key, state_key) = key.split();
+        let service_id = ServiceId::from_parts(partition_key, service_name, service_key);
+
+        let result = if payload_encryption::is_framed(value) {
+            let value = break_on_err(decrypt_value(value))?;
+            (self.f)((service_id, state_key, &value))
+        } else {
+            (self.f)((service_id, state_key, value))
+        };
+        self.stopped = result.is_break();
+        result.map_break(Ok)
+    }
+
+    /// Emits the pending entries sorting before the given key, skipping the one it shadows.
+    fn emit_pending_before(&mut self, key: Option<&[u8]>) -> ControlFlow<Result<()>> {
+        while let Some((pending_key, _)) = self.pending.front() {
+            // Both key kinds have the same layout after the key kind
+            let ordering = key.map_or(Ordering::Less, |key| {
+                pending_key[KeyKind::SERIALIZED_LENGTH..].cmp(&key[KeyKind::SERIALIZED_LENGTH..])
+            });
+            if ordering == Ordering::Greater {
+                break;
+            }
+
+            let (pending_key, value) = self.pending.pop_front().expect("not empty");
+            if ordering == Ordering::Equal {
+                continue;
+            }
+            let (partition_key, service_name, service_key, state_key) = break_on_err(
+                ExpiringStateKey::deserialize_from(&mut pending_key.as_ref()),
+            )?
+            .split();
+            self.emit(
+                StateKey {
+                    partition_key,
+                    service_name,
+                    service_key,
+                    state_key,
+                },
+                &value,
+            )?;
+        }
+        ControlFlow::Continue(())
+    }
 }
 
 impl ReadStateTable for PartitionStore {
@@ -122,7 +425,12 @@ impl ReadStateTable for PartitionStore {
         state_key: impl AsRef<[u8]>,
     ) -> Result<Option<Bytes>> {
         self.assert_partition_key(service_id)?;
-        get_user_state(self, service_id, state_key)
+        get_user_state(self, service_id, state_key, MillisSinceEpoch::now())
+    }
+
+    async fn get_user_state_size(&mut self, service_id: &ServiceId) -> Result<u64> {
+        self.assert_partition_key(service_id)?;
+        get_user_state_size(self, service_id)
     }
 
     fn get_all_user_states_for_service(
@@ -130,34 +438,334 @@ impl ReadStateTable for PartitionStore {
         service_id: &ServiceId,
     ) -> Result<impl Stream<Item = Result<(Bytes, Bytes)>> + Send> {
         self.assert_partition_key(service_id)?;
-        Ok(stream::iter(get_all_user_states_for_service(
-            self, service_id,
+        Ok(stream::iter(get_user_states_for_service(
+            self,
+            service_id,
+            &[],
+            MillisSinceEpoch::now(),
         )?))
     }
+
+    fn get_user_states_by_prefix(
+        &mut self,
+        service_id: &ServiceId,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Stream<Item = Result<(Bytes, Bytes)>> + Send> {
+        self.assert_partition_key(service_id)?;
+        Ok(stream::iter(get_user_states_for_service(
+            self,
+            service_id,
+            prefix.as_ref(),
+            MillisSinceEpoch::now(),
+        )?))
+    }
+}
+
+impl PartitionStore {
+    /// Scans the entries without ttl on the background iterator pool, merging in order the ones
+    /// with ttl, which are collected upfront. The entries with ttl sorting after the last entry
+    /// without ttl are emitted outside the pool.
+    fn for_each_merged_user_state<E, K, F>(
+        &self,
+        names: (&'static str, &'static str),
+        expiring_scan: TableScan<E>,
+        scan: TableScan<K>,
+        f: F,
+    ) -> Result<impl Future<Output = Result<()>> + Send>
+    where
+        E: TableKeyPrefix,
+        K: TableKeyPrefix,
+        F: FnMut((ServiceId, Bytes, &[u8])) -> ControlFlow<()> + Send + Sync + 'static,
+    {
+        let (expiring_name, name) = names;
+        let expiring_entries = Arc::new(Mutex::new(VecDeque::new()));
+        let collector = Arc::clone(&expiring_entries);
+        let now = MillisSinceEpoch::now();
+        let collect_expiring_entries = self
+            .iterator_for_each(
+                expiring_name,
+                Priority::Low,
+                expiring_scan,
+                move |(key, value)| {
+                    if let Some(value) = break_on_err(decode_expiring_value(value, now))? {
+                        collector.lock().push_back((
+                            Bytes::copy_from_slice(key),
+                            Bytes::copy_from_slice(value),
+                        ));
+                    }
+                    ControlFlow::Continue(())
+                },
+            )
+            .map_err(|_| StorageError::OperationalError)?;
+
+        let partition_store = self.clone();
+        Ok(async move {
+            collect_expiring_entries.await?;
+
+            let merge = Arc::new(Mutex::new(MergeExpiringUserState {
+                f,
+                pending: std::mem::take(&mut *expiring_entries.lock()),
+                stopped: false,
+            }));
+            let scan_merge = Arc::clone(&merge);
+            partition_store
+                .iterator_for_each(name, Priority::Low, scan, move |(mut key, value)| {
+                    let mut merge = scan_merge.lock();
+                    merge.emit_pending_before(Some(key))?;
+                    let row_key = break_on_err(StateKey::deserialize_from(&mut key))?;
+                    merge.emit(row_key, value)
+                })
+                .map_err(|_| StorageError::OperationalError)?
+                .await?;
+
+            let mut merge = merge.lock();
+            if !merge.stopped
+                && let ControlFlow::Break(Err(err)) = merge.emit_pending_before(None)
+            {
+                return Err(err);
+            }
+            Ok(())
+        })
+    }
 }
 
 impl ScanStateTable for PartitionStore {
     fn for_each_user_state<
-        F: FnMut((ServiceId, Bytes, &[u8])) -> std::ops::ControlFlow<()> + Send + Sync + 'static,
+        F: FnMut((ServiceId, Bytes, &[u8])) -> ControlFlow<()> + Send + Sync + 'static,
     >(
         &self,
         range: RangeInclusive<PartitionKey>,
-        mut f: F,
+        f: F,
     ) -> Result<impl Future<Output = Result<()>> + Send> {
-        self.iterator_for_each(
-            "df-user-state",
-            Priority::Low,
+        self.for_each_merged_user_state(
+            ("df-expiring-user-state", "df-user-state"),
+            TableScan::FullScanPartitionKeyRange::<ExpiringStateKey>(range.clone()),
             TableScan::FullScanPartitionKeyRange::<StateKey>(range),
-            move |(mut key, value)| {
-                let row_key = break_on_err(StateKey::deserialize_from(&mut key))?;
-                let (partition_key, service_name, service_key, state_key) = row_key.split();
+            f,
+        )
+    }
 
-                let service_id = ServiceId::from_parts(partition_key, service_name, service_key);
+    fn stream_user_states_for_service(
+        &self,
+        service_id: &ServiceId,
+        max_batch_bytes: NonZeroUsize,
+    ) -> Result<impl Stream<Item = Result<Vec<(Bytes, Bytes)>>> + Send> {
+        self.assert_partition_key(service_id)?;
+        let cursor = UserStatesCursor {
+            from: Some(user_states_prefix(service_id).serialize().freeze()),
+            expiring_from: Some(expiring_user_states_prefix(service_id).serialize().freeze()),
+        };
+
+        let partition_store = self.clone();
+        let service_id = service_id.clone();
+        Ok(stream::try_unfold(cursor, move |cursor| {
+            let partition_store = partition_store.clone();
+            let service_id = service_id.clone();
+            async move {
+                partition_store
+                    .next_user_states_batch(&service_id, cursor, max_batch_bytes)
+                    .await
+            }
+        }))
+    }
+}
 
-                f((service_id, state_key, value)).map_break(Ok)
-            },
-        )
-        .map_err(|_| StorageError::OperationalError)
+#[inline]
+fn user_states_prefix(service_id: &ServiceId) -> StateKeyBuilder {
+    StateKey::builder()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone())
+}
+
+#[inline]
+fn expiring_user_states_prefix(service_id: &ServiceId) -> ExpiringStateKeyBuilder {
+    ExpiringStateKey::builder()
+        .partition_key(service_id.partition_key())
+        .service_name(service_id.service_name.clone())
+        .service_key(service_id.key.clone())
+}
+
+/// Serialized keys to resume the scans of [`ScanStateTable::stream_user_states_for_service`]
+/// from, `None` once the table is read to the end.
+struct UserStatesCursor {
+    from: Option<Bytes>,
+    expiring_from: Option<Bytes>,
+}
+
+/// Entries read by a single scan of [`PartitionStore::read_user_states_chunk`].
+#[derive(Default)]
+struct UserStatesChunk {
+    /// Serialized keys and values, sorted by key
+    entries: Vec<(Bytes, Bytes)>,
+    size: usize,
+    /// Whether the scan stopped before reading all the entries
+    truncated: bool,
+}
+
+/// Returns the smallest key sorting after the given one.
+fn key_successor(key: &[u8]) -> Bytes {
+    let mut successor = BytesMut::with_capacity(key.len() + 1);
+    successor.put_slice(key);
+    successor.put_u8(0);
+    successor.freeze()
+}
+
+impl PartitionStore {
+    /// Reads the next batch of [`ScanStateTable::stream_user_states_for_service`]. Both tables
+    /// are scanned from the cursor until about `max_batch_bytes` of entries are read, then the
+    /// entries are merged up to the last key read by both scans. Returns `None` once both tables
+    /// are read to the end.
+    async fn next_user_states_batch(
+        &self,
+        service_id: &ServiceId,
+        cursor: UserStatesCursor,
+        max_batch_bytes: NonZeroUsize,
+    ) -> Result<Option<(Vec<(Bytes, Bytes)>, UserStatesCursor)>> {
+        let partition_key = service_id.partition_key();
+        let chunk = match &cursor.from {
+            Some(from) => {
+                self.read_user_states_chunk(
+                    "stream-user-state",
+                    TableScan::SinglePartitionKeyPrefixFrom(
+                        partition_key,
+                        user_states_prefix(service_id),
+                        from.clone(),
+                    ),
+                    max_batch_bytes,
+                    None,
+                )
+                .await?
+            }
+            None => UserStatesChunk::default(),
+        };
+        let expiring_chunk = match &cursor.expiring_from {
+            Some(from) => {
+                self.read_user_states_chunk(
+                    "stream-expiring-user-state",
+                    TableScan::SinglePartitionKeyPrefixFrom(
+                        partition_key,
+                        expiring_user_states_prefix(service_id),
+                        from.clone(),
+                    ),
+                    max_batch_bytes,
+                    Some(MillisSinceEpoch::now()),
+                )
+                .await?
+            }
+            None => UserStatesChunk::default(),
+        };
+
+        // The entries not read by a scan that stopped early might sort before the ones read from
+        // the other table, so merge only up to the last key read by such scans. Both key kinds
+        // have the same layout after the key kind.
+        let bound = [&chunk, &expiring_chunk]
+            .into_iter()
+            .filter(|chunk| chunk.truncated)
+            .filter_map(|chunk| chunk.entries.last())
+            .map(|(key, _)| key.slice(KeyKind::SERIALIZED_LENGTH..))
+            .min();
+        let before_bound = |entry: Option<&(Bytes, Bytes)>| {
+            entry
+                .map(|(key, _)| key.slice(KeyKind::SERIALIZED_LENGTH..))
+                .filter(|key| bound.as_ref().is_none_or(|bound| key <= bound))
+        };
+        let decrypt = |value| {
+            payload_encryption::decrypt(value).map_err(|err| StorageError::Generic(err.into()))
+        };
+
+        let (truncated, expiring_truncated) = (chunk.truncated, expiring_chunk.truncated);
+        let mut entries = chunk.entries.into_iter().peekable();
+        let mut expiring_entries = expiring_chunk.entries.into_iter().peekable();
+        let (mut last_key, mut last_expiring_key) = (None, None);
+        let mut batch = Vec::new();
+        loop {
+            let ordering = match (
+                before_bound(entries.peek()),
+                before_bound(expiring_entries.peek()),
+            ) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(key), Some(expiring_key)) => key.cmp(&expiring_key),
+            };
+            if ordering.is_ge() {
+                let (key, value) = expiring_entries.next().expect("not empty");
+                // The entry without ttl of the same state key shadows this one
+                if ordering.is_gt() {
+                    let state_key =
+                        ExpiringStateKey::deserialize_from(&mut key.as_ref())?.state_key;
+                    batch.push((state_key, decrypt(value)?));
+                }
+                last_expiring_key = Some(key);
+            }
+            if ordering.is_le() {
+                let (key, value) = entries.next().expect("not empty");
+                batch.push((user_state_key_from_slice(&key)?, decrypt(value)?));
+                last_key = Some(key);
+            }
+        }
+
+        if batch.is_empty() {
+            return Ok(None);
+        }
+        let next_from = |from: Option<Bytes>, last_key: Option<Bytes>, read_to_end: bool| {
+            if read_to_end {
+                None
+            } else {
+                last_key.map(|key| key_successor(&key)).or(from)
+            }
+        };
+        let cursor = UserStatesCursor {
+            from: next_from(
+                cursor.from,
+                last_key,
+                !truncated && entries.peek().is_none(),
+            ),
+            expiring_from: next_from(
+                cursor.expiring_from,
+                last_expiring_key,
+                !expiring_truncated && expiring_entries.peek().is_none(),
+            ),
+        };
+        Ok(Some((batch, cursor)))
+    }
+
+    /// Scans the entries on the background iterator pool, until about `max_batch_bytes` of
+    /// entries are read. If `now` is set, the entries are the ones with ttl, and the expired ones
+    /// are skipped.
+    async fn read_user_states_chunk<K: TableKeyPrefix>(
+        &self,
+        name: &'static str,
+        scan: TableScan<K>,
+        max_batch_bytes: NonZeroUsize,
+        now: Option<MillisSinceEpoch>,
+    ) -> Result<UserStatesChunk> {
+        let chunk = Arc::new(Mutex::new(UserStatesChunk::default()));
+        let collector = Arc::clone(&chunk);
+        self.iterator_for_each(name, Priority::Low, scan, move |(key, value)| {
+            let value = match now {
+                Some(now) => match break_on_err(decode_expiring_value(value, now))? {
+                    Some(value) => value,
+                    None => return ControlFlow::Continue(()),
+                },
+                None => value,
+            };
+            let mut chunk = collector.lock();
+            if chunk.size >= max_batch_bytes.get() {
+                chunk.truncated = true;
+                return ControlFlow::Break(Ok(()));
+            }
+            chunk.size += key.len() + value.len();
+            chunk
+                .entries
+                .push((Bytes::copy_from_slice(key), Bytes::copy_from_slice(value)));
+            ControlFlow::Continue(())
+        })
+        .map_err(|_| StorageError::OperationalError)?
+        .await?;
+
+        Ok(std::mem::take(&mut *chunk.lock()))
     }
 }
 
@@ -168,7 +776,13 @@ impl ReadStateTable for PartitionStoreTransaction<'_> {
         state_key: impl AsRef<[u8]> + Send,
     ) -> Result<Option<Bytes>> {
         self.assert_partition_key(service_id)?;
-        get_user_state(self, service_id, state_key)
+        let now = self.state_clock();
+        get_user_state(self, service_id, state_key, now)
+    }
+
+    async fn get_user_state_size(&mut self, service_id: &ServiceId) -> Result<u64> {
+        self.assert_partition_key(service_id)?;
+        get_user_state_size(self, service_id)
     }
 
     fn get_all_user_states_for_service(
@@ -176,21 +790,63 @@ impl ReadStateTable for PartitionStoreTransaction<'_> {
         service_id: &ServiceId,
     ) -> Result<impl Stream<Item = Result<(Bytes, Bytes)>> + Send> {
         self.assert_partition_key(service_id)?;
-        Ok(stream::iter(get_all_user_states_for_service(
-            self, service_id,
+        let now = self.state_clock();
+        Ok(stream::iter(get_user_states_for_service(
+            self,
+            service_id,
+            &[],
+            now,
+        )?))
+    }
+
+    fn get_user_states_by_prefix(
+        &mut self,
+        service_id: &ServiceId,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<impl Stream<Item = Result<(Bytes, Bytes)>> + Send> {
+        self.assert_partition_key(service_id)?;
+        let now = self.state_clock();
+        Ok(stream::iter(get_user_states_for_service(
+            self,
+            service_id,
+            prefix.as_ref(),
+            now,
         )?))
     }
 }
 
+impl PartitionStoreTransaction<'_> {
+    fn state_clock(&self) -> MillisSinceEpoch {
+        self.state_clock.unwrap_or_else(MillisSinceEpoch::now)
+    }
+}
+
 impl WriteStateTable for PartitionStoreTransaction<'_> {
+    fn track_user_state_size(&mut self, enabled: bool) {
+        self.track_user_state_size = enabled;
+    }
+
+    fn set_state_clock(&mut self, now: MillisSinceEpoch) {
+        self.state_clock = Some(now);
+    }
+
     fn put_user_state(
         &mut self,
         service_id: &ServiceId,
         state_key: impl AsRef<[u8]>,
         state_value: impl AsRef<[u8]>,
+        expires_at: Option<MillisSinceEpoch>,
     ) -> Result<()> {
         self.assert_partition_key(service_id)?;
-        put_user_state(self, service_id, state_key, state_value)
+        let track_size = self.track_user_state_size;
+        put_user_state(
+            self,
+            service_id,
+            state_key,
+            state_value,
+            expires_at,
+            track_size,
+        )
     }
 
     fn delete_user_state(
@@ -199,7 +855,8 @@ impl WriteStateTable for PartitionStoreTransaction<'_> {
         state_key: impl AsRef<[u8]>,
     ) -> Result<()> {
         self.assert_partition_key(service_id)?;
-        delete_user_state(self, service_id, state_key)
+        let track_size = self.track_user_state_size;
+        delete_user_state(self, service_id, state_key, track_size)
     }
 
     fn delete_all_user_state(&mut self, service_id: &ServiceId) -> Result<()> {
@@ -208,12 +865,6 @@ impl WriteStateTable for PartitionStoreTransaction<'_> {
     }
 }
 
-fn decode_user_state_key_value(k: &[u8], v: &[u8]) -> Result<(Bytes, Bytes)> {
-    let user_key = user_state_key_from_slice(k)?;
-    let user_value = Bytes::copy_from_slice(v);
-    Ok((user_key, user_value))
-}
-
 #[cfg(test)]
 mod tests {
     use crate::keys::TableKeyPrefix;
@@ -0,0 +1,85 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use bytes::Bytes;
+
+use crate::PartitionStore;
+use restate_storage_api::Transaction;
+use restate_storage_api::completion_event_table::{
+    CompletionEvent, CompletionEventSequence, ReadCompletionEventTable, WriteCompletionEventTable,
+};
+use restate_types::identifiers::InvocationId;
+use restate_types::logs::Lsn;
+
+fn sequence(lsn: u64, index: u32) -> CompletionEventSequence {
+    CompletionEventSequence::new(Lsn::new(lsn), index)
+}
+
+fn mock_completion_event(sequence: CompletionEventSequence) -> CompletionEvent {
+    CompletionEvent {
+        invocation_id: InvocationId::mock_random(),
+        payload: Bytes::from(sequence.to_string()),
+    }
+}
+
+fn sequences(
+    events: &[(CompletionEventSequence, CompletionEvent)],
+) -> Vec<CompletionEventSequence> {
+    events.iter().map(|(seq, _)| *seq).collect()
+}
+
+pub(crate) async fn run_tests(mut rocksdb: PartitionStore) {
+    let mut txn = rocksdb.transaction();
+    let mut events = Vec::new();
+    for seq in [
+        sequence(7, 0),
+        sequence(3, 1),
+        sequence(3, 0),
+        sequence(42, 0),
+    ] {
+        let event = mock_completion_event(seq);
+        txn.put_completion_event(seq, &event).unwrap();
+        events.push((seq, event));
+    }
+    txn.commit().await.expect("should not fail");
+    events.sort_by_key(|(seq, _)| *seq);
+
+    // ordered by sequence, up to the limit
+    assert_eq!(
+        rocksdb.get_completion_events(None, 2).await.unwrap(),
+        events[..2]
+    );
+    // the given sequence is excluded
+    assert_eq!(
+        sequences(
+            &rocksdb
+                .get_completion_events(Some(sequence(3, 1)), 10)
+                .await
+                .unwrap()
+        ),
+        vec![sequence(7, 0), sequence(42, 0)]
+    );
+    assert!(
+        rocksdb
+            .get_completion_events(Some(sequence(42, 0)), 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let mut txn = rocksdb.transaction();
+    txn.truncate_completion_events(sequence(7, 0)).unwrap();
+    txn.commit().await.expect("should not fail");
+
+    assert_eq!(
+        rocksdb.get_completion_events(None, 10).await.unwrap(),
+        events[3..]
+    );
+}
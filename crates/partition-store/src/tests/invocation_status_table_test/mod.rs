@@ -101,6 +101,7 @@ fn invoked_status(invocation_target: InvocationTarget) -> InvocationStatus {
         current_invocation_epoch: 1,
         completion_range_epoch_map: CompletionRangeEpochMap::from_trim_points([(5, 1)]),
         random_seed: None,
+        deadline: Some(MillisSinceEpoch::new(1_000)),
     })
 }
 
@@ -129,6 +130,7 @@ fn suspended_status(invocation_target: InvocationTarget) -> InvocationStatus {
             current_invocation_epoch: 1,
             completion_range_epoch_map: CompletionRangeEpochMap::from_trim_points([(5, 1)]),
             random_seed: None,
+            deadline: None,
         },
         waiting_for_notifications: HashSet::default(),
     }
@@ -29,6 +29,7 @@ use restate_types::invocation::{InvocationTarget, ServiceInvocation, Source};
 use restate_types::state_mut::ExternalStateMutation;
 
 mod barrier_test;
+mod completion_event_table_test;
 mod durable_lsn_tracking_test;
 mod idempotency_table_test;
 mod inbox_table_test;
@@ -37,6 +38,7 @@ mod journal_events_table_test;
 mod journal_table_test;
 mod journal_table_v2_test;
 mod outbox_table_test;
+mod parked_command_table_test;
 mod promise_table_test;
 mod snapshots_test;
 mod state_table_test;
@@ -78,6 +80,8 @@ async fn test_read_write() {
 
     inbox_table_test::run_tests(store.clone()).await;
     outbox_table_test::run_tests(store.clone()).await;
+    parked_command_table_test::run_tests(store.clone()).await;
+    completion_event_table_test::run_tests(store.clone()).await;
     state_table_test::run_tests(store.clone()).await;
     virtual_object_status_table_test::run_tests(store.clone()).await;
     timer_table_test::run_tests(store.clone()).await;
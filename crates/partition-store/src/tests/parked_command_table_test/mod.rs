@@ -0,0 +1,58 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use bytes::Bytes;
+
+use crate::PartitionStore;
+use restate_storage_api::Transaction;
+use restate_storage_api::parked_command_table::{
+    ParkedCommand, ReadParkedCommandTable, WriteParkedCommandTable,
+};
+use restate_types::logs::Lsn;
+use restate_types::time::MillisSinceEpoch;
+
+fn mock_parked_command(lsn: u64) -> ParkedCommand {
+    ParkedCommand {
+        lsn: Lsn::new(lsn),
+        parked_at: MillisSinceEpoch::new(lsn),
+        attempts: 3,
+        failure: format!("failure {lsn}"),
+        command_name: "Invoke".to_owned(),
+        envelope: Bytes::from(lsn.to_be_bytes().to_vec()),
+    }
+}
+
+pub(crate) async fn run_tests(mut rocksdb: PartitionStore) {
+    let mut txn = rocksdb.transaction();
+    for lsn in [7, 3, 42] {
+        txn.put_parked_command(&mock_parked_command(lsn)).unwrap();
+    }
+    txn.commit().await.expect("should not fail");
+
+    assert_eq!(
+        rocksdb.get_parked_command(Lsn::new(3)).await.unwrap(),
+        Some(mock_parked_command(3))
+    );
+    assert_eq!(rocksdb.get_parked_command(Lsn::new(4)).await.unwrap(), None);
+    // ordered by lsn, up to the limit
+    assert_eq!(
+        rocksdb.get_parked_commands(2).await.unwrap(),
+        vec![mock_parked_command(3), mock_parked_command(7)]
+    );
+
+    let mut txn = rocksdb.transaction();
+    txn.delete_parked_command(Lsn::new(3)).unwrap();
+    txn.commit().await.expect("should not fail");
+
+    assert_eq!(
+        rocksdb.get_parked_commands(10).await.unwrap(),
+        vec![mock_parked_command(7), mock_parked_command(42)]
+    );
+}
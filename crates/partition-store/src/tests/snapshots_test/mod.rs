@@ -8,17 +8,16 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::SystemTime;
 
 use tempfile::tempdir;
 
+use restate_rocksdb::RocksError;
 use restate_storage_api::Transaction;
 use restate_storage_api::fsm_table::{ReadFsmTable, WriteFsmTable};
-use restate_types::identifiers::{PartitionKey, SnapshotId};
+use restate_types::identifiers::SnapshotId;
 use restate_types::logs::{LogId, Lsn};
-use restate_types::partitions::Partition;
 use restate_types::time::MillisSinceEpoch;
 
 use crate::snapshots::{LocalPartitionSnapshot, PartitionSnapshotMetadata, SnapshotFormatVersion};
@@ -33,6 +32,7 @@ pub(crate) async fn run_tests(
     let snapshots_dir = tempdir().unwrap();
 
     let partition_id = partition_store.partition_id();
+    let partition = Arc::clone(partition_store.partition());
 
     let snapshot = partition_store
         .create_local_snapshot(snapshots_dir.path(), None, SnapshotId::new())
@@ -59,7 +59,14 @@ pub(crate) async fn run_tests(
     drop(partition_store);
     drop(snapshot);
 
-    manager.drop_partition(partition_id).await.unwrap();
+    // The partition store must be closed first
+    assert!(matches!(
+        manager.drop_partition(&partition).await,
+        Err(RocksError::AlreadyOpen)
+    ));
+    manager.close(partition_id).await;
+    manager.drop_partition(&partition).await.unwrap();
+    assert!(manager.get_partition_store(partition_id).await.is_none());
 
     let snapshot_meta: PartitionSnapshotMetadata = serde_json::from_str(&metadata_json).unwrap();
 
@@ -73,10 +80,7 @@ pub(crate) async fn run_tests(
     };
 
     let mut new_partition_store = manager
-        .open_from_snapshot(
-            &Partition::new(partition_id, RangeInclusive::new(0, PartitionKey::MAX - 1)),
-            snapshot,
-        )
+        .open_from_snapshot(&partition, snapshot)
         .await
         .unwrap();
 
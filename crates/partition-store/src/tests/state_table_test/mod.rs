@@ -10,12 +10,19 @@
 
 use super::{assert_stream_eq, storage_test_environment};
 
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use crate::PartitionStore;
 use bytes::Bytes;
+use futures::TryStreamExt;
 use restate_rocksdb::RocksDbManager;
 use restate_storage_api::Transaction;
-use restate_storage_api::state_table::{ReadStateTable, WriteStateTable};
-use restate_types::identifiers::ServiceId;
+use restate_storage_api::state_table::{ReadStateTable, ScanStateTable, WriteStateTable};
+use restate_types::identifiers::{PartitionKey, ServiceId};
+use restate_types::time::MillisSinceEpoch;
 
 fn populate_data<T: WriteStateTable>(table: &mut T) {
     table
@@ -23,6 +30,7 @@ fn populate_data<T: WriteStateTable>(table: &mut T) {
             &ServiceId::with_partition_key(1337, "svc-1", "key-1"),
             Bytes::from_static(b"k1"),
             Bytes::from_static(b"v1"),
+            None,
         )
         .expect("");
 
@@ -31,6 +39,7 @@ fn populate_data<T: WriteStateTable>(table: &mut T) {
             &ServiceId::with_partition_key(1337, "svc-1", "key-1"),
             Bytes::from_static(b"k2"),
             Bytes::from_static(b"v2"),
+            None,
         )
         .unwrap();
 
@@ -39,6 +48,7 @@ fn populate_data<T: WriteStateTable>(table: &mut T) {
             &ServiceId::with_partition_key(1337, "svc-1", "key-2"),
             Bytes::from_static(b"k2"),
             Bytes::from_static(b"v2"),
+            None,
         )
         .unwrap();
 }
@@ -112,6 +122,129 @@ pub(crate) async fn run_tests(mut rocksdb: PartitionStore) {
     verify_prefix_scan_after_delete(&mut txn).await;
 }
 
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_read_your_writes() {
+    let mut rocksdb = storage_test_environment().await;
+    let service_id = ServiceId::with_partition_key(1337, "svc-1", "key-1");
+    let one_hour = Some(MillisSinceEpoch::now() + Duration::from_secs(60 * 60));
+
+    let mut txn = rocksdb.transaction();
+    populate_data(&mut txn);
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    // overwrite of a committed entry
+    txn.put_user_state(&service_id, b"k1", b"v1-updated", None)
+        .unwrap();
+    assert_eq!(
+        txn.get_user_state(&service_id, b"k1").await.unwrap(),
+        Some(Bytes::from_static(b"v1-updated"))
+    );
+    // delete of a committed entry
+    deletes(&mut txn);
+    verify_delete(&mut txn).await;
+    // overwrite of an uncommitted entry with one with ttl
+    txn.put_user_state(&service_id, b"k3", b"v3", None).unwrap();
+    txn.put_user_state(&service_id, b"k3", b"v3-expiring", one_hour)
+        .unwrap();
+    assert_eq!(
+        txn.get_user_state(&service_id, b"k3").await.unwrap(),
+        Some(Bytes::from_static(b"v3-expiring"))
+    );
+    assert_stream_eq(
+        txn.get_all_user_states_for_service(&service_id).unwrap(),
+        vec![
+            (Bytes::from_static(b"k1"), Bytes::from_static(b"v1-updated")),
+            (
+                Bytes::from_static(b"k3"),
+                Bytes::from_static(b"v3-expiring"),
+            ),
+        ],
+    )
+    .await;
+
+    txn.delete_all_user_state(&service_id).unwrap();
+    assert_eq!(txn.get_user_state(&service_id, b"k1").await.unwrap(), None);
+    assert_stream_eq(
+        txn.get_all_user_states_for_service(&service_id).unwrap(),
+        vec![],
+    )
+    .await;
+    drop(txn);
+
+    // nothing was committed
+    point_lookup(&mut rocksdb).await;
+    prefix_scans(&mut rocksdb).await;
+
+    RocksDbManager::get().shutdown().await;
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_state_size() {
+    let mut rocksdb = storage_test_environment().await;
+    let service_id = ServiceId::with_partition_key(1337, "svc-1", "key-1");
+
+    let mut txn = rocksdb.transaction();
+    assert_eq!(txn.get_user_state_size(&service_id).await.unwrap(), 0);
+    txn.put_user_state(&service_id, b"k1", b"v1", None).unwrap();
+    txn.put_user_state(&service_id, b"k2", b"value-2", None)
+        .unwrap();
+    assert_eq!(txn.get_user_state_size(&service_id).await.unwrap(), 13);
+    txn.commit().await.expect("should not fail");
+    assert_eq!(rocksdb.get_user_state_size(&service_id).await.unwrap(), 13);
+
+    let mut txn = rocksdb.transaction();
+    // overwrite
+    txn.put_user_state(&service_id, b"k1", b"value-1", None)
+        .unwrap();
+    assert_eq!(txn.get_user_state_size(&service_id).await.unwrap(), 18);
+    // entries with ttl are not accounted, and they replace the entry without ttl
+    txn.put_user_state(
+        &service_id,
+        b"k2",
+        b"value-2",
+        Some(MillisSinceEpoch::now() + Duration::from_secs(60 * 60)),
+    )
+    .unwrap();
+    assert_eq!(txn.get_user_state_size(&service_id).await.unwrap(), 9);
+    // delete, also of a missing entry
+    txn.delete_user_state(&service_id, b"k1").unwrap();
+    txn.delete_user_state(&service_id, b"k3").unwrap();
+    assert_eq!(txn.get_user_state_size(&service_id).await.unwrap(), 0);
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    txn.put_user_state(&service_id, b"k1", b"v1", None).unwrap();
+    txn.delete_all_user_state(&service_id).unwrap();
+    assert_eq!(txn.get_user_state_size(&service_id).await.unwrap(), 0);
+    txn.commit().await.expect("should not fail");
+
+    // without tracking, the size is recomputed from the entries
+    let mut txn = rocksdb.transaction();
+    txn.put_user_state(&service_id, b"k1", b"v1", None).unwrap();
+    txn.track_user_state_size(false);
+    txn.put_user_state(&service_id, b"k2", b"value-2", None)
+        .unwrap();
+    txn.delete_user_state(&service_id, b"k1").unwrap();
+    assert_eq!(txn.get_user_state_size(&service_id).await.unwrap(), 9);
+    txn.commit().await.expect("should not fail");
+    assert_eq!(rocksdb.get_user_state_size(&service_id).await.unwrap(), 9);
+
+    // other services are not affected
+    let mut txn = rocksdb.transaction();
+    populate_data(&mut txn);
+    txn.commit().await.expect("should not fail");
+    assert_eq!(
+        rocksdb
+            .get_user_state_size(&ServiceId::with_partition_key(1337, "svc-1", "key-2"))
+            .await
+            .unwrap(),
+        4
+    );
+
+    RocksDbManager::get().shutdown().await;
+}
+
 #[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_delete_all() {
     let mut rocksdb = storage_test_environment().await;
@@ -149,3 +282,234 @@ async fn test_delete_all() {
 
     RocksDbManager::get().shutdown().await;
 }
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_prefix_scan() {
+    let mut rocksdb = storage_test_environment().await;
+    let service_id = ServiceId::with_partition_key(1337, "svc-1", "key-1");
+
+    let mut txn = rocksdb.transaction();
+    txn.put_user_state(&service_id, b"cart/item/1", b"v1", None)
+        .unwrap();
+    txn.put_user_state(&service_id, b"cart/item/10", b"v10", None)
+        .unwrap();
+    txn.put_user_state(&service_id, b"cart/owner", b"owner", None)
+        .unwrap();
+    txn.put_user_state(
+        &service_id,
+        b"cart/item/2",
+        b"v2",
+        Some(MillisSinceEpoch::now() + Duration::from_secs(60)),
+    )
+    .unwrap();
+    txn.put_user_state(&service_id, b"z", b"z", None).unwrap();
+    txn.put_user_state(
+        &ServiceId::with_partition_key(1337, "svc-1", "key-2"),
+        b"cart/item/3",
+        b"v3",
+        None,
+    )
+    .unwrap();
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    assert_stream_eq(
+        txn.get_user_states_by_prefix(&service_id, b"cart/item/")
+            .unwrap(),
+        vec![
+            (
+                Bytes::from_static(b"cart/item/1"),
+                Bytes::from_static(b"v1"),
+            ),
+            (
+                Bytes::from_static(b"cart/item/2"),
+                Bytes::from_static(b"v2"),
+            ),
+            (
+                Bytes::from_static(b"cart/item/10"),
+                Bytes::from_static(b"v10"),
+            ),
+        ],
+    )
+    .await;
+    assert_stream_eq(
+        txn.get_user_states_by_prefix(&service_id, b"unknown/")
+            .unwrap(),
+        vec![],
+    )
+    .await;
+
+    RocksDbManager::get().shutdown().await;
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ttl() {
+    let mut rocksdb = storage_test_environment().await;
+    let service_id = ServiceId::with_partition_key(1337, "svc-1", "key-1");
+    let one_hour = Some(MillisSinceEpoch::now() + Duration::from_secs(60 * 60));
+
+    let mut txn = rocksdb.transaction();
+    txn.put_user_state(&service_id, b"k1", b"v1", None).unwrap();
+    txn.put_user_state(&service_id, b"k2", b"v2", one_hour)
+        .unwrap();
+    txn.put_user_state(
+        &service_id,
+        b"k3",
+        b"v3",
+        Some(MillisSinceEpoch::UNIX_EPOCH),
+    )
+    .unwrap();
+    txn.put_user_state(&service_id, b"k4", b"expiring", one_hour)
+        .unwrap();
+    // Overrides the entry with ttl
+    txn.put_user_state(&service_id, b"k4", b"v4", None).unwrap();
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    assert_eq!(
+        txn.get_user_state(&service_id, b"k2").await.unwrap(),
+        Some(Bytes::from_static(b"v2"))
+    );
+    assert_eq!(txn.get_user_state(&service_id, b"k3").await.unwrap(), None);
+    let expected = vec![
+        (Bytes::from_static(b"k1"), Bytes::from_static(b"v1")),
+        (Bytes::from_static(b"k2"), Bytes::from_static(b"v2")),
+        (Bytes::from_static(b"k4"), Bytes::from_static(b"v4")),
+    ];
+    assert_stream_eq(
+        txn.get_all_user_states_for_service(&service_id).unwrap(),
+        expected.clone(),
+    )
+    .await;
+    drop(txn);
+
+    let scanned = Arc::new(Mutex::new(vec![]));
+    let collector = Arc::clone(&scanned);
+    rocksdb
+        .for_each_user_state(0..=PartitionKey::MAX, move |(_, key, value)| {
+            collector
+                .lock()
+                .unwrap()
+                .push((key, Bytes::copy_from_slice(value)));
+            ControlFlow::Continue(())
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    assert_eq!(*scanned.lock().unwrap(), expected);
+
+    // The transactions evaluate the expiration against their state clock
+    let mut txn = rocksdb.transaction();
+    txn.set_state_clock(MillisSinceEpoch::now() + Duration::from_secs(2 * 60 * 60));
+    assert_eq!(txn.get_user_state(&service_id, b"k2").await.unwrap(), None);
+    assert_stream_eq(
+        txn.get_all_user_states_for_service(&service_id).unwrap(),
+        vec![
+            (Bytes::from_static(b"k1"), Bytes::from_static(b"v1")),
+            (Bytes::from_static(b"k4"), Bytes::from_static(b"v4")),
+        ],
+    )
+    .await;
+    drop(txn);
+
+    // Setting a ttl overrides the entry without ttl
+    let mut txn = rocksdb.transaction();
+    txn.put_user_state(
+        &service_id,
+        b"k1",
+        b"v1",
+        Some(MillisSinceEpoch::UNIX_EPOCH),
+    )
+    .unwrap();
+    txn.commit().await.expect("should not fail");
+    assert_eq!(
+        rocksdb.get_user_state(&service_id, b"k1").await.unwrap(),
+        None
+    );
+
+    RocksDbManager::get().shutdown().await;
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_user_states() {
+    let mut rocksdb = storage_test_environment().await;
+    let service_id = ServiceId::with_partition_key(1337, "svc-1", "key-1");
+    let one_hour = Some(MillisSinceEpoch::now() + Duration::from_secs(60 * 60));
+
+    let mut txn = rocksdb.transaction();
+    for i in 0..10u8 {
+        txn.put_user_state(&service_id, [b'k', b'0' + i], [b'v', b'0' + i], None)
+            .unwrap();
+    }
+    // Entries with ttl interleaved with, shadowed by, and following the ones without ttl
+    txn.put_user_state(&service_id, b"k1a", b"expiring", one_hour)
+        .unwrap();
+    txn.put_user_state(&service_id, b"k2", b"expiring", one_hour)
+        .unwrap();
+    txn.put_user_state(&service_id, b"k2", b"v2", None).unwrap();
+    txn.put_user_state(&service_id, b"z", b"expiring", one_hour)
+        .unwrap();
+    txn.put_user_state(
+        &service_id,
+        b"zz",
+        b"expired",
+        Some(MillisSinceEpoch::UNIX_EPOCH),
+    )
+    .unwrap();
+    txn.put_user_state(
+        &ServiceId::with_partition_key(1337, "svc-1", "key-2"),
+        b"k0",
+        b"v0",
+        None,
+    )
+    .unwrap();
+    txn.commit().await.expect("should not fail");
+
+    let mut txn = rocksdb.transaction();
+    let expected: Vec<_> = txn
+        .get_all_user_states_for_service(&service_id)
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    drop(txn);
+    assert_eq!(expected.len(), 12);
+
+    // The serialized keys alone exceed 8 bytes, so every scan stops after a single entry, and a
+    // batch holds the entries up to the smallest key read from both tables. The last batch holds
+    // the last entries of both tables. The state keys are sorted by length first.
+    let batches: Vec<_> = rocksdb
+        .stream_user_states_for_service(&service_id, NonZeroUsize::new(8).unwrap())
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        batches.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2]
+    );
+    assert_eq!(batches.concat(), expected);
+
+    // A single batch if it fits
+    let batches: Vec<_> = rocksdb
+        .stream_user_states_for_service(&service_id, NonZeroUsize::new(usize::MAX).unwrap())
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(batches, vec![expected]);
+
+    // No batches without state
+    let batches: Vec<Vec<_>> = rocksdb
+        .stream_user_states_for_service(
+            &ServiceId::with_partition_key(1337, "svc-1", "unknown"),
+            NonZeroUsize::new(8).unwrap(),
+        )
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(batches.is_empty());
+
+    RocksDbManager::get().shutdown().await;
+}
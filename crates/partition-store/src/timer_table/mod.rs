@@ -108,6 +108,15 @@ fn exclusive_start_key_range(
                     },
                 }
             }
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => {
+                let incremented_invocation_uuid = increment_invocation_uuid(invocation_uuid);
+                TimerKey {
+                    timestamp: timer_key.timestamp,
+                    kind: TimerKeyKind::InvocationDeadline {
+                        invocation_uuid: incremented_invocation_uuid,
+                    },
+                }
+            }
         };
 
         let lower_bound = write_timer_key(partition_id, &next_timer_key);
@@ -288,6 +297,21 @@ mod tests {
         assert_eq!(got, key);
     }
 
+    #[test]
+    fn round_trip_invocation_deadline_kind() {
+        let key = TimerKey {
+            kind: TimerKeyKind::InvocationDeadline {
+                invocation_uuid: FIXTURE_INVOCATION,
+            },
+            timestamp: 87654321,
+        };
+
+        let key_bytes = write_timer_key(PartitionId::from(1337), &key).serialize();
+        let got = timer_key_from_key_slice(&key_bytes).expect("should not fail");
+
+        assert_eq!(got, key);
+    }
+
     #[test]
     fn test_lexicographical_sorting_by_timestamp() {
         let kinds = [
@@ -304,6 +328,9 @@ mod tests {
             TimerKeyKind::NeoInvoke {
                 invocation_uuid: FIXTURE_INVOCATION,
             },
+            TimerKeyKind::InvocationDeadline {
+                invocation_uuid: FIXTURE_INVOCATION,
+            },
         ];
 
         for first_kind in &kinds {
@@ -438,8 +465,24 @@ mod tests {
             timestamp: 300,
         };
 
+        let d = TimerKey {
+            kind: TimerKeyKind::NeoInvoke {
+                invocation_uuid: FIXTURE_INVOCATION,
+            },
+            timestamp: 300,
+        };
+
+        let e = TimerKey {
+            kind: TimerKeyKind::InvocationDeadline {
+                invocation_uuid: FIXTURE_INVOCATION,
+            },
+            timestamp: 300,
+        };
+
         assert_in_range(&a, &b);
         assert_in_range(&b, &c);
+        assert_in_range(&c, &d);
+        assert_in_range(&d, &e);
     }
 
     #[track_caller]
@@ -516,6 +559,9 @@ mod tests {
                         invocation_uuid: InvocationUuid::mock_random(),
                     }
                 }
+                TimerKeyKindDiscriminants::InvocationDeadline => TimerKeyKind::InvocationDeadline {
+                    invocation_uuid: InvocationUuid::mock_random(),
+                },
             }
         };
 
@@ -149,6 +149,11 @@ pub struct DbSpec {
     /// otherwise opening the database will fail with `UnknownColumnFamily` error.
     #[builder(default)]
     pub(crate) ensure_column_families: Vec<CfName>,
+    /// Opens the database as a read-only secondary instance of the database in `path`, e.g. to
+    /// inspect a copy of a node's data directory. The secondary instance keeps its own info logs
+    /// in this directory.
+    #[builder(setter(strip_option, name = "open_as_secondary"), default)]
+    pub(crate) secondary_path: Option<PathBuf>,
     /// Configurator for the database-level options.
     pub(crate) db_configurator: Box<dyn DbConfigurator + Send + Sync>,
     /// Options of the column family are applied after the values loaded from
@@ -167,6 +172,10 @@ impl DbSpec {
     pub fn name(&self) -> &DbName {
         &self.name
     }
+
+    pub fn is_secondary(&self) -> bool {
+        self.secondary_path.is_some()
+    }
 }
 
 impl DbSpecBuilder {
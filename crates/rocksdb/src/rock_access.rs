@@ -115,10 +115,14 @@ impl RocksAccess {
         write_buffer_manager: &WriteBufferManager,
         global_cache: &Cache,
     ) -> Result<Self, RocksError> {
-        let db_options =
+        let mut db_options =
             db_spec
                 .db_configurator
                 .get_db_options(db_spec.name(), env, write_buffer_manager);
+        if db_spec.is_secondary() {
+            // secondary instances must keep all the files of the primary open
+            db_options.set_max_open_files(-1);
+        }
         let mut all_cfs: HashSet<CfName> = match rocksdb::DB::list_cf(&db_options, &db_spec.path) {
             Ok(existing) => existing.into_iter().map(Into::into).collect(),
             Err(e) => {
@@ -141,13 +145,27 @@ impl RocksAccess {
             prepare_descriptors(&db_spec, write_buffer_manager, global_cache, &mut all_cfs)?;
         trace!(path = %db_spec.path.display(), "Opening rocksdb database '{}'", db_spec.name());
 
-        rocksdb::DB::open_cf_descriptors(&db_options, &db_spec.path, descriptors)
-            .map(|db| RocksAccess {
-                db,
-                db_options,
-                db_spec,
-            })
-            .map_err(RocksError::from_rocksdb_error)
+        let db = if let Some(secondary_path) = &db_spec.secondary_path {
+            trace!(
+                secondary_path = %secondary_path.display(),
+                "Opening rocksdb database '{}' as secondary instance", db_spec.name()
+            );
+            rocksdb::DB::open_cf_descriptors_as_secondary(
+                &db_options,
+                &db_spec.path,
+                secondary_path,
+                descriptors,
+            )
+        } else {
+            rocksdb::DB::open_cf_descriptors(&db_options, &db_spec.path, descriptors)
+        };
+
+        db.map(|db| RocksAccess {
+            db,
+            db_options,
+            db_spec,
+        })
+        .map_err(RocksError::from_rocksdb_error)
     }
 
     pub fn spec(&self) -> &DbSpec {
@@ -176,6 +194,13 @@ impl RocksAccess {
     #[tracing::instrument(skip_all, fields(db = %self.name()))]
     pub(crate) fn shutdown(&self) {
         let _x = RocksDbPerfGuard::new("shutdown");
+        if self.db_spec.is_secondary() {
+            // nothing to flush, secondary instances are read-only
+            self.db.cancel_all_background_work(true);
+            info!("Rocksdb '{}' secondary instance was closed", self.name());
+            return;
+        }
+
         if let Err(e) = self.db.flush_wal(true) {
             warn!(
                 db = %self.name(),
@@ -15,7 +15,7 @@ use std::mem;
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use bytes_utils::SegmentedBuf;
-use tracing::warn;
+use tracing::{debug, warn};
 
 use restate_serde_util::ByteCount;
 use restate_types::service_protocol::ServiceProtocolVersion;
@@ -27,19 +27,32 @@ pub enum EncodingError {
     DecodeMessage(MessageType, #[source] prost::DecodeError),
     #[error(transparent)]
     UnknownMessageType(#[from] UnknownMessageType),
-    #[error("hit message size limit: {0} >= {1}")]
+    #[error("received message {0:?} of {1} bytes, which hits the message size limit of {2} bytes")]
     #[code(restate_errors::RT0003)]
-    MessageSizeLimit(usize, usize),
+    MessageSizeLimit(MessageType, usize, usize),
+    #[error(
+        "cannot send message {0:?} of {1} bytes, which hits the outgoing message size limit of {2} bytes"
+    )]
+    #[code(restate_errors::RT0003)]
+    OutgoingMessageSizeLimit(MessageType, usize, usize),
+    #[error(
+        "received message {0:?} with the reserved header flag bits {1:#06x} set. This looks like a bug of the SDK, or the SDK implements a different revision of the protocol version"
+    )]
+    ReservedHeaderFlags(MessageType, u16),
 }
 
 // --- Input message encoder
 
 pub struct Encoder {
     arena: BytesMut,
+    message_size_limit: usize,
 }
 
 impl Encoder {
-    pub fn new(service_protocol_version: ServiceProtocolVersion) -> Self {
+    pub fn new(
+        service_protocol_version: ServiceProtocolVersion,
+        message_size_limit: Option<usize>,
+    ) -> Self {
         assert_ne!(
             service_protocol_version,
             ServiceProtocolVersion::Unspecified,
@@ -47,22 +60,39 @@ impl Encoder {
         );
         Self {
             arena: BytesMut::with_capacity(1024),
+            message_size_limit: message_size_limit.unwrap_or(usize::MAX),
         }
     }
 
+    /// Lowers the message size limit, e.g. to the one advertised by the deployment.
+    pub fn restrict_message_size_limit(&mut self, message_size_limit: usize) {
+        self.message_size_limit = self.message_size_limit.min(message_size_limit);
+    }
+
+    /// Returns true if the message can be sent without hitting the message size limit.
+    pub fn fits(&self, msg: &Message) -> bool {
+        msg.encoded_len() < self.message_size_limit
+    }
+
     /// Encodes a message to bytes
-    pub fn encode(&mut self, msg: Message) -> Bytes {
+    pub fn encode(&mut self, msg: Message) -> Result<Bytes, EncodingError> {
+        self.check_message_size(msg.ty(), msg.encoded_len())?;
         self.arena.reserve(self.encoded_len(&msg));
         self.encode_to_arena(msg).expect(
             "Encoding messages should be infallible, \
             this error indicates a bug in the invoker code. \
             Please contact the Restate developers.",
         );
-        self.arena.split().freeze()
+        Ok(self.arena.split().freeze())
     }
 
     /// Encodes a raw message to bytes
-    pub fn encode_raw(&mut self, msg_ty: MessageType, content: Bytes) -> Bytes {
+    pub fn encode_raw(
+        &mut self,
+        msg_ty: MessageType,
+        content: Bytes,
+    ) -> Result<Bytes, EncodingError> {
+        self.check_message_size(msg_ty, content.len())?;
         self.arena.reserve(8 + content.len());
         let len: u32 = content
             .len()
@@ -70,7 +100,22 @@ impl Encoder {
             .expect("Protocol messages can't be larger than u32");
         self.arena.put_u64(MessageHeader::new(msg_ty, len).into());
         self.arena.put(content);
-        self.arena.split().freeze()
+        Ok(self.arena.split().freeze())
+    }
+
+    fn check_message_size(
+        &self,
+        msg_ty: MessageType,
+        message_length: usize,
+    ) -> Result<(), EncodingError> {
+        if message_length >= self.message_size_limit {
+            return Err(EncodingError::OutgoingMessageSizeLimit(
+                msg_ty,
+                message_length,
+                self.message_size_limit,
+            ));
+        }
+        Ok(())
     }
 
     /// Includes header len
@@ -109,6 +154,7 @@ pub struct Decoder {
     state: DecoderState,
     message_size_warning: usize,
     message_size_limit: usize,
+    strict_header_flags: bool,
 }
 
 impl Decoder {
@@ -127,9 +173,18 @@ impl Decoder {
             state: DecoderState::WaitingHeader,
             message_size_warning,
             message_size_limit: message_size_limit.unwrap_or(usize::MAX),
+            strict_header_flags: false,
         }
     }
 
+    /// Rejects the messages with reserved header flag bits set, instead of ignoring them.
+    ///
+    /// No protocol version mandates it yet, because older SDKs might set them.
+    pub fn with_strict_header_flags(mut self, strict_header_flags: bool) -> Self {
+        self.strict_header_flags = strict_header_flags;
+        self
+    }
+
     pub fn has_remaining(&self) -> bool {
         self.buf.has_remaining()
     }
@@ -152,6 +207,7 @@ impl Decoder {
                 &mut self.buf,
                 self.message_size_warning,
                 self.message_size_limit,
+                self.strict_header_flags,
             )? {
                 return Ok(Some(res));
             }
@@ -179,12 +235,26 @@ impl DecoderState {
         mut buf: impl Buf,
         message_size_warning: usize,
         message_size_limit: usize,
+        strict_header_flags: bool,
     ) -> Result<Option<(MessageHeader, Message)>, EncodingError> {
         let mut res = None;
 
         *self = match mem::take(self) {
             DecoderState::WaitingHeader => {
                 let header: MessageHeader = buf.get_u64().try_into()?;
+                if header.reserved_flags() != 0 {
+                    if strict_header_flags {
+                        return Err(EncodingError::ReservedHeaderFlags(
+                            header.message_type(),
+                            header.reserved_flags(),
+                        ));
+                    }
+                    debug!(
+                        "Ignoring the reserved header flag bits {:#06x} of message {:?}",
+                        header.reserved_flags(),
+                        header.message_type()
+                    );
+                }
                 let message_length =
                     usize::try_from(header.frame_length()).expect("u32 must convert into usize");
 
@@ -200,6 +270,7 @@ impl DecoderState {
                 }
                 if message_length >= message_size_limit {
                     return Err(EncodingError::MessageSizeLimit(
+                        header.message_type(),
                         message_length,
                         message_size_limit,
                     ));
@@ -229,7 +300,7 @@ mod tests {
 
     #[test]
     fn fill_decoder_with_several_messages() {
-        let mut encoder = Encoder::new(ServiceProtocolVersion::V1);
+        let mut encoder = Encoder::new(ServiceProtocolVersion::V1, None);
         let mut decoder = Decoder::new(ServiceProtocolVersion::V1, usize::MAX, None);
 
         let expected_msg_0 = Message::new_start_message(
@@ -242,14 +313,15 @@ mod tests {
             10,
             Duration::ZERO,
             10,
+            None,
         );
 
         let expected_msg_1 = Message::InputCommand(Bytes::from_static(b"123"));
         let expected_msg_2 = Message::CallCompletionNotification(Bytes::from_static(b"456"));
 
-        decoder.push(encoder.encode(expected_msg_0.clone()));
-        decoder.push(encoder.encode(expected_msg_1.clone()));
-        decoder.push(encoder.encode(expected_msg_2.clone()));
+        decoder.push(encoder.encode(expected_msg_0.clone()).unwrap());
+        decoder.push(encoder.encode(expected_msg_1.clone()).unwrap());
+        decoder.push(encoder.encode(expected_msg_2.clone()).unwrap());
 
         let (actual_msg_header_0, actual_msg_0) = decoder.consume_next().unwrap().unwrap();
         assert_eq!(actual_msg_header_0.message_type(), MessageType::Start);
@@ -283,11 +355,11 @@ mod tests {
     }
 
     fn partial_decoding_test(split_index: usize) {
-        let mut encoder = Encoder::new(ServiceProtocolVersion::V1);
+        let mut encoder = Encoder::new(ServiceProtocolVersion::V1, None);
         let mut decoder = Decoder::new(ServiceProtocolVersion::V1, usize::MAX, None);
 
         let expected_msg = Message::InputCommand(Bytes::from_static(b"123"));
-        let expected_msg_encoded = encoder.encode(expected_msg.clone());
+        let expected_msg_encoded = encoder.encode(expected_msg.clone()).unwrap();
 
         decoder.push(expected_msg_encoded.slice(0..split_index));
         assert!(decoder.consume_next().unwrap().is_none());
@@ -309,16 +381,73 @@ mod tests {
             Some(u8::MAX as usize),
         );
 
-        let mut encoder = Encoder::new(ServiceProtocolVersion::V1);
+        let mut encoder = Encoder::new(ServiceProtocolVersion::V1, None);
         let message = Message::InputCommand((0..=u8::MAX).collect::<Vec<_>>().into());
         let expected_msg_size = message.encoded_len();
-        let msg = encoder.encode(message);
+        let msg = encoder.encode(message).unwrap();
 
         decoder.push(msg.clone());
         let_assert!(
-            EncodingError::MessageSizeLimit(msg_size, limit) = decoder.consume_next().unwrap_err()
+            EncodingError::MessageSizeLimit(msg_ty, msg_size, limit) =
+                decoder.consume_next().unwrap_err()
         );
+        assert_eq!(msg_ty, MessageType::InputCommand);
         assert_eq!(msg_size, expected_msg_size);
         assert_eq!(limit, u8::MAX as usize)
     }
+
+    #[test]
+    fn reserved_header_flags() {
+        let mut encoder = Encoder::new(ServiceProtocolVersion::V6, None);
+        let message = Message::CallCompletionNotification(Bytes::from_static(b"456"));
+        let mut encoded = BytesMut::from(&encoder.encode(message.clone()).unwrap()[..]);
+        // set a reserved flag bit
+        encoded[2] |= 0x80;
+        let encoded = encoded.freeze();
+
+        let mut lenient_decoder = Decoder::new(ServiceProtocolVersion::V6, usize::MAX, None);
+        lenient_decoder.push(encoded.clone());
+        let (header, actual_message) = lenient_decoder.consume_next().unwrap().unwrap();
+        assert_eq!(header.reserved_flags(), 0x8000);
+        assert_eq!(actual_message, message);
+
+        let mut strict_decoder = Decoder::new(ServiceProtocolVersion::V6, usize::MAX, None)
+            .with_strict_header_flags(true);
+        strict_decoder.push(encoded);
+        let_assert!(
+            EncodingError::ReservedHeaderFlags(msg_ty, reserved_flags) =
+                strict_decoder.consume_next().unwrap_err()
+        );
+        assert_eq!(msg_ty, MessageType::CallCompletionNotification);
+        assert_eq!(reserved_flags, 0x8000);
+    }
+
+    #[test]
+    fn reject_outgoing_message_over_negotiated_limit() {
+        let mut encoder = Encoder::new(ServiceProtocolVersion::V1, Some(u16::MAX as usize));
+        encoder.restrict_message_size_limit(u8::MAX as usize);
+
+        let small_message = Message::InputCommand(Bytes::from_static(b"123"));
+        assert!(encoder.fits(&small_message));
+        encoder.encode(small_message).unwrap();
+
+        let message = Message::InputCommand((0..=u8::MAX).collect::<Vec<_>>().into());
+        assert!(!encoder.fits(&message));
+        let_assert!(
+            EncodingError::OutgoingMessageSizeLimit(msg_ty, msg_size, limit) =
+                encoder.encode(message).unwrap_err()
+        );
+        assert_eq!(msg_ty, MessageType::InputCommand);
+        assert_eq!(msg_size, u8::MAX as usize + 1);
+        assert_eq!(limit, u8::MAX as usize);
+
+        let_assert!(
+            EncodingError::OutgoingMessageSizeLimit(..) = encoder
+                .encode_raw(
+                    MessageType::InputCommand,
+                    (0..=u8::MAX).collect::<Vec<_>>().into()
+                )
+                .unwrap_err()
+        );
+    }
 }
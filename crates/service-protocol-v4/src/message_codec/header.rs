@@ -10,6 +10,8 @@
 
 use super::{MessageType, UnknownMessageType};
 
+const FLAGS_MASK: u64 = 0xFFFF_0000_0000;
+const FLAGS_SHIFT: u32 = 32;
 const REQUIRES_ACK_MASK: u64 = 0x8000_0000_0000;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -19,6 +21,8 @@ pub struct MessageHeader {
 
     // --- Flags
     requires_ack_flag: Option<bool>,
+    /// Flag bits which are set but not defined for the message type.
+    reserved_flags: u16,
 }
 
 impl MessageHeader {
@@ -33,6 +37,7 @@ impl MessageHeader {
             ty,
             length,
             requires_ack_flag,
+            reserved_flags: 0,
         }
     }
 
@@ -50,6 +55,12 @@ impl MessageHeader {
     pub fn frame_length(&self) -> u32 {
         self.length
     }
+
+    /// Flag bits set by the sender which the protocol doesn't define for the message type.
+    #[inline]
+    pub fn reserved_flags(&self) -> u16 {
+        self.reserved_flags
+    }
 }
 
 macro_rules! read_flag_if {
@@ -74,7 +85,16 @@ impl TryFrom<u64> for MessageHeader {
         let requires_ack_flag = read_flag_if!(ty.allows_ack(), value, REQUIRES_ACK_MASK);
         let length = value as u32;
 
-        Ok(MessageHeader::_new(ty, requires_ack_flag, length))
+        let mut defined_flags = 0;
+        if ty.allows_ack() {
+            defined_flags |= REQUIRES_ACK_MASK;
+        }
+        let reserved_flags = ((value & FLAGS_MASK & !defined_flags) >> FLAGS_SHIFT) as u16;
+
+        Ok(MessageHeader {
+            reserved_flags,
+            ..MessageHeader::_new(ty, requires_ack_flag, length)
+        })
     }
 }
 
@@ -123,10 +143,28 @@ mod tests {
                 assert_eq!(header.message_type(), $ty);
                 assert_eq!(header.requires_ack(), $requires_ack);
                 assert_eq!(header.frame_length(), $len);
+                assert_eq!(header.reserved_flags(), 0);
             }
         };
     }
 
+    #[test]
+    fn reserved_flags() {
+        let serialized: u64 = MessageHeader::new(CallCompletionNotification, 22).into();
+        let header =
+            MessageHeader::try_from(serialized | REQUIRES_ACK_MASK | 0x0001_0000_0000).unwrap();
+        assert_eq!(header.requires_ack(), None);
+        assert_eq!(header.reserved_flags(), 0x8001);
+        assert_eq!(header.frame_length(), 22);
+
+        // the requires ack flag is defined for commands
+        let serialized: u64 = MessageHeader::new(SetStateCommand, 22).into();
+        let header =
+            MessageHeader::try_from(serialized | REQUIRES_ACK_MASK | 0x0001_0000_0000).unwrap();
+        assert_eq!(header.requires_ack(), Some(true));
+        assert_eq!(header.reserved_flags(), 0x0001);
+    }
+
     roundtrip_test!(
         call_completion_notification,
         MessageHeader::new(CallCompletionNotification, 22),
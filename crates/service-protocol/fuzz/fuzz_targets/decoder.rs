@@ -0,0 +1,43 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Feeds arbitrary bytes to the message decoder, which must never panic nor wait for more input
+//! than a valid frame requires.
+//!
+//! Run it from `crates/service-protocol` with `cargo +nightly fuzz run decoder`.
+
+#![no_main]
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+
+use restate_service_protocol::message::Decoder;
+use restate_types::service_protocol::ServiceProtocolVersion;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte decides how the input is chunked, to exercise the partial decoding
+    let Some((&chunk_size, data)) = data.split_first() else {
+        return;
+    };
+    let chunk_size = usize::from(chunk_size).max(1);
+
+    let mut decoder = Decoder::new(ServiceProtocolVersion::V3, usize::MAX, None);
+    for chunk in data.chunks(chunk_size) {
+        decoder.push(Bytes::copy_from_slice(chunk));
+        loop {
+            match decoder.consume_next() {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                // Decoding errors fail the invocation, the decoder is dropped afterwards
+                Err(_) => return,
+            }
+        }
+    }
+});
@@ -19,6 +19,7 @@ use restate_types::journal::raw::{PlainEntryHeader, RawEntry};
 use restate_types::service_protocol::ServiceProtocolVersion;
 
 use super::header::UnknownMessageType;
+use super::validation::validate_header;
 use super::*;
 
 #[derive(Debug, codederror::CodedError, thiserror::Error)]
@@ -31,6 +32,8 @@ pub enum EncodingError {
     #[error("hit message size limit: {0} >= {1}")]
     #[code(restate_errors::RT0003)]
     MessageSizeLimit(usize, usize),
+    #[error("protocol violation: {0}. This looks like a bug of the SDK")]
+    ProtocolViolation(#[from] ProtocolViolation),
 }
 
 // --- Input message encoder
@@ -205,7 +208,8 @@ impl DecoderState {
 
         *self = match mem::take(self) {
             DecoderState::WaitingHeader => {
-                let header: MessageHeader = buf.get_u64().try_into()?;
+                let raw_header = buf.get_u64();
+                let header: MessageHeader = raw_header.try_into()?;
                 let message_length =
                     usize::try_from(header.frame_length()).expect("u32 must convert into usize");
 
@@ -225,6 +229,7 @@ impl DecoderState {
                         message_size_limit,
                     ));
                 }
+                validate_header(raw_header, &header)?;
 
                 DecoderState::WaitingPayload(header)
             }
@@ -489,4 +494,31 @@ mod tests {
         assert_eq!(msg_size, expected_msg_size);
         assert_eq!(limit, u8::MAX as usize)
     }
+
+    #[test]
+    fn reject_malformed_header_without_waiting_for_the_payload() {
+        let mut decoder = Decoder::new(ServiceProtocolVersion::V1, usize::MAX, None);
+
+        // Output entry announcing a 4 GiB frame
+        decoder.push(Bytes::copy_from_slice(
+            &0x0401_0000_FFFF_FFFF_u64.to_be_bytes(),
+        ));
+        let_assert!(
+            EncodingError::ProtocolViolation(ProtocolViolation::FrameTooLarge { .. }) =
+                decoder.consume_next().unwrap_err()
+        );
+
+        let mut decoder = Decoder::new(ServiceProtocolVersion::V1, usize::MAX, None);
+
+        // End message with the completed flag
+        decoder.push(Bytes::copy_from_slice(
+            &0x0005_0001_0000_0000_u64.to_be_bytes(),
+        ));
+        let_assert!(
+            EncodingError::ProtocolViolation(ProtocolViolation::UnexpectedFlags {
+                message_type: MessageType::End,
+                flags: 0x0001
+            }) = decoder.consume_next().unwrap_err()
+        );
+    }
 }
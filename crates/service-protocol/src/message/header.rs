@@ -11,8 +11,8 @@
 use restate_types::journal::EntryType;
 
 const CUSTOM_MESSAGE_MASK: u16 = 0xFC00;
-const COMPLETED_MASK: u64 = 0x0001_0000_0000;
-const REQUIRES_ACK_MASK: u64 = 0x8000_0000_0000;
+pub(super) const COMPLETED_MASK: u64 = 0x0001_0000_0000;
+pub(super) const REQUIRES_ACK_MASK: u64 = 0x8000_0000_0000;
 
 type MessageTypeId = u16;
 
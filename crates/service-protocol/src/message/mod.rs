@@ -13,6 +13,7 @@
 
 mod encoding;
 mod header;
+mod validation;
 
 use std::time::Duration;
 
@@ -26,6 +27,7 @@ use restate_types::service_protocol;
 
 pub use encoding::{Decoder, Encoder, EncodingError};
 pub use header::{MessageHeader, MessageKind, MessageType};
+pub use validation::{MAX_FRAME_LENGTH, ProtocolViolation};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ProtocolMessage {
@@ -0,0 +1,137 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Validation of the messages received from the SDKs, before decoding their payload.
+//!
+//! A misbehaving SDK must only fail the invocation it's serving, hence every inconsistency
+//! detected here is reported as a [`ProtocolViolation`] rather than a panic.
+
+use super::header::{COMPLETED_MASK, REQUIRES_ACK_MASK};
+use super::{MessageHeader, MessageType};
+
+/// Upper bound of the frame length, applied even when no message size limit is configured,
+/// so that the decoder never buffers an arbitrarily large frame announced by a corrupted header.
+pub const MAX_FRAME_LENGTH: usize = 512 * 1024 * 1024;
+
+/// Bits of the header reserved for the flags.
+const FLAGS_MASK: u64 = 0xFFFF_0000_0000;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProtocolViolation {
+    #[error(
+        "message {message_type:?} announces a frame of {length} bytes, exceeding the maximum frame length of {max_frame_length} bytes"
+    )]
+    FrameTooLarge {
+        message_type: MessageType,
+        length: usize,
+        max_frame_length: usize,
+    },
+    #[error(
+        "message {message_type:?} has the flag bits {flags:#06x} set, which are not defined for this message type"
+    )]
+    UnexpectedFlags {
+        message_type: MessageType,
+        flags: u16,
+    },
+}
+
+/// Validates the header as read from the wire.
+pub(super) fn validate_header(
+    raw_header: u64,
+    header: &MessageHeader,
+) -> Result<(), ProtocolViolation> {
+    let message_type = header.message_type();
+
+    let length = usize::try_from(header.frame_length()).expect("u32 must convert into usize");
+    if length > MAX_FRAME_LENGTH {
+        return Err(ProtocolViolation::FrameTooLarge {
+            message_type,
+            length,
+            max_frame_length: MAX_FRAME_LENGTH,
+        });
+    }
+
+    let mut allowed_flags = 0;
+    if header.completed().is_some() {
+        allowed_flags |= COMPLETED_MASK;
+    }
+    if header.requires_ack().is_some() {
+        allowed_flags |= REQUIRES_ACK_MASK;
+    }
+    let unexpected_flags = raw_header & FLAGS_MASK & !allowed_flags;
+    if unexpected_flags != 0 {
+        return Err(ProtocolViolation::UnexpectedFlags {
+            message_type,
+            flags: (unexpected_flags >> 32) as u16,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(raw_header: u64) -> Result<(), ProtocolViolation> {
+        let header = MessageHeader::try_from(raw_header).unwrap();
+        validate_header(raw_header, &header)
+    }
+
+    #[test]
+    fn accept_defined_flags() {
+        // Completed and requires ack flags of a get state entry
+        assert_eq!(validate(0x0800_8001_0000_000A), Ok(()));
+        // Requires ack flag of a set state entry
+        assert_eq!(validate(0x0801_8000_0000_000A), Ok(()));
+        // No flags for a suspension message
+        assert_eq!(validate(0x0002_0000_0000_000A), Ok(()));
+    }
+
+    #[test]
+    fn reject_undefined_flags() {
+        // Completed flag on an entry which cannot be completed
+        assert_eq!(
+            validate(0x0801_0001_0000_000A),
+            Err(ProtocolViolation::UnexpectedFlags {
+                message_type: MessageType::SetStateEntry,
+                flags: 0x0001
+            })
+        );
+        // Requires ack flag on a core message
+        assert_eq!(
+            validate(0x0003_8000_0000_000A),
+            Err(ProtocolViolation::UnexpectedFlags {
+                message_type: MessageType::Error,
+                flags: 0x8000
+            })
+        );
+        // Reserved bits
+        assert_eq!(
+            validate(0x0C00_0101_0000_000A),
+            Err(ProtocolViolation::UnexpectedFlags {
+                message_type: MessageType::SleepEntry,
+                flags: 0x0100
+            })
+        );
+    }
+
+    #[test]
+    fn reject_frames_exceeding_max_length() {
+        assert_eq!(
+            validate(0x0401_0000_FFFF_FFFF),
+            Err(ProtocolViolation::FrameTooLarge {
+                message_type: MessageType::OutputEntry,
+                length: u32::MAX as usize,
+                max_frame_length: MAX_FRAME_LENGTH
+            })
+        );
+    }
+}
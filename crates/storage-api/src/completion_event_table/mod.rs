@@ -0,0 +1,79 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::future::Future;
+
+use bytes::Bytes;
+
+use restate_types::flexbuffers_storage_encode_decode;
+use restate_types::identifiers::InvocationId;
+use restate_types::logs::Lsn;
+
+use crate::Result;
+
+/// Position of a completion event in the completion event table: the lsn of the log record whose
+/// application completed the invocation, and the index of the event among the events of that
+/// record. Sequences increase monotonically and are the same on every replica of the partition.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[display("{lsn}/{index}")]
+pub struct CompletionEventSequence {
+    pub lsn: Lsn,
+    pub index: u32,
+}
+
+impl CompletionEventSequence {
+    pub const fn new(lsn: Lsn, index: u32) -> Self {
+        Self { lsn, index }
+    }
+}
+
+/// Event of a completed or failed invocation, stored until the partition leader delivered it to
+/// the completion sink.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CompletionEvent {
+    pub invocation_id: InvocationId,
+    /// The JSON encoded event.
+    pub payload: Bytes,
+}
+
+flexbuffers_storage_encode_decode!(CompletionEvent);
+
+pub trait ReadCompletionEventTable {
+    /// Returns up to `limit` completion events following the given sequence, or from the first
+    /// one if `None`, ordered by sequence.
+    fn get_completion_events(
+        &mut self,
+        after: Option<CompletionEventSequence>,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<(CompletionEventSequence, CompletionEvent)>>> + Send;
+}
+
+pub trait WriteCompletionEventTable {
+    fn put_completion_event(
+        &mut self,
+        sequence: CompletionEventSequence,
+        event: &CompletionEvent,
+    ) -> Result<()>;
+
+    /// Deletes the completion events up to, and including, the given sequence.
+    fn truncate_completion_events(&mut self, up_to: CompletionEventSequence) -> Result<()>;
+}
@@ -13,6 +13,7 @@ use std::future::Future;
 use restate_types::SemanticRestateVersion;
 use restate_types::logs::Lsn;
 use restate_types::message::MessageIndex;
+use restate_types::partitions::settings::PartitionSettings;
 use restate_types::schema::Schema;
 use restate_types::time::MillisSinceEpoch;
 
@@ -35,6 +36,14 @@ pub trait ReadFsmTable {
     ) -> impl Future<Output = Result<Option<PartitionDurability>>> + Send + '_;
 
     fn get_schema(&mut self) -> impl Future<Output = Result<Option<Schema>>> + Send + '_;
+
+    fn get_partition_settings(
+        &mut self,
+    ) -> impl Future<Output = Result<PartitionSettings>> + Send + '_;
+
+    /// Latest creation time of the applied records, against which the expiration of the state
+    /// entries is evaluated.
+    fn get_state_clock(&mut self) -> impl Future<Output = Result<MillisSinceEpoch>> + Send + '_;
 }
 
 pub trait WriteFsmTable {
@@ -49,6 +58,10 @@ pub trait WriteFsmTable {
     fn put_partition_durability(&mut self, durability: &PartitionDurability) -> Result<()>;
 
     fn put_schema(&mut self, schema: &Schema) -> Result<()>;
+
+    fn put_partition_settings(&mut self, settings: &PartitionSettings) -> Result<()>;
+
+    fn put_state_clock(&mut self, now: MillisSinceEpoch) -> Result<()>;
 }
 
 #[derive(Debug, Clone, Copy, derive_more::From, derive_more::Into)]
@@ -495,6 +495,8 @@ pub struct PreFlightInvocationMetadata {
     ///
     /// When None, infer the seed from the invocation id.
     pub random_seed: Option<u64>,
+    /// Time after which the invocation is failed, if it didn't complete yet.
+    pub deadline: Option<MillisSinceEpoch>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -521,6 +523,7 @@ impl PreFlightInvocationMetadata {
         service_invocation: ServiceInvocation,
     ) -> Self {
         Self {
+            deadline: service_invocation.deadline(),
             response_sinks: service_invocation.response_sink.into_iter().collect(),
             timestamps: StatusTimestamps::init(created_at),
             invocation_target: service_invocation.invocation_target,
@@ -681,6 +684,8 @@ pub struct InFlightInvocationMetadata {
     ///
     /// When None, infer the seed from the invocation id.
     pub random_seed: Option<u64>,
+    /// Time after which the invocation is failed, if it didn't complete yet.
+    pub deadline: Option<MillisSinceEpoch>,
 }
 
 impl InFlightInvocationMetadata {
@@ -717,6 +722,7 @@ impl InFlightInvocationMetadata {
                     current_invocation_epoch: 0,
                     completion_range_epoch_map: Default::default(),
                     random_seed: pre_flight_invocation_metadata.random_seed,
+                    deadline: pre_flight_invocation_metadata.deadline,
                 },
                 Some(InvocationInput { argument, headers }),
             ),
@@ -743,6 +749,7 @@ impl InFlightInvocationMetadata {
                     current_invocation_epoch: 0,
                     completion_range_epoch_map: Default::default(),
                     random_seed: pre_flight_invocation_metadata.random_seed,
+                    deadline: pre_flight_invocation_metadata.deadline,
                 },
                 None,
             ),
@@ -934,6 +941,7 @@ mod test_util {
                     span_context: Default::default(),
                 }),
                 random_seed: None,
+                deadline: None,
             }
         }
     }
@@ -961,6 +969,7 @@ mod test_util {
                 current_invocation_epoch: 0,
                 completion_range_epoch_map: Default::default(),
                 random_seed: None,
+                deadline: None,
             }
         }
     }
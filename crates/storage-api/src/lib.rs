@@ -29,6 +29,7 @@ pub enum StorageError {
 
 pub type Result<T> = std::result::Result<T, StorageError>;
 
+pub mod completion_event_table;
 pub mod deduplication_table;
 pub mod fsm_table;
 pub mod idempotency_table;
@@ -38,6 +39,7 @@ pub mod journal_events;
 pub mod journal_table;
 pub mod journal_table_v2;
 pub mod outbox_table;
+pub mod parked_command_table;
 pub mod promise_table;
 pub mod protobuf_types;
 pub mod service_status_table;
@@ -78,6 +80,8 @@ pub trait Storage {
     ) -> Self::TransactionType<'_>;
 }
 
+/// A storage transaction. Reads done through the transaction observe its own writes, including
+/// the deletes, before they are committed.
 pub trait Transaction:
     state_table::WriteStateTable
     + state_table::ReadStateTable
@@ -98,6 +102,7 @@ pub trait Transaction:
     + promise_table::ReadPromiseTable
     + promise_table::WritePromiseTable
     + journal_events::WriteJournalEventsTable
+    + completion_event_table::WriteCompletionEventTable
     + Send
 {
     fn commit(self) -> impl Future<Output = Result<()>> + Send;
@@ -0,0 +1,57 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::future::Future;
+
+use bytes::Bytes;
+
+use restate_types::flexbuffers_storage_encode_decode;
+use restate_types::logs::Lsn;
+use restate_types::time::MillisSinceEpoch;
+
+use crate::Result;
+
+/// A command which repeatedly failed to be applied by the partition processor and was therefore
+/// put aside, so that the partition could make progress with the subsequent commands.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParkedCommand {
+    /// Lsn of the log record carrying the command.
+    pub lsn: Lsn,
+    pub parked_at: MillisSinceEpoch,
+    /// Number of failed attempts to apply the command.
+    pub attempts: u32,
+    /// The last failure observed while applying the command.
+    pub failure: String,
+    /// Name of the command, for inspection purposes.
+    pub command_name: String,
+    /// The storage encoded envelope of the command.
+    pub envelope: Bytes,
+}
+
+flexbuffers_storage_encode_decode!(ParkedCommand);
+
+pub trait ReadParkedCommandTable {
+    fn get_parked_command(
+        &mut self,
+        lsn: Lsn,
+    ) -> impl Future<Output = Result<Option<ParkedCommand>>> + Send;
+
+    /// Returns up to `limit` parked commands, ordered by lsn.
+    fn get_parked_commands(
+        &mut self,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<ParkedCommand>>> + Send;
+}
+
+pub trait WriteParkedCommandTable {
+    fn put_parked_command(&mut self, parked_command: &ParkedCommand) -> Result<()>;
+
+    fn delete_parked_command(&mut self, lsn: Lsn) -> Result<()>;
+}
@@ -452,6 +452,7 @@ pub mod v1 {
                     current_invocation_epoch,
                     trim_points,
                     random_seed,
+                    deadline,
                     waiting_for_completions,
                     waiting_for_signal_indexes,
                     waiting_for_signal_names,
@@ -530,6 +531,7 @@ pub mod v1 {
                                             .try_into()?,
                                         idempotency_key: idempotency_key.map(ByteString::from),
                                         random_seed,
+                                        deadline: deadline.map(MillisSinceEpoch::new),
                                     },
                             },
                         ))
@@ -578,6 +580,7 @@ pub mod v1 {
                                             .try_into()?,
                                         idempotency_key: idempotency_key.map(ByteString::from),
                                         random_seed,
+                                        deadline: deadline.map(MillisSinceEpoch::new),
                                     },
                             },
                         ))
@@ -616,6 +619,7 @@ pub mod v1 {
                                         }),
                                     ),
                                 random_seed,
+                                deadline: deadline.map(MillisSinceEpoch::new),
                             },
                         ))
                     }
@@ -653,6 +657,7 @@ pub mod v1 {
                                         }),
                                     ),
                                 random_seed,
+                                deadline: deadline.map(MillisSinceEpoch::new),
                             },
                             waiting_for_notifications: waiting_for_completions
                                 .into_iter()
@@ -706,6 +711,7 @@ pub mod v1 {
                                         }),
                                     ),
                                 random_seed,
+                                deadline: deadline.map(MillisSinceEpoch::new),
                             },
                         ))
                     }
@@ -762,6 +768,7 @@ pub mod v1 {
                                     journal_retention_duration,
                                     idempotency_key,
                                     random_seed,
+                                    deadline,
                                     input:
                                         PreFlightInvocationArgument::Input(PreFlightInvocationInput {
                                             argument,
@@ -813,6 +820,7 @@ pub mod v1 {
                         waiting_for_signal_names: vec![],
                         result: None,
                         random_seed,
+                        deadline: deadline.map(|t| t.as_u64()),
                     },
                     crate::invocation_status_table::InvocationStatus::Scheduled(
                         crate::invocation_status_table::ScheduledInvocation {
@@ -835,6 +843,7 @@ pub mod v1 {
                                             },
                                         ),
                                     random_seed,
+                                    deadline,
                                 },
                         },
                     ) => {
@@ -892,6 +901,7 @@ pub mod v1 {
                             waiting_for_signal_names: vec![],
                             result: None,
                             random_seed,
+                            deadline: deadline.map(|t| t.as_u64()),
                         }
                     }
                     crate::invocation_status_table::InvocationStatus::Inboxed(
@@ -908,6 +918,7 @@ pub mod v1 {
                                     journal_retention_duration,
                                     idempotency_key,
                                     random_seed,
+                                    deadline,
                                     input:
                                         PreFlightInvocationArgument::Input(PreFlightInvocationInput {
                                             argument,
@@ -960,6 +971,7 @@ pub mod v1 {
                         waiting_for_signal_names: vec![],
                         result: None,
                         random_seed,
+                        deadline: deadline.map(|t| t.as_u64()),
                     },
                     crate::invocation_status_table::InvocationStatus::Inboxed(
                         crate::invocation_status_table::InboxedInvocation {
@@ -982,6 +994,7 @@ pub mod v1 {
                                             },
                                         ),
                                     random_seed,
+                                    deadline,
                                 },
                             inbox_sequence_number,
                         },
@@ -1039,6 +1052,7 @@ pub mod v1 {
                             waiting_for_signal_names: vec![],
                             result: None,
                             random_seed,
+                            deadline: deadline.map(|t| t.as_u64()),
                         }
                     }
                     crate::invocation_status_table::InvocationStatus::Invoked(
@@ -1058,6 +1072,7 @@ pub mod v1 {
                             current_invocation_epoch,
                             completion_range_epoch_map,
                             random_seed,
+                            deadline,
                         },
                     ) => {
                         let (deployment_id, service_protocol_version) = match pinned_deployment {
@@ -1120,6 +1135,7 @@ pub mod v1 {
                                 })
                                 .collect(),
                             random_seed,
+                            deadline: deadline.map(|t| t.as_u64()),
                         }
                     }
                     crate::invocation_status_table::InvocationStatus::Suspended {
@@ -1140,6 +1156,7 @@ pub mod v1 {
                                 current_invocation_epoch,
                                 completion_range_epoch_map,
                                 random_seed,
+                                deadline,
                             },
                         waiting_for_notifications,
                     } => {
@@ -1220,6 +1237,7 @@ pub mod v1 {
                                 })
                                 .collect(),
                             random_seed,
+                            deadline: deadline.map(|t| t.as_u64()),
                         }
                     }
                     crate::invocation_status_table::InvocationStatus::Paused(
@@ -1239,6 +1257,7 @@ pub mod v1 {
                             current_invocation_epoch,
                             completion_range_epoch_map,
                             random_seed,
+                            deadline,
                         },
                     ) => {
                         let (deployment_id, service_protocol_version) = match pinned_deployment {
@@ -1301,6 +1320,7 @@ pub mod v1 {
                                 })
                                 .collect(),
                             random_seed,
+                            deadline: deadline.map(|t| t.as_u64()),
                         }
                     }
                     crate::invocation_status_table::InvocationStatus::Completed(
@@ -1370,6 +1390,7 @@ pub mod v1 {
                             waiting_for_signal_names: vec![],
                             result: Some(response_result.into()),
                             random_seed,
+                            deadline: None,
                         }
                     }
                     crate::invocation_status_table::InvocationStatus::Free => {
@@ -1632,6 +1653,7 @@ pub mod v1 {
                     current_invocation_epoch: 0,
                     completion_range_epoch_map: Default::default(),
                     random_seed: None,
+                    deadline: None,
                 })
             }
         }
@@ -1750,6 +1772,7 @@ pub mod v1 {
                         current_invocation_epoch: 0,
                         completion_range_epoch_map: Default::default(),
                         random_seed: None,
+                        deadline: None,
                     },
                     waiting_for_completed_entries,
                 ))
@@ -1874,6 +1897,7 @@ pub mod v1 {
                         invocation_target,
                         journal_retention_duration: Default::default(),
                         random_seed: None,
+                        deadline: None,
                         input: PreFlightInvocationArgument::Input(PreFlightInvocationInput {
                             span_context,
                             headers,
@@ -1900,6 +1924,7 @@ pub mod v1 {
                             journal_retention_duration: _,
                             idempotency_key,
                             random_seed: _,
+                            deadline: _,
                         },
                     inbox_sequence_number,
                 } = value;
@@ -2984,6 +3009,9 @@ pub mod v1 {
                 let header = restate_types::journal::enriched::EnrichedEntryHeader::try_from(
                     header.ok_or(ConversionError::missing_field("header"))?,
                 )?;
+                // The state machine might have encrypted the input entry, see payload_encryption
+                let raw_entry = restate_types::payload_encryption::decrypt(raw_entry)
+                    .map_err(ConversionError::invalid_data)?;
 
                 Ok(restate_types::journal::enriched::EnrichedRawEntry::new(
                     header, raw_entry,
@@ -3737,6 +3765,9 @@ pub mod v1 {
             fn try_from(value: Entry) -> Result<Self, Self::Error> {
                 let header =
                     restate_types::storage::StoredRawEntryHeader::new(value.append_time.into());
+                // The state machine might have encrypted the payload, see payload_encryption
+                let content = restate_types::payload_encryption::decrypt(value.content)
+                    .map_err(ConversionError::invalid_data)?;
 
                 Ok(crate::journal_table_v2::StoredEntry(
                     match EntryType::try_from(value.ty)
@@ -3764,7 +3795,7 @@ pub mod v1 {
                                 journal_v2::raw::RawNotification::new(
                                     notification_ty,
                                     notification_id,
-                                    value.content,
+                                    content,
                                 ),
                             )
                         }
@@ -3773,7 +3804,7 @@ pub mod v1 {
                             ct @ journal_v2::CommandType::OneWayCall,
                         ) => restate_types::storage::StoredRawEntry::new(
                             header,
-                            journal_v2::raw::RawCommand::new(ct, value.content)
+                            journal_v2::raw::RawCommand::new(ct, content)
                                 .with_command_specific_metadata(
                                 journal_v2::raw::RawCommandSpecificMetadata::CallOrSend(Box::new(
                                     journal_v2::raw::CallOrSendMetadata::try_from(
@@ -3789,7 +3820,7 @@ pub mod v1 {
                         journal_v2::EntryType::Command(ct) => {
                             restate_types::storage::StoredRawEntry::new(
                                 header,
-                                journal_v2::raw::RawCommand::new(ct, value.content),
+                                journal_v2::raw::RawCommand::new(ct, content),
                             )
                         }
                     },
@@ -4273,6 +4304,16 @@ pub mod v1 {
                                 )?,
                             )
                         }
+                        timer::Value::InvocationDeadline(invocation_deadline) => {
+                            crate::timer_table::Timer::InvocationDeadline(
+                                restate_types::identifiers::InvocationId::try_from(
+                                    invocation_deadline
+                                        .invocation_id
+                                        .ok_or(ConversionError::missing_field("invocation_id"))?,
+                                )?,
+                                MillisSinceEpoch::new(invocation_deadline.invocation_creation_time),
+                            )
+                        }
                     },
                 )
             }
@@ -4302,6 +4343,13 @@ pub mod v1 {
                                 invocation_id: Some(InvocationId::from(invocation_id)),
                             })
                         }
+                        crate::timer_table::Timer::InvocationDeadline(
+                            invocation_id,
+                            invocation_creation_time,
+                        ) => timer::Value::InvocationDeadline(timer::InvocationDeadline {
+                            invocation_id: Some(InvocationId::from(invocation_id)),
+                            invocation_creation_time: invocation_creation_time.as_u64(),
+                        }),
                     }),
                 }
             }
@@ -8,15 +8,20 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::num::NonZeroUsize;
 use std::ops::RangeInclusive;
 
 use bytes::Bytes;
 use futures::Stream;
 
 use restate_types::identifiers::{PartitionKey, ServiceId};
+use restate_types::time::MillisSinceEpoch;
 
 use crate::Result;
 
+/// Reads skip the state entries whose ttl expired. Transactions evaluate the expiration against
+/// their state clock, see [`WriteStateTable::set_state_clock`], the others against the clock of
+/// the node.
 pub trait ReadStateTable {
     fn get_user_state(
         &mut self,
@@ -24,10 +29,25 @@ pub trait ReadStateTable {
         state_key: impl AsRef<[u8]> + Send,
     ) -> impl Future<Output = Result<Option<Bytes>>> + Send;
 
+    /// Returns the total size of the state entries without ttl of the service, that is the sum of
+    /// the lengths of their keys and values.
+    fn get_user_state_size(
+        &mut self,
+        service_id: &ServiceId,
+    ) -> impl Future<Output = Result<u64>> + Send;
+
     fn get_all_user_states_for_service(
         &mut self,
         service_id: &ServiceId,
     ) -> Result<impl Stream<Item = Result<(Bytes, Bytes)>> + Send>;
+
+    /// Returns the state entries of the service whose key starts with the given prefix, e.g.
+    /// `cart/item/`, in the same order of [`Self::get_all_user_states_for_service`].
+    fn get_user_states_by_prefix(
+        &mut self,
+        service_id: &ServiceId,
+        prefix: impl AsRef<[u8]> + Send,
+    ) -> Result<impl Stream<Item = Result<(Bytes, Bytes)>> + Send>;
 }
 
 pub trait ScanStateTable {
@@ -38,14 +58,43 @@ pub trait ScanStateTable {
         range: RangeInclusive<PartitionKey>,
         f: F,
     ) -> Result<impl Future<Output = Result<()>> + Send>;
+
+    /// Streams the state entries of the service in batches of about `max_batch_bytes`, in the
+    /// same order of [`ReadStateTable::get_all_user_states_for_service`]. Each batch is read by
+    /// bounded scans on the background iterator pool once the stream is polled, so large states
+    /// are never fully materialized in memory, and the pool never waits for the consumer.
+    ///
+    /// Unlike [`ReadStateTable::get_all_user_states_for_service`], the entries are not read from a
+    /// consistent snapshot: writes committed while streaming might or might not be returned.
+    fn stream_user_states_for_service(
+        &self,
+        service_id: &ServiceId,
+        max_batch_bytes: NonZeroUsize,
+    ) -> Result<impl Stream<Item = Result<Vec<(Bytes, Bytes)>>> + Send>;
 }
 
 pub trait WriteStateTable {
+    /// Whether to keep track of the total state size of the services when writing their state,
+    /// see [`ReadStateTable::get_user_state_size`]. Tracking requires reading the previous entry
+    /// on every write: when disabled, the tracked size is dropped instead, and it's recomputed
+    /// from the entries the next time it's read. Enabled by default.
+    fn track_user_state_size(&mut self, enabled: bool);
+
+    /// Sets the time against which the reads of this transaction evaluate the expiration of the
+    /// state entries. Once the transaction is committed, the storage eventually drops the
+    /// entries expired according to it, hence it must never go backwards: the state machine
+    /// sets it to the latest creation time of the applied records, which all the replicas agree
+    /// on.
+    fn set_state_clock(&mut self, now: MillisSinceEpoch);
+
+    /// Puts the given state entry. If an expiration time is given, the entry is not returned
+    /// anymore once it expires, and it's eventually dropped from the storage.
     fn put_user_state(
         &mut self,
         service_id: &ServiceId,
         state_key: impl AsRef<[u8]> + Send,
         state_value: impl AsRef<[u8]> + Send,
+        expires_at: Option<MillisSinceEpoch>,
     ) -> Result<()>;
 
     fn delete_user_state(
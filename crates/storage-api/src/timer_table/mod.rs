@@ -15,6 +15,7 @@ use futures::Stream;
 use restate_types::identifiers::{InvocationId, InvocationUuid, PartitionKey, WithPartitionKey};
 use restate_types::invocation::{InvocationEpoch, ServiceInvocation};
 use restate_types::time::MillisSinceEpoch;
+use restate_types::timer::TimerKind;
 
 use crate::Result;
 use crate::protobuf_types::PartitionStoreProtobufValue;
@@ -65,6 +66,13 @@ impl TimerKey {
             kind: TimerKeyKind::CleanInvocationStatus { invocation_uuid },
         }
     }
+
+    pub fn invocation_deadline(timestamp: u64, invocation_uuid: InvocationUuid) -> Self {
+        TimerKey {
+            timestamp,
+            kind: TimerKeyKind::InvocationDeadline { invocation_uuid },
+        }
+    }
 }
 
 impl PartialOrd for TimerKey {
@@ -104,6 +112,8 @@ pub enum TimerKeyKind {
     },
     /// Cleaning of invocation status
     CleanInvocationStatus { invocation_uuid: InvocationUuid },
+    /// Expiration of the deadline of an invocation
+    InvocationDeadline { invocation_uuid: InvocationUuid },
 }
 
 impl TimerKeyKind {
@@ -115,6 +125,7 @@ impl TimerKeyKind {
             } => invocation_uuid,
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => invocation_uuid,
             TimerKeyKind::NeoInvoke { invocation_uuid } => invocation_uuid,
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => invocation_uuid,
         }
     }
 }
@@ -134,7 +145,8 @@ impl Ord for TimerKeyKind {
                 } => invocation_uuid.cmp(other_invocation_uuid),
                 TimerKeyKind::CompleteJournalEntry { .. }
                 | TimerKeyKind::CleanInvocationStatus { .. }
-                | TimerKeyKind::NeoInvoke { .. } => Ordering::Less,
+                | TimerKeyKind::NeoInvoke { .. }
+                | TimerKeyKind::InvocationDeadline { .. } => Ordering::Less,
             },
             TimerKeyKind::CompleteJournalEntry {
                 invocation_uuid,
@@ -147,9 +159,9 @@ impl Ord for TimerKeyKind {
                 } => invocation_uuid
                     .cmp(other_invocation_uuid)
                     .then_with(|| journal_index.cmp(other_journal_index)),
-                TimerKeyKind::CleanInvocationStatus { .. } | TimerKeyKind::NeoInvoke { .. } => {
-                    Ordering::Less
-                }
+                TimerKeyKind::CleanInvocationStatus { .. }
+                | TimerKeyKind::NeoInvoke { .. }
+                | TimerKeyKind::InvocationDeadline { .. } => Ordering::Less,
             },
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => match other {
                 TimerKeyKind::Invoke { .. } | TimerKeyKind::CompleteJournalEntry { .. } => {
@@ -158,7 +170,9 @@ impl Ord for TimerKeyKind {
                 TimerKeyKind::CleanInvocationStatus {
                     invocation_uuid: other_invocation_uuid,
                 } => invocation_uuid.cmp(other_invocation_uuid),
-                TimerKeyKind::NeoInvoke { .. } => Ordering::Less,
+                TimerKeyKind::NeoInvoke { .. } | TimerKeyKind::InvocationDeadline { .. } => {
+                    Ordering::Less
+                }
             },
             TimerKeyKind::NeoInvoke { invocation_uuid } => match other {
                 TimerKeyKind::Invoke { .. }
@@ -167,6 +181,16 @@ impl Ord for TimerKeyKind {
                 TimerKeyKind::NeoInvoke {
                     invocation_uuid: other_invocation_uuid,
                 } => invocation_uuid.cmp(other_invocation_uuid),
+                TimerKeyKind::InvocationDeadline { .. } => Ordering::Less,
+            },
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => match other {
+                TimerKeyKind::Invoke { .. }
+                | TimerKeyKind::CompleteJournalEntry { .. }
+                | TimerKeyKind::CleanInvocationStatus { .. }
+                | TimerKeyKind::NeoInvoke { .. } => Ordering::Greater,
+                TimerKeyKind::InvocationDeadline {
+                    invocation_uuid: other_invocation_uuid,
+                } => invocation_uuid.cmp(other_invocation_uuid),
             },
         }
     }
@@ -190,6 +214,8 @@ pub enum Timer {
     // TODO remove this variant when removing the old invocation status table
     CleanInvocationStatus(InvocationId),
     NeoInvoke(InvocationId),
+    /// Deadline of the invocation created at the given time
+    InvocationDeadline(InvocationId, MillisSinceEpoch),
 }
 
 impl Timer {
@@ -236,12 +262,33 @@ impl Timer {
         )
     }
 
+    pub fn invocation_deadline(
+        timestamp: u64,
+        invocation_id: InvocationId,
+        invocation_creation_time: MillisSinceEpoch,
+    ) -> (TimerKey, Self) {
+        (
+            TimerKey::invocation_deadline(timestamp, invocation_id.invocation_uuid()),
+            Timer::InvocationDeadline(invocation_id, invocation_creation_time),
+        )
+    }
+
+    pub fn kind(&self) -> TimerKind {
+        match self {
+            Timer::Invoke(_) | Timer::NeoInvoke(_) => TimerKind::DelayedInvocation,
+            Timer::CompleteJournalEntry(..) => TimerKind::Sleep,
+            Timer::CleanInvocationStatus(_) => TimerKind::Cleanup,
+            Timer::InvocationDeadline(..) => TimerKind::Deadline,
+        }
+    }
+
     pub fn invocation_id(&self) -> InvocationId {
         match self {
             Timer::Invoke(service_invocation) => service_invocation.invocation_id,
             Timer::CompleteJournalEntry(invocation_id, _, _) => *invocation_id,
             Timer::CleanInvocationStatus(invocation_id) => *invocation_id,
             Timer::NeoInvoke(invocation_id) => *invocation_id,
+            Timer::InvocationDeadline(invocation_id, _) => *invocation_id,
         }
     }
 }
@@ -257,6 +304,7 @@ impl WithPartitionKey for Timer {
             Timer::Invoke(service_invocation) => service_invocation.partition_key(),
             Timer::CleanInvocationStatus(invocation_id) => invocation_id.partition_key(),
             Timer::NeoInvoke(invocation_id) => invocation_id.partition_key(),
+            Timer::InvocationDeadline(invocation_id, _) => invocation_id.partition_key(),
         }
     }
 }
@@ -17,6 +17,7 @@ use tokio::sync::watch;
 use tracing::warn;
 
 use datafusion::catalog::TableProvider;
+use datafusion::dataframe::DataFrame;
 use datafusion::error::DataFusionError;
 use datafusion::execution::SessionStateBuilder;
 use datafusion::execution::context::SQLOptions;
@@ -40,6 +41,7 @@ use restate_types::schema::deployment::DeploymentResolver;
 use restate_types::schema::service::ServiceMetadataResolver;
 
 use crate::analyzer;
+use crate::query_limits::{DeadlineStream, QueryLimits, QueryMemoryPool, ScanBudget};
 use crate::remote_query_scanner_manager::RemoteScannerManager;
 
 const SYS_INVOCATION_VIEW: &str = "CREATE VIEW sys_invocation as SELECT
@@ -77,6 +79,7 @@ const SYS_INVOCATION_VIEW: &str = "CREATE VIEW sys_invocation as SELECT
             sis.next_retry_at,
             sis.last_attempt_deployment_id,
             sis.last_attempt_server,
+            sis.last_attempt_diagnostic_headers,
             sis.last_failure,
             sis.last_failure_error_code,
             sis.last_failure_related_entry_index,
@@ -101,6 +104,50 @@ const SYS_INVOCATION_VIEW: &str = "CREATE VIEW sys_invocation as SELECT
         FROM sys_invocation_state sis
         RIGHT JOIN sys_invocation_status ss ON ss.id = sis.id";
 
+const SYS_SERVICE_CALL_VIEW: &str = "CREATE VIEW sys_service_call as SELECT
+            caller.target_service_name AS caller_service_name,
+            arrow_cast(split_part(j.invoked_target, '/', 1), 'LargeUtf8') AS callee_service_name,
+            count(*) AS calls,
+            count(callee.completion_result) AS completed_calls,
+            count(CASE WHEN callee.completion_result = 'failure' THEN 1 END) AS failed_calls
+        FROM sys_journal j
+        JOIN sys_invocation_status caller ON caller.id = j.id
+        LEFT JOIN sys_invocation_status callee ON callee.id = j.invoked_id
+        WHERE j.invoked_target IS NOT NULL
+        GROUP BY caller.target_service_name, split_part(j.invoked_target, '/', 1)";
+
+const SYS_VIRTUAL_OBJECT_LOCK_VIEW: &str = "CREATE VIEW sys_virtual_object_lock as SELECT
+            ks.service_name,
+            ks.service_key,
+            ks.invocation_id AS locked_by_id,
+            inv.target_handler_name AS locked_by_handler_name,
+            inv.status AS locked_by_status,
+            inv.running_at AS locked_since,
+            inv.retry_count AS locked_by_retry_count,
+            inv.last_failure AS locked_by_last_failure,
+            coalesce(ib.queue_length, 0) AS queue_length
+        FROM sys_keyed_service_status ks
+        LEFT JOIN sys_invocation inv ON inv.id = ks.invocation_id
+        LEFT JOIN (
+            SELECT service_name, service_key, count(*) AS queue_length
+            FROM sys_inbox
+            GROUP BY service_name, service_key
+        ) ib ON ib.service_name = ks.service_name AND ib.service_key = ks.service_key";
+
+const SYS_INBOX_QUEUE_VIEW: &str = "CREATE VIEW sys_inbox_queue as SELECT
+            ib.service_name,
+            ib.service_key,
+            ib.id,
+            ib.sequence_number,
+            ib.queue_position,
+            inv.target_handler_name,
+            inv.inboxed_at AS enqueued_at,
+            inv.invoked_by,
+            inv.invoked_by_id,
+            inv.invoked_by_subscription_id
+        FROM sys_inbox ib
+        JOIN sys_invocation_status inv ON inv.id = ib.id";
+
 const CLUSTER_LOGS_TAIL_SEGMENTS_VIEW: &str = "CREATE VIEW logs_tail_segments as SELECT
         l.* FROM logs AS l JOIN (
             SELECT log_id, max(segment_index) AS segment_index FROM logs GROUP BY log_id
@@ -170,6 +217,12 @@ where
             self.partition_store_manager.clone(),
             &self.remote_scanner_manager,
         )?;
+        crate::invocation_stats::register_self(
+            ctx,
+            self.partition_selector.clone(),
+            self.status.clone(),
+            &self.remote_scanner_manager,
+        )?;
         crate::invocation_status::register_self(
             ctx,
             self.partition_selector.clone(),
@@ -220,6 +273,11 @@ where
         )?;
 
         ctx.datafusion_context.sql(SYS_INVOCATION_VIEW).await?;
+        ctx.datafusion_context.sql(SYS_SERVICE_CALL_VIEW).await?;
+        ctx.datafusion_context
+            .sql(SYS_VIRTUAL_OBJECT_LOCK_VIEW)
+            .await?;
+        ctx.datafusion_context.sql(SYS_INBOX_QUEUE_VIEW).await?;
 
         Ok(())
     }
@@ -270,6 +328,7 @@ impl RegisterTable for ClusterTables {
 #[derive(Clone)]
 pub struct QueryContext {
     sql_options: SQLOptions,
+    limits: QueryLimits,
     datafusion_context: SessionContext,
 }
 
@@ -283,6 +342,7 @@ impl QueryContext {
             options.tmp_dir.clone(),
             options.query_parallelism(),
             &options.datafusion_options,
+            QueryLimits::from(options),
         )?;
 
         registerer.register(&ctx).await?;
@@ -338,6 +398,7 @@ impl QueryContext {
         temp_folder: Option<String>,
         default_parallelism: Option<usize>,
         datafusion_options: &HashMap<String, String>,
+        limits: QueryLimits,
     ) -> Result<Self, DataFusionError> {
         //
         // build the runtime
@@ -405,20 +466,46 @@ impl QueryContext {
 
         Ok(Self {
             sql_options,
+            limits,
             datafusion_context: ctx,
         })
     }
 
+    /// Runs the given query, within the configured [`QueryLimits`]. `EXPLAIN` shows the table
+    /// scans with the pushed down partition keys, projection and limit, while
+    /// `EXPLAIN ANALYZE` additionally reports the rows returned by each scan.
     pub async fn execute(
         &self,
         sql: &str,
     ) -> datafusion::common::Result<SendableRecordBatchStream> {
-        let state = self.datafusion_context.state();
+        let mut state = self.datafusion_context.state();
         let statement = state.sql_to_statement(sql, "postgres")?;
         let plan = state.statement_to_plan(statement).await?;
         self.sql_options.verify_plan(&plan)?;
-        let df = self.datafusion_context.execute_logical_plan(plan).await?;
-        df.execute_stream().await
+
+        // Each query gets its own budget of rows and memory
+        if let Some(max_rows_scanned) = self.limits.max_rows_scanned {
+            state
+                .config_mut()
+                .set_extension(Arc::new(ScanBudget::new(max_rows_scanned)));
+        }
+        if let Some(max_query_memory) = self.limits.max_query_memory {
+            let runtime = RuntimeEnvBuilder::from_runtime_env(state.runtime_env())
+                .with_memory_pool(Arc::new(QueryMemoryPool::new(
+                    Arc::clone(&state.runtime_env().memory_pool),
+                    max_query_memory,
+                )))
+                .build_arc()?;
+            state = SessionStateBuilder::new_from_existing(state)
+                .with_runtime_env(runtime)
+                .build();
+        }
+
+        let stream = DataFrame::new(state, plan).execute_stream().await?;
+        Ok(match self.limits.max_execution_time {
+            Some(max_execution_time) => Box::pin(DeadlineStream::new(stream, max_execution_time)),
+            None => stream,
+        })
     }
 }
 
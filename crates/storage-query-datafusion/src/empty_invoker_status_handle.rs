@@ -8,7 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use restate_invoker_api::{InvocationStatusReport, StatusHandle};
+use restate_invoker_api::{InvocationStatsReport, InvocationStatusReport, StatusHandle};
 use restate_types::identifiers::PartitionKey;
 use std::future::Future;
 use std::ops::RangeInclusive;
@@ -19,6 +19,7 @@ pub struct EmptyInvokerStatusHandle;
 
 impl StatusHandle for EmptyInvokerStatusHandle {
     type Iterator = iter::Empty<InvocationStatusReport>;
+    type StatsIterator = iter::Empty<InvocationStatsReport>;
 
     fn read_status(
         &self,
@@ -26,4 +27,11 @@ impl StatusHandle for EmptyInvokerStatusHandle {
     ) -> impl Future<Output = Self::Iterator> + Send {
         future::ready(iter::empty())
     }
+
+    fn read_stats(
+        &self,
+        _keys: RangeInclusive<PartitionKey>,
+    ) -> impl Future<Output = Self::StatsIterator> + Send {
+        future::ready(iter::empty())
+    }
 }
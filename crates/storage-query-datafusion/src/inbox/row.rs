@@ -16,6 +16,7 @@ use restate_types::identifiers::WithPartitionKey;
 pub(crate) fn append_inbox_row(
     builder: &mut SysInboxBuilder,
     inbox_entry: SequenceNumberInboxEntry,
+    queue_position: u64,
 ) {
     let mut row = builder.row();
 
@@ -34,6 +35,7 @@ pub(crate) fn append_inbox_row(
         }
 
         row.sequence_number(inbox_sequence_number);
+        row.queue_position(queue_position);
     } else {
         // todo think about how to present other inbox entries via datafusion: https://github.com/restatedev/restate/issues/1101
     }
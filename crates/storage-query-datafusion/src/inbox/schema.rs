@@ -30,6 +30,10 @@ define_table!(sys_inbox(
     /// Sequence number in the inbox.
     sequence_number: DataType::UInt64,
 
+    /// Position in the queue of the virtual object/workflow key, where `0` is the next
+    /// entry to acquire the lock once released.
+    queue_position: DataType::UInt64,
+
     /// Timestamp indicating the start of this invocation.
     /// DEPRECATED: you should not use this field anymore, but join with the sys_invocation table
     created_at: TimestampMillisecond,
@@ -15,7 +15,7 @@ use std::sync::Arc;
 use restate_partition_store::{PartitionStore, PartitionStoreManager};
 use restate_storage_api::StorageError;
 use restate_storage_api::inbox_table::{ScanInboxTable, SequenceNumberInboxEntry};
-use restate_types::identifiers::PartitionKey;
+use restate_types::identifiers::{PartitionKey, ServiceId};
 
 use crate::context::{QueryContext, SelectPartitions};
 use crate::inbox::row::append_inbox_row;
@@ -55,7 +55,8 @@ struct InboxScanner;
 
 impl ScanLocalPartition for InboxScanner {
     type Builder = SysInboxBuilder;
-    type Item<'a> = SequenceNumberInboxEntry;
+    /// Inbox entry together with its position in the queue
+    type Item<'a> = (SequenceNumberInboxEntry, u64);
     type ConversionError = std::convert::Infallible;
 
     fn for_each_row<
@@ -70,14 +71,30 @@ impl ScanLocalPartition for InboxScanner {
         range: RangeInclusive<PartitionKey>,
         mut f: F,
     ) -> Result<impl Future<Output = restate_storage_api::Result<()>> + Send, StorageError> {
-        partition_store.for_each_inbox(range, move |item| f(item).map_break(Result::unwrap))
+        // Inbox entries are sorted by service id and sequence number, hence the entries of
+        // each queue are scanned one after the other, starting from the head of the queue.
+        let mut current_queue: Option<(ServiceId, u64)> = None;
+        partition_store.for_each_inbox(range, move |item| {
+            let queue_position = match &mut current_queue {
+                Some((service_id, position)) if service_id == item.inbox_entry.service_id() => {
+                    *position += 1;
+                    *position
+                }
+                _ => {
+                    current_queue = Some((item.inbox_entry.service_id().clone(), 0));
+                    0
+                }
+            };
+            f((item, queue_position)).map_break(Result::unwrap)
+        })
     }
 
     fn append_row<'a>(
         row_builder: &mut Self::Builder,
         value: Self::Item<'a>,
     ) -> Result<(), Self::ConversionError> {
-        append_inbox_row(row_builder, value);
+        let (inbox_entry, queue_position) = value;
+        append_inbox_row(row_builder, inbox_entry, queue_position);
         Ok(())
     }
 }
@@ -58,6 +58,7 @@ async fn get_inbox() {
                 {
                     "id" => LargeStringArray: eq(invocation_id_1.to_string()),
                     "sequence_number" => UInt64Array: eq(0),
+                    "queue_position" => UInt64Array: eq(0),
                     "service_name" => LargeStringArray: eq(service_id.service_name.to_string()),
                     "service_key" => LargeStringArray: eq(service_id.key.to_string()),
                 }
@@ -67,6 +68,7 @@ async fn get_inbox() {
                 {
                     "id" => LargeStringArray: eq(invocation_id_2.to_string()),
                     "sequence_number" => UInt64Array: eq(1),
+                    "queue_position" => UInt64Array: eq(1),
                     "service_name" => LargeStringArray: eq(service_id.service_name.to_string()),
                     "service_key" => LargeStringArray: eq(service_id.key.to_string()),
                 }
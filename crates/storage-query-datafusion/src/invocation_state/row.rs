@@ -37,6 +37,18 @@ pub(crate) fn append_invocation_state_row(
     if let Some(last_attempt_server) = status_row.last_attempt_server() {
         row.last_attempt_server(last_attempt_server);
     }
+    if row.is_last_attempt_diagnostic_headers_defined()
+        && !status_row.last_attempt_diagnostic_headers().is_empty()
+    {
+        let diagnostic_headers: serde_json::Map<_, _> = status_row
+            .last_attempt_diagnostic_headers()
+            .iter()
+            .map(|(name, value)| (name.clone(), serde_json::Value::from(value.as_str())))
+            .collect();
+        row.last_attempt_diagnostic_headers(
+            serde_json::Value::Object(diagnostic_headers).to_string(),
+        );
+    }
 
     if let Some(next_retry_at) = status_row.next_retry_at() {
         row.next_retry_at(MillisSinceEpoch::as_u64(&next_retry_at.into()) as i64);
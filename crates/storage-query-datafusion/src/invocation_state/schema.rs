@@ -43,6 +43,11 @@ define_table!(sys_invocation_state(
     /// Server/SDK version, e.g. `restate-sdk-java/1.0.1`
     last_attempt_server: DataType::LargeUtf8,
 
+    /// JSON object with the diagnostic headers of the most recent attempt response,
+    /// as configured in `worker.invoker.attempt-diagnostic-headers`,
+    /// e.g. `{"server": "envoy", "x-request-id": "7c1f"}`.
+    last_attempt_diagnostic_headers: DataType::LargeUtf8,
+
     /// Timestamp indicating the start of the next attempt of this invocation.
     next_retry_at: TimestampMillisecond,
 
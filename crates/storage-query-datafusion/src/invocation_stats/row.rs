@@ -0,0 +1,51 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_invoker_api::InvocationStatsReport;
+use restate_types::time::MillisSinceEpoch;
+
+use crate::invocation_stats::schema::SysInvocationStatsBuilder;
+
+#[inline]
+pub(crate) fn append_invocation_stats_row(
+    builder: &mut SysInvocationStatsBuilder,
+    stats_row: InvocationStatsReport,
+) {
+    let mut row = builder.row();
+
+    row.partition_id(u32::from(stats_row.partition_id));
+    row.bucket_start(MillisSinceEpoch::as_u64(&stats_row.bucket_start.into()) as i64);
+    row.bucket_width(stats_row.bucket_width.as_millis() as i64);
+    row.service_name(&stats_row.service_name);
+    row.handler_name(&stats_row.handler_name);
+
+    let stats = stats_row.stats;
+    row.attempts(stats.attempts);
+    row.completed(stats.completed);
+    row.suspended(stats.suspended);
+    row.failed(stats.failed);
+
+    let attempt_duration = &stats.attempt_duration;
+    if let Some(avg) = attempt_duration.mean() {
+        row.attempt_duration_avg(avg.as_millis() as i64);
+    }
+    if let Some(p50) = attempt_duration.quantile(0.5) {
+        row.attempt_duration_p50(p50.as_millis() as i64);
+    }
+    if let Some(p90) = attempt_duration.quantile(0.9) {
+        row.attempt_duration_p90(p90.as_millis() as i64);
+    }
+    if let Some(p99) = attempt_duration.quantile(0.99) {
+        row.attempt_duration_p99(p99.as_millis() as i64);
+    }
+    if let Some(max) = attempt_duration.max() {
+        row.attempt_duration_max(max.as_millis() as i64);
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::table_macro::*;
+
+use datafusion::arrow::datatypes::DataType;
+
+define_sort_order!(sys_invocation_stats(
+    partition_id,
+    bucket_start,
+    service_name,
+    handler_name
+));
+
+define_table!(
+    /// Statistics of the invocation attempts, aggregated per handler and per minute. Statistics
+    /// are kept in memory by the partition leaders for the last hour, and are reset when the
+    /// leadership of a partition changes.
+    sys_invocation_stats(
+        /// The partition executing the invocation attempts.
+        partition_id: DataType::UInt32,
+
+        /// Start of the time bucket.
+        bucket_start: TimestampMillisecond,
+
+        /// Width of the time bucket.
+        bucket_width: DataType::Duration,
+
+        /// The name of the invoked service.
+        service_name: DataType::LargeUtf8,
+
+        /// The name of the invoked handler.
+        handler_name: DataType::LargeUtf8,
+
+        /// Number of invocation attempts started within the time bucket.
+        attempts: DataType::UInt64,
+
+        /// Number of invocation attempts that completed the invocation.
+        completed: DataType::UInt64,
+
+        /// Number of invocation attempts that ended with a suspension.
+        suspended: DataType::UInt64,
+
+        /// Number of invocation attempts that failed, either with a transient or a terminal error.
+        failed: DataType::UInt64,
+
+        /// Average duration of the invocation attempts that ended within the time bucket.
+        attempt_duration_avg: DataType::Duration,
+
+        /// Approximated median duration of the invocation attempts that ended within the time bucket.
+        attempt_duration_p50: DataType::Duration,
+
+        /// Approximated 90th percentile of the duration of the invocation attempts that ended within
+        /// the time bucket.
+        attempt_duration_p90: DataType::Duration,
+
+        /// Approximated 99th percentile of the duration of the invocation attempts that ended within
+        /// the time bucket.
+        attempt_duration_p99: DataType::Duration,
+
+        /// Maximum duration of the invocation attempts that ended within the time bucket.
+        attempt_duration_max: DataType::Duration,
+    )
+);
@@ -0,0 +1,123 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use tokio::sync::mpsc::Sender;
+
+use restate_invoker_api::{InvocationStatsReport, StatusHandle};
+use restate_types::identifiers::{PartitionId, PartitionKey};
+
+use crate::context::{QueryContext, SelectPartitions};
+use crate::invocation_stats::row::append_invocation_stats_row;
+use crate::invocation_stats::schema::{SysInvocationStatsBuilder, sys_invocation_stats_sort_order};
+use crate::partition_filter::FirstMatchingPartitionKeyExtractor;
+use crate::remote_query_scanner_manager::RemoteScannerManager;
+use crate::statistics::{RowEstimate, TableStatisticsBuilder};
+use crate::table_providers::{PartitionedTableProvider, ScanPartition};
+use crate::table_util::Builder;
+
+const NAME: &str = "sys_invocation_stats";
+
+pub(crate) fn register_self(
+    ctx: &QueryContext,
+    partition_selector: impl SelectPartitions,
+    status: Option<impl StatusHandle + Send + Sync + Debug + Clone + 'static>,
+    remote_scanner_manager: &RemoteScannerManager,
+) -> datafusion::common::Result<()> {
+    let local_partition_scanner = match status {
+        Some(status_handle) => {
+            let stats_scanner = Arc::new(StatsScanner { status_handle }) as Arc<dyn ScanPartition>;
+            Some(stats_scanner)
+        }
+        None => None,
+    };
+
+    let schema = SysInvocationStatsBuilder::schema();
+    let statistics =
+        TableStatisticsBuilder::new(schema.clone()).with_num_rows_estimate(RowEstimate::Small);
+
+    let stats_table = PartitionedTableProvider::new(
+        partition_selector,
+        schema,
+        sys_invocation_stats_sort_order(),
+        remote_scanner_manager.create_distributed_scanner(NAME, local_partition_scanner),
+        FirstMatchingPartitionKeyExtractor::default(),
+    )
+    .with_statistics(statistics.build());
+    ctx.register_partitioned_table(NAME, Arc::new(stats_table))
+}
+
+#[derive(Debug, Clone)]
+struct StatsScanner<S> {
+    status_handle: S,
+}
+
+impl<S: StatusHandle + Send + Sync + Debug + Clone + 'static> ScanPartition for StatsScanner<S> {
+    fn scan_partition(
+        &self,
+        _partition_id: PartitionId,
+        range: RangeInclusive<PartitionKey>,
+        projection: SchemaRef,
+        batch_size: usize,
+        limit: Option<usize>,
+    ) -> anyhow::Result<SendableRecordBatchStream> {
+        let status_handle = self.status_handle.clone();
+        let schema = projection.clone();
+        let mut stream_builder = RecordBatchReceiverStream::builder(projection, 1);
+        let tx = stream_builder.tx();
+
+        let background_task = async move {
+            // The table has no partition key column, hence the range spans the whole partition
+            let stats = status_handle.read_stats(range).await;
+            match limit {
+                Some(limit) => for_each_stats(schema, tx, stats.take(limit), batch_size).await,
+                None => for_each_stats(schema, tx, stats, batch_size).await,
+            }
+            Ok(())
+        };
+
+        stream_builder.spawn(background_task);
+        Ok(stream_builder.build())
+    }
+}
+
+async fn for_each_stats<'a, I>(
+    schema: SchemaRef,
+    tx: Sender<datafusion::common::Result<RecordBatch>>,
+    rows: I,
+    batch_size: usize,
+) where
+    I: Iterator<Item = InvocationStatsReport> + 'a,
+{
+    let mut builder = SysInvocationStatsBuilder::new(schema.clone());
+    for row in rows {
+        append_invocation_stats_row(&mut builder, row);
+        if builder.num_rows() >= batch_size {
+            let batch = builder.finish_and_new();
+            if tx.send(batch).await.is_err() {
+                // not sure what to do here?
+                // the other side has hung up on us.
+                // we probably don't want to panic, is it will cause the entire process to exit
+                return;
+            }
+        }
+    }
+    if !builder.empty() {
+        let result = builder.finish();
+        let _ = tx.send(result).await;
+    }
+}
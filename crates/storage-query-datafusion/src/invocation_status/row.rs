@@ -339,6 +339,9 @@ fn fill_journal_metadata(
     if row.is_journal_commands_size_defined() {
         row.journal_commands_size(status.inner.commands);
     }
+    if row.is_invocation_epoch_defined() {
+        row.invocation_epoch(status.inner.current_invocation_epoch);
+    }
 
     Ok(())
 }
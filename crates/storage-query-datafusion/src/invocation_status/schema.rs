@@ -92,6 +92,10 @@ define_table!(sys_invocation_status(
     /// Only relevant when pinned_service_protocol_version >= 4.
     journal_commands_size: DataType::UInt32,
 
+    /// The current epoch of the invocation, incremented every time the invocation is restarted
+    /// with a trimmed journal. Completions of previous epochs are discarded.
+    invocation_epoch: DataType::UInt32,
+
     /// Timestamp indicating the start of this invocation.
     created_at: TimestampMillisecond,
 
@@ -178,7 +178,14 @@ pub(crate) fn append_journal_row_v2(
         row.entry_lite_json(entry_lite_json);
     }
 
-    if row.is_entry_json_defined() || row.is_name_defined() {
+    let is_call = matches!(
+        raw_entry.ty(),
+        journal_v2::EntryType::Command(
+            journal_v2::CommandType::Call | journal_v2::CommandType::OneWayCall
+        )
+    );
+    let fill_invoked = is_call && (row.is_invoked_id_defined() || row.is_invoked_target_defined());
+    if row.is_entry_json_defined() || row.is_name_defined() || fill_invoked {
         // We need to parse the entry
         let Ok(entry) = raw_entry.decode::<ServiceProtocolV4Codec, journal_v2::Entry>() else {
             log_data_corruption_error!(
@@ -195,6 +202,18 @@ pub(crate) fn append_journal_row_v2(
         {
             row.entry_json(json);
         }
+        if let journal_v2::Entry::Command(
+            journal_v2::Command::Call(journal_v2::CallCommand { request, .. })
+            | journal_v2::Command::OneWayCall(journal_v2::OneWayCallCommand { request, .. }),
+        ) = &entry
+        {
+            if row.is_invoked_id_defined() {
+                row.fmt_invoked_id(request.invocation_id);
+            }
+            if row.is_invoked_target_defined() {
+                row.fmt_invoked_target(&request.invocation_target);
+            }
+        }
         if row.is_name_defined()
             && let journal_v2::Entry::Command(cmd) = entry
         {
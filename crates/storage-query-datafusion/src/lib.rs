@@ -17,6 +17,7 @@ mod deployment;
 mod idempotency;
 mod inbox;
 mod invocation_state;
+mod invocation_stats;
 mod invocation_status;
 mod journal;
 mod journal_events;
@@ -28,6 +29,7 @@ mod partition_replica_set;
 mod partition_state;
 mod partition_store_scanner;
 mod promise;
+mod query_limits;
 mod scanner_task;
 mod service;
 mod state;
@@ -44,11 +46,13 @@ use datafusion::arrow::ipc::convert::IpcSchemaEncoder;
 use datafusion::arrow::ipc::writer::DictionaryTracker;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::error::DataFusionError;
+pub use query_limits::QueryLimitError;
 
 #[cfg(test)]
 pub(crate) mod mocks;
 
 pub mod empty_invoker_status_handle;
+pub mod offline;
 mod partition_filter;
 pub mod remote_query_scanner_client;
 pub mod remote_query_scanner_manager;
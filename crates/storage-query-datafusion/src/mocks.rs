@@ -71,6 +71,14 @@ impl ServiceMetadataResolver for MockSchemas {
     fn list_service_names(&self) -> Vec<String> {
         self.0.list_service_names()
     }
+
+    fn resolve_ingress_route<'a>(
+        &self,
+        host: Option<&str>,
+        path: &'a str,
+    ) -> Option<(String, &'a str)> {
+        self.0.resolve_ingress_route(host, path)
+    }
 }
 
 impl DeploymentResolver for MockSchemas {
@@ -157,6 +165,20 @@ impl MockQueryEngine {
         + Debug
         + Clone
         + 'static,
+    ) -> Self {
+        Self::create_with_options(&QueryEngineOptions::default(), status, schemas).await
+    }
+
+    pub async fn create_with_options(
+        options: &QueryEngineOptions,
+        status: impl StatusHandle + Send + Sync + Debug + Clone + 'static,
+        schemas: impl DeploymentResolver
+        + ServiceMetadataResolver
+        + Send
+        + Sync
+        + Debug
+        + Clone
+        + 'static,
     ) -> Self {
         // Prepare Rocksdb
         RocksDbManager::init();
@@ -176,7 +198,7 @@ impl MockQueryEngine {
             manager.clone(),
             partition_store,
             QueryContext::with_user_tables(
-                &QueryEngineOptions::default(),
+                options,
                 MockPartitionSelector,
                 manager,
                 Some(status),
@@ -0,0 +1,93 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Query context over the partition stores of this process only, to inspect a copy of the data
+//! directory of a node without a running cluster.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+
+use restate_partition_store::PartitionStoreManager;
+use restate_types::NodeId;
+use restate_types::config::QueryEngineOptions;
+use restate_types::errors::GenericError;
+use restate_types::identifiers::PartitionId;
+use restate_types::live::Live;
+use restate_types::net::remote_query_scanner::RemoteQueryScannerOpen;
+use restate_types::partition_table::Partition;
+use restate_types::schema::Schema;
+
+use crate::BuildError;
+use crate::context::{QueryContext, SelectPartitions};
+use crate::empty_invoker_status_handle::EmptyInvokerStatusHandle;
+use crate::remote_query_scanner_client::{RemoteScanner, RemoteScannerService};
+use crate::remote_query_scanner_manager::{
+    PartitionLocation, PartitionLocator, RemoteScannerManager,
+};
+
+/// Creates a query context over the given partitions, which must be opened in the
+/// `partition_store_manager`.
+///
+/// Only the partition-key-based tables, like `sys_invocation`, `sys_journal` and `state`, are
+/// populated, as the schema and the cluster metadata aren't available offline.
+pub async fn create_offline_query_context(
+    options: &QueryEngineOptions,
+    partitions: Vec<(PartitionId, Partition)>,
+    partition_store_manager: Arc<PartitionStoreManager>,
+) -> Result<QueryContext, BuildError> {
+    QueryContext::with_user_tables(
+        options,
+        OfflinePartitions(Arc::new(partitions)),
+        partition_store_manager,
+        None::<EmptyInvokerStatusHandle>,
+        Live::from_value(Schema::default()),
+        RemoteScannerManager::new(Arc::new(NoRemoteScanner), Arc::new(AlwaysLocal)),
+    )
+    .await
+}
+
+#[derive(Clone, Debug)]
+struct OfflinePartitions(Arc<Vec<(PartitionId, Partition)>>);
+
+#[async_trait]
+impl SelectPartitions for OfflinePartitions {
+    async fn get_live_partitions(&self) -> Result<Vec<(PartitionId, Partition)>, GenericError> {
+        Ok(self.0.as_ref().clone())
+    }
+}
+
+#[derive(Debug)]
+struct NoRemoteScanner;
+
+#[async_trait]
+impl RemoteScannerService for NoRemoteScanner {
+    async fn open(
+        &self,
+        peer: NodeId,
+        _req: RemoteQueryScannerOpen,
+    ) -> Result<RemoteScanner, DataFusionError> {
+        Err(DataFusionError::Internal(format!(
+            "cannot scan partitions of node {peer} offline"
+        )))
+    }
+}
+
+struct AlwaysLocal;
+
+impl PartitionLocator for AlwaysLocal {
+    fn get_partition_target_node(
+        &self,
+        _partition_id: PartitionId,
+    ) -> anyhow::Result<PartitionLocation> {
+        Ok(PartitionLocation::Local)
+    }
+}
@@ -58,4 +58,14 @@ pub(crate) fn append_partition_row(
     if let Some(lsn) = state.target_tail_lsn {
         row.target_tail_lsn(lsn.into());
     }
+
+    row.applied_commands_per_second(state.applied_commands_per_second);
+
+    if let Some(apply_lag_ms) = state.apply_lag_ms {
+        row.apply_lag(apply_lag_ms as i64);
+    }
+
+    if let Some(age_ms) = state.oldest_unapplied_command_age_ms {
+        row.oldest_unapplied_command_age(age_ms as i64);
+    }
 }
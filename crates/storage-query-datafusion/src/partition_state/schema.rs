@@ -56,5 +56,14 @@ define_table!(
 
         /// Target tail LSN
         target_tail_lsn: DataType::UInt64,
+
+        /// Rate of commands applied since the previous status update
+        applied_commands_per_second: DataType::Float64,
+
+        /// Time between appending the last applied command to the log and applying it
+        apply_lag: DataType::Duration,
+
+        /// Age of the oldest command appended to the log, but not applied yet
+        oldest_unapplied_command_age: DataType::Duration,
     )
 );
@@ -16,10 +16,13 @@ use anyhow::anyhow;
 use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::error::DataFusionError;
 use datafusion::execution::SendableRecordBatchStream;
-use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::stream::{RecordBatchReceiverStream, RecordBatchStreamAdapter};
+use futures::StreamExt;
 
+use restate_partition_store::scan::split_partition_key_range;
 use restate_partition_store::{PartitionStore, PartitionStoreManager};
 use restate_storage_api::StorageError;
+use restate_types::config::Configuration;
 use restate_types::identifiers::{PartitionId, PartitionKey};
 
 use crate::table_providers::ScanPartition;
@@ -72,13 +75,41 @@ where
     RB: crate::table_util::Builder + Send + Sync + 'static,
 {
     fn scan_partition(
+        &self,
+        partition_id: PartitionId,
+        range: RangeInclusive<PartitionKey>,
+        projection: SchemaRef,
+        batch_size: usize,
+        limit: Option<usize>,
+    ) -> SendableRecordBatchStream {
+        let scan_parallelism = Configuration::pinned().admin.query_engine.scan_parallelism;
+        // with a limit, the first shard alone is likely to satisfy the query.
+        if limit.is_some() || scan_parallelism.get() == 1 {
+            return self.scan_range(partition_id, range, projection, batch_size, limit);
+        }
+
+        // Every shard is read by its own background iterator into its own bounded channel and
+        // the shard streams are concatenated in key order. This preserves the sort order of the
+        // table, while the later shards are read ahead concurrently.
+        let shards: Vec<_> = split_partition_key_range(&range, scan_parallelism)
+            .into_iter()
+            .map(|shard| self.scan_range(partition_id, shard, projection.clone(), batch_size, None))
+            .collect();
+
+        Box::pin(RecordBatchStreamAdapter::new(
+            projection,
+            futures::stream::iter(shards).flatten(),
+        ))
+    }
+
+    fn scan_range(
         &self,
         partition_id: PartitionId,
         range: RangeInclusive<PartitionKey>,
         projection: SchemaRef,
         batch_size: usize,
         mut limit: Option<usize>,
-    ) -> anyhow::Result<SendableRecordBatchStream> {
+    ) -> SendableRecordBatchStream {
         let partition_store_manager = self.partition_store_manager.clone();
         let mut stream_builder = RecordBatchReceiverStream::builder(projection.clone(), 1);
         let tx = stream_builder.tx();
@@ -128,7 +159,7 @@ where
             Ok(())
         };
         stream_builder.spawn(background_task);
-        Ok(stream_builder.build())
+        stream_builder.build()
     }
 }
 
@@ -145,6 +176,6 @@ where
         batch_size: usize,
         limit: Option<usize>,
     ) -> anyhow::Result<SendableRecordBatchStream> {
-        self.scan_partition(partition_id, range, projection, batch_size, limit)
+        Ok(self.scan_partition(partition_id, range, projection, batch_size, limit))
     }
 }
@@ -0,0 +1,191 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::DataFusionError;
+use datafusion::execution::memory_pool::{
+    MemoryConsumer, MemoryLimit, MemoryPool, MemoryReservation,
+};
+use datafusion::execution::{RecordBatchStream, SendableRecordBatchStream};
+use futures::Stream;
+use tokio::time::Sleep;
+
+use restate_types::config::QueryEngineOptions;
+
+/// Limits applied to every single query.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct QueryLimits {
+    pub(crate) max_rows_scanned: Option<usize>,
+    pub(crate) max_execution_time: Option<Duration>,
+    pub(crate) max_query_memory: Option<usize>,
+}
+
+impl From<&QueryEngineOptions> for QueryLimits {
+    fn from(options: &QueryEngineOptions) -> Self {
+        Self {
+            max_rows_scanned: options.max_rows_scanned.map(Into::into),
+            max_execution_time: options.max_execution_time.map(|d| d.to_std()),
+            max_query_memory: options.max_query_memory.map(Into::into),
+        }
+    }
+}
+
+/// A query exceeded one of its [`QueryLimits`]. The memory limit is reported as
+/// [`DataFusionError::ResourcesExhausted`] instead, so that the operators can spill.
+#[derive(Debug, thiserror::Error)]
+pub enum QueryLimitError {
+    #[error("the query scanned more than {0} rows, narrow it down with more selective filters")]
+    RowsScanned(usize),
+    #[error("the query ran for more than {0:?}")]
+    ExecutionTime(Duration),
+}
+
+/// Budget of rows shared by all the table scans of a query, set as session config extension.
+#[derive(Debug)]
+pub(crate) struct ScanBudget {
+    max_rows: usize,
+    scanned: AtomicUsize,
+}
+
+impl ScanBudget {
+    pub(crate) fn new(max_rows: usize) -> Self {
+        Self {
+            max_rows,
+            scanned: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn consume(&self, rows: usize) -> Result<(), DataFusionError> {
+        let scanned = self.scanned.fetch_add(rows, Ordering::Relaxed) + rows;
+        if scanned > self.max_rows {
+            return Err(DataFusionError::External(Box::new(
+                QueryLimitError::RowsScanned(self.max_rows),
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Memory pool limiting the memory of a single query, on top of the pool shared by all queries.
+#[derive(Debug)]
+pub(crate) struct QueryMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+    limit: usize,
+    reserved: AtomicUsize,
+}
+
+impl QueryMemoryPool {
+    pub(crate) fn new(inner: Arc<dyn MemoryPool>, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            reserved: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl MemoryPool for QueryMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer)
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer)
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.reserved.fetch_add(additional, Ordering::Relaxed);
+        self.inner.grow(reservation, additional)
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.reserved.fetch_sub(shrink, Ordering::Relaxed);
+        self.inner.shrink(reservation, shrink)
+    }
+
+    fn try_grow(
+        &self,
+        reservation: &MemoryReservation,
+        additional: usize,
+    ) -> datafusion::common::Result<()> {
+        let reserved = self.reserved.fetch_add(additional, Ordering::Relaxed) + additional;
+        if reserved > self.limit {
+            self.reserved.fetch_sub(additional, Ordering::Relaxed);
+            return Err(DataFusionError::ResourcesExhausted(format!(
+                "the query needs more than the maximum query memory of {} bytes",
+                self.limit
+            )));
+        }
+        self.inner
+            .try_grow(reservation, additional)
+            .inspect_err(|_| {
+                self.reserved.fetch_sub(additional, Ordering::Relaxed);
+            })
+    }
+
+    fn reserved(&self) -> usize {
+        self.reserved.load(Ordering::Relaxed)
+    }
+
+    fn memory_limit(&self) -> MemoryLimit {
+        MemoryLimit::Finite(self.limit)
+    }
+}
+
+/// Fails the wrapped stream once the deadline is reached.
+pub(crate) struct DeadlineStream {
+    inner: SendableRecordBatchStream,
+    deadline: Pin<Box<Sleep>>,
+    max_execution_time: Duration,
+    expired: bool,
+}
+
+impl DeadlineStream {
+    pub(crate) fn new(inner: SendableRecordBatchStream, max_execution_time: Duration) -> Self {
+        Self {
+            inner,
+            deadline: Box::pin(tokio::time::sleep(max_execution_time)),
+            max_execution_time,
+            expired: false,
+        }
+    }
+}
+
+impl Stream for DeadlineStream {
+    type Item = datafusion::common::Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.expired {
+            return Poll::Ready(None);
+        }
+        if let Poll::Ready(item) = self.inner.as_mut().poll_next(cx) {
+            return Poll::Ready(item);
+        }
+
+        ready!(self.deadline.as_mut().poll(cx));
+        self.expired = true;
+        Poll::Ready(Some(Err(DataFusionError::External(Box::new(
+            QueryLimitError::ExecutionTime(self.max_execution_time),
+        )))))
+    }
+}
+
+impl RecordBatchStream for DeadlineStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
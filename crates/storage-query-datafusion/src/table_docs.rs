@@ -9,8 +9,8 @@
 // by the Apache License, Version 2.0.
 
 use crate::{
-    deployment, idempotency, inbox, invocation_state, invocation_status, journal, journal_events,
-    keyed_service_status, promise, service, state,
+    deployment, idempotency, inbox, invocation_state, invocation_stats,
+    invocation_status, journal, journal_events, keyed_service_status, promise, service, state,
 };
 use std::borrow::Cow;
 
@@ -24,6 +24,7 @@ pub const ALL_TABLE_DOCS: &[StaticTableDocs] = &[
     keyed_service_status::schema::TABLE_DOCS,
     inbox::schema::TABLE_DOCS,
     idempotency::schema::TABLE_DOCS,
+    invocation_stats::schema::TABLE_DOCS,
     promise::schema::TABLE_DOCS,
     service::schema::TABLE_DOCS,
     deployment::schema::TABLE_DOCS,
@@ -200,6 +201,9 @@ pub fn sys_invocation_table_docs() -> OwnedTableDocs {
         sys_invocation_state
             .remove("last_attempt_server")
             .expect("last_attempt_server should exist"),
+        sys_invocation_state
+            .remove("last_attempt_diagnostic_headers")
+            .expect("last_attempt_diagnostic_headers should exist"),
         sys_invocation_state
             .remove("last_failure")
             .expect("last_failure should exist"),
@@ -243,3 +247,150 @@ pub fn sys_invocation_table_docs() -> OwnedTableDocs {
         columns,
     }
 }
+
+pub fn sys_service_call_table_docs() -> StaticTableDocs {
+    StaticTableDocs {
+        name: "sys_service_call",
+        description: "Calls between services, aggregated from the journals of the invocations currently stored. Calls made by invocations whose journal was already removed are not included.",
+        columns: &[
+            TableColumn {
+                name: "caller_service_name",
+                column_type: "Utf8",
+                description: "The name of the service performing the calls.",
+            },
+            TableColumn {
+                name: "callee_service_name",
+                column_type: "Utf8",
+                description: "The name of the invoked service.",
+            },
+            TableColumn {
+                name: "calls",
+                column_type: "Int64",
+                description: "Number of calls and one way calls from the caller to the callee.",
+            },
+            TableColumn {
+                name: "completed_calls",
+                column_type: "Int64",
+                description: "Number of calls whose invocation is completed and still retained.",
+            },
+            TableColumn {
+                name: "failed_calls",
+                column_type: "Int64",
+                description: "Number of calls whose invocation completed with a failure.",
+            },
+        ],
+    }
+}
+
+pub fn sys_virtual_object_lock_table_docs() -> StaticTableDocs {
+    StaticTableDocs {
+        name: "sys_virtual_object_lock",
+        description: "The virtual objects and workflows currently locked, together with the invocation holding the lock and the number of invocations queued behind it.",
+        columns: &[
+            TableColumn {
+                name: "service_name",
+                column_type: "Utf8",
+                description: "The name of the virtual object/workflow.",
+            },
+            TableColumn {
+                name: "service_key",
+                column_type: "Utf8",
+                description: "The key of the virtual object/workflow.",
+            },
+            TableColumn {
+                name: "locked_by_id",
+                column_type: "Utf8",
+                description: "[Invocation ID](/operate/invocation#invocation-identifier) of the invocation holding the lock.",
+            },
+            TableColumn {
+                name: "locked_by_handler_name",
+                column_type: "Utf8",
+                description: "The name of the handler of the invocation holding the lock.",
+            },
+            TableColumn {
+                name: "locked_by_status",
+                column_type: "Utf8",
+                description: "Status of the invocation holding the lock, see the `status` column of `sys_invocation`.",
+            },
+            TableColumn {
+                name: "locked_since",
+                column_type: "TimestampMillisecond",
+                description: "Timestamp indicating since when the invocation holds the lock.",
+            },
+            TableColumn {
+                name: "locked_by_retry_count",
+                column_type: "UInt64",
+                description: "The number of attempts of the invocation holding the lock, see the `retry_count` column of `sys_invocation`.",
+            },
+            TableColumn {
+                name: "locked_by_last_failure",
+                column_type: "Utf8",
+                description: "An error message describing the most recent failed attempt of the invocation holding the lock, if any.",
+            },
+            TableColumn {
+                name: "queue_length",
+                column_type: "Int64",
+                description: "Number of invocations and state mutations waiting in the inbox for the lock to be released.",
+            },
+        ],
+    }
+}
+
+pub fn sys_inbox_queue_table_docs() -> StaticTableDocs {
+    StaticTableDocs {
+        name: "sys_inbox_queue",
+        description: "The invocations queued in the inbox of the virtual objects and workflows, waiting for the lock to be released, together with when and by whom they were enqueued.",
+        columns: &[
+            TableColumn {
+                name: "service_name",
+                column_type: "Utf8",
+                description: "The name of the virtual object/workflow.",
+            },
+            TableColumn {
+                name: "service_key",
+                column_type: "Utf8",
+                description: "The key of the virtual object/workflow.",
+            },
+            TableColumn {
+                name: "id",
+                column_type: "Utf8",
+                description: "[Invocation ID](/operate/invocation#invocation-identifier) of the queued invocation.",
+            },
+            TableColumn {
+                name: "sequence_number",
+                column_type: "UInt64",
+                description: "Sequence number in the inbox.",
+            },
+            TableColumn {
+                name: "queue_position",
+                column_type: "UInt64",
+                description: "Position in the queue of the virtual object/workflow key, where `0` is the next entry to acquire the lock once released.",
+            },
+            TableColumn {
+                name: "target_handler_name",
+                column_type: "Utf8",
+                description: "The name of the invoked handler.",
+            },
+            TableColumn {
+                name: "enqueued_at",
+                column_type: "TimestampMillisecond",
+                description: "Timestamp indicating when the invocation was enqueued. Compare it with `running_at` of `sys_invocation` to measure the queuing delay.",
+            },
+            TableColumn {
+                name: "invoked_by",
+                column_type: "Utf8",
+                description: "Source of the invocation, e.g. `ingress` or `service`, see the `invoked_by` column of `sys_invocation`.",
+            },
+            TableColumn {
+                name: "invoked_by_id",
+                column_type: "Utf8",
+                description: "The caller [Invocation ID](/operate/invocation#invocation-identifier) if `invoked_by = 'service'`.",
+            },
+            TableColumn {
+                name: "invoked_by_subscription_id",
+                column_type: "Utf8",
+                description: "The subscription id if `invoked_by = 'subscription'`.",
+            },
+        ],
+    }
+}
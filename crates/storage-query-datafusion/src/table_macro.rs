@@ -34,6 +34,9 @@ macro_rules! define_builder {
     (DataType::Int32) => {
         ::datafusion::arrow::array::Int32Builder
     };
+    (DataType::Float64) => {
+        ::datafusion::arrow::array::Float64Builder
+    };
     (TimestampMillisecond) => {
         TimestampMillisecondUTCBuilder
     };
@@ -222,6 +225,9 @@ macro_rules! define_primitive_trait {
     (DataType::Int32) => {
         i32
     };
+    (DataType::Float64) => {
+        f64
+    };
     (TimestampMillisecond) => {
         i64
     };
@@ -258,6 +264,9 @@ macro_rules! define_data_type {
     (DataType::Int32) => {
         DataType::Int32
     };
+    (DataType::Float64) => {
+        DataType::Float64
+    };
     (TimestampMillisecond) => {
         DataType::Timestamp(
             ::datafusion::arrow::datatypes::TimeUnit::Millisecond,
@@ -298,6 +307,9 @@ macro_rules! document_type {
     (DataType::Int32) => {
         "Int32"
     };
+    (DataType::Float64) => {
+        "Float64"
+    };
     (TimestampMillisecond) => {
         "TimestampMillisecond"
     };
@@ -20,6 +20,7 @@ use datafusion::execution::context::TaskContext;
 use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
 use datafusion::physical_expr::EquivalenceProperties;
 use datafusion::physical_plan::execution_plan::{Boundedness, EmissionType};
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricBuilder, MetricsSet};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
@@ -32,6 +33,7 @@ use restate_types::partition_table::Partition;
 
 use crate::context::SelectPartitions;
 use crate::partition_filter::{FirstMatchingPartitionKeyExtractor, PartitionKeyExtractor};
+use crate::query_limits::ScanBudget;
 use crate::table_util::{find_sort_columns, make_ordering};
 
 pub trait ScanPartition: Send + Sync + Debug + 'static {
@@ -226,6 +228,7 @@ where
             scanner: self.partition_scanner.clone(),
             plan,
             statistics: self.statistics.clone().project(projection),
+            metrics: ExecutionPlanMetricsSet::new(),
         }))
     }
 
@@ -250,6 +253,36 @@ struct PartitionedExecutionPlan<T> {
     scanner: T,
     plan: PlanProperties,
     statistics: Statistics,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl<T> PartitionedExecutionPlan<T> {
+    /// Describes what was pushed down to the scan, shown by `EXPLAIN`.
+    fn fmt_pushdowns(&self, f: &mut Formatter, separator: &str) -> std::fmt::Result {
+        let physical_partitions = self
+            .logical_partitions
+            .iter()
+            .flat_map(|p| &p.physical_partitions);
+        let point_reads = physical_partitions
+            .clone()
+            .filter(|(_, p)| p.key_range.start() == p.key_range.end())
+            .count();
+        write!(
+            f,
+            "partitions={}{separator}partition_key_point_reads={point_reads}",
+            physical_partitions.count()
+        )?;
+        if let Some(limit) = self.limit {
+            write!(f, "{separator}limit={limit}")?;
+        }
+        let projection = self
+            .projected_schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect::<Vec<_>>();
+        write!(f, "{separator}projection=[{}]", projection.join(", "))
+    }
 }
 
 impl<T> ExecutionPlan for PartitionedExecutionPlan<T>
@@ -300,11 +333,18 @@ where
         Ok(self.statistics.clone())
     }
 
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
     fn execute(
         &self,
         partition: usize,
         context: Arc<TaskContext>,
     ) -> datafusion::common::Result<SendableRecordBatchStream> {
+        let output_rows = MetricBuilder::new(&self.metrics).output_rows(partition);
+        let scan_budget = context.session_config().get_extension::<ScanBudget>();
+
         let physical_partitions = self
             .logical_partitions
             .get(partition)
@@ -330,7 +370,15 @@ where
                         .map_err(|e| DataFusionError::External(e.into()))
                 }
             })
-            .try_flatten();
+            .try_flatten()
+            .map(move |batch| {
+                let batch = batch?;
+                output_rows.add(batch.num_rows());
+                if let Some(scan_budget) = &scan_budget {
+                    scan_budget.consume(batch.num_rows())?;
+                }
+                Ok(batch)
+            });
 
         Ok(Box::pin(RecordBatchStreamAdapter::new(
             self.projected_schema.clone(),
@@ -346,10 +394,12 @@ where
     fn fmt_as(&self, t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
         match t {
             DisplayFormatType::Default | DisplayFormatType::Verbose => {
-                write!(f, "PartitionedExecutionPlan({:?})", self.scanner)
+                write!(f, "PartitionedExecutionPlan({:?}): ", self.scanner)?;
+                self.fmt_pushdowns(f, ", ")
             }
             DisplayFormatType::TreeRender => {
-                write!(f, "PartitionedExecutionPlan\nscanner={:?}", self.scanner)
+                write!(f, "PartitionedExecutionPlan\nscanner={:?}\n", self.scanner)?;
+                self.fmt_pushdowns(f, "\n")
             }
         }
     }
@@ -8,30 +8,50 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::num::NonZeroUsize;
 use std::time::{Duration, SystemTime};
 
-use datafusion::arrow::array::{LargeStringArray, TimestampMillisecondArray, UInt64Array};
+use bytes::Bytes;
+use datafusion::arrow::array::{
+    Array, DurationMillisecondArray, Int64Array, LargeStringArray, StringArray,
+    TimestampMillisecondArray, UInt64Array,
+};
 use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
 use futures::StreamExt;
 use googletest::all;
 use googletest::prelude::{assert_that, eq};
 
+use crate::QueryLimitError;
 use crate::mocks::*;
 use crate::row;
 use restate_invoker_api::status_handle::InvocationStatusReportInner;
 use restate_invoker_api::status_handle::test_util::MockStatusHandle;
-use restate_invoker_api::{InvocationErrorReport, InvocationStatusReport};
+use restate_invoker_api::{
+    InvocationErrorReport, InvocationStats, InvocationStatsReport, InvocationStatusReport,
+};
 use restate_storage_api::Transaction;
+use restate_storage_api::inbox_table::{InboxEntry, WriteInboxTable};
 use restate_storage_api::invocation_status_table::{
-    CompletedInvocation, InFlightInvocationMetadata, InvocationStatus, WriteInvocationStatusTable,
+    CompletedInvocation, InFlightInvocationMetadata, InboxedInvocation, InvocationStatus,
+    PreFlightInvocationMetadata, StatusTimestamps, WriteInvocationStatusTable,
 };
+use restate_storage_api::journal_table::{JournalEntry, WriteJournalTable};
+use restate_storage_api::service_status_table::{
+    VirtualObjectStatus, WriteVirtualObjectStatusTable,
+};
+use restate_types::config::QueryEngineOptions;
 use restate_types::errors::InvocationError;
 use restate_types::identifiers::PartitionId;
-use restate_types::identifiers::{DeploymentId, InvocationId};
+use restate_types::identifiers::{DeploymentId, InvocationId, ServiceId};
 use restate_types::identifiers::{InvocationUuid, LeaderEpoch};
-use restate_types::invocation::InvocationTarget;
+use restate_types::invocation::{InvocationTarget, ResponseResult, VirtualObjectHandlerType};
 use restate_types::journal::EntryType;
+use restate_types::journal::enriched::{
+    CallEnrichmentResult, EnrichedEntryHeader, EnrichedRawEntry,
+};
 use restate_types::service_protocol::ServiceProtocolVersion;
+use restate_types::time::MillisSinceEpoch;
 
 #[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
 async fn query_sys_invocation() {
@@ -58,6 +78,10 @@ async fn query_sys_invocation() {
                 last_attempt_deployment_id: Some(DeploymentId::new()),
                 last_attempt_protocol_version: Some(ServiceProtocolVersion::V3),
                 last_attempt_server: Some("restate-sdk-java/0.8.0".to_owned()),
+                last_attempt_diagnostic_headers: vec![(
+                    "x-request-id".to_owned(),
+                    "7c1f".to_owned(),
+                )],
             },
         )),
         MockSchemas::default(),
@@ -90,6 +114,7 @@ async fn query_sys_invocation() {
                     "last_failure_related_entry_name" => LargeStringArray: eq("my-side-effect"),
                     "last_failure_related_entry_type" => LargeStringArray: eq(EntryType::Run.to_string()),
                     "last_attempt_server" => LargeStringArray: eq("restate-sdk-java/0.8.0"),
+                    "last_attempt_diagnostic_headers" => LargeStringArray: eq(r#"{"x-request-id":"7c1f"}"#),
                 }
             ))
         );
@@ -105,7 +130,8 @@ async fn query_sys_invocation() {
                 last_failure_related_entry_index,
                 last_failure_related_entry_name,
                 last_failure_related_entry_type,
-                last_attempt_server
+                last_attempt_server,
+                last_attempt_diagnostic_headers
             FROM sys_invocation
             LIMIT 1",
         )
@@ -127,7 +153,8 @@ async fn query_sys_invocation() {
                 last_failure_related_entry_index,
                 last_failure_related_entry_name,
                 last_failure_related_entry_type,
-                last_attempt_server
+                last_attempt_server,
+                last_attempt_diagnostic_headers
             FROM sys_invocation
             ORDER BY last_failure_related_entry_index
             LIMIT 1",
@@ -150,7 +177,8 @@ async fn query_sys_invocation() {
                 last_failure_related_entry_index,
                 last_failure_related_entry_name,
                 last_failure_related_entry_type,
-                last_attempt_server
+                last_attempt_server,
+                last_attempt_diagnostic_headers
             FROM sys_invocation
             WHERE id = '{invocation_id}'
             LIMIT 1"
@@ -189,6 +217,7 @@ async fn query_sys_invocation_with_protocol_v4() {
                 last_attempt_deployment_id: Some(DeploymentId::new()),
                 last_attempt_protocol_version: Some(ServiceProtocolVersion::V4),
                 last_attempt_server: Some("restate-sdk-java/1.3.0".to_owned()),
+                last_attempt_diagnostic_headers: vec![],
             },
         )),
         MockSchemas::default(),
@@ -327,3 +356,413 @@ async fn query_sys_invocation_status_completed() {
         )
     );
 }
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn query_sys_invocation_stats() {
+    let mut stats = InvocationStats {
+        attempts: 3,
+        completed: 1,
+        suspended: 1,
+        failed: 1,
+        ..InvocationStats::default()
+    };
+    stats.attempt_duration.record(Duration::from_millis(10));
+    stats.attempt_duration.record(Duration::from_millis(20));
+    stats.attempt_duration.record(Duration::from_millis(30));
+
+    let engine = MockQueryEngine::create_with(
+        MockStatusHandle::default().with_stats(InvocationStatsReport {
+            partition_id: PartitionId::MIN,
+            bucket_start: SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+            bucket_width: Duration::from_secs(60),
+            service_name: "MySvc".into(),
+            handler_name: "MyMethod".into(),
+            stats,
+        }),
+        MockSchemas::default(),
+    )
+    .await;
+
+    let records = engine
+        .execute(
+            "SELECT
+                bucket_start,
+                service_name,
+                handler_name,
+                attempts,
+                completed,
+                suspended,
+                failed,
+                attempt_duration_avg,
+                attempt_duration_max
+            FROM sys_invocation_stats",
+        )
+        .await
+        .unwrap()
+        .collect::<Vec<Result<RecordBatch, _>>>()
+        .await
+        .remove(0)
+        .unwrap();
+
+    assert_that!(
+        records,
+        all!(row!(
+            0,
+            {
+                "bucket_start" => TimestampMillisecondArray: eq(60_000),
+                "service_name" => LargeStringArray: eq("MySvc"),
+                "handler_name" => LargeStringArray: eq("MyMethod"),
+                "attempts" => UInt64Array: eq(3),
+                "completed" => UInt64Array: eq(1),
+                "suspended" => UInt64Array: eq(1),
+                "failed" => UInt64Array: eq(1),
+                "attempt_duration_avg" => DurationMillisecondArray: eq(20),
+                "attempt_duration_max" => DurationMillisecondArray: eq(30),
+            }
+        ))
+    );
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn query_exceeding_max_rows_scanned() {
+    let options = QueryEngineOptions {
+        max_rows_scanned: NonZeroUsize::new(1),
+        ..QueryEngineOptions::default()
+    };
+    let mut engine = MockQueryEngine::create_with_options(
+        &options,
+        MockStatusHandle::default(),
+        MockSchemas::default(),
+    )
+    .await;
+
+    let invocation_ids = [InvocationId::mock_random(), InvocationId::mock_random()];
+    let mut tx = engine.partition_store().transaction();
+    for invocation_id in &invocation_ids {
+        tx.put_invocation_status(
+            invocation_id,
+            &InvocationStatus::Completed(CompletedInvocation::mock_neo()),
+        )
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    // Point reads only scan the rows they need
+    let records = engine
+        .execute(format!(
+            "SELECT id FROM sys_invocation_status WHERE id = '{}'",
+            invocation_ids[0]
+        ))
+        .await
+        .unwrap()
+        .collect::<Vec<Result<RecordBatch, _>>>()
+        .await;
+    assert!(records.into_iter().all(|batch| batch.is_ok()));
+
+    let err = engine
+        .execute("SELECT id FROM sys_invocation_status")
+        .await
+        .unwrap()
+        .collect::<Vec<Result<RecordBatch, _>>>()
+        .await
+        .into_iter()
+        .find_map(Result::err)
+        .expect("the query exceeds the scanned rows limit");
+    let DataFusionError::External(err) = err.find_root() else {
+        panic!("unexpected error {err}");
+    };
+    assert!(matches!(
+        err.downcast_ref::<QueryLimitError>(),
+        Some(QueryLimitError::RowsScanned(1))
+    ));
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn explain_shows_the_pushdowns() {
+    let engine = MockQueryEngine::create().await;
+
+    let records = engine
+        .execute(format!(
+            "EXPLAIN SELECT id FROM sys_invocation_status WHERE id = '{}' LIMIT 1",
+            InvocationId::mock_random()
+        ))
+        .await
+        .unwrap()
+        .collect::<Vec<Result<RecordBatch, _>>>()
+        .await
+        .remove(0)
+        .unwrap();
+
+    let plans = records
+        .column_by_name("plan")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    let physical_plan = plans.value(plans.len() - 1);
+    assert!(
+        physical_plan.contains("partitions=1, partition_key_point_reads=1"),
+        "{physical_plan}"
+    );
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn query_sys_service_call() {
+    let caller_id = InvocationId::mock_random();
+    let succeeded_id = InvocationId::mock_random();
+    let failed_id = InvocationId::mock_random();
+    let call = |invocation_id, invocation_target| CallEnrichmentResult {
+        invocation_id,
+        invocation_target,
+        completion_retention_time: None,
+        span_context: Default::default(),
+    };
+
+    let mut engine = MockQueryEngine::create().await;
+
+    let mut tx = engine.partition_store().transaction();
+    tx.put_invocation_status(
+        &caller_id,
+        &InvocationStatus::Invoked(InFlightInvocationMetadata {
+            invocation_target: InvocationTarget::service("Checkout", "process"),
+            ..InFlightInvocationMetadata::mock()
+        }),
+    )
+    .unwrap();
+    tx.put_invocation_status(
+        &succeeded_id,
+        &InvocationStatus::Completed(CompletedInvocation::mock_neo()),
+    )
+    .unwrap();
+    tx.put_invocation_status(
+        &failed_id,
+        &InvocationStatus::Completed(CompletedInvocation {
+            response_result: ResponseResult::Failure(InvocationError::internal("my error")),
+            ..CompletedInvocation::mock_neo()
+        }),
+    )
+    .unwrap();
+    let entries = [
+        EnrichedEntryHeader::Call {
+            is_completed: true,
+            enrichment_result: Some(call(
+                succeeded_id,
+                InvocationTarget::virtual_object(
+                    "Cart",
+                    "my-key",
+                    "get",
+                    VirtualObjectHandlerType::Shared,
+                ),
+            )),
+        },
+        EnrichedEntryHeader::Call {
+            is_completed: true,
+            enrichment_result: Some(call(
+                failed_id,
+                InvocationTarget::service("Cart", "checkout"),
+            )),
+        },
+        EnrichedEntryHeader::OneWayCall {
+            enrichment_result: call(
+                InvocationId::mock_random(),
+                InvocationTarget::service("Email", "send"),
+            ),
+        },
+    ];
+    for (index, header) in entries.into_iter().enumerate() {
+        tx.put_journal_entry(
+            &caller_id,
+            index as u32 + 1,
+            &JournalEntry::Entry(EnrichedRawEntry::new(header, Bytes::new())),
+        )
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let records = engine
+        .execute("SELECT * FROM sys_service_call ORDER BY callee_service_name")
+        .await
+        .unwrap()
+        .collect::<Vec<Result<RecordBatch, _>>>()
+        .await
+        .remove(0)
+        .unwrap();
+
+    assert_that!(
+        records,
+        all!(
+            row!(
+                0,
+                {
+                    "caller_service_name" => LargeStringArray: eq("Checkout"),
+                    "callee_service_name" => LargeStringArray: eq("Cart"),
+                    "calls" => Int64Array: eq(2),
+                    "completed_calls" => Int64Array: eq(2),
+                    "failed_calls" => Int64Array: eq(1),
+                }
+            ),
+            row!(
+                1,
+                {
+                    "caller_service_name" => LargeStringArray: eq("Checkout"),
+                    "callee_service_name" => LargeStringArray: eq("Email"),
+                    "calls" => Int64Array: eq(1),
+                    "completed_calls" => Int64Array: eq(0),
+                    "failed_calls" => Int64Array: eq(0),
+                }
+            ),
+        )
+    );
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn query_sys_virtual_object_lock() {
+    let locked_service_id = ServiceId::new("Counter", "locked-key");
+    let holder_target = InvocationTarget::virtual_object(
+        "Counter",
+        "locked-key",
+        "add",
+        VirtualObjectHandlerType::Exclusive,
+    );
+    let holder_id = InvocationId::mock_generate(&holder_target);
+    let locked_since = MillisSinceEpoch::new(1_000);
+
+    let mut engine = MockQueryEngine::create().await;
+
+    let mut tx = engine.partition_store().transaction();
+    tx.put_invocation_status(
+        &holder_id,
+        &InvocationStatus::Invoked(InFlightInvocationMetadata {
+            invocation_target: holder_target,
+            timestamps: StatusTimestamps::new(
+                MillisSinceEpoch::new(500),
+                locked_since,
+                Some(MillisSinceEpoch::new(500)),
+                None,
+                Some(locked_since),
+                None,
+            ),
+            ..InFlightInvocationMetadata::mock()
+        }),
+    )
+    .unwrap();
+    tx.put_virtual_object_status(&locked_service_id, &VirtualObjectStatus::Locked(holder_id))
+        .unwrap();
+    for sequence_number in 0..2 {
+        tx.put_inbox_entry(
+            sequence_number,
+            &InboxEntry::Invocation(locked_service_id.clone(), InvocationId::mock_random()),
+        )
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let records = engine
+        .execute("SELECT * FROM sys_virtual_object_lock")
+        .await
+        .unwrap()
+        .collect::<Vec<Result<RecordBatch, _>>>()
+        .await
+        .remove(0)
+        .unwrap();
+
+    assert_eq!(records.num_rows(), 1);
+    assert_that!(
+        records,
+        row!(
+            0,
+            {
+                "service_name" => LargeStringArray: eq("Counter"),
+                "service_key" => LargeStringArray: eq("locked-key"),
+                "locked_by_id" => LargeStringArray: eq(holder_id.to_string()),
+                "locked_by_handler_name" => LargeStringArray: eq("add"),
+                "locked_by_status" => LargeStringArray: eq("ready"),
+                "locked_since" => TimestampMillisecondArray: eq(locked_since.as_u64() as i64),
+                "queue_length" => Int64Array: eq(2),
+            }
+        )
+    );
+}
+
+#[restate_core::test(flavor = "multi_thread", worker_threads = 2)]
+async fn query_sys_inbox_queue() {
+    let service_id = ServiceId::new("Counter", "contended-key");
+    let mut engine = MockQueryEngine::create().await;
+
+    let mut tx = engine.partition_store().transaction();
+    let mut queued_ids = vec![];
+    // Sequence numbers of the inbox are shared by all the keys of the partition
+    for (position, sequence_number) in [3, 7].into_iter().enumerate() {
+        let invocation_target = InvocationTarget::virtual_object(
+            "Counter",
+            "contended-key",
+            "add",
+            VirtualObjectHandlerType::Exclusive,
+        );
+        let invocation_id = InvocationId::mock_generate(&invocation_target);
+        let enqueued_at = MillisSinceEpoch::new(1_000 + position as u64);
+        tx.put_invocation_status(
+            &invocation_id,
+            &InvocationStatus::Inboxed(InboxedInvocation {
+                inbox_sequence_number: sequence_number,
+                metadata: PreFlightInvocationMetadata {
+                    invocation_target,
+                    timestamps: StatusTimestamps::new(
+                        enqueued_at,
+                        enqueued_at,
+                        Some(enqueued_at),
+                        None,
+                        None,
+                        None,
+                    ),
+                    ..PreFlightInvocationMetadata::mock()
+                },
+            }),
+        )
+        .unwrap();
+        tx.put_inbox_entry(
+            sequence_number,
+            &InboxEntry::Invocation(service_id.clone(), invocation_id),
+        )
+        .unwrap();
+        queued_ids.push(invocation_id);
+    }
+    tx.commit().await.unwrap();
+
+    let records = engine
+        .execute("SELECT * FROM sys_inbox_queue ORDER BY queue_position")
+        .await
+        .unwrap()
+        .collect::<Vec<Result<RecordBatch, _>>>()
+        .await
+        .remove(0)
+        .unwrap();
+
+    assert_eq!(records.num_rows(), 2);
+    assert_that!(
+        records,
+        all!(
+            row!(
+                0,
+                {
+                    "service_key" => LargeStringArray: eq("contended-key"),
+                    "id" => LargeStringArray: eq(queued_ids[0].to_string()),
+                    "sequence_number" => UInt64Array: eq(3),
+                    "queue_position" => UInt64Array: eq(0),
+                    "target_handler_name" => LargeStringArray: eq("add"),
+                    "enqueued_at" => TimestampMillisecondArray: eq(1_000),
+                    "invoked_by" => LargeStringArray: eq("ingress"),
+                }
+            ),
+            row!(
+                1,
+                {
+                    "id" => LargeStringArray: eq(queued_ids[1].to_string()),
+                    "sequence_number" => UInt64Array: eq(7),
+                    "queue_position" => UInt64Array: eq(1),
+                    "enqueued_at" => TimestampMillisecondArray: eq(1_001),
+                }
+            )
+        )
+    );
+}
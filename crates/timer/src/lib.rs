@@ -12,11 +12,15 @@ extern crate core;
 
 use std::future::Future;
 
+pub mod metric_definitions;
 mod service;
 
 use restate_types::timer::Timer;
 pub use service::TimerService;
-pub use service::clock::{Clock, TokioClock};
+pub use service::clock::{Clock, ManualSleep, RuntimeClock, TokioClock};
+
+#[cfg(any(test, feature = "test-util"))]
+pub use service::clock::ManualClock;
 
 pub trait TimerReader<T>
 where
@@ -0,0 +1,29 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+/// Optional to have but adds description/help message to the metrics emitted to
+/// the metrics' sink.
+use metrics::{Unit, describe_counter};
+
+pub const TIMER_SPILLED_TO_STORAGE: &str = "restate.timer.spilled_to_storage.total";
+pub const TIMER_STORAGE_SCANS: &str = "restate.timer.storage_scans.total";
+
+pub fn describe_metrics() {
+    describe_counter!(
+        TIMER_SPILLED_TO_STORAGE,
+        Unit::Count,
+        "Number of timers not kept in memory because of the in memory limit, to be loaded again from storage"
+    );
+    describe_counter!(
+        TIMER_STORAGE_SCANS,
+        Unit::Count,
+        "Number of scans of the storage for loading timers in memory"
+    );
+}
@@ -11,11 +11,16 @@
 use restate_types::time::MillisSinceEpoch;
 use std::future::Future;
 use std::ops::Add;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use std::time::{Duration, SystemTime};
 
 pub trait Clock {
     type SleepFuture: Future<Output = ()>;
 
+    /// Returns the current time of this clock.
+    fn now(&self) -> MillisSinceEpoch;
+
     /// Returns a sleep future that completes when `wake_up_time` is reached. None if this moment
     /// has already passed.
     fn sleep_until(&mut self, wake_up_time: MillisSinceEpoch) -> Option<Self::SleepFuture>;
@@ -26,6 +31,10 @@ pub struct TokioClock;
 impl Clock for TokioClock {
     type SleepFuture = tokio::time::Sleep;
 
+    fn now(&self) -> MillisSinceEpoch {
+        MillisSinceEpoch::now()
+    }
+
     fn sleep_until(&mut self, wake_up_time: MillisSinceEpoch) -> Option<Self::SleepFuture> {
         let now = SystemTime::now();
 
@@ -40,16 +49,80 @@ impl Clock for TokioClock {
     }
 }
 
-#[cfg(test)]
-pub mod tests {
-    use crate::service::clock::Clock;
-    use futures_util::future::{BoxFuture, FutureExt};
+/// Clock which is selected at runtime. Components which are wired up through the whole node (e.g.
+/// the timer service of the partition processor leader) use this clock so that tests can inject a
+/// [`ManualClock`] without changing the type of the component.
+#[derive(Debug, Clone, Default)]
+pub enum RuntimeClock {
+    /// Wall clock time driven by tokio's timers.
+    #[default]
+    Tokio,
+    /// Virtual time which only advances when told so.
+    #[cfg(any(test, feature = "test-util"))]
+    Manual(ManualClock),
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl From<ManualClock> for RuntimeClock {
+    fn from(clock: ManualClock) -> Self {
+        RuntimeClock::Manual(clock)
+    }
+}
+
+impl Clock for RuntimeClock {
+    type SleepFuture = tokio_util::either::Either<tokio::time::Sleep, ManualSleep>;
+
+    fn now(&self) -> MillisSinceEpoch {
+        match self {
+            RuntimeClock::Tokio => TokioClock.now(),
+            #[cfg(any(test, feature = "test-util"))]
+            RuntimeClock::Manual(clock) => clock.now(),
+        }
+    }
+
+    fn sleep_until(&mut self, wake_up_time: MillisSinceEpoch) -> Option<Self::SleepFuture> {
+        match self {
+            RuntimeClock::Tokio => TokioClock
+                .sleep_until(wake_up_time)
+                .map(tokio_util::either::Either::Left),
+            #[cfg(any(test, feature = "test-util"))]
+            RuntimeClock::Manual(clock) => clock
+                .sleep_until(wake_up_time)
+                .map(tokio_util::either::Either::Right),
+        }
+    }
+}
+
+/// Sleep future of the [`ManualClock`]. Completes once the clock has been advanced past the
+/// wake up time or when the clock is dropped.
+#[derive(Debug)]
+pub struct ManualSleep {
+    rx: tokio::sync::oneshot::Receiver<()>,
+}
+
+impl Future for ManualSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx).map(|_| ())
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+pub use manual::ManualClock;
+
+#[cfg(any(test, feature = "test-util"))]
+mod manual {
+    use super::{Clock, ManualSleep};
     use restate_types::time::MillisSinceEpoch;
     use std::cmp::{Ordering, Reverse};
     use std::collections::BinaryHeap;
     use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
+    /// Clock whose time only moves when it is explicitly advanced. Clones share the same time, so
+    /// a test can keep a handle to fast-forward the sleeps of the components it injected the clock
+    /// into.
     #[derive(Debug, Clone)]
     pub struct ManualClock {
         inner: Arc<Mutex<InnerManualClock>>,
@@ -62,11 +135,21 @@ pub mod tests {
             }
         }
 
-        pub fn advance_time_by(&mut self, duration: Duration) {
+        /// Returns the current virtual time.
+        pub fn now(&self) -> MillisSinceEpoch {
+            self.inner.lock().unwrap().time
+        }
+
+        /// Advances the time by `duration` and wakes up all sleeps which are due.
+        pub fn advance(&self, duration: Duration) {
             self.inner.lock().unwrap().advance_time(duration);
         }
 
-        pub fn advance_time_to(&mut self, time: MillisSinceEpoch) {
+        /// Advances the time to `time` and wakes up all sleeps which are due.
+        ///
+        /// # Panics
+        /// If `time` lies before the current time of the clock.
+        pub fn advance_to(&self, time: MillisSinceEpoch) {
             let mut inner = self.inner.lock().unwrap();
             assert!(inner.time <= time);
 
@@ -75,15 +158,25 @@ pub mod tests {
         }
     }
 
+    impl Default for ManualClock {
+        fn default() -> Self {
+            Self::new(MillisSinceEpoch::now())
+        }
+    }
+
     impl Clock for ManualClock {
-        type SleepFuture = BoxFuture<'static, ()>;
+        type SleepFuture = ManualSleep;
+
+        fn now(&self) -> MillisSinceEpoch {
+            ManualClock::now(self)
+        }
 
         fn sleep_until(&mut self, wake_up_time: MillisSinceEpoch) -> Option<Self::SleepFuture> {
             self.inner
                 .lock()
                 .unwrap()
                 .sleep_until(wake_up_time)
-                .map(|rx| rx.map(|result| result.unwrap_or_default()).boxed())
+                .map(|rx| ManualSleep { rx })
         }
     }
 
@@ -91,7 +184,7 @@ pub mod tests {
     struct InnerManualClock {
         current_sleep_future_id: usize,
         time: MillisSinceEpoch,
-        pending_sleep_futures: BinaryHeap<Reverse<SleepFuture>>,
+        pending_sleep_futures: BinaryHeap<Reverse<PendingSleep>>,
     }
 
     impl InnerManualClock {
@@ -129,7 +222,7 @@ pub mod tests {
             } else {
                 let (waker, rx) = tokio::sync::oneshot::channel();
                 self.current_sleep_future_id += 1;
-                self.pending_sleep_futures.push(Reverse(SleepFuture {
+                self.pending_sleep_futures.push(Reverse(PendingSleep {
                     id: self.current_sleep_future_id,
                     wake_up_time,
                     waker,
@@ -141,27 +234,27 @@ pub mod tests {
     }
 
     #[derive(Debug)]
-    pub struct SleepFuture {
+    struct PendingSleep {
         id: usize,
         wake_up_time: MillisSinceEpoch,
         waker: tokio::sync::oneshot::Sender<()>,
     }
 
-    impl PartialEq for SleepFuture {
+    impl PartialEq for PendingSleep {
         fn eq(&self, other: &Self) -> bool {
             self.id == other.id
         }
     }
 
-    impl Eq for SleepFuture {}
+    impl Eq for PendingSleep {}
 
-    impl PartialOrd for SleepFuture {
+    impl PartialOrd for PendingSleep {
         fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
             Some(self.cmp(other))
         }
     }
 
-    impl Ord for SleepFuture {
+    impl Ord for PendingSleep {
         fn cmp(&self, other: &Self) -> Ordering {
             self.wake_up_time
                 .cmp(&other.wake_up_time)
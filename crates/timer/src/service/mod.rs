@@ -10,7 +10,9 @@
 
 #![allow(clippy::enum_variant_names)]
 
+use metrics::counter;
 use pin_project::pin_project;
+use restate_types::time::MillisSinceEpoch;
 use restate_types::timer::TimerKey;
 use std::collections::HashSet;
 use std::fmt::Debug;
@@ -18,16 +20,17 @@ use std::future;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll, Waker, ready};
+use std::time::{Duration, Instant};
 use tokio_util::sync::ReusableBoxFuture;
 use tracing::trace;
 
+use crate::metric_definitions::{TIMER_SPILLED_TO_STORAGE, TIMER_STORAGE_SCANS};
+use wheel::TimerWheel;
+
 pub mod clock;
 #[cfg(test)]
 mod tests;
-
-// Using ahash for faster hashing operations. See: https://github.com/garro95/priority-queue#speeding-up
-type DoublePriorityQueue<T> =
-    priority_queue::DoublePriorityQueue<T, <T as crate::Timer>::TimerKey, ahash::RandomState>;
+mod wheel;
 
 #[pin_project(project = StateProj)]
 enum State<TimerKey, SleepFuture> {
@@ -62,6 +65,98 @@ enum ProcessTimersState<TimerKey, SleepFuture> {
     TriggerTimer,
 }
 
+/// Limit of the timers kept in memory. It is either a fixed number of timers, or it's derived from
+/// a memory budget and the average memory size of the recently seen timers, or the stricter of the
+/// two if both are configured.
+#[derive(Debug)]
+struct InMemoryLimit {
+    num_timers: Option<usize>,
+    memory_budget: Option<usize>,
+    /// Exponentially weighted moving average of the memory size of the timers in the queue
+    avg_timer_size: usize,
+}
+
+impl InMemoryLimit {
+    /// Weight of the memory size of a new timer in the moving average is 1 / 2^SMOOTHING_SHIFT
+    const SMOOTHING_SHIFT: u32 = 4;
+
+    fn new(
+        num_timers: Option<usize>,
+        memory_budget: Option<usize>,
+        initial_timer_size: usize,
+    ) -> Self {
+        Self {
+            num_timers,
+            memory_budget,
+            avg_timer_size: initial_timer_size.max(1),
+        }
+    }
+
+    fn get(&self) -> Option<usize> {
+        let by_memory_budget = self
+            .memory_budget
+            .map(|memory_budget| (memory_budget / self.avg_timer_size).max(1));
+        match (self.num_timers, by_memory_budget) {
+            (Some(num_timers), Some(by_memory_budget)) => Some(num_timers.min(by_memory_budget)),
+            (num_timers, by_memory_budget) => num_timers.or(by_memory_budget),
+        }
+    }
+
+    fn record_timer_size(&mut self, timer_size: usize) {
+        if self.memory_budget.is_some() {
+            self.avg_timer_size = (self.avg_timer_size
+                - (self.avg_timer_size >> Self::SMOOTHING_SHIFT)
+                + (timer_size >> Self::SMOOTHING_SHIFT))
+                .max(1);
+        }
+    }
+}
+
+/// Number of timers fired during the last complete second.
+#[derive(Debug)]
+struct FiredTimersRate {
+    window_start: Instant,
+    current_window: u64,
+    previous_window: u64,
+}
+
+impl FiredTimersRate {
+    const WINDOW: Duration = Duration::from_secs(1);
+
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            current_window: 0,
+            previous_window: 0,
+        }
+    }
+
+    fn record_fired(&mut self) {
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Self::WINDOW {
+            self.previous_window = if elapsed < 2 * Self::WINDOW {
+                self.current_window
+            } else {
+                0
+            };
+            self.current_window = 0;
+            self.window_start += Self::WINDOW * (elapsed.as_secs() as u32);
+        }
+        self.current_window += 1;
+    }
+
+    fn get(&self) -> u64 {
+        let elapsed = self.window_start.elapsed();
+        if elapsed < Self::WINDOW {
+            self.previous_window
+        } else if elapsed < 2 * Self::WINDOW {
+            self.current_window
+        } else {
+            0
+        }
+    }
+}
+
 /// Current batch of timers that is being processed by the service
 #[derive(Debug)]
 struct TimerBatch<T> {
@@ -102,9 +197,15 @@ where
 
     max_fired_timer: Option<Timer::TimerKey>,
 
-    timer_queue: DoublePriorityQueue<Timer>,
+    timer_queue: TimerWheel<Timer>,
 
-    num_timers_in_memory_limit: Option<usize>,
+    in_memory_limit: InMemoryLimit,
+
+    max_batch_size: usize,
+
+    batch_window: Duration,
+
+    fired_timers_rate: FiredTimersRate,
 }
 
 async fn get_timers<Timer, TimerReader>(
@@ -116,6 +217,7 @@ where
     Timer: crate::Timer + Debug,
     TimerReader: crate::TimerReader<Timer>,
 {
+    counter!(TIMER_STORAGE_SCANS).increment(1);
     let result = timer_reader
         .get_timers(num_timers, previous_timer_key)
         .await;
@@ -132,35 +234,73 @@ where
         clock: Clock,
         num_timers_in_memory_limit: Option<usize>,
         timer_reader: TimerReader,
+    ) -> Self {
+        Self::new_with_memory_budget(clock, num_timers_in_memory_limit, None, timer_reader)
+    }
+
+    /// Creates a timer service which additionally bounds the timers kept in memory by the given
+    /// memory budget in bytes. The number of timers fitting the budget is continuously adapted to
+    /// the [estimated memory size](restate_types::timer::Timer::estimated_memory_size) of the
+    /// timers.
+    pub fn new_with_memory_budget(
+        clock: Clock,
+        num_timers_in_memory_limit: Option<usize>,
+        memory_budget: Option<usize>,
+        timer_reader: TimerReader,
     ) -> Self {
         debug_assert!(
             num_timers_in_memory_limit.unwrap_or(usize::MAX) >= 1,
             "Timer service needs to keep at least one timer in memory."
         );
+        let in_memory_limit = InMemoryLimit::new(
+            num_timers_in_memory_limit,
+            memory_budget,
+            Self::queue_entry_overhead() + size_of::<Timer>(),
+        );
         Self {
             clock,
             timer_reader: None,
             read_future: ReusableBoxFuture::new(get_timers(
                 timer_reader,
-                num_timers_in_memory_limit.unwrap_or(usize::MAX),
+                in_memory_limit.get().unwrap_or(usize::MAX),
                 None,
             )),
-            num_timers_in_memory_limit,
+            in_memory_limit,
             state: State::LoadTimers {
                 removed_timers: Some(HashSet::default()),
             },
             removed_timers: None,
             max_fired_timer: None,
-            timer_queue: DoublePriorityQueue::default(),
+            timer_queue: TimerWheel::default(),
+            max_batch_size: 1,
+            batch_window: Duration::ZERO,
+            fired_timers_rate: FiredTimersRate::new(),
         }
     }
 
+    /// Makes [`Self::poll_next_timers`] return up to `max_batch_size` timers at once: together
+    /// with the next timer, the timers in memory which are due within the `batch_window` are
+    /// fired, i.e. up to `batch_window` before their wake-up time.
+    pub fn with_batching(mut self, max_batch_size: usize, batch_window: Duration) -> Self {
+        debug_assert!(
+            max_batch_size >= 1,
+            "Timer batches need to contain at least one timer."
+        );
+        self.max_batch_size = max_batch_size;
+        self.batch_window = batch_window;
+        self
+    }
+
     pub fn add_timer(self: Pin<&mut Self>, timer: Timer) {
         let this = self.project();
         let timer_queue = this.timer_queue;
         let max_fired_timer = this.max_fired_timer;
+        let in_memory_limit = this.in_memory_limit;
         let mut state = this.state;
 
+        in_memory_limit
+            .record_timer_size(Self::queue_entry_overhead() + timer.estimated_memory_size());
+
         match state.as_mut().project() {
             StateProj::Idle(waker) => {
                 debug_assert!(
@@ -174,7 +314,7 @@ where
                 let timer_batch =
                     TimerBatch::new(Self::max_timer_key(&timer_key, max_fired_timer.as_ref()));
 
-                timer_queue.push(timer, timer_key);
+                timer_queue.push(timer);
                 waker.wake_by_ref();
 
                 state.set(State::process_timers(Some(timer_batch)));
@@ -182,22 +322,20 @@ where
             StateProj::LoadTimers { removed_timers } => {
                 trace!("Add timer {timer:?} to in memory queue while loading timers from storage.");
 
-                let timer_key = timer.timer_key().clone();
                 // remove removed timer in case we removed it before
                 removed_timers
                     .as_mut()
                     .expect("removed_timers hash set must be present")
-                    .remove(&timer_key);
-                timer_queue.push(timer, timer_key);
-
-                this.num_timers_in_memory_limit
-                    .map(|num_timers_in_memory_limit| {
-                        Self::trim_timer_queue(
-                            timer_queue,
-                            num_timers_in_memory_limit,
-                            max_fired_timer.as_ref(),
-                        )
-                    });
+                    .remove(timer.timer_key());
+                timer_queue.push(timer);
+
+                in_memory_limit.get().map(|num_timers_in_memory_limit| {
+                    Self::trim_timer_queue(
+                        timer_queue,
+                        num_timers_in_memory_limit,
+                        max_fired_timer.as_ref(),
+                    )
+                });
             }
             StateProj::ProcessTimers {
                 timer_batch,
@@ -207,7 +345,7 @@ where
 
                 // if memory limit is configured, then check whether timer is in batch, otherwise
                 // add timer to batch (since all timers are kept in memory)
-                if this.num_timers_in_memory_limit.is_none()
+                if in_memory_limit.get().is_none()
                     || timer_batch
                         .as_ref()
                         .map(|batch| batch.contains(timer_key))
@@ -215,12 +353,11 @@ where
                 {
                     trace!("Add timer {timer:?} to in memory queue.");
                     let new_timer_key = timer_key.clone();
-                    let timer_key = timer_key.clone();
-                    timer_queue.push(timer, timer_key);
+                    timer_queue.push(timer);
 
                     // the new timer is guaranteed to be smaller than the current end
-                    let new_batch_end = this
-                        .num_timers_in_memory_limit
+                    let new_batch_end = in_memory_limit
+                        .get()
                         .map(|limit| {
                             Self::trim_timer_queue(timer_queue, limit, max_fired_timer.as_ref())
                         })
@@ -251,11 +388,16 @@ where
                     trace!(
                         "Ignore timer {timer:?} because it is not contained in the current timer batch {timer_batch:?}."
                     );
+                    counter!(TIMER_SPILLED_TO_STORAGE).increment(1);
                 }
             }
         }
     }
 
+    /// Cancels the timer with the given key, so that it won't fire anymore.
+    ///
+    /// The timer must also be removed from the storage backing the [`crate::TimerReader`]:
+    /// timers which don't fit in memory are only read from there once they are due.
     pub fn remove_timer(self: Pin<&mut Self>, key: Timer::TimerKey) {
         let this = self.project();
         let timer_queue = this.timer_queue;
@@ -284,7 +426,7 @@ where
             } => {
                 let removed_timer = timer_queue.remove(&key);
 
-                if let Some((timer, _)) = removed_timer {
+                if let Some(timer) = removed_timer {
                     trace!("Removed timer '{timer:?}' while processing timer batch.");
                 }
 
@@ -316,11 +458,11 @@ where
     }
 
     fn adjust_timer_batch_end(
-        timer_queue: &mut DoublePriorityQueue<Timer>,
+        timer_queue: &mut TimerWheel<Timer>,
         max_fired_timer: &mut Option<Timer::TimerKey>,
         timer_batch: &mut Option<TimerBatch<Timer::TimerKey>>,
     ) {
-        let max_timer_in_queue = timer_queue.peek_max().map(|(_, key)| key);
+        let max_timer_in_queue = timer_queue.peek_max();
 
         if let Some(max_timer_in_queue) = max_timer_in_queue {
             *timer_batch = Some(TimerBatch::new(Self::max_timer_key(
@@ -340,6 +482,7 @@ where
         let this = self.project();
         let timer_queue = this.timer_queue;
         let max_fired_timer = this.max_fired_timer;
+        let in_memory_limit = this.in_memory_limit;
         let mut state = this.state;
 
         loop {
@@ -364,14 +507,13 @@ where
 
                             // We can only stop loading timers if we know that all subsequent timers have
                             // a strictly larger timer key (later wake up time or larger key)
-                            if this
-                                .num_timers_in_memory_limit
+                            if in_memory_limit
+                                .get()
                                 .map(|limit| timer_queue.len() >= limit)
                                 .unwrap_or(false)
                                 && timer_queue
                                     .peek_max()
                                     .expect("Timer queue expected to contain an element.")
-                                    .1
                                     < timer_key
                             {
                                 trace!(
@@ -380,14 +522,17 @@ where
                                 break;
                             } else {
                                 trace!("Load timer {next_timer:?} into in memory queue.");
-                                let timer_key = timer_key.clone();
-                                timer_queue.push(next_timer, timer_key);
+                                in_memory_limit.record_timer_size(
+                                    Self::queue_entry_overhead()
+                                        + next_timer.estimated_memory_size(),
+                                );
+                                timer_queue.push(next_timer);
                             }
                         }
                     }
 
                     // get rid of larger timers that exceed in memory threshold
-                    this.num_timers_in_memory_limit.map(|limit| {
+                    in_memory_limit.get().map(|limit| {
                         Self::trim_timer_queue(timer_queue, limit, max_fired_timer.as_ref())
                     });
 
@@ -397,7 +542,7 @@ where
                         .expect("removed_timers must be present")
                         .clear();
 
-                    if let Some((_, timer_key)) = timer_queue.peek_max() {
+                    if let Some(timer_key) = timer_queue.peek_max() {
                         trace!("Start processing timers.");
                         let timer_batch = TimerBatch::new(Self::max_timer_key(
                             timer_key,
@@ -415,7 +560,7 @@ where
                     mut process_timers_state,
                 } => match process_timers_state.as_mut().project() {
                     ProcessTimersStateProj::ReadNextTimer => {
-                        if let Some((_, timer_key)) = timer_queue.peek_min() {
+                        if let Some(timer_key) = timer_queue.peek_min() {
                             let wake_up_time = timer_key.wake_up_time();
                             if let Some(sleep) = this.clock.sleep_until(wake_up_time) {
                                 trace!(
@@ -449,7 +594,7 @@ where
                                 this.timer_reader
                                     .take()
                                     .expect("timer_reader must be present"),
-                                this.num_timers_in_memory_limit.unwrap_or(usize::MAX),
+                                in_memory_limit.get().unwrap_or(usize::MAX),
                                 end_of_batch,
                             ));
                             state.set(State::LoadTimers { removed_timers });
@@ -462,17 +607,10 @@ where
                     ProcessTimersStateProj::TriggerTimer => {
                         process_timers_state.set(ProcessTimersState::ReadNextTimer);
 
-                        if let Some((timer, timer_key)) = timer_queue.pop_min() {
+                        if let Some(timer) = timer_queue.pop_min() {
                             trace!("Trigger timer {timer:?}.");
-
-                            // update max fired timer if the fired timer is larger
-                            if max_fired_timer
-                                .as_ref()
-                                .map(|max_fired_timer| *max_fired_timer < timer_key)
-                                .unwrap_or(true)
-                            {
-                                *max_fired_timer = Some(timer_key);
-                            }
+                            Self::update_max_fired_timer(max_fired_timer, timer.timer_key());
+                            this.fired_timers_rate.record_fired();
 
                             return Poll::Ready(timer);
                         }
@@ -486,12 +624,98 @@ where
         future::poll_fn(|cx| self.as_mut().poll_next_timer(cx)).await
     }
 
+    /// Polls the next timer together with the batch of timers fired with it, see
+    /// [`Self::with_batching`]. The timers are returned in the order of their keys.
+    pub fn poll_next_timers(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Vec<Timer>> {
+        let timer = ready!(self.as_mut().poll_next_timer(cx));
+        let mut timers = vec![timer];
+
+        // after firing a timer, the service continues with the next timer in memory, which can be
+        // fired right away if it is due within the batch window
+        let this = self.project();
+        while timers.len() < *this.max_batch_size {
+            let Some(timer_key) = this.timer_queue.peek_min() else {
+                break;
+            };
+            let batch_wake_up_time = MillisSinceEpoch::new(
+                timer_key
+                    .wake_up_time()
+                    .as_u64()
+                    .saturating_sub(this.batch_window.as_millis() as u64),
+            );
+            if this.clock.sleep_until(batch_wake_up_time).is_some() {
+                break;
+            }
+
+            let timer = this
+                .timer_queue
+                .pop_min()
+                .expect("Element must exist since queue is not empty.");
+            trace!("Trigger timer {timer:?} in batch.");
+            Self::update_max_fired_timer(this.max_fired_timer, timer.timer_key());
+            this.fired_timers_rate.record_fired();
+            timers.push(timer);
+        }
+
+        Poll::Ready(timers)
+    }
+
+    pub async fn next_timers(mut self: Pin<&mut Self>) -> Vec<Timer> {
+        future::poll_fn(|cx| self.as_mut().poll_next_timers(cx)).await
+    }
+
+    /// Number of timers loaded in memory. The other timers are loaded from the
+    /// [`crate::TimerReader`] once they're due.
+    pub fn num_loaded_timers(&self) -> usize {
+        self.timer_queue.len()
+    }
+
+    /// Wake-up time of the next timer loaded in memory.
+    pub fn next_wake_up_time(self: Pin<&mut Self>) -> Option<MillisSinceEpoch> {
+        self.project()
+            .timer_queue
+            .peek_min()
+            .map(|timer_key| timer_key.wake_up_time())
+    }
+
+    /// Current time of the clock driving this timer service.
+    pub fn now(&self) -> MillisSinceEpoch {
+        self.clock.now()
+    }
+
+    /// Number of timers fired during the last second.
+    pub fn fired_timers_per_second(&self) -> u64 {
+        self.fired_timers_rate.get()
+    }
+
+    /// Lists the first `limit` timers loaded in memory, in the order they fire.
+    pub fn list_pending_timers(&self, limit: usize) -> Vec<&Timer> {
+        let mut timers: Vec<_> = self.timer_queue.iter().collect();
+        timers.sort_unstable_by(|a, b| a.timer_key().cmp(b.timer_key()));
+        timers.truncate(limit);
+        timers
+    }
+
+    fn update_max_fired_timer(
+        max_fired_timer: &mut Option<Timer::TimerKey>,
+        timer_key: &Timer::TimerKey,
+    ) {
+        // update max fired timer if the fired timer is larger
+        if max_fired_timer
+            .as_ref()
+            .map(|max_fired_timer| max_fired_timer < timer_key)
+            .unwrap_or(true)
+        {
+            *max_fired_timer = Some(timer_key.clone());
+        }
+    }
+
     /// Trim timer queue with respect to target queue size and max fired timer so far.
     /// Only timers that are larger than the max fired timer can be trimmed. The next
     /// read from storage needs to continue at least from the max fired timer because
     /// we cannot guarantee that triggered timers have been deleted.
     fn trim_timer_queue(
-        timer_queue: &mut DoublePriorityQueue<Timer>,
+        timer_queue: &mut TimerWheel<Timer>,
         target_queue_size: usize,
         max_fired_timer: Option<&Timer::TimerKey>,
     ) -> bool {
@@ -503,7 +727,7 @@ where
         let mut has_trimmed_queue = false;
 
         while timer_queue.len() > target_queue_size {
-            let (_, current_max_key) = timer_queue
+            let current_max_key = timer_queue
                 .peek_max()
                 .expect("Element must exist since queue is not empty.");
 
@@ -513,10 +737,11 @@ where
                 .map(|last_fired_timer| last_fired_timer < current_max_key)
                 .unwrap_or(true)
             {
-                let (popped_timer, _) = timer_queue
+                let popped_timer = timer_queue
                     .pop_max()
                     .expect("Element must exist since queue is not empty.");
                 trace!("Removing timer {popped_timer:?} from in memory timer queue.");
+                counter!(TIMER_SPILLED_TO_STORAGE).increment(1);
                 has_trimmed_queue = true;
             } else {
                 break;
@@ -526,6 +751,13 @@ where
         has_trimmed_queue
     }
 
+    /// Memory used by the timer queue for each timer, besides the timer itself.
+    fn queue_entry_overhead() -> usize {
+        // the slots of the timer wheel are hash sets, storing a control byte and some spare
+        // capacity for each timer
+        size_of::<usize>()
+    }
+
     fn max_timer_key(
         timer_key: &Timer::TimerKey,
         max_fired_timer: Option<&Timer::TimerKey>,
@@ -8,8 +8,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::service::InMemoryLimit;
 use crate::service::clock::TokioClock;
-use crate::service::clock::tests::ManualClock;
+use crate::service::clock::{ManualClock, RuntimeClock};
 use crate::{Timer, TimerReader, TimerService};
 use futures_util::FutureExt;
 use restate_test_util::let_assert;
@@ -208,7 +209,7 @@ async fn timers_fire_in_wake_up_order() {
 
 #[test(tokio::test)]
 async fn loading_timers_from_reader() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
     let num_timers = 10;
 
@@ -220,7 +221,7 @@ async fn loading_timers_from_reader() {
     tokio::pin!(service);
 
     // trigger all timers
-    clock.advance_time_by(Duration::from_millis(num_timers - 1));
+    clock.advance(Duration::from_millis(num_timers - 1));
 
     for i in 0..num_timers {
         assert_eq!(
@@ -230,9 +231,146 @@ async fn loading_timers_from_reader() {
     }
 }
 
+#[test(tokio::test)]
+async fn loading_timers_within_memory_budget() {
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    let num_timers = 10;
+
+    for i in 0..num_timers {
+        timer_reader.add_timer(TimerValue::new(i, i.into()))
+    }
+
+    // budget for less than two timers
+    let memory_budget = 2 * size_of::<TimerValue>() - 1;
+    let service = TimerService::new_with_memory_budget(
+        clock.clone(),
+        None,
+        Some(memory_budget),
+        timer_reader,
+    );
+    tokio::pin!(service);
+
+    // trigger all timers
+    clock.advance(Duration::from_millis(num_timers - 1));
+
+    for i in 0..num_timers {
+        assert_eq!(
+            service.as_mut().next_timer().await,
+            TimerValue::new(i, i.into())
+        );
+    }
+}
+
+#[test(tokio::test)]
+async fn firing_timers_in_batches() {
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    let num_timers = 10;
+
+    for i in 0..num_timers {
+        timer_reader.add_timer(TimerValue::new(i, i.into()))
+    }
+
+    let service = TimerService::new(clock.clone(), Some(4), timer_reader)
+        .with_batching(3, Duration::from_millis(2));
+    tokio::pin!(service);
+    let timers = |range: std::ops::Range<u64>| {
+        range
+            .map(|i| TimerValue::new(i, i.into()))
+            .collect::<Vec<_>>()
+    };
+
+    // the timers due within the batch window are fired with the first one
+    assert_eq!(service.as_mut().next_timers().await, timers(0..3));
+    assert!(
+        tokio::time::timeout(Duration::from_millis(10), service.as_mut().next_timers())
+            .await
+            .is_err()
+    );
+
+    // batches don't span the timers which are not loaded in memory yet
+    clock.advance_to(MillisSinceEpoch::new(7));
+    assert_eq!(service.as_mut().next_timers().await, timers(3..4));
+    assert_eq!(service.as_mut().next_timers().await, timers(4..7));
+    assert_eq!(service.as_mut().next_timers().await, timers(7..8));
+    assert_eq!(service.as_mut().next_timers().await, timers(8..10));
+}
+
+#[test(tokio::test)]
+async fn introspect_loaded_timers() {
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    let num_timers = 10;
+
+    for i in 0..num_timers {
+        timer_reader.add_timer(TimerValue::new(i, (i + 1).into()))
+    }
+
+    let service = TimerService::new(clock.clone(), Some(5), timer_reader);
+    tokio::pin!(service);
+    assert_eq!(service.num_loaded_timers(), 0);
+
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(
+        service.as_mut().next_timer().await,
+        TimerValue::new(0, 1.into())
+    );
+
+    // only the timers loaded in memory are reported
+    assert_eq!(service.num_loaded_timers(), 4);
+    assert_eq!(service.as_mut().next_wake_up_time(), Some(2.into()));
+    assert_eq!(
+        service.list_pending_timers(2),
+        vec![&TimerValue::new(1, 2.into()), &TimerValue::new(2, 3.into())]
+    );
+}
+
+#[test(tokio::test)]
+async fn runtime_clock_fast_forwards_with_manual_clock() {
+    let clock = ManualClock::default();
+    let timer_reader = MockTimerReader::<TimerValue>::new();
+    let wake_up_time = MillisSinceEpoch::new(clock.now().as_u64() + 3_600_000);
+    timer_reader.add_timer(TimerValue::new(0, wake_up_time));
+
+    let service = TimerService::new(RuntimeClock::from(clock.clone()), Some(1), timer_reader);
+    tokio::pin!(service);
+
+    clock.advance(Duration::from_secs(3600));
+    assert_eq!(service.now(), wake_up_time);
+    assert_eq!(
+        service.as_mut().next_timer().await,
+        TimerValue::new(0, wake_up_time)
+    );
+}
+
+#[test]
+fn in_memory_limit_adapts_to_timer_size() {
+    let mut limit = InMemoryLimit::new(None, Some(1000), 10);
+    assert_eq!(limit.get(), Some(100));
+
+    for _ in 0..100 {
+        limit.record_timer_size(100);
+    }
+    assert_eq!(limit.get(), Some(10));
+
+    // the stricter limit applies
+    let mut limit = InMemoryLimit::new(Some(50), Some(1000), 10);
+    assert_eq!(limit.get(), Some(50));
+    for _ in 0..100 {
+        limit.record_timer_size(1000);
+    }
+    assert_eq!(limit.get(), Some(1));
+
+    // without a memory budget the timer sizes are irrelevant
+    let mut limit = InMemoryLimit::new(None, None, 10);
+    limit.record_timer_size(1000);
+    assert_eq!(limit.get(), None);
+}
+
 #[test(tokio::test)]
 async fn advancing_time_triggers_timer() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
     let num_timers = 10;
 
@@ -244,7 +382,7 @@ async fn advancing_time_triggers_timer() {
     tokio::pin!(service);
 
     // trigger half of the timers
-    clock.advance_time_by(Duration::from_millis(num_timers / 2 - 1));
+    clock.advance(Duration::from_millis(num_timers / 2 - 1));
 
     for i in 0..num_timers / 2 {
         assert_eq!(
@@ -261,7 +399,7 @@ async fn advancing_time_triggers_timer() {
     );
 
     // trigger the remaining half
-    clock.advance_time_by(Duration::from_millis(num_timers / 2));
+    clock.advance(Duration::from_millis(num_timers / 2));
 
     for i in num_timers / 2..num_timers {
         assert_eq!(
@@ -273,7 +411,7 @@ async fn advancing_time_triggers_timer() {
 
 #[test(tokio::test)]
 async fn add_new_timers() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
 
     timer_reader.add_timers(vec![
@@ -285,14 +423,14 @@ async fn add_new_timers() {
     let service = TimerService::new(clock.clone(), Some(1), timer_reader.clone());
     tokio::pin!(service);
 
-    clock.advance_time_to(MillisSinceEpoch::new(5));
+    clock.advance_to(MillisSinceEpoch::new(5));
     let new_timer = TimerValue::new(2, 5.into());
     timer_reader.add_timer(new_timer);
 
     // notify timer about new timer
     service.as_mut().add_timer(new_timer);
 
-    clock.advance_time_to(MillisSinceEpoch::new(10));
+    clock.advance_to(MillisSinceEpoch::new(10));
 
     for i in 0..4 {
         let_assert!(TimerValue { value, .. } = service.as_mut().next_timer().await);
@@ -302,7 +440,7 @@ async fn add_new_timers() {
 
 #[test(tokio::test)]
 async fn earlier_timers_replace_older_ones() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
     timer_reader.add_timer(TimerValue::new(1, 10.into()));
 
@@ -316,7 +454,7 @@ async fn earlier_timers_replace_older_ones() {
     timer_reader.add_timer(new_timer);
     service.as_mut().add_timer(new_timer);
 
-    clock.advance_time_to(MillisSinceEpoch::new(10));
+    clock.advance_to(MillisSinceEpoch::new(10));
 
     for i in 0..2 {
         let_assert!(TimerValue { value, .. } = service.as_mut().next_timer().await);
@@ -343,7 +481,7 @@ async fn yield_to_timer_service<
 
 #[test(tokio::test)]
 async fn earlier_timers_wont_trigger_reemission_of_fired_timers() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
     timer_reader.add_timer(TimerValue::new(0, 2.into()));
     timer_reader.add_timer(TimerValue::new(2, 5.into()));
@@ -354,7 +492,7 @@ async fn earlier_timers_wont_trigger_reemission_of_fired_timers() {
     // give timer service the chance to load the initial timers
     yield_to_timer_service(&mut service).await;
 
-    clock.advance_time_to(MillisSinceEpoch::new(3));
+    clock.advance_to(MillisSinceEpoch::new(3));
 
     let_assert!(TimerValue { value: 0, .. } = service.as_mut().next_timer().await);
 
@@ -362,7 +500,7 @@ async fn earlier_timers_wont_trigger_reemission_of_fired_timers() {
     timer_reader.add_timer(new_timer);
     service.as_mut().add_timer(new_timer);
 
-    clock.advance_time_to(MillisSinceEpoch::new(10));
+    clock.advance_to(MillisSinceEpoch::new(10));
 
     for i in 1..3 {
         let_assert!(TimerValue { value, .. } = service.as_mut().next_timer().await);
@@ -372,7 +510,7 @@ async fn earlier_timers_wont_trigger_reemission_of_fired_timers() {
 
 #[test(tokio::test)]
 async fn delete_loaded_timer() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
     timer_reader.add_timer(TimerValue::new(0, MillisSinceEpoch::from(0)));
     let timer = TimerValue::new(1, MillisSinceEpoch::from(1));
@@ -387,7 +525,7 @@ async fn delete_loaded_timer() {
         TimerValue::new(0, MillisSinceEpoch::from(0))
     );
 
-    clock.advance_time_to(MillisSinceEpoch::from(3));
+    clock.advance_to(MillisSinceEpoch::from(3));
 
     timer_reader.remove_timer(timer);
     service.as_mut().remove_timer(timer);
@@ -400,7 +538,7 @@ async fn delete_loaded_timer() {
 
 #[test(tokio::test)]
 async fn delete_last_loaded_timer() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
     timer_reader.add_timer(TimerValue::new(0, MillisSinceEpoch::from(0)));
     let timer = TimerValue::new(1, MillisSinceEpoch::from(1));
@@ -414,7 +552,7 @@ async fn delete_last_loaded_timer() {
         TimerValue::new(0, MillisSinceEpoch::from(0))
     );
 
-    clock.advance_time_to(MillisSinceEpoch::from(2));
+    clock.advance_to(MillisSinceEpoch::from(2));
 
     timer_reader.remove_timer(timer);
     service.as_mut().remove_timer(timer);
@@ -424,7 +562,7 @@ async fn delete_last_loaded_timer() {
 
 #[test(tokio::test)]
 async fn delete_only_loaded_timer() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let timer_reader = MockTimerReader::<TimerValue>::new();
     let timer = TimerValue::new(1, MillisSinceEpoch::from(1));
     timer_reader.add_timer(timer);
@@ -434,7 +572,7 @@ async fn delete_only_loaded_timer() {
 
     assert!(service.as_mut().next_timer().now_or_never().is_none());
 
-    clock.advance_time_to(MillisSinceEpoch::from(2));
+    clock.advance_to(MillisSinceEpoch::from(2));
 
     timer_reader.remove_timer(timer);
     service.as_mut().remove_timer(timer);
@@ -444,7 +582,7 @@ async fn delete_only_loaded_timer() {
 
 #[test(tokio::test)]
 async fn delete_loading_timer() {
-    let mut clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+    let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
     let (tx, timer_reader) = AsyncMockTimerReader::new();
 
     let service = TimerService::new(clock.clone(), None, timer_reader);
@@ -459,7 +597,7 @@ async fn delete_loading_timer() {
     ];
     tx.send(timers).expect("should not fail");
 
-    clock.advance_time_to(MillisSinceEpoch::from(3));
+    clock.advance_to(MillisSinceEpoch::from(3));
 
     service.as_mut().remove_timer(timer);
 
@@ -472,3 +610,37 @@ async fn delete_loading_timer() {
         TimerValue::new(2, MillisSinceEpoch::from(2))
     );
 }
+
+#[test(tokio::test)]
+async fn delete_awaited_timer() {
+    for num_timers_in_memory_limit in [None, Some(1)] {
+        let clock = ManualClock::new(MillisSinceEpoch::UNIX_EPOCH);
+        let timer_reader = MockTimerReader::<TimerValue>::new();
+        let timer = TimerValue::new(0, MillisSinceEpoch::from(5));
+        timer_reader.add_timer(timer);
+        timer_reader.add_timer(TimerValue::new(1, MillisSinceEpoch::from(10)));
+
+        let service = TimerService::new(
+            clock.clone(),
+            num_timers_in_memory_limit,
+            timer_reader.clone(),
+        );
+        tokio::pin!(service);
+
+        // give timer service the chance to await the first timer
+        yield_to_timer_service(&mut service).await;
+
+        timer_reader.remove_timer(timer);
+        service.as_mut().remove_timer(timer);
+
+        // the cancelled timer must not fire
+        clock.advance_to(MillisSinceEpoch::from(7));
+        yield_to_timer_service(&mut service).await;
+
+        clock.advance_to(MillisSinceEpoch::from(10));
+        assert_eq!(
+            service.as_mut().next_timer().await,
+            TimerValue::new(1, MillisSinceEpoch::from(10))
+        );
+    }
+}
@@ -0,0 +1,371 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{BTreeMap, HashSet};
+use std::mem;
+
+use restate_types::timer::TimerKey;
+
+use crate::Timer;
+
+// Using ahash for faster hashing operations.
+type Slot<T> = HashSet<T, ahash::RandomState>;
+
+/// Number of bits of the wake-up time covered by a level of the wheel.
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const SLOT_MASK: u64 = (SLOTS as u64) - 1;
+/// With millisecond wake-up times, the levels cover a bit more than two years from the
+/// wheel's elapsed time.
+const LEVELS: usize = 6;
+
+/// Hierarchical timing wheel keeping the timers ordered by their [`TimerKey`].
+///
+/// The timers are bucketed by wake-up time into the slots of the levels: slots of level `n` span
+/// `64^n` milliseconds. Adding and removing a timer is O(1), since its slot is determined by its
+/// wake-up time and by the time the wheel has elapsed to. Retrieving the earliest timer elapses
+/// the wheel to the earliest occupied slot and cascades its timers into the lower levels, until
+/// the timers with the earliest wake-up time are moved to the due timers, where they are ordered
+/// by key.
+///
+/// The wheel requires the order of the timer keys to be consistent with their wake-up times.
+pub(super) struct TimerWheel<T: Timer> {
+    /// Timers waking up later than this are in the levels or in the overflow
+    elapsed: u64,
+    /// Timers waking up not later than `elapsed`
+    due: BTreeMap<T::TimerKey, T>,
+    levels: [Level<T>; LEVELS],
+    /// Timers waking up too far after `elapsed` to fit in the levels
+    overflow: Slot<T>,
+    len: usize,
+    /// Key of the latest timer, if known
+    max_key: Option<T::TimerKey>,
+}
+
+struct Level<T> {
+    slots: [Slot<T>; SLOTS],
+    /// Bit `n` is set if slot `n` contains timers
+    occupied: u64,
+}
+
+impl<T> Default for Level<T> {
+    fn default() -> Self {
+        Self {
+            slots: std::array::from_fn(|_| Slot::default()),
+            occupied: 0,
+        }
+    }
+}
+
+enum Position {
+    Due,
+    Slot { level: usize, slot: usize },
+    Overflow,
+}
+
+impl<T: Timer> Default for TimerWheel<T> {
+    fn default() -> Self {
+        Self {
+            elapsed: 0,
+            due: BTreeMap::new(),
+            levels: std::array::from_fn(|_| Level::default()),
+            overflow: Slot::default(),
+            len: 0,
+            max_key: None,
+        }
+    }
+}
+
+impl<T: Timer> TimerWheel<T> {
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds the timer, replacing a timer with the same key.
+    pub(super) fn push(&mut self, timer: T) {
+        let timer_key = timer.timer_key();
+        if self.is_empty() {
+            // start elapsing from the first timer, rather than cascading it from the epoch
+            self.elapsed = timer_key.wake_up_time().as_u64();
+            self.max_key = Some(timer_key.clone());
+        } else if self
+            .max_key
+            .as_ref()
+            .is_some_and(|max_key| max_key < timer_key)
+        {
+            self.max_key = Some(timer_key.clone());
+        }
+
+        if self.insert(timer) {
+            self.len += 1;
+        }
+    }
+
+    pub(super) fn remove(&mut self, timer_key: &T::TimerKey) -> Option<T> {
+        let timer = match self.position(timer_key.wake_up_time().as_u64()) {
+            Position::Due => self.due.remove(timer_key),
+            Position::Slot { level, slot } => {
+                let level = &mut self.levels[level];
+                let timer = level.slots[slot].take(timer_key);
+                if level.slots[slot].is_empty() {
+                    level.occupied &= !(1 << slot);
+                }
+                timer
+            }
+            Position::Overflow => self.overflow.take(timer_key),
+        }?;
+
+        self.len -= 1;
+        if self.max_key.as_ref() == Some(timer_key) {
+            self.max_key = None;
+        }
+        Some(timer)
+    }
+
+    pub(super) fn peek_min(&mut self) -> Option<&T::TimerKey> {
+        self.elapse_to_next_due();
+        self.due.first_key_value().map(|(timer_key, _)| timer_key)
+    }
+
+    pub(super) fn pop_min(&mut self) -> Option<T> {
+        self.elapse_to_next_due();
+        let (timer_key, timer) = self.due.pop_first()?;
+        self.len -= 1;
+        if self.max_key.as_ref() == Some(&timer_key) {
+            self.max_key = None;
+        }
+        Some(timer)
+    }
+
+    pub(super) fn peek_max(&mut self) -> Option<&T::TimerKey> {
+        if self.max_key.is_none() {
+            self.max_key = self.find_max().cloned();
+        }
+        self.max_key.as_ref()
+    }
+
+    pub(super) fn pop_max(&mut self) -> Option<T> {
+        let max_key = self.peek_max()?.clone();
+        self.remove(&max_key)
+    }
+
+    /// Iterates over the timers in no particular order.
+    pub(super) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.due
+            .values()
+            .chain(
+                self.levels
+                    .iter()
+                    .flat_map(|level| level.slots.iter().flatten()),
+            )
+            .chain(self.overflow.iter())
+    }
+
+    /// Inserts the timer at its position, returns whether it didn't replace another timer.
+    fn insert(&mut self, timer: T) -> bool {
+        match self.position(timer.timer_key().wake_up_time().as_u64()) {
+            Position::Due => self.due.insert(timer.timer_key().clone(), timer).is_none(),
+            Position::Slot { level, slot } => {
+                let level = &mut self.levels[level];
+                level.occupied |= 1 << slot;
+                level.slots[slot].replace(timer).is_none()
+            }
+            Position::Overflow => self.overflow.replace(timer).is_none(),
+        }
+    }
+
+    fn position(&self, wake_up_time: u64) -> Position {
+        if wake_up_time <= self.elapsed {
+            return Position::Due;
+        }
+
+        // the highest level whose bits differ from the elapsed time
+        let masked = (self.elapsed ^ wake_up_time) | SLOT_MASK;
+        let level = ((u64::BITS - 1 - masked.leading_zeros()) / SLOT_BITS) as usize;
+        if level < LEVELS {
+            Position::Slot {
+                level,
+                slot: ((wake_up_time >> (level as u32 * SLOT_BITS)) & SLOT_MASK) as usize,
+            }
+        } else {
+            Position::Overflow
+        }
+    }
+
+    /// Elapses the wheel until there are due timers, or the wheel is empty.
+    fn elapse_to_next_due(&mut self) {
+        while self.due.is_empty() {
+            let timers = if let Some((level, slot)) = self.next_occupied_slot() {
+                // The occupied slots of a level all start after the elapsed time, and the ones
+                // of the lower levels start before the ones of the higher levels
+                let level_mask = (1 << ((level as u32 + 1) * SLOT_BITS)) - 1;
+                self.elapsed =
+                    (self.elapsed & !level_mask) | ((slot as u64) << (level as u32 * SLOT_BITS));
+
+                let level = &mut self.levels[level];
+                level.occupied &= !(1 << slot);
+                mem::take(&mut level.slots[slot])
+            } else if let Some(min_wake_up_time) = self
+                .overflow
+                .iter()
+                .map(|timer| timer.timer_key().wake_up_time().as_u64())
+                .min()
+            {
+                // Only happens after the wheel elapsed all the timers of the levels
+                self.elapsed = min_wake_up_time;
+                mem::take(&mut self.overflow)
+            } else {
+                return;
+            };
+
+            for timer in timers {
+                self.insert(timer);
+            }
+        }
+    }
+
+    fn next_occupied_slot(&self) -> Option<(usize, usize)> {
+        self.levels
+            .iter()
+            .enumerate()
+            .find(|(_, level)| level.occupied != 0)
+            .map(|(idx, level)| (idx, level.occupied.trailing_zeros() as usize))
+    }
+
+    fn find_max(&self) -> Option<&T::TimerKey> {
+        if !self.overflow.is_empty() {
+            return self.overflow.iter().map(Timer::timer_key).max();
+        }
+        if let Some(level) = self.levels.iter().rev().find(|level| level.occupied != 0) {
+            let slot = (u64::BITS - 1 - level.occupied.leading_zeros()) as usize;
+            return level.slots[slot].iter().map(Timer::timer_key).max();
+        }
+        self.due.last_key_value().map(|(timer_key, _)| timer_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_types::time::MillisSinceEpoch;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    struct TestTimer {
+        wake_up_time: u64,
+        id: u64,
+    }
+
+    impl Timer for TestTimer {
+        type TimerKey = TestTimer;
+
+        fn timer_key(&self) -> &Self::TimerKey {
+            self
+        }
+    }
+
+    impl TimerKey for TestTimer {
+        fn wake_up_time(&self) -> MillisSinceEpoch {
+            MillisSinceEpoch::new(self.wake_up_time)
+        }
+    }
+
+    fn timer(wake_up_time: u64, id: u64) -> TestTimer {
+        TestTimer { wake_up_time, id }
+    }
+
+    #[test]
+    fn pops_timers_in_key_order() {
+        let mut wheel = TimerWheel::default();
+        let base = 1_700_000_000_000;
+        // spread over all the levels and the overflow, with several timers per wake-up time
+        let offsets = [
+            0,
+            1,
+            63,
+            64,
+            4_095,
+            4_096,
+            300_000,
+            86_400_000,
+            90 * 86_400_000,
+            3 * 365 * 86_400_000,
+        ];
+        let mut timers = Vec::new();
+        for (id, offset) in offsets.iter().rev().enumerate() {
+            timers.push(timer(base + offset, id as u64));
+            timers.push(timer(base + offset, 100 + id as u64));
+        }
+        for timer in &timers {
+            wheel.push(timer.clone());
+        }
+        assert_eq!(wheel.len(), timers.len());
+
+        timers.sort();
+        assert_eq!(wheel.peek_max(), timers.last());
+        for expected in &timers {
+            assert_eq!(wheel.peek_min(), Some(expected));
+            assert_eq!(wheel.pop_min().as_ref(), Some(expected));
+        }
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.peek_min(), None);
+        assert_eq!(wheel.peek_max(), None);
+    }
+
+    #[test]
+    fn push_earlier_timers_while_elapsing() {
+        let mut wheel = TimerWheel::default();
+        wheel.push(timer(1_000, 0));
+        wheel.push(timer(5_000, 1));
+        assert_eq!(wheel.pop_min(), Some(timer(1_000, 0)));
+
+        // elapses the wheel to the second timer
+        assert_eq!(wheel.peek_min(), Some(&timer(5_000, 1)));
+        wheel.push(timer(2_000, 2));
+        wheel.push(timer(5_001, 3));
+
+        assert_eq!(wheel.pop_min(), Some(timer(2_000, 2)));
+        assert_eq!(wheel.pop_min(), Some(timer(5_000, 1)));
+        assert_eq!(wheel.pop_min(), Some(timer(5_001, 3)));
+        assert_eq!(wheel.pop_min(), None);
+    }
+
+    #[test]
+    fn remove_and_pop_max() {
+        let mut wheel = TimerWheel::default();
+        for i in 0..100 {
+            wheel.push(timer(1_000 + i * 997, i));
+        }
+        // replacing doesn't change the number of timers
+        wheel.push(timer(1_000, 0));
+        assert_eq!(wheel.len(), 100);
+
+        assert_eq!(
+            wheel.remove(&timer(1_000 + 50 * 997, 50)),
+            Some(timer(1_000 + 50 * 997, 50))
+        );
+        assert_eq!(wheel.remove(&timer(1_000 + 50 * 997, 50)), None);
+        assert_eq!(wheel.remove(&timer(1_001, 0)), None);
+
+        assert_eq!(wheel.pop_max(), Some(timer(1_000 + 99 * 997, 99)));
+        assert_eq!(wheel.peek_max(), Some(&timer(1_000 + 98 * 997, 98)));
+        assert_eq!(wheel.len(), 98);
+        assert_eq!(wheel.iter().count(), 98);
+
+        let popped: Vec<_> = std::iter::from_fn(|| wheel.pop_min())
+            .map(|timer| timer.id)
+            .collect();
+        assert_eq!(popped, (0..99).filter(|id| *id != 50).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Fault injection, to validate the retry and idempotency assumptions of the applications against
+//! a locally running Restate.
+//!
+//! The faults are configured at runtime through the chaos admin API, which requires building
+//! `restate-server` with the `chaos` feature and setting `admin.enable-chaos-api`. They apply only
+//! to the node which received them, and they're not persisted across restarts.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use arc_swap::ArcSwapOption;
+use serde::{Deserialize, Serialize};
+
+use restate_time_util::FriendlyDuration;
+
+static FAULTS: LazyLock<ArcSwapOption<Faults>> = LazyLock::new(Default::default);
+
+/// Faults injected in the node.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Faults {
+    /// Delay applied to every write to the partition store.
+    #[serde(skip_serializing_if = "FriendlyDuration::is_zero")]
+    pub storage_write_delay: FriendlyDuration,
+    /// Percentage of the writes to the partition store which fail, causing the partition
+    /// processor to restart.
+    pub storage_write_failure_percentage: u8,
+    /// Percentage of the partition processor rpcs (e.g. ingress requests) which are dropped
+    /// without a response.
+    pub partition_rpc_drop_percentage: u8,
+    /// Percentage of the invocation attempts which fail before reaching the deployment, by
+    /// service name.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub invocation_attempt_failure_percentage: HashMap<String, u8>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid {name} {value}, percentages must be between 0 and 100")]
+pub struct InvalidPercentageError {
+    name: String,
+    value: u8,
+}
+
+impl Faults {
+    pub fn validate(&self) -> Result<(), InvalidPercentageError> {
+        check_percentage(
+            "storage-write-failure-percentage",
+            self.storage_write_failure_percentage,
+        )?;
+        check_percentage(
+            "partition-rpc-drop-percentage",
+            self.partition_rpc_drop_percentage,
+        )?;
+        for (service_name, percentage) in &self.invocation_attempt_failure_percentage {
+            check_percentage(
+                &format!("invocation-attempt-failure-percentage of service '{service_name}'"),
+                *percentage,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn check_percentage(name: &str, value: u8) -> Result<(), InvalidPercentageError> {
+    if value > 100 {
+        return Err(InvalidPercentageError {
+            name: name.to_owned(),
+            value,
+        });
+    }
+    Ok(())
+}
+
+/// Replaces the faults injected in this node.
+pub fn set_faults(faults: Faults) -> Result<(), InvalidPercentageError> {
+    faults.validate()?;
+    if faults == Faults::default() {
+        FAULTS.store(None);
+    } else {
+        FAULTS.store(Some(Arc::new(faults)));
+    }
+    Ok(())
+}
+
+/// Removes all the faults injected in this node.
+pub fn clear_faults() {
+    FAULTS.store(None);
+}
+
+/// Faults currently injected in this node.
+pub fn current_faults() -> Faults {
+    FAULTS.load().as_deref().cloned().unwrap_or_default()
+}
+
+/// Delay to apply before writing to the partition store, if any.
+pub fn storage_write_delay() -> Option<Duration> {
+    FAULTS
+        .load()
+        .as_ref()
+        .and_then(|faults| faults.storage_write_delay.to_non_zero_std())
+}
+
+pub fn should_fail_storage_write() -> bool {
+    FAULTS
+        .load()
+        .as_ref()
+        .is_some_and(|faults| happens(faults.storage_write_failure_percentage))
+}
+
+pub fn should_drop_partition_rpc() -> bool {
+    FAULTS
+        .load()
+        .as_ref()
+        .is_some_and(|faults| happens(faults.partition_rpc_drop_percentage))
+}
+
+pub fn should_fail_invocation_attempt(service_name: &str) -> bool {
+    FAULTS.load().as_ref().is_some_and(|faults| {
+        faults
+            .invocation_attempt_failure_percentage
+            .get(service_name)
+            .is_some_and(|percentage| happens(*percentage))
+    })
+}
+
+fn happens(percentage: u8) -> bool {
+    percentage > 0 && rand::random_ratio(u32::from(percentage.min(100)), 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faults_validation() {
+        assert!(Faults::default().validate().is_ok());
+        assert!(
+            Faults {
+                partition_rpc_drop_percentage: 101,
+                ..Faults::default()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            Faults {
+                invocation_attempt_failure_percentage: HashMap::from([("Greeter".to_owned(), 200)]),
+                ..Faults::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn percentage_bounds() {
+        assert!((0..100).all(|_| !happens(0)));
+        assert!((0..100).all(|_| happens(100)));
+    }
+
+    #[test]
+    fn faults_serde() {
+        let faults: Faults = serde_json::from_str(
+            r#"{"storage-write-delay": "100ms", "invocation-attempt-failure-percentage": {"Greeter": 50}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            faults,
+            Faults {
+                storage_write_delay: FriendlyDuration::from_millis(100),
+                invocation_attempt_failure_percentage: HashMap::from([("Greeter".to_owned(), 50)]),
+                ..Faults::default()
+            }
+        );
+    }
+}
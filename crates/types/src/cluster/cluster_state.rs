@@ -208,6 +208,19 @@ pub struct PartitionProcessorStatus {
     // Set if replay_status is CatchingUp
     #[bilrost(12)]
     pub target_tail_lsn: Option<Lsn>,
+    /// Rate of commands applied since the previous status update.
+    #[bilrost(13)]
+    #[serde(default)]
+    pub applied_commands_per_second: f64,
+    /// Time between appending the last applied command to the log and applying it.
+    #[bilrost(14)]
+    #[serde(default)]
+    pub apply_lag_ms: Option<u64>,
+    /// Age of the oldest command appended to the log, but not applied yet. Zero if the partition
+    /// processor is caught up with the log tail.
+    #[bilrost(15)]
+    #[serde(default)]
+    pub oldest_unapplied_command_age_ms: Option<u64>,
 }
 
 impl Default for PartitionProcessorStatus {
@@ -225,6 +238,9 @@ impl Default for PartitionProcessorStatus {
             last_persisted_log_lsn: None,
             last_archived_log_lsn: None,
             target_tail_lsn: None,
+            applied_commands_per_second: 0.0,
+            apply_lag_ms: None,
+            oldest_unapplied_command_age_ms: None,
         }
     }
 }
@@ -18,7 +18,7 @@ use tokio::sync::Semaphore;
 
 use restate_time_util::NonZeroFriendlyDuration;
 
-use super::{CommonOptions, ListenerOptions, QueryEngineOptions};
+use super::{AuthenticationOptions, CommonOptions, ListenerOptions, QueryEngineOptions};
 use crate::net::address::{AdminPort, AdvertisedAddress, BindAddress};
 use crate::net::listener::AddressBook;
 
@@ -41,6 +41,13 @@ pub struct AdminOptions {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     advertised_admin_endpoint: Option<AdvertisedAddress<AdminPort>>,
 
+    /// # Authentication
+    ///
+    /// Authenticate the requests to the Admin APIs, with API keys, JWTs or client certificates.
+    /// The health check doesn't need credentials. Disabled if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    authentication: Option<AuthenticationOptions>,
+
     /// # Deployment routing headers
     ///
     /// List of header names considered routing headers.
@@ -68,6 +75,13 @@ pub struct AdminOptions {
     /// Disable serving the Restate Web UI on the admin port. Default is `false`.
     pub disable_web_ui: bool,
 
+    /// # Read-only during recovery
+    ///
+    /// Reject the Admin API requests that modify the cluster, with `503 Service Unavailable`, while
+    /// the partition processors of this node are recovering. Read-only requests are still served.
+    /// Default is `false`.
+    pub read_only_during_recovery: bool,
+
     #[cfg(any(test, feature = "test-util"))]
     pub disable_cluster_controller: bool,
 
@@ -75,6 +89,12 @@ pub struct AdminOptions {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[cfg_attr(feature = "schemars", schemars(skip))]
     pub storage_accounting_update_interval: Option<NonZeroFriendlyDuration>,
+
+    /// Serve the chaos API, to inject faults in this node. Requires building with the `chaos`
+    /// feature, and it must never be enabled in production.
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub enable_chaos_api: bool,
 }
 
 impl AdminOptions {
@@ -92,6 +112,10 @@ impl AdminOptions {
             .unwrap_or_else(|| self.admin_listener_options.advertised_address(address_book))
     }
 
+    pub fn authentication(&self) -> Option<&AuthenticationOptions> {
+        self.authentication.as_ref()
+    }
+
     pub fn data_dir(&self) -> PathBuf {
         super::data_dir("registry")
     }
@@ -124,6 +148,7 @@ impl Default for AdminOptions {
         Self {
             advertised_admin_endpoint: None,
             admin_listener_options: Default::default(),
+            authentication: None,
             // max is limited by Tower's LoadShedLayer.
             deployment_routing_headers: vec![],
             concurrent_api_requests_limit: None,
@@ -132,7 +157,9 @@ impl Default for AdminOptions {
             #[cfg(any(test, feature = "test-util"))]
             disable_cluster_controller: false,
             disable_web_ui: false,
+            read_only_during_recovery: false,
             storage_accounting_update_interval: None,
+            enable_chaos_api: false,
         }
     }
 }
@@ -0,0 +1,141 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use restate_time_util::FriendlyDuration;
+
+/// # Authentication options
+///
+/// Authentication of the requests to a server. A request is accepted if it presents any of the
+/// configured credentials: an API key, a JWT, or a verified client certificate. Requests without
+/// valid credentials are rejected with `401 Unauthorized`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct AuthenticationOptions {
+    /// # API keys
+    ///
+    /// Static API keys, passed by the clients either in the `x-restate-api-key` header or as
+    /// `Authorization: Bearer <key>`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_keys: Vec<ApiKeyOptions>,
+
+    /// # JWT
+    ///
+    /// Accept the JWTs passed as `Authorization: Bearer <token>`, signed by the keys published
+    /// by an identity provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<JwtOptions>,
+
+    /// # TLS
+    ///
+    /// Serve the TCP listeners over TLS, optionally authenticating the clients by their
+    /// certificate. Unix domain sockets are always served in plain text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsOptions>,
+}
+
+impl AuthenticationOptions {
+    /// Whether the requests must carry credentials, otherwise only TLS is configured.
+    pub fn requires_credentials(&self) -> bool {
+        !self.api_keys.is_empty()
+            || self.jwt.is_some()
+            || self
+                .tls
+                .as_ref()
+                .is_some_and(|tls| tls.client_ca_path.is_some())
+    }
+}
+
+/// # API key options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct ApiKeyOptions {
+    /// # Name
+    ///
+    /// Identifies the clients using the key in the logs, e.g. the name of the team owning it.
+    pub name: String,
+
+    /// # Key
+    pub key: String,
+}
+
+/// # JWT options
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct JwtOptions {
+    /// # JWKS URL
+    ///
+    /// URL of the JSON Web Key Set verifying the token signatures, e.g.
+    /// `https://idp.example.com/.well-known/jwks.json`.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub jwks_url: http::Uri,
+
+    /// # Issuer
+    ///
+    /// Expected `iss` claim of the tokens. Not checked if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+
+    /// # Audience
+    ///
+    /// Expected `aud` claim of the tokens, e.g. `restate`. Required, as the identity provider
+    /// may issue tokens for other applications with the same keys.
+    pub audience: String,
+
+    /// # JWKS refresh interval
+    ///
+    /// How often the key set is fetched again. Tokens signed by an unknown key trigger a fetch
+    /// at most once per minute.
+    #[serde(default = "default_jwks_refresh_interval")]
+    pub jwks_refresh_interval: FriendlyDuration,
+}
+
+fn default_jwks_refresh_interval() -> FriendlyDuration {
+    FriendlyDuration::from_secs(15 * 60)
+}
+
+/// # TLS options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct TlsOptions {
+    /// # Certificate path
+    ///
+    /// PEM file with the certificate chain of the server.
+    pub certificate_path: PathBuf,
+
+    /// # Key path
+    ///
+    /// PEM file with the key of the server certificate.
+    pub key_path: PathBuf,
+
+    /// # Client CA path
+    ///
+    /// PEM file with the certificate authorities of the clients. If set, the clients presenting a
+    /// certificate issued by them are authenticated (mutual TLS).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_ca_path: Option<PathBuf>,
+
+    /// # Require client certificate
+    ///
+    /// Refuse the connections of the clients not presenting a valid certificate, rather than
+    /// falling back to the other credentials. Requires `client-ca-path`. Default is `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_client_certificate: bool,
+}
@@ -8,17 +8,33 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::net::SocketAddr;
 use std::num::NonZeroUsize;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use tokio::sync::Semaphore;
 
+use restate_serde_util::NonZeroByteCount;
+use restate_time_util::FriendlyDuration;
+
 use crate::net::address::{AdvertisedAddress, BindAddress, HttpIngressPort};
 use crate::net::listener::AddressBook;
 
-use super::{CommonOptions, KafkaClusterOptions, ListenerOptions};
+use super::{
+    AuthenticationOptions, CommonOptions, KafkaClusterOptions, ListenerOptions, REDACTED,
+    SqsConnectionOptions, serialize_redacted,
+};
+
+/// Minimum length of [`SessionTokenOptions::secret`], as for the key of HMAC-SHA256.
+const MIN_SESSION_TOKEN_SECRET_LENGTH: usize = 32;
+
+/// Default of [`IngressOptions::max_request_body_size`].
+const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 32 * 1024 * 1024; // 32MiB
 
 /// # Ingress options
+#[serde_as]
 #[derive(Debug, Default, Clone, Serialize, Deserialize, derive_builder::Builder)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "schemars", schemars(rename = "IngressOptions"))]
@@ -35,8 +51,74 @@ pub struct IngressOptions {
     /// the ingress will reply immediately with an appropriate status code. Default is unlimited.
     concurrent_api_requests_limit: Option<NonZeroUsize>,
 
+    /// # Maximum request body size
+    ///
+    /// Requests with a larger body are rejected with `413 Payload Too Large`, as soon as their
+    /// `content-length` is received, or once the limit is reached while reading the body.
+    /// Default is 32 MiB.
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_request_body_size: Option<NonZeroUsize>,
+
     kafka_clusters: Vec<KafkaClusterOptions>,
 
+    /// # AWS SQS connections
+    ///
+    /// Connections to AWS SQS, used by the subscriptions with an `sqs://<connection_name>/<queue_name>`
+    /// source to long-poll the queue and invoke the sink with each message.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sqs_connections: Vec<SqsConnectionOptions>,
+
+    /// # Authentication
+    ///
+    /// Authenticate the requests to the ingress, with API keys, JWTs or client certificates. The
+    /// health check and the session token requests don't need credentials. Disabled if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    authentication: Option<AuthenticationOptions>,
+
+    /// # Request signatures
+    ///
+    /// Verify the signature of the requests to the given services, e.g. to accept the callbacks
+    /// of third-party webhook providers directly. Unsigned, stale, or wrongly signed requests
+    /// are rejected with `401 Unauthorized`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    request_signatures: Vec<RequestSignatureOptions>,
+
+    /// # Response cache
+    ///
+    /// Cache the responses of the handlers marked as side-effect-free, that is with the
+    /// `restate.side-effect-free` handler metadata set to `true`. Disabled if unset.
+    ///
+    /// The cache is local to each node and doesn't observe state changes: a cached response is
+    /// served until the configured `ttl` expires, hence it can be stale for up to the `ttl`.
+    /// Only mark as side-effect-free the handlers whose callers tolerate such staleness.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response_cache: Option<ResponseCacheOptions>,
+
+    /// # Session tokens
+    ///
+    /// Issue a signed session token when a workflow is submitted, allowing the submitter, e.g. a
+    /// browser, to attach to the workflow, get its output, and call its shared handlers without
+    /// knowing the workflow key or the invocation id. Disabled if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session_tokens: Option<SessionTokenOptions>,
+
+    /// # Validate JSON schemas
+    ///
+    /// Validate the request bodies against the JSON schemas declared by the handlers for their
+    /// input, rejecting the invalid ones with `400 Bad Request`. Default is `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    validate_json_schemas: bool,
+
+    /// # Additional listeners
+    ///
+    /// Further listeners served by this ingress next to the main one, e.g. an internal listener
+    /// exposing the private services too. All listeners share the same dispatcher and response
+    /// cache, while the concurrency limit applies to each listener separately.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    additional_listeners: Vec<IngressListenerOptions>,
+
     /// # Ingress endpoint
     ///
     /// [Deprecated] Use `advertised-address` instead.
@@ -76,6 +158,47 @@ impl IngressOptions {
             .collect()
     }
 
+    pub fn get_sqs_connection(&self, name: &str) -> Option<&SqsConnectionOptions> {
+        self.sqs_connections.iter().find(|c| c.name == name)
+    }
+
+    pub fn available_sqs_connections(&self) -> Vec<&str> {
+        self.sqs_connections
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+
+    pub fn request_signatures(&self) -> &[RequestSignatureOptions] {
+        &self.request_signatures
+    }
+
+    pub fn response_cache(&self) -> Option<&ResponseCacheOptions> {
+        self.response_cache.as_ref()
+    }
+
+    pub fn authentication(&self) -> Option<&AuthenticationOptions> {
+        self.authentication.as_ref()
+    }
+
+    pub fn session_tokens(&self) -> Option<&SessionTokenOptions> {
+        self.session_tokens.as_ref()
+    }
+
+    pub fn max_request_body_size(&self) -> usize {
+        self.max_request_body_size
+            .map(Into::into)
+            .unwrap_or(DEFAULT_MAX_REQUEST_BODY_SIZE)
+    }
+
+    pub fn validate_json_schemas(&self) -> bool {
+        self.validate_json_schemas
+    }
+
+    pub fn additional_listeners(&self) -> &[IngressListenerOptions] {
+        &self.additional_listeners
+    }
+
     pub fn concurrent_api_requests_limit(&self) -> usize {
         std::cmp::min(
             self.concurrent_api_requests_limit
@@ -90,4 +213,287 @@ impl IngressOptions {
         self.ingress_listener_options
             .merge(common.fabric_listener_options());
     }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self
+            .request_signatures
+            .iter()
+            .any(|options| options.max_clock_skew.to_std() > MAX_REQUEST_SIGNATURE_CLOCK_SKEW)
+        {
+            return Err(format!(
+                "request-signatures.max-clock-skew must be at most {}",
+                FriendlyDuration::new(MAX_REQUEST_SIGNATURE_CLOCK_SKEW)
+            ));
+        }
+
+        if let Some(session_tokens) = &self.session_tokens
+            && session_tokens.secret.len() < MIN_SESSION_TOKEN_SECRET_LENGTH
+        {
+            return Err(format!(
+                "session-tokens.secret must be at least {MIN_SESSION_TOKEN_SECRET_LENGTH} bytes long"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// # Ingress listener options
+///
+/// An additional TCP listener of the ingress, with its own exposure profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct IngressListenerOptions {
+    /// # Name
+    ///
+    /// Name of the listener, used in logs.
+    pub name: String,
+
+    /// # Bind address
+    ///
+    /// The combination of IP address and port to listen on, e.g. `0.0.0.0:9080`.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub bind_address: SocketAddr,
+
+    /// # Exposure
+    ///
+    /// Which services can be invoked through this listener.
+    #[serde(default)]
+    pub exposure: IngressExposure,
+
+    /// # Request signatures
+    ///
+    /// Signature verification for the requests received on this listener. If unset, the
+    /// `request-signatures` of the ingress apply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_signatures: Option<Vec<RequestSignatureOptions>>,
+}
+
+/// # Ingress exposure
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum IngressExposure {
+    /// # Public
+    ///
+    /// Only the services marked as public can be invoked.
+    #[default]
+    Public,
+    /// # All
+    ///
+    /// All services can be invoked, including the private ones.
+    All,
+}
+
+/// # Response cache options
+///
+/// The responses are cached per ingress node, keyed by the called handler, the object or workflow
+/// key, and the request body. They expire after the `ttl`, state changes don't invalidate them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct ResponseCacheOptions {
+    /// # Time to live
+    ///
+    /// How long a response is served from the cache. This bounds how stale the served
+    /// responses can be.
+    #[serde(default = "default_response_cache_ttl")]
+    pub ttl: FriendlyDuration,
+
+    /// # Capacity
+    ///
+    /// Maximum number of cached responses. The oldest responses are evicted first.
+    #[serde(default = "default_response_cache_capacity")]
+    pub capacity: NonZeroUsize,
+}
+
+fn default_response_cache_ttl() -> FriendlyDuration {
+    FriendlyDuration::from_secs(10)
+}
+
+fn default_response_cache_capacity() -> NonZeroUsize {
+    NonZeroUsize::new(10_000).expect("Non zero number")
+}
+
+/// # Session token options
+///
+/// The session tokens are issued in the `x-restate-session-token` header of the responses to
+/// the workflow submissions, and in the `sessionToken` field of the send responses. They are
+/// redeemed through the `/restate/session` paths, passing the token in the same header.
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct SessionTokenOptions {
+    /// # Secret
+    ///
+    /// Secret signing the tokens, at least 32 bytes long. It must be the same on all the nodes
+    /// running the ingress, and changing it invalidates the tokens issued so far. It's redacted
+    /// when dumping the configuration.
+    #[serde(serialize_with = "serialize_redacted")]
+    pub secret: String,
+
+    /// # Time to live
+    ///
+    /// How long a token can be used after being issued.
+    #[serde(default = "default_session_token_ttl")]
+    pub ttl: FriendlyDuration,
+
+    /// # Workflows
+    ///
+    /// Names of the workflow services issuing session tokens. If empty, all the workflows do.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workflows: Vec<String>,
+
+    /// # Scopes
+    ///
+    /// Operations allowed with the issued tokens.
+    #[serde(default = "default_session_token_scopes")]
+    pub scopes: Vec<SessionTokenScope>,
+}
+
+impl std::fmt::Debug for SessionTokenOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionTokenOptions")
+            .field("secret", &REDACTED)
+            .field("ttl", &self.ttl)
+            .field("workflows", &self.workflows)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+impl SessionTokenOptions {
+    /// Whether the submissions of the given workflow are issued a token.
+    pub fn issues_tokens_for(&self, workflow_name: &str) -> bool {
+        self.workflows.is_empty() || self.workflows.iter().any(|name| name == workflow_name)
+    }
+}
+
+fn default_session_token_ttl() -> FriendlyDuration {
+    FriendlyDuration::from_secs(24 * 60 * 60)
+}
+
+fn default_session_token_scopes() -> Vec<SessionTokenScope> {
+    vec![
+        SessionTokenScope::Attach,
+        SessionTokenScope::Output,
+        SessionTokenScope::SharedHandlers,
+    ]
+}
+
+/// # Session token scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionTokenScope {
+    /// # Attach
+    ///
+    /// Attach to the workflow run, waiting for its output.
+    Attach,
+    /// # Output
+    ///
+    /// Get the output of the workflow run, if completed.
+    Output,
+    /// # Shared handlers
+    ///
+    /// Call the shared handlers of the workflow, e.g. to query its progress or signal it.
+    SharedHandlers,
+}
+
+/// # Request signature options
+///
+/// Signature verification of the ingress requests to a set of services.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct RequestSignatureOptions {
+    /// # Services
+    ///
+    /// Names of the services whose requests must be signed.
+    pub services: Vec<String>,
+
+    /// # Maximum clock skew
+    ///
+    /// Requests signed longer ago, or further in the future, than this are rejected as stale.
+    /// At most 1 hour. Ignored by the HMAC scheme without `timestamp-header`.
+    #[serde(default = "default_max_clock_skew")]
+    pub max_clock_skew: FriendlyDuration,
+
+    #[serde(flatten)]
+    pub scheme: RequestSignatureScheme,
+}
+
+/// Maximum of [`RequestSignatureOptions::max_clock_skew`], bounding the window to replay requests.
+const MAX_REQUEST_SIGNATURE_CLOCK_SKEW: Duration = Duration::from_secs(60 * 60);
+
+fn default_max_clock_skew() -> FriendlyDuration {
+    FriendlyDuration::from_secs(300)
+}
+
+/// # Request signature scheme
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "scheme", rename_all = "kebab-case")]
+pub enum RequestSignatureScheme {
+    /// # HMAC-SHA256
+    ///
+    /// The request carries the HMAC-SHA256 of the body, computed with a shared secret.
+    /// When `timestamp-header` is set, the signed payload is `<timestamp>.<body>` instead, where
+    /// the timestamp is the value of that header in seconds since the Unix epoch.
+    #[serde(rename_all = "kebab-case")]
+    Hmac {
+        /// # Secret
+        ///
+        /// Secret shared with the signer.
+        secret: String,
+        /// # Signature header
+        ///
+        /// Header carrying the signature, e.g. `x-hub-signature-256`.
+        signature_header: String,
+        /// # Signature prefix
+        ///
+        /// Prefix of the signature header value to strip before decoding, e.g. `sha256=`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature_prefix: Option<String>,
+        /// # Signature encoding
+        #[serde(default)]
+        encoding: SignatureEncoding,
+        /// # Timestamp header
+        ///
+        /// Header carrying the signing time, e.g. `x-signature-timestamp`. Requests signed
+        /// longer ago than `max-clock-skew` are rejected, so that they can't be replayed.
+        ///
+        /// Leave it unset for signers which sign the body only, as GitHub does. Such requests
+        /// can be replayed, and `max-clock-skew` doesn't apply to them.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timestamp_header: Option<String>,
+    },
+    /// # AWS Signature Version 4
+    ///
+    /// The request is signed with AWS SigV4 in the `authorization` header, as API gateways
+    /// do, covering the payload too.
+    #[serde(rename_all = "kebab-case")]
+    AwsSigv4 {
+        /// # Access key id
+        access_key_id: String,
+        /// # Secret access key
+        secret_access_key: String,
+        /// # Region
+        region: String,
+        /// # Service
+        ///
+        /// Service name of the credential scope.
+        service: String,
+    },
+}
+
+/// # Signature encoding
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
 }
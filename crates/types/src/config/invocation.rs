@@ -8,7 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroUsize};
 
 use serde::{Deserialize, Serialize};
 
@@ -39,6 +39,18 @@ pub struct InvocationOptions {
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub max_journal_retention: Option<FriendlyDuration>,
 
+    /// # Default maximum journal length
+    ///
+    /// Default maximum number of journal entries of an invocation. Invocations exceeding it fail
+    /// with a terminal error, instead of letting a handler stuck in a loop grow its journal
+    /// without bounds. The maximum can be overridden per service using the Admin API.
+    ///
+    /// The maximum is part of the replicated state of the partitions: the leader of each
+    /// partition replicates the maximum configured on its node to the other replicas, which
+    /// enforce the same value regardless of their own configuration. Unset means no limit.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub default_max_journal_length: Option<NonZeroU32>,
+
     /// # Default retry policy
     ///
     /// The default retry policy to use for invocations.
@@ -63,6 +75,7 @@ impl Default for InvocationOptions {
         Self {
             default_journal_retention: FriendlyDuration::from_secs(60 * 60 * 24),
             max_journal_retention: None,
+            default_max_journal_length: None,
             default_retry_policy: InvocationRetryPolicyOptions::default(),
             max_retry_policy_max_attempts: None,
         }
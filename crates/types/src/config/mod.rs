@@ -12,6 +12,7 @@ mod util;
 use enumset::EnumSet;
 pub use util::*;
 mod admin;
+mod authentication;
 mod aws;
 mod bifrost;
 #[cfg(feature = "clap")]
@@ -28,9 +29,11 @@ mod networking;
 mod object_store;
 mod query_engine;
 mod rocksdb;
+mod sqs;
 mod worker;
 
 pub use admin::*;
+pub use authentication::*;
 pub use aws::*;
 pub use bifrost::*;
 #[cfg(feature = "clap")]
@@ -47,6 +50,7 @@ pub use networking::*;
 pub use object_store::*;
 pub use query_engine::*;
 pub use rocksdb::*;
+pub use sqs::*;
 pub use worker::*;
 
 use std::fs;
@@ -69,6 +73,7 @@ pub static PRODUCTION_PROFILE_DEFAULTS: LazyLock<Configuration> = LazyLock::new(
     let mut default = Configuration::default();
 
     default.common.auto_provision = false;
+    default.worker.invoker.disable_service_stubs = true;
 
     default
 });
@@ -271,6 +276,14 @@ impl Configuration {
         Ok(toml::to_string_pretty(self)?)
     }
 
+    /// Configuration as JSON, with the values of the options which look like secrets, e.g.
+    /// passwords or access keys, replaced by [`REDACTED`].
+    pub fn to_redacted_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).expect("configuration can be serialized");
+        redact_secrets(&mut value);
+        value
+    }
+
     /// Checks whether the given configuration is valid. Returns an [`InvalidConfigurationError`]
     /// it if is not valid.
     pub fn validate(&self) -> Result<(), InvalidConfigurationError> {
@@ -311,10 +324,60 @@ impl Configuration {
             }
         }
 
+        self.worker
+            .storage
+            .validate()
+            .map_err(InvalidConfigurationError::Storage)?;
+
+        self.ingress
+            .validate()
+            .map_err(InvalidConfigurationError::Ingress)?;
+
         Ok(())
     }
 }
 
+/// Replaces the redacted values, see [`Configuration::to_redacted_json`].
+pub const REDACTED: &str = "<redacted>";
+
+/// Serializes the secret options as [`REDACTED`], so that dumping the configuration doesn't leak
+/// them.
+fn serialize_redacted<T, S: serde::Serializer>(_: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+/// Fragments of the option names whose values are considered secrets.
+const SECRET_OPTION_FRAGMENTS: &[&str] = &[
+    "secret",
+    "password",
+    "token",
+    "credential",
+    "authorization",
+    "sasl",
+    "api-key",
+    "private-key",
+];
+
+fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                if SECRET_OPTION_FRAGMENTS
+                    .iter()
+                    .any(|fragment| key.contains(fragment))
+                {
+                    *value = serde_json::Value::String(REDACTED.to_owned());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
 #[derive(Clone, Debug, thiserror::Error, PartialEq, Eq)]
 pub enum InvalidConfigurationError {
     #[error(
@@ -325,6 +388,10 @@ pub enum InvalidConfigurationError {
     DeriveBindAddress(String),
     #[error("node-name is required: {0}")]
     RequiredNodeName(String),
+    #[error("invalid worker storage options: {0}")]
+    Storage(String),
+    #[error("invalid ingress options: {0}")]
+    Ingress(String),
 }
 
 #[allow(dead_code)]
@@ -364,6 +431,87 @@ fn read_subdirs(dir: &PathBuf) -> Vec<PathBuf> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn redacted_json_hides_secrets() {
+        let mut config = Configuration::default();
+        config.worker.snapshots.object_store.aws_secret_access_key = Some("s3cr3t".to_owned());
+
+        let json = config.to_redacted_json();
+        assert_eq!(
+            json["worker"]["snapshots"]["aws-secret-access-key"],
+            REDACTED
+        );
+        assert!(!json.to_string().contains("s3cr3t"));
+    }
+
+    #[test]
+    fn session_token_secret_is_validated_and_redacted() {
+        use restate_time_util::FriendlyDuration;
+
+        let session_tokens = |secret: &str| SessionTokenOptions {
+            secret: secret.to_owned(),
+            ttl: FriendlyDuration::from_secs(60),
+            workflows: vec![],
+            scopes: vec![],
+        };
+
+        let mut config = Configuration::default();
+        config.ingress = IngressOptionsBuilder::default()
+            .session_tokens(Some(session_tokens("s3cr3t")))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(InvalidConfigurationError::Ingress(_))
+        ));
+
+        let secret = "s3cr3t-s3cr3t-s3cr3t-s3cr3t-s3cr3t";
+        config.ingress = IngressOptionsBuilder::default()
+            .session_tokens(Some(session_tokens(secret)))
+            .build()
+            .unwrap();
+        assert!(config.validate().is_ok());
+        assert!(!config.dump().unwrap().contains(secret));
+        assert!(!format!("{config:?}").contains(secret));
+    }
+
+    #[test]
+    fn request_signature_clock_skew_is_bounded() {
+        use restate_time_util::FriendlyDuration;
+
+        let request_signatures = |max_clock_skew| {
+            vec![RequestSignatureOptions {
+                services: vec!["Webhooks".to_owned()],
+                max_clock_skew,
+                scheme: RequestSignatureScheme::Hmac {
+                    secret: "s3cr3t".to_owned(),
+                    signature_header: "x-signature".to_owned(),
+                    signature_prefix: None,
+                    encoding: SignatureEncoding::Hex,
+                    timestamp_header: Some("x-timestamp".to_owned()),
+                },
+            }]
+        };
+
+        let mut config = Configuration::default();
+        config.ingress = IngressOptionsBuilder::default()
+            .request_signatures(request_signatures(FriendlyDuration::from_secs(300)))
+            .build()
+            .unwrap();
+        assert!(config.validate().is_ok());
+
+        config.ingress = IngressOptionsBuilder::default()
+            .request_signatures(request_signatures(FriendlyDuration::from_secs(
+                24 * 60 * 60,
+            )))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(InvalidConfigurationError::Ingress(_))
+        ));
+    }
+
     #[test]
     fn test_read_subdirs_did_not_exist() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -445,4 +593,52 @@ mod tests {
             _ => panic!("Shoule be RequiredNodeName error"),
         }
     }
+
+    #[test]
+    fn test_configuration_validate_cold_data_paths() {
+        use std::num::NonZeroUsize;
+
+        use restate_serde_util::NonZeroByteCount;
+
+        let mut config = Configuration::default();
+        config.worker.storage.cold_data_paths = vec![
+            ColdDataPath {
+                path: PathBuf::from("/mnt/warm"),
+                target_size: None,
+            },
+            ColdDataPath {
+                path: PathBuf::from("/mnt/cold"),
+                target_size: None,
+            },
+        ];
+        // hot-data-target-size is missing
+        assert!(matches!(
+            config.validate(),
+            Err(InvalidConfigurationError::Storage(_))
+        ));
+
+        config.worker.storage.hot_data_target_size =
+            Some(NonZeroByteCount::new(NonZeroUsize::new(1 << 30).unwrap()));
+        // only the last path can be unbounded
+        assert!(matches!(
+            config.validate(),
+            Err(InvalidConfigurationError::Storage(_))
+        ));
+
+        config.worker.storage.cold_data_paths[0].target_size =
+            Some(NonZeroByteCount::new(NonZeroUsize::new(10 << 30).unwrap()));
+        assert!(config.validate().is_ok());
+
+        config.worker.storage.cold_data_paths[1].path = PathBuf::from("cold");
+        assert!(matches!(
+            config.validate(),
+            Err(InvalidConfigurationError::Storage(_))
+        ));
+
+        config.worker.storage.cold_data_paths[1].path = PathBuf::from("/mnt/co\0ld");
+        assert!(matches!(
+            config.validate(),
+            Err(InvalidConfigurationError::Storage(_))
+        ));
+    }
 }
@@ -15,6 +15,7 @@ use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
 use restate_serde_util::NonZeroByteCount;
+use restate_time_util::NonZeroFriendlyDuration;
 
 /// # Storage query engine options
 #[serde_as]
@@ -41,6 +42,37 @@ pub struct QueryEngineOptions {
     /// The degree of parallelism to use for query execution (Defaults to the number of available cores).
     query_parallelism: Option<NonZeroUsize>,
 
+    /// # Scan parallelism
+    ///
+    /// Number of concurrent storage iterators used to scan the key range of a single partition.
+    /// Each partition's key range is split into this many shards which are read ahead in
+    /// parallel, while rows are still returned in key order. Queries with a `LIMIT` always
+    /// scan sequentially.
+    pub scan_parallelism: NonZeroUsize,
+
+    /// # Max rows scanned
+    ///
+    /// Maximum number of rows a single query can read from the storage tables. Queries scanning
+    /// more rows fail. Unlimited by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows_scanned: Option<NonZeroUsize>,
+
+    /// # Max execution time
+    ///
+    /// Maximum time a single query can run for, including the time to stream the results.
+    /// Unlimited by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_execution_time: Option<NonZeroFriendlyDuration>,
+
+    /// # Max query memory
+    ///
+    /// Maximum memory in bytes a single query can use, out of the `memory-size` shared by all
+    /// the queries. Unlimited by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    pub max_query_memory: Option<NonZeroUsize>,
+
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     #[cfg_attr(feature = "schemars", schemars(skip))]
     pub datafusion_options: HashMap<String, String>,
@@ -59,6 +91,10 @@ impl Default for QueryEngineOptions {
             memory_size: NonZeroUsize::new(4 * 1024 * 1024 * 1024).unwrap(), // 4GiB
             tmp_dir: None,
             query_parallelism: None,
+            scan_parallelism: NonZeroUsize::new(4).unwrap(),
+            max_rows_scanned: None,
+            max_execution_time: None,
+            max_query_memory: None,
             datafusion_options: HashMap::new(),
         }
     }
@@ -0,0 +1,46 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use serde::{Deserialize, Serialize};
+
+/// # AWS SQS connection options
+///
+/// Configuration options to connect to AWS SQS. Credentials are resolved through the default
+/// AWS credentials provider chain.
+#[derive(Debug, Clone, Serialize, Deserialize, derive_builder::Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct SqsConnectionOptions {
+    /// Connection name (Used to identify subscriptions).
+    pub name: String,
+
+    /// # AWS Region
+    ///
+    /// Region of the queues, e.g. `eu-central-1`. Defaults to the region of the selected AWS
+    /// profile, or to the `AWS_REGION` env var.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub region: Option<String>,
+
+    /// # AWS Profile
+    ///
+    /// Name of the AWS profile to select. Defaults to 'AWS_PROFILE' env var, or otherwise
+    /// the `default` profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub aws_profile: Option<String>,
+
+    /// # Endpoint URL
+    ///
+    /// Overrides the SQS endpoint, e.g. to use a local SQS-compatible service for testing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub endpoint_url: Option<String>,
+}
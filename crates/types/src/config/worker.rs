@@ -8,10 +8,12 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::collections::HashMap;
+use std::num::{NonZeroU8, NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::time::Duration;
 
+use enumset::EnumSet;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use tracing::warn;
@@ -20,9 +22,10 @@ use restate_serde_util::NonZeroByteCount;
 use restate_time_util::{FriendlyDuration, NonZeroFriendlyDuration};
 
 use super::{CommonOptions, ObjectStoreOptions, RocksDbOptions, RocksDbOptionsBuilder};
-use crate::identifiers::PartitionId;
+use crate::identifiers::{DeploymentId, PartitionId};
 use crate::rate::Rate;
 use crate::retries::RetryPolicy;
+use crate::timer::TimerKind;
 
 /// # Worker options
 #[serde_as]
@@ -40,6 +43,46 @@ pub struct WorkerOptions {
     /// The number of timers in memory limit is used to bound the amount of timers loaded in memory. If this limit is set, when exceeding it, the timers farther in the future will be spilled to disk.
     num_timers_in_memory_limit: Option<NonZeroUsize>,
 
+    /// # Timers memory budget
+    ///
+    /// Memory budget of the timers loaded in memory by each partition leader. If set, the number of
+    /// timers kept in memory adapts to the memory size of the timers, spilling to disk the timers
+    /// farther in the future when exceeding it. Spilled timers are loaded back by scanning the
+    /// storage more frequently. If also `num-timers-in-memory-limit` is set, the stricter of the
+    /// two limits applies.
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timers_memory_budget: Option<NonZeroUsize>,
+
+    /// # Timers max batch size
+    ///
+    /// Maximum number of timers a partition leader fires at once with a single command. When many
+    /// timers are due at the same time, firing them in batches reduces the overhead of applying
+    /// them. Unset fires every timer with its own command.
+    ///
+    /// Since v1.6.0 (not compatible with earlier versions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timers_max_batch_size: Option<NonZeroUsize>,
+
+    /// # Timers batch window
+    ///
+    /// Timers due within this window are fired in the same batch, hence up to this much earlier
+    /// than their wake-up time. Only applies if `timers-max-batch-size` is set.
+    #[serde(default, skip_serializing_if = "FriendlyDuration::is_zero")]
+    timers_batch_window: FriendlyDuration,
+
+    /// # Timers interleave ratio
+    ///
+    /// Number of user-facing commands, like invoker effects and fired sleeps, a busy partition
+    /// leader proposes for each housekeeping timer it fires, like the cleanup of completed
+    /// invocations. Housekeeping timers then never starve the user-facing traffic, nor are they
+    /// starved by it, e.g. when a partition recovers with a backlog of due timers. Housekeeping
+    /// timers fire as soon as they're due when the leader is idle. Unset fires all the timers as
+    /// soon as they're due.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timers_interleave_ratio: Option<NonZeroUsize>,
+
     /// # Cleanup interval
     ///
     /// In order to clean up completed invocations, that is invocations invoked with an idempotency id, or workflows,
@@ -96,6 +139,121 @@ pub struct WorkerOptions {
     #[cfg_attr(feature = "schemars", schemars(skip))]
     #[serde(default, skip_serializing_if = "FriendlyDuration::is_zero")]
     trim_delay_interval: FriendlyDuration,
+
+    /// # Apply lag warning threshold
+    ///
+    /// A warning is emitted when a partition processor applies commands later than this after
+    /// they were appended to the log. Unset disables the warning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_lag_warning_threshold: Option<NonZeroFriendlyDuration>,
+
+    /// # Oldest unapplied command age warning threshold
+    ///
+    /// A warning is emitted when the oldest command a partition processor has not applied yet is
+    /// older than this. Unset disables the warning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oldest_unapplied_command_age_warning_threshold: Option<NonZeroFriendlyDuration>,
+
+    /// # Paused timer kinds
+    ///
+    /// Kinds of timers whose firing is paused on this node at startup, e.g. `["cleanup"]` to stop
+    /// the cleanup of completed invocations. Possible kinds are `sleep`, `delayed-invocation`,
+    /// `cleanup` and `deadline`. The paused kinds can be changed at runtime through the admin API.
+    #[serde(default, skip_serializing_if = "EnumSet::is_empty")]
+    pub paused_timer_kinds: EnumSet<TimerKind>,
+
+    /// # Deferred timers limit
+    ///
+    /// Maximum number of due timers of the paused kinds kept in memory by each partition leader
+    /// until their kind is resumed. Once reached, the further due timers of the paused kinds are
+    /// dropped from memory, and reloaded from storage once their kind is resumed. The timers of
+    /// the other kinds keep firing.
+    deferred_timers_limit: NonZeroUsize,
+
+    /// # State size quota
+    ///
+    /// Maximum total size of the state of a single Virtual Object or Workflow, that is the sum of
+    /// the lengths of its state keys and values. Invocations setting a state entry which would
+    /// exceed it fail with a terminal error. Entries with a time-to-live are not accounted.
+    /// Unset disables the quota.
+    ///
+    /// The quota is part of the replicated state of the partitions: the leader of each partition
+    /// replicates the quota configured on its node to the other replicas, which enforce the same
+    /// value regardless of their own configuration.
+    #[serde_as(as = "Option<NonZeroByteCount>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_size_quota: Option<NonZeroUsize>,
+
+    /// # Poison command max attempts
+    ///
+    /// Number of times the leader of a partition tries to apply a command before parking it, so
+    /// that the partition can make progress with the subsequent commands. The leader records its
+    /// decision in the log, and all the replicas failing to apply the command park it, while those
+    /// applying it successfully ignore the decision. Parked commands can be inspected and retried
+    /// through the admin API. Commands which fence off the state machine, like version barriers,
+    /// are never parked. Unset retries failing commands forever.
+    ///
+    /// Use it as a last resort to unblock a partition.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poison_command_max_attempts: Option<NonZeroU32>,
+
+    /// # Outbox consistency check interval
+    ///
+    /// Interval of the checks verifying that the outbox of each partition leader running on this
+    /// node is consistent with the dedup tables of the partitions running on this node, which
+    /// would otherwise drop or never receive some of the messages sent between partitions. Leaders
+    /// are also checked right after their election. Inconsistencies are logged, counted by the
+    /// `restate.partition.outbox_inconsistencies` metric and reported by the Admin API. Unset
+    /// disables the checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outbox_consistency_check_interval: Option<NonZeroFriendlyDuration>,
+
+    /// # Invocation lifecycle webhook
+    ///
+    /// Webhook receiving an event whenever an invocation is started, suspended, completed or
+    /// failed. Events are sent by the leaders of the partitions. Unset disables the events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invocation_lifecycle_webhook: Option<LifecycleWebhookOptions>,
+
+    /// # Payload encryption
+    ///
+    /// Encrypts the journal entries and the state values of the invocations before storing them
+    /// in the partition store, so that they're not stored in plaintext even without full-disk
+    /// encryption. Unset stores the new payloads in plaintext.
+    ///
+    /// Payloads are encrypted by every replica of the partition processors and decrypted when
+    /// read, hence all the nodes running partition processors need the same keys.
+    ///
+    /// The input entry and the journal entries of the invocations on service protocol V4 and
+    /// later are encrypted. The following payloads are still stored in plaintext: the journal
+    /// entries and completions of the invocations on service protocol V3 and earlier (except
+    /// the input entry), the input of the scheduled and inboxed invocations, and the output of
+    /// the completed invocations which are retained.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_encryption: Option<PayloadEncryptionOptions>,
+
+    /// # Completion Kafka sink
+    ///
+    /// Kafka topic receiving an event whenever an invocation is completed or failed, delivered
+    /// exactly once even across partition leader changes. Unset disables the sink.
+    ///
+    /// The events are stored by every replica of the partition processors until the leader
+    /// delivered them. Whether to store them is part of the replicated state of the partitions:
+    /// a leader with the sink configured enables it for all the replicas. It stays enabled when
+    /// a leader without the sink takes over, so that the events are kept until a leader with the
+    /// sink delivers them, see `discard-completion-events` to disable it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_kafka_sink: Option<CompletionKafkaSinkOptions>,
+
+    /// # Discard completion events
+    ///
+    /// Stops storing the completion events of the partitions led by this node, and discards the
+    /// stored ones which were not delivered yet. Use it to disable the completion Kafka sink for
+    /// good, after removing `completion-kafka-sink` from the configuration of all the nodes.
+    /// Ignored when `completion-kafka-sink` is configured.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub discard_completion_events: bool,
 }
 
 impl WorkerOptions {
@@ -111,6 +269,26 @@ impl WorkerOptions {
         self.num_timers_in_memory_limit.map(Into::into)
     }
 
+    pub fn deferred_timers_limit(&self) -> usize {
+        self.deferred_timers_limit.into()
+    }
+
+    pub fn timers_memory_budget(&self) -> Option<usize> {
+        self.timers_memory_budget.map(Into::into)
+    }
+
+    pub fn timers_max_batch_size(&self) -> Option<usize> {
+        self.timers_max_batch_size.map(Into::into)
+    }
+
+    pub fn timers_batch_window(&self) -> Duration {
+        self.timers_batch_window.into()
+    }
+
+    pub fn timers_interleave_ratio(&self) -> Option<NonZeroUsize> {
+        self.timers_interleave_ratio
+    }
+
     pub fn cleanup_interval(&self) -> Duration {
         self.cleanup_interval.into()
     }
@@ -125,6 +303,10 @@ impl Default for WorkerOptions {
         Self {
             internal_queue_length: NonZeroUsize::new(1000).expect("Non zero number"),
             num_timers_in_memory_limit: None,
+            timers_memory_budget: None,
+            timers_max_batch_size: None,
+            timers_batch_window: FriendlyDuration::ZERO,
+            timers_interleave_ratio: None,
             cleanup_interval: NonZeroFriendlyDuration::from_secs_unchecked(60 * 60),
             storage: StorageOptions::default(),
             invoker: Default::default(),
@@ -132,10 +314,173 @@ impl Default for WorkerOptions {
             snapshots: SnapshotsOptions::default(),
             trim_delay_interval: FriendlyDuration::ZERO,
             durability_mode: None,
+            apply_lag_warning_threshold: None,
+            oldest_unapplied_command_age_warning_threshold: None,
+            paused_timer_kinds: EnumSet::empty(),
+            deferred_timers_limit: NonZeroUsize::new(100_000).expect("Non zero number"),
+            state_size_quota: None,
+            poison_command_max_attempts: None,
+            outbox_consistency_check_interval: None,
+            invocation_lifecycle_webhook: None,
+            payload_encryption: None,
+            completion_kafka_sink: None,
+            discard_completion_events: false,
         }
     }
 }
 
+/// # Payload encryption options
+///
+/// Payloads are encrypted with AES-256-GCM. Each encrypted payload records the id of its key, so
+/// keys are rotated by adding a new key and making it the active one: the payloads encrypted with
+/// the previous keys stay readable as long as these keys are configured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct PayloadEncryptionOptions {
+    /// # Active key id
+    ///
+    /// Id of the key encrypting the new payloads, one of `keys`.
+    pub active_key_id: String,
+
+    /// # Keys
+    ///
+    /// Paths of the key files by key id. Each file contains the base64 encoding of a 256-bit
+    /// key, which can be generated with `openssl rand -base64 32`.
+    pub keys: HashMap<String, PathBuf>,
+
+    /// # Services
+    ///
+    /// Services whose payloads are encrypted. Empty encrypts the payloads of all the services.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<String>,
+}
+
+/// # Invocation lifecycle webhook options
+///
+/// The events are sent as `POST` requests with a JSON body, one event per request, in the order
+/// they're emitted by each partition.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct LifecycleWebhookOptions {
+    /// # URL
+    ///
+    /// Endpoint receiving the events, e.g. `https://alerting.example.com/restate-events`.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub url: http::Uri,
+
+    /// # Additional headers
+    ///
+    /// Headers added to every request, e.g. to authenticate them with the webhook.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub additional_headers: HashMap<String, String>,
+
+    /// # Request timeout
+    ///
+    /// Timeout of a single request to the webhook.
+    #[serde(default = "LifecycleWebhookOptions::default_request_timeout")]
+    pub request_timeout: NonZeroFriendlyDuration,
+
+    /// # Retry policy
+    ///
+    /// Retry policy of the requests failing with a network error or a non-successful status
+    /// code. Events still failing after the last attempt are dropped.
+    #[serde(default = "LifecycleWebhookOptions::default_retry_policy")]
+    pub retry_policy: RetryPolicy,
+
+    /// # Queue length
+    ///
+    /// Maximum number of events waiting to be sent. Events emitted while the queue is full are
+    /// dropped, so that a slow webhook never slows down the partition processors.
+    #[serde(default = "LifecycleWebhookOptions::default_queue_length")]
+    pub queue_length: NonZeroUsize,
+}
+
+impl LifecycleWebhookOptions {
+    fn default_request_timeout() -> NonZeroFriendlyDuration {
+        NonZeroFriendlyDuration::from_secs_unchecked(10)
+    }
+
+    fn default_retry_policy() -> RetryPolicy {
+        RetryPolicy::exponential(
+            Duration::from_millis(100),
+            2.0,
+            Some(10),
+            Some(Duration::from_secs(10)),
+        )
+    }
+
+    fn default_queue_length() -> NonZeroUsize {
+        NonZeroUsize::new(10_000).expect("Non zero number")
+    }
+}
+
+/// # Completion Kafka sink options
+///
+/// The events are the JSON events of the invocation lifecycle webhook, keyed by invocation id.
+/// Each partition leader produces them with a transactional producer, committing in the same
+/// transaction the sequence of the last delivered event to the progress topic. A new leader
+/// fences the producer of the previous one and resumes after the last committed sequence, so
+/// consumers reading with `isolation.level=read_committed` see every event exactly once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct CompletionKafkaSinkOptions {
+    /// # Cluster
+    ///
+    /// Name of the Kafka cluster, configured in `ingress.kafka-clusters`.
+    pub cluster: String,
+
+    /// # Topic
+    ///
+    /// Topic receiving the completion events.
+    pub topic: String,
+
+    /// # Progress topic
+    ///
+    /// Existing topic whose committed offsets store the sequence of the last delivered event of
+    /// each partition. The offset of its first partition is committed by the consumer group
+    /// `<transactional-id-prefix>-<partition-id>`, within the transaction delivering the events.
+    /// No records are produced to it.
+    pub progress_topic: String,
+
+    /// # Transactional id prefix
+    ///
+    /// Prefix of the transactional ids of the producers, suffixed by the partition id. It must
+    /// be unique across Restate clusters producing to the same Kafka cluster.
+    #[serde(default = "CompletionKafkaSinkOptions::default_transactional_id_prefix")]
+    pub transactional_id_prefix: String,
+
+    /// # Flush interval
+    ///
+    /// Interval at which the leader delivers the stored events.
+    #[serde(default = "CompletionKafkaSinkOptions::default_flush_interval")]
+    pub flush_interval: NonZeroFriendlyDuration,
+
+    /// # Max batch size
+    ///
+    /// Maximum number of events delivered in a single Kafka transaction.
+    #[serde(default = "CompletionKafkaSinkOptions::default_max_batch_size")]
+    pub max_batch_size: NonZeroUsize,
+}
+
+impl CompletionKafkaSinkOptions {
+    fn default_transactional_id_prefix() -> String {
+        "restate-completion-sink".to_owned()
+    }
+
+    fn default_flush_interval() -> NonZeroFriendlyDuration {
+        NonZeroFriendlyDuration::from_secs_unchecked(1)
+    }
+
+    fn default_max_batch_size() -> NonZeroUsize {
+        NonZeroUsize::new(1000).expect("Non zero number")
+    }
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -252,10 +597,20 @@ pub struct InvokerOptions {
     /// # Message size limit
     ///
     /// Threshold to fail the invocation in case protocol messages coming from a service are larger than the specified amount.
+    /// The invocation attempt fails with the error code `413`, and is retried according to the retry policy.
     #[serde_as(as = "Option<NonZeroByteCount>")]
     #[cfg_attr(feature = "schemars", schemars(with = "Option<NonZeroByteCount>"))]
     message_size_limit: Option<NonZeroUsize>,
 
+    /// # Eager state size limit
+    ///
+    /// Maximum size of the state of a virtual object or workflow sent eagerly to the service when
+    /// starting an invocation. Bigger states are sent partially, and the service fetches the
+    /// missing entries lazily when accessing them.
+    #[serde_as(as = "NonZeroByteCount")]
+    #[cfg_attr(feature = "schemars", schemars(with = "NonZeroByteCount"))]
+    eager_state_size_limit: NonZeroUsize,
+
     /// # Temporary directory
     ///
     /// Temporary directory to use for the invoker temporary files.
@@ -274,6 +629,17 @@ pub struct InvokerOptions {
     /// Number of concurrent invocations that can be processed by the invoker.
     concurrent_invocations_limit: Option<NonZeroUsize>,
 
+    /// # Limit number of concurrent invocations per key
+    ///
+    /// Number of concurrent invocations of the same virtual object or workflow key that can be
+    /// processed by the invoker. Invocations waiting for a free slot of their key are started in
+    /// round-robin order across keys, so that a single key with a large backlog cannot starve
+    /// the other keys. Exclusive handlers already run one at a time per key, hence this mostly
+    /// applies to shared handlers. At most `in-memory-queue-length-limit` invocations wait for
+    /// a free slot of their key, the following ones are kept in the invoker's input queue.
+    /// When `unset`, invocations are not limited per key.
+    concurrent_invocations_per_key_limit: Option<NonZeroUsize>,
+
     // -- Private config options (not exposed in the schema)
     #[cfg_attr(feature = "schemars", schemars(skip))]
     #[serde(skip_serializing_if = "std::ops::Not::not", default)]
@@ -304,6 +670,81 @@ pub struct InvokerOptions {
     /// When `unset`, no throttling is applied and actions are processed
     /// without throttling.
     pub action_throttling: Option<ThrottlingOptions>,
+
+    /// # Limit number of concurrent journal replays from this node
+    ///
+    /// Number of invocations that can concurrently replay their journal to the deployment
+    /// when resuming. Replays waiting for a free slot are admitted in round-robin order
+    /// across services, so that a single service with many resuming invocations cannot
+    /// starve the others. When `unset`, journal replays are not limited.
+    concurrent_replays_limit: Option<NonZeroUsize>,
+
+    /// # Journal replay throttling
+    ///
+    /// Configures the bandwidth available to replay journals to the deployments when resuming
+    /// invocations. Tokens of this bucket are the bytes of the replayed journal entries,
+    /// e.g. a `rate` of `10485760/s` limits replays to 10 MiB per second.
+    ///
+    /// The throttling limit is shared across all partitions running on this node.
+    /// When `unset`, no throttling is applied to journal replays.
+    pub replay_throttling: Option<ThrottlingOptions>,
+
+    /// # Service stubs
+    ///
+    /// Stub responses for services which are not implemented yet, keyed by service name and
+    /// handler name. Invocations of a stubbed handler, whose service has no registered deployment,
+    /// are completed by the invoker with the configured response. This allows to develop against
+    /// services whose handlers don't exist yet.
+    ///
+    /// Stubs are meant for development only, and are ignored when running with the production profile.
+    ///
+    /// Example:
+    ///
+    /// ```toml
+    /// [worker.invoker.service-stubs.PaymentService.charge]
+    /// response = { status = "approved", amount = "{{request.amount}}" }
+    /// ```
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub service_stubs: HashMap<String, HashMap<String, ServiceStubOptions>>,
+
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub disable_service_stubs: bool,
+
+    /// # Traffic mirroring
+    ///
+    /// Mirrors a percentage of the new invocations of a service to a shadow deployment, keyed by
+    /// service name. This allows to validate a new revision of a service under real traffic,
+    /// before routing the invocations to it.
+    ///
+    /// Mirrored invocations are fire-and-forget: the journal entries and the result proposed by
+    /// the shadow deployment are discarded, and the completions it waits for are never sent,
+    /// hence mirrored invocations usually suspend at their first call or sleep. Side effects
+    /// executed within the shadow deployment, like `ctx.run` closures, are not prevented though:
+    /// requests to the shadow deployment carry the `x-restate-shadow: true` header, which services
+    /// should check to skip them.
+    ///
+    /// The outcome and the duration of the primary and of the shadow attempts are exposed through
+    /// the `restate.invoker.mirrored_attempts.total` and
+    /// `restate.invoker.mirrored_attempt_duration.seconds` metrics.
+    ///
+    /// Example:
+    ///
+    /// ```toml
+    /// [worker.invoker.traffic-mirroring.Greeter]
+    /// deployment = "dp_11nGQpCRmau6ypL82KH2TnP"
+    /// percentage = 5
+    /// ```
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub traffic_mirroring: HashMap<String, TrafficMirroringOptions>,
+
+    /// # Attempt diagnostic headers
+    ///
+    /// Names of the response headers to capture from the deployment responses, and to expose
+    /// in the `last_attempt_diagnostic_headers` column of the `sys_invocation` table. Headers
+    /// are captured before the response status is checked, so they're available also for
+    /// attempts failed by gateways, load balancers or proxies in front of the deployment.
+    pub attempt_diagnostic_headers: Vec<String>,
 }
 
 impl InvokerOptions {
@@ -317,6 +758,10 @@ impl InvokerOptions {
         self.concurrent_invocations_limit.map(Into::into)
     }
 
+    pub fn concurrent_invocations_per_key_limit(&self) -> Option<usize> {
+        self.concurrent_invocations_per_key_limit.map(Into::into)
+    }
+
     pub fn in_memory_queue_length_limit(&self) -> usize {
         self.in_memory_queue_length_limit.into()
     }
@@ -324,6 +769,14 @@ impl InvokerOptions {
     pub fn message_size_limit(&self) -> Option<usize> {
         self.message_size_limit.map(Into::into)
     }
+
+    pub fn eager_state_size_limit(&self) -> NonZeroUsize {
+        self.eager_state_size_limit
+    }
+
+    pub fn concurrent_replays_limit(&self) -> Option<usize> {
+        self.concurrent_replays_limit.map(Into::into)
+    }
 }
 
 impl Default for InvokerOptions {
@@ -334,15 +787,56 @@ impl Default for InvokerOptions {
             abort_timeout: FriendlyDuration::new(DEFAULT_ABORT_TIMEOUT),
             message_size_warning: NonZeroUsize::new(10 * 1024 * 1024).unwrap(), // 10MiB
             message_size_limit: None,
+            eager_state_size_limit: NonZeroUsize::new(16 * 1024 * 1024).unwrap(), // 16MiB
             tmp_dir: None,
             concurrent_invocations_limit: Some(NonZeroUsize::new(1000).expect("is non zero")),
+            concurrent_invocations_per_key_limit: None,
             disable_eager_state: false,
             invocation_throttling: None,
             action_throttling: None,
+            concurrent_replays_limit: None,
+            replay_throttling: None,
+            service_stubs: HashMap::new(),
+            disable_service_stubs: false,
+            traffic_mirroring: HashMap::new(),
+            attempt_diagnostic_headers: vec!["server".to_owned(), "x-request-id".to_owned()],
         }
     }
 }
 
+/// # Service stub options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(rename = "ServiceStubOptions"))]
+#[serde(rename_all = "kebab-case")]
+pub struct ServiceStubOptions {
+    /// # Response
+    ///
+    /// JSON value returned as response of the stubbed handler. Strings equal to `{{request}}` are
+    /// replaced with the JSON request body, and strings equal to `{{request.<path>}}` with the
+    /// field of the request body at the given dot-separated path. When these placeholders are
+    /// part of a longer string, they're replaced with the textual representation of the value.
+    pub response: serde_json::Value,
+}
+
+/// # Traffic mirroring options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(rename = "TrafficMirroringOptions"))]
+#[serde(rename_all = "kebab-case")]
+pub struct TrafficMirroringOptions {
+    /// # Deployment
+    ///
+    /// Id of the shadow deployment receiving the mirrored invocations. It must expose the
+    /// mirrored service.
+    pub deployment: DeploymentId,
+
+    /// # Percentage
+    ///
+    /// Percentage of the new invocations of the service which are mirrored, between 0 and 100.
+    pub percentage: u8,
+}
+
 /// # Storage options
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize, derive_builder::Builder)]
@@ -372,6 +866,45 @@ pub struct StorageOptions {
     /// partitions.
     rocksdb_memory_ratio: f32,
 
+    /// # Bloom filter bits per key
+    ///
+    /// Number of bits per key used by the bloom filters of the partition store. Higher values
+    /// reduce the false positive rate of point lookups and prefix scans, at the cost of memory.
+    rocksdb_bloom_filter_bits_per_key: NonZeroU8,
+
+    /// # Table key prefixes
+    ///
+    /// The key prefixes used by the prefix bloom filters of the partition store tables. Longer
+    /// prefixes allow prefix scans, like reading the state of a virtual object or the journal of
+    /// an invocation, to skip the data files which don't contain the prefix.
+    ///
+    /// Changing the key prefixes requires a restart. Existing prefix bloom filters are ignored
+    /// until the data files are rewritten by compactions.
+    pub table_key_prefixes: TableKeyPrefixOptions,
+
+    /// # Cold data paths
+    ///
+    /// Paths where the partition store moves its older data files, for example to keep the
+    /// recently written data on fast disks and the bulk of the data, like the journals of
+    /// long-running invocations, on cheaper ones.
+    ///
+    /// The data files are written to the node data directory until they exceed
+    /// `hot-data-target-size`, then compactions move the older data to the cold data paths in the
+    /// given order, each filled up to its `target-size`. The last path can be unbounded. Data is
+    /// tiered by age rather than by table, as all the tables of a partition share the same column
+    /// family.
+    ///
+    /// Paths can be appended, but they can't be removed or reordered once data was written to them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cold_data_paths: Vec<ColdDataPath>,
+
+    /// # Hot data target size
+    ///
+    /// Target size of the partition store data files kept in the node data directory. Required
+    /// when `cold-data-paths` are configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hot_data_target_size: Option<NonZeroByteCount>,
+
     /// Whether to perform commits in background IO thread pools eagerly or not
     #[cfg_attr(feature = "schemars", schemars(skip))]
     #[serde(skip_serializing_if = "std::ops::Not::not", default)]
@@ -407,6 +940,10 @@ impl StorageOptions {
             .get()
     }
 
+    pub fn rocksdb_bloom_filter_bits_per_key(&self) -> f64 {
+        self.rocksdb_bloom_filter_bits_per_key.get().into()
+    }
+
     pub fn data_dir(&self, db_name: &str) -> PathBuf {
         super::data_dir(db_name)
     }
@@ -414,6 +951,100 @@ impl StorageOptions {
     pub fn snapshots_staging_dir(&self) -> PathBuf {
         super::data_dir("pp-snapshots")
     }
+
+    /// The paths where the data files of the given db are placed, with their target sizes. Empty
+    /// if no cold data paths are configured, in which case all data files stay in the data dir.
+    pub fn data_paths(&self, db_name: &str) -> Vec<(PathBuf, u64)> {
+        if self.cold_data_paths.is_empty() {
+            return Vec::new();
+        }
+
+        let hot_data_target_size = self
+            .hot_data_target_size
+            .map(|size| size.as_u64())
+            .unwrap_or(u64::MAX);
+        let mut data_paths = vec![(self.data_dir(db_name), hot_data_target_size)];
+        data_paths.extend(self.cold_data_paths.iter().map(|cold_data_path| {
+            (
+                cold_data_path.db_dir(db_name),
+                cold_data_path
+                    .target_size
+                    .map(|size| size.as_u64())
+                    .unwrap_or(u64::MAX),
+            )
+        }));
+        data_paths
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cold_data_paths.is_empty() {
+            return Ok(());
+        }
+
+        if self.hot_data_target_size.is_none() {
+            return Err(
+                "hot-data-target-size must be set when cold-data-paths are configured".to_owned(),
+            );
+        }
+
+        let last = self.cold_data_paths.len() - 1;
+        for (idx, cold_data_path) in self.cold_data_paths.iter().enumerate() {
+            let path = &cold_data_path.path;
+            if !path.is_absolute() {
+                return Err(format!(
+                    "cold data path '{}' must be absolute",
+                    path.display()
+                ));
+            }
+            // RocksDB takes the paths as C strings
+            if path.to_str().is_none_or(|path| path.contains('\0')) {
+                return Err(format!(
+                    "cold data path '{}' must be valid UTF-8 without nul characters",
+                    path.display()
+                ));
+            }
+            if idx < last && cold_data_path.target_size.is_none() {
+                return Err(format!(
+                    "cold data path '{}' requires a target-size, only the last path can be unbounded",
+                    path.display()
+                ));
+            }
+            if self.cold_data_paths[..idx]
+                .iter()
+                .any(|other| other.path == *path)
+            {
+                return Err(format!(
+                    "cold data path '{}' is configured more than once",
+                    path.display()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Cold data path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct ColdDataPath {
+    /// # Path
+    ///
+    /// Absolute path of the directory holding the data files. Each partition store database uses
+    /// a sub-directory named after it.
+    pub path: PathBuf,
+    /// # Target size
+    ///
+    /// Target size of the data files in this path, before compactions move them to the next path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_size: Option<NonZeroByteCount>,
+}
+
+impl ColdDataPath {
+    pub fn db_dir(&self, db_name: &str) -> PathBuf {
+        self.path.join(db_name)
+    }
 }
 
 impl Default for StorageOptions {
@@ -429,11 +1060,65 @@ impl Default for StorageOptions {
             // set by apply_common in runtime
             rocksdb_memory_budget: None,
             rocksdb_memory_ratio: 0.49,
+            rocksdb_bloom_filter_bits_per_key: NonZeroU8::new(10).unwrap(),
+            table_key_prefixes: TableKeyPrefixOptions::default(),
+            cold_data_paths: Vec::new(),
+            hot_data_target_size: None,
             always_commit_in_background: false,
         }
     }
 }
 
+/// # Table key prefixes
+///
+/// Key prefix of each partition store table. All the keys start with the partition key, hence
+/// `partition-key` is always a valid prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "schemars",
+    schemars(rename = "TableKeyPrefixOptions", default)
+)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct TableKeyPrefixOptions {
+    /// # State table
+    ///
+    /// Use `service` to include the service name in the prefix of the state keys.
+    pub state: ServiceKeyPrefix,
+    /// # Promise table
+    ///
+    /// Use `service` to include the workflow name in the prefix of the promise keys.
+    pub promise: ServiceKeyPrefix,
+    /// # Journal table
+    ///
+    /// Use `invocation` to include the invocation id in the prefix of the journal keys.
+    pub journal: InvocationKeyPrefix,
+}
+
+/// # Service key prefix
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceKeyPrefix {
+    /// The partition key only.
+    #[default]
+    PartitionKey,
+    /// The partition key followed by the service name.
+    Service,
+}
+
+/// # Invocation key prefix
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum InvocationKeyPrefix {
+    /// The partition key only.
+    #[default]
+    PartitionKey,
+    /// The partition key followed by the invocation uuid.
+    Invocation,
+}
+
 /// # Snapshot options.
 ///
 /// Partition store snapshotting settings. At a minimum, set `destination` and
@@ -22,12 +22,55 @@ pub type GenericError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 pub type BoxedMaybeRetryableError = Box<dyn MaybeRetryableError + Send + Sync>;
 
+/// Classifies errors by how they should be handled. Components mapping errors to retries or to
+/// responses (invoker, ingress, request dispatcher) use this classification rather than
+/// matching on the error variants of other crates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum ErrorClass {
+    /// The operation failed before it could have any effect, e.g. because it was routed to a
+    /// node which is not the leader anymore. It's always safe to retry right away.
+    #[display("transient")]
+    Transient,
+    /// The operation failed, but might succeed if retried later on. The operation might have
+    /// been (partially) applied, hence it should be retried only if it's idempotent.
+    #[display("retryable with backoff")]
+    RetryableWithBackoff,
+    /// The operation was rejected because of the user input, retrying won't help.
+    #[display("terminal user error")]
+    TerminalUser,
+    /// The system cannot complete the operation, retrying won't help.
+    #[display("terminal system error")]
+    TerminalSystem,
+}
+
+impl ErrorClass {
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorClass::Transient | ErrorClass::RetryableWithBackoff
+        )
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        !self.is_retryable()
+    }
+}
+
 /// Tells whether an error should be retried by upper layers or not.
 pub trait MaybeRetryableError: std::error::Error + 'static {
     /// Signal upper layers whether this error should be retried or not.
     fn retryable(&self) -> bool {
         false
     }
+
+    /// The [`ErrorClass`] of this error, by default derived from [`Self::retryable`].
+    fn error_class(&self) -> ErrorClass {
+        if self.retryable() {
+            ErrorClass::RetryableWithBackoff
+        } else {
+            ErrorClass::TerminalSystem
+        }
+    }
 }
 
 static_assertions::assert_obj_safe!(MaybeRetryableError);
@@ -165,12 +208,15 @@ pub mod codes {
     codes!(
         BAD_REQUEST 400 "Bad request",
         NOT_FOUND 404 "Not found",
+        DEADLINE_EXCEEDED 408 "Deadline exceeded",
         INTERNAL 500 "Internal",
         ABORTED 409 "Aborted",
         GONE 410 "Gone",
+        PAYLOAD_TOO_LARGE 413 "Payload too large",
         UNSUPPORTED_MEDIA_TYPE 415 "Unsupported media type",
         JOURNAL_MISMATCH 570 "Journal mismatch",
         PROTOCOL_VIOLATION 571 "Protocol violation",
+        JOURNAL_LENGTH_EXCEEDED 572 "Journal length exceeded",
         CONFLICT 409 "Conflict",
         NOT_READY 470 "Not ready",
     );
@@ -349,6 +395,18 @@ pub const KILLED_INVOCATION_ERROR: InvocationError =
 pub const CANCELED_INVOCATION_ERROR: InvocationError =
     InvocationError::new_static(codes::ABORTED, "canceled");
 
+pub const DEADLINE_EXCEEDED_INVOCATION_ERROR: InvocationError =
+    InvocationError::new_static(codes::DEADLINE_EXCEEDED, "deadline exceeded");
+
+pub const STATE_SIZE_QUOTA_EXCEEDED_INVOCATION_ERROR: InvocationError =
+    InvocationError::new_static(codes::BAD_REQUEST, "state size quota exceeded");
+
+pub const JOURNAL_LENGTH_EXCEEDED_INVOCATION_ERROR: InvocationError = InvocationError::new_static(
+    codes::JOURNAL_LENGTH_EXCEEDED,
+    "maximum journal length exceeded. Handlers running long loops should checkpoint their \
+    progress, e.g. in the state of a Virtual Object, and continue it in a new invocation",
+);
+
 pub const GONE_INVOCATION_ERROR: InvocationError = InvocationError::new_static(codes::GONE, "gone");
 
 pub const NOT_FOUND_INVOCATION_ERROR: InvocationError =
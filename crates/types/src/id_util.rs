@@ -10,6 +10,7 @@
 
 //! Resource identifier helpers and core structures
 
+use std::fmt;
 use std::str::FromStr;
 
 use generic_array::GenericArray;
@@ -248,3 +249,31 @@ impl<T: ResourceId + ?Sized> IdEncoder<T> {
         unsafe { std::str::from_utf8_unchecked(&self.buf[..self.pos]) }
     }
 }
+
+impl<T: ResourceId + ?Sized> Clone for IdEncoder<T> {
+    fn clone(&self) -> Self {
+        Self {
+            buf: self.buf.clone(),
+            pos: self.pos,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: ResourceId + ?Sized> AsRef<str> for IdEncoder<T> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<T: ResourceId + ?Sized> fmt::Display for IdEncoder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<T: ResourceId + ?Sized> fmt::Debug for IdEncoder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
@@ -10,14 +10,14 @@
 
 //! Restate uses many identifiers to uniquely identify its services and entities.
 
-use std::fmt::{self, Display, Formatter};
+use std::fmt::{self, Display, Formatter, Write};
 use std::hash::Hash;
 use std::mem::size_of;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use base64::Engine;
-use bytes::Bytes;
+use bytes::{BufMut, Bytes};
 use bytestring::ByteString;
 use generic_array::ArrayLength;
 use rand::RngCore;
@@ -184,6 +184,18 @@ pub trait ResourceId {
 
     /// Adds the various fields of this resource ID into the pre-initialized encoder
     fn push_to_encoder(&self, encoder: &mut IdEncoder<Self>);
+
+    /// Encodes this resource ID in its string representation into a stack buffer, without
+    /// allocating. Hot paths logging the same ID repeatedly can encode it once and reuse the
+    /// result instead of paying the base62 encoding on every `Display`.
+    fn to_encoded_str(&self) -> IdEncoder<Self>
+    where
+        Self: Sized,
+    {
+        let mut encoder = IdEncoder::new();
+        self.push_to_encoder(&mut encoder);
+        encoder
+    }
 }
 
 /// Discriminator for invocation instances
@@ -401,7 +413,10 @@ impl WithPartitionKey for ServiceId {
 
 impl Display for ServiceId {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}/{}", self.service_name, self.key)
+        // avoid the formatting machinery, service ids are logged on hot paths
+        f.write_str(&self.service_name)?;
+        f.write_char('/')?;
+        f.write_str(&self.key)
     }
 }
 
@@ -490,6 +505,13 @@ impl InvocationId {
         pk.len() + uuid.len()
     }
 
+    /// Appends the binary encoding of this id, as returned by [`InvocationId::to_bytes`], to
+    /// the given buffer.
+    pub fn put_raw_bytes(&self, buf: &mut impl BufMut) {
+        buf.put_u64(self.partition_key);
+        buf.put_slice(&self.inner.to_bytes());
+    }
+
     /// Generate random seed to feed RNG in SDKs.
     pub fn to_random_seed(&self) -> u64 {
         use std::hash::{DefaultHasher, Hash, Hasher};
@@ -576,9 +598,7 @@ impl Display for InvocationId {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         // encode the id such that it is possible to do a string prefix search for a
         // partition key using the first 17 characters.
-        let mut encoder = IdEncoder::new();
-        self.push_to_encoder(&mut encoder);
-        f.write_str(encoder.as_str())
+        f.write_str(self.to_encoded_str().as_str())
     }
 }
 
@@ -1335,6 +1355,19 @@ mod tests {
         )
     }
 
+    #[test]
+    fn encoded_invocation_id() {
+        let invocation_id = InvocationId::mock_random();
+        assert_eq!(
+            invocation_id.to_string(),
+            invocation_id.to_encoded_str().as_str()
+        );
+
+        let mut buf = bytes::BytesMut::new();
+        invocation_id.put_raw_bytes(&mut buf);
+        assert_eq!(&invocation_id.to_bytes()[..], &buf[..]);
+    }
+
     #[test]
     fn invocation_codec_capacity() {
         assert_eq!(38, InvocationId::str_encoded_len())
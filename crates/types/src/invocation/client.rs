@@ -8,34 +8,43 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::errors::InvocationError;
-use crate::identifiers::{DeploymentId, InvocationId, PartitionProcessorRpcRequestId};
+use crate::errors::{ErrorClass, InvocationError};
+use crate::identifiers::{DeploymentId, InvocationId, PartitionId, PartitionProcessorRpcRequestId};
 use crate::invocation::{InvocationQuery, InvocationRequest, InvocationResponse, InvocationTarget};
 use crate::journal::EntryIndex;
 use crate::journal_v2::Signal;
+use crate::logs::Lsn;
 use crate::time::MillisSinceEpoch;
 use bytes::Bytes;
+use std::fmt;
 use std::ops::RangeInclusive;
+use std::str::FromStr;
 use std::sync::Arc;
 
 #[derive(Debug, thiserror::Error)]
 #[error("{inner}")]
 pub struct InvocationClientError {
-    is_safe_to_retry: bool,
+    class: ErrorClass,
     #[source]
     inner: anyhow::Error,
 }
 
 impl InvocationClientError {
-    pub fn new(inner: impl Into<anyhow::Error>, is_safe_to_retry: bool) -> Self {
+    pub fn new(inner: impl Into<anyhow::Error>, class: ErrorClass) -> Self {
         Self {
-            is_safe_to_retry,
+            class,
             inner: inner.into(),
         }
     }
 
+    pub fn error_class(&self) -> ErrorClass {
+        self.class
+    }
+
+    /// Returns true when the operation can be retried assuming no state mutation could have
+    /// occurred in the partition processor.
     pub fn is_safe_to_retry(&self) -> bool {
-        self.is_safe_to_retry
+        self.class == ErrorClass::Transient
     }
 
     pub fn into_inner(self) -> anyhow::Error {
@@ -43,6 +52,57 @@ impl InvocationClientError {
     }
 }
 
+/// Token returned on writes, identifying the position of the partition log the partition
+/// processor must have applied to observe the write.
+///
+/// Passing the token to subsequent reads gives read-your-writes semantics, as the partition
+/// processor will wait to catch up with the token before serving the read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ConsistencyToken {
+    pub partition_id: PartitionId,
+    pub applied_lsn: Lsn,
+}
+
+impl ConsistencyToken {
+    pub fn new(partition_id: PartitionId, applied_lsn: Lsn) -> Self {
+        Self {
+            partition_id,
+            applied_lsn,
+        }
+    }
+}
+
+impl fmt::Display for ConsistencyToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.partition_id, self.applied_lsn)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("bad consistency token '{0}'")]
+pub struct ConsistencyTokenParseError(String);
+
+impl FromStr for ConsistencyToken {
+    type Err = ConsistencyTokenParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (partition_id, applied_lsn) = s
+            .split_once('-')
+            .ok_or_else(|| ConsistencyTokenParseError(s.to_owned()))?;
+        let partition_id = partition_id
+            .parse::<u16>()
+            .map_err(|_| ConsistencyTokenParseError(s.to_owned()))?;
+        let applied_lsn = applied_lsn
+            .parse::<u64>()
+            .map_err(|_| ConsistencyTokenParseError(s.to_owned()))?;
+
+        Ok(Self::new(
+            PartitionId::from(partition_id),
+            Lsn::from(applied_lsn),
+        ))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct SubmittedInvocationNotification {
     pub request_id: PartitionProcessorRpcRequestId,
@@ -50,6 +110,9 @@ pub struct SubmittedInvocationNotification {
     /// If true, this request_id created a "fresh invocation",
     /// otherwise the invocation was previously submitted.
     pub is_new_invocation: bool,
+    /// Token to read the invocation after it was submitted. Not set by older partition processors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consistency_token: Option<ConsistencyToken>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -199,17 +262,25 @@ pub trait InvocationClient {
     ) -> impl Future<Output = Result<InvocationOutput, InvocationClientError>> + Send;
 
     /// Attach to an existing invocation and wait for its output.
+    ///
+    /// If a [`ConsistencyToken`] is provided, the partition processor serves the request only
+    /// after having caught up with it.
     fn attach_invocation(
         &self,
         request_id: PartitionProcessorRpcRequestId,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> impl Future<Output = Result<AttachInvocationResponse, InvocationClientError>> + Send;
 
     /// Get an invocation output, when present.
+    ///
+    /// If a [`ConsistencyToken`] is provided, the partition processor serves the request only
+    /// after having caught up with it.
     fn get_invocation_output(
         &self,
         request_id: PartitionProcessorRpcRequestId,
         invocation_query: InvocationQuery,
+        consistency_token: Option<ConsistencyToken>,
     ) -> impl Future<Output = Result<GetInvocationOutputResponse, InvocationClientError>> + Send;
 
     /// **DEPRECATED** Append [`InvocationResponse`] to an existing invocation journal. Only ServiceProtocol <= 3
@@ -279,3 +350,21 @@ pub trait InvocationClient {
         invocation_id: InvocationId,
     ) -> impl Future<Output = Result<PauseInvocationResponse, InvocationClientError>> + Send;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consistency_token_roundtrip() {
+        let token = ConsistencyToken::new(PartitionId::from(3), Lsn::from(42));
+        assert_eq!(token.to_string(), "3-42");
+        assert_eq!(
+            token.to_string().parse::<ConsistencyToken>().unwrap(),
+            token
+        );
+
+        assert!("3".parse::<ConsistencyToken>().is_err());
+        assert!("3-abc".parse::<ConsistencyToken>().is_err());
+    }
+}
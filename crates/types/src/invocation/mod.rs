@@ -368,6 +368,20 @@ impl InvocationRequestHeader {
     pub fn journal_retention_duration(&self) -> Duration {
         self.journal_retention_duration
     }
+
+    /// Sets the deadline of the invocation, replacing the one in the headers if any.
+    pub fn with_deadline(&mut self, deadline: MillisSinceEpoch) {
+        self.headers
+            .retain(|h| !h.name.eq_ignore_ascii_case(DEADLINE_HEADER_NAME));
+        self.headers.push(Header::new(
+            DEADLINE_HEADER_NAME,
+            deadline.as_u64().to_string(),
+        ));
+    }
+
+    pub fn deadline(&self) -> Option<MillisSinceEpoch> {
+        deadline_from_headers(&self.headers)
+    }
 }
 
 impl WithInvocationId for InvocationRequestHeader {
@@ -510,6 +524,11 @@ impl ServiceInvocation {
         self.completion_retention_duration = invocation_retention.completion_retention;
         self.journal_retention_duration = invocation_retention.journal_retention;
     }
+
+    /// Time after which the invocation is failed, if it didn't complete yet.
+    pub fn deadline(&self) -> Option<MillisSinceEpoch> {
+        deadline_from_headers(&self.headers)
+    }
 }
 
 impl WithPartitionKey for ServiceInvocation {
@@ -877,6 +896,20 @@ impl Header {
     }
 }
 
+/// Header carrying the deadline of an invocation, in milliseconds since the unix epoch.
+///
+/// Being a request header, it's delivered to the service handler too, which can use it to adapt
+/// its work to the time left.
+pub const DEADLINE_HEADER_NAME: &str = "x-restate-deadline";
+
+fn deadline_from_headers(headers: &[Header]) -> Option<MillisSinceEpoch> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(DEADLINE_HEADER_NAME))
+        .and_then(|h| h.value.parse::<u64>().ok())
+        .map(MillisSinceEpoch::new)
+}
+
 impl fmt::Display for Header {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}: {}", self.name, self.value)
@@ -1339,6 +1372,26 @@ impl WithInvocationId for NotifySignalRequest {
     }
 }
 
+/// Represents a request to manually complete a pending journal entry of an invocation.
+///
+/// The partition processor applies it only if the invocation is still at the given epoch,
+/// and the entry is still waiting for the given completion.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CompletePendingEntryRequest {
+    pub invocation_id: InvocationId,
+    pub invocation_epoch: InvocationEpoch,
+    pub entry_index: EntryIndex,
+    /// For journals of service protocol <= 3, this is the entry index.
+    pub completion_id: CompletionId,
+    pub result: ResponseResult,
+}
+
+impl WithInvocationId for CompletePendingEntryRequest {
+    fn invocation_id(&self) -> InvocationId {
+        self.invocation_id
+    }
+}
+
 /// The invocation epoch represents the restarts count of the invocation, as seen from the Partition processor.
 pub type InvocationEpoch = u32;
 
@@ -1735,6 +1788,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deadline_header_is_replaced() {
+        let mut header = InvocationRequestHeader::initialize(
+            InvocationId::mock_random(),
+            InvocationTarget::mock_service(),
+        );
+        header.with_headers(vec![
+            Header::new("X-Restate-Deadline", "1"),
+            Header::new("content-type", "application/json"),
+        ]);
+        assert_eq!(header.deadline(), Some(MillisSinceEpoch::new(1)));
+
+        header.with_deadline(MillisSinceEpoch::new(1_700_000_000_000));
+        assert_eq!(header.headers.len(), 2);
+        assert_eq!(
+            header.deadline(),
+            Some(MillisSinceEpoch::new(1_700_000_000_000))
+        );
+
+        let invocation = ServiceInvocation::from_request(
+            InvocationRequest::new(header, Bytes::new()),
+            Source::Internal,
+        );
+        assert_eq!(
+            invocation.deadline(),
+            Some(MillisSinceEpoch::new(1_700_000_000_000))
+        );
+    }
+
     mod invocation_response {
         use super::*;
 
@@ -60,6 +60,13 @@ impl RawEntry {
     pub fn decode<D: Decoder, T: TryFromEntry>(&self) -> Result<T, RawEntryError> {
         Ok(<T as TryFromEntry>::try_from(D::decode_entry(self)?)?)
     }
+
+    pub fn serialized_content_mut(&mut self) -> &mut Bytes {
+        match self {
+            RawEntry::Command(cmd) => &mut cmd.serialized_content,
+            RawEntry::Notification(notification) => &mut notification.serialized_content,
+        }
+    }
 }
 
 // -- Raw command
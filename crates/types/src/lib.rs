@@ -18,6 +18,7 @@ mod restate_version;
 mod version;
 
 pub mod art;
+pub mod chaos;
 pub mod cluster;
 
 pub mod cluster_state;
@@ -44,6 +45,7 @@ pub mod net;
 pub mod nodes_config;
 pub mod partition_table;
 pub mod partitions;
+pub mod payload_encryption;
 pub mod protobuf;
 pub mod rate;
 pub mod replicated_loglet;
@@ -415,6 +415,22 @@ impl<P: ListenerPort> Listeners<P> {
             _phantom: std::marker::PhantomData,
         })
     }
+
+    pub async fn new_tcp_listener(socket_addr: SocketAddr) -> Result<Self, ListenError> {
+        let tcp_listener =
+            TcpListener::bind(socket_addr)
+                .await
+                .map_err(|err| ListenError::TcpBinding {
+                    service_name: P::NAME.to_owned(),
+                    address: socket_addr,
+                    source: err,
+                })?;
+        Ok(Self {
+            tcp_listener: Some(tcp_listener),
+            unix_listener: None,
+            _phantom: std::marker::PhantomData,
+        })
+    }
 }
 
 impl<P: ListenerPort> Drop for Listeners<P> {
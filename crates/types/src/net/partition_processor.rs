@@ -22,6 +22,7 @@ use crate::invocation::client::{
 };
 use crate::invocation::{InvocationQuery, InvocationRequest, InvocationResponse};
 use crate::journal_v2::Signal;
+use crate::logs::Lsn;
 use crate::net::ServiceTag;
 use crate::net::{default_wire_codec, define_rpc, define_service};
 use serde::{Deserialize, Serialize};
@@ -47,6 +48,10 @@ pub struct PartitionProcessorRpcRequest {
     pub request_id: PartitionProcessorRpcRequestId,
     pub partition_id: PartitionId,
     pub inner: PartitionProcessorRpcRequestInner,
+    /// If set, the partition processor serves the request only after having applied the log
+    /// at least up to this lsn. Used to give read-your-writes semantics to queries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_applied_lsn: Option<Lsn>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,6 +9,10 @@
 // by the Apache License, Version 2.0.
 
 mod configuration;
+pub mod outbox_consistency;
+pub mod parked_commands;
+pub mod recovery;
+pub mod settings;
 pub mod state;
 
 use crate::PlainNodeId;
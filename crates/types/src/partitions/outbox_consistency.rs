@@ -0,0 +1,141 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Registry of the outbox consistency checks of the partition leaders running on this node, see
+//! `worker.outbox-consistency-check-interval`.
+//!
+//! Partitions send messages to each other through their outbox. Each message gets the next outbox
+//! sequence number of the producing partition, and the downstream partitions remember the last
+//! sequence number they received from each producer in their dedup table, to drop the messages
+//! the shuffle sends more than once. The check verifies that these sequence numbers never got out
+//! of sync, which would make the partitions silently lose messages.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use parking_lot::Mutex;
+
+use crate::identifiers::{LeaderEpoch, PartitionId};
+use crate::message::MessageIndex;
+use crate::time::MillisSinceEpoch;
+
+/// Inconsistency between the outbox of a partition and the dedup tables of its downstream
+/// partitions.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum OutboxInconsistency {
+    /// Outbox messages between the outbox head and the next outbox sequence number are missing,
+    /// hence they will never be delivered.
+    #[serde(rename_all = "kebab-case")]
+    MissingOutboxMessages {
+        from_seq_number: MessageIndex,
+        to_seq_number: MessageIndex,
+    },
+    /// The dedup table of a downstream partition already contains a sequence number the outbox
+    /// has not assigned yet, hence the downstream partition will drop the next messages as
+    /// duplicates.
+    #[serde(rename_all = "kebab-case")]
+    DedupAheadOfOutbox {
+        consumer: PartitionId,
+        dedup_seq_number: MessageIndex,
+    },
+}
+
+/// Outcome of the last outbox consistency check of a partition leader.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OutboxConsistencyReport {
+    pub leader_epoch: LeaderEpoch,
+    pub checked_at: MillisSinceEpoch,
+    /// Sequence number of the oldest message not shuffled yet, if any
+    pub outbox_head_seq_number: Option<MessageIndex>,
+    /// Sequence number the next outbox message will get
+    pub outbox_seq_number: MessageIndex,
+    /// Partitions running on this node whose dedup table was compared with the outbox
+    pub checked_consumers: Vec<PartitionId>,
+    pub inconsistencies: Vec<OutboxInconsistency>,
+}
+
+impl OutboxConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+static REPORTS: LazyLock<Mutex<BTreeMap<PartitionId, OutboxConsistencyReport>>> =
+    LazyLock::new(Default::default);
+
+pub fn update(partition_id: PartitionId, report: OutboxConsistencyReport) {
+    REPORTS.lock().insert(partition_id, report);
+}
+
+/// Forgets the report of a partition, once its processor stopped.
+pub fn remove(partition_id: PartitionId) {
+    REPORTS.lock().remove(&partition_id);
+}
+
+pub fn report(partition_id: PartitionId) -> Option<OutboxConsistencyReport> {
+    REPORTS.lock().get(&partition_id).cloned()
+}
+
+/// Last reports of the partition leaders running on this node.
+pub fn all() -> BTreeMap<PartitionId, OutboxConsistencyReport> {
+    REPORTS.lock().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_json() {
+        let report = OutboxConsistencyReport {
+            leader_epoch: LeaderEpoch::from(3),
+            checked_at: MillisSinceEpoch::new(1_700_000_000_000),
+            outbox_head_seq_number: Some(10),
+            outbox_seq_number: 15,
+            checked_consumers: vec![PartitionId::from(0), PartitionId::from(1)],
+            inconsistencies: vec![
+                OutboxInconsistency::MissingOutboxMessages {
+                    from_seq_number: 11,
+                    to_seq_number: 12,
+                },
+                OutboxInconsistency::DedupAheadOfOutbox {
+                    consumer: PartitionId::from(1),
+                    dedup_seq_number: 20,
+                },
+            ],
+        };
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["inconsistencies"],
+            serde_json::json!([
+                {
+                    "kind": "missing-outbox-messages",
+                    "from-seq-number": 11,
+                    "to-seq-number": 12
+                },
+                {
+                    "kind": "dedup-ahead-of-outbox",
+                    "consumer": 1,
+                    "dedup-seq-number": 20
+                }
+            ])
+        );
+        assert!(!report.is_consistent());
+
+        let partition_id = PartitionId::from(4244);
+        update(partition_id, report.clone());
+        assert_eq!(super::report(partition_id), Some(report));
+        remove(partition_id);
+        assert!(super::report(partition_id).is_none());
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Registry of the partition processors running on this node, to inspect and retry the commands
+//! they parked after repeatedly failing to apply them.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use parking_lot::Mutex;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::identifiers::PartitionId;
+use crate::logs::Lsn;
+use crate::time::MillisSinceEpoch;
+
+/// A command parked by a partition processor.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ParkedCommandInfo {
+    pub lsn: Lsn,
+    pub command: String,
+    pub parked_at: MillisSinceEpoch,
+    /// Failed attempts to apply the command before it got parked
+    pub attempts: u32,
+    /// Last failure observed while applying the command
+    pub failure: String,
+}
+
+/// Request served by a partition processor running on this node.
+#[derive(Debug)]
+pub enum ParkedCommandsRequest {
+    /// Lists the first `limit` parked commands, ordered by lsn
+    List {
+        limit: usize,
+        reply: oneshot::Sender<Result<Vec<ParkedCommandInfo>, String>>,
+    },
+    /// Proposes to apply the parked command again. Replies `false` if there's no parked command
+    /// with the given lsn. Only the leader of the partition can serve it.
+    Retry {
+        lsn: Lsn,
+        reply: oneshot::Sender<Result<bool, String>>,
+    },
+}
+
+static PARTITION_PROCESSORS: LazyLock<
+    Mutex<BTreeMap<PartitionId, mpsc::Sender<ParkedCommandsRequest>>>,
+> = LazyLock::new(Default::default);
+
+/// Registers a partition processor running on this node, returning the requests it needs to serve.
+pub fn register_partition_processor(
+    partition_id: PartitionId,
+) -> mpsc::Receiver<ParkedCommandsRequest> {
+    let (tx, rx) = mpsc::channel(16);
+    PARTITION_PROCESSORS.lock().insert(partition_id, tx);
+    rx
+}
+
+pub fn deregister_partition_processor(partition_id: PartitionId) {
+    PARTITION_PROCESSORS.lock().remove(&partition_id);
+}
+
+/// Partition processor of the given partition, if it runs on this node.
+pub fn partition_processor(
+    partition_id: PartitionId,
+) -> Option<mpsc::Sender<ParkedCommandsRequest>> {
+    PARTITION_PROCESSORS.lock().get(&partition_id).cloned()
+}
@@ -0,0 +1,192 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Registry of the recovery progress of the partition processors starting on this node, from
+//! opening their partition store until they caught up with the tail of their log.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use parking_lot::Mutex;
+
+use crate::identifiers::PartitionId;
+use crate::time::MillisSinceEpoch;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, strum::Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum RecoveryPhase {
+    /// Opening the partition store, which includes restoring it from a snapshot if needed
+    OpeningStore,
+    /// Running the migrations of the partition store
+    Migrating,
+    /// Finding the tail of the log, to know how many records need to be replayed
+    FindingLogTail,
+    /// Replaying the records appended to the log since the last applied lsn
+    Replaying,
+    /// Caught up with the tail of the log
+    Recovered,
+}
+
+/// Recovery progress of a partition processor.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RecoveryProgress {
+    pub phase: RecoveryPhase,
+    pub started_at: MillisSinceEpoch,
+    pub phase_started_at: MillisSinceEpoch,
+    /// Log records to replay, known once the tail of the log was found. This includes the records
+    /// of the other partitions sharing the same log, which are skipped.
+    pub records_to_replay: u64,
+    pub replayed_records: u64,
+}
+
+impl RecoveryProgress {
+    fn new(now: MillisSinceEpoch) -> Self {
+        Self {
+            phase: RecoveryPhase::OpeningStore,
+            started_at: now,
+            phase_started_at: now,
+            records_to_replay: 0,
+            replayed_records: 0,
+        }
+    }
+
+    fn enter_phase(&mut self, phase: RecoveryPhase, now: MillisSinceEpoch) {
+        if self.phase != phase {
+            self.phase = phase;
+            self.phase_started_at = now;
+        }
+    }
+
+    pub fn is_recovered(&self) -> bool {
+        self.phase == RecoveryPhase::Recovered
+    }
+
+    /// Percentage of the recovery, based on the records replayed so far.
+    pub fn percentage(&self) -> f64 {
+        match self.phase {
+            RecoveryPhase::Recovered => 100.0,
+            RecoveryPhase::Replaying if self.records_to_replay > 0 => {
+                (self.replayed_records.min(self.records_to_replay) as f64 * 100.0)
+                    / self.records_to_replay as f64
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+static RECOVERIES: LazyLock<Mutex<BTreeMap<PartitionId, RecoveryProgress>>> =
+    LazyLock::new(Default::default);
+
+/// Starts tracking the recovery of a partition processor, which begins by opening its store.
+pub fn start(partition_id: PartitionId) {
+    RECOVERIES
+        .lock()
+        .insert(partition_id, RecoveryProgress::new(MillisSinceEpoch::now()));
+}
+
+pub fn enter_phase(partition_id: PartitionId, phase: RecoveryPhase) {
+    if let Some(progress) = RECOVERIES.lock().get_mut(&partition_id) {
+        progress.enter_phase(phase, MillisSinceEpoch::now());
+    }
+}
+
+/// Starts replaying the given number of log records, completing the recovery if there's none.
+pub fn start_replay(partition_id: PartitionId, records_to_replay: u64) {
+    if let Some(progress) = RECOVERIES.lock().get_mut(&partition_id) {
+        progress.records_to_replay = records_to_replay;
+        progress.replayed_records = 0;
+        progress.enter_phase(
+            if records_to_replay == 0 {
+                RecoveryPhase::Recovered
+            } else {
+                RecoveryPhase::Replaying
+            },
+            MillisSinceEpoch::now(),
+        );
+    }
+}
+
+pub fn on_replayed(partition_id: PartitionId, replayed_records: u64) {
+    if let Some(progress) = RECOVERIES.lock().get_mut(&partition_id)
+        && progress.phase == RecoveryPhase::Replaying
+    {
+        progress.replayed_records = replayed_records;
+    }
+}
+
+pub fn on_recovered(partition_id: PartitionId) {
+    if let Some(progress) = RECOVERIES.lock().get_mut(&partition_id) {
+        progress.replayed_records = progress.records_to_replay;
+        progress.enter_phase(RecoveryPhase::Recovered, MillisSinceEpoch::now());
+    }
+}
+
+/// Stops tracking the partition processor, once it stopped.
+pub fn remove(partition_id: PartitionId) {
+    RECOVERIES.lock().remove(&partition_id);
+}
+
+pub fn progress(partition_id: PartitionId) -> Option<RecoveryProgress> {
+    RECOVERIES.lock().get(&partition_id).cloned()
+}
+
+/// Recovery progress of all the partition processors running on this node.
+pub fn all() -> BTreeMap<PartitionId, RecoveryProgress> {
+    RECOVERIES.lock().clone()
+}
+
+/// Whether any of the partition processors running on this node is still recovering.
+pub fn is_recovering() -> bool {
+    RECOVERIES
+        .lock()
+        .values()
+        .any(|progress| !progress.is_recovered())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentage_follows_replayed_records() {
+        let partition_id = PartitionId::from(4242);
+        start(partition_id);
+        assert_eq!(progress(partition_id).unwrap().percentage(), 0.0);
+
+        enter_phase(partition_id, RecoveryPhase::FindingLogTail);
+        start_replay(partition_id, 200);
+        on_replayed(partition_id, 50);
+        let replaying = progress(partition_id).unwrap();
+        assert_eq!(replaying.phase, RecoveryPhase::Replaying);
+        assert_eq!(replaying.percentage(), 25.0);
+
+        on_recovered(partition_id);
+        let recovered = progress(partition_id).unwrap();
+        assert!(recovered.is_recovered());
+        assert_eq!(recovered.replayed_records, 200);
+        assert_eq!(recovered.percentage(), 100.0);
+
+        remove(partition_id);
+        assert!(progress(partition_id).is_none());
+    }
+
+    #[test]
+    fn nothing_to_replay_completes_the_recovery() {
+        let partition_id = PartitionId::from(4243);
+        start(partition_id);
+        start_replay(partition_id, 0);
+        assert!(progress(partition_id).unwrap().is_recovered());
+        remove(partition_id);
+    }
+}
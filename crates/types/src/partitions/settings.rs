@@ -0,0 +1,99 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::num::{NonZeroU32, NonZeroUsize};
+
+use crate::config::Configuration;
+use crate::flexbuffers_storage_encode_decode;
+
+/// Settings which affect the outcome of applying the commands of a partition.
+///
+/// Unlike the rest of the configuration, they are part of the replicated state of the partition,
+/// so that all the replicas apply the commands with the same settings regardless of their local
+/// configuration. The leader proposes the settings of its configuration whenever they differ from
+/// the applied ones, see [`PartitionSettings::from_configuration`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PartitionSettings {
+    /// See `worker.state-size-quota`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_size_quota: Option<NonZeroUsize>,
+    /// See `invocation.default-max-journal-length`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_max_journal_length: Option<NonZeroU32>,
+    /// Whether to store the completion events, see `worker.completion-kafka-sink`.
+    #[serde(default)]
+    pub store_completion_events: bool,
+}
+
+flexbuffers_storage_encode_decode!(PartitionSettings);
+
+impl PartitionSettings {
+    /// Settings of the given configuration, to replace the `applied` ones.
+    ///
+    /// Storing the completion events is only disabled explicitly, by
+    /// `worker.discard-completion-events`, as the stored events which were not delivered yet are
+    /// dropped. A leader without the completion sink keeps storing them otherwise.
+    pub fn from_configuration(config: &Configuration, applied: &PartitionSettings) -> Self {
+        Self {
+            state_size_quota: config.worker.state_size_quota,
+            default_max_journal_length: config.invocation.default_max_journal_length,
+            store_completion_events: config.worker.completion_kafka_sink.is_some()
+                || (applied.store_completion_events && !config.worker.discard_completion_events),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_time_util::NonZeroFriendlyDuration;
+
+    use crate::config::CompletionKafkaSinkOptions;
+
+    #[test]
+    fn store_completion_events_until_discarded() {
+        let disabled = PartitionSettings::default();
+        let enabled = PartitionSettings {
+            store_completion_events: true,
+            ..PartitionSettings::default()
+        };
+
+        let mut config = Configuration::default();
+        assert_eq!(
+            PartitionSettings::from_configuration(&config, &disabled),
+            disabled
+        );
+        // a leader without the sink keeps storing the events
+        assert_eq!(
+            PartitionSettings::from_configuration(&config, &enabled),
+            enabled
+        );
+
+        config.worker.discard_completion_events = true;
+        assert_eq!(
+            PartitionSettings::from_configuration(&config, &enabled),
+            disabled
+        );
+
+        config.worker.completion_kafka_sink = Some(CompletionKafkaSinkOptions {
+            cluster: "my-cluster".to_owned(),
+            topic: "completions".to_owned(),
+            progress_topic: "completions-progress".to_owned(),
+            transactional_id_prefix: "restate".to_owned(),
+            flush_interval: NonZeroFriendlyDuration::from_millis_unchecked(100),
+            max_batch_size: NonZeroUsize::new(100).unwrap(),
+        });
+        assert_eq!(
+            PartitionSettings::from_configuration(&config, &disabled),
+            enabled
+        );
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Envelope encryption of the journal entries and the state values stored in the partition
+//! store, see `worker.payload-encryption`.
+//!
+//! The state machine encrypts the payloads of the enabled services before storing them, and the
+//! partition store decrypts them when reading them. Encrypted payloads are self-describing:
+//!
+//! ```text
+//! MAGIC (4 bytes) | VERSION (1 byte) | KEY ID LENGTH (1 byte) | KEY ID | NONCE (12 bytes) | CIPHERTEXT + TAG
+//! ```
+//!
+//! The header up to the key id is authenticated as well. Recording the key id lets keys be
+//! rotated: new payloads are encrypted with the active key, while the payloads encrypted with
+//! the previous keys stay readable as long as the [`KeyProvider`] knows them.
+//!
+//! Only the journal entries of service protocol V4 and later, the input entries and the state
+//! values are encrypted. The other journal entries and the completions of the invocations on
+//! service protocol V3 and earlier, and the payloads of the invocation status (input of the scheduled and inboxed invocations,
+//! output of the completed invocations) are stored in plaintext.
+//!
+//! Payloads which are not encrypted are stored as they are, unless they start with the magic
+//! bytes. Those are framed with the plaintext version instead, see [`frame_plaintext`], so that
+//! every stored payload starting with the magic bytes is a versioned envelope:
+//!
+//! ```text
+//! MAGIC (4 bytes) | PLAINTEXT VERSION (1 byte) | PAYLOAD
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arc_swap::ArcSwapOption;
+use base64::Engine;
+use bytes::{BufMut, Bytes, BytesMut};
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::config::PayloadEncryptionOptions;
+
+const MAGIC: &[u8; 4] = b"\xffRPE";
+const PLAINTEXT_VERSION: u8 = 0;
+const VERSION: u8 = 1;
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("unknown payload encryption key '{0}'")]
+    UnknownKey(String),
+    #[error("cannot decrypt the payload, payload encryption is not configured on this node")]
+    NotConfigured,
+    #[error("malformed encrypted payload")]
+    Malformed,
+    #[error(
+        "cannot decrypt the payload with the key '{0}', either the key or the payload is corrupted"
+    )]
+    Decrypt(String),
+    #[error("cannot encrypt the payload")]
+    Encrypt,
+    #[error("bad payload encryption key '{key_id}': {reason}")]
+    BadKey { key_id: String, reason: String },
+}
+
+/// 256-bit AES-GCM key.
+pub struct EncryptionKey(LessSafeKey);
+
+impl EncryptionKey {
+    pub fn new(key: &[u8; KEY_LEN]) -> Self {
+        Self(LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, key).expect("AES-256-GCM keys are 32 bytes long"),
+        ))
+    }
+}
+
+/// Source of the payload encryption keys. The keys are loaded from files by default, see
+/// [`FileKeyProvider`], other providers can fetch them from a key management service.
+pub trait KeyProvider: Send + Sync + 'static {
+    /// Id of the key encrypting the new payloads, at most 255 bytes long.
+    fn active_key_id(&self) -> &str;
+
+    /// Key with the given id, to encrypt or decrypt payloads.
+    fn key(&self, key_id: &str) -> Option<Arc<EncryptionKey>>;
+}
+
+/// Provider of the keys configured in `worker.payload-encryption.keys`.
+pub struct FileKeyProvider {
+    active_key_id: String,
+    keys: HashMap<String, Arc<EncryptionKey>>,
+}
+
+impl FileKeyProvider {
+    /// Loads the keys, each file containing the base64 encoding of a 256-bit key.
+    pub fn load(
+        active_key_id: String,
+        key_files: &HashMap<String, PathBuf>,
+    ) -> Result<Self, EncryptionError> {
+        let mut keys = HashMap::with_capacity(key_files.len());
+        for (key_id, path) in key_files {
+            let bad_key = |reason: String| EncryptionError::BadKey {
+                key_id: key_id.clone(),
+                reason,
+            };
+            if key_id.len() > u8::MAX as usize {
+                return Err(bad_key("the id is longer than 255 bytes".to_owned()));
+            }
+            let encoded = std::fs::read_to_string(path)
+                .map_err(|err| bad_key(format!("cannot read {}: {err}", path.display())))?;
+            let key: [u8; KEY_LEN] = base64::prelude::BASE64_STANDARD
+                .decode(encoded.trim())
+                .map_err(|err| bad_key(format!("invalid base64: {err}")))?
+                .try_into()
+                .map_err(|_| bad_key("the key must be 32 bytes long".to_owned()))?;
+            keys.insert(key_id.clone(), Arc::new(EncryptionKey::new(&key)));
+        }
+
+        if !keys.contains_key(&active_key_id) {
+            return Err(EncryptionError::UnknownKey(active_key_id));
+        }
+        Ok(Self {
+            active_key_id,
+            keys,
+        })
+    }
+}
+
+impl KeyProvider for FileKeyProvider {
+    fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    fn key(&self, key_id: &str) -> Option<Arc<EncryptionKey>> {
+        self.keys.get(key_id).cloned()
+    }
+}
+
+pub struct PayloadEncryption {
+    key_provider: Arc<dyn KeyProvider>,
+    /// Services whose payloads are encrypted, all of them if empty
+    services: HashSet<String>,
+    rng: SystemRandom,
+}
+
+impl PayloadEncryption {
+    pub fn new(
+        key_provider: Arc<dyn KeyProvider>,
+        services: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            key_provider,
+            services: services.into_iter().collect(),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    pub fn from_options(options: &PayloadEncryptionOptions) -> Result<Self, EncryptionError> {
+        let key_provider = FileKeyProvider::load(options.active_key_id.clone(), &options.keys)?;
+        Ok(Self::new(
+            Arc::new(key_provider),
+            options.services.iter().cloned(),
+        ))
+    }
+
+    pub fn is_enabled_for(&self, service_name: &str) -> bool {
+        self.services.is_empty() || self.services.contains(service_name)
+    }
+
+    /// Encrypts the payload with the active key.
+    pub fn encrypt(&self, payload: &[u8]) -> Result<Bytes, EncryptionError> {
+        let key_id = self.key_provider.active_key_id();
+        let key = self
+            .key_provider
+            .key(key_id)
+            .ok_or_else(|| EncryptionError::UnknownKey(key_id.to_owned()))?;
+
+        let mut nonce = [0; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| EncryptionError::Encrypt)?;
+
+        let mut buf = BytesMut::with_capacity(
+            MAGIC.len() + 2 + key_id.len() + NONCE_LEN + payload.len() + AES_256_GCM.tag_len(),
+        );
+        buf.put_slice(MAGIC);
+        buf.put_u8(VERSION);
+        buf.put_u8(key_id.len() as u8);
+        buf.put_slice(key_id.as_bytes());
+        let header_len = buf.len();
+        buf.put_slice(&nonce);
+
+        let mut in_out = payload.to_vec();
+        key.0
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&buf[..header_len]),
+                &mut in_out,
+            )
+            .map_err(|_| EncryptionError::Encrypt)?;
+        buf.put_slice(&in_out);
+        Ok(buf.freeze())
+    }
+
+    /// Decrypts the payload, if it's encrypted.
+    pub fn decrypt(&self, payload: Bytes) -> Result<Bytes, EncryptionError> {
+        match version(&payload)? {
+            None => return Ok(payload),
+            Some(PLAINTEXT_VERSION) => return Ok(payload.slice(MAGIC.len() + 1..)),
+            Some(VERSION) => {}
+            Some(_) => return Err(EncryptionError::Malformed),
+        }
+
+        let key_id_offset = MAGIC.len() + 2;
+        let &key_id_len = payload
+            .get(MAGIC.len() + 1)
+            .ok_or(EncryptionError::Malformed)?;
+        let nonce_offset = key_id_offset + key_id_len as usize;
+        if payload.len() < nonce_offset + NONCE_LEN + AES_256_GCM.tag_len() {
+            return Err(EncryptionError::Malformed);
+        }
+        let key_id = std::str::from_utf8(&payload[key_id_offset..nonce_offset])
+            .map_err(|_| EncryptionError::Malformed)?;
+        let key = self
+            .key_provider
+            .key(key_id)
+            .ok_or_else(|| EncryptionError::UnknownKey(key_id.to_owned()))?;
+
+        let nonce =
+            Nonce::try_assume_unique_for_key(&payload[nonce_offset..nonce_offset + NONCE_LEN])
+                .map_err(|_| EncryptionError::Malformed)?;
+        let mut in_out = payload[nonce_offset + NONCE_LEN..].to_vec();
+        let plaintext_len = key
+            .0
+            .open_in_place(nonce, Aad::from(&payload[..nonce_offset]), &mut in_out)
+            .map_err(|_| EncryptionError::Decrypt(key_id.to_owned()))?
+            .len();
+        in_out.truncate(plaintext_len);
+        Ok(in_out.into())
+    }
+}
+
+static PAYLOAD_ENCRYPTION: ArcSwapOption<PayloadEncryption> = ArcSwapOption::const_empty();
+
+/// Sets the payload encryption of this node, `None` disables it.
+pub fn set_payload_encryption(payload_encryption: Option<PayloadEncryption>) {
+    PAYLOAD_ENCRYPTION.store(payload_encryption.map(Arc::new));
+}
+
+/// Payload encryption of this node, if configured.
+pub fn payload_encryption() -> Option<Arc<PayloadEncryption>> {
+    PAYLOAD_ENCRYPTION.load_full()
+}
+
+/// Version of the envelope of the payload, `None` if it's stored as it is.
+fn version(payload: &[u8]) -> Result<Option<u8>, EncryptionError> {
+    if !payload.starts_with(MAGIC) {
+        return Ok(None);
+    }
+    payload
+        .get(MAGIC.len())
+        .copied()
+        .map(Some)
+        .ok_or(EncryptionError::Malformed)
+}
+
+/// Frames the payload which is not encrypted, if needed to tell it apart from the envelopes.
+/// Every payload stored without [`PayloadEncryption::encrypt`] must be framed.
+pub fn frame_plaintext(payload: Bytes) -> Bytes {
+    if !payload.starts_with(MAGIC) {
+        return payload;
+    }
+    let mut buf = BytesMut::with_capacity(MAGIC.len() + 1 + payload.len());
+    buf.put_slice(MAGIC);
+    buf.put_u8(PLAINTEXT_VERSION);
+    buf.put_slice(&payload);
+    buf.freeze()
+}
+
+/// Whether the stored payload is an envelope, which needs to be decoded with [`decrypt`].
+pub fn is_framed(payload: &[u8]) -> bool {
+    payload.starts_with(MAGIC)
+}
+
+/// Decrypts the payload with the payload encryption of this node, if it's encrypted, or unframes
+/// it, see [`frame_plaintext`].
+pub fn decrypt(payload: Bytes) -> Result<Bytes, EncryptionError> {
+    match version(&payload)? {
+        None => Ok(payload),
+        Some(PLAINTEXT_VERSION) => Ok(payload.slice(MAGIC.len() + 1..)),
+        Some(_) => payload_encryption()
+            .ok_or(EncryptionError::NotConfigured)?
+            .decrypt(payload),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestKeyProvider {
+        active_key_id: &'static str,
+        keys: HashMap<&'static str, Arc<EncryptionKey>>,
+    }
+
+    impl KeyProvider for TestKeyProvider {
+        fn active_key_id(&self) -> &str {
+            self.active_key_id
+        }
+
+        fn key(&self, key_id: &str) -> Option<Arc<EncryptionKey>> {
+            self.keys.get(key_id).cloned()
+        }
+    }
+
+    fn encryption(active_key_id: &'static str) -> PayloadEncryption {
+        PayloadEncryption::new(
+            Arc::new(TestKeyProvider {
+                active_key_id,
+                keys: HashMap::from([
+                    ("k1", Arc::new(EncryptionKey::new(&[1; KEY_LEN]))),
+                    ("k2", Arc::new(EncryptionKey::new(&[2; KEY_LEN]))),
+                ]),
+            }),
+            ["Greeter".to_owned()],
+        )
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let encryption = encryption("k1");
+        assert!(encryption.is_enabled_for("Greeter"));
+        assert!(!encryption.is_enabled_for("Counter"));
+
+        let payload = Bytes::from_static(b"{\"name\":\"Francesco\"}");
+        let encrypted = encryption.encrypt(&payload).unwrap();
+        assert!(is_framed(&encrypted));
+        assert!(!encrypted.windows(payload.len()).any(|w| w == payload));
+        assert_eq!(encryption.decrypt(encrypted.clone()).unwrap(), payload);
+
+        // the same payload is encrypted with a different nonce every time
+        assert_ne!(encryption.encrypt(&payload).unwrap(), encrypted);
+
+        // plaintext payloads are returned as they are
+        assert_eq!(encryption.decrypt(payload.clone()).unwrap(), payload);
+
+        // the empty payload is supported too
+        let encrypted = encryption.encrypt(b"").unwrap();
+        assert!(encryption.decrypt(encrypted).unwrap().is_empty());
+    }
+
+    #[test]
+    fn decrypt_after_key_rotation() {
+        let encrypted = encryption("k1").encrypt(b"state").unwrap();
+        let rotated = encryption("k2");
+        assert_eq!(
+            rotated.decrypt(encrypted).unwrap(),
+            Bytes::from_static(b"state")
+        );
+
+        let encrypted = rotated.encrypt(b"state").unwrap();
+        assert_eq!(&encrypted[6..8], b"k2");
+    }
+
+    #[test]
+    fn frame_plaintext_starting_with_magic() {
+        let encryption = encryption("k1");
+
+        let payload = Bytes::from_static(b"state");
+        assert_eq!(frame_plaintext(payload.clone()), payload);
+
+        let mut payload = BytesMut::from(&MAGIC[..]);
+        payload.put_u8(VERSION);
+        payload.put_slice(b"plaintext");
+        let payload = payload.freeze();
+        let framed = frame_plaintext(payload.clone());
+        assert!(is_framed(&framed));
+        assert_eq!(encryption.decrypt(framed.clone()).unwrap(), payload);
+        assert_eq!(decrypt(framed).unwrap(), payload);
+    }
+
+    #[test]
+    fn reject_tampered_payloads() {
+        let encryption = encryption("k1");
+        let encrypted = encryption.encrypt(b"state").unwrap();
+
+        let mut tampered = encrypted.to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            encryption.decrypt(tampered.into()),
+            Err(EncryptionError::Decrypt(_))
+        ));
+
+        // the key id is authenticated
+        let mut tampered = encrypted.to_vec();
+        tampered[7] = b'2';
+        assert!(matches!(
+            encryption.decrypt(tampered.into()),
+            Err(EncryptionError::Decrypt(_))
+        ));
+
+        assert!(matches!(
+            encryption.decrypt(encrypted.slice(..10)),
+            Err(EncryptionError::Malformed)
+        ));
+    }
+
+    #[test]
+    fn load_key_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("k1");
+        std::fs::write(
+            &path,
+            format!(
+                "{}\n",
+                base64::prelude::BASE64_STANDARD.encode([7; KEY_LEN])
+            ),
+        )
+        .unwrap();
+        let key_files = HashMap::from([("k1".to_owned(), path)]);
+
+        let provider = FileKeyProvider::load("k1".to_owned(), &key_files).unwrap();
+        assert!(provider.key("k1").is_some());
+        assert!(matches!(
+            FileKeyProvider::load("k2".to_owned(), &key_files),
+            Err(EncryptionError::UnknownKey(_))
+        ));
+
+        std::fs::write(dir.path().join("k1"), "c2hvcnQ=").unwrap();
+        assert!(matches!(
+            FileKeyProvider::load("k1".to_owned(), &key_files),
+            Err(EncryptionError::BadKey { .. })
+        ));
+    }
+}
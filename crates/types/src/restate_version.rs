@@ -200,6 +200,50 @@ impl TryFrom<&RestateVersion> for SemanticRestateVersion {
     }
 }
 
+/// Compatibility information for this version.
+///
+/// It includes:
+/// * Minimum forward compatible version which can still read data written by this version
+/// * Minimum backward compatible version whose data this version can still read
+///
+/// # Important
+/// This information needs to be updated whenever we release a version that changes the
+/// compatible versions boundaries.
+pub static COMPATIBILITY_INFORMATION: CompatibilityInformation =
+    CompatibilityInformation::new(semver::Version::new(1, 4, 0), semver::Version::new(1, 0, 0));
+
+/// Compatibility information defining the minimum Restate version that can read data written by
+/// this version. Additionally, it specifies the minimum with which this version is backwards
+/// compatible.
+#[derive(Debug, Clone)]
+pub struct CompatibilityInformation {
+    /// Minimum version required to read data written by this version.
+    pub min_forward_compatible_version: semver::Version,
+    /// Minimum version from which this version can read data.
+    pub min_backward_compatible_version: semver::Version,
+}
+
+impl CompatibilityInformation {
+    pub const fn new(
+        min_forward_compatible_version: semver::Version,
+        min_backward_compatible_version: semver::Version,
+    ) -> Self {
+        Self {
+            min_forward_compatible_version,
+            min_backward_compatible_version,
+        }
+    }
+
+    /// True if the `target` version can read the data written by this version, hence it's safe
+    /// to replace this version with the `target` version.
+    pub fn is_compatible_with(&self, target: &SemanticRestateVersion) -> bool {
+        target
+            .0
+            .cmp_precedence(&self.min_forward_compatible_version)
+            != std::cmp::Ordering::Less
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,6 +289,23 @@ mod tests {
         assert_that!(version, eq(deserialized));
     }
 
+    #[test]
+    fn compatibility_information() {
+        let compatibility_information = CompatibilityInformation::new(
+            semver::Version::new(1, 4, 0),
+            semver::Version::new(1, 0, 0),
+        );
+        let is_compatible_with = |version: &str| {
+            compatibility_information
+                .is_compatible_with(&SemanticRestateVersion::parse(version).unwrap())
+        };
+
+        assert_that!(is_compatible_with("1.3.2"), eq(false));
+        assert_that!(is_compatible_with("1.4.0-dev"), eq(false));
+        assert_that!(is_compatible_with("1.4.0"), eq(true));
+        assert_that!(is_compatible_with("1.5.1"), eq(true));
+    }
+
     #[test]
     fn restate_version_newer_than() {
         // same same
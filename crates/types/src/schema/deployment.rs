@@ -48,6 +48,9 @@ pub struct Deployment {
     pub created_at: MillisSinceEpoch,
     /// User provided metadata during registration
     pub metadata: HashMap<String, String>,
+    /// Set while the deployment is draining, until when its in-flight invocations are awaited
+    /// before deleting it.
+    pub drain_deadline: Option<MillisSinceEpoch>,
     /// # Info
     ///
     /// List of configuration/deprecation information related to this deployment.
@@ -363,6 +366,7 @@ pub mod test_util {
                 created_at: MillisSinceEpoch::now(),
                 metadata: Default::default(),
                 additional_headers: Default::default(),
+                drain_deadline: None,
                 info: vec![],
             }
         }
@@ -383,6 +387,7 @@ pub mod test_util {
                 created_at: MillisSinceEpoch::now(),
                 metadata: Default::default(),
                 additional_headers: Default::default(),
+                drain_deadline: None,
                 info: vec![],
             }
         }
@@ -14,6 +14,7 @@ use crate::invocation::{
 
 use crate::identifiers::DeploymentId;
 use crate::retries::RetryIter;
+use crate::schema::service::IngressLimit;
 use bytes::Bytes;
 use bytestring::ByteString;
 use itertools::Itertools;
@@ -25,6 +26,14 @@ use std::{cmp, fmt};
 pub const DEFAULT_IDEMPOTENCY_RETENTION: Duration = Duration::from_secs(60 * 60 * 24);
 pub const DEFAULT_WORKFLOW_COMPLETION_RETENTION: Duration = Duration::from_secs(60 * 60 * 24);
 
+/// Handler metadata key marking a handler as side-effect-free when set to `true`.
+pub const SIDE_EFFECT_FREE_HANDLER_METADATA: &str = "restate.side-effect-free";
+
+/// Handler metadata key making the ingress derive an idempotency key from the request content
+/// when the caller doesn't pass one. The value is the window within which duplicate requests are
+/// deduplicated, e.g. `10m`.
+pub const DERIVE_IDEMPOTENCY_KEY_HANDLER_METADATA: &str = "restate.ingress.derive-idempotency-key";
+
 /// This API resolves invocation targets.
 ///
 /// This is used by invoker and ingress to resolve metadata required to ingest an invocation and run it.
@@ -88,12 +97,29 @@ pub struct InvocationTargetMetadata {
     pub completion_retention: Duration,
     /// Retention timer to be used for the journal. See [`InvocationTargetMetadata::compute_retention`] for more details.
     pub journal_retention: Duration,
+    /// Retention timer of the completion configured for the whole service, applying also to the
+    /// invocations without idempotency key. Zero if unset.
+    pub service_completion_retention: Duration,
 
     pub target_ty: InvocationTargetType,
     pub input_rules: InputRules,
     pub output_rules: OutputRules,
 
     pub deployment_status: DeploymentStatus,
+
+    /// Whether the handler is marked as side-effect-free through the
+    /// [`SIDE_EFFECT_FREE_HANDLER_METADATA`] handler metadata, hence its responses can be cached.
+    pub side_effect_free: bool,
+
+    /// Window within which requests without idempotency key are deduplicated by their content,
+    /// as set through the [`DERIVE_IDEMPOTENCY_KEY_HANDLER_METADATA`] handler metadata.
+    pub derived_idempotency_key_window: Option<Duration>,
+
+    /// Ingress limit shared by all the handlers of the service, see
+    /// [`IngressLimits`](crate::schema::service::IngressLimits).
+    pub service_ingress_limit: IngressLimit,
+    /// Ingress limit of this handler, applied in addition to the service limit.
+    pub handler_ingress_limit: IngressLimit,
 }
 
 impl InvocationTargetMetadata {
@@ -103,20 +129,46 @@ impl InvocationTargetMetadata {
             (InvocationTargetType::Workflow(WorkflowHandlerType::Workflow), _) | (_, true) => {
                 // We should retain the completion when the call is to a workflow, or has idempotency key
                 InvocationRetention {
-                    completion_retention: self.completion_retention,
+                    completion_retention: cmp::max(
+                        self.completion_retention,
+                        self.service_completion_retention,
+                    ),
                     // We need to make sure journal_retention is smaller or equal to completion_retention,
                     // due to implementation requirement that invocation status must be retained at least as long as the journal.
-                    journal_retention: cmp::min(self.journal_retention, self.completion_retention),
+                    journal_retention: cmp::min(
+                        self.journal_retention,
+                        cmp::max(self.completion_retention, self.service_completion_retention),
+                    ),
+                }
+            }
+            (_, _)
+                if !self.journal_retention.is_zero()
+                    || !self.service_completion_retention.is_zero() =>
+            {
+                InvocationRetention {
+                    // To retain the journal, we must retain the completion too. No way out of this.
+                    completion_retention: cmp::max(
+                        self.journal_retention,
+                        self.service_completion_retention,
+                    ),
+                    journal_retention: self.journal_retention,
                 }
             }
-            (_, _) if !self.journal_retention.is_zero() => InvocationRetention {
-                // To retain the journal, we must retain the completion too. No way out of this.
-                completion_retention: self.journal_retention,
-                journal_retention: self.journal_retention,
-            },
             _ => InvocationRetention::none(),
         }
     }
+
+    /// Retention of an invocation whose idempotency key was derived from the request content:
+    /// the completion is retained only for the deduplication `window`.
+    pub fn compute_derived_idempotency_key_retention(
+        &self,
+        window: Duration,
+    ) -> InvocationRetention {
+        InvocationRetention {
+            completion_retention: window,
+            journal_retention: cmp::min(self.journal_retention, window),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Default)]
@@ -140,6 +192,10 @@ pub enum InputValidationError {
     BadConfiguration,
     #[error("Content-type '{0}' does not match '{1}'")]
     ContentTypeNotMatching(String, InputContentType),
+    #[error("Body is not a valid JSON value: {0}")]
+    InvalidJson(String),
+    #[error("Body does not match the JSON schema of the handler: {0}")]
+    JsonSchemaMismatch(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -166,6 +222,41 @@ impl InputRules {
         res
     }
 
+    /// Validates the body against the JSON schema declared by the handler for the given
+    /// content-type, if any. This complements [`InputRules::validate`], which checks only the
+    /// content-type and the presence of the body.
+    pub fn validate_json_schema(
+        &self,
+        content_type: Option<&str>,
+        buf: &Bytes,
+    ) -> Result<(), InputValidationError> {
+        let Some(content_type) = content_type else {
+            return Ok(());
+        };
+        let Some(schema) = self
+            .input_validation_rules
+            .iter()
+            .find_map(|rule| match rule {
+                InputValidationRule::JsonValue {
+                    content_type: expected_content_type,
+                    schema: Some(schema),
+                } if expected_content_type.validate(content_type).is_ok() => Some(schema),
+                _ => None,
+            })
+        else {
+            return Ok(());
+        };
+
+        let value: serde_json::Value = serde_json::from_slice(buf)
+            .map_err(|e| InputValidationError::InvalidJson(e.to_string()))?;
+        // The schema was checked when the deployment was registered
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|_| InputValidationError::BadConfiguration)?;
+        validator.validate(&value).map_err(|e| {
+            InputValidationError::JsonSchemaMismatch(format!("{e} at '{}'", e.instance_path))
+        })
+    }
+
     pub fn json_schema(&self) -> Option<serde_json::Value> {
         for rule in &self.input_validation_rules {
             if let InputValidationRule::JsonValue { schema, .. } = rule {
@@ -241,8 +332,8 @@ pub enum InputValidationRule {
     JsonValue {
         // Can use wildcards
         content_type: InputContentType,
-        // Right now we don't use this schema for anything except printing,
-        // so no need to use a more specialized type (we validate the schema is valid inside the schema registry updater)
+        // Used for printing, and by the ingress to validate the input when enabled,
+        // see InputRules::validate_json_schema (we validate the schema is valid inside the schema registry updater)
         schema: Option<serde_json::Value>,
     },
 }
@@ -584,10 +675,15 @@ pub mod test_util {
                 public: true,
                 completion_retention: DEFAULT_IDEMPOTENCY_RETENTION,
                 journal_retention: Duration::ZERO,
+                service_completion_retention: Duration::ZERO,
                 target_ty: invocation_target_type,
                 input_rules: Default::default(),
                 output_rules: Default::default(),
                 deployment_status: DeploymentStatus::Enabled,
+                side_effect_free: false,
+                derived_idempotency_key_window: None,
+                service_ingress_limit: IngressLimit::default(),
+                handler_ingress_limit: IngressLimit::default(),
             }
         }
     }
@@ -707,6 +803,58 @@ mod tests {
             assert_input_not_valid!(input_rules, Some("application/cbor"), Bytes::new());
         }
 
+        #[test]
+        fn validate_json_schema() {
+            let input_rules = InputRules {
+                input_validation_rules: vec![
+                    InputValidationRule::NoBodyAndContentType,
+                    InputValidationRule::JsonValue {
+                        content_type: InputContentType::MimeTypeAndSubtype(
+                            "application".into(),
+                            "json".into(),
+                        ),
+                        schema: Some(serde_json::json!({
+                            "type": "object",
+                            "properties": { "name": { "type": "string" } },
+                            "required": ["name"]
+                        })),
+                    },
+                ],
+            };
+
+            assert!(
+                input_rules
+                    .validate_json_schema(
+                        Some("application/json"),
+                        &Bytes::from_static(br#"{"name": "Till"}"#)
+                    )
+                    .is_ok()
+            );
+            assert!(matches!(
+                input_rules.validate_json_schema(
+                    Some("application/json"),
+                    &Bytes::from_static(br#"{"name": 1}"#)
+                ),
+                Err(InputValidationError::JsonSchemaMismatch(_))
+            ));
+            assert!(matches!(
+                input_rules
+                    .validate_json_schema(Some("application/json"), &Bytes::from_static(b"{")),
+                Err(InputValidationError::InvalidJson(_))
+            ));
+            // No schema applies without a body, nor to other content-types
+            assert!(
+                input_rules
+                    .validate_json_schema(None, &Bytes::new())
+                    .is_ok()
+            );
+            assert!(
+                input_rules
+                    .validate_json_schema(Some("text/plain"), &Bytes::from_static(b"{"))
+                    .is_ok()
+            );
+        }
+
         #[test]
         fn validate_either_empty_or_non_empty_json() {
             let input_rules = InputRules {
@@ -3,7 +3,7 @@ mod serde_hacks;
 pub mod updater;
 
 use std::collections::HashMap;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::Duration;
@@ -15,7 +15,7 @@ use serde_json::Value;
 use serde_with::serde_as;
 
 use restate_serde_util::MapAsVecItem;
-use restate_time_util::FriendlyDuration;
+use restate_time_util::{FriendlyDuration, NonZeroFriendlyDuration};
 
 use crate::config::{Configuration, InvocationRetryPolicyOptions};
 use crate::deployment::{
@@ -31,13 +31,15 @@ use crate::retries::{RetryIter, RetryPolicy};
 use crate::schema::deployment::{DeploymentResolver, DeploymentType, ProtocolType};
 use crate::schema::info::Info;
 use crate::schema::invocation_target::{
-    DEFAULT_IDEMPOTENCY_RETENTION, DEFAULT_WORKFLOW_COMPLETION_RETENTION, DeploymentStatus,
-    InputRules, InvocationAttemptOptions, InvocationTargetMetadata, InvocationTargetResolver,
-    OnMaxAttempts, OutputRules,
+    DEFAULT_IDEMPOTENCY_RETENTION, DEFAULT_WORKFLOW_COMPLETION_RETENTION,
+    DERIVE_IDEMPOTENCY_KEY_HANDLER_METADATA, DeploymentStatus, InputRules,
+    InvocationAttemptOptions, InvocationTargetMetadata, InvocationTargetResolver, OnMaxAttempts,
+    OutputRules, SIDE_EFFECT_FREE_HANDLER_METADATA,
 };
 use crate::schema::metadata::openapi::ServiceOpenAPI;
 use crate::schema::service::{
-    HandlerRetryPolicyMetadata, ServiceMetadataResolver, ServiceRetryPolicyMetadata,
+    HandlerRetryPolicyMetadata, IngressLimits, IngressRoute, ServiceMetadataResolver,
+    ServiceRetryPolicyMetadata,
 };
 use crate::schema::subscriptions::{ListSubscriptionFilter, Subscription, SubscriptionResolver};
 use crate::schema::{deployment, service};
@@ -87,6 +89,26 @@ impl Versioned for Schema {
     }
 }
 
+impl Schema {
+    /// Max journal length of the invocations of the given service, when overridden for it. The
+    /// partition processors fall back to `default-max-journal-length` otherwise.
+    pub fn resolve_max_journal_length_override(
+        &self,
+        service_name: impl AsRef<str>,
+    ) -> Option<NonZeroU32> {
+        self.active_service_revisions
+            .get(service_name.as_ref())
+            .and_then(|revision| revision.service_revision.max_journal_length)
+    }
+
+    /// Ttl of the state entries set by the invocations of the given service, if any.
+    pub fn resolve_state_ttl(&self, service_name: impl AsRef<str>) -> Option<Duration> {
+        self.active_service_revisions
+            .get(service_name.as_ref())
+            .and_then(|revision| revision.service_revision.state_ttl)
+    }
+}
+
 mod storage {
     use crate::flexbuffers_storage_encode_decode;
 
@@ -117,6 +139,9 @@ impl ActiveServiceRevision {
     ) -> HashMap<String, Self> {
         let mut active_service_revisions = HashMap::new();
         for deployment in deployments {
+            if deployment.drain_deadline.is_some() {
+                continue;
+            }
             for service in deployment.services.values() {
                 active_service_revisions
                     .entry(service.name.clone())
@@ -168,6 +193,11 @@ struct Deployment {
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     metadata: HashMap<String, String>,
 
+    /// Set while the deployment is being removed: new invocations are not routed to its services
+    /// anymore, while the in-flight invocations pinned to it complete until this deadline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    drain_deadline: Option<MillisSinceEpoch>,
+
     #[serde_as(as = "restate_serde_util::MapAsVec")]
     services: HashMap<String, Arc<ServiceRevision>>,
 }
@@ -190,6 +220,7 @@ impl Deployment {
             created_at: self.created_at,
             metadata: self.metadata.clone(),
             additional_headers: self.delivery_options.additional_headers.clone(),
+            drain_deadline: self.drain_deadline,
             info: vec![],
         }
     }
@@ -308,6 +339,37 @@ struct ServiceRevision {
     #[serde(skip_serializing_if = "Option::is_none", default)]
     enable_lazy_state: Option<bool>,
 
+    /// Maximum number of journal entries of an invocation of this service.
+    ///
+    /// This overrides the default max journal length set in invocation options.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    max_journal_length: Option<NonZeroU32>,
+
+    /// Ttl of the state entries set by the invocations of this service. Expired entries are
+    /// not returned anymore, and they're eventually dropped.
+    #[serde(
+        with = "serde_with::As::<Option<FriendlyDuration>>",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    state_ttl: Option<Duration>,
+
+    /// The retention of the completed invocations of this service, regardless of their idempotency key.
+    #[serde(
+        with = "serde_with::As::<Option<FriendlyDuration>>",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    completion_retention: Option<Duration>,
+
+    /// Custom route exposing this service through the ingress.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    ingress_route: Option<IngressRoute>,
+
+    /// Rate limits and concurrency caps of the ingress requests to this service.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    ingress_limits: Option<IngressLimits>,
+
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -427,6 +489,13 @@ impl ServiceRevision {
                 .abort_timeout
                 .unwrap_or_else(|| configuration.worker.invoker.abort_timeout.into()),
             enable_lazy_state: self.enable_lazy_state.unwrap_or(false),
+            max_journal_length: self
+                .max_journal_length
+                .or(configuration.invocation.default_max_journal_length),
+            state_ttl: self.state_ttl,
+            completion_retention: self.completion_retention,
+            ingress_route: self.ingress_route.clone(),
+            ingress_limits: self.ingress_limits.clone(),
             retry_policy,
             info,
         }
@@ -720,10 +789,33 @@ impl InvocationTargetResolver for Schema {
             public: handler.public.unwrap_or(service_revision.public),
             completion_retention,
             journal_retention,
+            service_completion_retention: service_revision
+                .completion_retention
+                .unwrap_or(Duration::ZERO),
             target_ty: handler.target_ty,
             input_rules: handler.input_rules.clone(),
             output_rules: handler.output_rules.clone(),
             deployment_status,
+            side_effect_free: handler
+                .metadata
+                .get(SIDE_EFFECT_FREE_HANDLER_METADATA)
+                .is_some_and(|value| value.eq_ignore_ascii_case("true")),
+            derived_idempotency_key_window: handler
+                .metadata
+                .get(DERIVE_IDEMPOTENCY_KEY_HANDLER_METADATA)
+                .and_then(|value| value.parse::<NonZeroFriendlyDuration>().ok())
+                .map(Into::into),
+            service_ingress_limit: service_revision
+                .ingress_limits
+                .as_ref()
+                .map(|ingress_limits| ingress_limits.service)
+                .unwrap_or_default(),
+            handler_ingress_limit: service_revision
+                .ingress_limits
+                .as_ref()
+                .and_then(|ingress_limits| ingress_limits.handlers.get(handler_name))
+                .copied()
+                .unwrap_or_default(),
         })
     }
 
@@ -847,6 +939,25 @@ impl ServiceMetadataResolver for Schema {
             .map(|revision| revision.service_revision.name.clone())
             .collect()
     }
+
+    fn resolve_ingress_route<'a>(
+        &self,
+        host: Option<&str>,
+        path: &'a str,
+    ) -> Option<(String, &'a str)> {
+        service::match_ingress_route(
+            self.active_service_revisions
+                .iter()
+                .filter_map(|(service_name, revision)| {
+                    Some((
+                        service_name.as_str(),
+                        revision.service_revision.ingress_route.as_ref()?,
+                    ))
+                }),
+            host,
+            path,
+        )
+    }
 }
 
 impl SubscriptionResolver for Schema {
@@ -58,6 +58,7 @@ impl ServiceOpenAPI {
             attach_get_output_parameters
                 .push(parameters_ref(IDEMPOTENCY_KEY_PATH_PARAMETER_REF_NAME).into());
         }
+        call_parameters.push(parameters_ref(TIMEOUT_HEADER_PARAMETER_REF_NAME).into());
 
         let mut rpc_paths = Paths::builder();
         let mut send_paths = Paths::builder();
@@ -135,6 +136,7 @@ impl ServiceOpenAPI {
                         ))
                         .parameters(Some(call_parameters.clone()))
                         .parameter(parameters_ref(DELAY_PARAMETER_REF_NAME))
+                        .parameter(parameters_ref(DELAY_HEADER_PARAMETER_REF_NAME))
                         .tag(SEND_TAG_NAME.to_string())
                         .request_body(request_body)
                         .response("200", responses_ref(SEND_RESPONSE_REF_NAME))
@@ -499,7 +501,12 @@ fn infer_handler_response(
 fn restate_components() -> Components {
     Components::builder()
         .parameter(DELAY_PARAMETER_REF_NAME, delay_parameter())
+        .parameter(DELAY_HEADER_PARAMETER_REF_NAME, delay_header_parameter())
         .parameter(KEY_PARAMETER_REF_NAME, key_parameter())
+        .parameter(
+            TIMEOUT_HEADER_PARAMETER_REF_NAME,
+            timeout_header_parameter(),
+        )
         .parameter(
             IDEMPOTENCY_KEY_HEADER_PARAMETER_REF_NAME,
             idempotency_key_header_parameter(),
@@ -552,6 +559,21 @@ fn delay_parameter() -> Parameter {
         .build()
 }
 
+const DELAY_HEADER_PARAMETER_REF_NAME: &str = "delayHeader";
+
+fn delay_header_parameter() -> Parameter {
+    Parameter::builder()
+        .name("x-restate-delay")
+        .parameter_in(ParameterIn::Header)
+        .schema(Some(string_json_schema()))
+        .example(Some(Value::String("10s".to_string())))
+        .required(Required::False)
+        .description(Some(
+            "Alternative to the `delay` query parameter, which takes precedence if both are set.",
+        ))
+        .build()
+}
+
 const KEY_PARAMETER_REF_NAME: &str = "key";
 
 fn key_parameter() -> Parameter {
@@ -563,6 +585,19 @@ fn key_parameter() -> Parameter {
         .build()
 }
 
+const TIMEOUT_HEADER_PARAMETER_REF_NAME: &str = "timeoutHeader";
+
+fn timeout_header_parameter() -> Parameter {
+    Parameter::builder()
+        .name("x-restate-timeout")
+        .parameter_in(ParameterIn::Header)
+        .schema(Some(string_json_schema()))
+        .example(Some(Value::String("10s".to_string())))
+        .required(Required::False)
+        .description(Some("Time after which the invocation fails, if it didn't complete yet. The handler receives the resulting deadline, in milliseconds since the unix epoch, in the `x-restate-deadline` request header."))
+        .build()
+}
+
 const IDEMPOTENCY_KEY_HEADER_PARAMETER_REF_NAME: &str = "idempotencyKeyHeader";
 
 fn idempotency_key_header_parameter() -> Parameter {
@@ -691,6 +726,10 @@ fn send_response_json_schema() -> Value {
                 "type": "string",
                 "format": "date-time",
                 "description": "Time when the invocation will be executed, in case 'delay' is used"
+            },
+            "sessionToken": {
+                "type": "string",
+                "description": "Token to attach to the workflow run or get its output through /restate/session, in case session tokens are enabled"
             }
         },
         "required": ["invocationId", "status"],
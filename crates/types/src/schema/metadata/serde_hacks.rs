@@ -528,6 +528,11 @@ mod conversions {
                         inactivity_timeout: service.inactivity_timeout,
                         abort_timeout: service.abort_timeout,
                         enable_lazy_state: service.enable_lazy_state,
+                        max_journal_length: None,
+                        state_ttl: None,
+                        completion_retention: None,
+                        ingress_route: None,
+                        ingress_limits: None,
                         retry_policy_initial_interval: None,
                         retry_policy_exponentiation_factor: None,
                         retry_policy_max_attempts: None,
@@ -546,6 +551,7 @@ mod conversions {
                     sdk_version: deployment.metadata.sdk_version,
                     created_at: deployment.metadata.created_at,
                     metadata: Default::default(),
+                    drain_deadline: None,
                     services: v2_services,
                 };
                 v2_deployments.push(v2_deployment);
@@ -822,6 +828,7 @@ mod conversions {
                         sdk_version: None,
                         created_at: MillisSinceEpoch::now(),
                         metadata: Default::default(),
+                        drain_deadline: None,
                         services: HashMap::from([
                             (
                                 "Greeter".to_owned(),
@@ -838,6 +845,11 @@ mod conversions {
                                     inactivity_timeout: None,
                                     abort_timeout: None,
                                     enable_lazy_state: None,
+                                    max_journal_length: None,
+                                    state_ttl: None,
+                                    completion_retention: None,
+                                    ingress_route: None,
+                                    ingress_limits: None,
                                     retry_policy_initial_interval: None,
                                     retry_policy_exponentiation_factor: None,
                                     retry_policy_max_attempts: None,
@@ -884,6 +896,11 @@ mod conversions {
                                     inactivity_timeout: None,
                                     abort_timeout: None,
                                     enable_lazy_state: None,
+                                    max_journal_length: None,
+                                    state_ttl: None,
+                                    completion_retention: None,
+                                    ingress_route: None,
+                                    ingress_limits: None,
                                     retry_policy_initial_interval: None,
                                     retry_policy_exponentiation_factor: None,
                                     retry_policy_max_attempts: None,
@@ -958,6 +975,7 @@ mod conversions {
                         sdk_version: None,
                         created_at: MillisSinceEpoch::now(),
                         metadata: Default::default(),
+                        drain_deadline: None,
                         services: HashMap::from([(
                             "Greeter".to_owned(),
                             Arc::new(ServiceRevision {
@@ -973,6 +991,11 @@ mod conversions {
                                 inactivity_timeout: None,
                                 abort_timeout: None,
                                 enable_lazy_state: None,
+                                max_journal_length: None,
+                                state_ttl: None,
+                                completion_retention: None,
+                                ingress_route: None,
+                                ingress_limits: None,
                                 retry_policy_initial_interval: None,
                                 retry_policy_exponentiation_factor: None,
                                 retry_policy_max_attempts: None,
@@ -24,6 +24,7 @@ use crate::schema::invocation_target::{
     InputRules, InputValidationRule, OnMaxAttempts, OutputContentTypeRule, OutputRules,
 };
 use crate::schema::registry::{DeploymentConnectionParameters, DiscoveryResponse};
+use crate::schema::service::{IngressLimit, IngressLimits, IngressRoute};
 use crate::schema::subscriptions::{EventInvocationTargetTemplate, Sink, Source, Subscription};
 use crate::time::MillisSinceEpoch;
 use crate::{deployment, endpoint_manifest, identifiers};
@@ -32,7 +33,7 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use std::error::Error;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::ops::{Deref, Not, RangeInclusive};
 use std::sync::Arc;
 use std::time::Duration;
@@ -93,6 +94,11 @@ pub(in crate::schema) enum ServiceError {
     #[error("the service '{0}' already exists but the new revision removed the handlers {1:?}")]
     #[code(restate_errors::META0006)]
     RemovedHandlers(String, Vec<String>),
+    #[error(
+        "the service '{0}' already exists but the new revision changed the signature of the handlers {1:?}"
+    )]
+    #[code(restate_errors::META0006)]
+    IncompatibleHandlers(String, Vec<String>),
     #[error("the handler '{0}' input content-type is not valid: {1}")]
     #[code(unknown)]
     BadInputContentType(String, BadInputContentType),
@@ -131,6 +137,25 @@ pub(in crate::schema) enum ServiceError {
     #[error("modifying retention time for service type {0} is unsupported")]
     #[code(unknown)]
     CannotModifyRetentionTime(ServiceType),
+    #[error("the ingress route host '{0}' is invalid: it must be a host name without port")]
+    #[code(unknown)]
+    BadIngressRouteHost(String),
+    #[error(
+        "the ingress route path prefix '{0}' is invalid: it must start with '/' and cannot start with the reserved segments '/restate' or '/openapi'"
+    )]
+    #[code(unknown)]
+    BadIngressRoutePathPrefix(String),
+    #[error("the ingress route of service '{0}' conflicts with the ingress route of service '{1}'")]
+    #[code(unknown)]
+    ConflictingIngressRoute(String, String),
+    #[error(
+        "the ingress limit of '{0}' is invalid: the burst can be set only together with the requests per second"
+    )]
+    #[code(unknown)]
+    BadIngressLimitBurst(String),
+    #[error("the ingress limits of service '{0}' refer to the unknown handler '{1}'")]
+    #[code(unknown)]
+    UnknownIngressLimitHandler(String, String),
 }
 
 #[derive(Debug, thiserror::Error, codederror::CodedError)]
@@ -140,13 +165,17 @@ pub(in crate::schema) enum SubscriptionError {
     Override(SubscriptionId),
 
     #[error(
-        "invalid source URI '{0}': must have a scheme segment, with supported schemes: [kafka]."
+        "invalid source URI '{0}': must have a scheme segment, with supported schemes: [kafka, sqs]."
     )]
     InvalidSourceScheme(Uri),
     #[error(
         "invalid source URI '{0}': source URI of Kafka type must have a authority segment containing the cluster name."
     )]
     InvalidKafkaSourceAuthority(Uri),
+    #[error(
+        "invalid source URI '{0}': source URI of SQS type must have a authority segment containing the connection name, and a path segment containing the queue name."
+    )]
+    InvalidSqsSource(Uri),
 
     #[error(
         "invalid sink URI '{0}': must have a scheme segment, with supported schemes: [service]."
@@ -191,6 +220,15 @@ enum ServiceTypeMismatchBehavior {
     Warn,
 }
 
+/// Behavior when a handler signature changes in an incompatible way during service update
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum IncompatibleHandlerBehavior {
+    /// Fail with an error when a handler signature changes
+    Fail,
+    /// Log a warning but allow the signature change
+    Warn,
+}
+
 /// Behavior for service level settings during update
 enum ServiceLevelSettingsBehavior {
     /// Preserve existing service level settings
@@ -240,6 +278,13 @@ pub struct ModifyServiceRequest {
     pub workflow_completion_retention: Option<Duration>,
     pub inactivity_timeout: Option<Duration>,
     pub abort_timeout: Option<Duration>,
+    pub max_journal_length: Option<NonZeroU32>,
+    pub state_ttl: Option<Duration>,
+    pub completion_retention: Option<Duration>,
+    /// An empty route removes the custom ingress route of the service.
+    pub ingress_route: Option<IngressRoute>,
+    /// Empty limits remove the ingress limits of the service.
+    pub ingress_limits: Option<IngressLimits>,
 }
 
 /// Responsible for updating the provided [`Schema`] with new
@@ -320,8 +365,12 @@ impl SchemaUpdater {
             .deployments
             .iter()
             .filter(|(_, deployment)| {
-                deployment
-                    .semantic_eq_with_address_and_headers(&deployment_address, &additional_headers)
+                // A draining deployment is going away, the same endpoint is registered anew
+                deployment.drain_deadline.is_none()
+                    && deployment.semantic_eq_with_address_and_headers(
+                        &deployment_address,
+                        &additional_headers,
+                    )
             })
             // There are few situations where we might have multiple deployments for the same endpoint:
             // * If there is some different configuration of the Configuration.admin.deployment_routing_headers between nodes,
@@ -402,6 +451,22 @@ impl SchemaUpdater {
                 previous_service_revision,
                 ServiceLevelSettingsBehavior::UseDefaults,
             )?;
+
+            if let Some(previous_service_revision) = previous_service_revision {
+                Self::validate_handlers_compatibility(
+                    deployment_id,
+                    &deployment_address,
+                    &service_name,
+                    &new_service_revision,
+                    previous_service_revision,
+                    if allow_breaking_changes == AllowBreakingChanges::Yes {
+                        IncompatibleHandlerBehavior::Warn
+                    } else {
+                        IncompatibleHandlerBehavior::Fail
+                    },
+                )?;
+            }
+
             computed_services.insert(service_name.to_string(), Arc::new(new_service_revision));
         }
 
@@ -418,6 +483,7 @@ impl SchemaUpdater {
                 sdk_version: discovery_response.sdk_version,
                 created_at: MillisSinceEpoch::now(),
                 metadata,
+                drain_deadline: None,
                 services: computed_services,
             },
         );
@@ -519,6 +585,54 @@ impl SchemaUpdater {
         Ok(())
     }
 
+    /// Compares the handlers present in both revisions, detecting changes that would break existing callers:
+    /// * The handler type changed (e.g. from shared to exclusive)
+    /// * The new revision doesn't accept anymore an input content-type accepted by the previous revision
+    /// * The output content-type changed
+    fn validate_handlers_compatibility(
+        deployment_id: DeploymentId,
+        deployment_address: &DeploymentAddress,
+        service_name: &String,
+        new_service: &ServiceRevision,
+        existing_service: &ServiceRevision,
+        incompatible_handler_behavior: IncompatibleHandlerBehavior,
+    ) -> Result<(), SchemaError> {
+        let mut incompatible_handlers: Vec<String> = existing_service
+            .handlers
+            .iter()
+            .filter_map(|(name, existing_handler)| {
+                let new_handler = new_service.handlers.get(name)?;
+                let incompatible = existing_handler.target_ty != new_handler.target_ty
+                    || !accepts_input_of(&new_handler.input_rules, &existing_handler.input_rules)
+                    || existing_handler.output_rules.content_type_rule
+                        != new_handler.output_rules.content_type_rule;
+                incompatible.then(|| name.clone())
+            })
+            .collect();
+
+        if incompatible_handlers.is_empty() {
+            return Ok(());
+        }
+        incompatible_handlers.sort();
+
+        if incompatible_handler_behavior == IncompatibleHandlerBehavior::Fail {
+            return Err(SchemaError::Service(ServiceError::IncompatibleHandlers(
+                service_name.clone(),
+                incompatible_handlers,
+            )));
+        }
+        warn!(
+            restate.deployment.id = %deployment_id,
+            restate.deployment.address = %deployment_address,
+            "Going to change the signature of the following methods from service type {} due to an update: {:?}. \
+            This is a potentially dangerous operation, and might break existing callers.",
+            service_name,
+            incompatible_handlers
+        );
+
+        Ok(())
+    }
+
     fn create_service_revision(
         &self,
         service_name: &String,
@@ -606,6 +720,33 @@ impl SchemaUpdater {
             retry_policy_on_max_attempts
         );
 
+        // Set only through the admin API, hence it can only be preserved
+        let max_journal_length = if service_level_settings_behavior.preserve() {
+            previous_service_revision.and_then(|old_svc| old_svc.max_journal_length)
+        } else {
+            None
+        };
+        let state_ttl = if service_level_settings_behavior.preserve() {
+            previous_service_revision.and_then(|old_svc| old_svc.state_ttl)
+        } else {
+            None
+        };
+        let completion_retention = if service_level_settings_behavior.preserve() {
+            previous_service_revision.and_then(|old_svc| old_svc.completion_retention)
+        } else {
+            None
+        };
+        let ingress_route = if service_level_settings_behavior.preserve() {
+            previous_service_revision.and_then(|old_svc| old_svc.ingress_route.clone())
+        } else {
+            None
+        };
+        let ingress_limits = if service_level_settings_behavior.preserve() {
+            previous_service_revision.and_then(|old_svc| old_svc.ingress_limits.clone())
+        } else {
+            None
+        };
+
         let handlers = service
             .handlers
             .into_iter()
@@ -631,6 +772,11 @@ impl SchemaUpdater {
             inactivity_timeout,
             abort_timeout,
             enable_lazy_state: service.enable_lazy_state,
+            max_journal_length,
+            state_ttl,
+            completion_retention,
+            ingress_route,
+            ingress_limits,
             retry_policy_initial_interval,
             retry_policy_exponentiation_factor,
             retry_policy_max_attempts,
@@ -691,6 +837,7 @@ impl SchemaUpdater {
                         .clone(),
                     created_at: existing_deployment.created_at,
                     metadata: existing_deployment.metadata.clone(),
+                    drain_deadline: existing_deployment.drain_deadline,
                     services: existing_deployment.services.clone(),
                 },
             );
@@ -752,6 +899,17 @@ impl SchemaUpdater {
                     ServiceLevelSettingsBehavior::UseDefaults,
                 )?;
 
+                if let Some(previous_service_revision) = previous_service_revision {
+                    Self::validate_handlers_compatibility(
+                        deployment_id,
+                        &deployment_address,
+                        &service_name,
+                        &service_revision,
+                        previous_service_revision,
+                        IncompatibleHandlerBehavior::Warn,
+                    )?;
+                }
+
                 match self.schema.active_service_revisions.get(&service_name) {
                     Some(ActiveServiceRevision {
                         deployment_id: latest_deployment,
@@ -794,6 +952,7 @@ impl SchemaUpdater {
                     id: deployment_id,
                     created_at: existing_deployment.created_at,
                     metadata: existing_deployment.metadata.clone(),
+                    drain_deadline: existing_deployment.drain_deadline,
                 },
             );
 
@@ -803,6 +962,26 @@ impl SchemaUpdater {
         }
     }
 
+    /// Stops routing new invocations to the services of the given deployment, which stays
+    /// registered for the in-flight invocations pinned to it until it gets removed.
+    ///
+    /// Draining an already draining deployment replaces its drain deadline.
+    /// Returns false if the deployment doesn't exist.
+    pub fn drain_deployment(
+        &mut self,
+        deployment_id: DeploymentId,
+        drain_deadline: MillisSinceEpoch,
+    ) -> bool {
+        let Some(deployment) = self.schema.deployments.get_mut(&deployment_id) else {
+            return false;
+        };
+        if deployment.drain_deadline != Some(drain_deadline) {
+            deployment.drain_deadline = Some(drain_deadline);
+            self.mark_updated();
+        }
+        true
+    }
+
     /// Returns true if it was removed
     pub fn remove_deployment(&mut self, deployment_id: DeploymentId) -> bool {
         if let Some(deployment) = self.schema.deployments.remove(&deployment_id) {
@@ -862,6 +1041,21 @@ impl SchemaUpdater {
                     topic: topic_name.to_string(),
                 }
             }
+            Some("sqs") => {
+                let connection_name = source.authority().map(|a| a.as_str());
+                let queue_name = &source.path()[1..];
+                match connection_name {
+                    Some(connection_name) if !queue_name.is_empty() => Source::Sqs {
+                        connection: connection_name.to_string(),
+                        queue: queue_name.to_string(),
+                    },
+                    _ => {
+                        return Err(SchemaError::Subscription(
+                            SubscriptionError::InvalidSqsSource(source),
+                        ));
+                    }
+                }
+            }
             _ => {
                 return Err(SchemaError::Subscription(
                     SubscriptionError::InvalidSourceScheme(source),
@@ -934,7 +1128,7 @@ impl SchemaUpdater {
 
         let subscription = Configuration::pinned()
             .ingress
-            .create_kafka_subscription(id, source, sink, metadata.unwrap_or_default())
+            .create_subscription(id, source, sink, metadata.unwrap_or_default())
             .map_err(|e| SchemaError::Subscription(SubscriptionError::Validation(e.into())))?;
 
         self.schema.subscriptions.insert(id, subscription);
@@ -957,6 +1151,11 @@ impl SchemaUpdater {
         name: &str,
         modify_service_request: ModifyServiceRequest,
     ) -> Result<(), SchemaError> {
+        let new_ingress_route = modify_service_request
+            .ingress_route
+            .map(|ingress_route| self.validate_ingress_route(name, ingress_route))
+            .transpose()?;
+
         self.apply_change_to_active_service_revision(name, |svc| {
             if let Some(new_public_value) = modify_service_request.public {
                 svc.public = new_public_value;
@@ -986,6 +1185,21 @@ impl SchemaUpdater {
             if let Some(new_abort_timeout) = modify_service_request.abort_timeout {
                 svc.abort_timeout = Some(new_abort_timeout);
             }
+            if let Some(new_max_journal_length) = modify_service_request.max_journal_length {
+                svc.max_journal_length = Some(new_max_journal_length);
+            }
+            if let Some(new_state_ttl) = modify_service_request.state_ttl {
+                svc.state_ttl = Some(new_state_ttl);
+            }
+            if let Some(new_completion_retention) = modify_service_request.completion_retention {
+                svc.completion_retention = Some(new_completion_retention);
+            }
+            if let Some(new_ingress_route) = new_ingress_route {
+                svc.ingress_route = new_ingress_route;
+            }
+            if let Some(new_ingress_limits) = modify_service_request.ingress_limits {
+                svc.ingress_limits = validate_ingress_limits(svc, new_ingress_limits)?;
+            }
             Ok(())
         })?;
 
@@ -994,6 +1208,66 @@ impl SchemaUpdater {
         Ok(())
     }
 
+    /// Normalizes the given ingress route, returning `None` if it's empty, and checks it
+    /// doesn't clash with the ingress routes of the other services.
+    fn validate_ingress_route(
+        &self,
+        service_name: &str,
+        ingress_route: IngressRoute,
+    ) -> Result<Option<IngressRoute>, SchemaError> {
+        let host = ingress_route
+            .host
+            .map(|host| host.trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty());
+        if let Some(host) = &host
+            && !host
+                .parse::<http::uri::Authority>()
+                .is_ok_and(|authority| authority.port().is_none() && authority.as_str() == host)
+        {
+            return Err(SchemaError::Service(ServiceError::BadIngressRouteHost(
+                host.clone(),
+            )));
+        }
+
+        let path_prefix = ingress_route
+            .path_prefix
+            .map(|path_prefix| path_prefix.trim().trim_end_matches('/').to_owned())
+            .filter(|path_prefix| !path_prefix.is_empty());
+        if let Some(path_prefix) = &path_prefix
+            && (!path_prefix.starts_with('/')
+                || matches!(
+                    path_prefix.split('/').nth(1),
+                    Some("restate" | "openapi" | "")
+                ))
+        {
+            return Err(SchemaError::Service(
+                ServiceError::BadIngressRoutePathPrefix(path_prefix.clone()),
+            ));
+        }
+
+        let ingress_route = IngressRoute { host, path_prefix };
+        if ingress_route.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some((other_service_name, _)) =
+            self.schema
+                .active_service_revisions
+                .iter()
+                .find(|(other_service_name, revision)| {
+                    other_service_name.as_str() != service_name
+                        && revision.service_revision.ingress_route.as_ref() == Some(&ingress_route)
+                })
+        {
+            return Err(SchemaError::Service(ServiceError::ConflictingIngressRoute(
+                service_name.to_owned(),
+                other_service_name.clone(),
+            )));
+        }
+
+        Ok(Some(ingress_route))
+    }
+
     fn apply_change_to_active_service_revision(
         &mut self,
         svc_name: &str,
@@ -1230,6 +1504,67 @@ fn validate_service_name(name: &str) -> Result<(), ServiceError> {
     }
 }
 
+/// Checks the given ingress limits refer to handlers of the service, returning `None` if
+/// they're empty. The empty handler limits are dropped.
+fn validate_ingress_limits(
+    svc: &ServiceRevision,
+    mut ingress_limits: IngressLimits,
+) -> Result<Option<IngressLimits>, SchemaError> {
+    let check_burst = |target: &str, limit: &IngressLimit| {
+        if limit.burst.is_some() && limit.requests_per_second.is_none() {
+            Err(SchemaError::Service(ServiceError::BadIngressLimitBurst(
+                target.to_owned(),
+            )))
+        } else {
+            Ok(())
+        }
+    };
+
+    check_burst(&svc.name, &ingress_limits.service)?;
+    for (handler_name, limit) in &ingress_limits.handlers {
+        if !svc.handlers.contains_key(handler_name) {
+            return Err(SchemaError::Service(
+                ServiceError::UnknownIngressLimitHandler(svc.name.clone(), handler_name.clone()),
+            ));
+        }
+        check_burst(&format!("{}/{handler_name}", svc.name), limit)?;
+    }
+
+    ingress_limits.handlers.retain(|_, limit| !limit.is_empty());
+    Ok((!ingress_limits.is_empty()).then_some(ingress_limits))
+}
+
+/// Returns true if every kind of input accepted by `previous` is still accepted by `new`.
+///
+/// JSON schemas are not compared, only the content-types.
+fn accepts_input_of(new: &InputRules, previous: &InputRules) -> bool {
+    fn same_content_type(a: &InputValidationRule, b: &InputValidationRule) -> bool {
+        match (a, b) {
+            (
+                InputValidationRule::NoBodyAndContentType,
+                InputValidationRule::NoBodyAndContentType,
+            ) => true,
+            (
+                InputValidationRule::ContentType { content_type: a }
+                | InputValidationRule::JsonValue {
+                    content_type: a, ..
+                },
+                InputValidationRule::ContentType { content_type: b }
+                | InputValidationRule::JsonValue {
+                    content_type: b, ..
+                },
+            ) => a == b,
+            _ => false,
+        }
+    }
+
+    previous.input_validation_rules.iter().all(|previous_rule| {
+        new.input_validation_rules
+            .iter()
+            .any(|new_rule| same_content_type(new_rule, previous_rule))
+    })
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("invalid option '{name}'. Reason: {reason}")]
 pub struct ValidationError {
@@ -1238,15 +1573,32 @@ pub struct ValidationError {
 }
 
 impl IngressOptions {
-    fn create_kafka_subscription(
+    fn create_subscription(
         &self,
         id: SubscriptionId,
         source: Source,
         sink: Sink,
         mut metadata: HashMap<String, String>,
     ) -> Result<Subscription, ValidationError> {
+        match &source {
+            Source::Kafka { cluster, .. } => {
+                self.validate_kafka_metadata(id, cluster, &mut metadata)?
+            }
+            Source::Sqs { connection, .. } => {
+                self.validate_sqs_metadata(connection, &sink, &metadata)?
+            }
+        }
+
+        Ok(Subscription::new(id, source, sink, metadata))
+    }
+
+    fn validate_kafka_metadata(
+        &self,
+        id: SubscriptionId,
+        cluster: &str,
+        metadata: &mut HashMap<String, String>,
+    ) -> Result<(), ValidationError> {
         // Retrieve the cluster option and merge them with subscription metadata
-        let Source::Kafka { cluster, .. } = &source;
         let cluster_options = &self.get_kafka_cluster(cluster).ok_or(ValidationError {
             name: "source",
             reason: "specified cluster in the source URI does not exist. Make sure it is defined in the KafkaOptions",
@@ -1280,7 +1632,61 @@ impl IngressOptions {
             metadata.insert("client.id".to_string(), "restate".to_string());
         }
 
-        Ok(Subscription::new(id, source, sink, metadata))
+        Ok(())
+    }
+
+    fn validate_sqs_metadata(
+        &self,
+        connection: &str,
+        sink: &Sink,
+        metadata: &HashMap<String, String>,
+    ) -> Result<(), ValidationError> {
+        if self.get_sqs_connection(connection).is_none() {
+            return Err(ValidationError {
+                name: "source",
+                reason: "specified connection in the source URI does not exist. Make sure it is defined in the SQS connections of the ingress options",
+            });
+        }
+
+        let Sink::Invocation {
+            event_invocation_target_template,
+        } = sink;
+        if !matches!(
+            event_invocation_target_template,
+            EventInvocationTargetTemplate::Service { .. }
+        ) && !metadata.contains_key("key.attribute")
+        {
+            return Err(ValidationError {
+                name: "key.attribute",
+                reason: "must be set to the name of the message attribute containing the key, when the sink is a virtual object or workflow handler",
+            });
+        }
+
+        let parse_option = |name: &'static str, range: RangeInclusive<i32>, reason| match metadata
+            .get(name)
+            .map(|value| value.parse::<i32>())
+        {
+            Some(Ok(value)) if range.contains(&value) => Ok(()),
+            Some(_) => Err(ValidationError { name, reason }),
+            None => Ok(()),
+        };
+        parse_option(
+            "wait.time.seconds",
+            0..=20,
+            "must be a number of seconds between 0 and 20",
+        )?;
+        parse_option(
+            "max.number.of.messages",
+            1..=10,
+            "must be a number between 1 and 10",
+        )?;
+        parse_option(
+            "visibility.timeout.seconds",
+            0..=43_200,
+            "must be a number of seconds between 0 and 43200",
+        )?;
+
+        Ok(())
     }
 }
 
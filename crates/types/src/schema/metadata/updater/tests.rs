@@ -21,7 +21,7 @@ use crate::service_protocol::{
     MAX_INFLIGHT_SERVICE_PROTOCOL_VERSION, MIN_INFLIGHT_SERVICE_PROTOCOL_VERSION,
 };
 use http::HeaderName;
-use restate_test_util::{assert, assert_eq};
+use restate_test_util::{assert, assert_eq, let_assert};
 use test_log::test;
 
 const GREETER_SERVICE_NAME: &str = "greeter.Greeter";
@@ -584,6 +584,340 @@ fn force_deploy_private_service() -> Result<(), SchemaError> {
     Ok(())
 }
 
+#[test]
+fn modify_max_journal_length() -> Result<(), SchemaError> {
+    let mut updater = SchemaUpdater::default();
+    updater.add_deployment(add_deployment_request(vec![greeter_service()]))?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(
+        schemas
+            .assert_service(GREETER_SERVICE_NAME)
+            .max_journal_length,
+        None
+    );
+    assert_eq!(
+        schemas.resolve_max_journal_length_override(GREETER_SERVICE_NAME),
+        None
+    );
+
+    updater = SchemaUpdater::new(schemas);
+    updater.modify_service(
+        GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            max_journal_length: Some(NonZeroU32::new(100).unwrap()),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(
+        schemas
+            .assert_service(GREETER_SERVICE_NAME)
+            .max_journal_length,
+        NonZeroU32::new(100)
+    );
+    assert_eq!(
+        schemas.resolve_max_journal_length_override(GREETER_SERVICE_NAME),
+        NonZeroU32::new(100)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn modify_ingress_route() -> Result<(), SchemaError> {
+    let mut updater = SchemaUpdater::default();
+    updater.add_deployment(add_deployment_request(vec![
+        greeter_service(),
+        another_greeter_service(),
+    ]))?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(
+        schemas.resolve_ingress_route(Some("payments.example.com"), "/payments/greet"),
+        None
+    );
+
+    updater = SchemaUpdater::new(schemas);
+    updater.modify_service(
+        GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            ingress_route: Some(IngressRoute {
+                host: Some("Payments.Example.com".to_owned()),
+                path_prefix: Some("/payments/".to_owned()),
+            }),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(
+        schemas.assert_service(GREETER_SERVICE_NAME).ingress_route,
+        Some(IngressRoute {
+            host: Some("payments.example.com".to_owned()),
+            path_prefix: Some("/payments".to_owned()),
+        })
+    );
+    assert_eq!(
+        schemas.resolve_ingress_route(Some("payments.example.com"), "/payments/greet"),
+        Some((GREETER_SERVICE_NAME.to_owned(), "/greet"))
+    );
+    assert_eq!(
+        schemas.resolve_ingress_route(Some("payments.example.com"), "/paymentsgreet"),
+        None
+    );
+    assert_eq!(
+        schemas.resolve_ingress_route(Some("orders.example.com"), "/payments/greet"),
+        None
+    );
+
+    // Another service can't use the same route
+    updater = SchemaUpdater::new(schemas);
+    let_assert!(
+        Err(SchemaError::Service(ServiceError::ConflictingIngressRoute(
+            service,
+            other_service
+        ))) = updater.modify_service(
+            ANOTHER_GREETER_SERVICE_NAME,
+            ModifyServiceRequest {
+                ingress_route: Some(IngressRoute {
+                    host: Some("payments.example.com".to_owned()),
+                    path_prefix: Some("/payments".to_owned()),
+                }),
+                ..ModifyServiceRequest::default()
+            },
+        )
+    );
+    assert_eq!(service, ANOTHER_GREETER_SERVICE_NAME);
+    assert_eq!(other_service, GREETER_SERVICE_NAME);
+
+    // Reserved prefixes are rejected
+    let_assert!(
+        Err(SchemaError::Service(
+            ServiceError::BadIngressRoutePathPrefix(_)
+        )) = updater.modify_service(
+            ANOTHER_GREETER_SERVICE_NAME,
+            ModifyServiceRequest {
+                ingress_route: Some(IngressRoute {
+                    host: None,
+                    path_prefix: Some("/restate/payments".to_owned()),
+                }),
+                ..ModifyServiceRequest::default()
+            },
+        )
+    );
+
+    // The more specific route wins
+    updater.modify_service(
+        ANOTHER_GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            ingress_route: Some(IngressRoute {
+                host: Some("payments.example.com".to_owned()),
+                path_prefix: None,
+            }),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+    assert_eq!(
+        schemas.resolve_ingress_route(Some("payments.example.com"), "/payments/greet"),
+        Some((GREETER_SERVICE_NAME.to_owned(), "/greet"))
+    );
+    assert_eq!(
+        schemas.resolve_ingress_route(Some("payments.example.com"), "/greet"),
+        Some((ANOTHER_GREETER_SERVICE_NAME.to_owned(), "/greet"))
+    );
+
+    // An empty route removes it
+    updater = SchemaUpdater::new(schemas);
+    updater.modify_service(
+        GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            ingress_route: Some(IngressRoute::default()),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+    assert_eq!(
+        schemas.assert_service(GREETER_SERVICE_NAME).ingress_route,
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn modify_ingress_limits() -> Result<(), SchemaError> {
+    let mut updater = SchemaUpdater::default();
+    updater.add_deployment(add_deployment_request(vec![greeter_service()]))?;
+    let schemas = updater.into_inner();
+
+    let service_limit = IngressLimit {
+        requests_per_second: NonZeroU32::new(100),
+        burst: None,
+        max_concurrency: NonZeroU32::new(10),
+    };
+    let handler_limit = IngressLimit {
+        requests_per_second: NonZeroU32::new(5),
+        burst: NonZeroU32::new(20),
+        max_concurrency: None,
+    };
+
+    updater = SchemaUpdater::new(schemas);
+    updater.modify_service(
+        GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            ingress_limits: Some(IngressLimits {
+                service: service_limit,
+                handlers: HashMap::from([(GREET_HANDLER_NAME.to_owned(), handler_limit)]),
+            }),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+
+    let invocation_target =
+        schemas.assert_invocation_target(GREETER_SERVICE_NAME, GREET_HANDLER_NAME);
+    assert_eq!(invocation_target.service_ingress_limit, service_limit);
+    assert_eq!(invocation_target.handler_ingress_limit, handler_limit);
+    assert_eq!(handler_limit.effective_burst(), NonZeroU32::new(20));
+    assert_eq!(service_limit.effective_burst(), NonZeroU32::new(100));
+
+    // Limits of unknown handlers are rejected
+    updater = SchemaUpdater::new(schemas);
+    let_assert!(
+        Err(SchemaError::Service(
+            ServiceError::UnknownIngressLimitHandler(_, handler)
+        )) = updater.modify_service(
+            GREETER_SERVICE_NAME,
+            ModifyServiceRequest {
+                ingress_limits: Some(IngressLimits {
+                    service: IngressLimit::default(),
+                    handlers: HashMap::from([("unknown".to_owned(), handler_limit)]),
+                }),
+                ..ModifyServiceRequest::default()
+            },
+        )
+    );
+    assert_eq!(handler, "unknown");
+
+    // The burst requires a rate
+    let_assert!(
+        Err(SchemaError::Service(ServiceError::BadIngressLimitBurst(_))) = updater.modify_service(
+            GREETER_SERVICE_NAME,
+            ModifyServiceRequest {
+                ingress_limits: Some(IngressLimits {
+                    service: IngressLimit {
+                        burst: NonZeroU32::new(10),
+                        ..IngressLimit::default()
+                    },
+                    handlers: HashMap::new(),
+                }),
+                ..ModifyServiceRequest::default()
+            },
+        )
+    );
+
+    // Empty limits remove them
+    updater.modify_service(
+        GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            ingress_limits: Some(IngressLimits::default()),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+    assert_eq!(
+        schemas.assert_service(GREETER_SERVICE_NAME).ingress_limits,
+        None
+    );
+    assert!(
+        schemas
+            .assert_invocation_target(GREETER_SERVICE_NAME, GREET_HANDLER_NAME)
+            .service_ingress_limit
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn modify_state_ttl() -> Result<(), SchemaError> {
+    let mut updater = SchemaUpdater::default();
+    updater.add_deployment(add_deployment_request(vec![greeter_service()]))?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(schemas.assert_service(GREETER_SERVICE_NAME).state_ttl, None);
+    assert_eq!(schemas.resolve_state_ttl(GREETER_SERVICE_NAME), None);
+
+    updater = SchemaUpdater::new(schemas);
+    updater.modify_service(
+        GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            state_ttl: Some(Duration::from_secs(60 * 60)),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(
+        schemas.assert_service(GREETER_SERVICE_NAME).state_ttl,
+        Some(Duration::from_secs(60 * 60))
+    );
+    assert_eq!(
+        schemas.resolve_state_ttl(GREETER_SERVICE_NAME),
+        Some(Duration::from_secs(60 * 60))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn modify_completion_retention() -> Result<(), SchemaError> {
+    const COMPLETION_RETENTION: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+    let mut updater = SchemaUpdater::default();
+    updater.add_deployment(add_deployment_request(vec![greeter_service()]))?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(
+        schemas
+            .assert_service(GREETER_SERVICE_NAME)
+            .completion_retention,
+        None
+    );
+
+    updater = SchemaUpdater::new(schemas);
+    updater.modify_service(
+        GREETER_SERVICE_NAME,
+        ModifyServiceRequest {
+            completion_retention: Some(COMPLETION_RETENTION),
+            ..ModifyServiceRequest::default()
+        },
+    )?;
+    let schemas = updater.into_inner();
+
+    assert_eq!(
+        schemas
+            .assert_service(GREETER_SERVICE_NAME)
+            .completion_retention,
+        Some(COMPLETION_RETENTION)
+    );
+    // Applies to the invocations both with and without idempotency key
+    let target = schemas.assert_invocation_target(GREETER_SERVICE_NAME, GREET_HANDLER_NAME);
+    assert_eq!(
+        target.compute_retention(false).completion_retention,
+        COMPLETION_RETENTION
+    );
+    assert_eq!(
+        target.compute_retention(true).completion_retention,
+        COMPLETION_RETENTION
+    );
+
+    Ok(())
+}
+
 #[test]
 fn register_new_deployment_allow_breaking_changes() {
     let mut updater = SchemaUpdater::default();
@@ -857,6 +1191,67 @@ fn register_two_deployments_then_remove_second() {
     assert!(schemas.get_deployment(&deployment_id_2).is_none());
 }
 
+#[test]
+fn drain_latest_deployment() {
+    let ((_, deployment_id_1), schemas) =
+        SchemaUpdater::update_and_return(Schema::default(), |updater| {
+            updater.add_deployment(AddDeploymentRequest {
+                deployment_address: DeploymentAddress::mock_uri("http://localhost:9080"),
+                ..add_deployment_request(vec![greeter_service(), another_greeter_service()])
+            })
+        })
+        .unwrap();
+
+    let ((_, deployment_id_2), schemas) = SchemaUpdater::update_and_return(schemas, |updater| {
+        updater.add_deployment(AddDeploymentRequest {
+            deployment_address: DeploymentAddress::mock_uri("http://localhost:9081"),
+            ..add_deployment_request(vec![greeter_service()])
+        })
+    })
+    .unwrap();
+
+    let version_before_draining = schemas.version();
+    let drain_deadline = MillisSinceEpoch::new(1_000);
+    let schemas = SchemaUpdater::update(schemas, |updater| {
+        assert!(updater.drain_deployment(deployment_id_2, drain_deadline));
+        Ok::<(), Infallible>(())
+    })
+    .unwrap();
+
+    // New invocations are routed to the previous revision, while the drained deployment is still
+    // there for the invocations pinned to it
+    assert!(version_before_draining < schemas.version());
+    schemas.assert_service_deployment(GREETER_SERVICE_NAME, deployment_id_1);
+    schemas.assert_service_revision(GREETER_SERVICE_NAME, 1);
+    assert_eq!(
+        schemas
+            .get_deployment(&deployment_id_2)
+            .unwrap()
+            .drain_deadline,
+        Some(drain_deadline)
+    );
+    assert_eq!(
+        schemas
+            .get_deployment(&deployment_id_1)
+            .unwrap()
+            .drain_deadline,
+        None
+    );
+
+    // Registering the same endpoint again creates a new deployment
+    let ((add_result, deployment_id_3), schemas) =
+        SchemaUpdater::update_and_return(schemas, |updater| {
+            updater.add_deployment(AddDeploymentRequest {
+                deployment_address: DeploymentAddress::mock_uri("http://localhost:9081"),
+                ..add_deployment_request(vec![greeter_service()])
+            })
+        })
+        .unwrap();
+    assert_eq!(add_result, AddDeploymentResult::Created);
+    assert_ne!(deployment_id_3, deployment_id_2);
+    schemas.assert_service_deployment(GREETER_SERVICE_NAME, deployment_id_3);
+}
+
 mod remove_handler {
     use super::*;
 
@@ -998,6 +1393,90 @@ mod remove_handler {
     }
 }
 
+mod change_handler_signature {
+    use super::*;
+
+    use restate_test_util::{assert_eq, check, let_assert};
+    use test_log::test;
+
+    fn greeter_virtual_object_with_shared_handler() -> endpoint_manifest::Service {
+        let mut service = greeter_virtual_object();
+        service.handlers[0].ty = Some(endpoint_manifest::HandlerType::Shared);
+        service
+    }
+
+    #[test]
+    fn reject_changing_handler_type() {
+        let mut updater = SchemaUpdater::default();
+
+        updater
+            .add_deployment(AddDeploymentRequest {
+                deployment_address: DeploymentAddress::mock_uri("http://localhost:9080"),
+                ..add_deployment_request(vec![greeter_virtual_object()])
+            })
+            .unwrap();
+        let schemas = updater.into_inner();
+
+        updater = SchemaUpdater::new(schemas);
+        let rejection = updater
+            .add_deployment(AddDeploymentRequest {
+                deployment_address: DeploymentAddress::mock_uri("http://localhost:9081"),
+                ..add_deployment_request(vec![greeter_virtual_object_with_shared_handler()])
+            })
+            .unwrap_err();
+
+        let schemas = updater.into_inner();
+        schemas.assert_service_revision(GREETER_SERVICE_NAME, 1); // unchanged
+
+        let_assert!(
+            SchemaError::Service(ServiceError::IncompatibleHandlers(service, handlers)) = rejection
+        );
+        check!(service == GREETER_SERVICE_NAME);
+        check!(handlers == &[GREET_HANDLER_NAME]);
+    }
+
+    #[test]
+    fn reject_changing_handler_type_when_overwriting() {
+        let ((_, deployment_id), schemas) =
+            SchemaUpdater::update_and_return(Schema::default(), |updater| {
+                updater.add_deployment(add_deployment_request(vec![greeter_virtual_object()]))
+            })
+            .unwrap();
+        schemas.assert_service_deployment(GREETER_SERVICE_NAME, deployment_id);
+
+        let rejection = SchemaUpdater::new(schemas)
+            .add_deployment(AddDeploymentRequest {
+                overwrite: Overwrite::Yes,
+                ..add_deployment_request(vec![greeter_virtual_object_with_shared_handler()])
+            })
+            .unwrap_err();
+
+        let_assert!(SchemaError::Service(ServiceError::IncompatibleHandlers(..)) = rejection);
+    }
+
+    #[test]
+    fn works_with_allow_breaking_changes() {
+        let ((_, deployment_id), schemas) =
+            SchemaUpdater::update_and_return(Schema::default(), |updater| {
+                updater.add_deployment(add_deployment_request(vec![greeter_virtual_object()]))
+            })
+            .unwrap();
+
+        let ((result, new_deployment_id), schemas) =
+            SchemaUpdater::update_and_return(schemas, |updater| {
+                updater.add_deployment(AddDeploymentRequest {
+                    allow_breaking_changes: AllowBreakingChanges::Yes,
+                    overwrite: Overwrite::Yes,
+                    ..add_deployment_request(vec![greeter_virtual_object_with_shared_handler()])
+                })
+            })
+            .unwrap();
+        assert_eq!(result, AddDeploymentResult::Overwritten);
+        assert_eq!(new_deployment_id, deployment_id);
+        schemas.assert_service_revision(GREETER_SERVICE_NAME, 2);
+    }
+}
+
 #[test]
 fn update_latest_deployment() {
     let mut updater = SchemaUpdater::default();
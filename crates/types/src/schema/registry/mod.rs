@@ -33,6 +33,7 @@ use crate::schema::metadata::updater;
 use crate::schema::metadata::updater::{SchemaError, SchemaUpdater, ServiceError};
 use crate::schema::service::{HandlerMetadata, ServiceMetadata, ServiceMetadataResolver};
 use crate::schema::subscriptions::{ListSubscriptionFilter, Subscription, SubscriptionResolver};
+use crate::time::MillisSinceEpoch;
 
 pub use crate::schema::metadata::updater::{
     AddDeploymentResult, AllowBreakingChanges, ModifyServiceRequest, Overwrite,
@@ -58,7 +59,8 @@ impl SchemaRegistryError {
             SchemaRegistryErrorInner::Schema(schema_error) => match schema_error {
                 SchemaError::NotFound(_) => StatusCode::NOT_FOUND,
                 SchemaError::Service(ServiceError::DifferentType { .. })
-                | SchemaError::Service(ServiceError::RemovedHandlers { .. }) => {
+                | SchemaError::Service(ServiceError::RemovedHandlers { .. })
+                | SchemaError::Service(ServiceError::IncompatibleHandlers { .. }) => {
                     StatusCode::CONFLICT
                 }
                 SchemaError::Service(_) => StatusCode::BAD_REQUEST,
@@ -472,6 +474,33 @@ impl<Metadata: MetadataService, Discovery, Telemetry>
         Ok(())
     }
 
+    /// Stops routing new invocations to the given deployment, which stays registered for the
+    /// in-flight invocations pinned to it until it gets deleted.
+    pub async fn drain_deployment(
+        &self,
+        deployment_id: DeploymentId,
+        drain_deadline: MillisSinceEpoch,
+    ) -> Result<(), SchemaRegistryError> {
+        self.metadata_service
+            .update(|schema| {
+                Ok((
+                    (),
+                    SchemaUpdater::update(schema, |updater| {
+                        if updater.drain_deployment(deployment_id, drain_deadline) {
+                            Ok(())
+                        } else {
+                            Err(SchemaError::NotFound(format!(
+                                "deployment with id '{deployment_id}'"
+                            )))
+                        }
+                    })?,
+                ))
+            })
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn modify_service(
         &self,
         service_name: String,
@@ -9,7 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use std::collections::HashMap;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::time::Duration;
 
 use serde::Deserialize;
@@ -42,9 +42,17 @@ pub trait ServiceMetadataResolver {
     fn list_services(&self) -> Vec<ServiceMetadata>;
 
     fn list_service_names(&self) -> Vec<String>;
+
+    /// Resolves the service exposed through an [`IngressRoute`] matching the given request host
+    /// and path, returning the service name together with the path following the route prefix.
+    fn resolve_ingress_route<'a>(
+        &self,
+        host: Option<&str>,
+        path: &'a str,
+    ) -> Option<(String, &'a str)>;
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ServiceMetadata {
     /// # Name
@@ -181,6 +189,57 @@ pub struct ServiceMetadata {
     #[serde(default = "restate_serde_util::default::bool::<false>")]
     pub enable_lazy_state: bool,
 
+    /// # Max journal length
+    ///
+    /// Maximum number of journal entries of an invocation of this service. Invocations exceeding
+    /// it fail with a terminal error.
+    ///
+    /// If not overridden for this service, this returns the default max journal length
+    /// configured in invocation options. Unset means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_journal_length: Option<NonZeroU32>,
+
+    /// # State ttl
+    ///
+    /// Time to live of the state entries set by the invocations of this service, after which
+    /// they're not returned anymore and eventually dropped. Unset means the state entries
+    /// never expire.
+    #[serde(
+        with = "serde_with::As::<Option<FriendlyDuration>>",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>" /* TODO(slinkydeveloper) https://github.com/restatedev/restate/issues/3766 */))]
+    pub state_ttl: Option<Duration>,
+
+    /// # Completion retention
+    ///
+    /// The retention duration of the completed invocations of this service, including the ones
+    /// without idempotency key. Idempotent invocations and workflows are retained for at least
+    /// the `idempotency_retention` and `workflow_completion_retention` respectively. Unset means
+    /// only idempotent invocations, workflows and the invocations with a journal retention are
+    /// retained.
+    #[serde(
+        with = "serde_with::As::<Option<FriendlyDuration>>",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>" /* TODO(slinkydeveloper) https://github.com/restatedev/restate/issues/3766 */))]
+    pub completion_retention: Option<Duration>,
+
+    /// # Ingress route
+    ///
+    /// Custom route exposing this service through the ingress, in addition to the
+    /// `/<SERVICE_NAME>` paths.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_route: Option<IngressRoute>,
+
+    /// # Ingress limits
+    ///
+    /// Rate limits and concurrency caps of the ingress requests to this service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_limits: Option<IngressLimits>,
+
     /// # Retry policy
     ///
     /// Retry policy applied to invocations of this service.
@@ -204,6 +263,148 @@ impl restate_serde_util::MapAsVecItem for ServiceMetadata {
     }
 }
 
+/// # Ingress route
+///
+/// Custom route of a service in the ingress. Requests matching the route address the handlers
+/// of the service without the `/<SERVICE_NAME>` path segment, e.g. routing the host
+/// `payments.example.com` to the service `PaymentService` exposes its `charge` handler at
+/// `https://payments.example.com/charge`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct IngressRoute {
+    /// # Host
+    ///
+    /// Host the requests must target, without port, e.g. `payments.example.com`.
+    /// If unset, the route matches requests targeting any host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+
+    /// # Path prefix
+    ///
+    /// Path prefix replacing `/<SERVICE_NAME>` in the ingress paths of the service,
+    /// e.g. `/payments`. If unset, the route matches every path of the host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+}
+
+impl IngressRoute {
+    pub fn is_empty(&self) -> bool {
+        self.host.is_none() && self.path_prefix.is_none()
+    }
+
+    /// Returns the path following the route prefix, if the given host and path match the route.
+    pub fn strip_path<'a>(&self, host: Option<&str>, path: &'a str) -> Option<&'a str> {
+        if self.host.as_ref().is_some_and(|route_host| {
+            !host.is_some_and(|host| host.eq_ignore_ascii_case(route_host))
+        }) {
+            return None;
+        }
+        match &self.path_prefix {
+            None => Some(path),
+            Some(path_prefix) => {
+                let rest = path.strip_prefix(path_prefix.as_str())?;
+                (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+            }
+        }
+    }
+
+    /// Routes with a host take precedence over the ones without, then the longest path prefix wins.
+    fn precedence(&self) -> (bool, usize) {
+        (
+            self.host.is_some(),
+            self.path_prefix
+                .as_ref()
+                .map(String::len)
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// Picks among the given `(service_name, route)` pairs the route matching the request host and
+/// path, see [`ServiceMetadataResolver::resolve_ingress_route`].
+pub fn match_ingress_route<'r, 'a>(
+    routes: impl IntoIterator<Item = (&'r str, &'r IngressRoute)>,
+    host: Option<&str>,
+    path: &'a str,
+) -> Option<(String, &'a str)> {
+    routes
+        .into_iter()
+        .filter_map(|(service_name, route)| {
+            Some((
+                service_name,
+                route.precedence(),
+                route.strip_path(host, path)?,
+            ))
+        })
+        .max_by_key(|(_, precedence, _)| *precedence)
+        .map(|(service_name, _, rest)| (service_name.to_owned(), rest))
+}
+
+/// # Ingress limits
+///
+/// Limits of the requests accepted by the ingress for a service. Requests exceeding them are
+/// rejected before being dispatched, with `429 Too Many Requests`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct IngressLimits {
+    /// # Service limit
+    ///
+    /// Limit shared by the requests to all the handlers of the service.
+    #[serde(default, skip_serializing_if = "IngressLimit::is_empty")]
+    pub service: IngressLimit,
+
+    /// # Handler limits
+    ///
+    /// Limits of the requests to single handlers, applied in addition to the service limit.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub handlers: HashMap<String, IngressLimit>,
+}
+
+impl IngressLimits {
+    pub fn is_empty(&self) -> bool {
+        self.service.is_empty() && self.handlers.values().all(IngressLimit::is_empty)
+    }
+}
+
+/// # Ingress limit
+///
+/// Token bucket rate limit and concurrency cap of ingress requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct IngressLimit {
+    /// # Requests per second
+    ///
+    /// Rate at which requests are accepted. If unset, the rate is not limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_second: Option<NonZeroU32>,
+
+    /// # Burst
+    ///
+    /// Number of requests which can be accepted at once, exceeding the rate until it's
+    /// recovered. Defaults to the requests per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<NonZeroU32>,
+
+    /// # Max concurrency
+    ///
+    /// Maximum number of requests in flight at the same time. If unset, the concurrency is not
+    /// limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<NonZeroU32>,
+}
+
+impl IngressLimit {
+    pub fn is_empty(&self) -> bool {
+        self.requests_per_second.is_none() && self.max_concurrency.is_none()
+    }
+
+    /// Returns the burst of the rate limit, if the rate is limited.
+    pub fn effective_burst(&self) -> Option<NonZeroU32> {
+        self.requests_per_second
+            .map(|requests_per_second| self.burst.unwrap_or(requests_per_second))
+    }
+}
+
 fn default_idempotency_retention() -> Duration {
     DEFAULT_IDEMPOTENCY_RETENTION
 }
@@ -305,7 +506,7 @@ impl From<InvocationTargetType> for Option<HandlerMetadataType> {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct HandlerMetadata {
     /// # Name
@@ -549,6 +750,20 @@ pub mod test_util {
         fn list_service_names(&self) -> Vec<String> {
             self.0.values().map(|s| s.name.clone()).collect()
         }
+
+        fn resolve_ingress_route<'a>(
+            &self,
+            host: Option<&str>,
+            path: &'a str,
+        ) -> Option<(String, &'a str)> {
+            match_ingress_route(
+                self.0
+                    .values()
+                    .filter_map(|s| Some((s.name.as_str(), s.ingress_route.as_ref()?))),
+                host,
+                path,
+            )
+        }
     }
 
     impl ServiceMetadata {
@@ -596,6 +811,11 @@ pub mod test_util {
                 inactivity_timeout: Duration::from_secs(60),
                 abort_timeout: Duration::from_secs(60),
                 enable_lazy_state: false,
+                max_journal_length: None,
+                state_ttl: None,
+                completion_retention: None,
+                ingress_route: None,
+                ingress_limits: None,
                 retry_policy: Default::default(),
                 info: vec![],
             }
@@ -645,6 +865,11 @@ pub mod test_util {
                 inactivity_timeout: Duration::from_secs(60),
                 abort_timeout: Duration::from_secs(60),
                 enable_lazy_state: false,
+                max_journal_length: None,
+                state_ttl: None,
+                completion_retention: None,
+                ingress_route: None,
+                ingress_limits: None,
                 retry_policy: Default::default(),
                 info: vec![],
             }
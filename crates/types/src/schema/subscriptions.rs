@@ -21,6 +21,7 @@ use crate::invocation::{VirtualObjectHandlerType, WorkflowHandlerType};
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Source {
     Kafka { cluster: String, topic: String },
+    Sqs { connection: String, queue: String },
 }
 
 impl fmt::Display for Source {
@@ -29,6 +30,9 @@ impl fmt::Display for Source {
             Source::Kafka { cluster, topic, .. } => {
                 write!(f, "kafka://{cluster}/{topic}")
             }
+            Source::Sqs { connection, queue } => {
+                write!(f, "sqs://{connection}/{queue}")
+            }
         }
     }
 }
@@ -488,3 +488,31 @@ mod pb_into {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_highest_mutually_supported_version() {
+        assert_eq!(
+            ServiceProtocolVersion::pick(&(5..=6)),
+            Some(ServiceProtocolVersion::V6)
+        );
+        assert_eq!(
+            ServiceProtocolVersion::pick(&(5..=MAX_SERVICE_PROTOCOL_VERSION_VALUE)),
+            Some(MAX_INFLIGHT_SERVICE_PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            ServiceProtocolVersion::pick(&(1..=1)),
+            Some(ServiceProtocolVersion::V1)
+        );
+    }
+
+    #[test]
+    fn pick_without_overlap() {
+        let above = MAX_INFLIGHT_SERVICE_PROTOCOL_VERSION.as_repr() + 1;
+        assert_eq!(ServiceProtocolVersion::pick(&(above..=above + 1)), None);
+        assert_eq!(ServiceProtocolVersion::pick(&(-2..=0)), None);
+    }
+}
@@ -8,19 +8,232 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::identifiers::PartitionId;
 use crate::time::MillisSinceEpoch;
+use enumset::{EnumSet, EnumSetType};
+use parking_lot::Mutex;
 use std::borrow::Borrow;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::{mpsc, oneshot, watch};
 
 pub trait Timer: Hash + Eq + Borrow<Self::TimerKey> {
     type TimerKey: TimerKey + Send;
 
     fn timer_key(&self) -> &Self::TimerKey;
+
+    /// Estimated number of bytes the timer occupies in memory, including its heap allocations.
+    fn estimated_memory_size(&self) -> usize {
+        size_of_val(self)
+    }
 }
 
 /// Timer key establishes an absolute order on [`Timer`]. Naturally, this should be the key under
-/// which the timer value is stored and can be retrieved.
+/// which the timer value is stored and can be retrieved. The order must be consistent with the
+/// wake-up time, that is timers waking up earlier must have smaller keys.
 pub trait TimerKey: Ord + Clone + Hash + Debug {
     fn wake_up_time(&self) -> MillisSinceEpoch;
 }
+
+/// Kind of the timers fired by the partition processors.
+///
+/// The firing of a kind of timers can be paused on a node, e.g. to stop the cleanup of completed
+/// invocations during an incident. Firing of paused timers is deferred until they're resumed, or
+/// until another node takes over the leadership of the partition.
+#[derive(
+    Debug, Hash, EnumSetType, Ord, PartialOrd, strum::Display, serde::Serialize, serde::Deserialize,
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[enumset(serialize_repr = "list")]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum TimerKind {
+    /// Completes a sleep of an invocation
+    Sleep,
+    /// Starts an invocation scheduled with a delay
+    DelayedInvocation,
+    /// Cleans a completed invocation once its retention expired
+    Cleanup,
+    /// Fails an invocation still running once its deadline expired
+    Deadline,
+}
+
+/// Priority class of the commands fired by the timers of a kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum TimerPriority {
+    /// Timers user-facing traffic waits on, like sleeps and delayed invocations
+    Interactive,
+    /// Timers maintaining the partition, like the cleanup of completed invocations
+    Housekeeping,
+}
+
+static PAUSED_TIMER_KINDS: LazyLock<watch::Sender<EnumSet<TimerKind>>> =
+    LazyLock::new(|| watch::Sender::new(EnumSet::empty()));
+
+static TIMER_STATS: [TimerKindCounters; 4] = [const { TimerKindCounters::new() }; 4];
+
+/// Kinds of timers whose firing is paused on this node.
+pub fn paused_timer_kinds() -> EnumSet<TimerKind> {
+    *PAUSED_TIMER_KINDS.borrow()
+}
+
+/// Pauses the firing of the given kinds of timers on this node, resuming all the other kinds.
+pub fn set_paused_timer_kinds(kinds: EnumSet<TimerKind>) {
+    PAUSED_TIMER_KINDS.send_replace(kinds);
+}
+
+/// Watches the kinds of timers whose firing is paused on this node.
+pub fn watch_paused_timer_kinds() -> watch::Receiver<EnumSet<TimerKind>> {
+    PAUSED_TIMER_KINDS.subscribe()
+}
+
+/// Statistics of a kind of timers on this node, since it started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TimerKindStats {
+    pub kind: TimerKind,
+    pub paused: bool,
+    /// Timers fired by the leaders on this node
+    pub fired: u64,
+    /// Timers which are due but whose firing is deferred, because their kind is paused
+    pub deferred: u64,
+}
+
+impl TimerKind {
+    pub fn priority(self) -> TimerPriority {
+        match self {
+            TimerKind::Sleep | TimerKind::DelayedInvocation | TimerKind::Deadline => {
+                TimerPriority::Interactive
+            }
+            TimerKind::Cleanup => TimerPriority::Housekeeping,
+        }
+    }
+
+    /// Records that a timer of this kind fired.
+    pub fn record_fired(self) {
+        TIMER_STATS[self as usize]
+            .fired
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the firing of a timer of this kind is deferred, respectively is not deferred
+    /// anymore, because it fired or the partition leader stepped down.
+    pub fn record_deferred(self, deferred: bool) {
+        let counter = &TIMER_STATS[self as usize].deferred;
+        if deferred {
+            counter.fetch_add(1, Ordering::Relaxed);
+        } else {
+            counter.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(self) -> TimerKindStats {
+        let counters = &TIMER_STATS[self as usize];
+        TimerKindStats {
+            kind: self,
+            paused: paused_timer_kinds().contains(self),
+            fired: counters.fired.load(Ordering::Relaxed),
+            deferred: counters.deferred.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct TimerKindCounters {
+    fired: AtomicU64,
+    deferred: AtomicU64,
+}
+
+impl TimerKindCounters {
+    const fn new() -> Self {
+        Self {
+            fired: AtomicU64::new(0),
+            deferred: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Statistics of the timer service of a partition led by this node.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TimerServiceStats {
+    pub partition_id: PartitionId,
+    /// Timers loaded in memory, the others are loaded from storage once they're due
+    pub loaded_timers: usize,
+    /// Wake-up time of the next timer to fire
+    pub next_wake_up_time: Option<MillisSinceEpoch>,
+    /// Timers fired during the last second
+    pub fired_per_second: u64,
+}
+
+/// Timer loaded in memory by a partition leader, waiting to fire.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PendingTimer {
+    pub wake_up_time: MillisSinceEpoch,
+    pub kind: TimerKind,
+    pub description: String,
+}
+
+/// Introspection request served by the timer service of a partition leader.
+#[derive(Debug)]
+pub enum TimerServiceRequest {
+    Stats(oneshot::Sender<TimerServiceStats>),
+    /// Lists the first `limit` pending timers in the order they fire
+    ListPendingTimers {
+        limit: usize,
+        reply: oneshot::Sender<Vec<PendingTimer>>,
+    },
+}
+
+static TIMER_SERVICES: LazyLock<Mutex<BTreeMap<PartitionId, mpsc::Sender<TimerServiceRequest>>>> =
+    LazyLock::new(Default::default);
+
+/// Registers the timer service of a partition whose leader runs on this node, returning the
+/// introspection requests it needs to serve.
+pub fn register_timer_service(partition_id: PartitionId) -> mpsc::Receiver<TimerServiceRequest> {
+    let (tx, rx) = mpsc::channel(16);
+    TIMER_SERVICES.lock().insert(partition_id, tx);
+    rx
+}
+
+pub fn deregister_timer_service(partition_id: PartitionId) {
+    TIMER_SERVICES.lock().remove(&partition_id);
+}
+
+/// Timer services of the partitions led by this node, ordered by partition.
+pub fn timer_services() -> Vec<(PartitionId, mpsc::Sender<TimerServiceRequest>)> {
+    TIMER_SERVICES
+        .lock()
+        .iter()
+        .map(|(partition_id, tx)| (*partition_id, tx.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_kind_stats() {
+        TimerKind::Cleanup.record_fired();
+        TimerKind::Cleanup.record_deferred(true);
+        TimerKind::Cleanup.record_deferred(true);
+        TimerKind::Cleanup.record_deferred(false);
+        set_paused_timer_kinds(TimerKind::Cleanup.into());
+
+        assert_eq!(
+            TimerKind::Cleanup.stats(),
+            TimerKindStats {
+                kind: TimerKind::Cleanup,
+                paused: true,
+                fired: 1,
+                deferred: 1,
+            }
+        );
+        assert!(!TimerKind::Sleep.stats().paused);
+    }
+}
@@ -12,6 +12,7 @@ use std::ops::RangeInclusive;
 
 use restate_types::identifiers::{LeaderEpoch, PartitionId, PartitionKey};
 use restate_types::logs::{Keys, Lsn};
+use restate_types::partitions::settings::PartitionSettings;
 use restate_types::schema::Schema;
 use restate_types::time::MillisSinceEpoch;
 use restate_types::{GenerationalNodeId, SemanticRestateVersion};
@@ -73,3 +74,49 @@ pub struct UpsertSchema {
     pub partition_key_range: Keys,
     pub schema: Schema,
 }
+
+/// Consistently store the partition settings across partition replicas, see
+/// [`PartitionSettings`].
+///
+/// Since v1.6.0.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpdatePartitionSettings {
+    pub partition_key_range: Keys,
+    pub settings: PartitionSettings,
+}
+
+/// Parks the command at `lsn` of the partition with the same `partition_id`, instead of applying
+/// it, so that the partition can make progress with the subsequent commands.
+///
+/// It's proposed by the designated leader of the partition once it exhausted its attempts to apply
+/// the command. Since the replicas can't read past the command they fail to apply, they look up
+/// this command in the log ahead of their read pointer, and park the command with the attempts and
+/// failure recorded here. When reached in the log, the command is a no-op.
+///
+/// Since v1.6.0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParkCommand {
+    pub partition_id: PartitionId,
+    /// Lsn of the command to park.
+    pub lsn: Lsn,
+    pub parked_at: MillisSinceEpoch,
+    /// Number of failed attempts of the leader to apply the command.
+    pub attempts: u32,
+    /// The last failure observed by the leader while applying the command.
+    pub failure: String,
+}
+
+/// Re-applies a command that was previously parked by the partition processor after repeatedly
+/// failing to be applied. Like [`PartitionDurability`], this only applies to the partition with
+/// the same `partition_id`; other partitions ignore it at replay time.
+///
+/// Since v1.6.0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryParkedCommand {
+    pub partition_id: PartitionId,
+    /// Lsn of the parked command to retry.
+    pub lsn: Lsn,
+}
@@ -8,18 +8,22 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use restate_storage_api::completion_event_table::CompletionEventSequence;
 use restate_storage_api::deduplication_table::DedupInformation;
 use restate_types::identifiers::{LeaderEpoch, PartitionId, PartitionKey, WithPartitionKey};
 use restate_types::invocation::{
-    AttachInvocationRequest, GetInvocationOutputResponse, InvocationResponse,
-    InvocationTermination, NotifySignalRequest, PurgeInvocationRequest,
+    AttachInvocationRequest, CompletePendingEntryRequest, GetInvocationOutputResponse,
+    InvocationResponse, InvocationTermination, NotifySignalRequest, PurgeInvocationRequest,
     RestartAsNewInvocationRequest, ResumeInvocationRequest, ServiceInvocation,
 };
 use restate_types::logs::{self, HasRecordKeys, Keys, MatchKeyQuery};
 use restate_types::message::MessageIndex;
 use restate_types::state_mut::ExternalStateMutation;
 
-use crate::control::{AnnounceLeader, UpsertSchema, VersionBarrier};
+use crate::control::{
+    AnnounceLeader, ParkCommand, RetryParkedCommand, UpdatePartitionSettings, UpsertSchema,
+    VersionBarrier,
+};
 use crate::timer::TimerKeyValue;
 
 use self::control::PartitionDurability;
@@ -170,6 +174,9 @@ pub enum Command {
     InvokerEffect(Box<restate_invoker_api::Effect>),
     /// Timer has fired
     Timer(TimerKeyValue),
+    /// Timers have fired, applied in the given order
+    /// *Since v1.6.0
+    TimerBatch(Vec<TimerKeyValue>),
     /// Schedule timer
     ScheduleTimer(TimerKeyValue),
     /// Another partition processor is reporting a response of an invocation we requested.
@@ -189,6 +196,30 @@ pub enum Command {
     /// Upsert schema for consistent schema across replicas
     /// *Since v1.6.0
     UpsertSchema(UpsertSchema),
+
+    /// Update the partition settings for consistent settings across replicas
+    /// *Since v1.6.0
+    UpdatePartitionSettings(UpdatePartitionSettings),
+
+    /// Park a command of this partition which repeatedly failed to be applied.
+    /// See [`ParkCommand`] for more details.
+    /// *Since v1.6.0
+    ParkCommand(ParkCommand),
+
+    /// Re-apply a parked command of this partition.
+    /// See [`RetryParkedCommand`] for more details.
+    /// *Since v1.6.0
+    RetryParkedCommand(RetryParkedCommand),
+
+    /// Truncate the completion events up to, and including, the specified sequence, after the
+    /// leader delivered them to the completion sink.
+    /// *Since v1.6.0
+    TruncateCompletionEvents(CompletionEventSequence),
+
+    /// Manually complete a pending journal entry of an invocation.
+    /// See [`CompletePendingEntryRequest`] for more details.
+    /// *Since v1.6.0
+    CompletePendingEntry(CompletePendingEntryRequest),
 }
 
 impl Command {
@@ -228,6 +259,7 @@ impl HasRecordKeys for Envelope {
             // todo: Remove this, or pass the partition key range but filter based on partition-id
             // on read if needed.
             Command::TruncateOutbox(_) => Keys::Single(self.partition_key()),
+            Command::TruncateCompletionEvents(_) => Keys::Single(self.partition_key()),
             Command::ProxyThrough(_) => Keys::Single(self.partition_key()),
             Command::AttachInvocation(_) => Keys::Single(self.partition_key()),
             Command::ResumeInvocation(req) => Keys::Single(req.partition_key()),
@@ -235,11 +267,26 @@ impl HasRecordKeys for Envelope {
             // todo: Handle journal entries that request cross-partition invocations
             Command::InvokerEffect(effect) => Keys::Single(effect.invocation_id.partition_key()),
             Command::Timer(timer) => Keys::Single(timer.invocation_id().partition_key()),
+            Command::TimerBatch(timers) => {
+                let partition_keys = timers
+                    .iter()
+                    .map(|timer| timer.invocation_id().partition_key());
+                match (partition_keys.clone().min(), partition_keys.max()) {
+                    (Some(min), Some(max)) => Keys::RangeInclusive(min..=max),
+                    _ => Keys::Single(self.partition_key()),
+                }
+            }
             Command::ScheduleTimer(timer) => Keys::Single(timer.invocation_id().partition_key()),
             Command::InvocationResponse(response) => Keys::Single(response.partition_key()),
             Command::NotifySignal(sig) => Keys::Single(sig.partition_key()),
             Command::NotifyGetInvocationOutputResponse(res) => Keys::Single(res.partition_key()),
+            Command::CompletePendingEntry(req) => Keys::Single(req.partition_key()),
             Command::UpsertSchema(schema) => schema.partition_key_range.clone(),
+            Command::UpdatePartitionSettings(update) => update.partition_key_range.clone(),
+            // targets the partition by ID, see `UpdatePartitionDurability` above.
+            Command::ParkCommand(_) | Command::RetryParkedCommand(_) => {
+                Keys::Single(self.partition_key())
+            }
         }
     }
 }
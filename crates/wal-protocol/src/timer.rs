@@ -12,6 +12,7 @@ use restate_storage_api::timer_table::{Timer, TimerKey, TimerKeyKind};
 use restate_types::identifiers::{EntryIndex, InvocationId};
 use restate_types::invocation::{InvocationEpoch, ServiceInvocation};
 use restate_types::time::MillisSinceEpoch;
+use restate_types::timer::TimerKind;
 use std::borrow::Borrow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -68,6 +69,19 @@ impl TimerKeyValue {
         Self { timer_key, value }
     }
 
+    pub fn invocation_deadline(
+        wake_up_time: MillisSinceEpoch,
+        invocation_id: InvocationId,
+        invocation_creation_time: MillisSinceEpoch,
+    ) -> Self {
+        let (timer_key, value) = Timer::invocation_deadline(
+            wake_up_time.as_u64(),
+            invocation_id,
+            invocation_creation_time,
+        );
+        Self { timer_key, value }
+    }
+
     pub fn into_inner(self) -> (TimerKey, Timer) {
         (self.timer_key, self.value)
     }
@@ -87,6 +101,10 @@ impl TimerKeyValue {
     pub fn wake_up_time(&self) -> MillisSinceEpoch {
         MillisSinceEpoch::from(self.timer_key.timestamp)
     }
+
+    pub fn kind(&self) -> TimerKind {
+        self.value.kind()
+    }
 }
 
 impl Hash for TimerKeyValue {
@@ -116,6 +134,27 @@ impl restate_types::timer::Timer for TimerKeyValue {
     fn timer_key(&self) -> &Self::TimerKey {
         &self.timer_key
     }
+
+    fn estimated_memory_size(&self) -> usize {
+        size_of::<Self>()
+            + match &self.value {
+                Timer::Invoke(service_invocation) => {
+                    size_of::<ServiceInvocation>()
+                        + service_invocation.argument.len()
+                        + service_invocation
+                            .headers
+                            .iter()
+                            .map(|header| {
+                                size_of_val(header) + header.name.len() + header.value.len()
+                            })
+                            .sum::<usize>()
+                }
+                Timer::CompleteJournalEntry(..)
+                | Timer::CleanInvocationStatus(_)
+                | Timer::NeoInvoke(_)
+                | Timer::InvocationDeadline(..) => 0,
+            }
+    }
 }
 
 // Helper to display timer key
@@ -141,6 +180,9 @@ impl fmt::Display for TimerKeyDisplay<'_> {
             TimerKeyKind::CleanInvocationStatus { invocation_uuid } => {
                 write!(f, "Clean invocation status '{invocation_uuid}'")
             }
+            TimerKeyKind::InvocationDeadline { invocation_uuid } => {
+                write!(f, "Deadline of invocation '{invocation_uuid}'")
+            }
         }
     }
 }
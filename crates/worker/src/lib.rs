@@ -13,6 +13,7 @@ extern crate core;
 mod error;
 mod handle;
 mod invoker_integration;
+mod lifecycle_webhook;
 mod metric_definitions;
 mod partition;
 mod partition_processor_manager;
@@ -22,7 +23,7 @@ mod subscription_integration;
 use std::sync::Arc;
 
 use codederror::CodedError;
-use tracing::info;
+use tracing::{info, warn};
 
 use restate_bifrost::Bifrost;
 use restate_core::MetadataKind;
@@ -49,17 +50,25 @@ use restate_types::Versioned;
 use restate_types::config::Configuration;
 use restate_types::health::HealthStatus;
 use restate_types::partitions::state::PartitionReplicaSetStates;
+use restate_types::payload_encryption::{
+    EncryptionError, PayloadEncryption, set_payload_encryption,
+};
 use restate_types::protobuf::common::WorkerStatus;
 use restate_types::schema::subscriptions::SubscriptionResolver;
 
+use crate::lifecycle_webhook::{LifecycleEventSender, LifecycleWebhook};
 use crate::partition::invoker_storage_reader::InvokerStorageReader;
 use crate::partition_processor_manager::PartitionProcessorManager;
 
 pub use self::error::*;
 pub use self::handle::*;
+pub use self::lifecycle_webhook::LifecycleWebhookError;
 pub use crate::subscription_controller::SubscriptionController;
 pub use crate::subscription_integration::SubscriptionControllerHandle;
 
+#[cfg(any(test, feature = "test-util"))]
+pub use restate_timer::ManualClock;
+
 type PartitionProcessorBuilder = partition::PartitionProcessorBuilder<
     InvokerChannelServiceHandle<InvokerStorageReader<PartitionStore>>,
 >;
@@ -86,6 +95,10 @@ pub enum BuildError {
     ),
     #[code(unknown)]
     Invoker(#[from] restate_invoker_impl::BuildError),
+    #[code(unknown)]
+    LifecycleWebhook(#[from] LifecycleWebhookError),
+    #[code(unknown)]
+    PayloadEncryption(#[from] EncryptionError),
     #[error("failed constructing partition snapshot repository: {0}")]
     #[code(unknown)]
     SnapshotRepository(#[from] anyhow::Error),
@@ -97,6 +110,7 @@ pub struct Worker {
     ingress_kafka: IngressKafkaService,
     subscription_controller_handle: SubscriptionControllerHandle,
     partition_processor_manager: PartitionProcessorManager,
+    lifecycle_webhook: Option<LifecycleWebhook>,
 }
 
 impl Worker {
@@ -118,6 +132,14 @@ impl Worker {
         let config = Configuration::pinned();
         let metadata = Metadata::current();
 
+        if !config.worker.paused_timer_kinds.is_empty() {
+            warn!(
+                "Firing of the {:?} timers is paused by the configuration",
+                config.worker.paused_timer_kinds
+            );
+        }
+        restate_types::timer::set_paused_timer_kinds(config.worker.paused_timer_kinds);
+
         let schema = metadata.updateable_schema();
 
         // ingress_kafka
@@ -134,6 +156,24 @@ impl Worker {
             )));
         }
 
+        set_payload_encryption(
+            config
+                .worker
+                .payload_encryption
+                .as_ref()
+                .map(PayloadEncryption::from_options)
+                .transpose()?,
+        );
+
+        let (lifecycle_webhook, lifecycle_event_sender) =
+            match &config.worker.invocation_lifecycle_webhook {
+                Some(options) => {
+                    let (webhook, sender) = LifecycleWebhook::create(options)?;
+                    (Some(webhook), sender)
+                }
+                None => (None, LifecycleEventSender::default()),
+            };
+
         let partition_processor_manager = PartitionProcessorManager::new(
             health_status,
             Configuration::live(),
@@ -148,7 +188,8 @@ impl Worker {
             )
             .await
             .map_err(BuildError::SnapshotRepository)?,
-        );
+        )
+        .with_lifecycle_event_sender(lifecycle_event_sender);
 
         let remote_scanner_manager = RemoteScannerManager::new(
             create_remote_scanner_service(networking),
@@ -173,9 +214,19 @@ impl Worker {
             ingress_kafka,
             subscription_controller_handle,
             partition_processor_manager,
+            lifecycle_webhook,
         })
     }
 
+    /// Replaces the wall clock of the partition processors' timer services with the given
+    /// [`ManualClock`](restate_timer::ManualClock). Timers only fire once the test advances the
+    /// clock past their wake up time.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn with_timer_clock(mut self, clock: restate_timer::ManualClock) -> Self {
+        self.partition_processor_manager = self.partition_processor_manager.with_timer_clock(clock);
+        self
+    }
+
     pub fn storage_query_context(&self) -> &QueryContext {
         &self.storage_query_context
     }
@@ -206,6 +257,14 @@ impl Worker {
                 .run(Configuration::map_live(|c| &c.ingress)),
         )?;
 
+        if let Some(lifecycle_webhook) = self.lifecycle_webhook {
+            TaskCenter::spawn_child(
+                TaskKind::SystemService,
+                "invocation-lifecycle-webhook",
+                lifecycle_webhook.run(),
+            )?;
+        }
+
         self.partition_processor_manager.run().await?;
         info!("Worker role has stopped");
 
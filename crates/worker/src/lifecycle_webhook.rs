@@ -0,0 +1,248 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::str::FromStr;
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+use metrics::counter;
+use serde::{Serialize, Serializer};
+use tokio::sync::mpsc;
+use tracing::{debug, trace, warn};
+
+use restate_core::cancellation_watcher;
+use restate_types::config::LifecycleWebhookOptions;
+use restate_types::errors::InvocationError;
+use restate_types::identifiers::InvocationId;
+use restate_types::invocation::InvocationTarget;
+use restate_types::time::MillisSinceEpoch;
+
+use crate::metric_definitions::LIFECYCLE_WEBHOOK_EVENTS;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvocationLifecycleEventKind {
+    Started,
+    Suspended,
+    Completed,
+    Failed,
+}
+
+/// Event sent as JSON body to the invocation lifecycle webhook.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct InvocationLifecycleEvent {
+    #[serde(rename = "type")]
+    pub kind: InvocationLifecycleEventKind,
+    pub invocation_id: InvocationId,
+    pub service_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_key: Option<String>,
+    pub handler_name: String,
+    /// Creation time of the log record which caused the event.
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub timestamp: MillisSinceEpoch,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<InvocationLifecycleEventError>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct InvocationLifecycleEventError {
+    pub code: u16,
+    pub message: String,
+}
+
+impl InvocationLifecycleEvent {
+    pub fn new(
+        kind: InvocationLifecycleEventKind,
+        invocation_id: InvocationId,
+        invocation_target: &InvocationTarget,
+        timestamp: MillisSinceEpoch,
+    ) -> Self {
+        Self {
+            kind,
+            invocation_id,
+            service_name: invocation_target.service_name().to_string(),
+            service_key: invocation_target.key().map(ToString::to_string),
+            handler_name: invocation_target.handler_name().to_string(),
+            timestamp,
+            error: None,
+        }
+    }
+
+    pub fn with_error(mut self, error: &InvocationError) -> Self {
+        self.error = Some(InvocationLifecycleEventError {
+            code: error.code().into(),
+            message: error.message().to_owned(),
+        });
+        self
+    }
+}
+
+fn serialize_timestamp<S: Serializer>(
+    timestamp: &MillisSinceEpoch,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&timestamp.into_timestamp())
+}
+
+/// Handle the partition leaders use to emit the lifecycle events. Sending never blocks: events
+/// are dropped if the webhook doesn't keep up. The default handle drops every event.
+#[derive(Debug, Clone, Default)]
+pub struct LifecycleEventSender(Option<mpsc::Sender<InvocationLifecycleEvent>>);
+
+impl LifecycleEventSender {
+    pub fn send(&self, event: InvocationLifecycleEvent) {
+        let Some(tx) = &self.0 else {
+            return;
+        };
+        if let Err(err) = tx.try_send(event) {
+            counter!(LIFECYCLE_WEBHOOK_EVENTS, "outcome" => "dropped").increment(1);
+            trace!("Dropping invocation lifecycle event: {err}");
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LifecycleWebhookError {
+    #[error("invalid header '{0}' for the invocation lifecycle webhook")]
+    BadHeader(String),
+    #[error("cannot create the http client of the invocation lifecycle webhook: {0}")]
+    Client(#[from] reqwest::Error),
+}
+
+/// Sends the invocation lifecycle events emitted by the partition leaders of this node to the
+/// configured webhook.
+pub struct LifecycleWebhook {
+    options: LifecycleWebhookOptions,
+    client: reqwest::Client,
+    rx: mpsc::Receiver<InvocationLifecycleEvent>,
+}
+
+impl LifecycleWebhook {
+    pub fn create(
+        options: &LifecycleWebhookOptions,
+    ) -> Result<(Self, LifecycleEventSender), LifecycleWebhookError> {
+        let mut headers = HeaderMap::with_capacity(options.additional_headers.len());
+        for (name, value) in &options.additional_headers {
+            headers.insert(
+                HeaderName::from_str(name)
+                    .map_err(|_| LifecycleWebhookError::BadHeader(name.clone()))?,
+                HeaderValue::from_str(value)
+                    .map_err(|_| LifecycleWebhookError::BadHeader(name.clone()))?,
+            );
+        }
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(options.request_timeout.to_std())
+            .build()?;
+
+        let (tx, rx) = mpsc::channel(options.queue_length.get());
+        Ok((
+            Self {
+                options: options.clone(),
+                client,
+                rx,
+            },
+            LifecycleEventSender(Some(tx)),
+        ))
+    }
+
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        debug!(url = %self.options.url, "Running the invocation lifecycle webhook");
+        let mut cancellation_watcher = std::pin::pin!(cancellation_watcher());
+
+        loop {
+            let event = tokio::select! {
+                _ = &mut cancellation_watcher => break,
+                event = self.rx.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+            };
+            self.send(&event).await;
+        }
+
+        Ok(())
+    }
+
+    async fn send(&self, event: &InvocationLifecycleEvent) {
+        let url = self.options.url.to_string();
+        let result = self
+            .options
+            .retry_policy
+            .clone()
+            .retry(|| async {
+                self.client
+                    .post(&url)
+                    .json(event)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .map(|_| ())
+                    .inspect_err(|err| trace!("Invocation lifecycle webhook request failed: {err}"))
+            })
+            .await;
+
+        match result {
+            Ok(()) => {
+                counter!(LIFECYCLE_WEBHOOK_EVENTS, "outcome" => "sent").increment(1);
+            }
+            Err(err) => {
+                counter!(LIFECYCLE_WEBHOOK_EVENTS, "outcome" => "failed").increment(1);
+                warn!(
+                    restate.invocation.id = %event.invocation_id,
+                    "Failed sending the {:?} lifecycle event to the webhook, dropping it: {err}",
+                    event.kind
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_types::errors::KILLED_INVOCATION_ERROR;
+
+    use serde_json::json;
+
+    #[test]
+    fn event_json() {
+        let invocation_id = InvocationId::mock_random();
+        let event = InvocationLifecycleEvent::new(
+            InvocationLifecycleEventKind::Failed,
+            invocation_id,
+            &InvocationTarget::virtual_object(
+                "Counter",
+                "my-key",
+                "add",
+                restate_types::invocation::VirtualObjectHandlerType::Exclusive,
+            ),
+            MillisSinceEpoch::new(1_700_000_000_000),
+        )
+        .with_error(&KILLED_INVOCATION_ERROR);
+
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({
+                "type": "failed",
+                "invocation_id": invocation_id.to_string(),
+                "service_name": "Counter",
+                "service_key": "my-key",
+                "handler_name": "add",
+                "timestamp": "2023-11-14T22:13:20Z",
+                "error": {
+                    "code": 409,
+                    "message": "killed"
+                }
+            })
+        );
+    }
+}
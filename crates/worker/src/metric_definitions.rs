@@ -29,11 +29,33 @@ pub const PARTITION_TIME_SINCE_LAST_STATUS_UPDATE: &str =
     "restate.partition.time_since_last_status_update";
 pub const PARTITION_APPLIED_LSN_LAG: &str = "restate.partition.applied_lsn_lag";
 pub const PARTITION_IS_EFFECTIVE_LEADER: &str = "restate.partition.is_effective_leader";
+pub const PARTITION_APPLIED_COMMANDS_PER_SECOND: &str =
+    "restate.partition.applied_commands_per_second";
+pub const PARTITION_APPLY_LAG_SECONDS: &str = "restate.partition.apply_lag.seconds";
+pub const PARTITION_OLDEST_UNAPPLIED_COMMAND_AGE_SECONDS: &str =
+    "restate.partition.oldest_unapplied_command_age.seconds";
+
+pub const PARTITION_TIMERS_FIRED: &str = "restate.partition.timers_fired.total";
+pub const PARTITION_TIMERS_DEFERRED: &str = "restate.partition.timers_deferred.total";
+
+pub const PARTITION_CLEANED_INVOCATIONS: &str = "restate.partition.cleaned_invocations.total";
+
+pub const PARTITION_PARKED_COMMANDS: &str = "restate.partition.parked_commands.total";
 
 pub const PARTITION_RECORD_COMMITTED_TO_READ_LATENCY_SECONDS: &str =
     "restate.partition.record_committed_to_read_latency.seconds";
 
+pub const PARTITION_RECOVERY_PROGRESS: &str = "restate.partition.recovery_progress";
+
+pub const PARTITION_OUTBOX_INCONSISTENCIES: &str = "restate.partition.outbox_inconsistencies";
+
+pub const PARTITION_COMPLETION_SINK_EVENTS: &str = "restate.partition.completion_sink_events.total";
+
+pub const LIFECYCLE_WEBHOOK_EVENTS: &str = "restate.invocation_lifecycle_webhook.events.total";
+
 pub(crate) fn describe_metrics() {
+    restate_timer::metric_definitions::describe_metrics();
+
     describe_gauge!(
         PARTITION_BLOCKED_FLARE,
         Unit::Count,
@@ -97,4 +119,70 @@ pub(crate) fn describe_metrics() {
         Unit::Count,
         "Number of records between last applied lsn and the log tail"
     );
+
+    describe_gauge!(
+        PARTITION_APPLIED_COMMANDS_PER_SECOND,
+        Unit::Count,
+        "Rate of commands applied by the partition processor"
+    );
+
+    describe_gauge!(
+        PARTITION_APPLY_LAG_SECONDS,
+        Unit::Seconds,
+        "Time between appending the last applied command to the log and applying it"
+    );
+
+    describe_gauge!(
+        PARTITION_OLDEST_UNAPPLIED_COMMAND_AGE_SECONDS,
+        Unit::Seconds,
+        "Age of the oldest command appended to the log, but not applied yet"
+    );
+
+    describe_counter!(
+        PARTITION_TIMERS_FIRED,
+        Unit::Count,
+        "Number of timers fired by the partition leaders, by kind"
+    );
+
+    describe_counter!(
+        PARTITION_TIMERS_DEFERRED,
+        Unit::Count,
+        "Number of timers whose firing was deferred because their kind is paused, by kind"
+    );
+
+    describe_counter!(
+        PARTITION_CLEANED_INVOCATIONS,
+        Unit::Count,
+        "Number of completed invocations, or journals of completed invocations, purged once their retention expired"
+    );
+
+    describe_counter!(
+        PARTITION_PARKED_COMMANDS,
+        Unit::Count,
+        "Number of commands parked by the partition processors after repeatedly failing to apply them"
+    );
+
+    describe_gauge!(
+        PARTITION_RECOVERY_PROGRESS,
+        Unit::Percent,
+        "Percentage of the log records replayed by a recovering partition processor"
+    );
+
+    describe_gauge!(
+        PARTITION_OUTBOX_INCONSISTENCIES,
+        Unit::Count,
+        "Number of inconsistencies between the outbox of a partition leader and the dedup tables of the downstream partitions found by the last outbox consistency check"
+    );
+
+    describe_counter!(
+        PARTITION_COMPLETION_SINK_EVENTS,
+        Unit::Count,
+        "Number of completion events delivered to the completion Kafka sink by the partition leaders"
+    );
+
+    describe_counter!(
+        LIFECYCLE_WEBHOOK_EVENTS,
+        Unit::Count,
+        "Number of invocation lifecycle events, by outcome (sent, failed or dropped)"
+    );
 }
@@ -14,6 +14,7 @@ use std::time::{Duration, SystemTime};
 
 use anyhow::Context;
 use futures::StreamExt;
+use metrics::counter;
 use tokio::time::{Instant, MissedTickBehavior};
 use tracing::{debug, instrument, warn};
 
@@ -26,6 +27,8 @@ use restate_types::invocation::PurgeInvocationRequest;
 use restate_types::retries::with_jitter;
 use restate_wal_protocol::{Command, Destination, Envelope, Header, Source};
 
+use crate::metric_definitions::PARTITION_CLEANED_INVOCATIONS;
+
 pub(super) struct Cleaner<Storage> {
     leader_epoch: LeaderEpoch,
     partition_key_range: RangeInclusive<PartitionKey>,
@@ -150,6 +153,12 @@ where
                 )
                 .await
                 .context("Cannot append to bifrost purge invocation")?;
+                counter!(
+                    PARTITION_CLEANED_INVOCATIONS,
+                    "trigger" => "scan",
+                    "purged" => "invocation",
+                )
+                .increment(1);
                 continue;
             }
 
@@ -183,6 +192,12 @@ where
                     )
                     .await
                     .context("Cannot append to bifrost purge journal")?;
+                    counter!(
+                        PARTITION_CLEANED_INVOCATIONS,
+                        "trigger" => "scan",
+                        "purged" => "journal",
+                    )
+                    .increment(1);
                     continue;
                 }
             }
@@ -0,0 +1,569 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use metrics::counter;
+use rdkafka::consumer::{BaseConsumer, Consumer, ConsumerGroupMetadata};
+use rdkafka::message::{Header as KafkaHeader, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::{ClientConfig, Offset, TopicPartitionList};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, instrument, warn};
+
+use restate_bifrost::Bifrost;
+use restate_core::cancellation_watcher;
+use restate_storage_api::completion_event_table::{
+    CompletionEvent, CompletionEventSequence, ReadCompletionEventTable,
+};
+use restate_types::config::{CompletionKafkaSinkOptions, KafkaClusterOptions};
+use restate_types::identifiers::{LeaderEpoch, PartitionId, PartitionKey};
+use restate_wal_protocol::{Command, Destination, Envelope, Header, Source};
+
+use crate::metric_definitions::PARTITION_COMPLETION_SINK_EVENTS;
+
+/// Timeout of the blocking calls to the Kafka cluster.
+const KAFKA_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Delivers the completion events stored by the partition processor to the completion Kafka
+/// sink, exactly once.
+///
+/// The events are produced in Kafka transactions, together with the sequence of the last event of
+/// the transaction, committed as the offset of the progress topic by the consumer group of the
+/// partition. Every leader of the partition uses the same transactional id, so initializing the
+/// transactions fences the producer of the previous leader and aborts its pending transaction.
+/// The sink then resumes after the last committed sequence, hence no event is lost nor delivered
+/// twice.
+///
+/// Delivered events are truncated from the completion event table by appending
+/// [`Command::TruncateCompletionEvents`] to the log.
+pub(super) struct CompletionSink<Storage> {
+    partition_id: PartitionId,
+    leader_epoch: LeaderEpoch,
+    partition_key: PartitionKey,
+    storage: Storage,
+    bifrost: Bifrost,
+    options: CompletionKafkaSinkOptions,
+    cluster: KafkaClusterOptions,
+}
+
+impl<Storage> CompletionSink<Storage>
+where
+    Storage: ReadCompletionEventTable + Send + Sync + 'static,
+{
+    pub(super) fn new(
+        partition_id: PartitionId,
+        leader_epoch: LeaderEpoch,
+        partition_key: PartitionKey,
+        storage: Storage,
+        bifrost: Bifrost,
+        options: CompletionKafkaSinkOptions,
+        cluster: KafkaClusterOptions,
+    ) -> Self {
+        Self {
+            partition_id,
+            leader_epoch,
+            partition_key,
+            storage,
+            bifrost,
+            options,
+            cluster,
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub(super) async fn run(mut self) -> anyhow::Result<()> {
+        let flush_interval = Duration::from(self.options.flush_interval);
+        debug!(?flush_interval, topic = %self.options.topic, "Running completion sink");
+
+        let mut interval = tokio::time::interval(flush_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut producer = None;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {},
+                _ = cancellation_watcher() => {
+                    break;
+                }
+            }
+
+            if producer.is_none() {
+                match self.connect().await {
+                    Ok(connected) => producer = Some(connected),
+                    Err(e) => {
+                        warn!("Error when trying to connect the completion sink: {e:?}");
+                        continue;
+                    }
+                }
+            }
+
+            if let Err(e) = self
+                .deliver(producer.as_mut().expect("must be connected"))
+                .await
+            {
+                warn!("Error when trying to deliver the completion events: {e:?}");
+                // The next attempt aborts the pending transaction, if any, and re-reads the
+                // committed progress
+                producer = None;
+            }
+        }
+
+        debug!("Stopping completion sink");
+
+        Ok(())
+    }
+
+    async fn connect(&self) -> anyhow::Result<TransactionalProducer> {
+        let producer =
+            TransactionalProducer::create(self.partition_id, &self.cluster, &self.options).await?;
+        debug!(last_delivered = ?producer.last_delivered, "Connected completion sink");
+
+        // The previous leader might have failed before truncating its delivered events
+        if let Some(last_delivered) = producer.last_delivered {
+            self.truncate(last_delivered).await?;
+        }
+        Ok(producer)
+    }
+
+    async fn deliver(&mut self, producer: &mut TransactionalProducer) -> anyhow::Result<()> {
+        let max_batch_size = self.options.max_batch_size.get();
+        loop {
+            let events = self
+                .storage
+                .get_completion_events(producer.last_delivered, max_batch_size)
+                .await
+                .context("Cannot read the completion events")?;
+            let Some(&(last, _)) = events.last() else {
+                return Ok(());
+            };
+
+            producer.begin()?;
+            for (sequence, event) in &events {
+                producer.send_event(&self.options.topic, *sequence, event)?;
+            }
+            producer.commit(last).await?;
+            counter!(PARTITION_COMPLETION_SINK_EVENTS).increment(events.len() as u64);
+
+            self.truncate(last).await?;
+
+            if events.len() < max_batch_size {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn truncate(&self, up_to: CompletionEventSequence) -> anyhow::Result<()> {
+        restate_bifrost::append_to_bifrost(
+            &self.bifrost,
+            Arc::new(Envelope {
+                header: Header {
+                    source: Source::Processor {
+                        partition_id: None,
+                        partition_key: None,
+                        leader_epoch: self.leader_epoch,
+                    },
+                    dest: Destination::Processor {
+                        partition_key: self.partition_key,
+                        dedup: None,
+                    },
+                },
+                command: Command::TruncateCompletionEvents(up_to),
+            }),
+        )
+        .await
+        .context("Cannot append to bifrost truncate completion events")?;
+        Ok(())
+    }
+}
+
+/// Transactional producer of a partition, tracking the sequence of the last delivered event.
+///
+/// The sequence is stored as the metadata of the offset committed by the consumer group of the
+/// partition, within the transaction delivering the events. Reading the committed offsets of a
+/// consumer group waits for its pending transactional offsets, hence, unlike reading records from
+/// a topic, it's never held back by the pending transactions of other partitions.
+struct TransactionalProducer {
+    producer: FutureProducer,
+    progress_topic: String,
+    group_metadata: Arc<ConsumerGroupMetadata>,
+    /// Header identifying the partition producing the events
+    partition_id: String,
+    last_delivered: Option<CompletionEventSequence>,
+}
+
+impl TransactionalProducer {
+    async fn create(
+        partition_id: PartitionId,
+        cluster: &KafkaClusterOptions,
+        options: &CompletionKafkaSinkOptions,
+    ) -> anyhow::Result<Self> {
+        let mut client_config = ClientConfig::new();
+        client_config.set("metadata.broker.list", cluster.brokers.join(","));
+        for (k, v) in cluster.additional_options.clone() {
+            client_config.set(k, v);
+        }
+
+        let transactional_id = format!("{}-{partition_id}", options.transactional_id_prefix);
+        let producer: FutureProducer = client_config
+            .clone()
+            .set("transactional.id", &transactional_id)
+            .set("enable.idempotence", "true")
+            .create()
+            .context("Cannot create the Kafka producer")?;
+        let consumer: BaseConsumer = client_config
+            .set("group.id", &transactional_id)
+            .set("isolation.level", "read_committed")
+            .set("enable.auto.commit", "false")
+            .create()
+            .context("Cannot create the Kafka consumer")?;
+        let group_metadata = consumer
+            .group_metadata()
+            .context("Cannot get the Kafka consumer group metadata")?;
+
+        let progress_topic = options.progress_topic.clone();
+        let (producer, last_delivered) = {
+            let progress_topic = progress_topic.clone();
+            tokio::task::spawn_blocking(move || {
+                // Fences the producers of the previous leaders and aborts their pending
+                // transaction, hence the progress read afterward is the last committed one.
+                producer
+                    .init_transactions(KAFKA_TIMEOUT)
+                    .context("Cannot initialize the Kafka transactions")?;
+                let last_delivered = read_progress(&consumer, &progress_topic)?;
+                anyhow::Ok((producer, last_delivered))
+            })
+            .await??
+        };
+
+        Ok(Self {
+            producer,
+            progress_topic,
+            group_metadata: Arc::new(group_metadata),
+            partition_id: partition_id.to_string(),
+            last_delivered,
+        })
+    }
+
+    fn begin(&self) -> anyhow::Result<()> {
+        self.producer
+            .begin_transaction()
+            .context("Cannot begin the Kafka transaction")
+    }
+
+    fn send_event(
+        &self,
+        topic: &str,
+        sequence: CompletionEventSequence,
+        event: &CompletionEvent,
+    ) -> anyhow::Result<()> {
+        let key = event.invocation_id.to_string();
+        let lsn = sequence.lsn.to_string();
+        let index = sequence.index.to_string();
+        let headers = OwnedHeaders::new()
+            .insert(KafkaHeader {
+                key: "restate.partition.id",
+                value: Some(self.partition_id.as_str()),
+            })
+            .insert(KafkaHeader {
+                key: "restate.completion.lsn",
+                value: Some(lsn.as_str()),
+            })
+            .insert(KafkaHeader {
+                key: "restate.completion.index",
+                value: Some(index.as_str()),
+            });
+
+        // Delivery failures fail the commit of the transaction
+        self.producer
+            .send_result(
+                FutureRecord::to(topic)
+                    .key(key.as_str())
+                    .payload(event.payload.as_ref())
+                    .headers(headers),
+            )
+            .map_err(|(err, _)| err)
+            .context("Cannot produce the completion event")?;
+        Ok(())
+    }
+
+    /// Commits the transaction together with the progress offset.
+    async fn commit(&mut self, last: CompletionEventSequence) -> anyhow::Result<()> {
+        let mut offsets = TopicPartitionList::new();
+        {
+            let mut progress = offsets.add_partition(&self.progress_topic, 0);
+            // The offset is informational only, the sequence is read from the metadata
+            progress.set_offset(Offset::Offset(
+                i64::try_from(u64::from(last.lsn)).unwrap_or(i64::MAX),
+            ))?;
+            progress.set_metadata(serde_json::to_string(&last)?);
+        }
+
+        let producer = self.producer.clone();
+        let group_metadata = Arc::clone(&self.group_metadata);
+        tokio::task::spawn_blocking(move || {
+            producer
+                .send_offsets_to_transaction(&offsets, &group_metadata, KAFKA_TIMEOUT)
+                .context("Cannot send the progress offset to the Kafka transaction")?;
+            producer
+                .commit_transaction(KAFKA_TIMEOUT)
+                .context("Cannot commit the Kafka transaction")
+        })
+        .await??;
+        self.last_delivered = Some(last);
+        Ok(())
+    }
+}
+
+/// Reads the sequence of the last delivered event of the partition from the offset committed by
+/// its consumer group.
+fn read_progress(
+    consumer: &BaseConsumer,
+    progress_topic: &str,
+) -> anyhow::Result<Option<CompletionEventSequence>> {
+    let mut partitions = TopicPartitionList::new();
+    partitions.add_partition(progress_topic, 0);
+    // With read_committed, the fetch waits for the pending transactional offsets of the group,
+    // which initializing the transactions just aborted
+    let committed = consumer
+        .committed_offsets(partitions, KAFKA_TIMEOUT)
+        .context("Cannot fetch the committed progress offset")?;
+
+    let Some(progress) = committed.find_partition(progress_topic, 0) else {
+        return Ok(None);
+    };
+    progress
+        .error()
+        .context("Cannot fetch the committed progress offset")?;
+    if !matches!(progress.offset(), Offset::Offset(_)) || progress.metadata().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(progress.metadata())
+        .map(Some)
+        .context("Cannot decode the committed progress")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Instant;
+
+    use bytes::Bytes;
+    use googletest::prelude::*;
+    use rdkafka::Message;
+    use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+    use rdkafka::client::DefaultClientContext;
+    use restate_core::TestCoreEnvBuilder;
+    use restate_types::Version;
+    use restate_types::identifiers::InvocationId;
+    use restate_types::logs::Lsn;
+    use restate_types::partition_table::PartitionTable;
+    use test_log::test;
+
+    // These tests need a Kafka broker listening on localhost:9092, which can be started with:
+    //
+    //   docker run --rm -p 9092:9092 apache/kafka:3.9.0
+    //
+    // The kafka-tests CI job runs them against a broker started in a service container.
+
+    const BROKER: &str = "localhost:9092";
+
+    #[derive(Clone)]
+    struct MockCompletionEventTable(Vec<(CompletionEventSequence, CompletionEvent)>);
+
+    impl MockCompletionEventTable {
+        fn with_events(count: u64) -> Self {
+            Self(
+                (1..=count)
+                    .map(|lsn| {
+                        let invocation_id = InvocationId::mock_random();
+                        (
+                            CompletionEventSequence::new(Lsn::new(lsn), 0),
+                            CompletionEvent {
+                                invocation_id,
+                                payload: Bytes::from(invocation_id.to_string()),
+                            },
+                        )
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    impl ReadCompletionEventTable for MockCompletionEventTable {
+        async fn get_completion_events(
+            &mut self,
+            after: Option<CompletionEventSequence>,
+            limit: usize,
+        ) -> restate_storage_api::Result<Vec<(CompletionEventSequence, CompletionEvent)>> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|(sequence, _)| after.is_none_or(|after| *sequence > after))
+                .take(limit)
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn cluster() -> KafkaClusterOptions {
+        KafkaClusterOptions {
+            name: "test".to_owned(),
+            brokers: vec![BROKER.to_owned()],
+            additional_options: Default::default(),
+        }
+    }
+
+    async fn sink_options() -> CompletionKafkaSinkOptions {
+        let suffix = InvocationId::mock_random().to_string().to_lowercase();
+        let options = CompletionKafkaSinkOptions {
+            cluster: "test".to_owned(),
+            topic: format!("completions-{suffix}"),
+            progress_topic: format!("completions-progress-{suffix}"),
+            transactional_id_prefix: format!("completion-sink-{suffix}"),
+            flush_interval: restate_time_util::NonZeroFriendlyDuration::from_secs_unchecked(1),
+            max_batch_size: std::num::NonZeroUsize::new(2).unwrap(),
+        };
+
+        let admin: AdminClient<DefaultClientContext> = ClientConfig::new()
+            .set("bootstrap.servers", BROKER)
+            .create()
+            .unwrap();
+        let results = admin
+            .create_topics(
+                &[
+                    NewTopic::new(&options.topic, 1, TopicReplication::Fixed(1)),
+                    NewTopic::new(&options.progress_topic, 1, TopicReplication::Fixed(1)),
+                ],
+                &AdminOptions::new(),
+            )
+            .await
+            .unwrap();
+        for result in results {
+            result.unwrap();
+        }
+        options
+    }
+
+    /// Reads the committed records of the topic, as (key, payload) pairs.
+    async fn read_committed(topic: &str, expected: usize) -> Vec<(String, Bytes)> {
+        let topic = topic.to_owned();
+        tokio::task::spawn_blocking(move || {
+            let consumer: BaseConsumer = ClientConfig::new()
+                .set("bootstrap.servers", BROKER)
+                .set("isolation.level", "read_committed")
+                .set("enable.auto.commit", "false")
+                .create()
+                .unwrap();
+            let mut assignment = TopicPartitionList::new();
+            assignment
+                .add_partition_offset(&topic, 0, Offset::Beginning)
+                .unwrap();
+            consumer.assign(&assignment).unwrap();
+
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let mut records = Vec::new();
+            while records.len() < expected && Instant::now() < deadline {
+                if let Some(Ok(message)) = consumer.poll(Duration::from_millis(100)) {
+                    records.push((
+                        String::from_utf8(message.key().unwrap().to_vec()).unwrap(),
+                        Bytes::copy_from_slice(message.payload().unwrap()),
+                    ));
+                }
+            }
+            // wait a bit more to detect duplicates
+            if let Some(Ok(message)) = consumer.poll(Duration::from_secs(1)) {
+                panic!("unexpected record at offset {}", message.offset());
+            }
+            records
+        })
+        .await
+        .unwrap()
+    }
+
+    #[ignore = "requires a Kafka broker on localhost:9092"]
+    #[test(restate_core::test)]
+    async fn delivers_exactly_once_across_leaders() {
+        let env = TestCoreEnvBuilder::with_incoming_only_connector()
+            .set_partition_table(PartitionTable::with_equally_sized_partitions(
+                Version::MIN,
+                1,
+            ))
+            .build()
+            .await;
+        let bifrost = Bifrost::init_in_memory(env.metadata_writer).await;
+        let options = sink_options().await;
+        let storage = MockCompletionEventTable::with_events(5);
+
+        // The first leader delivers the first 3 events
+        let mut first_leader = CompletionSink::new(
+            PartitionId::MIN,
+            LeaderEpoch::INITIAL,
+            PartitionKey::MIN,
+            MockCompletionEventTable(storage.0[..3].to_vec()),
+            bifrost.clone(),
+            options.clone(),
+            cluster(),
+        );
+        let mut first_producer = first_leader.connect().await.unwrap();
+        first_leader.deliver(&mut first_producer).await.unwrap();
+        assert_that!(first_producer.last_delivered, some(eq(storage.0[2].0)));
+
+        // The next leader resumes after the last committed event, even though the delivered
+        // events were not truncated yet
+        let mut second_leader = CompletionSink::new(
+            PartitionId::MIN,
+            LeaderEpoch::INITIAL.next(),
+            PartitionKey::MIN,
+            storage.clone(),
+            bifrost.clone(),
+            options.clone(),
+            cluster(),
+        );
+        let mut second_producer = second_leader.connect().await.unwrap();
+        assert_that!(second_producer.last_delivered, some(eq(storage.0[2].0)));
+
+        // The first leader was fenced
+        let fenced = async {
+            first_producer.begin()?;
+            first_producer.send_event(&options.topic, storage.0[3].0, &storage.0[3].1)?;
+            first_producer.commit(storage.0[3].0).await
+        };
+        assert!(fenced.await.is_err());
+
+        second_leader.deliver(&mut second_producer).await.unwrap();
+
+        let expected: Vec<_> = storage
+            .0
+            .iter()
+            .map(|(_, event)| (event.invocation_id.to_string(), event.payload.clone()))
+            .collect();
+        assert_eq!(
+            read_committed(&options.topic, expected.len()).await,
+            expected
+        );
+
+        let truncations: Vec<_> = bifrost
+            .read_all(PartitionId::MIN.into())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.try_decode::<Envelope>().unwrap().unwrap().command)
+            .collect();
+        assert_that!(
+            truncations,
+            contains(pat!(Command::TruncateCompletionEvents(eq(storage.0[4].0))))
+        );
+    }
+}
@@ -15,8 +15,9 @@ use restate_invoker_api::invocation_reader::{
     EagerState, InvocationReader, InvocationReaderTransaction,
 };
 use restate_storage_api::invocation_status_table::{InvocationStatus, ReadInvocationStatusTable};
-use restate_storage_api::state_table::ReadStateTable;
+use restate_storage_api::state_table::ScanStateTable;
 use restate_storage_api::{IsolationLevel, journal_table as journal_table_v1, journal_table_v2};
+use restate_types::config::Configuration;
 use restate_types::identifiers::InvocationId;
 use restate_types::identifiers::ServiceId;
 use restate_types::service_protocol::ServiceProtocolVersion;
@@ -39,12 +40,13 @@ impl<Storage> InvokerStorageReader<Storage> {
 
 impl<Storage> InvocationReader for InvokerStorageReader<Storage>
 where
-    Storage: restate_storage_api::Storage + 'static,
+    Storage: restate_storage_api::Storage + ScanStateTable + Clone + Send + Sync + 'static,
 {
     type Transaction<'a> = InvokerStorageReaderTransaction<'a, Storage>;
 
     fn transaction(&mut self) -> Self::Transaction<'_> {
         InvokerStorageReaderTransaction {
+            storage: self.0.clone(),
             txn: self
                 .0
                 // we must use repeatable reads to avoid reading inconsistent values in the presence
@@ -58,12 +60,14 @@ pub(crate) struct InvokerStorageReaderTransaction<'a, Storage>
 where
     Storage: restate_storage_api::Storage + 'static,
 {
+    /// To stream the state, which is not read from the transaction
+    storage: Storage,
     txn: Storage::TransactionType<'a>,
 }
 
 impl<Storage> InvocationReaderTransaction for InvokerStorageReaderTransaction<'_, Storage>
 where
-    Storage: restate_storage_api::Storage + 'static,
+    Storage: restate_storage_api::Storage + ScanStateTable + Send + Sync + 'static,
 {
     type JournalStream =
         stream::Iter<IntoIter<restate_invoker_api::invocation_reader::JournalEntry>>;
@@ -111,6 +115,7 @@ where
                     invoked_status.current_invocation_epoch,
                     invoked_status.timestamps.modification_time(),
                     random_seed,
+                    invoked_status.deadline,
                 );
 
                 (journal_metadata, entries)
@@ -124,6 +129,7 @@ where
                         invoked_status.current_invocation_epoch,
                         invoked_status.timestamps.modification_time(),
                         random_seed,
+                        invoked_status.deadline,
                     ),
                     journal_table_v1::ReadJournalTable::get_journal(
                         &mut self.txn,
@@ -161,11 +167,30 @@ where
         &mut self,
         service_id: &ServiceId,
     ) -> Result<EagerState<Self::StateIter>, Self::Error> {
-        let user_states = self
-            .txn
-            .get_all_user_states_for_service(service_id)?
-            .try_collect::<Vec<_>>()
-            .await?;
+        // The state is streamed outside the transaction snapshot, so that a state too big to be
+        // sent eagerly is never fully read. No write is missed: exclusive handlers hold the lock
+        // of the service, hence only their own, already applied, journal entries modify it.
+        let size_limit = Configuration::pinned()
+            .worker
+            .invoker
+            .eager_state_size_limit();
+        let mut batches = std::pin::pin!(
+            self.storage
+                .stream_user_states_for_service(service_id, size_limit)?
+        );
+
+        let mut user_states = Vec::new();
+        let mut size = 0;
+        while let Some(batch) = batches.try_next().await? {
+            for (key, value) in batch {
+                size += key.len() + value.len();
+                if size > size_limit.get() {
+                    // The service fetches the missing entries lazily
+                    return Ok(EagerState::new_partial(user_states.into_iter()));
+                }
+                user_states.push((key, value));
+            }
+        }
 
         Ok(EagerState::new_complete(user_states.into_iter()))
     }
@@ -9,44 +9,60 @@
 // by the Apache License, Version 2.0.
 
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future;
 use std::future::Future;
 use std::ops::RangeInclusive;
 use std::pin::Pin;
 use std::task::{Context, Poll, ready};
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
+use enumset::EnumSet;
 use futures::future::OptionFuture;
-use futures::stream::FuturesUnordered;
+use futures::stream::{BoxStream, FuturesUnordered};
 use futures::{FutureExt, StreamExt, stream};
 use metrics::counter;
-use restate_types::logs::Keys;
-use restate_wal_protocol::control::UpsertSchema;
+use restate_wal_protocol::control::{UpdatePartitionSettings, UpsertSchema};
 use tokio_stream::wrappers::{ReceiverStream, WatchStream};
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use restate_bifrost::CommitToken;
 use restate_core::network::{Oneshot, Reciprocal};
 use restate_core::{Metadata, MetadataKind, TaskCenter, TaskHandle, TaskId};
 use restate_partition_store::PartitionStore;
+use restate_storage_api::timer_table::TimerKey;
+use restate_timer::TimerReader as _;
+use restate_types::config::Configuration;
 use restate_types::identifiers::{
     InvocationId, LeaderEpoch, PartitionId, PartitionKey, PartitionProcessorRpcRequestId,
     WithPartitionKey,
 };
-use restate_types::invocation::client::{InvocationOutput, SubmittedInvocationNotification};
+use restate_types::invocation::client::{
+    ConsistencyToken, InvocationOutput, SubmittedInvocationNotification,
+};
+use restate_types::logs::{Keys, Lsn};
 use restate_types::net::partition_processor::{
     PartitionProcessorRpcError, PartitionProcessorRpcResponse,
 };
+use restate_types::partitions::settings::PartitionSettings;
 use restate_types::time::MillisSinceEpoch;
+use restate_types::timer::{
+    PendingTimer, TimerKind, TimerServiceRequest, TimerServiceStats, deregister_timer_service,
+    paused_timer_kinds, register_timer_service, watch_paused_timer_kinds,
+};
 use restate_types::{SemanticRestateVersion, Version, Versioned};
 use restate_wal_protocol::Command;
-use restate_wal_protocol::timer::TimerKeyValue;
+use restate_wal_protocol::timer::{TimerKeyDisplay, TimerKeyValue};
 
-use crate::metric_definitions::{PARTITION_HANDLE_LEADER_ACTIONS, USAGE_LEADER_ACTION_COUNT};
+use crate::lifecycle_webhook::LifecycleEventSender;
+use crate::metric_definitions::{
+    PARTITION_HANDLE_LEADER_ACTIONS, PARTITION_TIMERS_DEFERRED, PARTITION_TIMERS_FIRED,
+    USAGE_LEADER_ACTION_COUNT,
+};
 use crate::partition::invoker_storage_reader::InvokerStorageReader;
 use crate::partition::leadership::self_proposer::SelfProposer;
-use crate::partition::leadership::{ActionEffect, Error, InvokerStream, TimerService};
+use crate::partition::leadership::timer_interleaver::TimerInterleaver;
+use crate::partition::leadership::{ActionEffect, Error, InvokerStream, TimerReader, TimerService};
 use crate::partition::shuffle;
 use crate::partition::shuffle::HintSender;
 use crate::partition::state_machine::{Action, StateMachine};
@@ -70,6 +86,18 @@ pub struct LeaderState {
     // returns a [`Error:TaskFailed`] error.
     shuffle_task_handle: Option<TaskHandle<anyhow::Result<()>>>,
     pub timer_service: Pin<Box<TimerService>>,
+    paused_timer_kinds: EnumSet<TimerKind>,
+    paused_timer_kinds_stream: WatchStream<EnumSet<TimerKind>>,
+    // due timers of the paused kinds, fired once their kind is resumed
+    deferred_timers: HashMap<TimerKey, TimerKeyValue>,
+    // further due timers of the paused kinds are dropped while this many timers are deferred
+    deferred_timers_limit: usize,
+    // dropped timers per kind, reloaded from storage once their kind is resumed
+    dropped_timers: HashMap<TimerKind, DroppedTimers>,
+    dropped_timers_reader: TimerReader,
+    // due housekeeping timers, fired interleaved with the interactive commands
+    timer_interleaver: TimerInterleaver,
+    timer_service_requests: ReceiverStream<TimerServiceRequest>,
     self_proposer: SelfProposer,
 
     awaiting_rpc_actions: HashMap<PartitionProcessorRpcRequestId, RpcReciprocal>,
@@ -78,10 +106,21 @@ pub struct LeaderState {
     invoker_stream: InvokerStream,
     shuffle_stream: ReceiverStream<shuffle::OutboxTruncation>,
     schema_stream: WatchStream<Version>,
+    // yields once when becoming leader, and then on every configuration update
+    settings_stream: BoxStream<'static, ()>,
     pub pending_cleanup_timers_to_schedule: VecDeque<(InvocationId, Duration)>,
     cleaner_task_id: TaskId,
     trimmer_task_id: TaskId,
+    completion_sink_task_id: Option<TaskId>,
     durability_tracker: DurabilityTracker,
+    lifecycle_event_sender: LifecycleEventSender,
+}
+
+/// Range of the due timers of a kind, which were dropped from memory while it was paused.
+struct DroppedTimers {
+    first: TimerKey,
+    last: TimerKey,
+    count: usize,
 }
 
 impl LeaderState {
@@ -93,12 +132,17 @@ impl LeaderState {
         shuffle_task_handle: TaskHandle<anyhow::Result<()>>,
         cleaner_task_id: TaskId,
         trimmer_task_id: TaskId,
+        completion_sink_task_id: Option<TaskId>,
         shuffle_hint_tx: HintSender,
         timer_service: TimerService,
+        timer_interleaver: TimerInterleaver,
+        deferred_timers_limit: usize,
+        dropped_timers_reader: TimerReader,
         self_proposer: SelfProposer,
         invoker_rx: InvokerStream,
         shuffle_rx: tokio::sync::mpsc::Receiver<shuffle::OutboxTruncation>,
         durability_tracker: DurabilityTracker,
+        lifecycle_event_sender: LifecycleEventSender,
     ) -> Self {
         LeaderState {
             partition_id,
@@ -107,11 +151,29 @@ impl LeaderState {
             shuffle_task_handle: Some(shuffle_task_handle),
             cleaner_task_id,
             trimmer_task_id,
+            completion_sink_task_id,
             shuffle_hint_tx,
             schema_stream: Metadata::with_current(|m| {
                 WatchStream::new(m.watch(MetadataKind::Schema))
             }),
+            settings_stream: stream::once(future::ready(()))
+                .chain(stream::unfold(
+                    Configuration::watcher(),
+                    |mut watcher| async move {
+                        watcher.changed().await;
+                        Some(((), watcher))
+                    },
+                ))
+                .boxed(),
             timer_service: Box::pin(timer_service),
+            paused_timer_kinds: paused_timer_kinds(),
+            paused_timer_kinds_stream: WatchStream::new(watch_paused_timer_kinds()),
+            deferred_timers: Default::default(),
+            deferred_timers_limit,
+            dropped_timers: Default::default(),
+            dropped_timers_reader,
+            timer_interleaver,
+            timer_service_requests: ReceiverStream::new(register_timer_service(partition_id)),
             self_proposer,
             awaiting_rpc_actions: Default::default(),
             awaiting_rpc_self_propose: Default::default(),
@@ -119,6 +181,7 @@ impl LeaderState {
             shuffle_stream: ReceiverStream::new(shuffle_rx),
             pending_cleanup_timers_to_schedule: Default::default(),
             durability_tracker,
+            lifecycle_event_sender,
         }
     }
 
@@ -131,8 +194,8 @@ impl LeaderState {
         let timer_stream = std::pin::pin!(stream::unfold(
             &mut self.timer_service,
             |timer_service| async {
-                let timer_value = timer_service.as_mut().next_timer().await;
-                Some((ActionEffect::Timer(timer_value), timer_service))
+                let timers = timer_service.as_mut().next_timers().await;
+                Some((ActionEffect::Timers(timers), timer_service))
             }
         ));
 
@@ -151,10 +214,26 @@ impl LeaderState {
             )
         });
 
+        let settings_stream = (&mut self.settings_stream).filter_map(|_| {
+            // only update the settings iff the configured ones differ from the applied ones
+            let settings = PartitionSettings::from_configuration(
+                &Configuration::pinned(),
+                &state_machine.settings,
+            );
+            std::future::ready(
+                (settings != state_machine.settings)
+                    .then_some(ActionEffect::UpdatePartitionSettings(settings)),
+            )
+        });
+
         let invoker_stream = (&mut self.invoker_stream).map(ActionEffect::Invoker);
         let shuffle_stream = (&mut self.shuffle_stream).map(ActionEffect::Shuffle);
         let dur_tracker_stream =
             (&mut self.durability_tracker).map(ActionEffect::PartitionMaintenance);
+        let paused_timer_kinds_stream =
+            (&mut self.paused_timer_kinds_stream).map(ActionEffect::PausedTimerKinds);
+        let timer_service_requests =
+            (&mut self.timer_service_requests).map(ActionEffect::TimerServiceRequest);
 
         let action_effects_stream = stream::unfold(
             &mut self.pending_cleanup_timers_to_schedule,
@@ -169,6 +248,15 @@ impl LeaderState {
             },
         )
         .fuse();
+        let housekeeping_timers_stream =
+            stream::unfold(&mut self.timer_interleaver, |timer_interleaver| {
+                future::ready(
+                    timer_interleaver
+                        .has_housekeeping_timers()
+                        .then_some((ActionEffect::HousekeepingTimers, timer_interleaver)),
+                )
+            })
+            .fuse();
         let awaiting_rpc_self_propose_stream =
             (&mut self.awaiting_rpc_self_propose).map(|_| ActionEffect::AwaitingRpcSelfProposeDone);
 
@@ -177,9 +265,13 @@ impl LeaderState {
             shuffle_stream,
             timer_stream,
             action_effects_stream,
+            housekeeping_timers_stream,
             awaiting_rpc_self_propose_stream,
             dur_tracker_stream,
-            schema_stream
+            schema_stream,
+            settings_stream,
+            paused_timer_kinds_stream,
+            timer_service_requests
         );
         let mut all_streams = all_streams.ready_chunks(BATCH_READY_UP_TO);
 
@@ -230,6 +322,11 @@ impl LeaderState {
         // We don't really care about waiting for the trimmer to finish cancelling
         TaskCenter::cancel_task(self.trimmer_task_id);
 
+        // Nor for the completion sink, as the next leader fences its pending transaction
+        if let Some(completion_sink_task_id) = self.completion_sink_task_id {
+            TaskCenter::cancel_task(completion_sink_task_id);
+        }
+
         // It's ok to not check the abort_result because either it succeeded or the invoker
         // is not running. If the invoker is not running, and we are not shutting down, then
         // we will fail the next time we try to invoke.
@@ -256,6 +353,51 @@ impl LeaderState {
         for fut in self.awaiting_rpc_self_propose.iter_mut() {
             fut.fail_with_lost_leadership(self.partition_id);
         }
+
+        deregister_timer_service(self.partition_id);
+
+        // The deferred timers are still stored, hence the next leader will fire them
+        for timer in self.deferred_timers.values() {
+            timer.kind().record_deferred(false);
+        }
+        for (kind, dropped_timers) in &self.dropped_timers {
+            for _ in 0..dropped_timers.count {
+                kind.record_deferred(false);
+            }
+        }
+        if self.timer_interleaver.has_housekeeping_timers() {
+            debug!(
+                "Leaving {} due housekeeping timers to the next leader",
+                self.timer_interleaver.num_housekeeping_timers()
+            );
+        }
+    }
+
+    fn handle_timer_service_request(&mut self, request: TimerServiceRequest) {
+        // the requester might have given up already, hence we ignore send errors
+        match request {
+            TimerServiceRequest::Stats(reply) => {
+                let _ = reply.send(TimerServiceStats {
+                    partition_id: self.partition_id,
+                    loaded_timers: self.timer_service.num_loaded_timers(),
+                    next_wake_up_time: self.timer_service.as_mut().next_wake_up_time(),
+                    fired_per_second: self.timer_service.fired_timers_per_second(),
+                });
+            }
+            TimerServiceRequest::ListPendingTimers { limit, reply } => {
+                let _ = reply.send(
+                    self.timer_service
+                        .list_pending_timers(limit)
+                        .into_iter()
+                        .map(|timer| PendingTimer {
+                            wake_up_time: timer.wake_up_time(),
+                            kind: timer.kind(),
+                            description: TimerKeyDisplay(timer.key()).to_string(),
+                        })
+                        .collect(),
+                );
+            }
+        }
     }
 
     pub async fn handle_action_effects(
@@ -275,6 +417,7 @@ impl LeaderState {
                         .await?;
                 }
                 ActionEffect::Invoker(invoker_effect) => {
+                    self.timer_interleaver.record_interactive_commands(1);
                     self.self_proposer
                         .propose(
                             invoker_effect.invocation_id.partition_key(),
@@ -292,17 +435,91 @@ impl LeaderState {
                         )
                         .await?;
                 }
-                ActionEffect::Timer(timer) => {
-                    self.self_proposer
-                        .propose(timer.invocation_id().partition_key(), Command::Timer(timer))
-                        .await?;
+                ActionEffect::Timers(timers) => {
+                    let mut due_timers = Vec::with_capacity(timers.len());
+                    for timer in timers {
+                        let kind = timer.kind();
+                        if self.paused_timer_kinds.contains(kind) {
+                            trace!(
+                                %kind,
+                                timer_key = ?timer.key(),
+                                "Defer firing timer because its kind is paused"
+                            );
+                            counter!(PARTITION_TIMERS_DEFERRED, "kind" => kind.to_string())
+                                .increment(1);
+                            kind.record_deferred(true);
+                            if self.deferred_timers.len() < self.deferred_timers_limit {
+                                self.deferred_timers.insert(timer.key().clone(), timer);
+                            } else {
+                                self.drop_deferred_timer(timer);
+                            }
+                        } else {
+                            due_timers.push(timer);
+                        }
+                    }
+                    let due_timers = self.timer_interleaver.prioritize(due_timers);
+                    self.timer_interleaver
+                        .record_interactive_commands(due_timers.len());
+                    self.fire_timers(due_timers).await?;
+                }
+                ActionEffect::HousekeepingTimers => {
+                    // fired one by one, as they were possibly due at different times
+                    for timer in self
+                        .timer_interleaver
+                        .release_housekeeping_timers(BATCH_READY_UP_TO)
+                    {
+                        self.fire_timer(timer).await?;
+                    }
+                }
+                ActionEffect::TimerServiceRequest(request) => {
+                    self.handle_timer_service_request(request);
+                }
+                ActionEffect::PausedTimerKinds(paused_timer_kinds) => {
+                    self.paused_timer_kinds = paused_timer_kinds;
+                    let resumed_timers: Vec<_> = self
+                        .deferred_timers
+                        .extract_if(|_, timer| !paused_timer_kinds.contains(timer.kind()))
+                        .map(|(_, timer)| timer)
+                        .collect();
+                    if !resumed_timers.is_empty() {
+                        debug!(
+                            "Firing {} deferred timers because their kind was resumed",
+                            resumed_timers.len()
+                        );
+                    }
+                    let mut fired_timers = HashSet::with_capacity(resumed_timers.len());
+                    for timer in resumed_timers {
+                        timer.kind().record_deferred(false);
+                        fired_timers.insert(timer.key().clone());
+                        self.fire_timer(timer).await?;
+                    }
+
+                    let resumed_kinds: Vec<_> = self
+                        .dropped_timers
+                        .keys()
+                        .copied()
+                        .filter(|kind| !paused_timer_kinds.contains(*kind))
+                        .collect();
+                    for kind in resumed_kinds {
+                        self.fire_dropped_timers(kind, &fired_timers).await?;
+                    }
                 }
                 ActionEffect::ScheduleCleanupTimer(invocation_id, duration) => {
+                    let Some(expiration_time) =
+                        u64::try_from(duration.as_millis())
+                            .ok()
+                            .and_then(|duration| {
+                                self.timer_service.now().as_u64().checked_add(duration)
+                            })
+                    else {
+                        // Lies far enough in the future, the cleaner will take care of it if needed
+                        continue;
+                    };
                     self.self_proposer
                         .propose(
                             invocation_id.partition_key(),
                             Command::ScheduleTimer(TimerKeyValue::clean_invocation_status(
-                                MillisSinceEpoch::from(SystemTime::now() + duration),
+                                MillisSinceEpoch::new(expiration_time),
                                 invocation_id,
                             )),
                         )
@@ -326,6 +543,19 @@ impl LeaderState {
                             .await?;
                     }
                 }
+                ActionEffect::UpdatePartitionSettings(settings) => {
+                    self.self_proposer
+                        .propose(
+                            *self.partition_key_range.start(),
+                            Command::UpdatePartitionSettings(UpdatePartitionSettings {
+                                partition_key_range: Keys::RangeInclusive(
+                                    self.partition_key_range.clone(),
+                                ),
+                                settings,
+                            }),
+                        )
+                        .await?;
+                }
                 ActionEffect::AwaitingRpcSelfProposeDone => {
                     // Nothing to do here
                 }
@@ -335,6 +565,117 @@ impl LeaderState {
         Ok(())
     }
 
+    /// Drops a due timer of a paused kind from memory, once the limit of deferred timers is
+    /// reached. It stays in storage, to be reloaded once its kind is resumed.
+    fn drop_deferred_timer(&mut self, timer: TimerKeyValue) {
+        let kind = timer.kind();
+        match self.dropped_timers.entry(kind) {
+            Entry::Occupied(mut o) => {
+                let dropped_timers = o.get_mut();
+                dropped_timers.last = timer.key().clone();
+                dropped_timers.count += 1;
+            }
+            Entry::Vacant(v) => {
+                warn!(
+                    %kind,
+                    "Dropping the due timers of the paused kind from memory until it is resumed, as {} timers were deferred",
+                    self.deferred_timers.len()
+                );
+                v.insert(DroppedTimers {
+                    first: timer.key().clone(),
+                    last: timer.key().clone(),
+                    count: 1,
+                });
+            }
+        }
+    }
+
+    /// Reloads the dropped timers of the resumed kind from storage, and fires them. The timer
+    /// service fires the timers in the order of their keys, hence all the stored timers of this
+    /// kind between the first and the last dropped one were deferred, or dropped as well.
+    async fn fire_dropped_timers(
+        &mut self,
+        kind: TimerKind,
+        fired_timers: &HashSet<TimerKey>,
+    ) -> Result<(), Error> {
+        let Some(dropped_timers) = self.dropped_timers.remove(&kind) else {
+            return Ok(());
+        };
+        debug!(
+            %kind,
+            "Reloading {} dropped timers from storage because their kind was resumed",
+            dropped_timers.count
+        );
+        for _ in 0..dropped_timers.count {
+            kind.record_deferred(false);
+        }
+
+        let mut previous_timer_key = None;
+        let mut timers = Vec::new();
+        loop {
+            let next_timers = self
+                .dropped_timers_reader
+                .get_timers(self.deferred_timers_limit, previous_timer_key.take())
+                .await;
+            let reached_end = next_timers.len() < self.deferred_timers_limit;
+
+            for timer in next_timers {
+                if *timer.key() > dropped_timers.last {
+                    break;
+                }
+                previous_timer_key = Some(timer.key().clone());
+                if timer.kind() == kind
+                    && *timer.key() >= dropped_timers.first
+                    && !fired_timers.contains(timer.key())
+                {
+                    timers.push(timer);
+                }
+            }
+            for timer in timers.drain(..) {
+                self.fire_timer(timer).await?;
+            }
+
+            if reached_end
+                || previous_timer_key
+                    .as_ref()
+                    .is_none_or(|timer_key| *timer_key >= dropped_timers.last)
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Fires the timers with a single command, unless there is only one of them.
+    async fn fire_timers(&mut self, mut timers: Vec<TimerKeyValue>) -> Result<(), Error> {
+        if timers.len() <= 1 {
+            if let Some(timer) = timers.pop() {
+                self.fire_timer(timer).await?;
+            }
+            return Ok(());
+        }
+
+        let partition_key = timers[0].invocation_id().partition_key();
+        let kinds: Vec<_> = timers.iter().map(TimerKeyValue::kind).collect();
+        self.self_proposer
+            .propose(partition_key, Command::TimerBatch(timers))
+            .await?;
+        for kind in kinds {
+            counter!(PARTITION_TIMERS_FIRED, "kind" => kind.to_string()).increment(1);
+            kind.record_fired();
+        }
+        Ok(())
+    }
+
+    async fn fire_timer(&mut self, timer: TimerKeyValue) -> Result<(), Error> {
+        let kind = timer.kind();
+        self.self_proposer
+            .propose(timer.invocation_id().partition_key(), Command::Timer(timer))
+            .await?;
+        counter!(PARTITION_TIMERS_FIRED, "kind" => kind.to_string()).increment(1);
+        kind.record_fired();
+        Ok(())
+    }
+
     pub async fn handle_rpc_proposal_command(
         &mut self,
         request_id: PartitionProcessorRpcRequestId,
@@ -359,12 +700,21 @@ impl LeaderState {
                 if let Err(e) = self.self_proposer.propose(partition_key, cmd).await {
                     reciprocal.send(Err(PartitionProcessorRpcError::Internal(e.to_string())));
                 } else {
+                    self.timer_interleaver.record_interactive_commands(1);
                     v.insert(reciprocal);
                 }
             }
         }
     }
 
+    pub async fn self_propose(
+        &mut self,
+        partition_key: PartitionKey,
+        cmd: Command,
+    ) -> Result<(), Error> {
+        self.self_proposer.propose(partition_key, cmd).await
+    }
+
     pub async fn self_propose_and_respond_asynchronously(
         &mut self,
         partition_key: PartitionKey,
@@ -378,6 +728,7 @@ impl LeaderState {
             .await
         {
             Ok(commit_token) => {
+                self.timer_interleaver.record_interactive_commands(1);
                 self.awaiting_rpc_self_propose.push(SelfAppendFuture::new(
                     commit_token,
                     success_response,
@@ -388,10 +739,12 @@ impl LeaderState {
         }
     }
 
+    /// Handles the actions produced by applying the records up to `applied_lsn`.
     pub fn handle_actions(
         &mut self,
         invoker_tx: &mut impl restate_invoker_api::InvokerHandle<InvokerStorageReader<PartitionStore>>,
         actions: impl Iterator<Item = Action>,
+        applied_lsn: Lsn,
     ) -> Result<(), Error> {
         for action in actions {
             let action_name = action.name();
@@ -406,7 +759,7 @@ impl LeaderState {
             )
             .increment(1);
 
-            self.handle_action(action, invoker_tx)?;
+            self.handle_action(action, invoker_tx, applied_lsn)?;
         }
 
         Ok(())
@@ -416,6 +769,7 @@ impl LeaderState {
         &mut self,
         action: Action,
         invoker_tx: &mut impl restate_invoker_api::InvokerHandle<InvokerStorageReader<PartitionStore>>,
+        applied_lsn: Lsn,
     ) -> Result<(), Error> {
         let partition_leader_epoch = (self.partition_id, self.leader_epoch);
         match action {
@@ -443,6 +797,9 @@ impl LeaderState {
                 self.timer_service.as_mut().add_timer(timer_value)
             }
             Action::DeleteTimer { timer_key } => {
+                if let Some(timer) = self.deferred_timers.remove(&timer_key) {
+                    timer.kind().record_deferred(false);
+                }
                 self.timer_service.as_mut().remove_timer(timer_key)
             }
             Action::AckStoredCommand {
@@ -503,6 +860,10 @@ impl LeaderState {
                             request_id,
                             execution_time,
                             is_new_invocation,
+                            consistency_token: Some(ConsistencyToken::new(
+                                self.partition_id,
+                                applied_lsn,
+                            )),
                         },
                     )));
                 }
@@ -588,6 +949,9 @@ impl LeaderState {
                     )));
                 }
             }
+            Action::NotifyInvocationLifecycleEvent { event } => {
+                self.lifecycle_event_sender.send(event)
+            }
         }
 
         Ok(())
@@ -11,6 +11,7 @@
 mod durability_tracker;
 mod leader_state;
 mod self_proposer;
+mod timer_interleaver;
 pub mod trim_queue;
 
 use std::cmp::Ordering;
@@ -20,6 +21,7 @@ use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::Duration;
 
+use enumset::EnumSet;
 use futures::{StreamExt, TryStreamExt};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
@@ -38,30 +40,36 @@ use restate_storage_api::invocation_status_table::{
 };
 use restate_storage_api::outbox_table::{OutboxMessage, ReadOutboxTable};
 use restate_storage_api::timer_table::{ReadTimerTable, TimerKey};
-use restate_timer::TokioClock;
+use restate_timer::RuntimeClock;
 use restate_types::GenerationalNodeId;
 use restate_types::cluster::cluster_state::RunMode;
 use restate_types::config::Configuration;
 use restate_types::errors::GenericError;
 use restate_types::identifiers::{InvocationId, PartitionKey, PartitionProcessorRpcRequestId};
 use restate_types::identifiers::{LeaderEpoch, PartitionLeaderEpoch};
+use restate_types::logs::Lsn;
 use restate_types::message::MessageIndex;
 use restate_types::net::partition_processor::{
     PartitionProcessorRpcError, PartitionProcessorRpcResponse,
 };
 use restate_types::partitions::Partition;
+use restate_types::partitions::settings::PartitionSettings;
 use restate_types::partitions::state::PartitionReplicaSetStates;
 use restate_types::retries::with_jitter;
 use restate_types::schema::Schema;
 use restate_types::storage::StorageEncodeError;
+use restate_types::timer::{TimerKind, TimerServiceRequest};
 use restate_wal_protocol::Command;
 use restate_wal_protocol::control::{AnnounceLeader, PartitionDurability};
 use restate_wal_protocol::timer::TimerKeyValue;
 
+use crate::lifecycle_webhook::LifecycleEventSender;
 use crate::partition::cleaner::Cleaner;
+use crate::partition::completion_sink::CompletionSink;
 use crate::partition::invoker_storage_reader::InvokerStorageReader;
 use crate::partition::leadership::leader_state::LeaderState;
 use crate::partition::leadership::self_proposer::SelfProposer;
+use crate::partition::leadership::timer_interleaver::TimerInterleaver;
 use crate::partition::shuffle;
 use crate::partition::shuffle::{OutboxReaderError, Shuffle, ShuffleMetadata};
 use crate::partition::state_machine::{Action, StateMachine};
@@ -70,7 +78,7 @@ use crate::partition::types::InvokerEffect;
 use self::durability_tracker::DurabilityTracker;
 use self::trim_queue::{LogTrimmer, TrimQueue};
 
-type TimerService = restate_timer::TimerService<TimerKeyValue, TokioClock, TimerReader>;
+type TimerService = restate_timer::TimerService<TimerKeyValue, RuntimeClock, TimerReader>;
 type InvokerStream = ReceiverStream<InvokerEffect>;
 
 #[derive(Debug, thiserror::Error)]
@@ -122,10 +130,15 @@ pub(crate) enum TaskTermination {
 pub(crate) enum ActionEffect {
     Invoker(Box<restate_invoker_api::Effect>),
     Shuffle(shuffle::OutboxTruncation),
-    Timer(TimerKeyValue),
+    Timers(Vec<TimerKeyValue>),
+    /// Queued housekeeping timers might be allowed to fire
+    HousekeepingTimers,
+    PausedTimerKinds(EnumSet<TimerKind>),
+    TimerServiceRequest(TimerServiceRequest),
     ScheduleCleanupTimer(InvocationId, Duration),
     PartitionMaintenance(PartitionDurability),
     UpsertSchema(Schema),
+    UpdatePartitionSettings(PartitionSettings),
     AwaitingRpcSelfProposeDone,
 }
 enum State {
@@ -157,6 +170,8 @@ pub(crate) struct LeadershipState<I> {
     bifrost: Bifrost,
     #[allow(unused)]
     trim_queue: TrimQueue,
+    timer_clock: RuntimeClock,
+    lifecycle_event_sender: LifecycleEventSender,
 }
 
 impl<I> LeadershipState<I>
@@ -170,6 +185,8 @@ where
         bifrost: Bifrost,
         last_seen_leader_epoch: Option<LeaderEpoch>,
         trim_queue: TrimQueue,
+        timer_clock: RuntimeClock,
+        lifecycle_event_sender: LifecycleEventSender,
     ) -> Self {
         Self {
             state: State::Follower,
@@ -178,6 +195,8 @@ where
             bifrost,
             last_seen_leader_epoch,
             trim_queue,
+            timer_clock,
+            lifecycle_event_sender,
         }
     }
 
@@ -358,11 +377,18 @@ where
             )
             .await?;
 
-            let timer_service = TimerService::new(
-                TokioClock,
+            let timer_service = TimerService::new_with_memory_budget(
+                self.timer_clock.clone(),
                 config.worker.num_timers_in_memory_limit(),
+                config.worker.timers_memory_budget(),
                 TimerReader::from(partition_store.clone()),
             );
+            let timer_service = match config.worker.timers_max_batch_size() {
+                Some(max_batch_size) => {
+                    timer_service.with_batching(max_batch_size, config.worker.timers_batch_window())
+                }
+                None => timer_service,
+            };
 
             let (shuffle_tx, shuffle_rx) = mpsc::channel(config.worker.internal_queue_length());
 
@@ -390,6 +416,36 @@ where
             let cleaner_task_id =
                 TaskCenter::spawn_child(TaskKind::Cleaner, "cleaner", cleaner.run())?;
 
+            let completion_sink_task_id = match &config.worker.completion_kafka_sink {
+                Some(options) => match config.ingress.get_kafka_cluster(&options.cluster) {
+                    Some(cluster) => {
+                        let completion_sink = CompletionSink::new(
+                            self.partition.partition_id,
+                            *leader_epoch,
+                            *self.partition.key_range.start(),
+                            partition_store.clone(),
+                            self.bifrost.clone(),
+                            options.clone(),
+                            cluster.clone(),
+                        );
+                        Some(TaskCenter::spawn_child(
+                            TaskKind::CompletionSink,
+                            "completion-sink",
+                            completion_sink.run(),
+                        )?)
+                    }
+                    None => {
+                        warn!(
+                            "The completion Kafka sink cluster '{}' is not configured in ingress.kafka-clusters, completion events are not delivered. Configured Kafka clusters: {:?}",
+                            options.cluster,
+                            config.ingress.available_kafka_clusters()
+                        );
+                        None
+                    }
+                },
+                None => None,
+            };
+
             let trimmer_task_id = LogTrimmer::spawn(
                 self.bifrost.clone(),
                 self.partition.log_id(),
@@ -419,12 +475,17 @@ where
                 shuffle_task_handle,
                 cleaner_task_id,
                 trimmer_task_id,
+                completion_sink_task_id,
                 shuffle_hint_tx,
                 timer_service,
+                TimerInterleaver::new(config.worker.timers_interleave_ratio()),
+                config.worker.deferred_timers_limit(),
+                TimerReader::from(partition_store.clone()),
                 self_proposer,
                 invoker_rx,
                 shuffle_rx,
                 durability_tracker,
+                self.lifecycle_event_sender.clone(),
             )));
 
             Ok(())
@@ -502,13 +563,17 @@ where
         }
     }
 
-    pub fn handle_actions(&mut self, actions: impl Iterator<Item = Action>) -> Result<(), Error> {
+    pub fn handle_actions(
+        &mut self,
+        actions: impl Iterator<Item = Action>,
+        applied_lsn: Lsn,
+    ) -> Result<(), Error> {
         match &mut self.state {
             State::Follower | State::Candidate { .. } => {
                 // nothing to do :-)
             }
             State::Leader(leader_state) => {
-                leader_state.handle_actions(&mut self.invoker_tx, actions)?;
+                leader_state.handle_actions(&mut self.invoker_tx, actions, applied_lsn)?;
             }
         }
 
@@ -586,6 +651,21 @@ impl<I> LeadershipState<I> {
         }
     }
 
+    /// Self proposes the command to this partition if we are the leader. Returns `false` otherwise.
+    pub async fn self_propose(
+        &mut self,
+        partition_key: PartitionKey,
+        cmd: Command,
+    ) -> Result<bool, Error> {
+        match &mut self.state {
+            State::Follower | State::Candidate { .. } => Ok(false),
+            State::Leader(leader_state) => {
+                leader_state.self_propose(partition_key, cmd).await?;
+                Ok(true)
+            }
+        }
+    }
+
     /// Self propose to this partition, and register the reciprocal to respond asynchronously.
     pub async fn self_propose_and_respond_asynchronously(
         &mut self,
@@ -657,24 +737,36 @@ impl shuffle::OutboxReader for OutboxReader {
 
 #[cfg(test)]
 mod tests {
+    use crate::lifecycle_webhook::LifecycleEventSender;
+    use crate::partition::invoker_storage_reader::InvokerStorageReader;
     use crate::partition::leadership::trim_queue::TrimQueue;
     use crate::partition::leadership::{LeadershipState, State};
+    use crate::partition::state_machine::StateMachine;
     use assert2::let_assert;
-    use restate_bifrost::Bifrost;
+    use enumset::EnumSet;
+    use restate_bifrost::{Bifrost, LogReadStream};
     use restate_core::{TaskCenter, TestCoreEnv};
     use restate_invoker_api::test_util::MockInvokerHandle;
-    use restate_partition_store::PartitionStoreManager;
+    use restate_partition_store::{PartitionStore, PartitionStoreManager};
     use restate_rocksdb::RocksDbManager;
-    use restate_types::GenerationalNodeId;
-    use restate_types::config::Configuration;
-    use restate_types::identifiers::{LeaderEpoch, PartitionId, PartitionKey};
+    use restate_storage_api::Transaction;
+    use restate_storage_api::timer_table::{TimerKey, WriteTimerTable};
+    use restate_types::config::{Configuration, WorkerOptionsBuilder};
+    use restate_types::identifiers::{InvocationId, LeaderEpoch, PartitionId, PartitionKey};
     use restate_types::logs::{KeyFilter, Lsn, SequenceNumber};
     use restate_types::partitions::Partition;
     use restate_types::partitions::state::PartitionReplicaSetStates;
+    use restate_types::time::MillisSinceEpoch;
+    use restate_types::timer::{TimerKind, set_paused_timer_kinds};
+    use restate_types::{GenerationalNodeId, SemanticRestateVersion};
     use restate_wal_protocol::control::AnnounceLeader;
+    use restate_wal_protocol::timer::TimerKeyValue;
     use restate_wal_protocol::{Command, Envelope};
+    use std::collections::HashMap;
+    use std::num::NonZeroUsize;
     use std::ops::RangeInclusive;
     use std::sync::Arc;
+    use std::time::Duration;
     use test_log::test;
     use tokio_stream::StreamExt;
 
@@ -700,6 +792,8 @@ mod tests {
             bifrost.clone(),
             None,
             TrimQueue::default(),
+            RuntimeClock::default(),
+            LifecycleEventSender::default(),
         );
 
         assert!(matches!(state.state, State::Follower));
@@ -750,4 +844,156 @@ mod tests {
         RocksDbManager::get().shutdown().await;
         Ok(())
     }
+
+    #[test(restate_core::test)]
+    async fn paused_timer_kinds_are_deferred_and_reloaded_once_resumed() -> googletest::Result<()> {
+        let env = TestCoreEnv::create_with_single_node(0, 0).await;
+
+        RocksDbManager::init();
+        let bifrost = Bifrost::init_in_memory(env.metadata_writer).await;
+        let replica_set_states = PartitionReplicaSetStates::default();
+
+        let partition_store_manager = PartitionStoreManager::create().await?;
+        let mut partition_store = partition_store_manager.open(&PARTITION, None).await?;
+
+        // three due cleanup timers, of which only the first one is kept in memory while the
+        // cleanup is paused, and a due sleep timer which fires nevertheless
+        let cleanup_timers: Vec<_> = (1..=3)
+            .map(|timestamp| {
+                TimerKeyValue::clean_invocation_status(
+                    MillisSinceEpoch::new(timestamp),
+                    InvocationId::mock_random(),
+                )
+            })
+            .collect();
+        let sleep_timer = TimerKeyValue::complete_journal_entry(
+            MillisSinceEpoch::new(4),
+            InvocationId::mock_random(),
+            1,
+            0,
+        );
+        let mut tx = partition_store.transaction();
+        for timer in cleanup_timers.iter().chain([&sleep_timer]) {
+            tx.put_timer(timer.key(), timer.value())?;
+        }
+        tx.commit().await?;
+
+        let mut config = Configuration::default();
+        config.worker = WorkerOptionsBuilder::default()
+            .deferred_timers_limit(NonZeroUsize::new(1).unwrap())
+            .build()?;
+        set_paused_timer_kinds(EnumSet::only(TimerKind::Cleanup));
+
+        let mut state = LeadershipState::new(
+            Arc::new(PARTITION),
+            MockInvokerHandle::default(),
+            bifrost.clone(),
+            None,
+            TrimQueue::default(),
+            RuntimeClock::default(),
+            LifecycleEventSender::default(),
+        );
+        let state_machine = StateMachine::new(
+            0,
+            0,
+            None,
+            PARTITION_KEY_RANGE,
+            SemanticRestateVersion::unknown().clone(),
+            Default::default(),
+            None,
+        );
+
+        let leader_epoch = LeaderEpoch::from(1);
+        state.run_for_leader(leader_epoch).await?;
+
+        let mut reader =
+            bifrost.create_reader(PARTITION_ID.into(), KeyFilter::Any, Lsn::OLDEST, Lsn::MAX)?;
+        let envelope = reader
+            .next()
+            .await
+            .unwrap()?
+            .try_decode::<Envelope>()
+            .unwrap()?;
+        let_assert!(Command::AnnounceLeader(announce_leader) = envelope.command);
+        state
+            .on_announce_leader(
+                &announce_leader,
+                &mut partition_store,
+                &replica_set_states,
+                &config,
+            )
+            .await?;
+
+        let mut fired_timers = HashMap::new();
+        run_until_fired(
+            &mut state,
+            &state_machine,
+            &mut reader,
+            &mut fired_timers,
+            1,
+        )
+        .await?;
+        assert_eq!(
+            fired_timers,
+            HashMap::from([(sleep_timer.key().clone(), 1)])
+        );
+
+        set_paused_timer_kinds(EnumSet::empty());
+        run_until_fired(
+            &mut state,
+            &state_machine,
+            &mut reader,
+            &mut fired_timers,
+            4,
+        )
+        .await?;
+        assert_eq!(
+            fired_timers,
+            cleanup_timers
+                .iter()
+                .chain([&sleep_timer])
+                .map(|timer| (timer.key().clone(), 1))
+                .collect()
+        );
+
+        state.step_down().await;
+
+        TaskCenter::current()
+            .shutdown_node("test_completed", 0)
+            .await;
+        RocksDbManager::get().shutdown().await;
+        Ok(())
+    }
+
+    /// Runs the leader until the given number of timers fired, counting the fired timers.
+    async fn run_until_fired(
+        state: &mut LeadershipState<MockInvokerHandle<InvokerStorageReader<PartitionStore>>>,
+        state_machine: &StateMachine,
+        reader: &mut LogReadStream,
+        fired_timers: &mut HashMap<TimerKey, usize>,
+        num_timers: usize,
+    ) -> googletest::Result<()> {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while fired_timers.values().sum::<usize>() < num_timers {
+                tokio::select! {
+                    action_effects = state.run(state_machine) => {
+                        state.handle_action_effects(action_effects?).await?;
+                    }
+                    record = reader.next() => {
+                        let envelope = record.unwrap()?.try_decode::<Envelope>().unwrap()?;
+                        let timers = match envelope.command {
+                            Command::Timer(timer) => vec![timer],
+                            Command::TimerBatch(timers) => timers,
+                            _ => vec![],
+                        };
+                        for timer in timers {
+                            *fired_timers.entry(timer.key().clone()).or_default() += 1;
+                        }
+                    }
+                }
+            }
+            googletest::Result::Ok(())
+        })
+        .await?
+    }
 }
@@ -0,0 +1,168 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+
+use restate_types::timer::TimerPriority;
+use restate_wal_protocol::timer::TimerKeyValue;
+
+/// Interleaves the firing of the housekeeping timers with the interactive commands proposed by
+/// the leader.
+///
+/// While the leader keeps proposing interactive commands, one queued housekeeping timer is
+/// released every `ratio` of them. Once the leader didn't propose any interactive command since
+/// the last release, the housekeeping timers are released without waiting.
+pub(super) struct TimerInterleaver {
+    ratio: Option<NonZeroUsize>,
+    housekeeping_timers: VecDeque<TimerKeyValue>,
+    // interactive commands proposed since the last release
+    pending_interactive_commands: usize,
+    // interactive commands not yet paid off by the release of a housekeeping timer
+    credits: usize,
+}
+
+impl TimerInterleaver {
+    pub(super) fn new(ratio: Option<NonZeroUsize>) -> Self {
+        Self {
+            ratio,
+            housekeeping_timers: VecDeque::new(),
+            pending_interactive_commands: 0,
+            credits: 0,
+        }
+    }
+
+    /// Queues the housekeeping timers among the given due timers, returning the ones to fire
+    /// right away.
+    pub(super) fn prioritize(&mut self, timers: Vec<TimerKeyValue>) -> Vec<TimerKeyValue> {
+        if self.ratio.is_none() {
+            return timers;
+        }
+
+        let mut interactive_timers = Vec::with_capacity(timers.len());
+        for timer in timers {
+            match timer.kind().priority() {
+                TimerPriority::Interactive => interactive_timers.push(timer),
+                TimerPriority::Housekeeping => self.housekeeping_timers.push_back(timer),
+            }
+        }
+        interactive_timers
+    }
+
+    pub(super) fn record_interactive_commands(&mut self, num_commands: usize) {
+        // only the commands competing with queued housekeeping timers count
+        if !self.housekeeping_timers.is_empty() {
+            self.pending_interactive_commands += num_commands;
+        }
+    }
+
+    pub(super) fn has_housekeeping_timers(&self) -> bool {
+        !self.housekeeping_timers.is_empty()
+    }
+
+    pub(super) fn num_housekeeping_timers(&self) -> usize {
+        self.housekeeping_timers.len()
+    }
+
+    /// Releases up to `limit` queued housekeeping timers which are allowed to fire.
+    pub(super) fn release_housekeeping_timers(&mut self, limit: usize) -> Vec<TimerKeyValue> {
+        let num_timers = match self.ratio {
+            Some(ratio) if self.pending_interactive_commands > 0 => {
+                self.credits += self.pending_interactive_commands;
+                let num_timers = self.credits / ratio;
+                self.credits %= ratio.get();
+                num_timers
+            }
+            // idle, or priorities are disabled
+            _ => {
+                self.credits = 0;
+                usize::MAX
+            }
+        };
+        self.pending_interactive_commands = 0;
+
+        let num_timers = num_timers.min(limit).min(self.housekeeping_timers.len());
+        let timers = self.housekeeping_timers.drain(..num_timers).collect();
+        if self.housekeeping_timers.is_empty() {
+            self.credits = 0;
+        }
+        timers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use googletest::prelude::*;
+
+    use restate_types::identifiers::InvocationId;
+    use restate_types::time::MillisSinceEpoch;
+
+    fn cleanup_timer(wake_up_time: u64) -> TimerKeyValue {
+        TimerKeyValue::clean_invocation_status(
+            MillisSinceEpoch::new(wake_up_time),
+            InvocationId::mock_random(),
+        )
+    }
+
+    fn delayed_invocation_timer(wake_up_time: u64) -> TimerKeyValue {
+        TimerKeyValue::neo_invoke(
+            MillisSinceEpoch::new(wake_up_time),
+            InvocationId::mock_random(),
+        )
+    }
+
+    #[test]
+    fn fire_all_timers_when_disabled() {
+        let mut interleaver = TimerInterleaver::new(None);
+
+        let timers = interleaver.prioritize(vec![cleanup_timer(1), delayed_invocation_timer(2)]);
+
+        assert_that!(timers.len(), eq(2));
+        assert_that!(interleaver.has_housekeeping_timers(), eq(false));
+    }
+
+    #[test]
+    fn interleave_housekeeping_timers_while_busy() {
+        let mut interleaver = TimerInterleaver::new(NonZeroUsize::new(3));
+
+        let timers = interleaver.prioritize(vec![
+            cleanup_timer(1),
+            delayed_invocation_timer(2),
+            cleanup_timer(3),
+            cleanup_timer(4),
+        ]);
+        assert_that!(timers.len(), eq(1));
+        assert_that!(interleaver.num_housekeeping_timers(), eq(3));
+
+        // two interactive commands don't pay off a housekeeping timer yet
+        interleaver.record_interactive_commands(2);
+        assert_that!(interleaver.release_housekeeping_timers(10), empty());
+
+        // but they add up with the next ones
+        interleaver.record_interactive_commands(4);
+        let released = interleaver.release_housekeeping_timers(10);
+        assert_that!(released.len(), eq(2));
+        assert_that!(released[0].wake_up_time(), eq(MillisSinceEpoch::new(1)));
+        assert_that!(interleaver.num_housekeeping_timers(), eq(1));
+    }
+
+    #[test]
+    fn release_housekeeping_timers_when_idle() {
+        let mut interleaver = TimerInterleaver::new(NonZeroUsize::new(100));
+
+        interleaver.prioritize(vec![cleanup_timer(1), cleanup_timer(2), cleanup_timer(3)]);
+
+        assert_that!(interleaver.release_housekeeping_timers(2).len(), eq(2));
+        assert_that!(interleaver.release_housekeeping_timers(2).len(), eq(1));
+        assert_that!(interleaver.has_housekeeping_timers(), eq(false));
+    }
+}
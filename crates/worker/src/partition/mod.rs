@@ -9,8 +9,10 @@
 // by the Apache License, Version 2.0.
 
 mod cleaner;
+mod completion_sink;
 pub mod invoker_storage_reader;
 mod leadership;
+mod pending_rpcs;
 mod rpc;
 pub mod shuffle;
 mod state_machine;
@@ -22,15 +24,16 @@ use std::time::Duration;
 
 use anyhow::Context;
 use assert2::let_assert;
+use bytes::BytesMut;
 use enumset::EnumSet;
 use futures::{FutureExt, Stream, StreamExt};
-use metrics::{SharedString, gauge, histogram};
+use metrics::{SharedString, counter, gauge, histogram};
 use tokio::sync::{mpsc, watch};
 use tokio::time::{Instant, MissedTickBehavior};
 use tracing::{Span, debug, error, info, instrument, trace, warn};
 
 use restate_bifrost::loglet::FindTailOptions;
-use restate_bifrost::{Bifrost, LogEntry, MaybeRecord};
+use restate_bifrost::{Bifrost, ErrorRecoveryStrategy, LogEntry, MaybeRecord};
 use restate_core::network::{Oneshot, Reciprocal, ServiceMessage, Verdict};
 use restate_core::{Metadata, ShutdownError, cancellation_watcher, my_node_id};
 use restate_partition_store::{PartitionStore, PartitionStoreTransaction};
@@ -40,8 +43,14 @@ use restate_storage_api::deduplication_table::{
 };
 use restate_storage_api::fsm_table::{PartitionDurability, ReadFsmTable, WriteFsmTable};
 use restate_storage_api::outbox_table::ReadOutboxTable;
+use restate_storage_api::parked_command_table::{
+    ParkedCommand, ReadParkedCommandTable, WriteParkedCommandTable,
+};
 use restate_storage_api::{StorageError, Transaction};
 use restate_time_util::DurationExt;
+use restate_timer::RuntimeClock;
+#[cfg(feature = "chaos")]
+use restate_types::chaos;
 use restate_types::cluster::cluster_state::{PartitionProcessorStatus, ReplayStatus, RunMode};
 use restate_types::config::Configuration;
 use restate_types::identifiers::LeaderEpoch;
@@ -51,23 +60,33 @@ use restate_types::net::partition_processor::{
     PartitionLeaderService, PartitionProcessorRpcError, PartitionProcessorRpcRequest,
     PartitionProcessorRpcResponse,
 };
+use restate_types::partitions::parked_commands::{self, ParkedCommandInfo, ParkedCommandsRequest};
+use restate_types::partitions::recovery::{self, RecoveryPhase};
 use restate_types::partitions::state::PartitionReplicaSetStates;
 use restate_types::retries::{RetryPolicy, with_jitter};
 use restate_types::schema::Schema;
-use restate_types::storage::StorageDecodeError;
+use restate_types::storage::{StorageCodec, StorageDecodeError};
 use restate_types::time::{MillisSinceEpoch, NanosSinceEpoch};
 use restate_types::{GenerationalNodeId, SemanticRestateVersion};
-use restate_wal_protocol::control::AnnounceLeader;
-use restate_wal_protocol::{Command, Destination, Envelope, Header};
+use restate_wal_protocol::control::{AnnounceLeader, ParkCommand, RetryParkedCommand};
+use restate_wal_protocol::{Command, Destination, Envelope, Header, Source};
 
 use self::leadership::trim_queue::TrimQueue;
+use crate::lifecycle_webhook::LifecycleEventSender;
 use crate::metric_definitions::{
-    PARTITION_BLOCKED_FLARE, PARTITION_LABEL, PARTITION_RECORD_COMMITTED_TO_READ_LATENCY_SECONDS,
+    PARTITION_BLOCKED_FLARE, PARTITION_LABEL, PARTITION_PARKED_COMMANDS,
+    PARTITION_RECORD_COMMITTED_TO_READ_LATENCY_SECONDS, PARTITION_RECOVERY_PROGRESS,
 };
 use crate::partition::invoker_storage_reader::InvokerStorageReader;
 use crate::partition::leadership::LeadershipState;
+use crate::partition::pending_rpcs::PendingRpcs;
 use crate::partition::state_machine::{ActionCollector, StateMachine};
 
+/// Maximum time an rpc waits for the partition processor to catch up with its consistency token.
+const CONSISTENT_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the replay progress of a recovering partition is logged.
+const RECOVERY_PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Target leader state of the partition processor.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum TargetLeaderState {
@@ -76,6 +95,16 @@ pub enum TargetLeaderState {
     Follower,
 }
 
+/// A command the partition processor failed to apply. The partition processor manager keeps track
+/// of it across restarts of the processor. Once it failed `worker.poison-command-max-attempts`
+/// times on the designated leader, the leader proposes to park it with a [`ParkCommand`].
+#[derive(Debug, Clone)]
+pub struct FailedCommand {
+    pub lsn: Lsn,
+    pub attempts: u32,
+    pub failure: String,
+}
+
 #[derive(Debug)]
 pub(super) struct PartitionProcessorBuilder<InvokerInputSender> {
     status: PartitionProcessorStatus,
@@ -83,6 +112,9 @@ pub(super) struct PartitionProcessorBuilder<InvokerInputSender> {
     target_leader_state_rx: watch::Receiver<TargetLeaderState>,
     network_svc_rx: mpsc::Receiver<ServiceMessage<PartitionLeaderService>>,
     status_watch_tx: watch::Sender<PartitionProcessorStatus>,
+    timer_clock: RuntimeClock,
+    failed_command: Option<FailedCommand>,
+    lifecycle_event_sender: LifecycleEventSender,
 }
 
 impl<InvokerInputSender> PartitionProcessorBuilder<InvokerInputSender>
@@ -96,6 +128,7 @@ where
         network_svc_rx: mpsc::Receiver<ServiceMessage<PartitionLeaderService>>,
         status_watch_tx: watch::Sender<PartitionProcessorStatus>,
         invoker_tx: InvokerInputSender,
+        timer_clock: RuntimeClock,
     ) -> Self {
         Self {
             status,
@@ -103,9 +136,27 @@ where
             target_leader_state_rx,
             network_svc_rx,
             status_watch_tx,
+            timer_clock,
+            failed_command: None,
+            lifecycle_event_sender: LifecycleEventSender::default(),
         }
     }
 
+    /// Command which failed to apply in the previous runs of the processor.
+    pub(super) fn with_failed_command(mut self, failed_command: Option<FailedCommand>) -> Self {
+        self.failed_command = failed_command;
+        self
+    }
+
+    /// Sender of the invocation lifecycle events emitted while being the leader.
+    pub(super) fn with_lifecycle_event_sender(
+        mut self,
+        lifecycle_event_sender: LifecycleEventSender,
+    ) -> Self {
+        self.lifecycle_event_sender = lifecycle_event_sender;
+        self
+    }
+
     pub async fn build(
         self,
         bifrost: Bifrost,
@@ -118,11 +169,13 @@ where
             network_svc_rx: rpc_rx,
             status_watch_tx,
             status,
-            ..
+            timer_clock,
+            failed_command,
+            lifecycle_event_sender,
         } = self;
 
         let partition_id_str = SharedString::from(partition_store.partition_id().to_string());
-        let state_machine = Self::create_state_machine(&mut partition_store).await?;
+        let state_machine = create_state_machine(&mut partition_store).await?;
 
         let trim_queue = TrimQueue::default();
         if let Some(ref partition_durability) = partition_store.get_partition_durability().await? {
@@ -157,6 +210,8 @@ where
             bifrost.clone(),
             last_seen_leader_epoch,
             trim_queue.clone(),
+            timer_clock,
+            lifecycle_event_sender,
         );
 
         Ok(PartitionProcessor {
@@ -171,40 +226,50 @@ where
             status,
             replica_set_states,
             trim_queue,
+            pending_rpcs: PendingRpcs::default(),
+            failed_command,
+            park_command: None,
         })
     }
+}
 
-    async fn create_state_machine(
-        partition_store: &mut PartitionStore,
-    ) -> Result<StateMachine, state_machine::Error> {
-        let inbox_seq_number = partition_store.get_inbox_seq_number().await?;
-        let outbox_seq_number = partition_store.get_outbox_seq_number().await?;
-        let outbox_head_seq_number = partition_store.get_outbox_head_seq_number().await?;
-        let min_restate_version = partition_store.get_min_restate_version().await?;
-        let schema = partition_store.get_schema().await?;
-
-        if !SemanticRestateVersion::current().is_equal_or_newer_than(&min_restate_version) {
-            gauge!(PARTITION_BLOCKED_FLARE, PARTITION_LABEL =>
-                partition_store.partition_id().to_string())
-            .set(1);
-            return Err(state_machine::Error::VersionBarrier {
-                required_min_version: min_restate_version,
-                barrier_reason: String::new(),
-            });
-        }
-
-        let state_machine = StateMachine::new(
-            inbox_seq_number,
-            outbox_seq_number,
-            outbox_head_seq_number,
-            partition_store.partition_key_range().clone(),
-            min_restate_version,
-            EnumSet::empty(),
-            schema,
-        );
-
-        Ok(state_machine)
+/// Creates the state machine from the state persisted in the partition store.
+async fn create_state_machine(
+    partition_store: &mut PartitionStore,
+) -> Result<StateMachine, state_machine::Error> {
+    let inbox_seq_number = partition_store.get_inbox_seq_number().await?;
+    let outbox_seq_number = partition_store.get_outbox_seq_number().await?;
+    let outbox_head_seq_number = partition_store.get_outbox_head_seq_number().await?;
+    let min_restate_version = partition_store.get_min_restate_version().await?;
+    let schema = partition_store.get_schema().await?;
+    let settings = partition_store.get_partition_settings().await?;
+    let state_clock = partition_store.get_state_clock().await?;
+
+    if !SemanticRestateVersion::current().is_equal_or_newer_than(&min_restate_version) {
+        gauge!(PARTITION_BLOCKED_FLARE, PARTITION_LABEL =>
+            partition_store.partition_id().to_string())
+        .set(1);
+        return Err(state_machine::Error::VersionBarrier {
+            required_min_version: min_restate_version,
+            barrier_reason: String::new(),
+        });
     }
+
+    let config = Configuration::pinned();
+    let state_machine = StateMachine::new(
+        inbox_seq_number,
+        outbox_seq_number,
+        outbox_head_seq_number,
+        partition_store.partition_key_range().clone(),
+        min_restate_version,
+        EnumSet::empty(),
+        schema,
+    )
+    .with_settings(settings)
+    .with_state_clock(state_clock)
+    .with_lifecycle_events(config.worker.invocation_lifecycle_webhook.is_some());
+
+    Ok(state_machine)
 }
 
 pub struct PartitionProcessor<InvokerSender> {
@@ -220,6 +285,10 @@ pub struct PartitionProcessor<InvokerSender> {
 
     partition_store: PartitionStore,
     trim_queue: TrimQueue,
+    pending_rpcs: PendingRpcs,
+    failed_command: Option<FailedCommand>,
+    /// The leader's decision to park the command which failed to apply in the previous runs
+    park_command: Option<ParkCommand>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -257,6 +326,13 @@ pub enum ProcessorError {
     StoreOpen(#[from] restate_partition_store::OpenError),
     #[error(transparent)]
     StateMachine(#[from] state_machine::Error),
+    #[error("failed applying command '{command}' at lsn {lsn}: {source}")]
+    ApplyCommand {
+        lsn: Lsn,
+        command: &'static str,
+        #[source]
+        source: state_machine::Error,
+    },
     #[error(transparent)]
     ActionEffect(#[from] leadership::Error),
     #[error(transparent)]
@@ -267,6 +343,20 @@ pub enum ProcessorError {
     Other(#[from] anyhow::Error),
 }
 
+/// Commands which fence off the state machine, or which the partition processor applies itself, are
+/// never parked.
+fn is_parkable(command: &Command) -> bool {
+    !matches!(
+        command,
+        Command::AnnounceLeader(_)
+            | Command::UpdatePartitionDurability(_)
+            | Command::ParkCommand(_)
+            | Command::VersionBarrier(_)
+            | Command::UpsertSchema(_)
+            | Command::UpdatePartitionSettings(_)
+    )
+}
+
 struct LsnEnvelope {
     pub lsn: Lsn,
     pub created_at: NanosSinceEpoch,
@@ -309,7 +399,10 @@ where
         };
 
         // clean up pending rpcs and stop child tasks
+        parked_commands::deregister_partition_processor(self.partition_store.partition_id());
         self.leadership_state.step_down().await;
+        self.pending_rpcs
+            .fail_all(|| PartitionProcessorRpcError::Stopping);
 
         // Drain leader network service
         self.network_leader_svc_rx.close();
@@ -323,8 +416,10 @@ where
 
     async fn run_inner(&mut self) -> Result<(), ProcessorError> {
         let mut partition_store = self.partition_store.clone();
+        let partition_id = self.partition_store.partition_id();
 
         // Run migrations
+        recovery::enter_phase(partition_id, RecoveryPhase::Migrating);
         partition_store.verify_and_run_migrations().await?;
 
         let last_applied_lsn = partition_store
@@ -333,7 +428,6 @@ where
             .unwrap_or(Lsn::INVALID);
 
         let log_id = self.partition_store.partition().log_id();
-        let partition_id = self.partition_store.partition_id();
         let my_node = my_node_id().as_plain();
 
         self.status.last_applied_log_lsn = Some(last_applied_lsn);
@@ -345,6 +439,7 @@ where
         self.replica_set_states
             .note_durable_lsn(partition_id, my_node, durable_lsn);
 
+        recovery::enter_phase(partition_id, RecoveryPhase::FindingLogTail);
         // If the underlying log is not provisioned, now is the time to provision it.
         // We'll retry a few times before giving back control to PPM
         //
@@ -402,6 +497,14 @@ where
             self.status.replay_status = ReplayStatus::CatchingUp;
         }
 
+        if let Some(failed_command) = self.failed_command.take()
+            && failed_command.lsn > last_applied_lsn
+        {
+            self.park_command = self
+                .find_or_propose_park_command(failed_command, current_tail.offset())
+                .await?;
+        }
+
         let mut live_config = Configuration::live();
         let mut live_schemas = Metadata::with_current(|m| m.updateable_schema());
 
@@ -431,15 +534,27 @@ where
         let mut watch_leader_changes = self.replica_set_states.watch_leadership_state(partition_id);
         watch_leader_changes.mark_changed();
 
+        let mut parked_commands_rx = parked_commands::register_partition_processor(partition_id);
+
         let started_at = Instant::now();
+        // to derive the rate of applied commands
+        let mut last_status_update_at = started_at;
+        let mut applied_since_last_status_update = 0u64;
+        let recovery_progress_gauge =
+            gauge!(PARTITION_RECOVERY_PROGRESS, PARTITION_LABEL => self.partition_id_str.clone());
+        let mut last_recovery_log_at = started_at;
         if self.status.replay_status == ReplayStatus::CatchingUp {
             let catchup_len = current_tail.offset().as_u64() - last_applied_lsn.next().as_u64();
+            recovery::start_replay(partition_id, catchup_len);
+            recovery_progress_gauge.set(0.0);
             info!(
                 "Partition {partition_id} started. Replaying {catchup_len} record(s) in range: [{}..{}]",
                 last_applied_lsn.next(),
                 current_tail.offset().prev()
             );
         } else {
+            recovery::start_replay(partition_id, 0);
+            recovery_progress_gauge.set(100.0);
             info!("Partition {partition_id} started");
         }
 
@@ -464,6 +579,14 @@ where
                 }
                 Some(msg) = self.network_leader_svc_rx.recv() => {
                     match msg {
+                        #[cfg(feature = "chaos")]
+                        ServiceMessage::Rpc(msg) if chaos::should_drop_partition_rpc() => {
+                            // The sender observes the request as dropped by the network
+                            debug!(
+                                "Dropping partition processor rpc {} because of the injected faults",
+                                msg.msg_type()
+                            );
+                        }
                         ServiceMessage::Rpc(msg) if msg.msg_type() == PartitionProcessorRpcRequest::TYPE => {
                             let msg = msg.into_typed::<PartitionProcessorRpcRequest>();
                             // note: split() decodes the payload
@@ -473,7 +596,11 @@ where
                         msg => { msg.fail(Verdict::MessageUnrecognized); }
                     }
                 }
+                Some(request) = parked_commands_rx.recv() => {
+                    self.on_parked_commands_request(request, &mut partition_store).await;
+                }
                 _ = status_update_timer.tick() => {
+                    self.on_pending_rpcs(&mut partition_store, live_schemas.live_load()).await;
                     if durable_lsn_watch.has_changed().map_err(|e| ProcessorError::Other(e.into()))? {
                         let durable_lsn = durable_lsn_watch
                                 .borrow_and_update()
@@ -485,6 +612,36 @@ where
                             durable_lsn,
                         );
                     }
+                    let now = Instant::now();
+                    self.status.applied_commands_per_second = applied_since_last_status_update as f64
+                        / now.duration_since(last_status_update_at).as_secs_f64();
+                    last_status_update_at = now;
+                    applied_since_last_status_update = 0;
+
+                    if self.status.replay_status == ReplayStatus::CatchingUp {
+                        let replayed = self
+                            .status
+                            .last_applied_log_lsn
+                            .unwrap_or(last_applied_lsn)
+                            .as_u64()
+                            .saturating_sub(last_applied_lsn.as_u64());
+                        recovery::on_replayed(partition_id, replayed);
+                        if let Some(progress) = recovery::progress(partition_id) {
+                            recovery_progress_gauge.set(progress.percentage());
+                            if now.duration_since(last_recovery_log_at) >= RECOVERY_PROGRESS_LOG_INTERVAL {
+                                last_recovery_log_at = now;
+                                info!(
+                                    "Partition {partition_id} is replaying: {:.1}% ({}/{} record(s))",
+                                    progress.percentage(),
+                                    progress.replayed_records,
+                                    progress.records_to_replay,
+                                );
+                            }
+                        }
+                    } else {
+                        recovery_progress_gauge.set(100.0);
+                    }
+
                     self.status_watch_tx.send_modify(|old| {
                         old.clone_from(&self.status);
                         old.updated_at = MillisSinceEpoch::now();
@@ -512,9 +669,10 @@ where
                             follower_record_write_to_read_latency.record(record.created_at().elapsed());
                         }
 
+                        let created_at = record.created_at();
                         let record = LsnEnvelope {
                             lsn,
-                            created_at: record.created_at(),
+                            created_at,
                             envelope: record.decode_arc()?,
                         };
 
@@ -523,6 +681,8 @@ where
                             &mut transaction,
                             &mut action_collector,
                         ).await?;
+                        applied_since_last_status_update += 1;
+                        self.status.apply_lag_ms = Some(created_at.elapsed().as_millis() as u64);
 
                         if let Some(announce_leader) = maybe_announce_leader {
                             // commit all changes so far, this is important so that the actuators see all changes
@@ -561,7 +721,9 @@ where
 
                     // Commit our changes and notify actuators about actions if we are the leader
                     transaction.commit().await?;
-                    self.leadership_state.handle_actions(action_collector.drain(..))?;
+                    let applied_lsn = self.status.last_applied_log_lsn.unwrap_or(Lsn::INVALID);
+                    self.leadership_state.handle_actions(action_collector.drain(..), applied_lsn)?;
+                    self.on_pending_rpcs(&mut partition_store, live_schemas.live_load()).await;
                 },
                 result = self.leadership_state.run(&self.state_machine) => {
                     let action_effects = result?;
@@ -608,6 +770,22 @@ where
         partition_store: &mut PartitionStore,
         schemas: &Schema,
     ) {
+        if let Some(min_applied_lsn) = body.min_applied_lsn
+            && self
+                .status
+                .last_applied_log_lsn
+                .is_none_or(|applied_lsn| applied_lsn < min_applied_lsn)
+        {
+            // Serve the request once we've caught up, to give read-your-writes semantics
+            self.pending_rpcs.push(
+                min_applied_lsn,
+                Instant::now() + CONSISTENT_RPC_TIMEOUT,
+                response_tx,
+                body,
+            );
+            return;
+        }
+
         let _ = rpc::RpcHandler::handle(
             rpc::RpcContext::new(&mut self.leadership_state, schemas, partition_store),
             body,
@@ -615,6 +793,79 @@ where
         )
         .await;
     }
+
+    /// Serves the pending rpcs whose consistency token has been caught up with, and fails the
+    /// ones which waited longer than [`CONSISTENT_RPC_TIMEOUT`].
+    async fn on_pending_rpcs(&mut self, partition_store: &mut PartitionStore, schemas: &Schema) {
+        if self.pending_rpcs.is_empty() {
+            return;
+        }
+
+        let applied_lsn = self.status.last_applied_log_lsn.unwrap_or(Lsn::INVALID);
+        for (response_tx, request) in self
+            .pending_rpcs
+            .take_caught_up(applied_lsn, Instant::now())
+        {
+            self.on_rpc(response_tx, request, partition_store, schemas)
+                .await;
+        }
+    }
+
+    async fn on_parked_commands_request(
+        &mut self,
+        request: ParkedCommandsRequest,
+        partition_store: &mut PartitionStore,
+    ) {
+        match request {
+            ParkedCommandsRequest::List { limit, reply } => {
+                let parked_commands = partition_store
+                    .get_parked_commands(limit)
+                    .await
+                    .map(|parked_commands| {
+                        parked_commands
+                            .into_iter()
+                            .map(|parked_command| ParkedCommandInfo {
+                                lsn: parked_command.lsn,
+                                command: parked_command.command_name,
+                                parked_at: parked_command.parked_at,
+                                attempts: parked_command.attempts,
+                                failure: parked_command.failure,
+                            })
+                            .collect()
+                    })
+                    .map_err(|err| err.to_string());
+                let _ = reply.send(parked_commands);
+            }
+            ParkedCommandsRequest::Retry { lsn, reply } => {
+                let result = match partition_store.get_parked_command(lsn).await {
+                    Ok(Some(_)) => {
+                        let partition_id = partition_store.partition_id();
+                        let partition_key = *partition_store.partition_key_range().start();
+                        let retry =
+                            Command::RetryParkedCommand(RetryParkedCommand { partition_id, lsn });
+                        match self
+                            .leadership_state
+                            .self_propose(partition_key, retry)
+                            .await
+                        {
+                            Ok(true) => {
+                                info!(%lsn, "Proposed to retry the parked command");
+                                Ok(true)
+                            }
+                            Ok(false) => {
+                                Err(format!("Partition {partition_id} is not led by this node"))
+                            }
+                            Err(err) => Err(err.to_string()),
+                        }
+                    }
+                    Ok(None) => Ok(false),
+                    Err(err) => Err(err.to_string()),
+                };
+                let _ = reply.send(result);
+            }
+        }
+    }
+
     async fn maybe_advance<'a>(
         &mut self,
         maybe_record: LogEntry,
@@ -666,6 +917,7 @@ where
                 // finished catching up
                 self.status.replay_status = ReplayStatus::Active;
                 self.status.target_tail_lsn = None;
+                recovery::on_recovered(self.partition_store.partition_id());
                 info!(
                     "Partition {} caught up in {}!",
                     self.partition_id_str,
@@ -685,7 +937,7 @@ where
         record: LsnEnvelope,
         transaction: &mut PartitionStoreTransaction<'_>,
         action_collector: &mut ActionCollector,
-    ) -> Result<Option<Box<AnnounceLeader>>, state_machine::Error> {
+    ) -> Result<Option<Box<AnnounceLeader>>, ProcessorError> {
         trace!(lsn = %record.lsn, "Processing bifrost record for '{}': {:?}", record.envelope.command.name(), record.envelope.header);
 
         if let Some(dedup_information) = self.is_targeted_to_me(&record.envelope.header) {
@@ -711,6 +963,28 @@ where
             let record_lsn = record.lsn;
             let envelope = Arc::unwrap_or_clone(record.envelope);
 
+            let command = envelope.command.name();
+            let parkable = is_parkable(&envelope.command);
+            if let Some(park_command) = self
+                .park_command
+                .take_if(|park_command| park_command.lsn == record_lsn)
+                && parkable
+            {
+                self.park(&envelope, park_command, transaction)?;
+                return Ok(None);
+            }
+            let into_apply_error = |source| {
+                if parkable {
+                    ProcessorError::ApplyCommand {
+                        lsn: record_lsn,
+                        command,
+                        source,
+                    }
+                } else {
+                    ProcessorError::StateMachine(source)
+                }
+            };
+
             if let Command::AnnounceLeader(announce_leader) = envelope.command {
                 // leadership change detected, let's finish our transaction here
                 return Ok(Some(announce_leader));
@@ -734,6 +1008,51 @@ where
                 if self.trim_queue.push(&partition_durability) {
                     transaction.put_partition_durability(&partition_durability)?;
                 }
+            } else if let Command::ParkCommand(park_command) = envelope.command {
+                // The replicas which failed to apply the command parked it already, when reading
+                // this command ahead of their read pointer. The others applied it successfully.
+                trace!(
+                    "Reached the decision to park the command at lsn {} of partition {}",
+                    park_command.lsn, park_command.partition_id
+                );
+            } else if let Command::RetryParkedCommand(retry) = envelope.command {
+                if retry.partition_id != self.partition_store.partition_id() {
+                    self.status.num_skipped_records += 1;
+                    trace!(
+                        "Ignore retry-parked-command message which is not targeted to me. Message is for {} but I'm {}",
+                        retry.partition_id,
+                        self.partition_store.partition_id()
+                    );
+                    return Ok(None);
+                }
+
+                let Some(parked_command) = transaction.get_parked_command(retry.lsn).await? else {
+                    debug!(
+                        "Ignoring retry of the command at lsn {} which is not parked",
+                        retry.lsn
+                    );
+                    return Ok(None);
+                };
+                transaction.delete_parked_command(retry.lsn)?;
+                let mut parked_envelope = parked_command.envelope;
+                let parked_envelope: Envelope = StorageCodec::decode(&mut parked_envelope)?;
+
+                info!(
+                    parked_lsn = %retry.lsn,
+                    "Retrying parked command '{}'",
+                    parked_command.command_name
+                );
+                self.state_machine
+                    .apply(
+                        parked_envelope.command,
+                        record_created_at.into(),
+                        record_lsn,
+                        transaction,
+                        action_collector,
+                        self.leadership_state.is_leader(),
+                    )
+                    .await
+                    .map_err(into_apply_error)?;
             } else {
                 self.state_machine
                     .apply(
@@ -744,7 +1063,8 @@ where
                         action_collector,
                         self.leadership_state.is_leader(),
                     )
-                    .await?;
+                    .await
+                    .map_err(into_apply_error)?;
             }
         } else {
             self.status.num_skipped_records += 1;
@@ -757,6 +1077,117 @@ where
         Ok(None)
     }
 
+    /// Looks up the leader's decision to park the command which failed to apply in the previous
+    /// runs of the processor. The decision is appended to the log after the command, hence it's
+    /// searched ahead of the read pointer, up to the tail. If there is none, and this processor is
+    /// the designated leader which exhausted its attempts to apply the command, it proposes it.
+    ///
+    /// Only the decision found in the log, the same for all the replicas, parks the command. The
+    /// attempts counted locally only decide whether to propose it.
+    async fn find_or_propose_park_command(
+        &self,
+        failed_command: FailedCommand,
+        tail: Lsn,
+    ) -> Result<Option<ParkCommand>, ProcessorError> {
+        let partition_id = self.partition_store.partition_id();
+        let log_id = self.partition_store.partition().log_id();
+        if failed_command.lsn.next() < tail {
+            let mut record_stream = self.bifrost.create_reader(
+                log_id,
+                KeyFilter::Within(self.partition_store.partition_key_range().clone()),
+                failed_command.lsn.next(),
+                tail.prev(),
+            )?;
+            while let Some(entry) = record_stream.next().await {
+                let (_, MaybeRecord::Data(record)) = entry?.dissolve() else {
+                    continue;
+                };
+                let envelope: Arc<Envelope> = record.decode_arc()?;
+                if let Command::ParkCommand(park_command) = &envelope.command
+                    && park_command.partition_id == partition_id
+                    && park_command.lsn == failed_command.lsn
+                {
+                    return Ok(Some(park_command.clone()));
+                }
+            }
+        }
+
+        let attempts_exhausted = Configuration::pinned()
+            .worker
+            .poison_command_max_attempts
+            .is_some_and(|max_attempts| failed_command.attempts >= max_attempts.get());
+        if !attempts_exhausted
+            || !matches!(
+                *self.target_leader_state_rx.borrow(),
+                TargetLeaderState::Leader(_)
+            )
+        {
+            return Ok(None);
+        }
+
+        let park_command = ParkCommand {
+            partition_id,
+            lsn: failed_command.lsn,
+            parked_at: MillisSinceEpoch::now(),
+            attempts: failed_command.attempts,
+            failure: failed_command.failure,
+        };
+        let envelope = Envelope::new(
+            Header {
+                dest: Destination::Processor {
+                    partition_key: *self.partition_store.partition_key_range().start(),
+                    dedup: None,
+                },
+                source: Source::ControlPlane {},
+            },
+            Command::ParkCommand(park_command.clone()),
+        );
+        let lsn = self
+            .bifrost
+            .append(log_id, ErrorRecoveryStrategy::default(), Arc::new(envelope))
+            .await?;
+        info!(
+            %lsn,
+            "Proposed to park the command at lsn {} after {} failed attempts",
+            park_command.lsn,
+            park_command.attempts
+        );
+        Ok(Some(park_command))
+    }
+
+    /// Parks the command instead of applying it, as decided by the leader, so that the partition
+    /// can make progress.
+    fn park(
+        &self,
+        envelope: &Envelope,
+        park_command: ParkCommand,
+        transaction: &mut PartitionStoreTransaction<'_>,
+    ) -> Result<(), ProcessorError> {
+        let command_name = envelope.command.name();
+        error!(
+            lsn = %park_command.lsn,
+            attempts = park_command.attempts,
+            "Parking command '{command_name}' which repeatedly failed to apply: {}. \
+            The partition skips it until it's retried through the admin API.",
+            park_command.failure
+        );
+        counter!(PARTITION_PARKED_COMMANDS, PARTITION_LABEL => self.partition_id_str.clone())
+            .increment(1);
+
+        let envelope = StorageCodec::encode_and_split(envelope, &mut BytesMut::new())
+            .map_err(|err| ProcessorError::Other(err.into()))?
+            .freeze();
+        transaction.put_parked_command(&ParkedCommand {
+            lsn: park_command.lsn,
+            parked_at: park_command.parked_at,
+            attempts: park_command.attempts,
+            failure: park_command.failure,
+            command_name: command_name.to_owned(),
+            envelope,
+        })?;
+        Ok(())
+    }
+
     fn is_targeted_to_me<'a>(&self, header: &'a Header) -> Option<&'a Option<DedupInformation>> {
         match &header.dest {
             Destination::Processor {
@@ -0,0 +1,159 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use tokio::time::Instant;
+
+use restate_core::network::{Oneshot, Reciprocal};
+use restate_types::logs::Lsn;
+use restate_types::net::partition_processor::{
+    PartitionProcessorRpcError, PartitionProcessorRpcRequest, PartitionProcessorRpcResponse,
+};
+
+pub(super) type RpcResponseTx =
+    Reciprocal<Oneshot<Result<PartitionProcessorRpcResponse, PartitionProcessorRpcError>>>;
+
+/// Rpcs waiting for the partition processor to catch up with their consistency token.
+#[derive(Default)]
+pub(super) struct PendingRpcs(Vec<PendingRpc>);
+
+struct PendingRpc {
+    min_applied_lsn: Lsn,
+    deadline: Instant,
+    response_tx: RpcResponseTx,
+    request: PartitionProcessorRpcRequest,
+}
+
+impl PendingRpcs {
+    pub fn push(
+        &mut self,
+        min_applied_lsn: Lsn,
+        deadline: Instant,
+        response_tx: RpcResponseTx,
+        request: PartitionProcessorRpcRequest,
+    ) {
+        self.0.push(PendingRpc {
+            min_applied_lsn,
+            deadline,
+            response_tx,
+            request,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Removes the rpcs whose consistency token has been caught up with by the applied lsn, and
+    /// fails the ones which waited past their deadline.
+    pub fn take_caught_up(
+        &mut self,
+        applied_lsn: Lsn,
+        now: Instant,
+    ) -> Vec<(RpcResponseTx, PartitionProcessorRpcRequest)> {
+        let mut caught_up = Vec::new();
+        for pending_rpc in std::mem::take(&mut self.0) {
+            if pending_rpc.min_applied_lsn <= applied_lsn {
+                caught_up.push((pending_rpc.response_tx, pending_rpc.request));
+            } else if pending_rpc.deadline <= now {
+                pending_rpc
+                    .response_tx
+                    .send(Err(PartitionProcessorRpcError::Internal(format!(
+                        "timed out waiting for the partition processor to apply lsn {}",
+                        pending_rpc.min_applied_lsn
+                    ))));
+            } else {
+                self.0.push(pending_rpc);
+            }
+        }
+        caught_up
+    }
+
+    /// Fails all the rpcs, e.g. because the partition processor is stopping.
+    pub fn fail_all(&mut self, error: impl Fn() -> PartitionProcessorRpcError) {
+        for pending_rpc in self.0.drain(..) {
+            pending_rpc.response_tx.send(Err(error()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use restate_types::identifiers::{InvocationId, PartitionId};
+    use restate_types::net::partition_processor::PartitionProcessorRpcRequestInner;
+
+    fn request(min_applied_lsn: Lsn) -> PartitionProcessorRpcRequest {
+        PartitionProcessorRpcRequest {
+            request_id: Default::default(),
+            partition_id: PartitionId::MIN,
+            inner: PartitionProcessorRpcRequestInner::PurgeInvocation {
+                invocation_id: InvocationId::mock_random(),
+            },
+            min_applied_lsn: Some(min_applied_lsn),
+        }
+    }
+
+    #[test]
+    fn serves_rpcs_once_caught_up() {
+        let now = Instant::now();
+        let mut pending_rpcs = PendingRpcs::default();
+        let (tx, rx) = Reciprocal::mock();
+        pending_rpcs.push(
+            Lsn::new(10),
+            now + Duration::from_secs(30),
+            tx,
+            request(Lsn::new(10)),
+        );
+
+        assert!(pending_rpcs.take_caught_up(Lsn::new(9), now).is_empty());
+        assert!(!pending_rpcs.is_empty());
+
+        let caught_up = pending_rpcs.take_caught_up(Lsn::new(10), now);
+        assert_eq!(caught_up.len(), 1);
+        assert_eq!(caught_up[0].1.min_applied_lsn, Some(Lsn::new(10)));
+        assert!(pending_rpcs.is_empty());
+        rx.assert_not_received();
+    }
+
+    #[restate_core::test]
+    async fn fails_rpcs_past_their_deadline() {
+        let now = Instant::now();
+        let mut pending_rpcs = PendingRpcs::default();
+        let (expired_tx, expired_rx) = Reciprocal::mock();
+        pending_rpcs.push(Lsn::new(10), now, expired_tx, request(Lsn::new(10)));
+        let (tx, _rx) = Reciprocal::mock();
+        pending_rpcs.push(
+            Lsn::new(10),
+            now + Duration::from_secs(30),
+            tx,
+            request(Lsn::new(10)),
+        );
+
+        assert!(
+            pending_rpcs
+                .take_caught_up(Lsn::new(9), now + Duration::from_secs(1))
+                .is_empty()
+        );
+        assert!(matches!(
+            expired_rx.recv().await,
+            Err(PartitionProcessorRpcError::Internal(_))
+        ));
+        // The rpc whose deadline isn't over is still pending
+        assert_eq!(
+            pending_rpcs
+                .take_caught_up(Lsn::new(10), now + Duration::from_secs(1))
+                .len(),
+            1
+        );
+    }
+}
@@ -224,6 +224,8 @@ where
             request_id,
             partition_id: _,
             inner,
+            // Consistency is enforced by the partition processor before dispatching the rpc
+            min_applied_lsn: _,
         }: PartitionProcessorRpcRequest,
         replier: Replier<Self::Output>,
     ) -> Result<(), Self::Error> {
@@ -25,6 +25,8 @@ use restate_types::time::MillisSinceEpoch;
 use restate_wal_protocol::timer::TimerKeyValue;
 use std::time::Duration;
 
+use crate::lifecycle_webhook::InvocationLifecycleEvent;
+
 pub type ActionCollector = Vec<Action>;
 
 #[derive(Debug, Eq, PartialEq, strum::IntoStaticStr)]
@@ -104,6 +106,10 @@ pub enum Action {
         request_id: PartitionProcessorRpcRequestId,
         response: RestartAsNewInvocationResponse,
     },
+    /// Only emitted when the invocation lifecycle webhook is configured.
+    NotifyInvocationLifecycleEvent {
+        event: InvocationLifecycleEvent,
+    },
 }
 
 impl Action {
@@ -79,9 +79,9 @@ mod tests {
 
         // Fill with some state the service K/V store
         let mut txn = test_env.storage.transaction();
-        txn.put_user_state(&service_id, b"my-key-1", b"my-val-1")
+        txn.put_user_state(&service_id, b"my-key-1", b"my-val-1", None)
             .unwrap();
-        txn.put_user_state(&service_id, b"my-key-2", b"my-val-2")
+        txn.put_user_state(&service_id, b"my-key-2", b"my-val-2", None)
             .unwrap();
         txn.commit().await.unwrap();
 
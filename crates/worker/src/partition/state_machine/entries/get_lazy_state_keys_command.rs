@@ -82,8 +82,10 @@ mod tests {
 
         // Mock some state
         let mut txn = test_env.storage.transaction();
-        txn.put_user_state(&service_id, b"key1", b"value1").unwrap();
-        txn.put_user_state(&service_id, b"key2", b"value2").unwrap();
+        txn.put_user_state(&service_id, b"key1", b"value1", None)
+            .unwrap();
+        txn.put_user_state(&service_id, b"key2", b"value2", None)
+            .unwrap();
         txn.commit().await.unwrap();
 
         let completion_id = 1;
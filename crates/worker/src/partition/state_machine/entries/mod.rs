@@ -67,7 +67,9 @@ use crate::partition::state_machine::entries::send_signal_command::ApplySendSign
 use crate::partition::state_machine::entries::set_state_command::ApplySetStateCommand;
 use crate::partition::state_machine::entries::sleep_command::ApplySleepCommand;
 use crate::partition::state_machine::lifecycle::VerifyOrMigrateJournalTableToV2Command;
-use crate::partition::state_machine::{CommandHandler, Error, StateMachineApplyContext};
+use crate::partition::state_machine::{
+    CommandHandler, Error, StateMachineApplyContext, encrypt_journal_entry,
+};
 
 pub(super) struct OnJournalEntryCommand {
     pub(super) invocation_id: InvocationId,
@@ -361,16 +363,24 @@ where
                 journal_meta.commands += 1;
             }
 
+            // Make sure that a deterministic append time is set based on Bifrost's record creation
+            // time. This ensures that the append time does not depend on the application time of
+            // the record and ensures that subsequent journal entries have monotonically increasing
+            // append times.
+            let stored_entry = encrypt_journal_entry(
+                self.invocation_status
+                    .invocation_target()
+                    .expect("In-Flight invocation target must be present")
+                    .service_name(),
+                StoredRawEntry::new(StoredRawEntryHeader::new(ctx.record_created_at), entry),
+            )?;
+
             // Store journal entry
             WriteJournalTable::put_journal_entry(
                 ctx.storage,
                 self.invocation_id,
                 entry_index,
-                // Make sure that a deterministic append time is set based on Bifrost's record creation
-                // time. This ensures that the append time does not depend on the application time of
-                // the record and ensures that subsequent journal entries have monotonically increasing
-                // append times.
-                &StoredRawEntry::new(StoredRawEntryHeader::new(ctx.record_created_at), entry),
+                &stored_entry,
                 &related_completion_ids,
             )?;
         }
@@ -10,7 +10,9 @@
 
 use crate::debug_if_leader;
 use crate::partition::state_machine::entries::ApplyJournalCommandEffect;
-use crate::partition::state_machine::{CommandHandler, Error, StateMachineApplyContext};
+use crate::partition::state_machine::{
+    CommandHandler, Error, StateMachineApplyContext, encrypt_state_value,
+};
 use restate_storage_api::state_table::WriteStateTable;
 use restate_tracing_instrumentation as instrumentation;
 use restate_types::journal_v2::{EntryMetadata, SetStateCommand};
@@ -51,8 +53,10 @@ where
                 "Set state"
             );
 
+            let value = encrypt_state_value(&service_id.service_name, self.entry.value)?;
+            let expires_at = ctx.state_expiration(&service_id.service_name);
             ctx.storage
-                .put_user_state(&service_id, self.entry.key, self.entry.value)
+                .put_user_state(&service_id, self.entry.key, value, expires_at)
                 .map_err(Error::Storage)?;
         } else {
             warn!(
@@ -64,3 +68,145 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::partition::state_machine::Action;
+    use crate::partition::state_machine::StateMachine;
+    use crate::partition::state_machine::tests::fixtures::invoker_entry_effect;
+    use crate::partition::state_machine::tests::{TestEnv, fixtures};
+    use bytes::Bytes;
+    use googletest::prelude::*;
+    use restate_storage_api::Transaction;
+    use restate_storage_api::fsm_table::ReadFsmTable;
+    use restate_storage_api::state_table::{ReadStateTable, WriteStateTable};
+    use restate_types::SemanticRestateVersion;
+    use restate_types::identifiers::{PartitionKey, ServiceId};
+    use restate_types::journal_v2::SetStateCommand;
+    use restate_types::logs::Keys;
+    use restate_types::partitions::settings::PartitionSettings;
+    use restate_wal_protocol::Command;
+    use restate_wal_protocol::control::UpdatePartitionSettings;
+    use std::num::NonZeroUsize;
+
+    #[restate_core::test]
+    async fn set_state_exceeding_quota_kills_invocation() {
+        let mut test_env = TestEnv::create_with_state_machine(
+            StateMachine::new(
+                0,    /* inbox_seq_number */
+                0,    /* outbox_seq_number */
+                None, /* outbox_head_seq_number */
+                PartitionKey::MIN..=PartitionKey::MAX,
+                SemanticRestateVersion::unknown().clone(),
+                Default::default(),
+                None,
+            )
+            .with_state_size_quota(NonZeroUsize::new(32)),
+        )
+        .await;
+        let service_id = ServiceId::new("MySvc", "my-key");
+
+        let mut txn = test_env.storage.transaction();
+        txn.put_user_state(&service_id, b"my-key-1", b"my-val-1", None)
+            .unwrap();
+        txn.commit().await.unwrap();
+
+        let invocation_id =
+            fixtures::mock_start_invocation_with_service_id(&mut test_env, service_id.clone())
+                .await;
+        fixtures::mock_pinned_deployment_v5(&mut test_env, invocation_id).await;
+
+        // Overwriting the entry within the quota is fine
+        let actions = test_env
+            .apply(invoker_entry_effect(
+                invocation_id,
+                SetStateCommand {
+                    key: "my-key-1".into(),
+                    value: Bytes::from_static(b"my-longer-val-1"),
+                    name: Default::default(),
+                },
+            ))
+            .await;
+        assert_that!(actions, not(contains(pat!(Action::AbortInvocation { .. }))));
+
+        let actions = test_env
+            .apply(invoker_entry_effect(
+                invocation_id,
+                SetStateCommand {
+                    key: "my-key-2".into(),
+                    value: Bytes::from_static(b"my-val-2"),
+                    name: Default::default(),
+                },
+            ))
+            .await;
+        assert_that!(
+            actions,
+            contains(pat!(Action::AbortInvocation {
+                invocation_id: eq(invocation_id),
+            }))
+        );
+        assert_that!(
+            test_env
+                .storage
+                .get_user_state(&service_id, b"my-key-2")
+                .await
+                .unwrap(),
+            none()
+        );
+        assert_eq!(
+            test_env
+                .storage
+                .get_user_state_size(&service_id)
+                .await
+                .unwrap(),
+            23
+        );
+
+        test_env.shutdown().await;
+    }
+
+    #[restate_core::test]
+    async fn state_size_quota_is_replicated() {
+        let mut test_env = TestEnv::create().await;
+        let service_id = ServiceId::new("MySvc", "my-key");
+        let settings = PartitionSettings {
+            state_size_quota: NonZeroUsize::new(16),
+        };
+
+        let _ = test_env
+            .apply(Command::UpdatePartitionSettings(UpdatePartitionSettings {
+                partition_key_range: Keys::RangeInclusive(PartitionKey::MIN..=PartitionKey::MAX),
+                settings: settings.clone(),
+            }))
+            .await;
+        assert_eq!(test_env.state_machine.settings, settings);
+        assert_eq!(
+            test_env.storage.get_partition_settings().await.unwrap(),
+            settings
+        );
+
+        let invocation_id =
+            fixtures::mock_start_invocation_with_service_id(&mut test_env, service_id.clone())
+                .await;
+        fixtures::mock_pinned_deployment_v5(&mut test_env, invocation_id).await;
+
+        let actions = test_env
+            .apply(invoker_entry_effect(
+                invocation_id,
+                SetStateCommand {
+                    key: "my-key-1".into(),
+                    value: Bytes::from_static(b"my-longer-val-1"),
+                    name: Default::default(),
+                },
+            ))
+            .await;
+        assert_that!(
+            actions,
+            contains(pat!(Action::AbortInvocation {
+                invocation_id: eq(invocation_id),
+            }))
+        );
+
+        test_env.shutdown().await;
+    }
+}
@@ -22,6 +22,7 @@ use restate_storage_api::outbox_table::WriteOutboxTable;
 use restate_storage_api::promise_table::{ReadPromiseTable, WritePromiseTable};
 use restate_storage_api::state_table::{ReadStateTable, WriteStateTable};
 use restate_storage_api::timer_table::WriteTimerTable;
+use restate_types::errors::CANCELED_INVOCATION_ERROR;
 use restate_types::identifiers::InvocationId;
 use restate_types::invocation::client::CancelInvocationResponse;
 use restate_types::invocation::{
@@ -67,7 +68,7 @@ where
             }
             InvocationStatus::Inboxed(inboxed) => {
                 ctx.terminate_inboxed_invocation(
-                    TerminationFlavor::Cancel,
+                    CANCELED_INVOCATION_ERROR,
                     self.invocation_id,
                     inboxed,
                 )
@@ -76,7 +77,7 @@ where
             }
             InvocationStatus::Scheduled(scheduled) => {
                 ctx.terminate_scheduled_invocation(
-                    TerminationFlavor::Cancel,
+                    CANCELED_INVOCATION_ERROR,
                     self.invocation_id,
                     scheduled,
                 )
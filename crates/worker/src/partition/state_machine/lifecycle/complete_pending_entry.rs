@@ -0,0 +1,321 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::debug_if_leader;
+use crate::partition::state_machine::lifecycle::OnNotifyInvocationResponse;
+use crate::partition::state_machine::{
+    CommandHandler, Error, StateMachineApplyContext, should_use_journal_table_v2,
+};
+use assert2::let_assert;
+use restate_service_protocol::codec::ProtobufRawEntryCodec;
+use restate_service_protocol_v4::entry_codec::ServiceProtocolV4Codec;
+use restate_storage_api::fsm_table::WriteFsmTable;
+use restate_storage_api::invocation_status_table::{
+    ReadInvocationStatusTable, WriteInvocationStatusTable,
+};
+use restate_storage_api::journal_table as journal_table_v1;
+use restate_storage_api::journal_table_v2;
+use restate_storage_api::outbox_table::WriteOutboxTable;
+use restate_storage_api::promise_table::{ReadPromiseTable, WritePromiseTable};
+use restate_storage_api::state_table::{ReadStateTable, WriteStateTable};
+use restate_storage_api::timer_table::{Timer, WriteTimerTable};
+use restate_types::invocation::CompletePendingEntryRequest;
+use restate_types::journal::enriched::EnrichedEntryHeader;
+use restate_types::journal::{Completion, Entry, SleepEntry};
+use restate_types::journal_v2;
+use restate_types::journal_v2::{EntryMetadata, EntryType};
+
+pub struct OnCompletePendingEntryCommand {
+    pub request: CompletePendingEntryRequest,
+}
+
+impl<'ctx, 's: 'ctx, S> CommandHandler<&'ctx mut StateMachineApplyContext<'s, S>>
+    for OnCompletePendingEntryCommand
+where
+    S: journal_table_v1::WriteJournalTable
+        + journal_table_v1::ReadJournalTable
+        + journal_table_v2::WriteJournalTable
+        + journal_table_v2::ReadJournalTable
+        + WriteTimerTable
+        + ReadInvocationStatusTable
+        + WriteInvocationStatusTable
+        + WriteFsmTable
+        + ReadPromiseTable
+        + WritePromiseTable
+        + ReadStateTable
+        + WriteStateTable
+        + WriteOutboxTable,
+{
+    async fn apply(self, ctx: &'ctx mut StateMachineApplyContext<'s, S>) -> Result<(), Error> {
+        let CompletePendingEntryRequest {
+            invocation_id,
+            invocation_epoch,
+            entry_index,
+            completion_id,
+            result,
+        } = self.request;
+
+        let status = ctx.get_invocation_status(&invocation_id).await?;
+        let Some(current_invocation_epoch) = status
+            .get_invocation_metadata()
+            .map(|metadata| metadata.current_invocation_epoch)
+        else {
+            debug_if_leader!(
+                ctx.is_leader,
+                "Ignoring manual completion of entry {} because the invocation is not in-flight",
+                entry_index
+            );
+            return Ok(());
+        };
+        if current_invocation_epoch != invocation_epoch {
+            debug_if_leader!(
+                ctx.is_leader,
+                "Ignoring manual completion of entry {} for epoch {} because the invocation is at epoch {}",
+                entry_index,
+                invocation_epoch,
+                current_invocation_epoch
+            );
+            return Ok(());
+        }
+
+        if !should_use_journal_table_v2(&status) {
+            // Journals of service protocol <= 3 are completed by entry index
+            let Some(journal_table_v1::JournalEntry::Entry(entry)) =
+                journal_table_v1::ReadJournalTable::get_journal_entry(
+                    ctx.storage,
+                    &invocation_id,
+                    entry_index,
+                )
+                .await?
+            else {
+                debug_if_leader!(
+                    ctx.is_leader,
+                    "Ignoring manual completion of entry {} because it doesn't exist",
+                    entry_index
+                );
+                return Ok(());
+            };
+            if entry.header().is_completed() != Some(false) || completion_id != entry_index {
+                debug_if_leader!(
+                    ctx.is_leader,
+                    "Ignoring manual completion of entry {} because it is not pending",
+                    entry_index
+                );
+                return Ok(());
+            }
+
+            if let EnrichedEntryHeader::Sleep { .. } = entry.header() {
+                let_assert!(
+                    Entry::Sleep(SleepEntry { wake_up_time, .. }) =
+                        entry.deserialize_entry_ref::<ProtobufRawEntryCodec>()?
+                );
+                let (timer_key, _) = Timer::complete_journal_entry(
+                    wake_up_time,
+                    invocation_id,
+                    entry_index,
+                    // Journal v3 doesn't support invocation epoch
+                    0,
+                );
+                ctx.do_delete_timer(timer_key).await?;
+            }
+
+            return ctx
+                .handle_completion(
+                    invocation_id,
+                    status,
+                    Completion {
+                        entry_index,
+                        result: result.into(),
+                    },
+                )
+                .await;
+        }
+
+        let Some(entry) = journal_table_v2::ReadJournalTable::get_journal_entry(
+            ctx.storage,
+            invocation_id,
+            entry_index,
+        )
+        .await?
+        .filter(|entry| matches!(entry.ty(), EntryType::Command(_))) else {
+            debug_if_leader!(
+                ctx.is_leader,
+                "Ignoring manual completion of entry {} because it is not a command",
+                entry_index
+            );
+            return Ok(());
+        };
+        // These are the commands that can be completed with an InvocationResponse
+        let (expected_completion_id, sleep_wake_up_time) = match entry
+            .decode::<ServiceProtocolV4Codec, journal_v2::Command>()?
+        {
+            journal_v2::Command::Call(cmd) => (cmd.result_completion_id, None),
+            journal_v2::Command::Sleep(cmd) => (cmd.completion_id, Some(cmd.wake_up_time)),
+            journal_v2::Command::GetPromise(cmd) => (cmd.completion_id, None),
+            journal_v2::Command::AttachInvocation(cmd) => (cmd.completion_id, None),
+            journal_v2::Command::GetInvocationOutput(cmd) => (cmd.completion_id, None),
+            _ => {
+                debug_if_leader!(
+                    ctx.is_leader,
+                    "Ignoring manual completion of entry {} because it cannot be completed manually",
+                    entry_index
+                );
+                return Ok(());
+            }
+        };
+        if expected_completion_id != completion_id
+            || journal_table_v2::ReadJournalTable::has_completion(
+                ctx.storage,
+                invocation_id,
+                completion_id,
+            )
+            .await?
+        {
+            debug_if_leader!(
+                ctx.is_leader,
+                "Ignoring manual completion of entry {} because completion id {} is not pending",
+                entry_index,
+                completion_id
+            );
+            return Ok(());
+        }
+
+        if let Some(wake_up_time) = sleep_wake_up_time {
+            let (timer_key, _) = Timer::complete_journal_entry(
+                wake_up_time.as_u64(),
+                invocation_id,
+                completion_id,
+                current_invocation_epoch,
+            );
+            ctx.do_delete_timer(timer_key).await?;
+        }
+
+        OnNotifyInvocationResponse {
+            invocation_id,
+            invocation_epoch: current_invocation_epoch,
+            status,
+            caller_completion_id: completion_id,
+            result,
+        }
+        .apply(ctx)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::partition::state_machine::Action;
+    use crate::partition::state_machine::tests::fixtures::invoker_entry_effect;
+    use crate::partition::state_machine::tests::{TestEnv, fixtures, matchers};
+    use bytes::Bytes;
+    use googletest::prelude::{all, assert_that, contains, eq, not, ok, pat};
+    use restate_storage_api::invocation_status_table::ReadInvocationStatusTable;
+    use restate_types::identifiers::InvocationId;
+    use restate_types::invocation::{InvocationEpoch, ResponseResult};
+    use restate_types::journal_v2::raw::TryFromEntry;
+    use restate_types::journal_v2::{SleepCommand, SleepCompletion};
+    use restate_types::time::MillisSinceEpoch;
+    use restate_wal_protocol::Command;
+
+    fn complete_pending_entry(
+        invocation_id: InvocationId,
+        invocation_epoch: InvocationEpoch,
+    ) -> Command {
+        Command::CompletePendingEntry(CompletePendingEntryRequest {
+            invocation_id,
+            invocation_epoch,
+            entry_index: 1,
+            completion_id: 1,
+            result: ResponseResult::Success(Bytes::new()),
+        })
+    }
+
+    #[restate_core::test]
+    async fn complete_pending_sleep() {
+        let mut test_env = TestEnv::create().await;
+        let invocation_id = fixtures::mock_start_invocation(&mut test_env).await;
+        fixtures::mock_pinned_deployment_v5(&mut test_env, invocation_id).await;
+
+        let _ = test_env
+            .apply(invoker_entry_effect(
+                invocation_id,
+                SleepCommand {
+                    wake_up_time: MillisSinceEpoch::new(1337),
+                    completion_id: 1,
+                    name: Default::default(),
+                },
+            ))
+            .await;
+
+        // A completion for another epoch is ignored
+        let actions = test_env
+            .apply(complete_pending_entry(invocation_id, 1))
+            .await;
+        assert_that!(
+            actions,
+            all!(
+                not(contains(matchers::actions::delete_sleep_timer(1))),
+                not(contains(pat!(Action::ForwardNotification {
+                    invocation_id: eq(invocation_id)
+                })))
+            )
+        );
+
+        // The completion deletes the sleep timer and is forwarded to the invocation
+        let actions = test_env
+            .apply(complete_pending_entry(invocation_id, 0))
+            .await;
+        assert_that!(
+            actions,
+            all!(
+                contains(matchers::actions::delete_sleep_timer(1)),
+                contains(pat!(Action::ForwardNotification {
+                    invocation_id: eq(invocation_id),
+                    invocation_epoch: eq(0),
+                }))
+            )
+        );
+        assert_that!(
+            test_env.storage.get_invocation_status(&invocation_id).await,
+            // This has Input, SleepCommand and SleepCompletion
+            ok(matchers::storage::has_journal_length(3))
+        );
+        assert_that!(
+            TryFromEntry::try_from(
+                test_env
+                    .read_journal_to_vec(invocation_id, 3)
+                    .await
+                    .remove(2)
+            ),
+            ok(pat!(SleepCompletion {
+                completion_id: eq(1)
+            }))
+        );
+
+        // Completing it again is ignored
+        let actions = test_env
+            .apply(complete_pending_entry(invocation_id, 0))
+            .await;
+        assert_that!(
+            actions,
+            not(contains(pat!(Action::ForwardNotification {
+                invocation_id: eq(invocation_id)
+            })))
+        );
+        assert_that!(
+            test_env.storage.get_invocation_status(&invocation_id).await,
+            ok(matchers::storage::has_journal_length(3))
+        );
+
+        test_env.shutdown().await;
+    }
+}
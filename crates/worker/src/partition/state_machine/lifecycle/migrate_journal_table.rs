@@ -8,7 +8,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::partition::state_machine::{CommandHandler, Error, StateMachineApplyContext};
+use crate::partition::state_machine::{
+    CommandHandler, Error, StateMachineApplyContext, encrypt_journal_entry,
+};
 use assert2::let_assert;
 use restate_service_protocol::codec::ProtobufRawEntryCodec;
 use restate_service_protocol_v4::entry_codec::ServiceProtocolV4Codec;
@@ -65,10 +67,13 @@ where
                     ctx.storage,
                     self.invocation_id,
                     0,
-                    &StoredRawEntry::new(
-                        StoredRawEntryHeader::new(ctx.record_created_at),
-                        new_raw_entry,
-                    ),
+                    &encrypt_journal_entry(
+                        self.metadata.invocation_target.service_name(),
+                        StoredRawEntry::new(
+                            StoredRawEntryHeader::new(ctx.record_created_at),
+                            new_raw_entry,
+                        ),
+                    )?,
                     &[],
                 )?;
                 journal_table_v1::WriteJournalTable::delete_journal(
@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 mod cancel;
+mod complete_pending_entry;
 mod event;
 mod manual_resume;
 mod migrate_journal_table;
@@ -26,6 +27,7 @@ mod suspend;
 mod version_barrier;
 
 pub(super) use cancel::OnCancelCommand;
+pub(super) use complete_pending_entry::OnCompletePendingEntryCommand;
 pub(super) use event::OnInvokerEventCommand;
 pub(super) use manual_resume::OnManualResumeCommand;
 pub(super) use migrate_journal_table::VerifyOrMigrateJournalTableToV2Command;
@@ -9,7 +9,9 @@
 // by the Apache License, Version 2.0.
 
 use crate::debug_if_leader;
-use crate::partition::state_machine::{Action, CommandHandler, Error, StateMachineApplyContext};
+use crate::partition::state_machine::{
+    Action, CommandHandler, Error, StateMachineApplyContext, encrypt_journal_entry,
+};
 use ahash::HashSet;
 use opentelemetry::trace::Span;
 use restate_service_protocol_v4::entry_codec::ServiceProtocolV4Codec;
@@ -124,6 +126,8 @@ where
         // - Pass it over to the on_pre_flight_invocation function, which deals with the rest
 
         // --- Let's start copying the journal
+        // The entries are read decrypted, hence they must be encrypted again
+        let service_name = completed_invocation.invocation_target.service_name();
         let mut new_journal_index = 0;
         let mut new_journal_commands = 0;
         let mut missing_completions = HashSet::default();
@@ -150,7 +154,7 @@ where
                         ctx.storage,
                         new_invocation_id,
                         new_journal_index,
-                        &entry,
+                        &encrypt_journal_entry(service_name, entry)?,
                         &related_completion_ids,
                     )?;
                 }
@@ -170,7 +174,7 @@ where
                         ctx.storage,
                         new_invocation_id,
                         new_journal_index,
-                        &entry,
+                        &encrypt_journal_entry(service_name, entry)?,
                         &[],
                     )?;
                 }
@@ -196,7 +200,7 @@ where
                     ctx.storage,
                     new_invocation_id,
                     new_journal_index,
-                    &entry,
+                    &encrypt_journal_entry(service_name, entry)?,
                     &[],
                 )?;
 
@@ -261,6 +265,7 @@ where
             idempotency_key: None,
             execution_time: None,
             response_sinks: Default::default(),
+            deadline: None,
         };
 
         // --- Invocation metadata ready, now go through the usual flow
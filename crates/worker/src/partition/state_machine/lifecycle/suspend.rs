@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::lifecycle_webhook::InvocationLifecycleEventKind;
 use crate::partition::state_machine::{CommandHandler, Error, StateMachineApplyContext};
 use restate_storage_api::invocation_status_table::{InvocationStatus, WriteInvocationStatusTable};
 use restate_storage_api::journal_table_v2::ReadJournalTable;
@@ -76,6 +77,12 @@ where
             in_flight_invocation_metadata
                 .timestamps
                 .update(ctx.record_created_at);
+            ctx.notify_lifecycle_event(
+                InvocationLifecycleEventKind::Suspended,
+                self.invocation_id,
+                &in_flight_invocation_metadata.invocation_target,
+                None,
+            );
             invocation_status = InvocationStatus::Suspended {
                 metadata: in_flight_invocation_metadata,
                 waiting_for_notifications: self.waiting_for_notifications,
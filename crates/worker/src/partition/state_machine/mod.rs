@@ -19,6 +19,7 @@ use std::borrow::Cow;
 use std::collections::HashSet;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::ops::RangeInclusive;
 use std::str::FromStr;
 use std::time::Instant;
@@ -35,13 +36,16 @@ use restate_invoker_api::InvokeInputJournal;
 use restate_service_protocol::codec::ProtobufRawEntryCodec;
 use restate_service_protocol_v4::entry_codec::ServiceProtocolV4Codec;
 use restate_storage_api::Result as StorageResult;
+use restate_storage_api::completion_event_table::{
+    CompletionEvent, CompletionEventSequence, WriteCompletionEventTable,
+};
 use restate_storage_api::fsm_table::WriteFsmTable;
 use restate_storage_api::idempotency_table::{IdempotencyTable, ReadOnlyIdempotencyTable};
 use restate_storage_api::inbox_table::{InboxEntry, WriteInboxTable};
 use restate_storage_api::invocation_status_table::{
     CompletedInvocation, InFlightInvocationMetadata, InboxedInvocation, JournalRetentionPolicy,
     PreFlightInvocationArgument, PreFlightInvocationJournal, PreFlightInvocationMetadata,
-    ReadInvocationStatusTable, WriteInvocationStatusTable,
+    ReadInvocationStatusTable, StatusTimestamps, WriteInvocationStatusTable,
 };
 use restate_storage_api::invocation_status_table::{InvocationStatus, ScheduledInvocation};
 use restate_storage_api::journal_events::WriteJournalEventsTable;
@@ -60,9 +64,11 @@ use restate_storage_api::timer_table::TimerKey;
 use restate_storage_api::timer_table::{Timer, WriteTimerTable};
 use restate_tracing_instrumentation as instrumentation;
 use restate_types::errors::{
-    ALREADY_COMPLETED_INVOCATION_ERROR, CANCELED_INVOCATION_ERROR, GenericError,
-    InvocationErrorCode, KILLED_INVOCATION_ERROR, NOT_FOUND_INVOCATION_ERROR,
-    NOT_READY_INVOCATION_ERROR, WORKFLOW_ALREADY_INVOKED_INVOCATION_ERROR,
+    ALREADY_COMPLETED_INVOCATION_ERROR, CANCELED_INVOCATION_ERROR,
+    DEADLINE_EXCEEDED_INVOCATION_ERROR, GenericError, InvocationError, InvocationErrorCode,
+    JOURNAL_LENGTH_EXCEEDED_INVOCATION_ERROR, KILLED_INVOCATION_ERROR, NOT_FOUND_INVOCATION_ERROR,
+    NOT_READY_INVOCATION_ERROR, STATE_SIZE_QUOTA_EXCEEDED_INVOCATION_ERROR,
+    WORKFLOW_ALREADY_INVOKED_INVOCATION_ERROR,
 };
 use restate_types::identifiers::{
     AwakeableIdentifier, EntryIndex, ExternalSignalIdentifier, InvocationId, PartitionKey,
@@ -98,10 +104,13 @@ use restate_types::journal_v2::{
 };
 use restate_types::logs::Lsn;
 use restate_types::message::MessageIndex;
+use restate_types::partitions::settings::PartitionSettings;
+use restate_types::payload_encryption::{EncryptionError, frame_plaintext, payload_encryption};
 use restate_types::schema::Schema;
 use restate_types::service_protocol::ServiceProtocolVersion;
 use restate_types::state_mut::ExternalStateMutation;
 use restate_types::state_mut::StateMutationVersion;
+use restate_types::storage::StoredRawEntry;
 use restate_types::time::MillisSinceEpoch;
 use restate_types::{RestateVersion, SemanticRestateVersion};
 use restate_types::{Versioned, journal::*};
@@ -110,7 +119,10 @@ use restate_wal_protocol::timer::TimerKeyDisplay;
 use restate_wal_protocol::timer::TimerKeyValue;
 
 use self::utils::SpanExt;
-use crate::metric_definitions::{PARTITION_APPLY_COMMAND, USAGE_LEADER_JOURNAL_ENTRY_COUNT};
+use crate::lifecycle_webhook::{InvocationLifecycleEvent, InvocationLifecycleEventKind};
+use crate::metric_definitions::{
+    PARTITION_APPLY_COMMAND, PARTITION_CLEANED_INVOCATIONS, USAGE_LEADER_JOURNAL_ENTRY_COUNT,
+};
 use crate::partition::state_machine::lifecycle::OnCancelCommand;
 use crate::partition::types::{InvokerEffect, InvokerEffectKind, OutboxMessageExt};
 
@@ -133,6 +145,16 @@ pub struct StateMachine {
 
     /// Enabled experimental features.
     pub(crate) experimental_features: EnumSet<ExperimentalFeature>,
+
+    /// Replicated settings of the partition, see [`StateMachine::with_settings`].
+    pub(crate) settings: PartitionSettings,
+
+    /// Latest creation time of the applied records, see [`StateMachine::with_state_clock`].
+    pub(crate) state_clock: MillisSinceEpoch,
+
+    /// Whether to emit the invocation lifecycle events, see
+    /// [`StateMachine::with_lifecycle_events`].
+    pub(crate) emit_lifecycle_events: bool,
 }
 
 impl Debug for StateMachine {
@@ -176,6 +198,8 @@ pub enum Error {
         "error when trying to apply invocation response with completion id {0}, because no command was found for given completion id"
     )]
     MissingCommandForInvocationResponse(CompletionId),
+    #[error(transparent)]
+    PayloadEncryption(#[from] EncryptionError),
 }
 
 #[macro_export]
@@ -210,6 +234,30 @@ macro_rules! info_span_if_leader {
     }};
 }
 
+/// Encrypts the payload of the journal entry, if the payload encryption is enabled for the
+/// service, or frames it otherwise. The partition store decrypts it when reading the entry.
+pub(crate) fn encrypt_journal_entry(
+    service_name: &str,
+    mut entry: StoredRawEntry,
+) -> Result<StoredRawEntry, EncryptionError> {
+    let content = entry.inner.serialized_content_mut();
+    *content = encrypt_payload(service_name, content.clone())?;
+    Ok(entry)
+}
+
+/// Encrypts the state value, if the payload encryption is enabled for the service, or frames it
+/// otherwise.
+fn encrypt_state_value(service_name: &str, value: Bytes) -> Result<Bytes, EncryptionError> {
+    encrypt_payload(service_name, value)
+}
+
+fn encrypt_payload(service_name: &str, payload: Bytes) -> Result<Bytes, EncryptionError> {
+    match payload_encryption() {
+        Some(encryption) if encryption.is_enabled_for(service_name) => encryption.encrypt(&payload),
+        _ => Ok(frame_plaintext(payload)),
+    }
+}
+
 impl StateMachine {
     pub fn new(
         inbox_seq_number: MessageIndex,
@@ -228,8 +276,59 @@ impl StateMachine {
             min_restate_version,
             experimental_features,
             schema,
+            settings: PartitionSettings::default(),
+            state_clock: MillisSinceEpoch::UNIX_EPOCH,
+            emit_lifecycle_events: false,
         }
     }
+
+    /// Settings applied so far, as persisted in the partition store. They're updated by the
+    /// [`Command::UpdatePartitionSettings`] command.
+    pub fn with_settings(mut self, settings: PartitionSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// State clock as persisted in the partition store. The state entries set by the services
+    /// with a state ttl expire according to it: unlike the creation time of the records, it
+    /// never goes backwards when the leader changes, so that the replicas agree on the expired
+    /// entries also after the storage dropped them.
+    pub fn with_state_clock(mut self, state_clock: MillisSinceEpoch) -> Self {
+        self.state_clock = state_clock;
+        self
+    }
+
+    /// Invocations setting a state entry which would make the state of their service exceed the
+    /// quota fail with [`STATE_SIZE_QUOTA_EXCEEDED_INVOCATION_ERROR`].
+    pub fn with_state_size_quota(mut self, state_size_quota: Option<NonZeroUsize>) -> Self {
+        self.settings.state_size_quota = state_size_quota;
+        self
+    }
+
+    /// Invocations appending an entry to a journal which already reached the max journal length
+    /// of their service fail with [`JOURNAL_LENGTH_EXCEEDED_INVOCATION_ERROR`]. Services can
+    /// override the default max journal length in the schema.
+    pub fn with_default_max_journal_length(
+        mut self,
+        default_max_journal_length: Option<NonZeroU32>,
+    ) -> Self {
+        self.settings.default_max_journal_length = default_max_journal_length;
+        self
+    }
+
+    /// When enabled, the leader emits [`Action::NotifyInvocationLifecycleEvent`] whenever an
+    /// invocation starts, suspends, completes or fails.
+    pub fn with_lifecycle_events(mut self, emit_lifecycle_events: bool) -> Self {
+        self.emit_lifecycle_events = emit_lifecycle_events;
+        self
+    }
+
+    /// When enabled, every replica stores an event in the completion event table whenever an
+    /// invocation completes or fails, for the leader to deliver it to the completion sink.
+    pub fn with_completion_events(mut self, store_completion_events: bool) -> Self {
+        self.settings.store_completion_events = store_completion_events;
+        self
+    }
 }
 
 pub(crate) struct StateMachineApplyContext<'a, S> {
@@ -245,6 +344,11 @@ pub(crate) struct StateMachineApplyContext<'a, S> {
     partition_key_range: RangeInclusive<PartitionKey>,
     #[allow(dead_code)]
     experimental_features: &'a EnumSet<ExperimentalFeature>,
+    settings: &'a mut PartitionSettings,
+    state_clock: MillisSinceEpoch,
+    emit_lifecycle_events: bool,
+    /// Completion events of the command being applied, stored once it's applied
+    completion_events: Vec<InvocationLifecycleEvent>,
     is_leader: bool,
 }
 
@@ -271,7 +375,14 @@ impl StateMachine {
             let start = Instant::now();
             // Apply the command
             let command_type = command.name();
-            let res = StateMachineApplyContext {
+            // Tracking the state size is needed only to enforce the quota
+            transaction.track_user_state_size(self.settings.state_size_quota.is_some());
+            if record_created_at > self.state_clock {
+                self.state_clock = record_created_at;
+                transaction.put_state_clock(record_created_at)?;
+            }
+            transaction.set_state_clock(self.state_clock);
+            let mut ctx = StateMachineApplyContext {
                 storage: transaction,
                 record_created_at,
                 record_lsn,
@@ -283,10 +394,16 @@ impl StateMachine {
                 schema: &mut self.schema,
                 partition_key_range: self.partition_key_range.clone(),
                 experimental_features: &self.experimental_features,
+                settings: &mut self.settings,
+                state_clock: self.state_clock,
+                emit_lifecycle_events: self.emit_lifecycle_events,
+                completion_events: Vec::new(),
                 is_leader,
-            }
-            .on_apply(command)
-            .await;
+            };
+            let res = match ctx.on_apply(command).await {
+                Ok(()) => ctx.write_completion_events(),
+                Err(err) => Err(err),
+            };
             histogram!(PARTITION_APPLY_COMMAND, "command" => command_type).record(start.elapsed());
             res
         }
@@ -376,6 +493,15 @@ impl<S> StateMachineApplyContext<'_, S> {
                     "Register cleanup invocation status timer"
                 )
             }
+            Timer::InvocationDeadline(invocation_id, _) => {
+                debug_if_leader!(
+                    self.is_leader,
+                    restate.invocation.id = %invocation_id,
+                    restate.timer.wake_up_time = %timer_value.wake_up_time(),
+                    restate.timer.key = %TimerKeyDisplay(timer_value.key()),
+                    "Register invocation deadline timer"
+                )
+            }
         };
 
         self.storage
@@ -443,10 +569,13 @@ impl<S> StateMachineApplyContext<'_, S> {
             + WriteStateTable
             + journal_table_v2::WriteJournalTable
             + journal_table_v2::ReadJournalTable
-            + WriteJournalEventsTable,
+            + WriteJournalEventsTable
+            + WriteCompletionEventTable,
     {
         match command {
-            Command::UpdatePartitionDurability(_) => {
+            Command::UpdatePartitionDurability(_)
+            | Command::ParkCommand(_)
+            | Command::RetryParkedCommand(_) => {
                 // no-op :-)
                 //
                 // This is a partition-level command that doesn't impact the state machine.
@@ -541,7 +670,22 @@ impl<S> StateMachineApplyContext<'_, S> {
                 *self.outbox_head_seq_number = Some(index + 1);
                 Ok(())
             }
+            Command::TruncateCompletionEvents(up_to) => {
+                self.storage.truncate_completion_events(up_to)?;
+                Ok(())
+            }
+            Command::CompletePendingEntry(request) => {
+                lifecycle::OnCompletePendingEntryCommand { request }
+                    .apply(self)
+                    .await
+            }
             Command::Timer(timer) => self.on_timer(timer).await,
+            Command::TimerBatch(timers) => {
+                for timer in timers {
+                    self.on_timer(timer).await?;
+                }
+                Ok(())
+            }
             Command::TerminateInvocation(invocation_termination) => {
                 self.on_terminate_invocation(invocation_termination).await
             }
@@ -630,6 +774,24 @@ impl<S> StateMachineApplyContext<'_, S> {
                     *self.schema = Some(upsert.schema);
                 }
 
+                Ok(())
+            }
+            Command::UpdatePartitionSettings(update) => {
+                debug!("Partition settings updated to {:?}", update.settings);
+                if self.settings.store_completion_events && !update.settings.store_completion_events
+                {
+                    // Discarded explicitly, see `worker.discard-completion-events`
+                    self.storage
+                        .truncate_completion_events(CompletionEventSequence::new(
+                            self.record_lsn,
+                            u32::MAX,
+                        ))?;
+                }
+                self.storage.put_partition_settings(&update.settings)?;
+                self.storage
+                    .track_user_state_size(update.settings.state_size_quota.is_some());
+                *self.settings = update.settings;
+
                 Ok(())
             }
         }
@@ -678,6 +840,14 @@ impl<S> StateMachineApplyContext<'_, S> {
             return Ok(());
         };
 
+        // The deadline is enforced also if the caller went away in the meantime
+        if let Some(deadline) = service_invocation.deadline() {
+            self.register_timer(
+                TimerKeyValue::invocation_deadline(deadline, invocation_id, self.record_created_at),
+                service_invocation.span_context.clone(),
+            )?;
+        }
+
         // Prepare PreFlightInvocationMetadata structure
         let submit_notification_sink = service_invocation.submit_notification_sink.take();
         let pre_flight_invocation_metadata = PreFlightInvocationMetadata::from_service_invocation(
@@ -1073,16 +1243,22 @@ impl<S> StateMachineApplyContext<'_, S> {
         // When pinning the deployment version we figure the concrete protocol version
         // * If <= V3, we keep everything in JournalTable V1
         // * If >= V4, we migrate the JournalTable to V2
-        let input_entry = JournalEntry::Entry(ProtobufRawEntryCodec::serialize_as_input_entry(
+        let input_entry = ProtobufRawEntryCodec::serialize_as_input_entry(
             invocation_input.headers,
             invocation_input.argument,
-        ));
+        );
+        let mut stored_input_entry = input_entry.clone();
+        let content = stored_input_entry.serialized_entry_mut();
+        *content = encrypt_payload(
+            in_flight_invocation_metadata
+                .invocation_target
+                .service_name(),
+            content.clone(),
+        )?;
         self.storage
-            .put_journal_entry(&invocation_id, 0, &input_entry)
+            .put_journal_entry(&invocation_id, 0, &JournalEntry::Entry(stored_input_entry))
             .map_err(Error::Storage)?;
 
-        let_assert!(JournalEntry::Entry(input_entry) = input_entry);
-
         Ok(InvokeInputJournal::CachedJournal(
             restate_invoker_api::JournalMetadata::new(
                 in_flight_invocation_metadata.journal_metadata.length,
@@ -1097,6 +1273,7 @@ impl<S> StateMachineApplyContext<'_, S> {
                 in_flight_invocation_metadata
                     .random_seed
                     .unwrap_or_else(|| invocation_id.to_random_seed()),
+                in_flight_invocation_metadata.deadline,
             ),
             vec![
                 restate_invoker_api::invocation_reader::JournalEntry::JournalV1(
@@ -1117,6 +1294,12 @@ impl<S> StateMachineApplyContext<'_, S> {
     {
         debug_if_leader!(self.is_leader, "Invoke");
 
+        self.notify_lifecycle_event(
+            InvocationLifecycleEventKind::Started,
+            invocation_id,
+            &in_flight_invocation_metadata.invocation_target,
+            None,
+        );
         self.action_collector.push(Action::Invoke {
             invocation_id,
             invocation_epoch: in_flight_invocation_metadata.current_invocation_epoch,
@@ -1244,23 +1427,27 @@ impl<S> StateMachineApplyContext<'_, S> {
 
         match status {
             InvocationStatus::Invoked(metadata) => {
-                self.kill_invoked_invocation(invocation_id, metadata)
+                self.kill_invoked_invocation(invocation_id, metadata, KILLED_INVOCATION_ERROR)
                     .await?;
                 self.reply_to_kill(response_sink, KillInvocationResponse::Ok);
             }
             InvocationStatus::Suspended { metadata, .. } | InvocationStatus::Paused(metadata) => {
-                self.kill_suspended_or_paused_invocation(invocation_id, metadata)
-                    .await?;
+                self.kill_suspended_or_paused_invocation(
+                    invocation_id,
+                    metadata,
+                    KILLED_INVOCATION_ERROR,
+                )
+                .await?;
                 self.reply_to_kill(response_sink, KillInvocationResponse::Ok);
             }
             InvocationStatus::Inboxed(inboxed) => {
-                self.terminate_inboxed_invocation(TerminationFlavor::Kill, invocation_id, inboxed)
+                self.terminate_inboxed_invocation(KILLED_INVOCATION_ERROR, invocation_id, inboxed)
                     .await?;
                 self.reply_to_kill(response_sink, KillInvocationResponse::Ok);
             }
             InvocationStatus::Scheduled(scheduled) => {
                 self.terminate_scheduled_invocation(
-                    TerminationFlavor::Kill,
+                    KILLED_INVOCATION_ERROR,
                     invocation_id,
                     scheduled,
                 )
@@ -1408,7 +1595,7 @@ impl<S> StateMachineApplyContext<'_, S> {
             }
             InvocationStatus::Inboxed(inboxed) => {
                 self.terminate_inboxed_invocation(
-                    TerminationFlavor::Cancel,
+                    CANCELED_INVOCATION_ERROR,
                     invocation_id,
                     inboxed,
                 )
@@ -1417,7 +1604,7 @@ impl<S> StateMachineApplyContext<'_, S> {
             }
             InvocationStatus::Scheduled(scheduled) => {
                 self.terminate_scheduled_invocation(
-                    TerminationFlavor::Cancel,
+                    CANCELED_INVOCATION_ERROR,
                     invocation_id,
                     scheduled,
                 )
@@ -1448,7 +1635,7 @@ impl<S> StateMachineApplyContext<'_, S> {
 
     async fn terminate_inboxed_invocation(
         &mut self,
-        termination_flavor: TerminationFlavor,
+        error: InvocationError,
         invocation_id: InvocationId,
         inboxed_invocation: InboxedInvocation,
     ) -> Result<(), Error>
@@ -1459,13 +1646,9 @@ impl<S> StateMachineApplyContext<'_, S> {
             + WriteFsmTable
             + WriteJournalTable
             + journal_table_v2::WriteJournalTable
-            + WriteJournalEventsTable,
+            + WriteJournalEventsTable
+            + WriteTimerTable,
     {
-        let error = match termination_flavor {
-            TerminationFlavor::Kill => KILLED_INVOCATION_ERROR,
-            TerminationFlavor::Cancel => CANCELED_INVOCATION_ERROR,
-        };
-
         let InboxedInvocation {
             inbox_sequence_number,
             metadata:
@@ -1473,6 +1656,7 @@ impl<S> StateMachineApplyContext<'_, S> {
                     response_sinks,
                     invocation_target,
                     input,
+                    deadline,
                     ..
                 },
         } = inboxed_invocation;
@@ -1495,6 +1679,8 @@ impl<S> StateMachineApplyContext<'_, S> {
             inbox_sequence_number,
         )
         .await?;
+        self.do_delete_invocation_deadline_timer(invocation_id, deadline)
+            .await?;
         self.do_free_invocation(invocation_id)?;
 
         // If there's a journal, delete journal
@@ -1528,7 +1714,7 @@ impl<S> StateMachineApplyContext<'_, S> {
 
     async fn terminate_scheduled_invocation(
         &mut self,
-        termination_flavor: TerminationFlavor,
+        error: InvocationError,
         invocation_id: InvocationId,
         scheduled_invocation: ScheduledInvocation,
     ) -> Result<(), Error>
@@ -1541,11 +1727,6 @@ impl<S> StateMachineApplyContext<'_, S> {
             + journal_table_v2::WriteJournalTable
             + WriteJournalEventsTable,
     {
-        let error = match termination_flavor {
-            TerminationFlavor::Kill => KILLED_INVOCATION_ERROR,
-            TerminationFlavor::Cancel => CANCELED_INVOCATION_ERROR,
-        };
-
         let ScheduledInvocation {
             metadata:
                 PreFlightInvocationMetadata {
@@ -1553,6 +1734,7 @@ impl<S> StateMachineApplyContext<'_, S> {
                     input,
                     invocation_target,
                     execution_time,
+                    deadline,
                     ..
                 },
         } = scheduled_invocation;
@@ -1577,6 +1759,8 @@ impl<S> StateMachineApplyContext<'_, S> {
         } else {
             warn!("Scheduled invocations must always have an execution time.");
         }
+        self.do_delete_invocation_deadline_timer(invocation_id, deadline)
+            .await?;
 
         // Free invocation
         self.do_free_invocation(invocation_id)?;
@@ -1614,6 +1798,7 @@ impl<S> StateMachineApplyContext<'_, S> {
         &mut self,
         invocation_id: InvocationId,
         metadata: InFlightInvocationMetadata,
+        error: InvocationError,
     ) -> Result<(), Error>
     where
         S: WriteInboxTable
@@ -1629,7 +1814,8 @@ impl<S> StateMachineApplyContext<'_, S> {
             + WriteFsmTable
             + journal_table_v2::WriteJournalTable
             + journal_table_v2::ReadJournalTable
-            + WriteJournalEventsTable,
+            + WriteJournalEventsTable
+            + WriteTimerTable,
     {
         self.kill_child_invocations(&invocation_id, metadata.journal_metadata.length, &metadata)
             .await?;
@@ -1637,7 +1823,7 @@ impl<S> StateMachineApplyContext<'_, S> {
         self.end_invocation(
             invocation_id,
             metadata,
-            Some(ResponseResult::Failure(KILLED_INVOCATION_ERROR)),
+            Some(ResponseResult::Failure(error)),
         )
         .await?;
         self.do_send_abort_invocation_to_invoker(invocation_id, InvocationEpoch::MAX);
@@ -1648,6 +1834,7 @@ impl<S> StateMachineApplyContext<'_, S> {
         &mut self,
         invocation_id: InvocationId,
         metadata: InFlightInvocationMetadata,
+        error: InvocationError,
     ) -> Result<(), Error>
     where
         S: WriteInboxTable
@@ -1663,7 +1850,8 @@ impl<S> StateMachineApplyContext<'_, S> {
             + WriteFsmTable
             + journal_table_v2::WriteJournalTable
             + journal_table_v2::ReadJournalTable
-            + WriteJournalEventsTable,
+            + WriteJournalEventsTable
+            + WriteTimerTable,
     {
         self.kill_child_invocations(&invocation_id, metadata.journal_metadata.length, &metadata)
             .await?;
@@ -1671,7 +1859,7 @@ impl<S> StateMachineApplyContext<'_, S> {
         self.end_invocation(
             invocation_id,
             metadata,
-            Some(ResponseResult::Failure(KILLED_INVOCATION_ERROR)),
+            Some(ResponseResult::Failure(error)),
         )
         .await?;
         self.do_send_abort_invocation_to_invoker(invocation_id, InvocationEpoch::MAX);
@@ -1900,6 +2088,7 @@ impl<S> StateMachineApplyContext<'_, S> {
             + journal_table_v2::ReadJournalTable
             + WriteJournalEventsTable,
     {
+        let wake_up_time = timer_value.wake_up_time();
         let (key, value) = timer_value.into_inner();
         self.do_delete_timer(key).await?;
 
@@ -1938,6 +2127,34 @@ impl<S> StateMachineApplyContext<'_, S> {
                 self.on_service_invocation(service_invocation).await
             }
             Timer::CleanInvocationStatus(invocation_id) => {
+                match self.completion_retention_expiration(&invocation_id).await? {
+                    // The invocation was purged already, or it's retained forever
+                    None => return Ok(()),
+                    // The timer is scheduled on the leader clock, while the completion time is
+                    // the record time, hence the retention expired if either of them says so.
+                    Some(expiration_time)
+                        if expiration_time > wake_up_time
+                            && expiration_time > self.record_created_at =>
+                    {
+                        // Either the leader clock lags behind the record time, or this is a newer
+                        // invocation with the same id, e.g. a request with the same idempotency
+                        // key after the purge. Check again once its retention expired.
+                        self.register_timer(
+                            TimerKeyValue::clean_invocation_status(expiration_time, invocation_id),
+                            Default::default(),
+                        )?;
+                        return Ok(());
+                    }
+                    Some(_) => {}
+                }
+                if self.is_leader {
+                    counter!(
+                        PARTITION_CLEANED_INVOCATIONS,
+                        "trigger" => "timer",
+                        "purged" => "invocation",
+                    )
+                    .increment(1);
+                }
                 lifecycle::OnPurgeCommand {
                     invocation_id,
                     response_sink: None,
@@ -1947,9 +2164,108 @@ impl<S> StateMachineApplyContext<'_, S> {
                 Ok(())
             }
             Timer::NeoInvoke(invocation_id) => self.on_neo_invoke_timer(invocation_id).await,
+            Timer::InvocationDeadline(invocation_id, invocation_creation_time) => {
+                self.on_invocation_deadline_timer(invocation_id, invocation_creation_time)
+                    .await
+            }
         }
     }
 
+    /// Fails the invocation with [`DEADLINE_EXCEEDED_INVOCATION_ERROR`] if it's still running.
+    async fn on_invocation_deadline_timer(
+        &mut self,
+        invocation_id: InvocationId,
+        invocation_creation_time: MillisSinceEpoch,
+    ) -> Result<(), Error>
+    where
+        S: ReadInvocationStatusTable
+            + WriteInvocationStatusTable
+            + WriteVirtualObjectStatusTable
+            + WriteInboxTable
+            + WriteFsmTable
+            + ReadStateTable
+            + WriteStateTable
+            + ReadJournalTable
+            + WriteJournalTable
+            + WriteOutboxTable
+            + WriteTimerTable
+            + journal_table_v2::WriteJournalTable
+            + journal_table_v2::ReadJournalTable
+            + WriteJournalEventsTable,
+    {
+        let status = self.get_invocation_status(&invocation_id).await?;
+        if status.get_timestamps().map(StatusTimestamps::creation_time)
+            != Some(invocation_creation_time)
+        {
+            // The invocation is gone, or it's a newer invocation with the same id, e.g. a request
+            // with the same idempotency key after the purge.
+            return Ok(());
+        }
+
+        let error = DEADLINE_EXCEEDED_INVOCATION_ERROR;
+        match status {
+            InvocationStatus::Invoked(metadata) => {
+                debug_if_leader!(
+                    self.is_leader,
+                    restate.invocation.id = %invocation_id,
+                    "Invocation exceeded its deadline"
+                );
+                self.kill_invoked_invocation(invocation_id, metadata, error)
+                    .await
+            }
+            InvocationStatus::Suspended { metadata, .. } | InvocationStatus::Paused(metadata) => {
+                debug_if_leader!(
+                    self.is_leader,
+                    restate.invocation.id = %invocation_id,
+                    "Invocation exceeded its deadline"
+                );
+                self.kill_suspended_or_paused_invocation(invocation_id, metadata, error)
+                    .await
+            }
+            InvocationStatus::Inboxed(inboxed) => {
+                self.terminate_inboxed_invocation(error, invocation_id, inboxed)
+                    .await
+            }
+            InvocationStatus::Scheduled(scheduled) => {
+                self.terminate_scheduled_invocation(error, invocation_id, scheduled)
+                    .await
+            }
+            InvocationStatus::Completed(_) | InvocationStatus::Free => {
+                // The invocation completed before its deadline
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the time the completion retention of the invocation expires at, or `None` if the
+    /// invocation is not completed or its retention never expires.
+    async fn completion_retention_expiration(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> Result<Option<MillisSinceEpoch>, Error>
+    where
+        S: ReadInvocationStatusTable,
+    {
+        let InvocationStatus::Completed(completed_invocation) =
+            self.get_invocation_status(invocation_id).await?
+        else {
+            return Ok(None);
+        };
+        let Some(completed_time) = completed_invocation.timestamps.completed_transition_time()
+        else {
+            // Invocations of the old invocation status table are cleaned up by their timer only
+            return Ok(Some(MillisSinceEpoch::UNIX_EPOCH));
+        };
+        Ok(u64::try_from(
+            completed_invocation
+                .completion_retention_duration
+                .as_millis(),
+        )
+        .ok()
+        .and_then(|retention| completed_time.as_u64().checked_add(retention))
+        .map(MillisSinceEpoch::new))
+    }
+
     async fn on_neo_invoke_timer(&mut self, invocation_id: InvocationId) -> Result<(), Error>
     where
         S: ReadVirtualObjectStatusTable
@@ -2091,13 +2407,42 @@ impl<S> StateMachineApplyContext<'_, S> {
                 .await?;
             }
             InvokerEffectKind::JournalEntry { entry_index, entry } => {
+                let invocation_metadata = invocation_status
+                    .into_invocation_metadata()
+                    .expect("Must be present if status is invoked");
+                if self.exceeds_max_journal_length(&invocation_metadata) {
+                    return self
+                        .kill_invocation_exceeding_max_journal_length(
+                            effect.invocation_id,
+                            invocation_metadata,
+                        )
+                        .await;
+                }
+                if self.settings.state_size_quota.is_some()
+                    && let EnrichedEntryHeader::SetState { .. } = entry.header()
+                    && let Entry::SetState(SetStateEntry { key, value }) =
+                        entry.deserialize_entry_ref::<ProtobufRawEntryCodec>()?
+                    && self
+                        .exceeds_state_size_quota(
+                            &invocation_metadata.invocation_target,
+                            &key,
+                            value.len(),
+                        )
+                        .await?
+                {
+                    return self
+                        .kill_invocation_exceeding_state_size_quota(
+                            effect.invocation_id,
+                            invocation_metadata,
+                        )
+                        .await;
+                }
+
                 self.handle_journal_entry(
                     effect.invocation_id,
                     entry_index,
                     entry,
-                    invocation_status
-                        .into_invocation_metadata()
-                        .expect("Must be present if status is invoked"),
+                    invocation_metadata,
                 )
                 .await?;
             }
@@ -2113,6 +2458,48 @@ impl<S> StateMachineApplyContext<'_, S> {
                     .or(entry.map(|entry| entry.inner))
                     .expect("Either the raw_entry field or the legacy entry field must be set");
 
+                if self.exceeds_max_journal_length(
+                    invocation_status
+                        .get_invocation_metadata()
+                        .expect("Must be present if status is invoked"),
+                ) {
+                    return self
+                        .kill_invocation_exceeding_max_journal_length(
+                            effect.invocation_id,
+                            invocation_status
+                                .into_invocation_metadata()
+                                .expect("Must be present if status is invoked"),
+                        )
+                        .await;
+                }
+                if self.settings.state_size_quota.is_some()
+                    && entry.ty() == journal_v2::EntryType::Command(CommandType::SetState)
+                {
+                    let set_state =
+                        entry.decode::<ServiceProtocolV4Codec, journal_v2::SetStateCommand>()?;
+                    let invocation_target = &invocation_status
+                        .get_invocation_metadata()
+                        .expect("Must be present if status is invoked")
+                        .invocation_target;
+                    if self
+                        .exceeds_state_size_quota(
+                            invocation_target,
+                            set_state.key.as_bytes(),
+                            set_state.value.len(),
+                        )
+                        .await?
+                    {
+                        return self
+                            .kill_invocation_exceeding_state_size_quota(
+                                effect.invocation_id,
+                                invocation_status
+                                    .into_invocation_metadata()
+                                    .expect("Must be present if status is invoked"),
+                            )
+                            .await;
+                    }
+                }
+
                 entries::OnJournalEntryCommand::from_raw_entry(
                     effect.invocation_id,
                     invocation_status,
@@ -2242,8 +2629,13 @@ impl<S> StateMachineApplyContext<'_, S> {
             + WriteStateTable
             + journal_table_v2::WriteJournalTable
             + journal_table_v2::ReadJournalTable
-            + WriteJournalEventsTable,
+            + WriteJournalEventsTable
+            + WriteTimerTable,
     {
+        // The invocation ended before its deadline, if any
+        self.do_delete_invocation_deadline_timer(invocation_id, invocation_metadata.deadline)
+            .await?;
+
         let invocation_target = invocation_metadata.invocation_target.clone();
         let journal_length = invocation_metadata.journal_metadata.length;
         let completion_retention = invocation_metadata.completion_retention_duration;
@@ -2301,9 +2693,15 @@ impl<S> StateMachineApplyContext<'_, S> {
                     ResponseResult::Failure(err) => Err((err.code(), err.message().to_owned())),
                 },
             );
+            self.notify_invocation_ended(invocation_id, &invocation_target, Some(&response_result));
 
-            // Store the completed status, if needed
+            // Store the completed status, if needed, and schedule its cleanup
             if !completion_retention.is_zero() {
+                self.action_collector
+                    .push(Action::ScheduleInvocationStatusCleanup {
+                        invocation_id,
+                        retention: completion_retention,
+                    });
                 let completed_invocation = CompletedInvocation::from_in_flight_invocation_metadata(
                     invocation_metadata,
                     if journal_retention.is_zero() {
@@ -2325,6 +2723,32 @@ impl<S> StateMachineApplyContext<'_, S> {
                 invocation_metadata.timestamps.creation_time(),
                 Ok(()),
             );
+
+            if (self.is_leader && self.emit_lifecycle_events)
+                || self.settings.store_completion_events
+            {
+                // Only the lifecycle and completion events need to know whether the invocation failed
+                let response_result = match response_result_override {
+                    Some(response_result) => Some(response_result),
+                    None => {
+                        self.read_last_output_entry_result(
+                            &invocation_id,
+                            journal_length,
+                            invocation_metadata
+                                .pinned_deployment
+                                .as_ref()
+                                .map(|pd| pd.service_protocol_version)
+                                .unwrap_or_default(),
+                        )
+                        .await?
+                    }
+                };
+                self.notify_invocation_ended(
+                    invocation_id,
+                    &invocation_target,
+                    response_result.as_ref(),
+                );
+            }
         }
 
         // If no retention, immediately cleanup the invocation status
@@ -2472,6 +2896,129 @@ impl<S> StateMachineApplyContext<'_, S> {
         Ok(())
     }
 
+    /// Whether setting the given state entry would make the state of the invoked service exceed
+    /// the quota. Entries shrinking the state are always allowed, e.g. after lowering the quota.
+    async fn exceeds_state_size_quota(
+        &mut self,
+        invocation_target: &InvocationTarget,
+        state_key: &[u8],
+        value_length: usize,
+    ) -> Result<bool, Error>
+    where
+        S: ReadStateTable,
+    {
+        let (Some(quota), Some(service_id)) = (
+            self.settings.state_size_quota,
+            invocation_target.as_keyed_service_id(),
+        ) else {
+            return Ok(false);
+        };
+
+        let size = self.storage.get_user_state_size(&service_id).await?;
+        let previous_entry_size = self
+            .storage
+            .get_user_state(&service_id, state_key)
+            .await?
+            .map_or(0, |value| (state_key.len() + value.len()) as u64);
+        let new_size =
+            (size + (state_key.len() + value_length) as u64).saturating_sub(previous_entry_size);
+
+        Ok(new_size > quota.get() as u64 && new_size > size)
+    }
+
+    async fn kill_invocation_exceeding_state_size_quota(
+        &mut self,
+        invocation_id: InvocationId,
+        metadata: InFlightInvocationMetadata,
+    ) -> Result<(), Error>
+    where
+        S: WriteInboxTable
+            + WriteVirtualObjectStatusTable
+            + ReadInvocationStatusTable
+            + WriteInvocationStatusTable
+            + ReadStateTable
+            + WriteStateTable
+            + WriteJournalTable
+            + ReadJournalTable
+            + WriteOutboxTable
+            + WriteFsmTable
+            + journal_table_v2::WriteJournalTable
+            + journal_table_v2::ReadJournalTable
+            + WriteJournalEventsTable
+            + WriteTimerTable,
+    {
+        debug_if_leader!(
+            self.is_leader,
+            restate.invocation.id = %invocation_id,
+            "Invocation exceeded the state size quota"
+        );
+        self.kill_invoked_invocation(
+            invocation_id,
+            metadata,
+            STATE_SIZE_QUOTA_EXCEEDED_INVOCATION_ERROR,
+        )
+        .await
+    }
+
+    /// Whether appending an entry would make the journal of the invocation exceed the max journal
+    /// length of the invoked service.
+    fn exceeds_max_journal_length(&self, metadata: &InFlightInvocationMetadata) -> bool {
+        let max_journal_length = self
+            .schema
+            .as_ref()
+            .and_then(|schema| {
+                schema
+                    .resolve_max_journal_length_override(metadata.invocation_target.service_name())
+            })
+            .or(self.settings.default_max_journal_length);
+
+        max_journal_length.is_some_and(|max| metadata.journal_metadata.length >= max.get())
+    }
+
+    /// Expiration time of a state entry set now by an invocation of the given service, if the
+    /// service has a state ttl.
+    fn state_expiration(&self, service_name: &str) -> Option<MillisSinceEpoch> {
+        self.schema
+            .as_ref()
+            .and_then(|schema| schema.resolve_state_ttl(service_name))
+            .map(|ttl| self.state_clock + ttl)
+    }
+
+    async fn kill_invocation_exceeding_max_journal_length(
+        &mut self,
+        invocation_id: InvocationId,
+        metadata: InFlightInvocationMetadata,
+    ) -> Result<(), Error>
+    where
+        S: WriteInboxTable
+            + WriteVirtualObjectStatusTable
+            + ReadInvocationStatusTable
+            + WriteInvocationStatusTable
+            + ReadStateTable
+            + WriteStateTable
+            + WriteJournalTable
+            + ReadJournalTable
+            + WriteOutboxTable
+            + WriteFsmTable
+            + journal_table_v2::WriteJournalTable
+            + journal_table_v2::ReadJournalTable
+            + WriteJournalEventsTable
+            + WriteTimerTable,
+    {
+        debug_if_leader!(
+            self.is_leader,
+            restate.invocation.id = %invocation_id,
+            restate.journal.length = metadata.journal_metadata.length,
+            "Invocation exceeded the max journal length"
+        );
+        self.kill_invoked_invocation(
+            invocation_id,
+            metadata,
+            JOURNAL_LENGTH_EXCEEDED_INVOCATION_ERROR,
+        )
+        .await
+    }
+
     async fn handle_journal_entry(
         &mut self,
         invocation_id: InvocationId,
@@ -3515,6 +4062,83 @@ impl<S> StateMachineApplyContext<'_, S> {
         }
     }
 
+    fn notify_lifecycle_event(
+        &mut self,
+        kind: InvocationLifecycleEventKind,
+        invocation_id: InvocationId,
+        invocation_target: &InvocationTarget,
+        error: Option<&InvocationError>,
+    ) {
+        if !self.emit_lifecycle_events {
+            return;
+        }
+
+        let mut event = InvocationLifecycleEvent::new(
+            kind,
+            invocation_id,
+            invocation_target,
+            self.record_created_at,
+        );
+        if let Some(error) = error {
+            event = event.with_error(error);
+        }
+        self.action_collector
+            .push(Action::NotifyInvocationLifecycleEvent { event });
+    }
+
+    fn notify_invocation_ended(
+        &mut self,
+        invocation_id: InvocationId,
+        invocation_target: &InvocationTarget,
+        response_result: Option<&ResponseResult>,
+    ) {
+        let (kind, error) = match response_result {
+            Some(ResponseResult::Failure(err)) => (InvocationLifecycleEventKind::Failed, Some(err)),
+            _ => (InvocationLifecycleEventKind::Completed, None),
+        };
+        self.notify_lifecycle_event(kind, invocation_id, invocation_target, error);
+
+        if self.settings.store_completion_events {
+            let mut event = InvocationLifecycleEvent::new(
+                kind,
+                invocation_id,
+                invocation_target,
+                self.record_created_at,
+            );
+            if let Some(error) = error {
+                event = event.with_error(error);
+            }
+            self.completion_events.push(event);
+        }
+    }
+
+    /// Stores the completion events collected while applying the command, keyed by the lsn of the
+    /// applied record so that every replica assigns them the same sequence.
+    fn write_completion_events(&mut self) -> Result<(), Error>
+    where
+        S: WriteCompletionEventTable,
+    {
+        for (index, event) in std::mem::take(&mut self.completion_events)
+            .into_iter()
+            .enumerate()
+        {
+            let sequence = CompletionEventSequence::new(
+                self.record_lsn,
+                u32::try_from(index).expect("completion events of a record should fit into u32"),
+            );
+            let payload = serde_json::to_vec(&event)
+                .map_err(|err| restate_storage_api::StorageError::Generic(err.into()))?;
+            self.storage.put_completion_event(
+                sequence,
+                &CompletionEvent {
+                    invocation_id: event.invocation_id,
+                    payload: Bytes::from(payload),
+                },
+            )?;
+        }
+        Ok(())
+    }
+
     fn handle_outgoing_message(&mut self, message: OutboxMessage) -> Result<(), Error>
     where
         S: WriteOutboxTable + WriteFsmTable,
@@ -3860,6 +4484,12 @@ impl<S> StateMachineApplyContext<'_, S> {
         );
 
         metadata.timestamps.update(self.record_created_at);
+        self.notify_lifecycle_event(
+            InvocationLifecycleEventKind::Suspended,
+            invocation_id,
+            &metadata.invocation_target,
+            None,
+        );
         self.storage
             .put_invocation_status(
                 &invocation_id,
@@ -4069,8 +4699,10 @@ impl<S> StateMachineApplyContext<'_, S> {
             "Effect: Set state"
         );
 
+        let value = encrypt_state_value(&service_id.service_name, value)?;
+        let expires_at = self.state_expiration(&service_id.service_name);
         self.storage
-            .put_user_state(&service_id, key, value)
+            .put_user_state(&service_id, key, value, expires_at)
             .map_err(Error::Storage)
     }
 
@@ -4147,6 +4779,24 @@ impl<S> StateMachineApplyContext<'_, S> {
         Ok(())
     }
 
+    async fn do_delete_invocation_deadline_timer(
+        &mut self,
+        invocation_id: InvocationId,
+        deadline: Option<MillisSinceEpoch>,
+    ) -> Result<(), Error>
+    where
+        S: WriteTimerTable,
+    {
+        if let Some(deadline) = deadline {
+            self.do_delete_timer(TimerKey::invocation_deadline(
+                deadline.as_u64(),
+                invocation_id.invocation_uuid(),
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
     async fn append_journal_entry(
         &mut self,
         invocation_id: InvocationId,
@@ -4487,7 +5137,9 @@ impl<S> StateMachineApplyContext<'_, S> {
 
         // overwrite existing key value pairs
         for (key, value) in state {
-            self.storage.put_user_state(&service_id, key, value)?;
+            let value = encrypt_state_value(&service_id.service_name, value)
+                .map_err(|err| restate_storage_api::StorageError::Generic(err.into()))?;
+            self.storage.put_user_state(&service_id, key, value, None)?;
         }
 
         Ok(())
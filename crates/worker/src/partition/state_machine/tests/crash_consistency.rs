@@ -0,0 +1,287 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Crashes the partition processor at every write of a command, and checks that the tables of the
+//! partition store are consistent with each other after restarting from the committed state.
+
+use super::{fixtures, *};
+
+use std::time::{Duration, SystemTime};
+
+use restate_storage_api::inbox_table::{InboxEntry, ScanInboxTable};
+use restate_storage_api::invocation_status_table::ScanInvocationStatusTable;
+use restate_storage_api::service_status_table::{
+    ScanVirtualObjectStatusTable, VirtualObjectStatus,
+};
+use restate_storage_api::timer_table::{ReadTimerTable, Timer};
+use restate_storage_api::{journal_table, journal_table_v2};
+use restate_types::invocation::{
+    InvocationTargetType, TerminationFlavor, VirtualObjectHandlerType,
+};
+use restate_types::time::MillisSinceEpoch;
+
+use crate::partition::create_state_machine;
+use crate::partition::state_machine::should_use_journal_table_v2;
+
+/// Applies the command failing the `n`-th write of its transaction, for every `n` until the
+/// command applies without reaching the failpoint. After each failure the transaction is dropped
+/// and the state machine is restarted from the partition store, like after a crash, and the
+/// invariants of the partition store are checked.
+async fn apply_crashing_at_every_write(env: &mut TestEnv, command: Command) -> Vec<Action> {
+    for num_writes in 0.. {
+        let mut transaction = env.storage.transaction();
+        transaction.fail_writes_after(num_writes);
+        let mut action_collector = ActionCollector::default();
+        let result = env
+            .state_machine
+            .apply(
+                command.clone(),
+                MillisSinceEpoch::now(),
+                Lsn::OLDEST,
+                &mut transaction,
+                &mut action_collector,
+                true,
+            )
+            .await;
+
+        if !transaction.write_failpoint_triggered() {
+            result.expect("command should apply");
+            transaction.commit().await.unwrap();
+            assert_invariants(&mut env.storage).await;
+            return action_collector;
+        }
+
+        drop(transaction);
+        env.state_machine = create_state_machine(&mut env.storage)
+            .await
+            .expect("state machine should restart");
+        assert_invariants(&mut env.storage).await;
+    }
+    unreachable!("the number of writes of a command is bounded")
+}
+
+/// Asserts that the tables of the partition store agree with each other:
+///
+/// * inboxed invocations, and only those, are in the inbox
+/// * scheduled invocations, and only those, have an invoke timer
+/// * virtual objects are locked by, and only by, in-flight exclusive invocations
+/// * in-flight invocations have all the entries of their journal
+async fn assert_invariants(storage: &mut PartitionStore) {
+    let range = PartitionKey::MIN..=PartitionKey::MAX;
+
+    let statuses: HashMap<InvocationId, InvocationStatus> = storage
+        .scan_invocation_statuses(range.clone())
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+
+    let inboxed = Arc::new(Mutex::new(HashSet::new()));
+    storage
+        .for_each_inbox(range.clone(), {
+            let inboxed = Arc::clone(&inboxed);
+            move |entry| {
+                if let InboxEntry::Invocation(_, invocation_id) = entry.inbox_entry {
+                    inboxed.lock().unwrap().insert(invocation_id);
+                }
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    let inboxed = Arc::into_inner(inboxed).unwrap().into_inner().unwrap();
+
+    let locks = Arc::new(Mutex::new(HashMap::new()));
+    storage
+        .for_each_virtual_object_status(range, {
+            let locks = Arc::clone(&locks);
+            move |(service_id, status)| {
+                if let VirtualObjectStatus::Locked(invocation_id) = status {
+                    locks.lock().unwrap().insert(service_id, invocation_id);
+                }
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    let locks = Arc::into_inner(locks).unwrap().into_inner().unwrap();
+
+    let scheduled: HashSet<InvocationId> = storage
+        .next_timers_greater_than(None, usize::MAX)
+        .unwrap()
+        .try_filter_map(|(_, timer)| {
+            std::future::ready(Ok(match timer {
+                Timer::NeoInvoke(invocation_id) => Some(invocation_id),
+                _ => None,
+            }))
+        })
+        .try_collect()
+        .await
+        .unwrap();
+
+    for invocation_id in &inboxed {
+        assert!(
+            matches!(
+                statuses.get(invocation_id),
+                Some(InvocationStatus::Inboxed(_))
+            ),
+            "inbox entry of {invocation_id} without inboxed status"
+        );
+    }
+    for invocation_id in &scheduled {
+        assert!(
+            matches!(
+                statuses.get(invocation_id),
+                Some(InvocationStatus::Scheduled(_))
+            ),
+            "invoke timer of {invocation_id} without scheduled status"
+        );
+    }
+    for (service_id, invocation_id) in &locks {
+        let invocation_target = statuses
+            .get(invocation_id)
+            .and_then(InvocationStatus::get_invocation_metadata)
+            .map(|metadata| &metadata.invocation_target);
+        assert!(
+            invocation_target.is_some_and(|invocation_target| {
+                invocation_target.as_keyed_service_id().as_ref() == Some(service_id)
+            }),
+            "{service_id} locked by {invocation_id}, which is not in-flight"
+        );
+    }
+
+    for (invocation_id, status) in &statuses {
+        match status {
+            InvocationStatus::Inboxed(_) => assert!(
+                inboxed.contains(invocation_id),
+                "inboxed {invocation_id} not in the inbox"
+            ),
+            InvocationStatus::Scheduled(_) => assert!(
+                scheduled.contains(invocation_id),
+                "scheduled {invocation_id} without invoke timer"
+            ),
+            InvocationStatus::Invoked(metadata)
+            | InvocationStatus::Suspended { metadata, .. }
+            | InvocationStatus::Paused(metadata) => {
+                if metadata.invocation_target.invocation_target_ty()
+                    == InvocationTargetType::VirtualObject(VirtualObjectHandlerType::Exclusive)
+                {
+                    let service_id = metadata
+                        .invocation_target
+                        .as_keyed_service_id()
+                        .expect("virtual objects are keyed");
+                    assert_eq!(
+                        locks.get(&service_id),
+                        Some(invocation_id),
+                        "in-flight {invocation_id} doesn't hold the lock of {service_id}"
+                    );
+                }
+
+                let length = metadata.journal_metadata.length;
+                let entries = if should_use_journal_table_v2(status) {
+                    journal_table_v2::ReadJournalTable::get_journal(storage, *invocation_id, length)
+                        .unwrap()
+                        .try_collect::<Vec<_>>()
+                        .await
+                        .unwrap()
+                        .len()
+                } else {
+                    journal_table::ReadJournalTable::get_journal(storage, invocation_id, length)
+                        .unwrap()
+                        .try_collect::<Vec<_>>()
+                        .await
+                        .unwrap()
+                        .len()
+                };
+                assert_eq!(
+                    entries, length as usize,
+                    "journal of in-flight {invocation_id} is incomplete"
+                );
+            }
+            InvocationStatus::Completed(_) | InvocationStatus::Free => {}
+        }
+    }
+}
+
+#[restate_core::test]
+async fn virtual_object_lifecycle_is_crash_consistent() {
+    let mut test_env = TestEnv::create().await;
+
+    let invocation_target = InvocationTarget::virtual_object(
+        "MyObject",
+        "my-key",
+        "handler",
+        VirtualObjectHandlerType::Exclusive,
+    );
+    let first_id = InvocationId::mock_generate(&invocation_target);
+    let second_id = InvocationId::mock_generate(&invocation_target);
+    let delayed_id = InvocationId::mock_generate(&invocation_target);
+    let wake_up_time = MillisSinceEpoch::from(SystemTime::now() + Duration::from_secs(60));
+
+    // invoked, then inboxed behind the first one
+    for invocation_id in [first_id, second_id] {
+        apply_crashing_at_every_write(
+            &mut test_env,
+            Command::Invoke(Box::new(ServiceInvocation {
+                invocation_id,
+                invocation_target: invocation_target.clone(),
+                ..ServiceInvocation::mock()
+            })),
+        )
+        .await;
+    }
+    apply_crashing_at_every_write(
+        &mut test_env,
+        Command::Invoke(Box::new(ServiceInvocation {
+            invocation_id: delayed_id,
+            invocation_target: invocation_target.clone(),
+            execution_time: Some(wake_up_time),
+            ..ServiceInvocation::mock()
+        })),
+    )
+    .await;
+
+    // completing the first invocation dequeues the second one
+    apply_crashing_at_every_write(&mut test_env, fixtures::invoker_end_effect(first_id)).await;
+    assert_that!(
+        test_env.storage.get_invocation_status(&second_id).await,
+        ok(pat!(InvocationStatus::Invoked { .. }))
+    );
+
+    // the delayed invocation is inboxed behind the second one
+    apply_crashing_at_every_write(
+        &mut test_env,
+        Command::Timer(TimerKeyValue::neo_invoke(wake_up_time, delayed_id)),
+    )
+    .await;
+    assert_that!(
+        test_env.storage.get_invocation_status(&delayed_id).await,
+        ok(pat!(InvocationStatus::Inboxed { .. }))
+    );
+
+    // killing the second invocation dequeues the delayed one
+    apply_crashing_at_every_write(
+        &mut test_env,
+        Command::TerminateInvocation(InvocationTermination {
+            invocation_id: second_id,
+            flavor: TerminationFlavor::Kill,
+            response_sink: None,
+        }),
+    )
+    .await;
+    assert_that!(
+        test_env.storage.get_invocation_status(&delayed_id).await,
+        ok(pat!(InvocationStatus::Invoked { .. }))
+    );
+
+    test_env.shutdown().await;
+}
@@ -76,6 +76,46 @@ async fn send_with_delay() {
     test_env.shutdown().await;
 }
 
+#[test(restate_core::test)]
+async fn send_with_delay_fired_in_batch() {
+    let mut test_env = TestEnv::create().await;
+
+    let wake_up_time = MillisSinceEpoch::from(SystemTime::now() + Duration::from_secs(60));
+    let invocation_ids = [InvocationId::mock_random(), InvocationId::mock_random()];
+    for invocation_id in invocation_ids {
+        let _ = test_env
+            .apply(Command::Invoke(Box::new(ServiceInvocation {
+                invocation_id,
+                invocation_target: InvocationTarget::mock_service(),
+                execution_time: Some(wake_up_time),
+                ..ServiceInvocation::mock()
+            })))
+            .await;
+    }
+
+    // Fire both timers with a single command
+    let actions = test_env
+        .apply(Command::TimerBatch(
+            invocation_ids
+                .iter()
+                .map(|invocation_id| TimerKeyValue::neo_invoke(wake_up_time, *invocation_id))
+                .collect(),
+        ))
+        .await;
+
+    for invocation_id in invocation_ids {
+        assert_that!(
+            actions,
+            contains(matchers::actions::invoke_for_id(invocation_id))
+        );
+        assert_that!(
+            test_env.storage.get_invocation_status(&invocation_id).await,
+            ok(pat!(InvocationStatus::Invoked { .. }))
+        );
+    }
+    test_env.shutdown().await;
+}
+
 #[test(restate_core::test)]
 async fn send_with_delay_to_locked_virtual_object() {
     let mut test_env = TestEnv::create().await;
@@ -20,7 +20,9 @@ use restate_storage_api::timer_table::{
 };
 use restate_types::deployment::PinnedDeployment;
 use restate_types::identifiers::EntryIndex;
-use restate_types::invocation::{IngressInvocationResponseSink, TerminationFlavor};
+use restate_types::invocation::{
+    DEADLINE_HEADER_NAME, IngressInvocationResponseSink, TerminationFlavor,
+};
 use restate_types::journal::enriched::EnrichedEntryHeader;
 use restate_types::journal_v2::NotificationId;
 use restate_types::service_protocol;
@@ -180,6 +182,142 @@ async fn terminate_scheduled_invocation(
     Ok(())
 }
 
+#[restate_core::test]
+async fn fail_invocation_exceeding_deadline() -> anyhow::Result<()> {
+    let mut test_env = TestEnv::create().await;
+
+    let invocation_id = InvocationId::mock_random();
+    let rpc_id = PartitionProcessorRpcRequestId::new();
+    let deadline = MillisSinceEpoch::new(1_000);
+
+    let actions = test_env
+        .apply(Command::Invoke(Box::new(ServiceInvocation {
+            invocation_id,
+            headers: vec![Header::new(
+                DEADLINE_HEADER_NAME,
+                deadline.as_u64().to_string(),
+            )],
+            response_sink: Some(ServiceInvocationResponseSink::ingress(rpc_id)),
+            ..ServiceInvocation::mock()
+        })))
+        .await;
+    assert_that!(
+        actions,
+        all!(
+            contains(matchers::actions::invoke_for_id(invocation_id)),
+            contains(pat!(Action::RegisterTimer { .. }))
+        )
+    );
+    let creation_time = test_env
+        .storage()
+        .get_invocation_status(&invocation_id)
+        .await?
+        .get_timestamps()
+        .unwrap()
+        .creation_time();
+
+    // A timer of an older invocation with the same id is ignored
+    let actions = test_env
+        .apply(Command::Timer(TimerKeyValue::invocation_deadline(
+            deadline,
+            invocation_id,
+            MillisSinceEpoch::new(creation_time.as_u64() - 1),
+        )))
+        .await;
+    assert_that!(actions, not(contains(pat!(Action::IngressResponse { .. }))));
+
+    let actions = test_env
+        .apply(Command::Timer(TimerKeyValue::invocation_deadline(
+            deadline,
+            invocation_id,
+            creation_time,
+        )))
+        .await;
+    assert_that!(
+        actions,
+        all!(
+            contains(pat!(Action::AbortInvocation {
+                invocation_id: eq(invocation_id),
+            })),
+            contains(pat!(Action::IngressResponse {
+                request_id: eq(rpc_id),
+                invocation_id: some(eq(invocation_id)),
+                response: eq(InvocationOutputResponse::Failure(
+                    DEADLINE_EXCEEDED_INVOCATION_ERROR
+                ))
+            }))
+        )
+    );
+    let current_invocation_status = test_env
+        .storage()
+        .get_invocation_status(&invocation_id)
+        .await?;
+    assert!(let InvocationStatus::Free = current_invocation_status);
+
+    // Deadline timers of invocations that are gone already are ignored
+    let actions = test_env
+        .apply(Command::Timer(TimerKeyValue::invocation_deadline(
+            deadline,
+            invocation_id,
+            creation_time,
+        )))
+        .await;
+    assert_that!(actions, not(contains(pat!(Action::IngressResponse { .. }))));
+
+    test_env.shutdown().await;
+    Ok(())
+}
+
+#[restate_core::test]
+async fn kill_invocation_deletes_deadline_timer() -> anyhow::Result<()> {
+    let mut test_env = TestEnv::create().await;
+
+    let invocation_id = InvocationId::mock_random();
+    let deadline = MillisSinceEpoch::new(1_000);
+
+    let _ = test_env
+        .apply(Command::Invoke(Box::new(ServiceInvocation {
+            invocation_id,
+            headers: vec![Header::new(
+                DEADLINE_HEADER_NAME,
+                deadline.as_u64().to_string(),
+            )],
+            ..ServiceInvocation::mock()
+        })))
+        .await;
+
+    let actions = test_env
+        .apply(Command::TerminateInvocation(InvocationTermination {
+            invocation_id,
+            flavor: TerminationFlavor::Kill,
+            response_sink: None,
+        }))
+        .await;
+    assert_that!(
+        actions,
+        contains(pat!(Action::DeleteTimer {
+            timer_key: eq(TimerKey::invocation_deadline(
+                deadline.as_u64(),
+                invocation_id.invocation_uuid()
+            ))
+        }))
+    );
+
+    // The deadline timer is gone
+    assert_that!(
+        test_env
+            .storage
+            .next_timers_greater_than(None, usize::MAX)
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await?,
+        empty()
+    );
+
+    test_env.shutdown().await;
+    Ok(())
+}
+
 #[restate_core::test]
 async fn kill_call_tree() -> anyhow::Result<()> {
     let mut test_env = TestEnv::create().await;
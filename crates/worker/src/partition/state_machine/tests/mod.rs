@@ -10,6 +10,7 @@
 
 use super::*;
 
+mod crash_consistency;
 mod delayed_send;
 pub mod fixtures;
 mod idempotency;
@@ -18,6 +19,7 @@ mod kill_cancel;
 pub mod matchers;
 mod workflow;
 
+use crate::lifecycle_webhook::InvocationLifecycleEventError;
 use crate::partition::state_machine::tests::fixtures::{
     background_invoke_entry, incomplete_invoke_entry,
 };
@@ -35,10 +37,12 @@ use restate_partition_store::{PartitionStore, PartitionStoreManager};
 use restate_rocksdb::RocksDbManager;
 use restate_service_protocol::codec::ProtobufRawEntryCodec;
 use restate_storage_api::Transaction;
+use restate_storage_api::completion_event_table::ReadCompletionEventTable;
+use restate_storage_api::fsm_table::ReadFsmTable;
 use restate_storage_api::inbox_table::ReadInboxTable;
 use restate_storage_api::invocation_status_table::{
-    InFlightInvocationMetadata, InvocationStatus, ReadInvocationStatusTable,
-    WriteInvocationStatusTable,
+    InFlightInvocationMetadata, InvocationStatus, InvocationStatusDiscriminants,
+    ReadInvocationStatusTable, WriteInvocationStatusTable,
 };
 use restate_storage_api::journal_table::{JournalEntry, ReadJournalTable};
 use restate_storage_api::outbox_table::ReadOutboxTable;
@@ -691,8 +695,8 @@ async fn clear_all_user_states() -> anyhow::Result<()> {
 
     // Fill with some state the service K/V store
     let mut txn = test_env.storage.transaction();
-    txn.put_user_state(&service_id, b"my-key-1", b"my-val-1")?;
-    txn.put_user_state(&service_id, b"my-key-2", b"my-val-2")?;
+    txn.put_user_state(&service_id, b"my-key-1", b"my-val-1", None)?;
+    txn.put_user_state(&service_id, b"my-key-2", b"my-val-2", None)?;
     txn.commit().await.unwrap();
 
     let invocation_id =
@@ -730,8 +734,8 @@ async fn get_state_keys() -> TestResult {
 
     // Mock some state
     let mut txn = test_env.storage.transaction();
-    txn.put_user_state(&service_id, b"key1", b"value1")?;
-    txn.put_user_state(&service_id, b"key2", b"value2")?;
+    txn.put_user_state(&service_id, b"key1", b"value1", None)?;
+    txn.put_user_state(&service_id, b"key2", b"value2", None)?;
     txn.commit().await.unwrap();
 
     let actions = test_env
@@ -1286,3 +1290,403 @@ async fn deduplicate_requests_with_same_pp_rpc_request_id() -> TestResult {
     test_env.shutdown().await;
     Ok(())
 }
+
+#[restate_core::test]
+async fn cleanup_timer_purges_only_expired_invocations() {
+    let mut test_env = TestEnv::create().await;
+    let invocation_id = InvocationId::mock_random();
+
+    // Completed just now, retained for one hour by the mock
+    let mut txn = test_env.storage().transaction();
+    txn.put_invocation_status(
+        &invocation_id,
+        &InvocationStatus::Completed(CompletedInvocation::mock_neo()),
+    )
+    .unwrap();
+    txn.commit().await.unwrap();
+
+    // The retention didn't expire yet, the cleanup is scheduled again
+    let actions = test_env
+        .apply(Command::Timer(TimerKeyValue::clean_invocation_status(
+            MillisSinceEpoch::now(),
+            invocation_id,
+        )))
+        .await;
+    assert!(actions.iter().any(|action| matches!(
+        action,
+        Action::RegisterTimer { timer_value }
+            if timer_value.value() == &Timer::CleanInvocationStatus(invocation_id)
+    )));
+    assert_that!(
+        test_env
+            .storage()
+            .get_invocation_status(&invocation_id)
+            .await
+            .unwrap(),
+        matchers::storage::is_variant(InvocationStatusDiscriminants::Completed)
+    );
+
+    // The timer wakes up after the retention expired, even though the record time lags behind
+    let _ = test_env
+        .apply(Command::Timer(TimerKeyValue::clean_invocation_status(
+            MillisSinceEpoch::after(std::time::Duration::from_secs(2 * 60 * 60)),
+            invocation_id,
+        )))
+        .await;
+    assert_that!(
+        test_env
+            .storage()
+            .get_invocation_status(&invocation_id)
+            .await
+            .unwrap(),
+        pat!(InvocationStatus::Free)
+    );
+
+    let mut txn = test_env.storage().transaction();
+    txn.put_invocation_status(
+        &invocation_id,
+        &InvocationStatus::Completed(CompletedInvocation {
+            completion_retention_duration: std::time::Duration::ZERO,
+            ..CompletedInvocation::mock_neo()
+        }),
+    )
+    .unwrap();
+    txn.commit().await.unwrap();
+
+    let _ = test_env
+        .apply(Command::Timer(TimerKeyValue::clean_invocation_status(
+            MillisSinceEpoch::now(),
+            invocation_id,
+        )))
+        .await;
+    assert_that!(
+        test_env
+            .storage()
+            .get_invocation_status(&invocation_id)
+            .await
+            .unwrap(),
+        pat!(InvocationStatus::Free)
+    );
+
+    test_env.shutdown().await;
+}
+
+#[restate_core::test]
+async fn journal_exceeding_max_length_kills_invocation() {
+    let mut test_env = TestEnv::create_with_state_machine(
+        StateMachine::new(
+            0,    /* inbox_seq_number */
+            0,    /* outbox_seq_number */
+            None, /* outbox_head_seq_number */
+            PartitionKey::MIN..=PartitionKey::MAX,
+            SemanticRestateVersion::unknown().clone(),
+            Default::default(),
+            None,
+        )
+        .with_default_max_journal_length(NonZeroU32::new(2)),
+    )
+    .await;
+    let service_id = ServiceId::new("MySvc", "my-key");
+    let invocation_id =
+        fixtures::mock_start_invocation_with_service_id(&mut test_env, service_id.clone()).await;
+    fixtures::mock_pinned_deployment_v5(&mut test_env, invocation_id).await;
+
+    let set_state = |key: &'static str| {
+        fixtures::invoker_entry_effect(
+            invocation_id,
+            journal_v2::SetStateCommand {
+                key: key.into(),
+                value: Bytes::from_static(b"my-val"),
+                name: Default::default(),
+            },
+        )
+    };
+
+    // The journal contains the input command, hence there's room for one more entry
+    let actions = test_env.apply(set_state("my-key-1")).await;
+    assert_that!(actions, not(contains(pat!(Action::AbortInvocation { .. }))));
+
+    let actions = test_env.apply(set_state("my-key-2")).await;
+    assert_that!(
+        actions,
+        contains(pat!(Action::AbortInvocation {
+            invocation_id: eq(invocation_id),
+        }))
+    );
+    assert_that!(
+        test_env
+            .storage
+            .get_user_state(&service_id, b"my-key-2")
+            .await
+            .unwrap(),
+        none()
+    );
+    assert_that!(
+        test_env.storage.get_invocation_status(&invocation_id).await,
+        ok(not(pat!(InvocationStatus::Invoked { .. })))
+    );
+
+    test_env.shutdown().await;
+}
+
+#[restate_core::test]
+async fn state_clock_never_goes_backwards() {
+    let mut test_env = TestEnv::create().await;
+    let service_id = ServiceId::new("MySvc", "my-key");
+
+    for created_at in [2_000, 1_000] {
+        let mut transaction = test_env.storage.transaction();
+        test_env
+            .state_machine
+            .apply(
+                Command::PatchState(ExternalStateMutation {
+                    service_id: service_id.clone(),
+                    version: None,
+                    state: HashMap::default(),
+                }),
+                MillisSinceEpoch::new(created_at),
+                Lsn::OLDEST,
+                &mut transaction,
+                &mut ActionCollector::default(),
+                true,
+            )
+            .await
+            .unwrap();
+        transaction.commit().await.unwrap();
+    }
+
+    // Records created by a leader with a clock behind don't move it backwards
+    assert_eq!(
+        test_env.state_machine.state_clock,
+        MillisSinceEpoch::new(2_000)
+    );
+    assert_eq!(
+        test_env.storage.get_state_clock().await.unwrap(),
+        MillisSinceEpoch::new(2_000)
+    );
+
+    test_env.shutdown().await;
+}
+
+#[restate_core::test]
+async fn emit_invocation_lifecycle_events() {
+    let mut test_env = TestEnv::create_with_state_machine(
+        StateMachine::new(
+            0,    /* inbox_seq_number */
+            0,    /* outbox_seq_number */
+            None, /* outbox_head_seq_number */
+            PartitionKey::MIN..=PartitionKey::MAX,
+            SemanticRestateVersion::unknown().clone(),
+            Default::default(),
+            None,
+        )
+        .with_lifecycle_events(true),
+    )
+    .await;
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let invocation_id = InvocationId::mock_generate(&invocation_target);
+
+    let actions = test_env
+        .apply(Command::Invoke(Box::new(ServiceInvocation::initialize(
+            invocation_id,
+            invocation_target.clone(),
+            Source::Ingress(PartitionProcessorRpcRequestId::new()),
+        ))))
+        .await;
+    assert_that!(
+        actions,
+        contains(pat!(Action::NotifyInvocationLifecycleEvent {
+            event: pat!(InvocationLifecycleEvent {
+                kind: eq(InvocationLifecycleEventKind::Started),
+                invocation_id: eq(invocation_id),
+                service_name: eq(invocation_target.service_name().to_string()),
+                error: none(),
+            })
+        }))
+    );
+    fixtures::mock_pinned_deployment_v5(&mut test_env, invocation_id).await;
+
+    let actions = test_env
+        .apply(fixtures::invoker_suspended(
+            invocation_id,
+            [journal_v2::NotificationId::for_completion(1)],
+        ))
+        .await;
+    assert_that!(
+        actions,
+        contains(pat!(Action::NotifyInvocationLifecycleEvent {
+            event: pat!(InvocationLifecycleEvent {
+                kind: eq(InvocationLifecycleEventKind::Suspended),
+                invocation_id: eq(invocation_id),
+            })
+        }))
+    );
+
+    let actions = test_env
+        .apply(Command::TerminateInvocation(InvocationTermination {
+            invocation_id,
+            flavor: TerminationFlavor::Kill,
+            response_sink: None,
+        }))
+        .await;
+    assert_that!(
+        actions,
+        contains(pat!(Action::NotifyInvocationLifecycleEvent {
+            event: pat!(InvocationLifecycleEvent {
+                kind: eq(InvocationLifecycleEventKind::Failed),
+                invocation_id: eq(invocation_id),
+                error: some(pat!(InvocationLifecycleEventError {
+                    code: eq(u16::from(KILLED_INVOCATION_ERROR.code())),
+                    message: eq("killed"),
+                })),
+            })
+        }))
+    );
+
+    test_env.shutdown().await;
+}
+
+#[restate_core::test]
+async fn store_completion_events() {
+    let mut test_env = TestEnv::create_with_state_machine(
+        StateMachine::new(
+            0,    /* inbox_seq_number */
+            0,    /* outbox_seq_number */
+            None, /* outbox_head_seq_number */
+            PartitionKey::MIN..=PartitionKey::MAX,
+            SemanticRestateVersion::unknown().clone(),
+            Default::default(),
+            None,
+        )
+        .with_completion_events(true),
+    )
+    .await;
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let invocation_id = InvocationId::mock_generate(&invocation_target);
+
+    let actions = test_env
+        .apply(Command::Invoke(Box::new(ServiceInvocation::initialize(
+            invocation_id,
+            invocation_target.clone(),
+            Source::Ingress(PartitionProcessorRpcRequestId::new()),
+        ))))
+        .await;
+    // Only the completions are stored, and the lifecycle events are not emitted
+    assert_that!(
+        actions,
+        not(contains(pat!(
+            Action::NotifyInvocationLifecycleEvent { .. }
+        )))
+    );
+    assert_that!(
+        test_env
+            .storage()
+            .get_completion_events(None, 10)
+            .await
+            .unwrap(),
+        empty()
+    );
+
+    test_env
+        .apply(Command::TerminateInvocation(InvocationTermination {
+            invocation_id,
+            flavor: TerminationFlavor::Kill,
+            response_sink: None,
+        }))
+        .await;
+
+    let completion_events = test_env
+        .storage()
+        .get_completion_events(None, 10)
+        .await
+        .unwrap();
+    let_assert!([(sequence, event)] = completion_events.as_slice());
+    assert_eq!(*sequence, CompletionEventSequence::new(Lsn::OLDEST, 0));
+    assert_eq!(event.invocation_id, invocation_id);
+    let payload: serde_json::Value = serde_json::from_slice(&event.payload).unwrap();
+    assert_eq!(payload["type"], "failed");
+    assert_eq!(payload["invocation_id"], invocation_id.to_string());
+    assert_eq!(
+        payload["error"]["code"],
+        u16::from(KILLED_INVOCATION_ERROR.code())
+    );
+
+    test_env
+        .apply(Command::TruncateCompletionEvents(*sequence))
+        .await;
+    assert_that!(
+        test_env
+            .storage()
+            .get_completion_events(None, 10)
+            .await
+            .unwrap(),
+        empty()
+    );
+
+    test_env.shutdown().await;
+}
+
+#[restate_core::test]
+async fn disabling_completion_events_drops_them() {
+    let mut test_env = TestEnv::create_with_state_machine(
+        StateMachine::new(
+            0,    /* inbox_seq_number */
+            0,    /* outbox_seq_number */
+            None, /* outbox_head_seq_number */
+            PartitionKey::MIN..=PartitionKey::MAX,
+            SemanticRestateVersion::unknown().clone(),
+            Default::default(),
+            None,
+        )
+        .with_completion_events(true),
+    )
+    .await;
+    let invocation_target = InvocationTarget::mock_virtual_object();
+    let invocation_id = InvocationId::mock_generate(&invocation_target);
+
+    test_env
+        .apply(Command::Invoke(Box::new(ServiceInvocation::initialize(
+            invocation_id,
+            invocation_target.clone(),
+            Source::Ingress(PartitionProcessorRpcRequestId::new()),
+        ))))
+        .await;
+    test_env
+        .apply(Command::TerminateInvocation(InvocationTermination {
+            invocation_id,
+            flavor: TerminationFlavor::Kill,
+            response_sink: None,
+        }))
+        .await;
+    assert_eq!(
+        test_env
+            .storage()
+            .get_completion_events(None, 10)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    test_env
+        .apply(Command::UpdatePartitionSettings(
+            restate_wal_protocol::control::UpdatePartitionSettings {
+                partition_key_range: restate_types::logs::Keys::RangeInclusive(
+                    PartitionKey::MIN..=PartitionKey::MAX,
+                ),
+                settings: Default::default(),
+            },
+        ))
+        .await;
+    assert!(!test_env.state_machine.settings.store_completion_events);
+    assert_that!(
+        test_env
+            .storage()
+            .get_completion_events(None, 10)
+            .await
+            .unwrap(),
+        empty()
+    );
+
+    test_env.shutdown().await;
+}
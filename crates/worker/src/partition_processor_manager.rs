@@ -8,6 +8,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod apply_slo;
+mod outbox_consistency;
 mod processor_state;
 mod spawn_processor_task;
 
@@ -44,7 +46,7 @@ use restate_core::{
 };
 use restate_core::{RuntimeTaskHandle, TaskCenter};
 use restate_invoker_api::StatusHandle;
-use restate_invoker_impl::{ChannelStatusReader, TokenBucket};
+use restate_invoker_impl::{ChannelStatusReader, ReplayLimiter, TokenBucket};
 use restate_metadata_server::{MetadataStoreClient, ReadModifyWriteError};
 use restate_metadata_store::{ReadWriteError, RetryError, retry_on_retryable_error};
 use restate_partition_store::PartitionStoreManager;
@@ -52,7 +54,9 @@ use restate_partition_store::snapshots::{
     PartitionSnapshotMetadata, SnapshotPartitionTask, SnapshotRepository,
 };
 use restate_partition_store::{SnapshotError, SnapshotErrorKind};
+use restate_storage_api::StorageError;
 use restate_time_util::DurationExt;
+use restate_timer::RuntimeClock;
 use restate_types::cluster::cluster_state::ReplayStatus;
 use restate_types::cluster::cluster_state::{PartitionProcessorStatus, RunMode};
 use restate_types::config::Configuration;
@@ -73,17 +77,23 @@ use restate_types::net::{RpcRequest as _, UnaryMessage};
 use restate_types::nodes_config::{NodesConfigError, NodesConfiguration, WorkerState};
 use restate_types::partition_table::PartitionTable;
 use restate_types::partitions::Partition;
+use restate_types::partitions::outbox_consistency::OutboxConsistencyReport;
 use restate_types::partitions::state::PartitionReplicaSetStates;
 use restate_types::protobuf::common::WorkerStatus;
 use restate_types::retries::with_jitter;
 use restate_types::{GenerationalNodeId, SharedString};
 
+use crate::lifecycle_webhook::LifecycleEventSender;
 use crate::metric_definitions::NUM_PARTITIONS;
 use crate::metric_definitions::PARTITION_IS_EFFECTIVE_LEADER;
 use crate::metric_definitions::PARTITION_LABEL;
 use crate::metric_definitions::PARTITION_TIME_SINCE_LAST_STATUS_UPDATE;
 use crate::metric_definitions::{NUM_ACTIVE_PARTITIONS, PARTITION_APPLIED_LSN_LAG};
-use crate::partition::ProcessorError;
+use crate::partition::{FailedCommand, ProcessorError};
+use crate::partition_processor_manager::apply_slo::ApplySloTracker;
+use crate::partition_processor_manager::outbox_consistency::{
+    OutboxConsistencyChecks, check_outbox_consistency,
+};
 use crate::partition_processor_manager::processor_state::{
     LeaderEpochToken, ProcessorState, StartedProcessor,
 };
@@ -124,15 +134,23 @@ pub struct PartitionProcessorManager {
     replica_set_states: PartitionReplicaSetStates,
     target_tail_lsns: HashMap<PartitionId, Lsn>,
     archived_lsns: HashMap<PartitionId, Lsn>,
+    apply_slo: ApplySloTracker,
     invokers_status_reader: MultiplexedInvokerStatusReader,
 
     asynchronous_operations: JoinSet<AsynchronousEvent>,
 
     pending_snapshots: HashMap<PartitionId, PendingSnapshotTask>,
     latest_snapshots: HashMap<PartitionId, SnapshotCreated>,
+    /// Clock driving the timer services of the partition processor leaders.
+    timer_clock: RuntimeClock,
+    /// Receives the invocation lifecycle events emitted by the partition processor leaders.
+    lifecycle_event_sender: LifecycleEventSender,
     snapshot_export_tasks: FuturesUnordered<TaskHandle<SnapshotResultInternal>>,
     snapshot_repository: Option<SnapshotRepository>,
     fast_forward_on_startup: HashMap<PartitionId, Lsn>,
+    /// Last command each partition processor failed to apply, to park it after too many attempts
+    failed_commands: HashMap<PartitionId, FailedCommand>,
+    outbox_consistency_checks: OutboxConsistencyChecks,
 
     partition_table: Live<PartitionTable>,
     wait_for_partition_table_update: bool,
@@ -140,6 +158,7 @@ pub struct PartitionProcessorManager {
     // throttling
     invocation_token_bucket: Option<TokenBucket>,
     action_token_bucket: Option<TokenBucket>,
+    replay_limiter: ReplayLimiter,
 }
 
 type SnapshotResult = Result<SnapshotCreated, SnapshotError>;
@@ -215,11 +234,8 @@ impl MultiplexedInvokerStatusReader {
     }
 }
 
-impl StatusHandle for MultiplexedInvokerStatusReader {
-    type Iterator =
-        std::iter::Flatten<std::vec::IntoIter<<ChannelStatusReader as StatusHandle>::Iterator>>;
-
-    async fn read_status(&self, keys: RangeInclusive<PartitionKey>) -> Self::Iterator {
+impl MultiplexedInvokerStatusReader {
+    fn overlapping_readers(&self, keys: &RangeInclusive<PartitionKey>) -> Vec<ChannelStatusReader> {
         let mut overlapping_partitions = Vec::new();
 
         // first clone the readers while holding the lock, then release the lock before reading the
@@ -235,14 +251,41 @@ impl StatusHandle for MultiplexedInvokerStatusReader {
         // (this uniquely defines the order between the partitions)
         overlapping_partitions.sort_by(|(a, _), (b, _)| a.start().cmp(b.start()));
 
-        let mut result = Vec::with_capacity(overlapping_partitions.len());
+        overlapping_partitions
+            .into_iter()
+            .map(|(_, reader)| reader)
+            .collect()
+    }
+}
 
-        for (_, reader) in overlapping_partitions {
+impl StatusHandle for MultiplexedInvokerStatusReader {
+    type Iterator =
+        std::iter::Flatten<std::vec::IntoIter<<ChannelStatusReader as StatusHandle>::Iterator>>;
+    type StatsIterator = std::iter::Flatten<
+        std::vec::IntoIter<<ChannelStatusReader as StatusHandle>::StatsIterator>,
+    >;
+
+    async fn read_status(&self, keys: RangeInclusive<PartitionKey>) -> Self::Iterator {
+        let readers = self.overlapping_readers(&keys);
+        let mut result = Vec::with_capacity(readers.len());
+
+        for reader in readers {
             result.push(reader.read_status(keys.clone()).await);
         }
 
         result.into_iter().flatten()
     }
+
+    async fn read_stats(&self, keys: RangeInclusive<PartitionKey>) -> Self::StatsIterator {
+        let readers = self.overlapping_readers(&keys);
+        let mut result = Vec::with_capacity(readers.len());
+
+        for reader in readers {
+            result.push(reader.read_stats(keys.clone()).await);
+        }
+
+        result.into_iter().flatten()
+    }
 }
 
 impl PartitionProcessorManager {
@@ -289,6 +332,23 @@ impl PartitionProcessorManager {
                 bucket
             });
 
+        let replay_token_bucket = config
+            .worker
+            .invoker
+            .replay_throttling
+            .as_ref()
+            .map(|opts| {
+                let limit = Limit::from(opts.clone());
+                let capacity = limit.burst();
+                let bucket = TokenBucket::from_parts(limit, gardal::TokioClock::default());
+                bucket.add_tokens(capacity.get());
+                bucket
+            });
+        let replay_limiter = ReplayLimiter::new(
+            config.worker.invoker.concurrent_replays_limit(),
+            replay_token_bucket,
+        );
+
         let (tx, rx) = mpsc::channel(updateable_config.pinned().worker.internal_queue_length());
         Self {
             health_status,
@@ -305,20 +365,39 @@ impl PartitionProcessorManager {
             replica_set_states,
             archived_lsns: HashMap::default(),
             target_tail_lsns: HashMap::default(),
+            apply_slo: ApplySloTracker::default(),
             invokers_status_reader: MultiplexedInvokerStatusReader::default(),
             asynchronous_operations: JoinSet::default(),
             pending_snapshots: HashMap::default(),
             latest_snapshots: HashMap::default(),
+            timer_clock: RuntimeClock::default(),
+            lifecycle_event_sender: LifecycleEventSender::default(),
             snapshot_export_tasks: FuturesUnordered::default(),
             snapshot_repository,
             fast_forward_on_startup: HashMap::default(),
+            failed_commands: HashMap::default(),
+            outbox_consistency_checks: OutboxConsistencyChecks::default(),
             partition_table: Metadata::with_current(|m| m.updateable_partition_table()),
             wait_for_partition_table_update: false,
             invocation_token_bucket,
             action_token_bucket,
+            replay_limiter,
         }
     }
 
+    /// Drives the timers of all partition processors started by this manager with the given
+    /// clock, so that tests can fast-forward durable sleeps and delayed invocations.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn with_timer_clock(mut self, clock: restate_timer::ManualClock) -> Self {
+        self.timer_clock = RuntimeClock::from(clock);
+        self
+    }
+
+    pub fn with_lifecycle_event_sender(mut self, sender: LifecycleEventSender) -> Self {
+        self.lifecycle_event_sender = sender;
+        self
+    }
+
     pub fn invokers_status_reader(&self) -> MultiplexedInvokerStatusReader {
         self.invokers_status_reader.clone()
     }
@@ -344,6 +423,10 @@ impl PartitionProcessorManager {
         let mut update_target_tail_lsns = tokio::time::interval(Duration::from_secs(1));
         update_target_tail_lsns.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
+        // frequent enough to check the newly elected leaders soon after their election
+        let mut outbox_consistency_check_interval = tokio::time::interval(Duration::from_secs(10));
+        outbox_consistency_check_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
         let mut ppm_svc_rx = self.ppm_svc_rx.take().start();
         let mut pp_rpc_rx = self.pp_rpc_rx.take().start();
         self.health_status.update(WorkerStatus::Ready);
@@ -368,6 +451,9 @@ impl PartitionProcessorManager {
                 _ = update_target_tail_lsns.tick() => {
                     self.update_target_tail_lsns();
                 }
+                _ = outbox_consistency_check_interval.tick() => {
+                    self.trigger_outbox_consistency_checks();
+                }
                 Some(op) = ppm_svc_rx.next() => {
                     self.handle_ppm_service_op(op);
                 }
@@ -579,6 +665,7 @@ impl PartitionProcessorManager {
                 gauge!(NUM_ACTIVE_PARTITIONS).set(self.processor_states.len() as f64);
             }
             EventKind::Stopped(result) => {
+                self.outbox_consistency_checks.remove(partition_id);
                 let delay = match self.processor_states.remove(&partition_id) {
                     None => {
                         debug!("Stopped partition processor which is no longer running.");
@@ -627,6 +714,9 @@ impl PartitionProcessorManager {
                                     }
                                 }
                                 Err(err) => {
+                                    if let ProcessorError::ApplyCommand { lsn, .. } = err {
+                                        self.note_failed_command(partition_id, *lsn, err);
+                                    }
                                     let next_delay = RestartDelay::Exponential {
                                         start_time,
                                         last_delay: delay,
@@ -696,6 +786,19 @@ impl PartitionProcessorManager {
                         v.insert(tail_lsn);
                     }
                 }
+
+                if let Some(status) = self
+                    .processor_states
+                    .get(&partition_id)
+                    .and_then(ProcessorState::partition_processor_status)
+                {
+                    self.apply_slo.on_new_tail(
+                        partition_id,
+                        tail_lsn,
+                        &status,
+                        &self.updateable_config.live_load().worker,
+                    );
+                }
             }
             EventKind::NewArchivedLsn { archived_lsn } => {
                 self.archived_lsns
@@ -703,6 +806,20 @@ impl PartitionProcessorManager {
                     .and_modify(|lsn| *lsn = archived_lsn.max(*lsn))
                     .or_insert(archived_lsn);
             }
+            EventKind::OutboxConsistencyChecked {
+                leader_epoch,
+                result,
+            } => {
+                let is_still_leader = self
+                    .active_leader_epoch(partition_id)
+                    .is_some_and(|current_leader_epoch| current_leader_epoch == leader_epoch);
+                self.outbox_consistency_checks.on_completed(
+                    partition_id,
+                    leader_epoch,
+                    is_still_leader,
+                    result,
+                );
+            }
         }
     }
 
@@ -807,6 +924,10 @@ impl PartitionProcessorManager {
                 // it is a bit unfortunate that we share PartitionProcessorStatus between the
                 // PP and the PPManager :-(. Maybe at some point we want to split the struct for it.
                 status.last_archived_log_lsn = self.archived_lsns.get(partition_id).cloned();
+                status.oldest_unapplied_command_age_ms = self
+                    .apply_slo
+                    .oldest_unapplied_command_age(*partition_id)
+                    .map(|age| age.as_millis() as u64);
                 let current_tail_lsn = self.target_tail_lsns.get(partition_id).cloned();
 
                 let target_tail_lsn = if current_tail_lsn > status.target_tail_lsn {
@@ -1144,6 +1265,68 @@ impl PartitionProcessorManager {
         }
     }
 
+    /// Leader epoch of the partition processor, if it's an active leader.
+    fn active_leader_epoch(&self, partition_id: PartitionId) -> Option<LeaderEpoch> {
+        self.processor_states
+            .get(&partition_id)?
+            .partition_processor_status()
+            .filter(|status| {
+                status.effective_mode == RunMode::Leader
+                    && status.replay_status == ReplayStatus::Active
+            })?
+            .last_observed_leader_epoch
+    }
+
+    fn trigger_outbox_consistency_checks(&mut self) {
+        let Some(interval) = self
+            .updateable_config
+            .live_load()
+            .worker
+            .outbox_consistency_check_interval
+        else {
+            return;
+        };
+
+        // the partitions whose store is open on this node
+        let consumers: Vec<_> = self.processor_states.keys().copied().collect();
+        for &partition_id in &consumers {
+            let Some(leader_epoch) = self.active_leader_epoch(partition_id) else {
+                continue;
+            };
+            if !self
+                .outbox_consistency_checks
+                .is_due(partition_id, leader_epoch, interval.to_std())
+            {
+                continue;
+            }
+
+            let psm = self.partition_store_manager.clone();
+            let consumers = consumers.clone();
+            self.asynchronous_operations
+                .build_task()
+                .name(&format!("check-outbox-consistency-{partition_id}"))
+                .spawn(
+                    async move {
+                        let result =
+                            check_outbox_consistency(partition_id, leader_epoch, psm, consumers)
+                                .await;
+
+                        AsynchronousEvent {
+                            partition_id,
+                            inner: EventKind::OutboxConsistencyChecked {
+                                leader_epoch,
+                                result,
+                            },
+                        }
+                    }
+                    .in_current_tc(),
+                )
+                .expect("to spawn outbox consistency check task");
+            self.outbox_consistency_checks
+                .on_started(partition_id, leader_epoch);
+        }
+    }
+
     fn spawn_update_archived_lsn_task(&mut self, partition_id: PartitionId) {
         let psm = self.partition_store_manager.clone();
         self.asynchronous_operations
@@ -1255,6 +1438,7 @@ impl PartitionProcessorManager {
                     );
                 }
                 self.archived_lsns.remove(&partition_id);
+                self.apply_slo.remove(partition_id);
                 self.latest_snapshots.remove(&partition_id);
             }
         }
@@ -1262,6 +1446,27 @@ impl PartitionProcessorManager {
         gauge!(NUM_ACTIVE_PARTITIONS).set(self.processor_states.len() as f64);
     }
 
+    /// Counts the consecutive failed attempts of the partition processor to apply the command at `lsn`.
+    fn note_failed_command(&mut self, partition_id: PartitionId, lsn: Lsn, err: &ProcessorError) {
+        let failed_command = self
+            .failed_commands
+            .entry(partition_id)
+            .and_modify(|failed_command| {
+                if failed_command.lsn == lsn {
+                    failed_command.attempts += 1;
+                } else {
+                    failed_command.lsn = lsn;
+                    failed_command.attempts = 1;
+                }
+            })
+            .or_insert_with(|| FailedCommand {
+                lsn,
+                attempts: 1,
+                failure: String::new(),
+            });
+        failed_command.failure = err.to_string();
+    }
+
     /// Starts a partition processor if this node is part of the replica set of the given partition.
     /// Returns true if this node is part of the replica set of the given partition. Otherwise, false.
     fn restart_partition_processor_if_replica(
@@ -1316,8 +1521,12 @@ impl PartitionProcessorManager {
             self.replica_set_states.clone(),
             self.partition_store_manager.clone(),
             self.fast_forward_on_startup.remove(&partition_id),
+            self.failed_commands.get(&partition_id).cloned(),
             self.invocation_token_bucket.clone(),
             self.action_token_bucket.clone(),
+            self.replay_limiter.clone(),
+            self.timer_clock.clone(),
+            self.lifecycle_event_sender.clone(),
         );
 
         self.asynchronous_operations
@@ -1467,6 +1676,10 @@ enum EventKind {
     NewArchivedLsn {
         archived_lsn: Lsn,
     },
+    OutboxConsistencyChecked {
+        leader_epoch: LeaderEpoch,
+        result: Result<Option<OutboxConsistencyReport>, StorageError>,
+    },
 }
 
 #[cfg(test)]
@@ -0,0 +1,191 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+use ahash::HashMap;
+use metrics::gauge;
+use tracing::{info, warn};
+
+use restate_time_util::DurationExt;
+use restate_types::cluster::cluster_state::PartitionProcessorStatus;
+use restate_types::config::WorkerOptions;
+use restate_types::identifiers::PartitionId;
+use restate_types::logs::{Lsn, SequenceNumber};
+use restate_types::time::MillisSinceEpoch;
+
+use crate::metric_definitions::{
+    PARTITION_APPLIED_COMMANDS_PER_SECOND, PARTITION_APPLY_LAG_SECONDS, PARTITION_LABEL,
+    PARTITION_OLDEST_UNAPPLIED_COMMAND_AGE_SECONDS,
+};
+
+/// Tracks how far behind the log tail the partition processors are, warning when the configured
+/// thresholds are exceeded.
+#[derive(Default)]
+pub(super) struct ApplySloTracker {
+    partitions: HashMap<PartitionId, PartitionApplyState>,
+}
+
+#[derive(Default)]
+struct PartitionApplyState {
+    /// A tail observed while the partition processor was lagging behind, and when it was observed.
+    /// The oldest unapplied command was appended before this observation.
+    lagging_since: Option<(Lsn, MillisSinceEpoch)>,
+    apply_lag_exceeded: bool,
+    oldest_unapplied_command_age_exceeded: bool,
+}
+
+impl ApplySloTracker {
+    pub fn on_new_tail(
+        &mut self,
+        partition_id: PartitionId,
+        tail: Lsn,
+        status: &PartitionProcessorStatus,
+        options: &WorkerOptions,
+    ) {
+        let state = self.partitions.entry(partition_id).or_default();
+        let applied_lsn = status.last_applied_log_lsn.unwrap_or(Lsn::INVALID);
+
+        // tail points to the next free lsn slot
+        if applied_lsn >= tail.prev() {
+            state.lagging_since = None;
+        } else if state
+            .lagging_since
+            .is_none_or(|(observed_tail, _)| applied_lsn >= observed_tail.prev())
+        {
+            // the commands observed back then are applied by now, the oldest unapplied one is newer
+            state.lagging_since = Some((tail, MillisSinceEpoch::now()));
+        }
+
+        let labels = [(PARTITION_LABEL, partition_id.to_string())];
+        gauge!(PARTITION_APPLIED_COMMANDS_PER_SECOND, &labels)
+            .set(status.applied_commands_per_second);
+        if let Some(apply_lag_ms) = status.apply_lag_ms {
+            gauge!(PARTITION_APPLY_LAG_SECONDS, &labels)
+                .set(Duration::from_millis(apply_lag_ms).as_secs_f64());
+        }
+        let oldest_unapplied_command_age = state.oldest_unapplied_command_age();
+        gauge!(PARTITION_OLDEST_UNAPPLIED_COMMAND_AGE_SECONDS, &labels)
+            .set(oldest_unapplied_command_age.as_secs_f64());
+
+        if let Some(threshold) = options.apply_lag_warning_threshold
+            && let Some(apply_lag_ms) = status.apply_lag_ms
+        {
+            let apply_lag = Duration::from_millis(apply_lag_ms);
+            let exceeded = apply_lag > threshold.to_std();
+            if exceeded && !state.apply_lag_exceeded {
+                warn!(
+                    %partition_id,
+                    apply_lag = %apply_lag.friendly(),
+                    threshold = %threshold,
+                    applied_commands_per_second = status.applied_commands_per_second,
+                    "Partition processor is falling behind: apply lag exceeds the threshold"
+                );
+            } else if !exceeded && state.apply_lag_exceeded {
+                info!(
+                    %partition_id,
+                    apply_lag = %apply_lag.friendly(),
+                    "Partition processor apply lag is back within the threshold"
+                );
+            }
+            state.apply_lag_exceeded = exceeded;
+        }
+
+        if let Some(threshold) = options.oldest_unapplied_command_age_warning_threshold {
+            let exceeded = oldest_unapplied_command_age > threshold.to_std();
+            if exceeded && !state.oldest_unapplied_command_age_exceeded {
+                warn!(
+                    %partition_id,
+                    oldest_unapplied_command_age = %oldest_unapplied_command_age.friendly(),
+                    threshold = %threshold,
+                    %applied_lsn,
+                    %tail,
+                    "Partition processor is falling behind: oldest unapplied command age exceeds the threshold"
+                );
+            } else if !exceeded && state.oldest_unapplied_command_age_exceeded {
+                info!(
+                    %partition_id,
+                    "Partition processor caught up with the oldest unapplied commands"
+                );
+            }
+            state.oldest_unapplied_command_age_exceeded = exceeded;
+        }
+    }
+
+    /// Approximated by the tail observations, hence it's at most a tail refresh interval off.
+    pub fn oldest_unapplied_command_age(&self, partition_id: PartitionId) -> Option<Duration> {
+        self.partitions
+            .get(&partition_id)
+            .map(PartitionApplyState::oldest_unapplied_command_age)
+    }
+
+    pub fn remove(&mut self, partition_id: PartitionId) {
+        self.partitions.remove(&partition_id);
+    }
+}
+
+impl PartitionApplyState {
+    fn oldest_unapplied_command_age(&self) -> Duration {
+        self.lagging_since
+            .map(|(_, observed_at)| observed_at.elapsed())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(applied_lsn: u64) -> PartitionProcessorStatus {
+        PartitionProcessorStatus {
+            last_applied_log_lsn: Some(Lsn::new(applied_lsn)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn oldest_unapplied_command_tracking() {
+        let options = WorkerOptions::default();
+        let partition_id = PartitionId::MIN;
+        let mut tracker = ApplySloTracker::default();
+
+        // caught up
+        tracker.on_new_tail(partition_id, Lsn::new(11), &status(10), &options);
+        assert!(tracker.partitions[&partition_id].lagging_since.is_none());
+
+        // lagging behind
+        tracker.on_new_tail(partition_id, Lsn::new(21), &status(10), &options);
+        let lagging_since = tracker.partitions[&partition_id].lagging_since;
+        assert_eq!(lagging_since.map(|(tail, _)| tail), Some(Lsn::new(21)));
+
+        // still applying the commands appended before the first observation
+        tracker.on_new_tail(partition_id, Lsn::new(31), &status(15), &options);
+        assert_eq!(
+            tracker.partitions[&partition_id].lagging_since,
+            lagging_since
+        );
+
+        // applied the commands of the first observation, but not the later ones
+        tracker.on_new_tail(partition_id, Lsn::new(31), &status(20), &options);
+        assert_eq!(
+            tracker.partitions[&partition_id]
+                .lagging_since
+                .map(|(tail, _)| tail),
+            Some(Lsn::new(31))
+        );
+
+        // caught up again
+        tracker.on_new_tail(partition_id, Lsn::new(31), &status(30), &options);
+        assert_eq!(
+            tracker.oldest_unapplied_command_age(partition_id),
+            Some(Duration::ZERO)
+        );
+    }
+}
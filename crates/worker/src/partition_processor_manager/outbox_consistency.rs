@@ -0,0 +1,344 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ahash::HashMap;
+use metrics::gauge;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use restate_partition_store::{PartitionStore, PartitionStoreManager};
+use restate_storage_api::StorageError;
+use restate_storage_api::deduplication_table::{
+    DedupSequenceNumber, ProducerId, ReadDeduplicationTable,
+};
+use restate_storage_api::fsm_table::ReadFsmTable;
+use restate_storage_api::outbox_table::ReadOutboxTable;
+use restate_types::identifiers::{LeaderEpoch, PartitionId};
+use restate_types::message::MessageIndex;
+use restate_types::partitions::outbox_consistency::{
+    self, OutboxConsistencyReport, OutboxInconsistency,
+};
+use restate_types::time::MillisSinceEpoch;
+
+use crate::metric_definitions::{PARTITION_LABEL, PARTITION_OUTBOX_INCONSISTENCIES};
+
+/// Upper bound of the outbox messages scanned by a single check, to not keep the storage busy
+/// when the shuffle of a partition is lagging behind.
+const MAX_SCANNED_OUTBOX_MESSAGES: usize = 10_000;
+
+/// Schedules the outbox consistency checks of the partition leaders running on this node: once
+/// right after they got elected, and then periodically.
+#[derive(Default)]
+pub(super) struct OutboxConsistencyChecks {
+    partitions: HashMap<PartitionId, LastCheck>,
+}
+
+struct LastCheck {
+    leader_epoch: LeaderEpoch,
+    started_at: Instant,
+    in_flight: bool,
+}
+
+impl OutboxConsistencyChecks {
+    /// Whether the leader of the given partition needs to be checked.
+    pub(super) fn is_due(
+        &self,
+        partition_id: PartitionId,
+        leader_epoch: LeaderEpoch,
+        interval: Duration,
+    ) -> bool {
+        match self.partitions.get(&partition_id) {
+            None => true,
+            Some(last_check) => {
+                !last_check.in_flight
+                    && (last_check.leader_epoch != leader_epoch
+                        || last_check.started_at.elapsed() >= interval)
+            }
+        }
+    }
+
+    pub(super) fn on_started(&mut self, partition_id: PartitionId, leader_epoch: LeaderEpoch) {
+        self.partitions.insert(
+            partition_id,
+            LastCheck {
+                leader_epoch,
+                started_at: Instant::now(),
+                in_flight: true,
+            },
+        );
+    }
+
+    /// Publishes the outcome of a check, unless the partition processor stopped being the leader
+    /// of the checked epoch in the meantime: its partition store might then lag behind the
+    /// dedup tables of the downstream partitions.
+    pub(super) fn on_completed(
+        &mut self,
+        partition_id: PartitionId,
+        leader_epoch: LeaderEpoch,
+        is_still_leader: bool,
+        result: Result<Option<OutboxConsistencyReport>, StorageError>,
+    ) {
+        let Some(last_check) = self.partitions.get_mut(&partition_id) else {
+            // the partition processor stopped in the meantime
+            return;
+        };
+        if last_check.leader_epoch != leader_epoch {
+            return;
+        }
+        last_check.in_flight = false;
+
+        let report = match result {
+            Ok(Some(report)) if is_still_leader => report,
+            Ok(_) => {
+                debug!(%partition_id, "Discarding the outbox consistency check of a former leader");
+                return;
+            }
+            Err(err) => {
+                warn!(%partition_id, "Failed checking the consistency of the outbox: {err}");
+                return;
+            }
+        };
+
+        for inconsistency in &report.inconsistencies {
+            match inconsistency {
+                OutboxInconsistency::MissingOutboxMessages {
+                    from_seq_number,
+                    to_seq_number,
+                } => warn!(
+                    %partition_id,
+                    "The outbox misses the messages [{from_seq_number}..={to_seq_number}], they won't be delivered to their target partitions"
+                ),
+                OutboxInconsistency::DedupAheadOfOutbox {
+                    consumer,
+                    dedup_seq_number,
+                } => warn!(
+                    %partition_id,
+                    "Partition {consumer} already received the outbox message {dedup_seq_number}, while the next outbox message will be {}. It will drop the next messages as duplicates",
+                    report.outbox_seq_number
+                ),
+            }
+        }
+        gauge!(PARTITION_OUTBOX_INCONSISTENCIES, PARTITION_LABEL => partition_id.to_string())
+            .set(report.inconsistencies.len() as f64);
+        outbox_consistency::update(partition_id, report);
+    }
+
+    pub(super) fn remove(&mut self, partition_id: PartitionId) {
+        if self.partitions.remove(&partition_id).is_some() {
+            gauge!(PARTITION_OUTBOX_INCONSISTENCIES, PARTITION_LABEL => partition_id.to_string())
+                .set(0.0);
+        }
+        outbox_consistency::remove(partition_id);
+    }
+}
+
+/// Compares the outbox of the `producer` partition with the dedup tables of the `consumers`,
+/// the partitions whose store is open on this node. Returns `None` if the store of the producer
+/// is not open anymore.
+///
+/// The `producer` must be led by this node, so that its store contains every outbox message the
+/// previous leaders sent.
+pub(super) async fn check_outbox_consistency(
+    producer: PartitionId,
+    leader_epoch: LeaderEpoch,
+    partition_store_manager: Arc<PartitionStoreManager>,
+    consumers: Vec<PartitionId>,
+) -> Result<Option<OutboxConsistencyReport>, StorageError> {
+    let producer_id = ProducerId::Partition(producer);
+
+    // The dedup tables are read before the outbox: since sequence numbers only grow, the outbox
+    // read afterwards must have assigned every sequence number found in the dedup tables.
+    let mut checked_consumers = Vec::with_capacity(consumers.len());
+    let mut dedup_seq_numbers = Vec::new();
+    for consumer in consumers {
+        let Some(mut consumer_store) = partition_store_manager.get_partition_store(consumer).await
+        else {
+            continue;
+        };
+        checked_consumers.push(consumer);
+        if let Some(DedupSequenceNumber::Sn(dedup_seq_number)) = consumer_store
+            .get_dedup_sequence_number(&producer_id)
+            .await?
+        {
+            dedup_seq_numbers.push((consumer, dedup_seq_number));
+        }
+    }
+
+    let Some(mut producer_store) = partition_store_manager.get_partition_store(producer).await
+    else {
+        return Ok(None);
+    };
+    let outbox_seq_number = producer_store.get_outbox_seq_number().await?;
+    let outbox_head_seq_number = producer_store.get_outbox_head_seq_number().await?;
+
+    let mut inconsistencies: Vec<_> = dedup_seq_numbers
+        .into_iter()
+        .filter(|(_, dedup_seq_number)| *dedup_seq_number >= outbox_seq_number)
+        .map(
+            |(consumer, dedup_seq_number)| OutboxInconsistency::DedupAheadOfOutbox {
+                consumer,
+                dedup_seq_number,
+            },
+        )
+        .collect();
+
+    if let Some(outbox_head_seq_number) = outbox_head_seq_number {
+        let missing = find_missing_outbox_messages(
+            &mut producer_store,
+            outbox_head_seq_number,
+            outbox_seq_number,
+        )
+        .await?;
+
+        if !missing.is_empty() {
+            // The shuffle keeps truncating the outbox while it's scanned, the messages it
+            // truncated in the meantime were delivered rather than missing.
+            let outbox_head_seq_number = producer_store
+                .get_outbox_head_seq_number()
+                .await?
+                .unwrap_or(outbox_seq_number);
+            inconsistencies.extend(missing.into_iter().filter_map(
+                |(from_seq_number, to_seq_number)| {
+                    (to_seq_number >= outbox_head_seq_number).then_some(
+                        OutboxInconsistency::MissingOutboxMessages {
+                            from_seq_number: from_seq_number.max(outbox_head_seq_number),
+                            to_seq_number,
+                        },
+                    )
+                },
+            ));
+        }
+    }
+
+    Ok(Some(OutboxConsistencyReport {
+        leader_epoch,
+        checked_at: MillisSinceEpoch::now(),
+        outbox_head_seq_number,
+        outbox_seq_number,
+        checked_consumers,
+        inconsistencies,
+    }))
+}
+
+/// Returns the ranges of sequence numbers missing between the outbox head and the next outbox
+/// sequence number.
+async fn find_missing_outbox_messages(
+    partition_store: &mut PartitionStore,
+    outbox_head_seq_number: MessageIndex,
+    outbox_seq_number: MessageIndex,
+) -> Result<Vec<(MessageIndex, MessageIndex)>, StorageError> {
+    let mut missing = Vec::new();
+    let mut next_seq_number = outbox_head_seq_number;
+    let mut scanned_messages = 0;
+
+    while next_seq_number < outbox_seq_number && scanned_messages < MAX_SCANNED_OUTBOX_MESSAGES {
+        scanned_messages += 1;
+        // messages added after reading the outbox sequence number are ignored
+        match partition_store
+            .get_next_outbox_message(next_seq_number)
+            .await?
+            .map(|(seq_number, _)| seq_number)
+            .filter(|seq_number| *seq_number < outbox_seq_number)
+        {
+            Some(seq_number) => {
+                if seq_number > next_seq_number {
+                    missing.push((next_seq_number, seq_number - 1));
+                }
+                next_seq_number = seq_number + 1;
+            }
+            None => {
+                missing.push((next_seq_number, outbox_seq_number - 1));
+                break;
+            }
+        }
+    }
+
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_rocksdb::RocksDbManager;
+    use restate_storage_api::Transaction;
+    use restate_storage_api::deduplication_table::WriteDeduplicationTable;
+    use restate_storage_api::fsm_table::WriteFsmTable;
+    use restate_storage_api::outbox_table::{OutboxMessage, WriteOutboxTable};
+    use restate_types::identifiers::PartitionKey;
+    use restate_types::invocation::ServiceInvocation;
+    use restate_types::partitions::Partition;
+
+    #[restate_core::test]
+    async fn finds_missing_messages_and_dedup_ahead_of_outbox() -> googletest::Result<()> {
+        RocksDbManager::init();
+        let partition_store_manager = PartitionStoreManager::create().await?;
+
+        let producer = PartitionId::from(0);
+        let consumer = PartitionId::from(1);
+        let mut producer_store = partition_store_manager
+            .open(
+                &Partition::new(producer, PartitionKey::MIN..=PartitionKey::MAX / 2),
+                None,
+            )
+            .await?;
+        let mut consumer_store = partition_store_manager
+            .open(
+                &Partition::new(consumer, PartitionKey::MAX / 2 + 1..=PartitionKey::MAX),
+                None,
+            )
+            .await?;
+
+        // Messages [0, 2) were shuffled, message 3 got lost
+        let message = OutboxMessage::ServiceInvocation(Box::new(ServiceInvocation::mock()));
+        let mut txn = producer_store.transaction();
+        txn.put_outbox_seq_number(5)?;
+        txn.put_outbox_message(2, &message)?;
+        txn.put_outbox_message(4, &message)?;
+        txn.put_dedup_seq_number(ProducerId::Partition(producer), &DedupSequenceNumber::Sn(1))?;
+        txn.commit().await?;
+
+        let mut txn = consumer_store.transaction();
+        txn.put_dedup_seq_number(ProducerId::Partition(producer), &DedupSequenceNumber::Sn(7))?;
+        txn.commit().await?;
+
+        let report = check_outbox_consistency(
+            producer,
+            LeaderEpoch::INITIAL,
+            Arc::clone(&partition_store_manager),
+            vec![producer, consumer],
+        )
+        .await?
+        .expect("producer store is open");
+
+        assert_eq!(report.outbox_head_seq_number, Some(2));
+        assert_eq!(report.outbox_seq_number, 5);
+        assert_eq!(report.checked_consumers, vec![producer, consumer]);
+        assert_eq!(
+            report.inconsistencies,
+            vec![
+                OutboxInconsistency::DedupAheadOfOutbox {
+                    consumer,
+                    dedup_seq_number: 7,
+                },
+                OutboxInconsistency::MissingOutboxMessages {
+                    from_seq_number: 3,
+                    to_seq_number: 3,
+                },
+            ]
+        );
+
+        RocksDbManager::get().shutdown().await;
+        Ok(())
+    }
+}
@@ -18,23 +18,27 @@ use tracing::{instrument, warn};
 use restate_bifrost::Bifrost;
 use restate_core::{Metadata, RuntimeTaskHandle, TaskCenter, TaskKind, cancellation_token};
 use restate_invoker_impl::Service as InvokerService;
-use restate_invoker_impl::TokenBucket;
+use restate_invoker_impl::{ReplayLimiter, TokenBucket};
 use restate_partition_store::{PartitionStore, PartitionStoreManager};
 use restate_service_protocol::codec::ProtobufRawEntryCodec;
+use restate_timer::RuntimeClock;
 use restate_types::SharedString;
 use restate_types::cluster::cluster_state::PartitionProcessorStatus;
 use restate_types::config::Configuration;
+use restate_types::identifiers::PartitionId;
 use restate_types::live::Live;
 use restate_types::live::LiveLoadExt;
 use restate_types::logs::Lsn;
 use restate_types::partitions::Partition;
+use restate_types::partitions::recovery;
 use restate_types::partitions::state::PartitionReplicaSetStates;
 use restate_types::schema::Schema;
 
 use crate::PartitionProcessorBuilder;
 use crate::invoker_integration::EntryEnricher;
+use crate::lifecycle_webhook::LifecycleEventSender;
 use crate::partition::invoker_storage_reader::InvokerStorageReader;
-use crate::partition::{ProcessorError, TargetLeaderState};
+use crate::partition::{FailedCommand, ProcessorError, TargetLeaderState};
 use crate::partition_processor_manager::processor_state::StartedProcessor;
 
 pub struct SpawnPartitionProcessorTask {
@@ -45,8 +49,12 @@ pub struct SpawnPartitionProcessorTask {
     replica_set_states: PartitionReplicaSetStates,
     partition_store_manager: Arc<PartitionStoreManager>,
     fast_forward_lsn: Option<Lsn>,
+    failed_command: Option<FailedCommand>,
     invocation_token_bucket: Option<TokenBucket>,
     action_token_bucket: Option<TokenBucket>,
+    replay_limiter: ReplayLimiter,
+    timer_clock: RuntimeClock,
+    lifecycle_event_sender: LifecycleEventSender,
 }
 
 impl SpawnPartitionProcessorTask {
@@ -59,8 +67,12 @@ impl SpawnPartitionProcessorTask {
         replica_set_states: PartitionReplicaSetStates,
         partition_store_manager: Arc<PartitionStoreManager>,
         fast_forward_lsn: Option<Lsn>,
+        failed_command: Option<FailedCommand>,
         invocation_token_bucket: Option<TokenBucket>,
         action_token_bucket: Option<TokenBucket>,
+        replay_limiter: ReplayLimiter,
+        timer_clock: RuntimeClock,
+        lifecycle_event_sender: LifecycleEventSender,
     ) -> Self {
         Self {
             task_name,
@@ -70,8 +82,12 @@ impl SpawnPartitionProcessorTask {
             replica_set_states,
             partition_store_manager,
             fast_forward_lsn,
+            failed_command,
             invocation_token_bucket,
             action_token_bucket,
+            replay_limiter,
+            timer_clock,
+            lifecycle_event_sender,
         }
     }
 
@@ -98,8 +114,12 @@ impl SpawnPartitionProcessorTask {
             replica_set_states,
             partition_store_manager,
             fast_forward_lsn,
+            failed_command,
             invocation_token_bucket,
             action_token_bucket,
+            replay_limiter,
+            timer_clock,
+            lifecycle_event_sender,
         } = self;
 
         let config = configuration.pinned();
@@ -116,6 +136,7 @@ impl SpawnPartitionProcessorTask {
             schema,
             invocation_token_bucket,
             action_token_bucket,
+            replay_limiter,
         )?;
 
         let status_reader = invoker.status_reader();
@@ -125,8 +146,16 @@ impl SpawnPartitionProcessorTask {
         let status = PartitionProcessorStatus::new();
         let (watch_tx, watch_rx) = watch::channel(status.clone());
 
-        let pp_builder =
-            PartitionProcessorBuilder::new(status, control_rx, net_rx, watch_tx, invoker.handle());
+        let pp_builder = PartitionProcessorBuilder::new(
+            status,
+            control_rx,
+            net_rx,
+            watch_tx,
+            invoker.handle(),
+            timer_clock,
+        )
+        .with_failed_command(failed_command)
+        .with_lifecycle_event_sender(lifecycle_event_sender);
 
         let invoker_name = Arc::from(format!("invoker-{}", partition.partition_id));
         let invoker_config = configuration.clone().map(|c| &c.worker.invoker);
@@ -138,6 +167,7 @@ impl SpawnPartitionProcessorTask {
             Some(partition.partition_id),
             {
                 move || async move {
+                    let _recovery = RecoveryGuard::start(partition.partition_id);
 
                     let open_partition_store = async {
                         if let Some(delay) = delay {
@@ -199,3 +229,19 @@ impl SpawnPartitionProcessorTask {
         Ok((state, root_task_handle))
     }
 }
+
+/// Tracks the recovery of a partition processor until its task ends, however it ends.
+struct RecoveryGuard(PartitionId);
+
+impl RecoveryGuard {
+    fn start(partition_id: PartitionId) -> Self {
+        recovery::start(partition_id);
+        Self(partition_id)
+    }
+}
+
+impl Drop for RecoveryGuard {
+    fn drop(&mut self) {
+        recovery::remove(self.0);
+    }
+}
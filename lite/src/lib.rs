@@ -19,6 +19,8 @@ use tokio::sync::Mutex;
 use tokio::sync::oneshot;
 use tracing::{debug, warn};
 
+use restate_admin_client::AdminClient;
+use restate_admin_rest_model::deployments::RegisterDeploymentRequest;
 use restate_core::TaskCenter;
 use restate_core::TaskCenterBuilder;
 use restate_core::TaskHandle;
@@ -69,6 +71,10 @@ pub struct Options {
     pub enable_tcp: bool,
     pub memory_budget: NonZero<usize>,
     pub data_dir: Option<PathBuf>,
+    /// Drives durable sleeps, delayed invocations and retention timers with this clock instead
+    /// of the wall clock, so tests can fast-forward them by advancing the clock.
+    #[cfg(feature = "test-util")]
+    pub timer_clock: Option<restate_node::ManualClock>,
 }
 
 impl Default for Options {
@@ -78,6 +84,8 @@ impl Default for Options {
             use_random_ports: false,
             enable_tcp: false,
             data_dir: None,
+            #[cfg(feature = "test-util")]
+            timer_clock: None,
         }
     }
 }
@@ -241,7 +249,15 @@ impl Restate {
         let task = task_center.to_handle().spawn_unmanaged_child(
             TaskKind::SystemBoot,
             "restate",
-            run_restate(config.clone(), data_dir, address_book, started, stopped),
+            run_restate(
+                config.clone(),
+                data_dir,
+                address_book,
+                #[cfg(feature = "test-util")]
+                opts.timer_clock,
+                started,
+                stopped,
+            ),
         )?;
 
         // mark restate as running
@@ -280,16 +296,10 @@ impl Restate {
             })
             .expect("admin is always set");
         // register mock service
-        let client = reqwest::Client::builder().unix_socket(admin_uds).build()?;
-        let discovery_payload = serde_json::json!({"uri": url.to_owned()}).to_string();
-        let discovery_result = client
-            .post("http://local/deployments")
-            .header(http::header::CONTENT_TYPE, "application/json")
-            .body(discovery_payload)
-            .send()
+        let client = AdminClient::from_uds(admin_uds)?;
+        client
+            .register_deployment(RegisterDeploymentRequest::http(url.parse::<http::Uri>()?))
             .await?;
-
-        discovery_result.error_for_status()?;
         Ok(())
     }
 
@@ -325,6 +335,7 @@ async fn run_restate(
     config: Configuration,
     data_dir: PathBuf,
     address_book: AddressBook,
+    #[cfg(feature = "test-util")] timer_clock: Option<restate_node::ManualClock>,
     started: oneshot::Sender<()>,
     stopped: oneshot::Sender<Result<()>>,
 ) -> Result<()> {
@@ -347,6 +358,11 @@ async fn run_restate(
     }));
 
     let node = Node::create(Live::from_value(config), Default::default(), address_book).await?;
+    #[cfg(feature = "test-util")]
+    let node = match timer_clock {
+        Some(timer_clock) => node.with_timer_clock(timer_clock),
+        None => node,
+    };
     // We ignore errors since we will wait for shutdown below anyway.
     // This starts node roles and the rest of the system async under tasks managed by
     // the TaskCenter.
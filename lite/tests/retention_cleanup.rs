@@ -0,0 +1,120 @@
+// Copyright (c) 2023 - 2025 Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, bail};
+use http::header::{ACCEPT, CONTENT_TYPE};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use restate_lite::{AddressKind, Options, Restate};
+use restate_node::ManualClock;
+use restate_types::net::address::{AdminPort, HttpIngressPort, ListenerPort};
+use restate_types::time::MillisSinceEpoch;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Fast-forwards the cleanup of a completed invocation with the manual timer clock, instead of
+/// waiting for the default journal retention of one day.
+#[tokio::test(flavor = "multi_thread")]
+async fn retention_cleanup_fires_with_manual_clock() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let clock = ManualClock::new(MillisSinceEpoch::now());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let mock_svc_addr = format!("http://{}/", listener.local_addr()?);
+    let (running_tx, running_rx) = oneshot::channel();
+    tokio::spawn(mock_service_endpoint::listener::run_listener(
+        listener,
+        || {
+            let _ = running_tx.send(());
+        },
+    ));
+    running_rx.await?;
+
+    let restate = Restate::create(Options {
+        data_dir: Some(temp_dir.path().to_path_buf()),
+        timer_clock: Some(clock.clone()),
+        ..Default::default()
+    })
+    .await?;
+    restate.discover_deployment(&mock_svc_addr).await?;
+
+    let uds_client = |name: &str| {
+        let path = restate
+            .get_bound_addresses()
+            .into_iter()
+            .find(|address| address.name == name && address.kind == AddressKind::Unix)
+            .map(|address| address.address)
+            .context("address must be bound")?;
+        anyhow::Ok(reqwest::Client::builder().unix_socket(path).build()?)
+    };
+    let ingress_client = uds_client(HttpIngressPort::NAME)?;
+    let admin_client = uds_client(AdminPort::NAME)?;
+
+    // The ingress might not know the service right after the discovery
+    let start = Instant::now();
+    let invocation_id = loop {
+        let response = ingress_client
+            .post("http://localhost/Counter/my-key/add")
+            .header(CONTENT_TYPE, "application/json")
+            .body("1")
+            .send()
+            .await?;
+        if response.status().is_success() {
+            break response
+                .headers()
+                .get("x-restate-id")
+                .context("response must contain the invocation id")?
+                .to_str()?
+                .to_owned();
+        }
+        if start.elapsed() > TIMEOUT {
+            bail!("invocation failed with {}", response.status());
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+
+    assert!(is_invocation_retained(&admin_client, &invocation_id).await?);
+
+    // Advance the clock in steps, as the cleanup timer is scheduled by the leader right after
+    // the completion and might not be registered yet.
+    let start = Instant::now();
+    while is_invocation_retained(&admin_client, &invocation_id).await? {
+        if start.elapsed() > TIMEOUT {
+            bail!("invocation {invocation_id} wasn't cleaned up");
+        }
+        clock.advance(Duration::from_secs(2 * 24 * 60 * 60));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    restate.stop().await
+}
+
+async fn is_invocation_retained(
+    admin_client: &reqwest::Client,
+    invocation_id: &str,
+) -> anyhow::Result<bool> {
+    let response: serde_json::Value = admin_client
+        .post("http://localhost/query")
+        .header(ACCEPT, "application/json")
+        .json(&serde_json::json!({
+            "query": format!("SELECT id FROM sys_invocation WHERE id = '{invocation_id}'")
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(response["rows"]
+        .as_array()
+        .is_some_and(|rows| !rows.is_empty()))
+}